@@ -0,0 +1,24 @@
+;; Minimal WASM filter: tags every response with `x-filtered-by: axon-wasm`.
+;; Real filters are usually compiled from Rust, Go or AssemblyScript; see the
+;; "WASM Filters" section of the README for the ABI.
+(module
+  (memory (export "memory") 1)
+
+  ;; Bump allocator for the JSON input written by the gateway
+  (global $next (mut i32) (i32.const 1024))
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    global.get $next
+    local.set $ptr
+    global.get $next
+    local.get $len
+    i32.add
+    global.set $next
+    local.get $ptr)
+
+  ;; Action returned for every response (47 bytes at offset 0)
+  (data (i32.const 0) "{\"set_headers\":[[\"x-filtered-by\",\"axon-wasm\"]]}")
+
+  (func (export "on_response") (param $ptr i32) (param $len i32) (result i64)
+    ;; (ptr << 32) | len with ptr = 0
+    i64.const 47))
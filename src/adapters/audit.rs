@@ -0,0 +1,433 @@
+//! Structured audit records for security decisions.
+//!
+//! WAF blocks (and, optionally, monitor-mode detections), IP filter denials,
+//! rate limit rejections and authentication failures each produce an
+//! [`AuditEvent`]. Events are queued on a bounded channel and written by a
+//! background task to the configured sink, so a slow SIEM endpoint never
+//! delays request handling; when the queue is full new records are dropped
+//! and counted in `axon_audit_records_dropped_total`.
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::body::Body as AxumBody;
+use chrono::{DateTime, Utc};
+use eyre::{Result, WrapErr, eyre};
+use http::{HeaderMap, Method, Uri, header};
+use hyper::Request;
+use serde::Serialize;
+use tokio::{
+    fs::{File, OpenOptions},
+    io::AsyncWriteExt,
+    net::UdpSocket,
+    sync::mpsc,
+};
+
+use crate::{
+    adapters::middleware::X_REQUEST_ID,
+    config::models::{AuditConfig, AuditSinkConfig},
+    core::waf::ThreatLevel,
+    ports::http_client::HttpClient,
+};
+
+/// Records written to file and syslog sinks per wakeup.
+const WRITE_BATCH: usize = 64;
+
+/// Security check that produced an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventKind {
+    Waf,
+    IpFilter,
+    RateLimit,
+    Auth,
+    AdminAuth,
+    Authorization,
+}
+
+/// What the gateway did with the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditDecision {
+    Blocked,
+    /// Detected but allowed through (WAF monitor mode)
+    Monitored,
+}
+
+/// One audit record, serialized as a flat JSON object.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEvent {
+    pub timestamp: DateTime<Utc>,
+    pub event: AuditEventKind,
+    pub decision: AuditDecision,
+    /// Rule that fired: a WAF threat type, limiter kind or auth scheme
+    pub rule: String,
+    pub threat_level: &'static str,
+    pub client_ip: Option<String>,
+    pub method: String,
+    pub host: Option<String>,
+    pub path: String,
+    pub route: Option<String>,
+    pub request_id: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(
+        event: AuditEventKind,
+        decision: AuditDecision,
+        rule: impl Into<String>,
+        threat_level: ThreatLevel,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            event,
+            decision,
+            rule: rule.into(),
+            threat_level: threat_level.as_str(),
+            client_ip: None,
+            method: String::new(),
+            host: None,
+            path: String::new(),
+            route: None,
+            request_id: None,
+            detail: None,
+        }
+    }
+
+    /// Event for a WAF violation; IP filter rules get their own kind.
+    pub fn waf(threat_type: &str, threat_level: ThreatLevel, blocked: bool) -> Self {
+        let kind = match threat_type {
+            "IP_BLACKLISTED" | "IP_NOT_WHITELISTED" | "IP_BANNED" => AuditEventKind::IpFilter,
+            _ => AuditEventKind::Waf,
+        };
+        let decision = if blocked {
+            AuditDecision::Blocked
+        } else {
+            AuditDecision::Monitored
+        };
+        Self::new(kind, decision, threat_type, threat_level)
+    }
+
+    /// Fill in the method, host, path and request ID.
+    pub fn request(mut self, method: &Method, uri: &Uri, headers: &HeaderMap) -> Self {
+        self.method = method.to_string();
+        self.path = uri.path().to_string();
+        self.host = headers
+            .get(header::HOST)
+            .and_then(|v| v.to_str().ok())
+            .or_else(|| uri.host())
+            .map(str::to_string);
+        self.request_id = headers
+            .get(X_REQUEST_ID)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        self
+    }
+
+    pub fn client(mut self, ip: Option<IpAddr>) -> Self {
+        self.client_ip = ip.map(|ip| ip.to_string());
+        self
+    }
+
+    pub fn route(mut self, route: impl Into<String>) -> Self {
+        self.route = Some(route.into());
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Syslog severity: critical threats map to `crit`, low ones to `notice`.
+    fn syslog_severity(&self) -> u8 {
+        match self.threat_level {
+            "critical" => 2,
+            "high" => 3,
+            "medium" => 4,
+            _ => 5,
+        }
+    }
+}
+
+/// Handle for emitting audit records; cheap to clone.
+///
+/// The default handle is disabled and discards every record.
+#[derive(Clone, Default)]
+pub struct AuditLog {
+    tx: Option<mpsc::Sender<AuditEvent>>,
+    include_monitored: bool,
+}
+
+impl AuditLog {
+    /// A handle that records nothing.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Open the configured sink and start the writer task.
+    ///
+    /// Files are opened and syslog addresses resolved here, so a bad sink
+    /// fails startup rather than silently losing records.
+    pub async fn start(config: &AuditConfig, http_client: Arc<dyn HttpClient>) -> Result<Self> {
+        if !config.enabled {
+            return Ok(Self::disabled());
+        }
+        let sink_config = config
+            .sink
+            .as_ref()
+            .ok_or_else(|| eyre!("audit.sink is required when the audit log is enabled"))?;
+        let (sink, batch_size) = Sink::open(sink_config, http_client).await?;
+        let (tx, rx) = mpsc::channel(config.queue_size.max(1));
+        tokio::spawn(run_sink(sink, rx, batch_size));
+        Ok(Self {
+            tx: Some(tx),
+            include_monitored: config.include_monitored,
+        })
+    }
+
+    /// A handle feeding `capacity` records into the returned receiver.
+    #[cfg(test)]
+    pub(crate) fn channel(capacity: usize) -> (Self, mpsc::Receiver<AuditEvent>) {
+        let (tx, rx) = mpsc::channel(capacity);
+        let log = Self {
+            tx: Some(tx),
+            include_monitored: true,
+        };
+        (log, rx)
+    }
+
+    /// Queue `event` for the sink without waiting.
+    pub fn record(&self, event: AuditEvent) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+        if event.decision == AuditDecision::Monitored && !self.include_monitored {
+            return;
+        }
+        match tx.try_send(event) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                crate::metrics::record_audit_dropped("queue_full", 1);
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                crate::metrics::record_audit_dropped("sink_closed", 1);
+            }
+        }
+    }
+}
+
+enum Sink {
+    File(File),
+    Syslog {
+        socket: UdpSocket,
+        facility: u8,
+    },
+    Webhook {
+        client: Arc<dyn HttpClient>,
+        url: String,
+        headers: Vec<(String, String)>,
+    },
+}
+
+impl Sink {
+    async fn open(config: &AuditSinkConfig, client: Arc<dyn HttpClient>) -> Result<(Self, usize)> {
+        match config {
+            AuditSinkConfig::File { path } => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await
+                    .wrap_err_with(|| format!("Failed to open audit log '{path}'"))?;
+                Ok((Sink::File(file), WRITE_BATCH))
+            }
+            AuditSinkConfig::Syslog { address, facility } => {
+                let target = tokio::net::lookup_host(address.as_str())
+                    .await
+                    .wrap_err_with(|| format!("Failed to resolve syslog address '{address}'"))?
+                    .next()
+                    .ok_or_else(|| eyre!("Syslog address '{address}' did not resolve"))?;
+                let local: SocketAddr = if target.is_ipv6() {
+                    "[::]:0".parse()?
+                } else {
+                    "0.0.0.0:0".parse()?
+                };
+                let socket = UdpSocket::bind(local).await?;
+                socket
+                    .connect(target)
+                    .await
+                    .wrap_err_with(|| format!("Failed to connect to syslog at {target}"))?;
+                let sink = Sink::Syslog {
+                    socket,
+                    facility: *facility,
+                };
+                Ok((sink, WRITE_BATCH))
+            }
+            AuditSinkConfig::Webhook {
+                url,
+                headers,
+                batch_size,
+            } => {
+                let sink = Sink::Webhook {
+                    client,
+                    url: url.clone(),
+                    headers: headers
+                        .iter()
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect(),
+                };
+                Ok((sink, (*batch_size).max(1)))
+            }
+        }
+    }
+
+    async fn write(&mut self, batch: &[AuditEvent]) -> Result<()> {
+        match self {
+            Sink::File(file) => {
+                let mut out = Vec::new();
+                for event in batch {
+                    serde_json::to_writer(&mut out, event)?;
+                    out.push(b'\n');
+                }
+                file.write_all(&out).await?;
+                file.flush().await?;
+            }
+            Sink::Syslog { socket, facility } => {
+                for event in batch {
+                    socket.send(&syslog_message(event, *facility)?).await?;
+                }
+            }
+            Sink::Webhook {
+                client,
+                url,
+                headers,
+            } => {
+                let mut request =
+                    Request::post(url.as_str()).header(header::CONTENT_TYPE, "application/json");
+                for (name, value) in headers.iter() {
+                    request = request.header(name.as_str(), value.as_str());
+                }
+                let request = request.body(AxumBody::from(serde_json::to_vec(batch)?))?;
+                let response = client.send_request(request).await?;
+                if !response.status().is_success() {
+                    return Err(eyre!("Webhook returned status {}", response.status()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// RFC 5424 message carrying the event as JSON.
+fn syslog_message(event: &AuditEvent, facility: u8) -> Result<Vec<u8>> {
+    let priority = u16::from(facility) * 8 + u16::from(event.syslog_severity());
+    let mut message = format!(
+        "<{priority}>1 {} - axon {} audit - ",
+        event
+            .timestamp
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        std::process::id()
+    )
+    .into_bytes();
+    serde_json::to_writer(&mut message, event)?;
+    Ok(message)
+}
+
+async fn run_sink(mut sink: Sink, mut rx: mpsc::Receiver<AuditEvent>, batch_size: usize) {
+    let mut batch = Vec::with_capacity(batch_size);
+    while rx.recv_many(&mut batch, batch_size).await > 0 {
+        if let Err(e) = sink.write(&batch).await {
+            tracing::warn!(error = %e, records = batch.len(), "Failed to write audit records");
+            crate::metrics::record_audit_dropped("sink_error", batch.len() as u64);
+        }
+        batch.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::HttpClientAdapter;
+
+    #[tokio::test]
+    async fn test_file_and_syslog_sinks() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let client: Arc<dyn HttpClient> = Arc::new(HttpClientAdapter::new().unwrap());
+        let config = AuditConfig {
+            enabled: true,
+            sink: Some(AuditSinkConfig::File {
+                path: path.to_string_lossy().into_owned(),
+            }),
+            queue_size: 16,
+            include_monitored: false,
+        };
+        let log = AuditLog::start(&config, client.clone()).await.unwrap();
+
+        let uri: Uri = "/login?next=/".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, "example.com".parse().unwrap());
+        log.record(
+            AuditEvent::waf("SQL_INJECTION", ThreatLevel::Critical, true)
+                .request(&Method::POST, &uri, &headers)
+                .client(Some("203.0.113.7".parse().unwrap())),
+        );
+        // Monitor-mode detections are skipped when not included
+        log.record(AuditEvent::waf("XSS", ThreatLevel::High, false));
+        log.record(AuditEvent::waf(
+            "IP_BLACKLISTED",
+            ThreatLevel::Critical,
+            true,
+        ));
+
+        let mut lines = Vec::new();
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            lines = std::fs::read_to_string(&path)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+                .collect();
+            if lines.len() == 2 {
+                break;
+            }
+        }
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "waf");
+        assert_eq!(lines[0]["decision"], "blocked");
+        assert_eq!(lines[0]["rule"], "SQL_INJECTION");
+        assert_eq!(lines[0]["threat_level"], "critical");
+        assert_eq!(lines[0]["client_ip"], "203.0.113.7");
+        assert_eq!(lines[0]["host"], "example.com");
+        assert_eq!(lines[0]["path"], "/login");
+        assert_eq!(lines[1]["event"], "ip_filter");
+
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = AuditConfig {
+            sink: Some(AuditSinkConfig::Syslog {
+                address: receiver.local_addr().unwrap().to_string(),
+                facility: 13,
+            }),
+            ..config
+        };
+        let log = AuditLog::start(&config, client).await.unwrap();
+        log.record(AuditEvent::new(
+            AuditEventKind::Auth,
+            AuditDecision::Blocked,
+            "bearer",
+            ThreatLevel::Medium,
+        ));
+        let mut buf = [0u8; 2048];
+        let len = tokio::time::timeout(std::time::Duration::from_secs(5), receiver.recv(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+        // facility 13 * 8 + warning (4)
+        assert!(message.starts_with("<108>1 "), "{message}");
+        assert!(message.contains("\"event\":\"auth\""), "{message}");
+    }
+}
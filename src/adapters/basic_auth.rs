@@ -0,0 +1,237 @@
+//! HTTP Basic authentication for routes.
+//!
+//! Credentials come from inline `users` and/or an htpasswd file read when the
+//! middleware is registered. Password hashes are verified on the blocking
+//! pool, since bcrypt is deliberately slow, and every rejection is recorded
+//! in the audit log like the token `auth` middleware's. The user is left in
+//! the request extensions as an [`AuthenticatedUser`] for `rbac:` policies.
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::Request,
+    http::{HeaderValue, StatusCode, header},
+    middleware::{Next, from_fn},
+    response::Response,
+};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use eyre::{WrapErr, eyre};
+use tower::{Layer, util::BoxCloneSyncService};
+
+use crate::{
+    adapters::{
+        audit::{AuditDecision, AuditEvent, AuditEventKind, AuditLog},
+        middleware::MiddlewareRegistry,
+    },
+    config::models::{BASIC_AUTH_MIDDLEWARE_PREFIX, BasicAuthConfig},
+    core::{
+        client_ip::request_client_ip,
+        error_response,
+        htpasswd::{Htpasswd, PasswordHash},
+        rbac::AuthenticatedUser,
+        waf::ThreatLevel,
+    },
+};
+
+/// A [`BasicAuthConfig`] with its users loaded.
+pub struct BasicAuth {
+    users: Htpasswd,
+    challenge: HeaderValue,
+}
+
+impl BasicAuth {
+    /// Load the users of a configuration, reading its htpasswd file.
+    pub fn new(config: &BasicAuthConfig) -> eyre::Result<Self> {
+        let mut users = match &config.htpasswd_file {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .wrap_err_with(|| format!("Cannot read htpasswd file {path}"))?;
+                Htpasswd::parse(&contents).wrap_err_with(|| format!("htpasswd file {path}"))?
+            }
+            None => Htpasswd::default(),
+        };
+        for (user, hash) in &config.users {
+            let hash: PasswordHash = hash
+                .parse()
+                .wrap_err_with(|| format!("Password hash of user '{user}'"))?;
+            users.insert(user, hash);
+        }
+        let challenge = HeaderValue::from_str(&format!(
+            "Basic realm=\"{}\", charset=\"UTF-8\"",
+            config.realm
+        ))
+        .map_err(|_| eyre!("Invalid realm '{}'", config.realm))?;
+        Ok(Self { users, challenge })
+    }
+
+    /// The user of an `Authorization` header value with valid credentials.
+    pub fn authenticate(&self, authorization: &str) -> Option<String> {
+        basic_credentials(authorization)
+            .filter(|(user, password)| self.users.verify(user, password))
+            .map(|(user, _)| user)
+    }
+}
+
+/// User and password of an `Authorization: Basic` header value.
+pub(crate) fn basic_credentials(authorization: &str) -> Option<(String, String)> {
+    let (scheme, credentials) = authorization.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let credentials = String::from_utf8(STANDARD.decode(credentials.trim()).ok()?).ok()?;
+    let (user, password) = credentials.split_once(':')?;
+    Some((user.to_string(), password.to_string()))
+}
+
+/// Record a rejected authentication attempt in the audit log.
+pub(crate) fn record_auth_failure(audit: &AuditLog, req: &Request, rule: &str, route: &str) {
+    let client = request_client_ip(req);
+    audit.record(
+        AuditEvent::new(
+            AuditEventKind::Auth,
+            AuditDecision::Blocked,
+            rule,
+            ThreatLevel::Medium,
+        )
+        .request(req.method(), req.uri(), req.headers())
+        .client(client)
+        .route(route),
+    );
+}
+
+/// Reject requests without valid Basic credentials with a `401` challenge.
+async fn basic_auth_middleware(
+    mut req: Request,
+    next: Next,
+    auth: Arc<BasicAuth>,
+    audit: AuditLog,
+    route: Arc<str>,
+) -> Response {
+    let authorization = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let rule = match authorization {
+        Some(authorization) => {
+            let auth = auth.clone();
+            let user = tokio::task::spawn_blocking(move || auth.authenticate(&authorization))
+                .await
+                .unwrap_or(None);
+            if let Some(name) = user {
+                req.extensions_mut().insert(AuthenticatedUser {
+                    name,
+                    groups: Arc::from([]),
+                });
+                return next.run(req).await;
+            }
+            "invalid_credentials"
+        }
+        None => "missing_credentials",
+    };
+
+    record_auth_failure(&audit, &req, rule, &route);
+    let mut response = error_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+    response
+        .headers_mut()
+        .insert(header::WWW_AUTHENTICATE, auth.challenge.clone());
+    response
+}
+
+/// Register every configured setting as `basic_auth:<name>`.
+pub fn register_basic_auth(
+    registry: &mut MiddlewareRegistry,
+    configs: &HashMap<String, BasicAuthConfig>,
+) -> eyre::Result<()> {
+    for (name, config) in configs {
+        let auth =
+            Arc::new(BasicAuth::new(config).wrap_err_with(|| format!("Basic auth '{name}'"))?);
+        registry.register(
+            format!("{BASIC_AUTH_MIDDLEWARE_PREFIX}{name}"),
+            move |inner, ctx| {
+                let auth = auth.clone();
+                let audit = ctx.audit.clone();
+                let route: Arc<str> = Arc::from(ctx.route_prefix);
+                BoxCloneSyncService::new(
+                    from_fn(move |req, next| {
+                        basic_auth_middleware(req, next, auth.clone(), audit.clone(), route.clone())
+                    })
+                    .layer(inner),
+                )
+            },
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, body::Body, middleware, routing::get};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_basic_auth() {
+        let htpasswd = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(
+            htpasswd.path(),
+            "alice:$apr1$r31.....$HqJZimcKQFAMYayBlzkrA/\n",
+        )
+        .unwrap();
+        let config = BasicAuthConfig {
+            realm: "Internal".to_string(),
+            users: HashMap::from([("bob".to_string(), bcrypt::hash("hunter2", 4).unwrap())]),
+            htpasswd_file: Some(htpasswd.path().to_string_lossy().into_owned()),
+        };
+        let auth = Arc::new(BasicAuth::new(&config).unwrap());
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn(move |req, next| {
+                basic_auth_middleware(
+                    req,
+                    next,
+                    auth.clone(),
+                    AuditLog::disabled(),
+                    Arc::from("/"),
+                )
+            }));
+        let call = |authorization: Option<String>| {
+            let app = app.clone();
+            async move {
+                let mut req = Request::get("/");
+                if let Some(authorization) = authorization {
+                    req = req.header(header::AUTHORIZATION, authorization);
+                }
+                app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap()
+            }
+        };
+        let basic = |credentials: &str| Some(format!("Basic {}", STANDARD.encode(credentials)));
+
+        assert_eq!(
+            call(basic("alice:myPassword")).await.status(),
+            StatusCode::OK
+        );
+        assert_eq!(call(basic("bob:hunter2")).await.status(), StatusCode::OK);
+        let rejected = call(basic("bob:myPassword")).await;
+        assert_eq!(rejected.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            rejected.headers()[header::WWW_AUTHENTICATE],
+            "Basic realm=\"Internal\", charset=\"UTF-8\""
+        );
+        assert_eq!(call(None).await.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            call(Some("Bearer abc".to_string())).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            call(basic("carol:myPassword")).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let unsupported = BasicAuthConfig {
+            users: HashMap::from([("eve".to_string(), "plaintext".to_string())]),
+            ..BasicAuthConfig::default()
+        };
+        assert!(BasicAuth::new(&unsupported).is_err());
+    }
+}
@@ -1,16 +1,34 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 
+use arc_swap::ArcSwap;
 use async_trait::async_trait;
 use eyre::{Context, Result};
 use notify::{RecursiveMode, Watcher};
 use tokio::sync::mpsc;
 
 use crate::{
-    config::{loader::load_config, models::ServerConfig},
+    config::{
+        loader::load_config,
+        models::{ConfigWatchConfig, ConfigWatchMode, ServerConfig},
+    },
     ports::config_provider::ConfigProvider,
 };
 
 /// Configuration provider that loads from a local file and watches for changes.
+///
+/// Changes are detected with filesystem notifications. Where those are
+/// unreliable (NFS, some container runtimes) the provider polls the file's
+/// content hash instead, either because `config_watch.mode = "poll"` or, in
+/// `auto` mode, because the watcher reported an error.
 pub struct FileConfigProvider {
     path: PathBuf,
     // We keep the watcher alive by storing it, even though we don't access it directly after init
@@ -19,6 +37,10 @@ pub struct FileConfigProvider {
     update_tx: mpsc::Sender<()>,
     // We store the receiver in an Option so we can take it once
     update_rx: std::sync::Mutex<Option<mpsc::Receiver<()>>>,
+    // Watch settings from the most recently loaded configuration
+    watch_config: Arc<ArcSwap<ConfigWatchConfig>>,
+    // Set once the filesystem watcher has failed or could not be started
+    watcher_failed: Arc<AtomicBool>,
 }
 
 impl FileConfigProvider {
@@ -31,9 +53,17 @@ impl FileConfigProvider {
             _watcher: None,
             update_tx: tx,
             update_rx: std::sync::Mutex::new(Some(rx)),
+            watch_config: Arc::new(ArcSwap::from_pointee(ConfigWatchConfig::default())),
+            watcher_failed: Arc::new(AtomicBool::new(false)),
         };
 
-        provider.init_watcher()?;
+        if let Err(e) = provider.init_watcher() {
+            tracing::warn!(
+                "Failed to start config file watcher: {:#}; falling back to polling",
+                e
+            );
+            provider.watcher_failed.store(true, Ordering::Relaxed);
+        }
         Ok(provider)
     }
 
@@ -44,14 +74,17 @@ impl FileConfigProvider {
             .file_name()
             .ok_or_else(|| eyre::eyre!("Invalid config path"))?
             .to_owned();
+        let watch_config = self.watch_config.clone();
+        let watcher_failed = self.watcher_failed.clone();
 
         let mut watcher =
             notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
                 match res {
                     Ok(event) => {
-                        if (event.kind.is_modify()
-                            || event.kind.is_create()
-                            || event.kind.is_remove())
+                        if watch_config.load().mode != ConfigWatchMode::Poll
+                            && (event.kind.is_modify()
+                                || event.kind.is_create()
+                                || event.kind.is_remove())
                             && event
                                 .paths
                                 .iter()
@@ -62,7 +95,14 @@ impl FileConfigProvider {
                             let _ = tx.try_send(());
                         }
                     }
-                    Err(e) => tracing::error!("File watch error: {:?}", e),
+                    Err(e) => {
+                        tracing::error!("File watch error: {:?}", e);
+                        if !watcher_failed.swap(true, Ordering::Relaxed)
+                            && watch_config.load().mode == ConfigWatchMode::Auto
+                        {
+                            tracing::warn!("Falling back to polling for config changes");
+                        }
+                    }
                 }
             })?;
 
@@ -76,6 +116,23 @@ impl FileConfigProvider {
     }
 }
 
+/// Whether the polling task should report content changes.
+fn polling_active(config: &ConfigWatchConfig, watcher_failed: bool) -> bool {
+    match config.mode {
+        ConfigWatchMode::Auto => watcher_failed,
+        ConfigWatchMode::Notify => false,
+        ConfigWatchMode::Poll => true,
+    }
+}
+
+/// Hash of the file's content, or `None` if it cannot be read.
+async fn content_hash(path: &Path) -> Option<u64> {
+    let bytes = tokio::fs::read(path).await.ok()?;
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
 #[async_trait]
 impl ConfigProvider for FileConfigProvider {
     async fn load_config(&self) -> Result<ServerConfig> {
@@ -83,15 +140,52 @@ impl ConfigProvider for FileConfigProvider {
             .path
             .to_str()
             .ok_or_else(|| eyre::eyre!("Invalid path"))?;
-        load_config(path_str).await
+        let config = load_config(path_str).await?;
+        self.watch_config
+            .store(Arc::new(config.config_watch.clone()));
+        Ok(config)
     }
 
     fn watch(&self) -> mpsc::Receiver<()> {
-        self.update_rx
+        let rx = self
+            .update_rx
             .lock()
             .expect("failed to lock update_rx mutex")
             .take()
-            .expect("Watch can only be called once")
+            .expect("Watch can only be called once");
+
+        let tx = self.update_tx.clone();
+        let path = self.path.clone();
+        let watch_config = self.watch_config.clone();
+        let watcher_failed = self.watcher_failed.clone();
+        tokio::spawn(async move {
+            // None until the first check, which only records the baseline
+            let mut was_active = None;
+            let mut last_hash = None;
+
+            while !tx.is_closed() {
+                let settings = watch_config.load_full();
+                let active = polling_active(&settings, watcher_failed.load(Ordering::Relaxed));
+                if active {
+                    let hash = content_hash(&path).await;
+                    let changed = match was_active {
+                        None => false,
+                        // Switching to polling may have missed a change; reload once to catch up
+                        Some(false) => true,
+                        Some(true) => hash != last_hash,
+                    };
+                    if changed {
+                        tracing::debug!("Config file content changed (polling)");
+                        let _ = tx.try_send(());
+                    }
+                    last_hash = hash;
+                }
+                was_active = Some(active);
+                tokio::time::sleep(Duration::from_secs(settings.poll_interval_secs.max(1))).await;
+            }
+        });
+
+        rx
     }
 }
 
@@ -158,4 +252,53 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_poll_mode_detects_changes() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("config.toml");
+        let config_for = |port: u16| {
+            format!(
+                r#"
+                listen_addr = "127.0.0.1:{port}"
+                [config_watch]
+                mode = "poll"
+                poll_interval_secs = 1
+                [routes."/"]
+                type = "static"
+                root = "./static"
+            "#
+            )
+        };
+        std::fs::write(&file_path, config_for(8080))?;
+
+        let provider = FileConfigProvider::new(&file_path)?;
+        let config = provider.load_config().await?;
+        assert_eq!(config.config_watch.mode, ConfigWatchMode::Poll);
+        let mut rx = provider.watch();
+
+        // Let the poller record its baseline before changing the file
+        sleep(Duration::from_millis(200)).await;
+        std::fs::write(&file_path, config_for(9090))?;
+
+        let notification = tokio::time::timeout(Duration::from_secs(4), rx.recv()).await;
+        assert!(notification.is_ok(), "Timed out waiting for polled update");
+
+        let config = provider.load_config().await?;
+        assert_eq!(config.listen_addr, "127.0.0.1:9090");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_polling_active_by_mode() {
+        let config = |mode| ConfigWatchConfig {
+            mode,
+            poll_interval_secs: 5,
+        };
+        assert!(!polling_active(&config(ConfigWatchMode::Auto), false));
+        assert!(polling_active(&config(ConfigWatchMode::Auto), true));
+        assert!(!polling_active(&config(ConfigWatchMode::Notify), true));
+        assert!(polling_active(&config(ConfigWatchMode::Poll), false));
+    }
 }
@@ -1,21 +1,66 @@
-use std::time::Duration;
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 
 use async_trait::async_trait;
-use eyre::{Context, Result};
+use config::{Config, File, FileFormat};
+use eyre::{Context, Result, eyre};
 use hpx::Client;
+use sha2::{Digest, Sha256};
 use tokio::sync::mpsc;
 
-use crate::{config::models::ServerConfig, ports::config_provider::ConfigProvider};
+use crate::{
+    config::{ServerConfigValidator, models::ServerConfig},
+    ports::config_provider::ConfigProvider,
+};
+
+/// Settings for fetching configuration from a central config service.
+#[derive(Debug, Clone)]
+pub struct RemoteConfigOptions {
+    /// Header carrying `token`; `Authorization` sends it as a bearer token
+    pub auth_header: String,
+    pub token: Option<String>,
+    /// Expected hex SHA-256 of the configuration document
+    pub sha256: Option<String>,
+    /// Local copy of the last valid document, used when the service is
+    /// unreachable at startup
+    pub cache_path: Option<PathBuf>,
+    pub poll_interval: Duration,
+}
+
+impl Default for RemoteConfigOptions {
+    fn default() -> Self {
+        Self {
+            auth_header: "Authorization".to_string(),
+            token: None,
+            sha256: None,
+            cache_path: None,
+            poll_interval: Duration::from_secs(10),
+        }
+    }
+}
 
 /// Configuration provider that fetches from an HTTP URL and polls for changes.
+///
+/// Documents are parsed by the URL's extension (`.toml`, `.yaml`/`.yml`,
+/// otherwise JSON), checked against the pinned checksum and validated before
+/// use. The last accepted document is written to the cache path so a gateway
+/// can cold-start while the config service is down.
 pub struct HttpConfigProvider {
     url: String,
     client: Client,
+    options: RemoteConfigOptions,
+    format: FileFormat,
+    /// Set once a configuration has been served; the cache is only a
+    /// cold-start fallback, never a substitute for a failed reload.
+    loaded: AtomicBool,
     update_rx: std::sync::Mutex<Option<mpsc::Receiver<()>>>,
 }
 
 impl HttpConfigProvider {
-    pub fn new(url: String, poll_interval: Duration) -> Self {
+    pub fn new(url: String, options: RemoteConfigOptions) -> Self {
         let (tx, rx) = mpsc::channel(1);
         let client = Client::builder()
             .timeout(Duration::from_secs(10))
@@ -23,45 +68,43 @@ impl HttpConfigProvider {
             .unwrap_or_default();
 
         let provider = Self {
+            format: document_format(&url),
             url: url.clone(),
             client: client.clone(),
+            options: options.clone(),
+            loaded: AtomicBool::new(false),
             update_rx: std::sync::Mutex::new(Some(rx)),
         };
 
-        // Start polling task
+        // Poll the raw document and signal when its digest changes
         tokio::spawn(async move {
-            let mut last_config_hash: Option<u64> = None;
-            let mut interval = tokio::time::interval(poll_interval);
+            let mut last_digest: Option<String> = None;
+            let mut interval = tokio::time::interval(options.poll_interval);
 
             loop {
                 interval.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
 
-                // Fetch config to check for changes (naive approach: fetch & hash)
-                // In a real system, use ETag or Last-Modified
-                match Self::fetch_config(&client, &url).await {
-                    Ok(config) => {
-                        use std::{
-                            collections::hash_map::DefaultHasher,
-                            hash::{Hash, Hasher},
-                        };
-
-                        // We can't easily hash ServerConfig without deriving Hash.
-                        // For now, let's just serialize to JSON and hash that.
-                        if let Ok(json) = serde_json::to_string(&config) {
-                            let mut hasher = DefaultHasher::new();
-                            json.hash(&mut hasher);
-                            let new_hash = hasher.finish();
-
-                            if let Some(last) = last_config_hash {
-                                if last != new_hash {
-                                    tracing::info!("Remote configuration changed");
-                                    let _ = tx.try_send(());
-                                    last_config_hash = Some(new_hash);
-                                }
-                            } else {
-                                last_config_hash = Some(new_hash);
-                            }
+                match Self::fetch_document(&client, &url, &options).await {
+                    Ok(document) => {
+                        let digest = sha256_hex(&document);
+                        if let Some(pinned) = &options.sha256
+                            && !pinned.eq_ignore_ascii_case(&digest)
+                        {
+                            tracing::warn!(
+                                url = %url,
+                                digest = %digest,
+                                "Remote configuration does not match the pinned checksum; ignoring"
+                            );
+                            continue;
+                        }
+                        if last_digest.as_ref().is_some_and(|last| *last != digest) {
+                            tracing::info!("Remote configuration changed");
+                            let _ = tx.try_send(());
                         }
+                        last_digest = Some(digest);
                     }
                     Err(e) => {
                         tracing::warn!("Failed to poll remote config: {}", e);
@@ -73,16 +116,70 @@ impl HttpConfigProvider {
         provider
     }
 
-    async fn fetch_config(client: &Client, url: &str) -> Result<ServerConfig> {
-        let resp = client
-            .get(url)
-            .send()
-            .await
-            .context("Failed to send request")?;
-        let config = resp
-            .json::<ServerConfig>()
+    /// Fetch, verify and parse the remote document without validating it.
+    pub async fn fetch(&self) -> Result<ServerConfig> {
+        let document = Self::fetch_document(&self.client, &self.url, &self.options).await?;
+        self.verify_checksum(&document)?;
+        let config = parse_document(&document, self.format)
+            .with_context(|| format!("Failed to parse config from {}", self.url))?;
+        Ok(config)
+    }
+
+    async fn fetch_document(
+        client: &Client,
+        url: &str,
+        options: &RemoteConfigOptions,
+    ) -> Result<String> {
+        let mut request = client.get(url);
+        if let Some(token) = &options.token {
+            request = if options.auth_header.eq_ignore_ascii_case("authorization") {
+                request.bearer_auth(token)
+            } else {
+                request.header(options.auth_header.as_str(), token.as_str())
+            };
+        }
+        let resp = request.send().await.context("Failed to send request")?;
+        if !resp.status().is_success() {
+            return Err(eyre!("Config server returned {}", resp.status()));
+        }
+        resp.text().await.context("Failed to read config body")
+    }
+
+    fn verify_checksum(&self, document: &str) -> Result<()> {
+        match &self.options.sha256 {
+            Some(pinned) if !pinned.eq_ignore_ascii_case(&sha256_hex(document)) => Err(eyre!(
+                "Config checksum mismatch: expected {pinned}, got {}",
+                sha256_hex(document)
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fetch and validate the remote document, then refresh the local cache.
+    async fn load_remote(&self) -> Result<ServerConfig> {
+        let document = Self::fetch_document(&self.client, &self.url, &self.options).await?;
+        let config = self.accept_document(&document)?;
+        if let Some(cache_path) = &self.options.cache_path
+            && let Err(e) = write_cache(cache_path, &document).await
+        {
+            tracing::warn!(path = %cache_path.display(), "Failed to cache remote config: {e:#}");
+        }
+        Ok(config)
+    }
+
+    /// Load the cached document written by a previous successful fetch.
+    async fn load_cached(&self, cache_path: &Path) -> Result<ServerConfig> {
+        let document = tokio::fs::read_to_string(cache_path)
             .await
-            .context("Failed to parse JSON config")?;
+            .with_context(|| format!("Failed to read config cache {}", cache_path.display()))?;
+        self.accept_document(&document)
+    }
+
+    fn accept_document(&self, document: &str) -> Result<ServerConfig> {
+        self.verify_checksum(document)?;
+        let config = parse_document(document, self.format)
+            .with_context(|| format!("Failed to parse config from {}", self.url))?;
+        ServerConfigValidator::validate(&config).context("Remote config failed validation")?;
         Ok(config)
     }
 }
@@ -90,7 +187,24 @@ impl HttpConfigProvider {
 #[async_trait]
 impl ConfigProvider for HttpConfigProvider {
     async fn load_config(&self) -> Result<ServerConfig> {
-        Self::fetch_config(&self.client, &self.url).await
+        let config = match self.load_remote().await {
+            Ok(config) => config,
+            Err(e) => match &self.options.cache_path {
+                Some(cache_path) if !self.loaded.load(Ordering::Acquire) => {
+                    tracing::warn!(
+                        url = %self.url,
+                        cache = %cache_path.display(),
+                        "Remote config unavailable ({e:#}); starting from cached copy"
+                    );
+                    self.load_cached(cache_path).await.wrap_err_with(|| {
+                        format!("Remote config unavailable ({e:#}) and no usable cache")
+                    })?
+                }
+                _ => return Err(e),
+            },
+        };
+        self.loaded.store(true, Ordering::Release);
+        Ok(config)
     }
 
     fn watch(&self) -> mpsc::Receiver<()> {
@@ -102,20 +216,104 @@ impl ConfigProvider for HttpConfigProvider {
     }
 }
 
+/// Document format implied by the URL path's extension; JSON by default.
+pub(crate) fn document_format(url: &str) -> FileFormat {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    match path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+    {
+        Some(ext) if ext == "toml" => FileFormat::Toml,
+        Some(ext) if ext == "yaml" || ext == "yml" => FileFormat::Yaml,
+        _ => FileFormat::Json,
+    }
+}
+
+fn parse_document(document: &str, format: FileFormat) -> Result<ServerConfig> {
+    let mut config: ServerConfig = if format == FileFormat::Json {
+        serde_json::from_str(document).context("Failed to parse JSON config")?
+    } else {
+        Config::builder()
+            .add_source(File::from_str(document, format))
+            .build()
+            .context("Failed to build config")?
+            .try_deserialize()
+            .context("Failed to deserialize config")?
+    };
+    config.expand_virtual_hosts();
+    Ok(config)
+}
+
+pub(crate) fn sha256_hex(document: &str) -> String {
+    Sha256::digest(document.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Replace the cache file atomically so a crash never leaves a partial copy.
+async fn write_cache(path: &Path, document: &str) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    tokio::fs::write(&tmp, document)
+        .await
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::{Arc, Mutex};
 
-    use axum::{Json, Router, routing::get};
+    use axum::{Json, Router, http::HeaderMap, routing::get};
     use tokio::net::TcpListener;
 
     use super::*;
+    use crate::config::models::{RouteConfig, RouteConfigEntry};
+
+    fn valid_config(listen_addr: &str) -> ServerConfig {
+        let mut config = ServerConfig {
+            listen_addr: listen_addr.to_string(),
+            ..ServerConfig::default()
+        };
+        config.routes.insert(
+            "/".to_string(),
+            RouteConfigEntry::Single(Box::new(RouteConfig::Redirect {
+                target: "https://example.com".to_string(),
+                host: None,
+                status_code: None,
+                rate_limit: None,
+                middlewares: vec![],
+                error_pages: None,
+                disable_http3: false,
+                priority: None,
+            })),
+        );
+        config
+    }
+
+    async fn serve(app: Router) -> Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        Ok(format!("http://{addr}"))
+    }
+
+    fn options(poll_interval: Duration) -> RemoteConfigOptions {
+        RemoteConfigOptions {
+            poll_interval,
+            ..RemoteConfigOptions::default()
+        }
+    }
 
     #[tokio::test]
     async fn test_http_config_provider() -> Result<()> {
         // Shared state to update config dynamically
-        let config_state = Arc::new(Mutex::new(ServerConfig::default()));
-        config_state.lock().unwrap().listen_addr = "127.0.0.1:8080".to_string();
+        let config_state = Arc::new(Mutex::new(valid_config("127.0.0.1:8080")));
 
         let state = config_state.clone();
         let app = Router::new().route(
@@ -125,17 +323,10 @@ mod tests {
                 Json(config)
             }),
         );
-
-        let listener = TcpListener::bind("127.0.0.1:0").await?;
-        let addr = listener.local_addr()?;
-        let url = format!("http://{}/config", addr);
-
-        tokio::spawn(async move {
-            axum::serve(listener, app).await.unwrap();
-        });
+        let url = format!("{}/config", serve(app).await?);
 
         // Create provider with short poll interval
-        let provider = HttpConfigProvider::new(url, Duration::from_millis(100));
+        let provider = HttpConfigProvider::new(url, options(Duration::from_millis(100)));
 
         // Initial load
         let config = provider.load_config().await?;
@@ -167,4 +358,92 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_toml_document_with_token_and_checksum() -> Result<()> {
+        const DOCUMENT: &str = r#"
+listen_addr = "127.0.0.1:8081"
+
+[routes."/"]
+type = "redirect"
+target = "https://example.com"
+"#;
+        let app = Router::new().route(
+            "/axon.toml",
+            get(|headers: HeaderMap| async move {
+                match headers.get("x-config-token") {
+                    Some(token) if token == "secret" => Ok(DOCUMENT),
+                    _ => Err(axum::http::StatusCode::UNAUTHORIZED),
+                }
+            }),
+        );
+        let url = format!("{}/axon.toml", serve(app).await?);
+        let poll = Duration::from_secs(3600);
+
+        let unauthenticated = HttpConfigProvider::new(url.clone(), options(poll));
+        assert!(unauthenticated.load_config().await.is_err());
+
+        let authenticated = RemoteConfigOptions {
+            auth_header: "X-Config-Token".to_string(),
+            token: Some("secret".to_string()),
+            ..options(poll)
+        };
+        let provider = HttpConfigProvider::new(url.clone(), authenticated.clone());
+        assert_eq!(provider.load_config().await?.listen_addr, "127.0.0.1:8081");
+
+        let pinned = RemoteConfigOptions {
+            sha256: Some(sha256_hex(DOCUMENT).to_uppercase()),
+            ..authenticated.clone()
+        };
+        assert!(
+            HttpConfigProvider::new(url.clone(), pinned)
+                .load_config()
+                .await
+                .is_ok()
+        );
+
+        let wrong_pin = RemoteConfigOptions {
+            sha256: Some(sha256_hex("something else")),
+            ..authenticated
+        };
+        assert!(
+            HttpConfigProvider::new(url, wrong_pin)
+                .load_config()
+                .await
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cold_start_from_cache() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache_path = dir.path().join("axon.json");
+        let poll = Duration::from_secs(3600);
+
+        let app = Router::new().route(
+            "/config",
+            get(|| async { Json(valid_config("127.0.0.1:8082")) }),
+        );
+        let url = format!("{}/config", serve(app).await?);
+        let cached = RemoteConfigOptions {
+            cache_path: Some(cache_path.clone()),
+            ..options(poll)
+        };
+        HttpConfigProvider::new(url, cached.clone())
+            .load_config()
+            .await?;
+        assert!(cache_path.exists());
+
+        // Config service is down: the cached copy is used at startup only
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let unreachable = format!("http://{}/config", listener.local_addr()?);
+        drop(listener);
+        let provider = HttpConfigProvider::new(unreachable, cached);
+        assert_eq!(provider.load_config().await?.listen_addr, "127.0.0.1:8082");
+        assert!(provider.load_config().await.is_err());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,285 @@
+//! Per-route cookie policies.
+//!
+//! A policy strips selected cookies from requests before they reach the
+//! backend, rewrites the attributes of the backend's `Set-Cookie` headers,
+//! and can encrypt (AES-256-GCM) or sign (HMAC-SHA256) selected cookie
+//! values so clients only ever hold protected values while backends keep
+//! seeing plain ones. Protected cookies that fail to decrypt or verify are
+//! dropped from the request. Retired secrets keep opening older cookies until
+//! their grace period ends (see [`CookieSealer`]).
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::Request,
+    http::{HeaderValue, header},
+    middleware::{Next, from_fn},
+    response::Response,
+};
+use eyre::{WrapErr, bail};
+use tower::{Layer, util::BoxCloneSyncService};
+
+use crate::{
+    adapters::middleware::MiddlewareRegistry,
+    config::models::{COOKIE_POLICY_MIDDLEWARE_PREFIX, CookiePolicyConfig, SetCookieAttributes},
+    core::cookies,
+    utils::CookieSealer,
+};
+
+/// A [`CookiePolicyConfig`] with its sealer built.
+pub struct CookiePolicy {
+    strip_request_cookies: Vec<String>,
+    set_cookie: SetCookieAttributes,
+    encrypt: Vec<String>,
+    sign: Vec<String>,
+    sealer: Option<CookieSealer>,
+}
+
+impl CookiePolicy {
+    /// Build a policy from configuration.
+    pub fn new(config: &CookiePolicyConfig) -> eyre::Result<Self> {
+        let sealer = match &config.secret {
+            Some(secret) => {
+                let mut sealer = CookieSealer::new(secret)?;
+                for retired in &config.retired_secrets {
+                    sealer = sealer.with_retired_secret(&retired.secret, retired.accept_until)?;
+                }
+                Some(sealer)
+            }
+            None if !config.encrypt.is_empty() || !config.sign.is_empty() => {
+                bail!("A secret is required to encrypt or sign cookies")
+            }
+            None => None,
+        };
+        Ok(Self {
+            strip_request_cookies: config.strip_request_cookies.clone(),
+            set_cookie: config.set_cookie.clone(),
+            encrypt: config.encrypt.clone(),
+            sign: config.sign.clone(),
+            sealer,
+        })
+    }
+
+    /// The `Cookie` header to forward, given the client's `Cookie` header
+    /// values; `None` when no cookie is left.
+    pub fn request_cookies<'a>(&self, headers: impl Iterator<Item = &'a str>) -> Option<String> {
+        let mut forwarded = Vec::new();
+        for (name, value) in headers.flat_map(cookies::parse_cookie_header) {
+            if self.strip_request_cookies.iter().any(|n| n == name) {
+                continue;
+            }
+            let value = match &self.sealer {
+                Some(sealer) if self.encrypt.iter().any(|n| n == name) => sealer.open(name, value),
+                Some(sealer) if self.sign.iter().any(|n| n == name) => {
+                    sealer.verify(name, value).map(str::to_string)
+                }
+                _ => Some(value.to_string()),
+            };
+            match value {
+                Some(value) => forwarded.push(format!("{name}={value}")),
+                None => tracing::debug!(cookie = name, "Dropping cookie that failed verification"),
+            }
+        }
+        (!forwarded.is_empty()).then(|| forwarded.join("; "))
+    }
+
+    /// Protect and rewrite one backend `Set-Cookie` value.
+    pub fn response_cookie(&self, set_cookie: &str) -> String {
+        let protected = match (&self.sealer, cookies::set_cookie_pair(set_cookie)) {
+            // Empty values are deletions and stay as they are
+            (Some(sealer), Some((name, value))) if !value.is_empty() => {
+                if self.encrypt.iter().any(|n| n == name) {
+                    Some(sealer.seal(name, value))
+                } else if self.sign.iter().any(|n| n == name) {
+                    Some(sealer.sign(name, value))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        let set_cookie = match protected {
+            Some(value) => cookies::replace_set_cookie_value(set_cookie, &value),
+            None => set_cookie.to_string(),
+        };
+        cookies::rewrite_set_cookie(&set_cookie, &self.set_cookie)
+    }
+}
+
+/// Apply a cookie policy to a request and its response.
+async fn cookie_policy_middleware(
+    mut req: Request,
+    next: Next,
+    policy: Arc<CookiePolicy>,
+) -> Response {
+    if req.headers().contains_key(header::COOKIE) {
+        let forwarded = policy.request_cookies(
+            req.headers()
+                .get_all(header::COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok()),
+        );
+        req.headers_mut().remove(header::COOKIE);
+        if let Some(value) = forwarded.and_then(|value| HeaderValue::from_str(&value).ok()) {
+            req.headers_mut().insert(header::COOKIE, value);
+        }
+    }
+
+    let mut response = next.run(req).await;
+    let set_cookies: Vec<String> = response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| policy.response_cookie(value))
+        })
+        .collect();
+    if !set_cookies.is_empty() {
+        response.headers_mut().remove(header::SET_COOKIE);
+        for set_cookie in set_cookies {
+            if let Ok(value) = HeaderValue::from_str(&set_cookie) {
+                response.headers_mut().append(header::SET_COOKIE, value);
+            }
+        }
+    }
+    response
+}
+
+/// Register every configured policy as `cookie_policy:<name>`.
+pub fn register_cookie_policies(
+    registry: &mut MiddlewareRegistry,
+    policies: &HashMap<String, CookiePolicyConfig>,
+) -> eyre::Result<()> {
+    for (name, config) in policies {
+        let policy = Arc::new(
+            CookiePolicy::new(config).wrap_err_with(|| format!("Cookie policy '{name}'"))?,
+        );
+        registry.register(
+            format!("{COOKIE_POLICY_MIDDLEWARE_PREFIX}{name}"),
+            move |inner, _| {
+                let policy = policy.clone();
+                BoxCloneSyncService::new(
+                    from_fn(move |req, next| cookie_policy_middleware(req, next, policy.clone()))
+                        .layer(inner),
+                )
+            },
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, body::Body, middleware, routing::get};
+    use base64::{Engine as _, engine::general_purpose::STANDARD};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::config::models::CookieSameSite;
+
+    fn policy() -> CookiePolicy {
+        CookiePolicy::new(&CookiePolicyConfig {
+            strip_request_cookies: vec!["_ga".to_string()],
+            set_cookie: SetCookieAttributes {
+                secure: Some(true),
+                same_site: Some(CookieSameSite::Strict),
+                ..SetCookieAttributes::default()
+            },
+            encrypt: vec!["session".to_string()],
+            sign: vec!["cart".to_string()],
+            secret: Some(STANDARD.encode([7u8; 32])),
+            retired_secrets: vec![],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_protected_cookies_round_trip() {
+        let policy = policy();
+        let sealed = policy.response_cookie("session=user-42; Path=/");
+        assert!(sealed.ends_with("; Path=/; Secure; SameSite=Strict"));
+        let (_, sealed_value) = cookies::set_cookie_pair(&sealed).unwrap();
+        assert!(!sealed_value.contains("user-42"));
+        let signed = policy.response_cookie("cart=3");
+        let (_, signed_value) = cookies::set_cookie_pair(&signed).unwrap();
+        assert!(signed_value.starts_with("3."));
+
+        let header = format!("_ga=x; session={sealed_value}; cart={signed_value}; theme=dark");
+        assert_eq!(
+            policy
+                .request_cookies([header.as_str()].into_iter())
+                .as_deref(),
+            Some("session=user-42; cart=3; theme=dark")
+        );
+
+        // Tampered or moved values are dropped
+        let tampered = format!("session={signed_value}; cart=4.{}", &signed_value[2..]);
+        assert_eq!(
+            policy.request_cookies([tampered.as_str()].into_iter()),
+            None
+        );
+        let swapped = format!("cart={sealed_value}");
+        assert_eq!(policy.request_cookies([swapped.as_str()].into_iter()), None);
+
+        assert!(
+            CookiePolicy::new(&CookiePolicyConfig {
+                sign: vec!["cart".to_string()],
+                secret: Some(STANDARD.encode([7u8; 16])),
+                ..CookiePolicyConfig::default()
+            })
+            .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cookie_policy_middleware() {
+        let policy = Arc::new(policy());
+        let app = Router::new()
+            .route(
+                "/",
+                get(|req: Request| async move {
+                    let cookie = req
+                        .headers()
+                        .get(header::COOKIE)
+                        .map(|v| v.to_str().unwrap().to_string())
+                        .unwrap_or_default();
+                    Response::builder()
+                        .header(header::SET_COOKIE, "session=abc; HttpOnly")
+                        .header(header::SET_COOKIE, "theme=dark; Secure")
+                        .body(Body::from(cookie))
+                        .unwrap()
+                }),
+            )
+            .layer(middleware::from_fn(move |req, next| {
+                cookie_policy_middleware(req, next, policy.clone())
+            }));
+
+        let response = app
+            .oneshot(
+                Request::get("/")
+                    .header(header::COOKIE, "_ga=1")
+                    .header(header::COOKIE, "theme=light")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let set_cookies: Vec<&str> = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .map(|v| v.to_str().unwrap())
+            .collect();
+        assert_eq!(set_cookies.len(), 2);
+        assert!(set_cookies[0].starts_with("session="));
+        assert!(!set_cookies[0].starts_with("session=abc"));
+        assert!(set_cookies[0].ends_with("; HttpOnly; Secure; SameSite=Strict"));
+        assert_eq!(set_cookies[1], "theme=dark; Secure; SameSite=Strict");
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"theme=light");
+    }
+}
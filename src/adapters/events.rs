@@ -0,0 +1,389 @@
+//! Request events published to a message broker.
+//!
+//! Every completed request can be summarized as a [`RequestEvent`] (route,
+//! status, latency, tenant and WAF detections) for analytics pipelines.
+//! Events are sampled, queued on a bounded channel and published in batches
+//! by a background task, so a slow or unavailable broker never delays
+//! requests. Events that do not fit the queue or that the broker does not
+//! accept are dropped and counted in `axon_events_dropped_total`.
+//!
+//! Kafka and NATS sinks sit behind the `kafka` and `nats` Cargo features.
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use eyre::{Result, eyre};
+use http::{HeaderMap, HeaderName};
+use rand::RngExt;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::config::models::{EventSinkConfig, EventsConfig};
+
+/// How long a batch may wait for the broker before it counts as failed
+#[cfg_attr(not(any(feature = "kafka", feature = "nats")), allow(dead_code))]
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Summary of one completed request
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestEvent {
+    pub timestamp: DateTime<Utc>,
+    pub request_id: String,
+    /// Metric label of the matched route; `None` when no route matched
+    pub route: Option<String>,
+    pub vhost: Option<String>,
+    pub method: String,
+    pub status: u16,
+    pub latency_ms: u64,
+    pub tenant: Option<String>,
+    pub client_ip: Option<String>,
+    /// WAF threat types detected, whether blocked or only monitored
+    pub threats: Vec<String>,
+}
+
+impl RequestEvent {
+    /// Broker partitioning key: the tenant, else the route
+    fn key(&self) -> Option<&str> {
+        self.tenant.as_deref().or(self.route.as_deref())
+    }
+}
+
+/// Handle for publishing request events; cheap to clone.
+///
+/// The default handle is disabled and discards every event.
+#[derive(Clone, Default)]
+pub struct EventPublisher {
+    tx: Option<mpsc::Sender<RequestEvent>>,
+    sample_rate: f64,
+    tenant_header: Option<HeaderName>,
+}
+
+impl EventPublisher {
+    /// A handle that publishes nothing.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Connect the configured sink and start the publishing task.
+    pub async fn start(config: &EventsConfig) -> Result<Self> {
+        if !config.enabled {
+            return Ok(Self::disabled());
+        }
+        let sink_config = config
+            .sink
+            .as_ref()
+            .ok_or_else(|| eyre!("events.sink is required when events are enabled"))?;
+        let tenant_header = config
+            .tenant_header
+            .as_deref()
+            .map(|name| {
+                HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| eyre!("Invalid header name '{name}'"))
+            })
+            .transpose()?;
+        let sink = open_sink(sink_config).await?;
+        let (tx, rx) = mpsc::channel(config.queue_size.max(1));
+        tokio::spawn(run_sink(sink, rx, config.batch_size.max(1)));
+        Ok(Self {
+            tx: Some(tx),
+            sample_rate: config.sample_rate,
+            tenant_header,
+        })
+    }
+
+    /// A handle feeding events sampled at `sample_rate` into the returned
+    /// receiver.
+    #[cfg(test)]
+    pub(crate) fn channel(
+        capacity: usize,
+        sample_rate: f64,
+    ) -> (Self, mpsc::Receiver<RequestEvent>) {
+        let (tx, rx) = mpsc::channel(capacity);
+        let publisher = Self {
+            tx: Some(tx),
+            sample_rate,
+            tenant_header: Some(HeaderName::from_static("x-tenant")),
+        };
+        (publisher, rx)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.tx.is_some()
+    }
+
+    /// The tenant named by a request's headers
+    pub fn tenant(&self, headers: &HeaderMap) -> Option<String> {
+        let value = headers.get(self.tenant_header.as_ref()?)?.to_str().ok()?;
+        (!value.is_empty()).then(|| value.to_string())
+    }
+
+    /// Queue `event` for the sink without waiting, if it is sampled.
+    pub fn publish(&self, event: RequestEvent) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+        if event.threats.is_empty()
+            && self.sample_rate < 1.0
+            && rand::rng().random::<f64>() >= self.sample_rate
+        {
+            return;
+        }
+        match tx.try_send(event) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                crate::metrics::record_events_dropped("queue_full", 1);
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                crate::metrics::record_events_dropped("sink_closed", 1);
+            }
+        }
+    }
+}
+
+/// A broker connection
+#[async_trait::async_trait]
+trait EventSink: Send {
+    /// Publish serialized events with their keys; returns how many failed.
+    async fn publish(&mut self, batch: &[(Option<String>, Vec<u8>)]) -> usize;
+}
+
+async fn open_sink(config: &EventSinkConfig) -> Result<Box<dyn EventSink>> {
+    match config {
+        #[cfg(feature = "kafka")]
+        EventSinkConfig::Kafka {
+            brokers,
+            topic,
+            properties,
+        } => Ok(Box::new(kafka::KafkaSink::new(brokers, topic, properties)?)),
+        #[cfg(feature = "nats")]
+        EventSinkConfig::Nats { url, subject } => {
+            Ok(Box::new(nats::NatsSink::connect(url, subject).await?))
+        }
+        #[allow(unreachable_patterns)]
+        other => {
+            let feature = other.feature();
+            Err(eyre!(
+                "'{feature}' requires building with the `{feature}` feature"
+            ))
+        }
+    }
+}
+
+async fn run_sink(
+    mut sink: Box<dyn EventSink>,
+    mut rx: mpsc::Receiver<RequestEvent>,
+    batch_size: usize,
+) {
+    let mut batch = Vec::with_capacity(batch_size);
+    while rx.recv_many(&mut batch, batch_size).await > 0 {
+        let serialized: Vec<_> = batch
+            .drain(..)
+            .filter_map(|event| {
+                let payload = serde_json::to_vec(&event).ok()?;
+                Some((event.key().map(str::to_string), payload))
+            })
+            .collect();
+        let failed = sink.publish(&serialized).await;
+        if failed > 0 {
+            tracing::warn!(
+                failed,
+                events = serialized.len(),
+                "Failed to publish request events"
+            );
+            crate::metrics::record_events_dropped("sink_error", failed as u64);
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use std::collections::HashMap;
+
+    use eyre::{Result, WrapErr};
+    use futures_util::future::join_all;
+    use rdkafka::{
+        ClientConfig,
+        producer::{FutureProducer, FutureRecord},
+        util::Timeout,
+    };
+
+    use super::{EventSink, PUBLISH_TIMEOUT};
+
+    pub(super) struct KafkaSink {
+        producer: FutureProducer,
+        topic: String,
+    }
+
+    impl KafkaSink {
+        pub(super) fn new(
+            brokers: &[String],
+            topic: &str,
+            properties: &HashMap<String, String>,
+        ) -> Result<Self> {
+            let mut config = ClientConfig::new();
+            config.set("bootstrap.servers", brokers.join(",")).set(
+                "message.timeout.ms",
+                PUBLISH_TIMEOUT.as_millis().to_string(),
+            );
+            for (key, value) in properties {
+                config.set(key, value);
+            }
+            let producer = config
+                .create()
+                .wrap_err("Failed to create the Kafka producer")?;
+            Ok(Self {
+                producer,
+                topic: topic.to_string(),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl EventSink for KafkaSink {
+        async fn publish(&mut self, batch: &[(Option<String>, Vec<u8>)]) -> usize {
+            // Enqueue without waiting for room, so a full producer queue
+            // drops events instead of stalling the channel
+            let deliveries = batch.iter().map(|(key, payload)| {
+                let mut record = FutureRecord::to(&self.topic).payload(payload);
+                if let Some(key) = key {
+                    record = record.key(key);
+                }
+                self.producer
+                    .send(record, Timeout::After(Default::default()))
+            });
+            join_all(deliveries)
+                .await
+                .iter()
+                .filter(|delivery| delivery.is_err())
+                .count()
+        }
+    }
+}
+
+#[cfg(feature = "nats")]
+mod nats {
+    use eyre::{Result, WrapErr};
+
+    use super::{EventSink, PUBLISH_TIMEOUT};
+
+    pub(super) struct NatsSink {
+        client: async_nats::Client,
+        subject: String,
+    }
+
+    impl NatsSink {
+        /// Connect in the background, so an unreachable server does not
+        /// fail startup; events are dropped until it is reachable.
+        pub(super) async fn connect(url: &str, subject: &str) -> Result<Self> {
+            let client = async_nats::ConnectOptions::new()
+                .retry_on_initial_connect()
+                .connect(url)
+                .await
+                .wrap_err_with(|| format!("Failed to connect to NATS at {url}"))?;
+            Ok(Self {
+                client,
+                subject: subject.to_string(),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl EventSink for NatsSink {
+        async fn publish(&mut self, batch: &[(Option<String>, Vec<u8>)]) -> usize {
+            if self.client.connection_state() != async_nats::connection::State::Connected {
+                return batch.len();
+            }
+            let publish = async {
+                for (index, (_, payload)) in batch.iter().enumerate() {
+                    if self
+                        .client
+                        .publish(self.subject.clone(), payload.clone().into())
+                        .await
+                        .is_err()
+                    {
+                        return batch.len() - index;
+                    }
+                }
+                if self.client.flush().await.is_err() {
+                    return batch.len();
+                }
+                0
+            };
+            tokio::time::timeout(PUBLISH_TIMEOUT, publish)
+                .await
+                .unwrap_or(batch.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(threats: &[&str]) -> RequestEvent {
+        RequestEvent {
+            timestamp: Utc::now(),
+            request_id: "req-1".to_string(),
+            route: Some("/api".to_string()),
+            vhost: None,
+            method: "GET".to_string(),
+            status: 200,
+            latency_ms: 3,
+            tenant: None,
+            client_ip: Some("203.0.113.5".to_string()),
+            threats: threats.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_sampling_keeps_threats() {
+        let (publisher, mut rx) = EventPublisher::channel(16, 0.0);
+        publisher.publish(event(&[]));
+        publisher.publish(event(&["SQL_INJECTION"]));
+        assert_eq!(rx.try_recv().unwrap().threats, vec!["SQL_INJECTION"]);
+        assert!(rx.try_recv().is_err());
+
+        let (publisher, mut rx) = EventPublisher::channel(1, 1.0);
+        publisher.publish(event(&[]));
+        publisher.publish(event(&[]));
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_event_tenant_and_key() {
+        let (publisher, _rx) = EventPublisher::channel(1, 1.0);
+        let mut headers = HeaderMap::new();
+        assert_eq!(publisher.tenant(&headers), None);
+        headers.insert("x-tenant", "acme".parse().unwrap());
+        assert_eq!(publisher.tenant(&headers), Some("acme".to_string()));
+        assert_eq!(EventPublisher::disabled().tenant(&headers), None);
+
+        let mut event = event(&[]);
+        assert_eq!(event.key(), Some("/api"));
+        event.tenant = Some("acme".to_string());
+        assert_eq!(event.key(), Some("acme"));
+    }
+
+    #[tokio::test]
+    async fn test_start_requires_sink_feature() {
+        let config = EventsConfig {
+            enabled: true,
+            sink: Some(EventSinkConfig::Kafka {
+                brokers: vec!["127.0.0.1:9092".to_string()],
+                topic: "requests".to_string(),
+                properties: std::collections::HashMap::new(),
+            }),
+            ..EventsConfig::default()
+        };
+        assert_eq!(
+            EventPublisher::start(&config).await.is_ok(),
+            cfg!(feature = "kafka")
+        );
+        assert!(
+            !EventPublisher::start(&EventsConfig::default())
+                .await
+                .unwrap()
+                .is_enabled()
+        );
+    }
+}
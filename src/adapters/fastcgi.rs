@@ -0,0 +1,571 @@
+//! FastCGI backends such as PHP-FPM.
+//!
+//! [`HttpClientAdapter`](super::HttpClientAdapter) hands requests for
+//! `fastcgi://host:port` targets to [`send_request`] instead of speaking
+//! HTTP. Each request opens a connection, sends the CGI params built from
+//! the request and the route's [`FastCgiConfig`], streams the body as
+//! `FCGI_STDIN` and reads the CGI response from `FCGI_STDOUT`; `FCGI_STDERR`
+//! output is logged. Connections are not reused.
+use std::{
+    collections::BTreeMap,
+    io,
+    time::{Duration, Instant},
+};
+
+use axum::body::Body as AxumBody;
+use bytes::Bytes;
+use http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header, request::Parts};
+use http_body_util::BodyExt;
+use hyper::{Request, Response, body::Body as HttpBody};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpStream, tcp::OwnedReadHalf},
+};
+
+use crate::{
+    config::models::FastCgiConfig,
+    ports::http_client::{BackendTiming, HttpClientError, HttpClientResult},
+};
+
+/// URI scheme of FastCGI targets
+pub const SCHEME: &str = "fastcgi";
+
+const VERSION: u8 = 1;
+const BEGIN_REQUEST: u8 = 1;
+const END_REQUEST: u8 = 3;
+const PARAMS: u8 = 4;
+const STDIN: u8 = 5;
+const STDOUT: u8 = 6;
+const STDERR: u8 = 7;
+const RESPONDER: u16 = 1;
+/// Only one request is sent per connection
+const REQUEST_ID: u16 = 1;
+/// Largest record content
+const MAX_CONTENT: usize = u16::MAX as usize;
+/// Largest CGI response head accepted
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+
+/// The script a request path runs, and the path after it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScriptPath {
+    pub script_name: String,
+    pub path_info: String,
+}
+
+/// Map a request path to a script; `None` for paths with `..` segments or
+/// NUL bytes, which could name files outside the root.
+pub fn script_path(path: &str, config: &FastCgiConfig) -> Option<ScriptPath> {
+    let lowercase = path.to_ascii_lowercase();
+    if lowercase.contains("%00")
+        || lowercase
+            .split('/')
+            .any(|segment| segment.replace("%2e", ".") == "..")
+    {
+        return None;
+    }
+    if let Some(script) = &config.script {
+        return Some(ScriptPath {
+            script_name: script.clone(),
+            path_info: String::new(),
+        });
+    }
+
+    let extension = config.script_extension.as_str();
+    let mut end = 0;
+    for segment in path.split('/') {
+        end += segment.len();
+        if segment.len() > extension.len() && segment.ends_with(extension) {
+            return Some(ScriptPath {
+                script_name: path[..end].to_string(),
+                path_info: path[end..].to_string(),
+            });
+        }
+        end += 1;
+    }
+    let script_name = if path.ends_with('/') {
+        format!("{path}{}", config.index)
+    } else {
+        path.to_string()
+    };
+    Some(ScriptPath {
+        script_name,
+        path_info: String::new(),
+    })
+}
+
+/// CGI params for a request whose body is `content_length` bytes.
+///
+/// Request headers become `HTTP_*` params, except `Proxy` (which would set
+/// `HTTP_PROXY` for the script) and the content headers, which have their
+/// own. The client address is the last `X-Forwarded-For` entry, which the
+/// gateway appends.
+pub fn params(
+    parts: &Parts,
+    config: &FastCgiConfig,
+    content_length: u64,
+) -> Option<BTreeMap<String, String>> {
+    let path = parts.uri.path();
+    let script = script_path(path, config)?;
+    let mut params = BTreeMap::new();
+
+    for name in parts.headers.keys() {
+        if name == header::CONTENT_TYPE || name == header::CONTENT_LENGTH || name == "proxy" {
+            continue;
+        }
+        let value = parts
+            .headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let param = format!(
+            "HTTP_{}",
+            name.as_str().to_ascii_uppercase().replace('-', "_")
+        );
+        params.insert(param, value);
+    }
+
+    let header = |name: &str| parts.headers.get(name).and_then(|v| v.to_str().ok());
+    let https = header("x-forwarded-proto") == Some("https");
+    if let Some(host) = header("host").or(header("x-forwarded-host")) {
+        let (name, port) = match host.rsplit_once(':') {
+            Some((name, port)) if !name.ends_with(']') || host.starts_with('[') => {
+                (name, port.to_string())
+            }
+            _ => (host, if https { "443" } else { "80" }.to_string()),
+        };
+        params.insert("SERVER_NAME".to_string(), name.to_string());
+        params.insert("SERVER_PORT".to_string(), port);
+    }
+    if https {
+        params.insert("HTTPS".to_string(), "on".to_string());
+    }
+    if let Some(client) = header("x-forwarded-for").and_then(|v| v.rsplit(',').next()) {
+        params.insert("REMOTE_ADDR".to_string(), client.trim().to_string());
+    }
+    if let Some(content_type) = header("content-type") {
+        params.insert("CONTENT_TYPE".to_string(), content_type.to_string());
+    }
+
+    let root = config.root.trim_end_matches('/');
+    let request_uri = parts
+        .uri
+        .path_and_query()
+        .map_or(path, |path_and_query| path_and_query.as_str());
+    for (name, value) in [
+        ("GATEWAY_INTERFACE", "CGI/1.1".to_string()),
+        ("SERVER_SOFTWARE", "axon".to_string()),
+        ("SERVER_PROTOCOL", "HTTP/1.1".to_string()),
+        ("REQUEST_METHOD", parts.method.to_string()),
+        ("REQUEST_URI", request_uri.to_string()),
+        ("DOCUMENT_URI", path.to_string()),
+        ("DOCUMENT_ROOT", root.to_string()),
+        (
+            "QUERY_STRING",
+            parts.uri.query().unwrap_or_default().to_string(),
+        ),
+        ("CONTENT_LENGTH", content_length.to_string()),
+        ("SCRIPT_FILENAME", format!("{root}{}", script.script_name)),
+        ("SCRIPT_NAME", script.script_name),
+        ("PATH_INFO", script.path_info),
+    ] {
+        params.insert(name.to_string(), value);
+    }
+    params.extend(config.params.clone());
+    Some(params)
+}
+
+/// Append `content` as records of type `kind`, followed by an empty one
+/// when `end_stream`.
+fn push_records(out: &mut Vec<u8>, kind: u8, content: &[u8], end_stream: bool) {
+    let chunks = content.chunks(MAX_CONTENT);
+    let end = end_stream.then_some(&[][..]);
+    for chunk in chunks.chain(end) {
+        let padding = (8 - chunk.len() % 8) % 8;
+        let [id_hi, id_lo] = REQUEST_ID.to_be_bytes();
+        let [len_hi, len_lo] = (chunk.len() as u16).to_be_bytes();
+        out.extend_from_slice(&[
+            VERSION,
+            kind,
+            id_hi,
+            id_lo,
+            len_hi,
+            len_lo,
+            padding as u8,
+            0,
+        ]);
+        out.extend_from_slice(chunk);
+        out.resize(out.len() + padding, 0);
+    }
+}
+
+/// `FCGI_BEGIN_REQUEST` followed by the encoded params
+fn request_head(params: &BTreeMap<String, String>) -> Vec<u8> {
+    let mut out = Vec::new();
+    let [role_hi, role_lo] = RESPONDER.to_be_bytes();
+    push_records(
+        &mut out,
+        BEGIN_REQUEST,
+        &[role_hi, role_lo, 0, 0, 0, 0, 0, 0],
+        false,
+    );
+
+    let mut encoded = Vec::new();
+    for (name, value) in params {
+        for len in [name.len(), value.len()] {
+            match u8::try_from(len) {
+                Ok(len) if len < 0x80 => encoded.push(len),
+                _ => encoded.extend_from_slice(&(len as u32 | 0x8000_0000).to_be_bytes()),
+            }
+        }
+        encoded.extend_from_slice(name.as_bytes());
+        encoded.extend_from_slice(value.as_bytes());
+    }
+    push_records(&mut out, PARAMS, &encoded, true);
+    out
+}
+
+/// `FCGI_STDOUT` content of a response, with `FCGI_STDERR` logged.
+struct Records {
+    reader: BufReader<OwnedReadHalf>,
+    backend: String,
+    done: bool,
+}
+
+impl Records {
+    /// The next non-empty stdout content; `None` once the request ended.
+    async fn next(&mut self) -> io::Result<Option<Bytes>> {
+        while !self.done {
+            let mut header = [0u8; 8];
+            let record = async {
+                self.reader.read_exact(&mut header).await?;
+                let len = usize::from(u16::from_be_bytes([header[4], header[5]]));
+                let mut content = vec![0; len + usize::from(header[6])];
+                self.reader.read_exact(&mut content).await?;
+                content.truncate(len);
+                io::Result::Ok(content)
+            };
+            let content = match record.await {
+                Ok(content) => content,
+                Err(e) => {
+                    self.done = true;
+                    return Err(e);
+                }
+            };
+            match header[1] {
+                STDOUT if !content.is_empty() => return Ok(Some(Bytes::from(content))),
+                STDERR if !content.is_empty() => tracing::warn!(
+                    backend = %self.backend,
+                    "FastCGI stderr: {}",
+                    String::from_utf8_lossy(&content).trim_end()
+                ),
+                END_REQUEST => self.done = true,
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Where the CGI head ends and the body starts, if the blank line arrived
+fn head_end(stdout: &[u8]) -> Option<(usize, usize)> {
+    let crlf = stdout
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| (i, i + 4));
+    let lf = stdout
+        .windows(2)
+        .position(|w| w == b"\n\n")
+        .map(|i| (i, i + 2));
+    [crlf, lf].into_iter().flatten().min()
+}
+
+/// Status and headers of a CGI response head. Without a `Status` header, a
+/// `Location` makes it a `302`.
+fn parse_head(head: &[u8]) -> Option<(StatusCode, HeaderMap)> {
+    let mut status = None;
+    let mut headers = HeaderMap::new();
+    for line in head.split(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            continue;
+        }
+        let colon = line.iter().position(|&b| b == b':')?;
+        let name = line[..colon].trim_ascii();
+        let value = line[colon + 1..].trim_ascii();
+        if name.eq_ignore_ascii_case(b"status") {
+            let code = value.split(|&b| b == b' ').next()?;
+            status = Some(StatusCode::from_bytes(code).ok()?);
+        } else {
+            headers.append(
+                HeaderName::from_bytes(name).ok()?,
+                HeaderValue::from_bytes(value).ok()?,
+            );
+        }
+    }
+    let status = status.unwrap_or(if headers.contains_key(header::LOCATION) {
+        StatusCode::FOUND
+    } else {
+        StatusCode::OK
+    });
+    Some((status, headers))
+}
+
+/// Run `req` on the FastCGI server named by its URI.
+///
+/// A body without a known length is buffered first, since scripts read
+/// `CONTENT_LENGTH` bytes. The response body streams from the connection.
+pub async fn send_request(
+    req: Request<AxumBody>,
+    config: &FastCgiConfig,
+) -> HttpClientResult<Response<AxumBody>> {
+    let backend = req
+        .uri()
+        .authority()
+        .ok_or_else(|| HttpClientError::InvalidRequest("FastCGI target has no port".to_string()))?
+        .to_string();
+    let (parts, body) = req.into_parts();
+    let (body, content_length) = match body.size_hint().exact() {
+        Some(len) => (body, len),
+        None => {
+            let bytes = body
+                .collect()
+                .await
+                .map_err(|e| HttpClientError::InvalidRequest(format!("request body: {e}")))?
+                .to_bytes();
+            let len = bytes.len() as u64;
+            (AxumBody::from(bytes), len)
+        }
+    };
+    let params = params(&parts, config, content_length).ok_or_else(|| {
+        HttpClientError::InvalidRequest(format!("'{}' is outside the FastCGI root", parts.uri))
+    })?;
+
+    let connect_error =
+        |e: io::Error| HttpClientError::ConnectionError(format!("FastCGI backend {backend}: {e}"));
+    let sent = Instant::now();
+    let stream = TcpStream::connect(&backend).await.map_err(connect_error)?;
+    let connected = sent.elapsed();
+    let (read, mut write) = stream.into_split();
+
+    write
+        .write_all(&request_head(&params))
+        .await
+        .map_err(connect_error)?;
+    let mut body = body;
+    while let Some(frame) = body.frame().await {
+        let frame =
+            frame.map_err(|e| HttpClientError::InvalidRequest(format!("request body: {e}")))?;
+        // An empty record would end stdin early
+        if let Some(data) = frame.into_data().ok().filter(|data| !data.is_empty()) {
+            let mut records = Vec::with_capacity(data.len() + 16);
+            push_records(&mut records, STDIN, &data, false);
+            write.write_all(&records).await.map_err(connect_error)?;
+        }
+    }
+    let mut end = Vec::new();
+    push_records(&mut end, STDIN, &[], true);
+    write.write_all(&end).await.map_err(connect_error)?;
+
+    let mut records = Records {
+        reader: BufReader::new(read),
+        backend: backend.clone(),
+        done: false,
+    };
+    let mut stdout = Vec::new();
+    let (head_len, body_start) = loop {
+        match records.next().await.map_err(connect_error)? {
+            Some(data) => stdout.extend_from_slice(&data),
+            None => {
+                return Err(HttpClientError::ConnectionError(format!(
+                    "FastCGI backend {backend} ended the request without a response head"
+                )));
+            }
+        }
+        if let Some(split) = head_end(&stdout) {
+            break split;
+        }
+        if stdout.len() > MAX_HEAD_BYTES {
+            return Err(HttpClientError::ConnectionError(format!(
+                "FastCGI backend {backend} sent a response head over {MAX_HEAD_BYTES} bytes"
+            )));
+        }
+    };
+    let (status, headers) = parse_head(&stdout[..head_len]).ok_or_else(|| {
+        HttpClientError::ConnectionError(format!(
+            "FastCGI backend {backend} sent an invalid response head"
+        ))
+    })?;
+
+    let first = Bytes::from(stdout).slice(body_start..);
+    let first = (!first.is_empty()).then_some(first);
+    let stream = futures_util::stream::unfold((records, first), |(mut records, first)| async {
+        if let Some(first) = first {
+            return Some((Ok(first), (records, None)));
+        }
+        match records.next().await {
+            Ok(Some(data)) => Some((Ok(data), (records, None))),
+            Ok(None) => None,
+            Err(e) => Some((Err(e), (records, None))),
+        }
+    });
+    let mut response = Response::new(AxumBody::from_stream(stream));
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    response.extensions_mut().insert(BackendTiming {
+        connect: connected,
+        first_byte: sent.elapsed().saturating_sub(connected),
+    });
+    Ok(response)
+}
+
+/// Whether the FastCGI server at `authority` accepts connections
+pub async fn probe(authority: &str, timeout: Duration) -> bool {
+    matches!(
+        tokio::time::timeout(timeout, TcpStream::connect(authority)).await,
+        Ok(Ok(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn config() -> FastCgiConfig {
+        FastCgiConfig {
+            root: "/var/www/html/".to_string(),
+            index: "index.php".to_string(),
+            script: None,
+            script_extension: ".php".to_string(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_script_path() {
+        let config = config();
+        let split = |path| script_path(path, &config);
+        assert_eq!(
+            split("/blog/index.php/2024/hello"),
+            Some(ScriptPath {
+                script_name: "/blog/index.php".to_string(),
+                path_info: "/2024/hello".to_string(),
+            })
+        );
+        assert_eq!(split("/admin/").unwrap().script_name, "/admin/index.php");
+        assert_eq!(split("/robots.txt").unwrap().script_name, "/robots.txt");
+        assert_eq!(split("/.php/x").unwrap().script_name, "/.php/x");
+        assert_eq!(split("/../etc/passwd.php"), None);
+        assert_eq!(split("/a/%2E%2e/b.php"), None);
+
+        let front = FastCgiConfig {
+            script: Some("/index.php".to_string()),
+            ..config.clone()
+        };
+        assert_eq!(
+            script_path("/api/users", &front).unwrap().script_name,
+            "/index.php"
+        );
+    }
+
+    #[test]
+    fn test_params() {
+        let (parts, ()) = Request::post("/app.php/items?page=2")
+            .header("host", "shop.example.com")
+            .header("x-forwarded-proto", "https")
+            .header("x-forwarded-for", "198.51.100.7, 203.0.113.5")
+            .header("content-type", "application/json")
+            .header("proxy", "http://evil.example")
+            .header("x-request-id", "abc")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let params = params(&parts, &config(), 2).unwrap();
+        let param = |name: &str| params.get(name).map(String::as_str);
+        assert_eq!(param("SCRIPT_FILENAME"), Some("/var/www/html/app.php"));
+        assert_eq!(param("SCRIPT_NAME"), Some("/app.php"));
+        assert_eq!(param("PATH_INFO"), Some("/items"));
+        assert_eq!(param("REQUEST_URI"), Some("/app.php/items?page=2"));
+        assert_eq!(param("QUERY_STRING"), Some("page=2"));
+        assert_eq!(param("SERVER_NAME"), Some("shop.example.com"));
+        assert_eq!(param("SERVER_PORT"), Some("443"));
+        assert_eq!(param("HTTPS"), Some("on"));
+        assert_eq!(param("REMOTE_ADDR"), Some("203.0.113.5"));
+        assert_eq!(param("CONTENT_TYPE"), Some("application/json"));
+        assert_eq!(param("CONTENT_LENGTH"), Some("2"));
+        assert_eq!(param("HTTP_X_REQUEST_ID"), Some("abc"));
+        assert_eq!(param("HTTP_PROXY"), None);
+        assert_eq!(param("HTTP_CONTENT_TYPE"), None);
+    }
+
+    /// Read one request from `stream`, returning its params and stdin
+    async fn read_request(stream: &mut TcpStream) -> (Vec<u8>, Vec<u8>) {
+        let (mut params, mut stdin) = (Vec::new(), Vec::new());
+        loop {
+            let mut header = [0u8; 8];
+            stream.read_exact(&mut header).await.unwrap();
+            let len = usize::from(u16::from_be_bytes([header[4], header[5]]));
+            let mut content = vec![0; len + usize::from(header[6])];
+            stream.read_exact(&mut content).await.unwrap();
+            content.truncate(len);
+            match header[1] {
+                PARAMS => params.extend(content),
+                STDIN if len == 0 => return (params, stdin),
+                STDIN => stdin.extend(content),
+                _ => {}
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fastcgi_exchange() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (params, stdin) = read_request(&mut stream).await;
+            let mut out = Vec::new();
+            push_records(&mut out, STDERR, b"PHP Notice: demo\n", false);
+            push_records(
+                &mut out,
+                STDOUT,
+                b"Status: 201 Created\r\nContent-Type: text/plain\r\nX-Powered-By: PHP\r\n\r\ncreated ",
+                false,
+            );
+            push_records(&mut out, STDOUT, &stdin, true);
+            push_records(&mut out, END_REQUEST, &[0; 8], false);
+            stream.write_all(&out).await.unwrap();
+            params
+        });
+
+        let req = Request::post(format!("fastcgi://{addr}/items.php"))
+            .header("host", "shop.example.com")
+            .body(AxumBody::from("widget"))
+            .unwrap();
+        let response = send_request(req, &config()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()["x-powered-by"], "PHP");
+        assert!(response.extensions().get::<BackendTiming>().is_some());
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "created widget");
+
+        let params = server.await.unwrap();
+        let script = b"\x0f\x17SCRIPT_FILENAME/var/www/html/items.php";
+        assert!(params.windows(script.len()).any(|w| w == script));
+        // The listener closed with the server task
+        assert!(!probe(&addr.to_string(), Duration::from_secs(1)).await);
+    }
+
+    #[test]
+    fn test_parse_head() {
+        let (status, headers) =
+            parse_head(b"Location: /login\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2").unwrap();
+        assert_eq!(status, StatusCode::FOUND);
+        assert_eq!(headers.get_all("set-cookie").iter().count(), 2);
+        assert_eq!(parse_head(b"not a header"), None);
+        assert_eq!(head_end(b"A: b\n\nbody"), Some((4, 6)));
+    }
+}
@@ -2,9 +2,10 @@
 //!
 //! This module provides the concrete implementation of the [`FileSystem`] port
 //! used by the gateway for serving static assets. It wraps `tower_http::ServeDir`
-//! to efficiently serve files and directory listings (with automatic
-//! `index.html` resolution) and adds a small collection of helper methods for
-//! safe, sandboxed file operations that prevent path traversal attacks.
+//! to efficiently serve files, resolves directory index files, optional
+//! directory listings, SPA fallbacks and custom 404 pages, and adds a small
+//! collection of helper methods for safe, sandboxed file operations that
+//! prevent path traversal attacks.
 //!
 //! Only the `serve_file` method is required by the port. The additional helper
 //! methods (`file_exists`, `read_file`, `write_file`, `delete_file`,
@@ -21,7 +22,7 @@
 //! # Example
 //! ```no_run
 //! # use axon::adapters::FileSystemAdapter;
-//! # use axon::ports::file_system::FileSystem;
+//! # use axon::ports::file_system::{FileSystem, StaticServeOptions};
 //! # use axum::body::Body;
 //! # use hyper::Request;
 //! # use std::convert::TryFrom;
@@ -30,20 +31,37 @@
 //! let req = Request::builder()
 //!     .uri("/static/logo.png")
 //!     .body(Body::empty())?;
-//! let response = fs.serve_file("./public", "logo.png", req).await?;
+//! let options = StaticServeOptions::default();
+//! let response = fs.serve_file("./public", "logo.png", req, &options).await?;
 //! assert!(response.status().is_success());
 //! # Ok(()) }
 //! ```
 use std::convert::TryFrom;
 
+use async_trait::async_trait;
 use axum::body::Body as AxumBody;
 use eyre::{Result, WrapErr};
 use http_body_util::BodyExt;
-use hyper::{Request, Response};
+use hyper::{
+    Method, Request, Response, StatusCode,
+    header::{self, HeaderValue},
+    http::request::Parts,
+};
 use tower::ServiceExt;
 use tower_http::services::ServeDir;
 
-use crate::ports::file_system::{FileSystem, FileSystemError};
+use crate::{
+    config::models::{ErrorPageFormat, EtagMode, ListingFormat},
+    core::{
+        content_type::media_type_for_path,
+        error_pages::{escape_html, negotiate_format},
+        error_response,
+    },
+    ports::file_system::{FileSystem, FileSystemError, StaticServeOptions},
+};
+
+/// Index file served for directories when none is configured.
+const DEFAULT_INDEX_FILE: &str = "index.html";
 
 /// File system adapter using `tower_http::ServeDir` for static file serving.
 ///
@@ -61,11 +79,15 @@ impl FileSystemAdapter {
     }
 }
 
+#[async_trait]
 impl FileSystem for FileSystemAdapter {
     /// Serve a file or directory index below `root`.
     ///
     /// `path` is a logical path relative to `root`. A leading slash is
-    /// tolerated. Directories automatically attempt to serve `index.html`.
+    /// tolerated. Directories are redirected to their trailing-slash form and
+    /// serve `options.index_file` (default `index.html`), or a generated
+    /// listing when enabled. Missing paths fall back to the SPA index or the
+    /// custom 404 page before producing a gateway 404.
     ///
     /// # Errors
     /// Returns a [`FileSystemError`] if the path is invalid (e.g. traversal
@@ -75,40 +97,463 @@ impl FileSystem for FileSystemAdapter {
         root: &str,
         path: &str,
         req: Request<AxumBody>,
+        options: &StaticServeOptions,
     ) -> Result<Response<AxumBody>, FileSystemError> {
-        let root = root.to_string();
-        let path = path.to_string();
-
-        // Create a new request with the path adjusted for ServeDir
-        let uri_string = format!("/{path}", path = path.trim_start_matches('/'));
-        let uri = hyper::Uri::try_from(uri_string)
-            .wrap_err("Failed to parse URI for file serving")
+        let relative = path.trim_start_matches('/');
+        let decoded = urlencoding::decode(relative)
             .map_err(|e| FileSystemError::InvalidPath(e.to_string()))?;
+        if std::path::Path::new(decoded.as_ref())
+            .components()
+            .any(|c| !matches!(c, std::path::Component::Normal(_)))
+        {
+            return Err(FileSystemError::InvalidPath(
+                "Path traversal attempt detected".to_string(),
+            ));
+        }
+
+        let (parts, _) = req.into_parts();
+        let is_read = parts.method == Method::GET || parts.method == Method::HEAD;
+        let index_file = options.index_file.as_deref().unwrap_or(DEFAULT_INDEX_FILE);
+        let full_path = std::path::Path::new(root).join(decoded.as_ref());
+
+        let mut response = if is_read && is_dir(&full_path).await {
+            if !relative.is_empty() && !relative.ends_with('/') {
+                // Relative links in the index only resolve below a trailing slash
+                let location = match parts.uri.query() {
+                    Some(query) => format!("{}/?{query}", parts.uri.path()),
+                    None => format!("{}/", parts.uri.path()),
+                };
+                return Response::builder()
+                    .status(StatusCode::TEMPORARY_REDIRECT)
+                    .header(header::LOCATION, location)
+                    .body(AxumBody::empty())
+                    .map_err(|e| FileSystemError::InvalidPath(e.to_string()));
+            }
+            if is_file(&full_path.join(index_file)).await {
+                serve_dir(
+                    root,
+                    &parts,
+                    &format!("{relative}{index_file}"),
+                    true,
+                    options,
+                )
+                .await?
+            } else if options.directory_listing {
+                directory_listing(&full_path, &parts, relative, options.listing_format).await?
+            } else {
+                not_found(root, &parts, index_file, options).await?
+            }
+        } else {
+            let response = serve_dir(root, &parts, relative, true, options).await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                not_found(root, &parts, index_file, options).await?
+            } else {
+                response
+            }
+        };
 
-        let (parts, body) = req.into_parts();
-        let mut new_req = Request::from_parts(parts, body);
-        *new_req.uri_mut() = uri;
+        if let Some(cache_control) = &options.cache_control {
+            let cacheable =
+                response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED;
+            if cacheable && !response.headers().contains_key(header::CACHE_CONTROL) {
+                let value = HeaderValue::from_str(cache_control)
+                    .map_err(|e| FileSystemError::InvalidPath(e.to_string()))?;
+                response.headers_mut().insert(header::CACHE_CONTROL, value);
+            }
+        }
 
-        // Use ServeDir from tower-http with index file support
-        let serve_dir = ServeDir::new(&root).append_index_html_on_directories(true); // 自动添加 index.html 到目录路径
+        Ok(response)
+    }
+}
 
-        let response = serve_dir
-            .oneshot(new_req)
-            .await
-            .wrap_err("ServeDir failed to serve file")
-            .map_err(|e| {
-                FileSystemError::IoError(std::io::Error::other(format!("ServeDir error: {e}")))
-            })?;
+/// Response for a path that does not exist below `root`.
+///
+/// Read requests fall back to the root index when `spa_fallback` is set,
+/// then to `not_found_page` (served with status 404); otherwise a gateway
+/// 404 is returned so error pages apply.
+async fn not_found(
+    root: &str,
+    parts: &Parts,
+    index_file: &str,
+    options: &StaticServeOptions,
+) -> Result<Response<AxumBody>, FileSystemError> {
+    let is_read = parts.method == Method::GET || parts.method == Method::HEAD;
+
+    if is_read && options.spa_fallback {
+        let response = serve_dir(root, parts, index_file, true, options).await?;
+        if response.status() != StatusCode::NOT_FOUND {
+            return Ok(response);
+        }
+    }
 
-        let (parts, tower_body) = response.into_parts();
-        let axum_body = AxumBody::new(tower_body.map_err(|e| {
-            tracing::error!("Error reading static file body: {}", e);
-            // Convert Infallible to a type compatible with AxumBody's error
-            axum::Error::new(e)
-        }));
+    if let Some(page) = options.not_found_page.as_deref().filter(|_| is_read) {
+        // Conditional headers must not turn the 404 page into a 304
+        let mut response = serve_dir(root, parts, page, false, options).await?;
+        if response.status().is_success() {
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            return Ok(response);
+        }
+        tracing::warn!(page, "static not_found_page is missing");
+    }
+
+    Ok(error_response(
+        StatusCode::NOT_FOUND,
+        "File or directory not found",
+    ))
+}
+
+/// Run `ServeDir` for `path` with the original request's method and,
+/// optionally, its headers (for conditional and range requests).
+///
+/// Served files get an entity tag per `options.etag`, which is checked
+/// against `If-None-Match` and `If-Range`; `ServeDir` itself handles ranges
+/// and the date-based preconditions.
+async fn serve_dir(
+    root: &str,
+    parts: &Parts,
+    path: &str,
+    keep_headers: bool,
+    options: &StaticServeOptions,
+) -> Result<Response<AxumBody>, FileSystemError> {
+    let uri_string = format!("/{path}", path = path.trim_start_matches('/'));
+    let uri = hyper::Uri::try_from(uri_string)
+        .wrap_err("Failed to parse URI for file serving")
+        .map_err(|e| FileSystemError::InvalidPath(e.to_string()))?;
+
+    let etag_enabled = options.etag != EtagMode::Off;
+    let file_request = |with_range: bool| {
+        let mut new_req = Request::new(AxumBody::empty());
+        *new_req.method_mut() = parts.method.clone();
+        *new_req.uri_mut() = uri.clone();
+        if keep_headers {
+            *new_req.headers_mut() = parts.headers.clone();
+            // If-None-Match takes precedence over If-Modified-Since (RFC 9110 13.2.2)
+            if etag_enabled && parts.headers.contains_key(header::IF_NONE_MATCH) {
+                new_req.headers_mut().remove(header::IF_MODIFIED_SINCE);
+            }
+            if !with_range {
+                new_req.headers_mut().remove(header::RANGE);
+            }
+        }
+        new_req
+    };
+
+    // Directory handling (index file, listing) is done by the caller
+    let mut serve_dir = ServeDir::new(root).append_index_html_on_directories(false);
+    if options.precompressed {
+        serve_dir = serve_dir.precompressed_br().precompressed_gzip();
+    }
 
-        Ok(Response::from_parts(parts, axum_body))
+    let mut response = run_serve_dir(&serve_dir, file_request(true)).await?;
+    set_content_type(&mut response, path, options);
+    let status = response.status();
+    if !etag_enabled || !(status == StatusCode::OK || status == StatusCode::PARTIAL_CONTENT) {
+        return Ok(response);
     }
+
+    // Precompressed variants are distinct representations with their own tag
+    let decoded = urlencoding::decode(path.trim_start_matches('/'))
+        .map_err(|e| FileSystemError::InvalidPath(e.to_string()))?;
+    let suffix = match response
+        .headers()
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some("br") => ".br",
+        Some("gzip") => ".gz",
+        _ => "",
+    };
+    let file = std::path::Path::new(root).join(format!("{decoded}{suffix}"));
+    let Some(etag) = entity_tag(&file, options.etag).await else {
+        return Ok(response);
+    };
+
+    if keep_headers {
+        if let Some(if_none_match) = parts.headers.get(header::IF_NONE_MATCH)
+            && if_none_match_matches(if_none_match, &etag)
+        {
+            let mut not_modified = Response::new(AxumBody::empty());
+            *not_modified.status_mut() = StatusCode::NOT_MODIFIED;
+            for name in [header::LAST_MODIFIED, header::VARY] {
+                if let Some(value) = response.headers().get(&name) {
+                    not_modified.headers_mut().insert(name, value.clone());
+                }
+            }
+            not_modified.headers_mut().insert(header::ETAG, etag);
+            return Ok(not_modified);
+        }
+
+        if status == StatusCode::PARTIAL_CONTENT
+            && let Some(if_range) = parts.headers.get(header::IF_RANGE)
+            && !if_range_matches(
+                if_range,
+                &etag,
+                response.headers().get(header::LAST_MODIFIED),
+            )
+        {
+            // The client's partial copy is stale: send the whole representation
+            response = run_serve_dir(&serve_dir, file_request(false)).await?;
+            set_content_type(&mut response, path, options);
+        }
+    }
+
+    if options.precompressed && !response.headers().contains_key(header::VARY) {
+        response
+            .headers_mut()
+            .insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+    response.headers_mut().insert(header::ETAG, etag);
+    Ok(response)
+}
+
+/// Type a served file from its extension, applying the configured mappings
+/// over the built-in table `ServeDir` uses.
+fn set_content_type(response: &mut Response<AxumBody>, path: &str, options: &StaticServeOptions) {
+    let served = matches!(
+        response.status(),
+        StatusCode::OK | StatusCode::PARTIAL_CONTENT
+    );
+    if served
+        && let Some(value) = media_type_for_path(path, &options.content_types)
+            .and_then(|media_type| HeaderValue::from_str(&media_type).ok())
+    {
+        response.headers_mut().insert(header::CONTENT_TYPE, value);
+    }
+}
+
+async fn run_serve_dir(
+    serve_dir: &ServeDir,
+    req: Request<AxumBody>,
+) -> Result<Response<AxumBody>, FileSystemError> {
+    let response = serve_dir
+        .clone()
+        .oneshot(req)
+        .await
+        .wrap_err("ServeDir failed to serve file")
+        .map_err(|e| {
+            FileSystemError::IoError(std::io::Error::other(format!("ServeDir error: {e}")))
+        })?;
+
+    let (parts, tower_body) = response.into_parts();
+    let axum_body = AxumBody::new(tower_body.map_err(|e| {
+        tracing::error!("Error reading static file body: {}", e);
+        // Convert Infallible to a type compatible with AxumBody's error
+        axum::Error::new(e)
+    }));
+
+    Ok(Response::from_parts(parts, axum_body))
+}
+
+/// Entity tag from the file's modification time and size (`"<mtime>-<size>"`
+/// in hex, like nginx), or `None` when metadata is unavailable.
+async fn entity_tag(file: &std::path::Path, mode: EtagMode) -> Option<HeaderValue> {
+    let metadata = tokio::fs::metadata(file).await.ok()?;
+    let mtime = metadata
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    let tag = format!("\"{mtime:x}-{len:x}\"", len = metadata.len());
+    let tag = match mode {
+        EtagMode::Strong => tag,
+        EtagMode::Weak => format!("W/{tag}"),
+        EtagMode::Off => return None,
+    };
+    HeaderValue::from_str(&tag).ok()
+}
+
+/// Weak comparison of `If-None-Match` against `etag` (RFC 9110 13.1.2).
+fn if_none_match_matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    let Ok(value) = if_none_match.to_str() else {
+        return false;
+    };
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag.to_str().unwrap_or_default());
+    value.trim() == "*" || value.split(',').any(|candidate| opaque(candidate) == etag)
+}
+
+/// Whether an `If-Range` validator still matches: strong comparison for
+/// entity tags, exact match for dates (RFC 9110 13.1.5).
+fn if_range_matches(
+    if_range: &HeaderValue,
+    etag: &HeaderValue,
+    last_modified: Option<&HeaderValue>,
+) -> bool {
+    let value = if_range.as_bytes();
+    if value.starts_with(b"W/") {
+        false
+    } else if value.starts_with(b"\"") {
+        !etag.as_bytes().starts_with(b"W/") && value == etag.as_bytes()
+    } else {
+        last_modified.is_some_and(|date| date.as_bytes() == value)
+    }
+}
+
+async fn is_dir(path: &std::path::Path) -> bool {
+    tokio::fs::metadata(path).await.is_ok_and(|m| m.is_dir())
+}
+
+async fn is_file(path: &std::path::Path) -> bool {
+    tokio::fs::metadata(path).await.is_ok_and(|m| m.is_file())
+}
+
+/// One row of a generated directory listing.
+struct ListingEntry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Render a listing of `dir`, the directory at `relative` below the route
+/// root, as HTML or JSON.
+///
+/// Directories sort before files, then by name. HTML listings link each
+/// ancestor up to the route root in a breadcrumb.
+async fn directory_listing(
+    dir: &std::path::Path,
+    parts: &Parts,
+    relative: &str,
+    format: ListingFormat,
+) -> Result<Response<AxumBody>, FileSystemError> {
+    let mut entries = Vec::new();
+    let mut read_dir = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        // Follow symlinks so linked directories are listed as directories
+        let Ok(metadata) = tokio::fs::metadata(entry.path()).await else {
+            continue;
+        };
+        entries.push(ListingEntry {
+            name,
+            is_dir: metadata.is_dir(),
+            size: if metadata.is_dir() { 0 } else { metadata.len() },
+            modified: metadata.modified().ok().map(chrono::DateTime::from),
+        });
+    }
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+    let request_path = parts.uri.path();
+    let base = request_path.strip_suffix(relative).unwrap_or("/");
+    let json = match format {
+        ListingFormat::Html => false,
+        ListingFormat::Json => true,
+        ListingFormat::Auto => {
+            let accept = parts
+                .headers
+                .get(header::ACCEPT)
+                .and_then(|v| v.to_str().ok());
+            accept.is_some_and(|a| a.contains("application/json"))
+                && negotiate_format(accept) == ErrorPageFormat::Json
+        }
+    };
+
+    let (content_type, body) = if json {
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                let modified = entry
+                    .modified
+                    .map(|m| m.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+                serde_json::json!({
+                    "name": entry.name,
+                    "type": if entry.is_dir { "directory" } else { "file" },
+                    "size": entry.size,
+                    "modified": modified,
+                })
+            })
+            .collect();
+        let listing = serde_json::json!({
+            "path": urlencoding::decode(request_path).unwrap_or_default(),
+            "entries": entries,
+        });
+        ("application/json", listing.to_string())
+    } else {
+        (
+            "text/html; charset=utf-8",
+            listing_html(&entries, base, relative),
+        )
+    };
+
+    let length = body.len();
+    let body = if parts.method == Method::HEAD {
+        AxumBody::empty()
+    } else {
+        AxumBody::from(body)
+    };
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_LENGTH, length)
+        .header(header::VARY, "accept")
+        .body(body)
+        .map_err(|e| FileSystemError::InvalidPath(e.to_string()))
+}
+
+fn listing_html(entries: &[ListingEntry], base: &str, relative: &str) -> String {
+    let decode = |value: &str| escape_html(&urlencoding::decode(value).unwrap_or_default());
+    let title = decode(&format!("{base}{relative}"));
+
+    // Breadcrumb from the route root down to the listed directory
+    let root = if base.ends_with('/') {
+        base.to_string()
+    } else {
+        format!("{base}/")
+    };
+    let mut href = root.clone();
+    let mut breadcrumb = format!("<a href=\"{}\">{}</a>", escape_html(&href), decode(&root));
+    for segment in relative.split('/').filter(|s| !s.is_empty()) {
+        href.push_str(segment);
+        href.push('/');
+        breadcrumb.push_str(&format!(
+            " <a href=\"{}\">{}/</a>",
+            escape_html(&href),
+            decode(segment)
+        ));
+    }
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Index of {title}</title></head>\n<body>\n<h1>Index of {title}</h1>\n<nav>{breadcrumb}</nav>\n<table>\n<thead><tr><th>Name</th><th>Size</th><th>Last modified</th></tr></thead>\n<tbody>\n"
+    );
+    if !relative.trim_matches('/').is_empty() {
+        html.push_str("<tr><td><a href=\"../\">../</a></td><td>-</td><td></td></tr>\n");
+    }
+    for entry in entries {
+        let suffix = if entry.is_dir { "/" } else { "" };
+        let size = if entry.is_dir {
+            "-".to_string()
+        } else {
+            format_size(entry.size)
+        };
+        let modified = entry
+            .modified
+            .map(|m| m.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        html.push_str(&format!(
+            "<tr><td><a href=\"{href}{suffix}\">{label}{suffix}</a></td><td>{size}</td><td>{modified}</td></tr>\n",
+            href = urlencoding::encode(&entry.name),
+            label = escape_html(&entry.name),
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    html
+}
+
+/// Human-readable size using binary units, e.g. `1.5 KiB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
 }
 
 impl FileSystemAdapter {
@@ -368,4 +813,252 @@ mod tests {
         assert!(entries.contains(&"file2.txt".to_string()));
         assert!(entries.contains(&"subdir".to_string()));
     }
+
+    fn get(uri: &str) -> Request<AxumBody> {
+        Request::builder().uri(uri).body(AxumBody::empty()).unwrap()
+    }
+
+    async fn body_of(response: Response<AxumBody>) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_spa_fallback_and_not_found_page() {
+        let temp_dir = TempDir::new().unwrap();
+        let fs = FileSystemAdapter::new();
+        create_test_file(&temp_dir, "index.html", "app")
+            .await
+            .unwrap();
+        create_test_file(&temp_dir, "404.html", "missing")
+            .await
+            .unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let spa = StaticServeOptions {
+            spa_fallback: true,
+            ..StaticServeOptions::default()
+        };
+        let response = fs
+            .serve_file(
+                root,
+                "/dashboard/settings",
+                get("/dashboard/settings"),
+                &spa,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_of(response).await, "app");
+
+        let custom_404 = StaticServeOptions {
+            not_found_page: Some("404.html".to_string()),
+            ..StaticServeOptions::default()
+        };
+        let response = fs
+            .serve_file(root, "/nope.js", get("/nope.js"), &custom_404)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body_of(response).await, "missing");
+
+        let response = fs
+            .serve_file(
+                root,
+                "/nope.js",
+                get("/nope.js"),
+                &StaticServeOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(
+            response
+                .extensions()
+                .get::<crate::core::GatewayError>()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_directory_index_listing_and_cache_control() {
+        let temp_dir = TempDir::new().unwrap();
+        let fs = FileSystemAdapter::new();
+        create_test_file(&temp_dir, "docs/home.htm", "docs home")
+            .await
+            .unwrap();
+        create_test_file(&temp_dir, "files/a <b>.txt", "a")
+            .await
+            .unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let options = StaticServeOptions {
+            index_file: Some("home.htm".to_string()),
+            directory_listing: true,
+            cache_control: Some("public, max-age=60".to_string()),
+            ..StaticServeOptions::default()
+        };
+
+        let response = fs
+            .serve_file(root, "/docs", get("/static/docs?v=1"), &options)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "/static/docs/?v=1");
+
+        let response = fs
+            .serve_file(root, "/docs/", get("/static/docs/"), &options)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "public, max-age=60"
+        );
+        assert_eq!(body_of(response).await, "docs home");
+
+        let response = fs
+            .serve_file(root, "/files/", get("/static/files/"), &options)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let listing = body_of(response).await;
+        assert!(listing.contains("<a href=\"a%20%3Cb%3E.txt\">a &lt;b&gt;.txt</a>"));
+        assert!(listing.contains("<td>1 B</td>"));
+        assert!(listing.contains(
+            "<nav><a href=\"/static/\">/static/</a> <a href=\"/static/files/\">files/</a></nav>"
+        ));
+
+        create_test_file(&temp_dir, "files/sub/b.txt", "b")
+            .await
+            .unwrap();
+        let json_options = StaticServeOptions {
+            listing_format: ListingFormat::Auto,
+            ..options.clone()
+        };
+        let request = Request::builder()
+            .uri("/static/files/")
+            .header(header::ACCEPT, "application/json")
+            .body(AxumBody::empty())
+            .unwrap();
+        let response = fs
+            .serve_file(root, "/files/", request, &json_options)
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let listing: serde_json::Value = serde_json::from_str(&body_of(response).await).unwrap();
+        assert_eq!(listing["path"], "/static/files/");
+        let entries = listing["entries"].as_array().unwrap();
+        assert_eq!(entries[0]["name"], "sub");
+        assert_eq!(entries[0]["type"], "directory");
+        assert_eq!(entries[1]["name"], "a <b>.txt");
+        assert_eq!(entries[1]["size"], 1);
+        assert!(entries[1]["modified"].is_string());
+
+        let response = fs
+            .serve_file(root, "/files/", get("/static/files/"), &json_options)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/html; charset=utf-8"
+        );
+
+        let no_listing = StaticServeOptions::default();
+        let response = fs
+            .serve_file(root, "/files/", get("/static/files/"), &no_listing)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_precompressed_variant_and_conditional_requests() {
+        let temp_dir = TempDir::new().unwrap();
+        let fs = FileSystemAdapter::new();
+        create_test_file(&temp_dir, "app.js", "console.log('plain');")
+            .await
+            .unwrap();
+        create_test_file(&temp_dir, "app.js.gz", "gzipped")
+            .await
+            .unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        let options = StaticServeOptions {
+            precompressed: true,
+            content_types: [("js".to_string(), "text/javascript".to_string())].into(),
+            ..StaticServeOptions::default()
+        };
+        let request = |headers: &[(header::HeaderName, &str)]| {
+            let mut builder = Request::builder().uri("/app.js");
+            for (name, value) in headers {
+                builder = builder.header(name, *value);
+            }
+            builder.body(AxumBody::empty()).unwrap()
+        };
+
+        let plain = fs
+            .serve_file(root, "/app.js", request(&[]), &options)
+            .await
+            .unwrap();
+        let plain_etag = plain.headers()[header::ETAG].clone();
+        assert!(plain_etag.to_str().unwrap().starts_with('"'));
+        assert_eq!(plain.headers()[header::VARY], "accept-encoding");
+        assert_eq!(plain.headers()[header::CONTENT_TYPE], "text/javascript");
+
+        let gzipped = fs
+            .serve_file(
+                root,
+                "/app.js",
+                request(&[(header::ACCEPT_ENCODING, "gzip")]),
+                &options,
+            )
+            .await
+            .unwrap();
+        assert_eq!(gzipped.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(gzipped.headers()[header::CONTENT_TYPE], "text/javascript");
+        assert_ne!(gzipped.headers()[header::ETAG], plain_etag);
+        assert_eq!(body_of(gzipped).await, "gzipped");
+
+        let etag = plain_etag.to_str().unwrap();
+        let not_modified = fs
+            .serve_file(
+                root,
+                "/app.js",
+                request(&[(header::IF_NONE_MATCH, &format!("\"other\", W/{etag}"))]),
+                &options,
+            )
+            .await
+            .unwrap();
+        assert_eq!(not_modified.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(not_modified.headers()[header::ETAG], plain_etag);
+
+        let partial = fs
+            .serve_file(
+                root,
+                "/app.js",
+                request(&[(header::RANGE, "bytes=0-6"), (header::IF_RANGE, etag)]),
+                &options,
+            )
+            .await
+            .unwrap();
+        assert_eq!(partial.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(body_of(partial).await, "console");
+
+        let stale = fs
+            .serve_file(
+                root,
+                "/app.js",
+                request(&[
+                    (header::RANGE, "bytes=0-6"),
+                    (header::IF_RANGE, "\"stale\""),
+                ]),
+                &options,
+            )
+            .await
+            .unwrap();
+        assert_eq!(stale.status(), StatusCode::OK);
+        assert_eq!(body_of(stale).await, "console.log('plain');");
+    }
 }
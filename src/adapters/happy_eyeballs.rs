@@ -0,0 +1,179 @@
+//! Dual-stack connection racing for backend connections (RFC 8305).
+//!
+//! A backend name resolves to all of its addresses, which are ordered by the
+//! configured [`IpPreference`] with the two families interleaved. Connection
+//! attempts start one Connection Attempt Delay apart, or as soon as the
+//! previous attempt fails. The first connection established wins and the
+//! attempts still running are dropped. A broken address family then costs
+//! one delay instead of a full connect timeout.
+use std::{
+    future::Future,
+    io,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+use futures_util::{StreamExt, stream::FuturesUnordered};
+
+use crate::config::models::IpPreference;
+
+/// Metric label of an address's family
+pub fn family(addr: &SocketAddr) -> &'static str {
+    match addr.ip() {
+        IpAddr::V4(_) => "ipv4",
+        IpAddr::V6(_) => "ipv6",
+    }
+}
+
+/// Order resolved addresses for connecting: the preferred family first, then
+/// alternating between families, each keeping the resolver's order.
+pub fn order_addresses(addrs: Vec<SocketAddr>, preference: IpPreference) -> Vec<SocketAddr> {
+    let prefer_v6 = match preference {
+        IpPreference::Auto => addrs.first().is_some_and(SocketAddr::is_ipv6),
+        IpPreference::Ipv6 | IpPreference::Ipv6Only => true,
+        IpPreference::Ipv4 | IpPreference::Ipv4Only => false,
+    };
+    let (v6, v4): (Vec<_>, Vec<_>) = addrs.into_iter().partition(SocketAddr::is_ipv6);
+    let (preferred, other) = match preference {
+        IpPreference::Ipv6Only => (v6, Vec::new()),
+        IpPreference::Ipv4Only => (v4, Vec::new()),
+        _ if prefer_v6 => (v6, v4),
+        _ => (v4, v6),
+    };
+    let mut ordered = Vec::with_capacity(preferred.len() + other.len());
+    let (mut preferred, mut other) = (preferred.into_iter(), other.into_iter());
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => return ordered,
+            (first, second) => ordered.extend(first.into_iter().chain(second)),
+        }
+    }
+}
+
+/// Connect to the first of `addrs` that accepts, starting a new attempt every
+/// `attempt_delay` or when one fails. `on_failure` sees each failed attempt;
+/// when all fail, the last error is returned.
+pub async fn race<T, E, Fut>(
+    addrs: Vec<SocketAddr>,
+    attempt_delay: Duration,
+    mut connect: impl FnMut(SocketAddr) -> Fut,
+    mut on_failure: impl FnMut(SocketAddr, &E),
+) -> Result<T, E>
+where
+    Fut: Future<Output = Result<T, E>>,
+    E: From<io::Error>,
+{
+    let mut remaining = addrs.into_iter();
+    let mut attempts = FuturesUnordered::new();
+    let mut last_error = None;
+    // Each pass starts the next attempt: at first, after a failure, or once
+    // the delay has run out
+    loop {
+        if let Some(addr) = remaining.next() {
+            let attempt = connect(addr);
+            attempts.push(async move { (addr, attempt.await) });
+        }
+        if attempts.is_empty() {
+            return Err(last_error.unwrap_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "no addresses to connect to").into()
+            }));
+        }
+        tokio::select! {
+            Some((addr, result)) = attempts.next() => match result {
+                Ok(connection) => return Ok(connection),
+                Err(e) => {
+                    on_failure(addr, &e);
+                    last_error = Some(e);
+                }
+            },
+            () = tokio::time::sleep(attempt_delay), if remaining.len() > 0 => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    fn addrs(list: &[&str]) -> Vec<SocketAddr> {
+        list.iter().map(|addr| addr.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_order_addresses() {
+        let resolved = addrs(&["10.0.0.1:80", "10.0.0.2:80", "[2001:db8::1]:80"]);
+        let order = |preference| order_addresses(resolved.clone(), preference);
+
+        assert_eq!(
+            order(IpPreference::Auto),
+            addrs(&["10.0.0.1:80", "[2001:db8::1]:80", "10.0.0.2:80"])
+        );
+        assert_eq!(
+            order(IpPreference::Ipv6),
+            addrs(&["[2001:db8::1]:80", "10.0.0.1:80", "10.0.0.2:80"])
+        );
+        assert_eq!(
+            order(IpPreference::Ipv4Only),
+            addrs(&["10.0.0.1:80", "10.0.0.2:80"])
+        );
+        assert_eq!(order(IpPreference::Ipv6Only), addrs(&["[2001:db8::1]:80"]));
+    }
+
+    #[tokio::test]
+    async fn test_race_falls_back_after_delay_or_failure() {
+        let delay = Duration::from_millis(50);
+        let v6 = "[2001:db8::1]:80".parse().unwrap();
+
+        // A hanging attempt is joined by the next address after the delay
+        let started = Instant::now();
+        let mut failed = Vec::new();
+        let connected = race(
+            addrs(&["[2001:db8::1]:80", "10.0.0.1:80"]),
+            delay,
+            |addr| async move {
+                if addr == v6 {
+                    std::future::pending::<()>().await;
+                }
+                Ok::<_, io::Error>(addr)
+            },
+            |addr, _| failed.push(addr),
+        )
+        .await
+        .unwrap();
+        assert_eq!(connected, "10.0.0.1:80".parse().unwrap());
+        assert!(started.elapsed() >= delay);
+        assert!(failed.is_empty());
+
+        // A failed attempt starts the next one at once
+        let started = Instant::now();
+        let mut failed = Vec::new();
+        let connected = race(
+            addrs(&["[2001:db8::1]:80", "10.0.0.1:80"]),
+            Duration::from_secs(5),
+            |addr| async move {
+                if addr == v6 {
+                    return Err(io::Error::from(io::ErrorKind::ConnectionRefused));
+                }
+                Ok(addr)
+            },
+            |addr, _| failed.push(family(&addr)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(connected, "10.0.0.1:80".parse().unwrap());
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(failed, ["ipv6"]);
+
+        let err = race(
+            Vec::new(),
+            delay,
+            |addr| async move { Ok::<_, io::Error>(addr) },
+            |_, _| {},
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -8,9 +8,10 @@
 //! providing hysteresis so a single transient error does not flip health.
 //!
 //! # Algorithm
-//! * Each iteration waits `interval_secs`.
-//! * For every backend the dedicated endpoint is probed (backend specific path
-//!   override or the global default).
+//! * A cycle starts every `interval_secs`.
+//! * Every backend's dedicated endpoint is probed (backend specific path
+//!   override or the global default), up to `max_concurrency` at a time. Each
+//!   probe is bounded by `timeout_secs` and its latency is recorded.
 //! * On success: increment `consecutive_successes`, reset failures; once the
 //!   success threshold is met an UNHEALTHY backend becomes HEALTHY.
 //! * On failure: increment `consecutive_failures`, reset successes; once the
@@ -20,17 +21,26 @@
 //!
 //! The loop currently runs indefinitely; graceful shutdown is coordinated by
 //! higher‑level shutdown signaling (not yet integrated directly here).
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use base64::{Engine as _, engine::general_purpose::STANDARD};
 use eyre::{Result, WrapErr};
-use tokio::time::sleep;
+use futures_util::StreamExt;
+use tokio::time::MissedTickBehavior;
 
 use crate::{
-    config::{HealthCheckConfig, HealthStatus},
+    adapters::notifications::{Notification, notify},
+    config::{HealthCheckConfig, HealthStatus, NotificationEvent, UpstreamTlsConfig},
     core::{GatewayService, backend::BackendHealth},
-    ports::http_client::HttpClient,
+    ports::http_client::{HealthCheckOptions, HttpClient},
 };
 
+/// Backends probed at once when `health_check.max_concurrency` is unset.
+const DEFAULT_MAX_CONCURRENCY: usize = 16;
+
 /// Periodically probes backend health endpoints and updates aggregated status.
 pub struct HealthChecker {
     gateway_service: Arc<GatewayService>,
@@ -61,7 +71,6 @@ impl HealthChecker {
         }
 
         let interval = Duration::from_secs(health_config.interval_secs);
-        let timeout_secs = health_config.timeout_secs;
 
         tracing::info!(
             "Starting health checker with interval: {}s, timeout: {}s, default path: {}",
@@ -70,69 +79,107 @@ impl HealthChecker {
             health_config.path
         );
 
+        // Cycles start on a fixed cadence, however long the previous one took
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick completes immediately; skip it so the server can start up
+        ticker.tick().await;
+
         loop {
-            // Sleep at the beginning to allow the server to start up
-            sleep(interval).await;
+            ticker.tick().await;
+            self.check_all_backends(health_config).await;
+        }
+    }
 
-            tracing::info!("Running health checks on all backends...");
+    /// Probe every known backend once, at most `max_concurrency` at a time.
+    ///
+    /// Each probe is bounded by `timeout_secs`, so one slow backend only delays
+    /// its own result.
+    async fn check_all_backends(&self, health_config: &HealthCheckConfig) {
+        tracing::info!("Running health checks on all backends...");
+        let started = Instant::now();
 
-            // Check each backend using scc::HashMap scan method
-            let backend_health = self.gateway_service.backend_health();
-            let mut backends_to_check = Vec::new();
+        let backend_health = self.gateway_service.backend_health();
+        let mut backends_to_check = Vec::new();
 
-            let backends_ref = &mut backends_to_check;
-            backend_health
-                .retain_async(|target, _| {
-                    backends_ref.push(target.clone());
-                    true
-                })
-                .await;
+        let backends_ref = &mut backends_to_check;
+        backend_health
+            .retain_async(|target, _| {
+                backends_ref.push(target.clone());
+                true
+            })
+            .await;
 
-            for target in backends_to_check {
-                // Get backend-specific health check path or use default
-                let backend_path = self.gateway_service.get_backend_health_path(&target);
-
-                // Construct health check URL
-                let health_check_url = format!("{target}{backend_path}");
-
-                tracing::debug!("Health checking: {}", health_check_url);
-
-                // Perform the health check with timeout
-                match self
-                    .http_client
-                    .health_check(&health_check_url, timeout_secs)
-                    .await
-                {
-                    Ok(is_healthy) => {
-                        // Update counters and status using thresholds
-                        if let Some(h) = backend_health.get_async(&target).await {
-                            if is_healthy {
-                                self.handle_health_check_success(&target, h.get(), health_config);
-                            } else {
-                                self.handle_health_check_failure(
-                                    &target,
-                                    h.get(),
-                                    health_config,
-                                    "backend reported unhealthy",
-                                );
-                            }
-                        }
-                    }
-                    Err(err) => {
-                        tracing::warn!("Health check failed for backend {}: {}", target, err);
-                        if let Some(h) = backend_health.get_async(&target).await {
-                            self.handle_health_check_failure(
-                                &target,
-                                h.get(),
-                                health_config,
-                                &format!("request error: {err}"),
-                            );
-                        }
-                    }
-                }
-            }
+        let concurrency = health_config
+            .max_concurrency
+            .unwrap_or(DEFAULT_MAX_CONCURRENCY)
+            .max(1);
+        futures_util::stream::iter(backends_to_check)
+            .for_each_concurrent(concurrency, |target| async move {
+                self.probe_backend(&target, health_config).await;
+            })
+            .await;
+
+        tracing::debug!(
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Health check cycle completed"
+        );
+    }
 
-            tracing::debug!("Health check cycle completed");
+    /// Probe one backend and apply the result to its counters.
+    async fn probe_backend(&self, target: &str, health_config: &HealthCheckConfig) {
+        // Get backend-specific health check path or use default
+        let backend_path = self.gateway_service.get_backend_health_path(target);
+
+        // Construct health check URL
+        let health_check_url = format!("{target}{backend_path}");
+
+        tracing::debug!("Health checking: {}", health_check_url);
+
+        // Perform the health check, bounding it even if the client does not
+        let started = Instant::now();
+        let options = self.probe_options(target);
+        let result = tokio::time::timeout(
+            Duration::from_secs(health_config.timeout_secs),
+            self.http_client
+                .health_check(&health_check_url, &options, health_config.timeout_secs),
+        )
+        .await;
+        let outcome = match &result {
+            Ok(Ok(true)) => "healthy",
+            Ok(Ok(false)) => "unhealthy",
+            Ok(Err(_)) => "error",
+            Err(_) => "timeout",
+        };
+        crate::metrics::record_health_check_duration(target, outcome, started.elapsed());
+
+        let backend_health = self.gateway_service.backend_health();
+        let Some(h) = backend_health.get_async(target).await else {
+            return;
+        };
+        match result {
+            Ok(Ok(true)) => self.handle_health_check_success(target, h.get(), health_config),
+            Ok(Ok(false)) => self.handle_health_check_failure(
+                target,
+                h.get(),
+                health_config,
+                "backend reported unhealthy",
+            ),
+            Ok(Err(err)) => {
+                tracing::warn!("Health check failed for backend {}: {}", target, err);
+                self.handle_health_check_failure(
+                    target,
+                    h.get(),
+                    health_config,
+                    &format!("request error: {err}"),
+                );
+            }
+            Err(_) => self.handle_health_check_failure(
+                target,
+                h.get(),
+                health_config,
+                &format!("timed out after {}s", health_config.timeout_secs),
+            ),
         }
     }
 
@@ -173,6 +220,11 @@ impl HealthChecker {
                 successes
             );
             backend_health.mark_healthy();
+            notify(
+                Notification::new(NotificationEvent::BackendHealthy)
+                    .subject(target)
+                    .detail(format!("{successes} consecutive successful health checks")),
+            );
         }
     }
 
@@ -217,6 +269,47 @@ impl HealthChecker {
                 reason
             );
             backend_health.mark_unhealthy();
+            notify(
+                Notification::new(NotificationEvent::BackendUnhealthy)
+                    .subject(target)
+                    .detail(format!(
+                        "{failures} consecutive failed health checks: {reason}"
+                    )),
+            );
+        }
+    }
+
+    /// The Host, TLS settings and credentials `target` is probed with. TLS
+    /// settings come from `[backend_health_checks]`, or else from a route
+    /// forwarding to the backend. A Host override is also the SNI of HTTPS
+    /// probes without a `server_name`.
+    fn probe_options(&self, target: &str) -> HealthCheckOptions {
+        let settings = self.gateway_service.backend_health_check(target);
+        let host = settings.and_then(|s| s.host.clone());
+        let mut tls = settings
+            .and_then(|s| s.tls.clone())
+            .or_else(|| self.gateway_service.backend_upstream_tls(target).cloned());
+        if let Some(host) = &host
+            && target.starts_with("https://")
+        {
+            let tls = tls.get_or_insert_with(UpstreamTlsConfig::default);
+            if tls.server_name.is_none() {
+                tls.server_name = host
+                    .parse::<http::uri::Authority>()
+                    .map(|authority| authority.host().to_string())
+                    .ok();
+            }
+        }
+        let authorization = settings
+            .and_then(|s| s.basic_auth.as_ref())
+            .map(|credentials| {
+                let pair = format!("{}:{}", credentials.username, credentials.password);
+                format!("Basic {}", STANDARD.encode(pair))
+            });
+        HealthCheckOptions {
+            host,
+            tls,
+            authorization,
         }
     }
 
@@ -228,7 +321,11 @@ impl HealthChecker {
         let health_check_url = format!("{backend_url}{backend_path}");
 
         self.http_client
-            .health_check(&health_check_url, health_config.timeout_secs)
+            .health_check(
+                &health_check_url,
+                &self.probe_options(backend_url),
+                health_config.timeout_secs,
+            )
             .await
             .wrap_err_with(|| format!("Failed to check health for backend: {backend_url}"))
     }
@@ -293,7 +390,10 @@ mod tests {
 
     use super::*;
     use crate::{
-        config::models::{HealthCheckConfig, ServerConfig},
+        config::models::{
+            BackendHealthCheckConfig, BasicAuthCredentials, HealthCheckConfig, RouteConfig,
+            ServerConfig,
+        },
         ports::http_client::HttpClientError,
     };
 
@@ -323,12 +423,46 @@ mod tests {
         async fn health_check(
             &self,
             _url: &str,
+            _options: &HealthCheckOptions,
             _timeout_secs: u64,
         ) -> Result<bool, HttpClientError> {
             Ok(self.should_succeed)
         }
     }
 
+    // Mock HTTP client whose probes take time, tracking how many overlap
+    #[derive(Default)]
+    struct SlowHttpClient {
+        in_flight: std::sync::atomic::AtomicUsize,
+        max_in_flight: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpClient for SlowHttpClient {
+        async fn send_request(
+            &self,
+            _req: hyper::Request<AxumBody>,
+        ) -> Result<hyper::Response<AxumBody>, HttpClientError> {
+            Err(HttpClientError::ConnectionError(
+                "not used in tests".to_string(),
+            ))
+        }
+
+        async fn health_check(
+            &self,
+            url: &str,
+            _options: &HealthCheckOptions,
+            _timeout_secs: u64,
+        ) -> Result<bool, HttpClientError> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            let delay = if url.contains("stuck") { 30_000 } else { 100 };
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(true)
+        }
+    }
+
     fn create_test_health_config() -> HealthCheckConfig {
         HealthCheckConfig {
             enabled: true,
@@ -337,6 +471,7 @@ mod tests {
             unhealthy_threshold: 3,
             healthy_threshold: 2,
             timeout_secs: 5,
+            max_concurrency: None,
         }
     }
 
@@ -419,6 +554,53 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_probes_run_concurrently_with_isolated_timeouts() {
+        let gateway_service = Arc::new(GatewayService::new(Arc::new(ServerConfig::default())));
+        let backends = [
+            "http://stuck.example.com",
+            "http://a.example.com",
+            "http://b.example.com",
+            "http://c.example.com",
+        ];
+        for backend in backends {
+            let _ = gateway_service
+                .backend_health()
+                .insert_async(
+                    backend.to_string(),
+                    BackendHealth::new(backend.parse().unwrap()),
+                )
+                .await;
+        }
+        let http_client = Arc::new(SlowHttpClient::default());
+        let health_checker = HealthChecker::new(gateway_service.clone(), http_client.clone());
+        let health_config = HealthCheckConfig {
+            timeout_secs: 1,
+            max_concurrency: Some(2),
+            ..create_test_health_config()
+        };
+
+        let started = Instant::now();
+        health_checker.check_all_backends(&health_config).await;
+
+        // The stuck probe times out on its own while the others share the
+        // second slot; sequential probing would take over 30s
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(http_client.max_in_flight.load(Ordering::SeqCst), 2);
+        let counters = |backend: &str| {
+            gateway_service.backend_health().read_sync(backend, |_, h| {
+                (
+                    h.consecutive_successes.load(Ordering::Relaxed),
+                    h.consecutive_failures.load(Ordering::Relaxed),
+                )
+            })
+        };
+        assert_eq!(counters(backends[0]), Some((0, 1)));
+        for backend in &backends[1..] {
+            assert_eq!(counters(backend), Some((1, 0)));
+        }
+    }
+
     #[tokio::test]
     async fn test_check_backend_health() {
         let gateway_service = Arc::new(GatewayService::new(Arc::new(ServerConfig::default())));
@@ -431,4 +613,49 @@ mod tests {
         assert!(result.is_ok());
         assert!(result.unwrap());
     }
+
+    #[test]
+    fn test_probe_options_follow_backend_settings() {
+        let mut route = RouteConfig::proxy("https://10.0.0.5:8443");
+        if let RouteConfig::Proxy { upstream_tls, .. } = &mut route {
+            *upstream_tls = Some(UpstreamTlsConfig {
+                ca_bundle: Some("private-ca.pem".to_string()),
+                ..UpstreamTlsConfig::default()
+            });
+        }
+        let config = ServerConfig::builder()
+            .listen_addr("127.0.0.1:8080")
+            .route("/api", route)
+            .backend_health_check(
+                "https://10.0.0.5:8443",
+                BackendHealthCheckConfig {
+                    host: Some("api.internal:8443".to_string()),
+                    basic_auth: Some(BasicAuthCredentials {
+                        username: "probe".to_string(),
+                        password: "secret".to_string(),
+                    }),
+                    ..BackendHealthCheckConfig::default()
+                },
+            )
+            .build()
+            .unwrap();
+        let gateway_service = Arc::new(GatewayService::new(Arc::new(config)));
+        let http_client = Arc::new(MockHttpClient::new(true)) as Arc<dyn HttpClient>;
+        let health_checker = HealthChecker::new(gateway_service, http_client);
+
+        let options = health_checker.probe_options("https://10.0.0.5:8443");
+        assert_eq!(options.host.as_deref(), Some("api.internal:8443"));
+        // The route's CA is kept and the Host override becomes the SNI
+        let tls = options.tls.expect("route TLS settings");
+        assert_eq!(tls.ca_bundle.as_deref(), Some("private-ca.pem"));
+        assert_eq!(tls.server_name.as_deref(), Some("api.internal"));
+        assert_eq!(
+            options.authorization.as_deref(),
+            Some("Basic cHJvYmU6c2VjcmV0")
+        );
+
+        let options = health_checker.probe_options("http://other:80");
+        assert!(options.host.is_none() && options.tls.is_none());
+        assert!(options.authorization.is_none());
+    }
 }
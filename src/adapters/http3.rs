@@ -1,43 +1,112 @@
 //! Minimal HTTP/3 (QUIC) listener that bridges requests into the existing
-//! `HttpHandler` (HTTP/1.1 & HTTP/2 path). This is an early implementation
-//! focused on getting a functional baseline that compiles; streaming bodies,
-//! flow control awareness, metrics, and graceful shutdown will follow.
+//! `HttpHandler` (HTTP/1.1 & HTTP/2 path).
+//!
+//! Request and response bodies are streamed chunk by chunk. Reading the
+//! request body stops while the handler has not taken the previous chunks,
+//! and response chunks are only polled once QUIC flow control lets the
+//! previous one out, so neither direction is buffered in full. Time spent
+//! waiting either way is reported as `axon_http3_stream_blocked_seconds_total`.
+//!
+//! Like the TCP listener, each QUIC connection is registered with the
+//! [`ConnectionTracker`] and its requests are counted until their response
+//! body has been sent. Requests reach the handler with the client address
+//! and version `HTTP/3`, so routes can refuse them (`disable_http3`).
 //!
 //! Current limitations (to be addressed):
-//! - Request & response bodies fully buffered (no streaming)
 //! - No per-request timeout / cancellation wiring
-//! - Lacks protocol–specific metrics (counts / latency histograms)
 //! - No integration with global graceful shutdown yet
 //! - Limited error classification / backoff
 
-use std::{net::SocketAddr, sync::Arc, time::Instant};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{BoxError, body::Body as AxumBody};
+use bytes::{Buf, Bytes};
 use eyre::{Result, WrapErr};
-use futures_util::StreamExt; // for map on ReceiverStream
-use http_body_util::BodyExt; // for frame() on Body
-use hyper::{Request, Response};
-use quinn::{Endpoint, ServerConfig as QuinnServerConfig};
-use tokio::task::JoinHandle;
-use tracing::{error, info, warn};
+use h3::{error::Code, server::RequestStream};
+use http_body_util::{BodyExt, StreamBody}; // for frame() on Body
+use hyper::{Request, Response, body::Frame};
+use quinn::{
+    Endpoint, IdleTimeout, MtuDiscoveryConfig, ServerConfig as QuinnServerConfig, TransportConfig,
+    VarInt,
+    congestion::{BbrConfig, ControllerFactory, CubicConfig, NewRenoConfig},
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error, info, warn};
 
 use crate::{
     adapters::http_handler::HttpHandler,
-    metrics::{increment_request_total, record_request_duration},
-    utils::graceful_shutdown::ShutdownToken,
+    config::{Http3Config, Http3CongestionControl},
+    metrics::{
+        record_http3_connection, record_http3_connection_closed, record_http3_stream_blocked,
+    },
+    utils::{ConnectionInfo, ConnectionTracker, graceful_shutdown::ShutdownToken},
 };
 
+/// Request body chunks read ahead of the handler
+const REQUEST_BODY_BUFFER_CHUNKS: usize = 4;
+
+/// Map `[protocols.http3_config]` onto quinn's transport settings.
+pub fn transport_config(config: &Http3Config) -> Result<TransportConfig> {
+    let var_int = |value: u64, key: &str| {
+        VarInt::from_u64(value).wrap_err_with(|| format!("http3_config.{key} is too large"))
+    };
+    let mut transport = TransportConfig::default();
+    transport
+        .receive_window(var_int(config.max_data, "max_data")?)
+        .stream_receive_window(var_int(config.max_stream_data, "max_stream_data")?)
+        .max_concurrent_bidi_streams(var_int(config.max_streams_bidi, "max_streams_bidi")?);
+
+    // 0 disables the idle timeout
+    let idle_timeout = match config.max_idle_timeout {
+        0 => None,
+        millis => Some(
+            IdleTimeout::try_from(Duration::from_millis(millis))
+                .wrap_err("http3_config.max_idle_timeout is too large")?,
+        ),
+    };
+    transport.max_idle_timeout(idle_timeout);
+
+    let controller: Arc<dyn ControllerFactory + Send + Sync> = match config.congestion_control {
+        Http3CongestionControl::Cubic => Arc::new(CubicConfig::default()),
+        Http3CongestionControl::Reno => Arc::new(NewRenoConfig::default()),
+        Http3CongestionControl::Bbr => Arc::new(BbrConfig::default()),
+    };
+    transport.congestion_controller_factory(controller);
+
+    // Path MTU discovery probes up to this size instead of quinn's default
+    if let Some(max_packet_size) = config.max_packet_size {
+        let mut mtu_discovery = MtuDiscoveryConfig::default();
+        mtu_discovery.upper_bound(max_packet_size);
+        transport.mtu_discovery_config(Some(mtu_discovery));
+    }
+    Ok(transport)
+}
+
 /// Spawn an HTTP/3 QUIC endpoint using a fully prepared rustls server config
 /// (already containing ALPN h3). Returns a background task handle.
+///
+/// Transport limits, the congestion controller and 0-RTT come from
+/// `http3_config`.
 pub async fn spawn_http3(
     listen_addr: SocketAddr,
     handler: Arc<HttpHandler>,
-    rustls_config: rustls::ServerConfig,
+    mut rustls_config: rustls::ServerConfig,
+    http3_config: &Http3Config,
+    tracker: Arc<ConnectionTracker>,
     mut shutdown: Option<ShutdownToken>,
 ) -> Result<JoinHandle<()>> {
-    let mut transport = quinn::TransportConfig::default();
-    // Conservative initial concurrency – later make configurable (Http3Config)
-    transport.max_concurrent_bidi_streams(100u32.into());
+    let transport = transport_config(http3_config)?;
+    // QUIC only allows 0 (no early data) or u32::MAX (accept 0-RTT)
+    rustls_config.max_early_data_size = if http3_config.enable_0rtt {
+        u32::MAX
+    } else {
+        0
+    };
 
     // Convert rustls::ServerConfig into quinn crypto config
     let crypto = Arc::new(
@@ -75,15 +144,26 @@ pub async fn spawn_http3(
                         }
                     };
                     let handler = handler.clone();
+                    let tracker = tracker.clone();
                     tokio::spawn(async move {
                         match connecting.await {
                             Ok(connection) => {
+                                record_http3_connection("accepted");
                                 let remote = connection.remote_address();
-                                if let Err(e) = handle_connection(connection, handler.clone()).await {
+                                let connection_info = tracker.register_connection(remote).await;
+                                if let Err(e) =
+                                    handle_connection(connection, handler, connection_info.clone())
+                                        .await
+                                {
                                     error!(error=%e, %remote, "http3 connection error");
                                 }
+                                tracker.unregister_connection(connection_info.id).await;
+                                record_http3_connection_closed();
+                            }
+                            Err(e) => {
+                                record_http3_connection("handshake_failed");
+                                warn!(error=%e, "QUIC handshake failed");
                             }
-                            Err(e) => warn!(error=%e, "QUIC handshake failed"),
                         }
                     });
                 }
@@ -94,15 +174,33 @@ pub async fn spawn_http3(
     Ok(task)
 }
 
-async fn handle_connection(quinn_conn: quinn::Connection, handler: Arc<HttpHandler>) -> Result<()> {
+async fn handle_connection(
+    quinn_conn: quinn::Connection,
+    handler: Arc<HttpHandler>,
+    connection_info: Arc<ConnectionInfo>,
+) -> Result<()> {
+    let remote_addr = connection_info.remote_addr;
+    let closed = quinn_conn.clone();
     // Establish h3 server connection. Current h3 API returns a Connection directly.
     let mut h3_conn =
         h3::server::Connection::<_, bytes::Bytes>::new(h3_quinn::Connection::new(quinn_conn))
             .await
             .wrap_err("h3 connection handshake failed")?;
 
+    let mut closing = false;
     loop {
-        let accepted = h3_conn.accept().await; // Result<Option<(Request<()>, RequestStream)>, Error>
+        let accepted = tokio::select! {
+            accepted = h3_conn.accept() => accepted, // Result<Option<(Request<()>, RequestStream)>, Error>
+            _ = connection_info.close_requested(), if !closing => {
+                // GOAWAY: accept no new requests, let in-flight ones finish
+                closing = true;
+                if let Err(e) = h3_conn.shutdown(0).await {
+                    warn!(error=%e, "h3 GOAWAY failed");
+                    break;
+                }
+                continue;
+            }
+        };
         let resolver = match accepted {
             Ok(opt) => match opt {
                 Some(r) => r,
@@ -114,145 +212,162 @@ async fn handle_connection(quinn_conn: quinn::Connection, handler: Arc<HttpHandl
             }
         };
         let handler_clone = handler.clone();
+        let closed = closed.clone();
+        // Count the request against the connection until its response is sent
+        let request_guard = connection_info.track_request();
         tokio::spawn(async move {
+            let _request_guard = request_guard;
             match resolver.resolve_request().await {
                 Ok((req_head, req_stream)) => {
-                    let start = Instant::now();
-                    use bytes::Bytes;
-                    use tokio::sync::mpsc;
-                    use tokio_stream::wrappers::ReceiverStream;
+                    serve_request(req_head, req_stream, &handler_clone, remote_addr, &closed).await;
+                }
+                Err(e) => error!(error=%e, "HTTP/3 request resolve error"),
+            }
+        });
+    }
+    Ok(())
+}
 
-                    // Channel for streaming request body chunks
-                    let (tx, rx) = mpsc::channel::<Result<Bytes, eyre::Report>>(32);
-                    let req_stream_shared = Arc::new(tokio::sync::Mutex::new(req_stream));
-                    let req_stream_for_body = req_stream_shared.clone();
-                    tokio::spawn(async move {
-                        loop {
-                            let mut guard = req_stream_for_body.lock().await;
-                            match guard.recv_data().await {
-                                Ok(Some(mut buf_impl)) => {
-                                    use bytes::{Buf, BytesMut};
-                                    let mut bytes_mut =
-                                        BytesMut::with_capacity(buf_impl.remaining());
-                                    while buf_impl.has_remaining() {
-                                        let chunk_part = buf_impl.chunk();
-                                        bytes_mut.extend_from_slice(chunk_part);
-                                        let len = chunk_part.len();
-                                        buf_impl.advance(len);
-                                    }
-                                    let bytes = bytes_mut.freeze();
-                                    if tx.send(Ok(bytes)).await.is_err() {
-                                        break;
-                                    }
-                                }
-                                Ok(None) => {
-                                    break;
-                                }
-                                Err(e) => {
-                                    let report: eyre::Report =
-                                        eyre::eyre!(e).wrap_err("recv h3 body stream");
-                                    let _ = tx.send(Err(report)).await; // best-effort
-                                    break;
-                                }
-                            }
-                        }
-                        // drop tx -> end of stream
-                    });
+/// Run one request stream through the handler, streaming the request body in
+/// and the response body out. The handler is dropped, cancelling its backend
+/// request, if the connection closes before it answers.
+async fn serve_request(
+    req_head: Request<()>,
+    req_stream: RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>,
+    handler: &HttpHandler,
+    remote_addr: SocketAddr,
+    connection: &quinn::Connection,
+) {
+    let (mut send, recv) = req_stream.split();
 
-                    // Build synthetic hyper (axum) request with streaming body
-                    let (parts, _) = req_head.into_parts();
-                    let method_str = parts.method.as_str().to_string();
-                    let path_str = parts.uri.path().to_string();
-                    let mut builder = Request::builder()
-                        .method(parts.method.clone())
-                        .uri(parts.uri.clone());
-                    for (k, v) in parts.headers.iter() {
-                        builder = builder.header(k, v);
-                    }
+    let (tx, rx) = mpsc::channel(REQUEST_BODY_BUFFER_CHUNKS);
+    tokio::spawn(forward_request_body(recv, tx));
 
-                    let stream = ReceiverStream::new(rx).map(|res| {
-                        match res {
-                            Ok(bytes) => Ok::<Bytes, BoxError>(bytes),
-                            Err(e) => {
-                                error!(error=?e, "error streaming HTTP/3 request body");
-                                // Provide empty chunk then end; convert error into empty data for now
-                                Ok(Bytes::new())
-                            }
-                        }
-                    });
-                    let body = AxumBody::from_stream(stream);
+    let (mut parts, ()) = req_head.into_parts();
+    parts.version = http::Version::HTTP_3;
+    let body = AxumBody::new(StreamBody::new(ReceiverStream::new(rx)));
+    let hyper_req = Request::from_parts(parts, body);
 
-                    let hyper_req: Request<AxumBody> = match builder.body(body) {
-                        Ok(r) => r,
-                        Err(e) => {
-                            error!(error=%e, "build synthetic hyper request from h3");
-                            return;
-                        }
-                    };
+    let handled = tokio::select! {
+        handled = handler.handle_request(hyper_req, Some(remote_addr)) => handled,
+        reason = connection.closed() => {
+            debug!(reason = %reason, "h3 connection closed before the response");
+            return;
+        }
+    };
+    let hyper_resp = match handled {
+        Ok(r) => r,
+        Err(e) => {
+            error!(error=%e, "handler error for h3 request");
+            let mut response = Response::new(AxumBody::from("Internal Server Error"));
+            *response.status_mut() = http::StatusCode::INTERNAL_SERVER_ERROR;
+            response
+        }
+    };
 
-                    let hyper_resp = match handler_clone.handle_request(hyper_req, None).await {
-                        Ok(r) => r,
-                        Err(e) => {
-                            error!(error=%e, "handler error for h3 request");
-                            match Response::builder()
-                                .status(500)
-                                .body(AxumBody::from("Internal Server Error"))
-                            {
-                                Ok(r) => r,
-                                Err(build_err) => {
-                                    error!(error=%build_err, "failed to build 500 response for h3 request");
-                                    return;
-                                }
-                            }
-                        }
-                    };
+    let (resp_parts, mut resp_body) = hyper_resp.into_parts();
+    if let Err(e) = send
+        .send_response(Response::from_parts(resp_parts, ()))
+        .await
+    {
+        error!(error=%e, "send h3 response headers");
+        return;
+    }
 
-                    let status_for_metrics = hyper_resp.status().as_u16();
-                    let (resp_parts, mut resp_body) = hyper_resp.into_parts();
-                    let mut h3_resp_builder = http::Response::builder().status(resp_parts.status);
-                    for (k, v) in resp_parts.headers.iter() {
-                        h3_resp_builder = h3_resp_builder.header(k, v);
-                    }
-                    let h3_resp = match h3_resp_builder.body(()) {
-                        Ok(r) => r,
-                        Err(e) => {
-                            error!(error=%e, "build h3 response headers");
-                            return;
-                        }
-                    };
-                    let mut req_stream = req_stream_shared.lock().await;
-                    if let Err(e) = req_stream.send_response(h3_resp).await {
-                        error!(error=%e, "send h3 response headers");
-                        return;
-                    }
-                    // Stream response body in chunks to avoid buffering entire body in memory.
-                    while let Some(frame) = resp_body.frame().await {
-                        match frame {
-                            Ok(f) => {
-                                if let Some(data) = f.data_ref()
-                                    && !data.is_empty()
-                                    && let Err(e) = req_stream.send_data(data.clone()).await
-                                {
-                                    error!(error=%e, "send h3 response data frame");
-                                    break;
-                                }
-                            }
-                            Err(e) => {
-                                error!(error=%e, "error reading response body frame for h3");
-                                break;
-                            }
-                        }
-                    }
-                    // Record metrics after response fully sent
-                    increment_request_total(&path_str, &method_str, status_for_metrics, "http3");
-                    record_request_duration(&path_str, &method_str, "http3", start.elapsed());
+    // `send_data` waits for the client to grant flow-control credit, so a slow
+    // client stops us polling the response body and, through it, the upstream.
+    while let Some(frame) = resp_body.frame().await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                error!(error=%e, "error reading response body frame for h3");
+                send.stop_stream(Code::H3_INTERNAL_ERROR);
+                return;
+            }
+        };
+        match frame.into_data() {
+            Ok(data) if data.is_empty() => {}
+            Ok(data) => {
+                let started = Instant::now();
+                let sent = send.send_data(data).await;
+                record_http3_stream_blocked("response", started.elapsed());
+                if let Err(e) = sent {
+                    error!(error=%e, "send h3 response data frame");
+                    return;
                 }
-                Err(e) => error!(error=%e, "HTTP/3 request resolve error"),
             }
-        });
+            Err(frame) => {
+                if let Ok(trailers) = frame.into_trailers()
+                    && let Err(e) = send.send_trailers(trailers).await
+                {
+                    error!(error=%e, "send h3 response trailers");
+                    return;
+                }
+            }
+        }
+    }
+    if let Err(e) = send.finish().await {
+        warn!(error=%e, "finish h3 response stream");
+    }
+}
+
+/// Pump request body chunks, then any trailers, from the QUIC stream into
+/// the handler's body.
+///
+/// The channel holds only a few chunks: once it is full we stop reading, so
+/// quinn stops extending the client's stream window until the upstream
+/// catches up.
+async fn forward_request_body(
+    mut recv: RequestStream<h3_quinn::RecvStream, Bytes>,
+    tx: mpsc::Sender<Result<Frame<Bytes>, BoxError>>,
+) {
+    let mut finished = false;
+    while !finished {
+        let chunk = match recv.recv_data().await {
+            Ok(Some(mut buf)) => Ok(Frame::data(buf.copy_to_bytes(buf.remaining()))),
+            Ok(None) => {
+                finished = true;
+                match recv.recv_trailers().await {
+                    Ok(Some(trailers)) => Ok(Frame::trailers(trailers)),
+                    Ok(None) => return,
+                    Err(e) => Err(BoxError::from(e)),
+                }
+            }
+            Err(e) => Err(BoxError::from(e)),
+        };
+        let failed = chunk.is_err();
+        let started = Instant::now();
+        if tx.send(chunk).await.is_err() {
+            // The handler dropped the body without reading all of it
+            recv.stop_sending(Code::H3_NO_ERROR);
+            return;
+        }
+        record_http3_stream_blocked("request", started.elapsed());
+        if failed {
+            return;
+        }
     }
-    Ok(())
 }
 
-// (Tests for HTTP/3 integration will be added once the functional path is
-// validated; initial attempt removed due to evolving upstream crate APIs.)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transport_config_from_http3_config() {
+        let config = Http3Config {
+            max_idle_timeout: 0,
+            congestion_control: Http3CongestionControl::Bbr,
+            max_packet_size: Some(1452),
+            ..Http3Config::default()
+        };
+        assert!(transport_config(&config).is_ok());
+
+        let config = Http3Config {
+            max_stream_data: u64::MAX,
+            ..Http3Config::default()
+        };
+        let err = transport_config(&config).unwrap_err();
+        assert!(err.to_string().contains("max_stream_data"));
+    }
+}
@@ -1,42 +1,255 @@
-use std::time::Duration;
+use std::{
+    future::Future,
+    io,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
 use axum::body::Body as AxumBody;
-use eyre::Result;
+use eyre::{Result, WrapErr, eyre};
 use http_body_util::BodyExt;
-use hyper::{Request, Response, Version, header, header::HeaderValue};
-use hyper_rustls::HttpsConnector;
+use hyper::{
+    HeaderMap, Request, Response, StatusCode, Uri, Version,
+    body::{Body as HttpBody, Frame, SizeHint},
+    header,
+    header::{HeaderName, HeaderValue},
+    http::uri::Scheme,
+    rt::{Read, ReadBufCursor, Write},
+};
+use hyper_rustls::{FixedServerNameResolver, HttpsConnector};
 use hyper_util::{
-    client::legacy::{Client, connect::HttpConnector},
-    rt::TokioExecutor,
+    client::legacy::{
+        Client,
+        connect::{Connected, Connection, HttpConnector},
+    },
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+};
+use rustls::{
+    DigitallySignedStruct, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, ServerName, UnixTime},
 };
 use rustls_native_certs::load_native_certs;
-use tokio::time::timeout;
+use tokio::{net::TcpStream, time::timeout};
+use tower::Service;
 
-use crate::ports::http_client::{HttpClient, HttpClientError, HttpClientResult};
+use crate::{
+    adapters::{fastcgi, happy_eyeballs, spiffe::SpiffeSource},
+    config::models::{
+        IpPreference, ResponseTimeoutConfig, UpstreamConfig, UpstreamSocketConfig,
+        UpstreamTlsConfig,
+    },
+    metrics,
+    ports::http_client::{
+        BackendTiming, HealthCheckOptions, HttpClient, HttpClientError, HttpClientResult,
+        UpstreamRequestOptions,
+    },
+};
 
 /// HTTP client adapter using Hyper with Rustls (HTTP/1.1 + HTTP/2).
 ///
 /// Responsibilities:
-/// * Adds a small set of sensible default headers
+/// * Adds configurable default headers to health checks and opted-in routes
 /// * Forces request version to HTTP/1.1 while allowing ALPN to negotiate h2
 /// * Performs HEAD based health checks with timeout
 /// * Converts between Hyper body and Axum body types
+/// * Applies upstream pool / keep-alive tuning from [`UpstreamConfig`] and reports
+///   pool utilization metrics
+/// * Honors per-route [`UpstreamRequestOptions`] passed as a request extension
+///   (backend TLS settings, header case preservation)
+/// * Presents SPIFFE SVIDs from the Workload API on routes that opt in
 ///
 /// This adapter is intentionally minimal; higher level retries / circuit breaking
 /// can be layered on a different abstraction if required.
 pub struct HttpClientAdapter {
-    client: Client<HttpsConnector<HttpConnector>, AxumBody>,
+    client: UpstreamClient,
+    upstream: UpstreamConfig,
+    /// Clients for routes with non-default request options, built lazily and cached
+    route_clients: scc::HashMap<UpstreamRequestOptions, UpstreamClient>,
+    /// Headers injected into health checks and opted-in routes when absent
+    default_headers: HeaderMap,
+    /// Configured headers removed in both directions along with hop-by-hop ones
+    strip_headers: Vec<HeaderName>,
+    /// Workload API identity source for routes using SPIFFE mTLS
+    spiffe: Option<Arc<SpiffeSource>>,
 }
 
+type UpstreamClient = Client<HttpsConnector<TrackedConnector>, AxumBody>;
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
 impl HttpClientAdapter {
-    /// Create a new HTTP client adapter.
+    /// Create a new HTTP client adapter with default upstream settings.
     pub fn new() -> Result<Self> {
+        Self::with_config(&UpstreamConfig::default())
+    }
+
+    /// Create a new HTTP client adapter using the given upstream pool settings.
+    pub fn with_config(config: &UpstreamConfig) -> Result<Self> {
         // Install default crypto provider for rustls if not already set
         let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
 
+        let spiffe = config
+            .spiffe
+            .as_ref()
+            .map(SpiffeSource::start)
+            .transpose()?;
+        let client = Self::build_client(config, &UpstreamRequestOptions::default(), None)?;
+        let default_headers = config
+            .default_headers
+            .iter()
+            .map(|(name, value)| {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .wrap_err_with(|| format!("Invalid default header name '{name}'"))?;
+                let value = HeaderValue::from_str(value)
+                    .wrap_err_with(|| format!("Invalid value for default header '{name}'"))?;
+                Ok((name, value))
+            })
+            .collect::<Result<HeaderMap>>()?;
+        let strip_headers = config
+            .strip_headers
+            .iter()
+            .map(|name| {
+                HeaderName::from_bytes(name.as_bytes())
+                    .wrap_err_with(|| format!("Invalid strip header name '{name}'"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        tracing::info!(
+            "Created new HTTP client (http2: {}, pool_max_idle_per_host: {:?}, pool_idle_timeout: {}s)",
+            config.http2_enabled,
+            config.pool_max_idle_per_host,
+            config.pool_idle_timeout_secs
+        );
+        Ok(Self {
+            client,
+            upstream: config.clone(),
+            route_clients: scc::HashMap::new(),
+            default_headers,
+            strip_headers,
+            spiffe,
+        })
+    }
+
+    /// Watch for the backend's informational responses. When `req` expects
+    /// `100 Continue`, its body waits for it, or for
+    /// `expect_continue_timeout_ms` since HTTP/2 backends and servers that
+    /// ignore `Expect` never send one. Other 1xx responses, such as `103
+    /// Early Hints`, cannot be passed on to the client and are only logged.
+    fn negotiate_continue(&self, req: &mut Request<AxumBody>) {
+        let expects_continue = req
+            .headers()
+            .get(header::EXPECT)
+            .is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(b"100-continue"));
+        let continued = (expects_continue && !req.body().is_end_stream())
+            .then(|| Arc::new(tokio::sync::Notify::new()));
+
+        let notify = continued.clone();
+        hyper::ext::on_informational(req, move |res| {
+            if res.status() == StatusCode::CONTINUE
+                && let Some(notify) = &notify
+            {
+                notify.notify_one();
+            } else {
+                tracing::debug!(status = %res.status(), "backend sent informational response");
+            }
+        });
+
+        if let Some(continued) = continued {
+            let wait = Duration::from_millis(self.upstream.expect_continue_timeout_ms);
+            let body = std::mem::take(req.body_mut());
+            *req.body_mut() = AxumBody::new(ContinueBody {
+                inner: body,
+                ready: Some(Box::pin(async move {
+                    let _ = timeout(wait, continued.notified()).await;
+                })),
+            });
+        }
+    }
+
+    /// Build a pooled client, applying route-specific request options.
+    fn build_client(
+        config: &UpstreamConfig,
+        options: &UpstreamRequestOptions,
+        spiffe: Option<&Arc<SpiffeSource>>,
+    ) -> Result<UpstreamClient> {
+        let tls = options.tls.as_ref();
+        let socket = options.socket.or(config.socket);
         let mut http_connector = HttpConnector::new();
         http_connector.enforce_http(false); // Allow HTTPS URLs
+        http_connector.set_connect_timeout(config.connect_timeout_secs.map(Duration::from_secs));
+        http_connector.set_keepalive(config.tcp_keepalive_secs.map(Duration::from_secs));
+        http_connector.set_nodelay(socket.nodelay.unwrap_or(false));
+        http_connector.set_send_buffer_size(socket.send_buffer_bytes);
+        http_connector.set_recv_buffer_size(socket.recv_buffer_bytes);
+        let tracked_connector = TrackedConnector {
+            inner: http_connector,
+            dscp: socket.dscp,
+            ip_preference: config.ip_preference,
+            attempt_delay: Duration::from_millis(config.happy_eyeballs_delay_ms),
+        };
+
+        let tls_config = Self::build_tls_config(tls, spiffe)?;
+
+        // Build HTTPS connector; h2 is only offered via ALPN when enabled
+        let mut https_builder = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_tls_config(tls_config)
+            .https_or_http();
+        if let Some(server_name) = tls.and_then(|t| t.server_name.as_deref()) {
+            let server_name = ServerName::try_from(server_name)
+                .wrap_err_with(|| format!("Invalid upstream server name '{server_name}'"))?
+                .to_owned();
+            https_builder =
+                https_builder.with_server_name_resolver(FixedServerNameResolver::new(server_name));
+        }
+        // Upgrades are an HTTP/1.1 mechanism
+        let https_connector = if config.http2_enabled && !options.upgrade {
+            https_builder
+                .enable_all_versions()
+                .wrap_connector(tracked_connector)
+        } else {
+            https_builder
+                .enable_http1() // Support HTTP/1.1
+                .wrap_connector(tracked_connector)
+        };
+
+        // Create client with TokioExecutor for async runtime
+        let mut builder = Client::builder(TokioExecutor::new());
+        builder
+            .pool_timer(TokioTimer::new())
+            .timer(TokioTimer::new())
+            .http1_preserve_header_case(options.preserve_header_case)
+            .pool_idle_timeout(
+                (config.pool_idle_timeout_secs > 0)
+                    .then(|| Duration::from_secs(config.pool_idle_timeout_secs)),
+            );
+        if let Some(max_idle) = config.pool_max_idle_per_host {
+            builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(interval) = config.http2_keep_alive_interval_secs {
+            builder
+                .http2_keep_alive_interval(Duration::from_secs(interval))
+                .http2_keep_alive_timeout(Duration::from_secs(config.http2_keep_alive_timeout_secs))
+                .http2_keep_alive_while_idle(config.http2_keep_alive_while_idle);
+        }
+        Ok(builder.build::<_, AxumBody>(https_connector))
+    }
+
+    /// Build the rustls client configuration (native roots plus optional CA bundle,
+    /// client certificate and verification override, or SPIFFE identities).
+    fn build_tls_config(
+        tls: Option<&UpstreamTlsConfig>,
+        spiffe: Option<&Arc<SpiffeSource>>,
+    ) -> Result<rustls::ClientConfig> {
+        if tls.is_some_and(|t| t.spiffe) {
+            let source = spiffe
+                .ok_or_else(|| eyre!("Route requires SPIFFE but upstream.spiffe is not set"))?;
+            return Ok(source.client_config());
+        }
 
         // Build rustls client config with modern protocols
         let mut root_cert_store = rustls::RootCertStore::empty();
@@ -58,55 +271,532 @@ impl HttpClientAdapter {
             );
         }
 
-        // Configure TLS with ALPN for HTTP/2 negotiation
-        let tls_config = rustls::ClientConfig::builder()
-            .with_root_certificates(root_cert_store)
-            .with_no_client_auth();
+        let Some(tls) = tls else {
+            return Ok(rustls::ClientConfig::builder()
+                .with_root_certificates(root_cert_store)
+                .with_no_client_auth());
+        };
 
-        // Build HTTPS connector with HTTP/2 support
-        let https_connector = hyper_rustls::HttpsConnectorBuilder::new()
-            .with_tls_config(tls_config)
-            .https_or_http()
-            .enable_http1() // Support HTTP/1.1
-            .wrap_connector(http_connector);
+        if let Some(ca_bundle) = &tls.ca_bundle {
+            let (added, ignored) =
+                root_cert_store.add_parsable_certificates(read_pem_certs(ca_bundle)?);
+            tracing::info!("Loaded {added} CA certificates from {ca_bundle} ({ignored} ignored)");
+        }
 
-        // Create client with TokioExecutor for async runtime
-        let client = Client::builder(TokioExecutor::new()).build::<_, AxumBody>(https_connector);
+        let builder = if tls.insecure_skip_verify {
+            tracing::warn!("Backend certificate verification is DISABLED for this route");
+            rustls::ClientConfig::builder()
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(Arc::new(
+                    rustls::crypto::aws_lc_rs::default_provider(),
+                ))))
+        } else {
+            rustls::ClientConfig::builder().with_root_certificates(root_cert_store)
+        };
 
-        tracing::info!("Created new HTTP client with HTTP/2 and HTTP/1.1 support");
-        Ok(Self { client })
+        match (&tls.client_cert, &tls.client_key) {
+            (Some(cert_path), Some(key_path)) => {
+                let certs = read_pem_certs(cert_path)?;
+                let key_file = std::fs::File::open(key_path)
+                    .wrap_err_with(|| format!("Failed to open client key {key_path}"))?;
+                let key = rustls_pemfile::private_key(&mut std::io::BufReader::new(key_file))
+                    .wrap_err_with(|| format!("Failed to parse client key {key_path}"))?
+                    .ok_or_else(|| eyre!("No private key found in {key_path}"))?;
+                builder
+                    .with_client_auth_cert(certs, key)
+                    .wrap_err("Invalid client certificate / key pair")
+            }
+            _ => Ok(builder.with_no_client_auth()),
+        }
     }
 
-    /// Add common HTTP headers to requests
-    /// Inject a consistent set of headers if absent (User-Agent, Accept ...).
-    fn add_common_headers(req: &mut Request<AxumBody>) {
-        let headers = req.headers_mut();
-        if !headers.contains_key(header::USER_AGENT) {
-            headers.insert(
-                header::USER_AGENT,
-                header::HeaderValue::from_static("Axon-Gateway/1.0"),
-            );
+    /// Pick the client for a request: the shared default, or a cached client
+    /// matching the route's request options.
+    fn client_for(&self, options: &UpstreamRequestOptions) -> HttpClientResult<UpstreamClient> {
+        // Only connection-level options select a distinct client
+        let key = UpstreamRequestOptions {
+            inject_default_headers: false,
+            response_timeout: ResponseTimeoutConfig::default(),
+            fastcgi: None,
+            ..options.clone()
+        };
+        if key == UpstreamRequestOptions::default() {
+            return Ok(self.client.clone());
         }
-        if !headers.contains_key(header::ACCEPT) {
-            headers.insert(
-                header::ACCEPT,
-                header::HeaderValue::from_static(
-                    "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,*/*;q=0.8",
-                ),
-            );
+        if let Some(client) = self.route_clients.read_sync(&key, |_, c| c.clone()) {
+            return Ok(client);
         }
-        if !headers.contains_key(header::ACCEPT_LANGUAGE) {
-            headers.insert(
-                header::ACCEPT_LANGUAGE,
-                header::HeaderValue::from_static("en-US,en;q=0.5"),
-            );
+        let client =
+            Self::build_client(&self.upstream, &key, self.spiffe.as_ref()).map_err(|e| {
+                HttpClientError::ConnectionError(format!("Backend client setup failed: {e}"))
+            })?;
+        let _ = self.route_clients.insert_sync(key, client.clone());
+        Ok(client)
+    }
+
+    /// Inject the configured default headers that are not already present.
+    fn add_default_headers(&self, headers: &mut HeaderMap) {
+        for (name, value) in &self.default_headers {
+            if !headers.contains_key(name) {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Read all PEM certificates from a file.
+fn read_pem_certs(path: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let file =
+        std::fs::File::open(path).wrap_err_with(|| format!("Failed to open PEM file {path}"))?;
+    rustls_pemfile::certs(&mut std::io::BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .wrap_err_with(|| format!("Failed to parse certificates from {path}"))
+}
+
+/// Certificate verifier that accepts any backend certificate (`insecure_skip_verify`).
+/// Handshake signatures are still checked so the connection is well-formed.
+#[derive(Debug)]
+struct NoCertificateVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Plain TCP connector wrapper that records upstream connection lifecycle metrics.
+#[derive(Clone)]
+struct TrackedConnector {
+    inner: HttpConnector,
+    /// DSCP marking applied once connected; `HttpConnector` has no option for it
+    dscp: Option<u8>,
+    /// Names are resolved here and their addresses raced, so `inner` only
+    /// ever connects to a single address
+    ip_preference: IpPreference,
+    attempt_delay: Duration,
+}
+
+impl TrackedConnector {
+    /// Connect to `uri`, racing its resolved addresses (RFC 8305).
+    async fn connect(
+        inner: HttpConnector,
+        uri: Uri,
+        preference: IpPreference,
+        attempt_delay: Duration,
+        backend: &str,
+    ) -> Result<TokioIo<TcpStream>, BoxError> {
+        let host = uri
+            .host()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "URI has no host"))?
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+        let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+            Some("https") => 443,
+            _ => 80,
+        });
+        let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => tokio::net::lookup_host((host, port)).await?.collect(),
+        };
+        let addrs = happy_eyeballs::order_addresses(addrs, preference);
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{host} has no address allowed by ip_preference {preference:?}"),
+            )
+            .into());
+        }
+        let scheme = uri.scheme().cloned().unwrap_or(Scheme::HTTP);
+        happy_eyeballs::race(
+            addrs,
+            attempt_delay,
+            |addr| {
+                let mut inner = inner.clone();
+                let target = Uri::builder()
+                    .scheme(scheme.clone())
+                    .authority(addr.to_string())
+                    .path_and_query("/")
+                    .build();
+                async move { inner.call(target?).await.map_err(BoxError::from) }
+            },
+            |addr, e| {
+                tracing::debug!(backend, %addr, error = %e, "backend connection attempt failed");
+                metrics::record_upstream_connect_failure(backend, happy_eyeballs::family(&addr));
+            },
+        )
+        .await
+    }
+}
+
+impl Service<Uri> for TrackedConnector {
+    type Response = TrackedConnection<<HttpConnector as Service<Uri>>::Response>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let backend = format!(
+            "{}://{}",
+            uri.scheme_str().unwrap_or("http"),
+            uri.authority().map_or("unknown", |a| a.as_str())
+        );
+        let inner = self.inner.clone();
+        let (dscp, preference, delay) = (self.dscp, self.ip_preference, self.attempt_delay);
+        Box::pin(async move {
+            let started = Instant::now();
+            let io = Self::connect(inner, uri, preference, delay, &backend).await?;
+            if let Some(dscp) = dscp
+                && let Err(e) = set_dscp(io.inner(), dscp)
+            {
+                tracing::warn!(backend = %backend, error = %e, "failed to set DSCP marking");
+            }
+            metrics::record_upstream_connection_opened(&backend);
+            Ok(TrackedConnection {
+                inner: io,
+                established: ConnectTiming {
+                    started,
+                    finished: Instant::now(),
+                },
+            })
+        })
+    }
+}
+
+/// Apply `socket` to a connected backend stream, for connections that do not
+/// go through the HTTP client.
+pub(crate) fn apply_socket_options(
+    stream: &tokio::net::TcpStream,
+    socket: &UpstreamSocketConfig,
+) -> io::Result<()> {
+    let sock = socket2::SockRef::from(stream);
+    if let Some(nodelay) = socket.nodelay {
+        sock.set_tcp_nodelay(nodelay)?;
+    }
+    if let Some(size) = socket.send_buffer_bytes {
+        sock.set_send_buffer_size(size)?;
+    }
+    if let Some(size) = socket.recv_buffer_bytes {
+        sock.set_recv_buffer_size(size)?;
+    }
+    if let Some(dscp) = socket.dscp {
+        set_dscp(stream, dscp)?;
+    }
+    Ok(())
+}
+
+/// Mark packets sent on `stream` with a DSCP code point, which occupies the
+/// upper six bits of the IPv4 TOS / IPv6 traffic class byte.
+fn set_dscp(stream: &tokio::net::TcpStream, dscp: u8) -> io::Result<()> {
+    let sock = socket2::SockRef::from(stream);
+    let tos = u32::from(dscp) << 2;
+    if stream.peer_addr()?.is_ipv6() {
+        sock.set_tclass_v6(tos)
+    } else {
+        sock.set_tos_v4(tos)
+    }
+}
+
+/// When an upstream TCP connection was being opened, handed to responses on
+/// it through [`Connected::extra`].
+#[derive(Debug, Clone, Copy)]
+struct ConnectTiming {
+    started: Instant,
+    finished: Instant,
+}
+
+impl ConnectTiming {
+    /// How long a request sent at `sent` waited for this connection; zero if
+    /// the connection was already open.
+    fn waited(&self, sent: Instant) -> Duration {
+        self.finished
+            .saturating_duration_since(sent.max(self.started))
+    }
+}
+
+/// Upstream connection that decrements the open-connection gauge when dropped.
+struct TrackedConnection<T> {
+    inner: T,
+    established: ConnectTiming,
+}
+
+impl<T> Drop for TrackedConnection<T> {
+    fn drop(&mut self) {
+        metrics::record_upstream_connection_closed();
+    }
+}
+
+impl<T: Connection> Connection for TrackedConnection<T> {
+    fn connected(&self) -> Connected {
+        self.inner.connected().extra(self.established)
+    }
+}
+
+impl<T: Read + Unpin> Read for TrackedConnection<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: ReadBufCursor<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: Write + Unpin> Write for TrackedConnection<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+}
+
+/// Headers that only apply to a single connection (RFC 7230 section 6.1),
+/// plus the legacy `Keep-Alive` and `Proxy-Connection`
+const HOP_BY_HOP_HEADERS: [HeaderName; 8] = [
+    header::CONNECTION,
+    HeaderName::from_static("keep-alive"),
+    HeaderName::from_static("proxy-connection"),
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+/// Remove hop-by-hop headers, the headers `Connection` names as hop-by-hop
+/// and the configured `extra` ones.
+fn strip_hop_by_hop(headers: &mut HeaderMap, extra: &[HeaderName]) {
+    let listed: Vec<HeaderName> = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect();
+    for name in listed.iter().chain(&HOP_BY_HOP_HEADERS).chain(extra) {
+        headers.remove(name);
+    }
+}
+
+/// Whether the client's `TE` header lists `trailers`
+fn accepts_trailers(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::TE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            coding
+                .split(';')
+                .next()
+                .is_some_and(|name| name.trim().eq_ignore_ascii_case("trailers"))
+        })
+}
+
+/// A request body held back until the backend answers `Expect:
+/// 100-continue`. Since the client's body is not read before then, the
+/// client only gets its own `100 Continue` once the backend is ready for it.
+struct ContinueBody {
+    inner: AxumBody,
+    ready: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+}
+
+impl HttpBody for ContinueBody {
+    type Data = bytes::Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if let Some(ready) = self.ready.as_mut() {
+            if ready.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            self.ready = None;
         }
-        if !headers.contains_key(header::CACHE_CONTROL) {
-            headers.insert(
-                header::CACHE_CONTROL,
-                header::HeaderValue::from_static("max-age=0"),
+        Pin::new(&mut self.inner).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// A response body that fails with a timeout once the request's total
+/// response timeout runs out, aborting the client response mid-stream.
+struct DeadlineBody {
+    inner: AxumBody,
+    deadline: Pin<Box<tokio::time::Sleep>>,
+    total_secs: u64,
+}
+
+impl HttpBody for DeadlineBody {
+    type Data = bytes::Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if self.deadline.as_mut().poll(cx).is_ready() {
+            tracing::warn!(
+                "Backend response body not complete within {}s",
+                self.total_secs
             );
+            return Poll::Ready(Some(Err(axum::Error::new(HttpClientError::Timeout(
+                self.total_secs,
+            )))));
         }
+        Pin::new(&mut self.inner).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// RAII guard tracking an upstream request until its response head arrives.
+struct InFlightGuard;
+
+impl InFlightGuard {
+    fn new() -> Self {
+        metrics::add_upstream_requests_in_flight(1);
+        Self
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        metrics::add_upstream_requests_in_flight(-1);
+    }
+}
+
+impl HttpClientAdapter {
+    /// Run `req` on a FastCGI backend under the same response timeouts as
+    /// HTTP backends.
+    async fn send_fastcgi(
+        &self,
+        req: Request<AxumBody>,
+        route_options: &UpstreamRequestOptions,
+    ) -> HttpClientResult<Response<AxumBody>> {
+        let config = route_options.fastcgi.as_ref().ok_or_else(|| {
+            HttpClientError::InvalidRequest(
+                "fastcgi:// targets require a route `fastcgi` section".to_string(),
+            )
+        })?;
+        let response_timeout = route_options
+            .response_timeout
+            .or(self.upstream.response_timeout);
+        let head_secs = [response_timeout.header_secs, response_timeout.total_secs]
+            .into_iter()
+            .flatten()
+            .min();
+        let deadline = response_timeout
+            .total_secs
+            .map(|secs| tokio::time::Instant::now() + Duration::from_secs(secs));
+
+        let uri = req.uri().clone();
+        let in_flight = InFlightGuard::new();
+        let result = match head_secs {
+            Some(secs) => timeout(
+                Duration::from_secs(secs),
+                fastcgi::send_request(req, config),
+            )
+            .await
+            .map_err(|_| {
+                tracing::warn!("FastCGI backend {} did not respond within {}s", uri, secs);
+                HttpClientError::Timeout(secs)
+            })?,
+            None => fastcgi::send_request(req, config).await,
+        };
+        drop(in_flight);
+        let response = result.inspect_err(|e| {
+            tracing::error!("Error making FastCGI request to {}: {}", uri, e);
+        })?;
+
+        let (mut parts, body) = response.into_parts();
+        strip_hop_by_hop(&mut parts.headers, &self.strip_headers);
+        let body = match (deadline, response_timeout.total_secs) {
+            (Some(deadline), Some(total_secs)) => AxumBody::new(DeadlineBody {
+                inner: body,
+                deadline: Box::pin(tokio::time::sleep_until(deadline)),
+                total_secs,
+            }),
+            _ => body,
+        };
+        Ok(Response::from_parts(parts, body))
     }
 }
 
@@ -122,18 +812,37 @@ impl HttpClient for HttpClientAdapter {
         &self,
         mut req: Request<AxumBody>,
     ) -> HttpClientResult<Response<AxumBody>> {
-        Self::add_common_headers(&mut req);
+        let route_options = req
+            .extensions_mut()
+            .remove::<UpstreamRequestOptions>()
+            .unwrap_or_default();
+        if route_options.inject_default_headers {
+            self.add_default_headers(req.headers_mut());
+        }
 
-        // Clean up hop-by-hop headers to avoid confusing the backend
-        req.headers_mut().remove(header::CONNECTION);
-        req.headers_mut().remove(header::UPGRADE);
-        req.headers_mut().remove(header::PROXY_AUTHENTICATE);
-        req.headers_mut().remove(header::PROXY_AUTHORIZATION);
-        req.headers_mut().remove(header::TE);
-        req.headers_mut().remove(header::TRAILER);
-        req.headers_mut().remove(header::TRANSFER_ENCODING);
+        // Clean up hop-by-hop headers to avoid confusing the backend. `TE:
+        // trailers` is kept so backends still send trailers the client accepts.
+        let accepts_trailers = accepts_trailers(req.headers());
+        let upgrade = route_options
+            .upgrade
+            .then(|| req.headers_mut().remove(header::UPGRADE))
+            .flatten();
+        strip_hop_by_hop(req.headers_mut(), &self.strip_headers);
+        if req.uri().scheme_str() == Some(fastcgi::SCHEME) {
+            return self.send_fastcgi(req, &route_options).await;
+        }
+        if accepts_trailers {
+            req.headers_mut()
+                .insert(header::TE, HeaderValue::from_static("trailers"));
+        }
+        if let Some(upgrade) = upgrade {
+            req.headers_mut().insert(header::UPGRADE, upgrade);
+            req.headers_mut()
+                .insert(header::CONNECTION, HeaderValue::from_static("upgrade"));
+        }
+        self.negotiate_continue(&mut req);
 
-        let client = self.client.clone();
+        let client = self.client_for(&route_options)?;
 
         // Extract backend information for logging and metrics
         let backend_identifier = format!(
@@ -184,9 +893,57 @@ impl HttpClient for HttpClientAdapter {
         let method_for_error_log = outgoing_request.method().clone();
         let uri_for_error_log = outgoing_request.uri().clone();
 
+        // The head must arrive within both limits; the body only within the
+        // total one, which upgraded connections are exempt from
+        let response_timeout = route_options
+            .response_timeout
+            .or(self.upstream.response_timeout);
+        let total_secs = response_timeout
+            .total_secs
+            .filter(|_| !route_options.upgrade);
+        let head_secs = [response_timeout.header_secs, total_secs]
+            .into_iter()
+            .flatten()
+            .min();
+        let deadline =
+            total_secs.map(|secs| tokio::time::Instant::now() + Duration::from_secs(secs));
+
         // Send request
-        match client.request(outgoing_request).await {
-            Ok(response) => {
+        let in_flight = InFlightGuard::new();
+        let sent = Instant::now();
+        let result = match head_secs {
+            Some(secs) => {
+                match timeout(Duration::from_secs(secs), client.request(outgoing_request)).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        tracing::Span::current().record("http.status_code", 504u16);
+                        tracing::warn!(
+                            "Backend {} did not respond to {} {} within {}s",
+                            backend_identifier,
+                            method_for_error_log,
+                            uri_for_error_log,
+                            secs
+                        );
+                        return Err(HttpClientError::Timeout(secs));
+                    }
+                }
+            }
+            None => client.request(outgoing_request).await,
+        };
+        drop(in_flight);
+
+        match result {
+            Ok(mut response) => {
+                let elapsed = sent.elapsed();
+                let connect = response
+                    .extensions()
+                    .get::<ConnectTiming>()
+                    .map_or(Duration::ZERO, |timing| timing.waited(sent));
+                response.extensions_mut().insert(BackendTiming {
+                    connect,
+                    first_byte: elapsed.saturating_sub(connect),
+                });
+
                 let status_code = response.status().as_u16();
 
                 // Record status code in the tracing span
@@ -195,11 +952,33 @@ impl HttpClient for HttpClientAdapter {
                 // Convert Hyper response body back to AxumBody
                 let (mut parts, hyper_body) = response.into_parts();
 
-                // Remove Transfer-Encoding header since the body is being decoded/streamed
-                // and the downstream server (Axum) will handle framing.
-                parts.headers.remove(header::TRANSFER_ENCODING);
+                // Hop-by-hop headers describe the backend connection, not the
+                // client's; the server frames the body itself. A `101` keeps
+                // `Upgrade` and `Connection` so the client can switch too.
+                let switching = (parts.status == StatusCode::SWITCHING_PROTOCOLS).then(|| {
+                    (
+                        parts.headers.remove(header::UPGRADE),
+                        parts.headers.remove(header::CONNECTION),
+                    )
+                });
+                strip_hop_by_hop(&mut parts.headers, &self.strip_headers);
+                if let Some((upgrade, connection)) = switching {
+                    if let Some(upgrade) = upgrade {
+                        parts.headers.insert(header::UPGRADE, upgrade);
+                    }
+                    if let Some(connection) = connection {
+                        parts.headers.insert(header::CONNECTION, connection);
+                    }
+                }
 
-                let axum_body = AxumBody::new(hyper_body);
+                let axum_body = match (deadline, total_secs) {
+                    (Some(deadline), Some(total_secs)) => AxumBody::new(DeadlineBody {
+                        inner: AxumBody::new(hyper_body),
+                        deadline: Box::pin(tokio::time::sleep_until(deadline)),
+                        total_secs,
+                    }),
+                    _ => AxumBody::new(hyper_body),
+                };
 
                 Ok(Response::from_parts(parts, axum_body))
             }
@@ -222,15 +1001,40 @@ impl HttpClient for HttpClientAdapter {
         }
     }
 
-    async fn health_check(&self, url: &str, timeout_secs: u64) -> HttpClientResult<bool> {
-        let client = self.client.clone();
+    async fn health_check(
+        &self,
+        url: &str,
+        options: &HealthCheckOptions,
+        timeout_secs: u64,
+    ) -> HttpClientResult<bool> {
+        if let Some(authority) = url.strip_prefix("fastcgi://") {
+            let authority = authority.split('/').next().unwrap_or_default();
+            return Ok(fastcgi::probe(authority, Duration::from_secs(timeout_secs)).await);
+        }
+        let client = self.client_for(&UpstreamRequestOptions {
+            tls: options.tls.clone(),
+            ..UpstreamRequestOptions::default()
+        })?;
 
-        let request = Request::builder()
+        let mut request = Request::builder()
             .method("HEAD")
             .uri(url)
             .version(Version::HTTP_11)
             .body(AxumBody::empty())
             .map_err(|e| HttpClientError::InvalidRequest(e.to_string()))?;
+        for (name, value) in [
+            (header::HOST, &options.host),
+            (header::AUTHORIZATION, &options.authorization),
+        ] {
+            if let Some(value) = value {
+                let value = HeaderValue::from_str(value)
+                    .map_err(|e| HttpClientError::InvalidRequest(format!("{name}: {e}")))?;
+                request.headers_mut().insert(name, value);
+            }
+        }
+        if self.upstream.default_headers_on_health_checks {
+            self.add_default_headers(request.headers_mut());
+        }
 
         tracing::debug!("Health checking URL: {} (Version set to HTTP/1.1)", url);
         let timeout_duration = Duration::from_secs(timeout_secs);
@@ -317,31 +1121,409 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_add_common_headers() {
-        let mut req = Request::builder()
-            .uri("https://example.com")
+    async fn test_http_client_with_upstream_config() {
+        let config = UpstreamConfig {
+            pool_max_idle_per_host: Some(8),
+            pool_idle_timeout_secs: 30,
+            connect_timeout_secs: Some(2),
+            tcp_keepalive_secs: Some(60),
+            http2_enabled: true,
+            http2_keep_alive_interval_secs: Some(15),
+            ..UpstreamConfig::default()
+        };
+        assert!(HttpClientAdapter::with_config(&config).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_route_clients_are_cached() {
+        let client = HttpClientAdapter::new().unwrap();
+        let options = UpstreamRequestOptions {
+            tls: Some(UpstreamTlsConfig {
+                server_name: Some("backend.internal".to_string()),
+                insecure_skip_verify: true,
+                ..UpstreamTlsConfig::default()
+            }),
+            preserve_header_case: true,
+            inject_default_headers: true,
+            upgrade: false,
+            response_timeout: ResponseTimeoutConfig {
+                header_secs: Some(5),
+                total_secs: None,
+            },
+            socket: UpstreamSocketConfig::default(),
+            fastcgi: None,
+        };
+
+        assert!(client.client_for(&options).is_ok());
+        // Header injection and timeouts do not select a separate client
+        let without_injection = UpstreamRequestOptions {
+            inject_default_headers: false,
+            response_timeout: ResponseTimeoutConfig::default(),
+            ..options.clone()
+        };
+        assert!(client.client_for(&without_injection).is_ok());
+        assert_eq!(client.route_clients.len(), 1);
+
+        // Default options reuse the shared client
+        assert!(
+            client
+                .client_for(&UpstreamRequestOptions::default())
+                .is_ok()
+        );
+        assert_eq!(client.route_clients.len(), 1);
+
+        let missing_ca = UpstreamRequestOptions {
+            tls: Some(UpstreamTlsConfig {
+                ca_bundle: Some("/nonexistent/ca.pem".to_string()),
+                ..UpstreamTlsConfig::default()
+            }),
+            ..UpstreamRequestOptions::default()
+        };
+        assert!(client.client_for(&missing_ca).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_socket_options() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = axum::Router::new().fallback(|| async { "ok" });
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        apply_socket_options(
+            &stream,
+            &UpstreamSocketConfig {
+                nodelay: Some(true),
+                send_buffer_bytes: Some(64 * 1024),
+                recv_buffer_bytes: None,
+                dscp: Some(46),
+            },
+        )
+        .unwrap();
+        let sock = socket2::SockRef::from(&stream);
+        assert!(sock.tcp_nodelay().unwrap());
+        assert_eq!(sock.tos_v4().unwrap(), 46 << 2);
+        assert!(sock.send_buffer_size().unwrap() >= 64 * 1024);
+
+        // Route options are merged over the client's and select their own client
+        let client = HttpClientAdapter::with_config(&UpstreamConfig {
+            socket: UpstreamSocketConfig {
+                nodelay: Some(true),
+                ..UpstreamSocketConfig::default()
+            },
+            ..UpstreamConfig::default()
+        })
+        .unwrap();
+        let mut request = Request::builder()
+            .uri(format!("http://{addr}/"))
+            .body(AxumBody::empty())
+            .unwrap();
+        request.extensions_mut().insert(UpstreamRequestOptions {
+            socket: UpstreamSocketConfig {
+                dscp: Some(10),
+                ..UpstreamSocketConfig::default()
+            },
+            ..UpstreamRequestOptions::default()
+        });
+        let response = client.send_request(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(client.route_clients.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ip_preference() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = axum::Router::new().fallback(|| async { "ok" });
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let send = |ip_preference| async move {
+            let client = HttpClientAdapter::with_config(&UpstreamConfig {
+                ip_preference,
+                ..UpstreamConfig::default()
+            })
+            .unwrap();
+            let request = Request::builder()
+                .uri(format!("http://localhost:{port}/"))
+                .body(AxumBody::empty())
+                .unwrap();
+            client.send_request(request).await
+        };
+
+        // Whether or not localhost also resolves to ::1, only IPv4 reaches
+        // the backend
+        for preference in [
+            IpPreference::Auto,
+            IpPreference::Ipv6,
+            IpPreference::Ipv4Only,
+        ] {
+            assert_eq!(send(preference).await.unwrap().status(), StatusCode::OK);
+        }
+        assert!(send(IpPreference::Ipv6Only).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_responses_carry_backend_timing() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let app = axum::Router::new().fallback(|| async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            "ok"
+        });
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = HttpClientAdapter::new().unwrap();
+        let mut timings = Vec::new();
+        for _ in 0..2 {
+            let request = Request::builder()
+                .uri(&url)
+                .body(AxumBody::empty())
+                .unwrap();
+            let response = client.send_request(request).await.unwrap();
+            timings.push(*response.extensions().get::<BackendTiming>().unwrap());
+            response.into_body().collect().await.unwrap();
+        }
+
+        assert!(timings[0].connect > Duration::ZERO);
+        assert!(timings[0].first_byte >= Duration::from_millis(20));
+        // The second request reuses the pooled connection
+        assert_eq!(timings[1].connect, Duration::ZERO);
+        assert!(timings[1].first_byte >= Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn test_response_timeouts() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let app = axum::Router::new()
+            .route(
+                "/slow-head",
+                axum::routing::get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "late"
+                }),
+            )
+            .route(
+                "/slow-body",
+                axum::routing::get(|| async {
+                    let chunks = futures_util::stream::unfold(0, |sent| async move {
+                        if sent > 0 {
+                            tokio::time::sleep(Duration::from_secs(5)).await;
+                        }
+                        (sent < 2).then(|| (Ok::<_, io::Error>("chunk"), sent + 1))
+                    });
+                    AxumBody::from_stream(chunks)
+                }),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = HttpClientAdapter::with_config(&UpstreamConfig {
+            response_timeout: ResponseTimeoutConfig {
+                header_secs: Some(1),
+                total_secs: None,
+            },
+            ..UpstreamConfig::default()
+        })
+        .unwrap();
+        let request = |path: &str, total_secs: Option<u64>| {
+            let mut request = Request::builder()
+                .uri(format!("{base}{path}"))
+                .body(AxumBody::empty())
+                .unwrap();
+            request.extensions_mut().insert(UpstreamRequestOptions {
+                response_timeout: ResponseTimeoutConfig {
+                    header_secs: None,
+                    total_secs,
+                },
+                ..UpstreamRequestOptions::default()
+            });
+            request
+        };
+
+        let err = client
+            .send_request(request("/slow-head", None))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HttpClientError::Timeout(1)));
+
+        // The head arrives in time, but the body is cut off by the route's
+        // total timeout
+        let response = client
+            .send_request(request("/slow-body", Some(1)))
+            .await
+            .unwrap();
+        let started = Instant::now();
+        assert!(response.into_body().collect().await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn test_hop_by_hop_headers_are_stripped_both_ways() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let backend = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                head.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: x-backend-hop\r\n\
+                      x-backend-hop: 1\r\nkeep-alive: timeout=5\r\nx-internal: secret\r\n\
+                      x-kept: yes\r\n\r\nok",
+                )
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&head).to_lowercase()
+        });
+
+        let client = HttpClientAdapter::with_config(&UpstreamConfig {
+            strip_headers: vec!["x-internal".to_string()],
+            ..UpstreamConfig::default()
+        })
+        .unwrap();
+        let request = Request::get(&url)
+            .header(header::CONNECTION, "keep-alive, x-client-hop")
+            .header("x-client-hop", "1")
+            .header("keep-alive", "timeout=5")
+            .header("proxy-connection", "keep-alive")
+            .header("x-internal", "secret")
+            .header("x-kept", "yes")
             .body(AxumBody::empty())
             .unwrap();
+        let response = client.send_request(request).await.unwrap();
+        let head = backend.await.unwrap();
+        for name in [
+            "x-client-hop",
+            "keep-alive:",
+            "proxy-connection",
+            "x-internal",
+        ] {
+            assert!(!head.contains(name), "{name} forwarded: {head}");
+        }
+        assert!(head.contains("x-kept: yes"));
+
+        let headers = response.headers();
+        for name in ["connection", "x-backend-hop", "keep-alive", "x-internal"] {
+            assert!(!headers.contains_key(name), "{name} returned");
+        }
+        assert_eq!(headers["x-kept"], "yes");
+    }
+
+    #[tokio::test]
+    async fn test_add_default_headers() {
+        let client = HttpClientAdapter::new().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static("application/json"));
 
-        HttpClientAdapter::add_common_headers(&mut req);
+        client.add_default_headers(&mut headers);
 
-        let headers = req.headers();
         assert!(headers.contains_key(header::USER_AGENT));
-        assert!(headers.contains_key(header::ACCEPT));
         assert!(headers.contains_key(header::ACCEPT_LANGUAGE));
         assert!(headers.contains_key(header::CACHE_CONTROL));
-
-        // Verify User-Agent is set to Axon-Gateway
+        // Headers already set by the caller are left untouched
+        assert_eq!(
+            headers.get(header::ACCEPT).unwrap(),
+            HeaderValue::from_static("application/json")
+        );
         assert_eq!(
             headers.get(header::USER_AGENT).unwrap(),
             HeaderValue::from_static("Axon-Gateway/1.0")
         );
     }
 
+    #[test]
+    fn test_custom_default_headers() {
+        let config = UpstreamConfig {
+            default_headers: [("X-Gateway".to_string(), "axon".to_string())]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let client = HttpClientAdapter::with_config(&config).unwrap();
+        let mut headers = HeaderMap::new();
+        client.add_default_headers(&mut headers);
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.get("x-gateway").unwrap(), "axon");
+
+        let invalid = UpstreamConfig {
+            default_headers: [("bad header".to_string(), "x".to_string())]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        assert!(HttpClientAdapter::with_config(&invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expect_continue_and_trailers_pass_through() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/upload", listener.local_addr().unwrap());
+        let backend = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                head.extend_from_slice(&buf[..n]);
+            }
+            let head = String::from_utf8_lossy(&head).to_lowercase();
+            assert!(head.contains("expect: 100-continue"));
+            assert!(head.contains("te: trailers"));
+
+            // Nothing of the body arrives before `100 Continue`
+            let early = timeout(Duration::from_millis(100), stream.read(&mut buf)).await;
+            assert!(early.is_err());
+            stream
+                .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+                .await
+                .unwrap();
+            let n = timeout(Duration::from_secs(2), stream.read(&mut buf))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(&buf[..n], b"hello");
+
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\ntrailer: x-checksum\r\n\r\n\
+                      2\r\nok\r\n0\r\nx-checksum: abc\r\n\r\n",
+                )
+                .await
+                .unwrap();
+        });
+
+        let client = HttpClientAdapter::with_config(&UpstreamConfig {
+            expect_continue_timeout_ms: 10_000,
+            ..UpstreamConfig::default()
+        })
+        .unwrap();
+        let request = Request::post(&url)
+            .header(header::EXPECT, "100-continue")
+            .header(header::TE, "gzip, trailers")
+            .header(header::CONTENT_LENGTH, "5")
+            .body(AxumBody::from("hello"))
+            .unwrap();
+        let response = client.send_request(request).await.unwrap();
+        let body = response.into_body().collect().await.unwrap();
+        assert_eq!(body.trailers().unwrap().get("x-checksum").unwrap(), "abc");
+        assert_eq!(body.to_bytes(), "ok");
+        backend.await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_health_check_invalid_url() {
         let client = HttpClientAdapter::new().unwrap();
-        let result = client.health_check("invalid-url", 5).await;
+        let result = client
+            .health_check("invalid-url", &HealthCheckOptions::default(), 5)
+            .await;
 
         // Should return Ok(false) for connection errors
         match result {
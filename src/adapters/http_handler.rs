@@ -15,40 +15,116 @@
 //! * Track active connections & requests for observability and graceful shutdown.
 //!
 //! The actual network server (Axum) delegates into `HttpHandler::handle_request`.
-use std::{net::SocketAddr, str::FromStr, sync::Arc, time::Instant};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
 use arc_swap::ArcSwap;
 use axum::{
+    Router,
     body::{Body as AxumBody, to_bytes},
-    http::{HeaderMap, StatusCode, header},
+    extract::ConnectInfo,
+    http::{HeaderMap, HeaderValue, StatusCode, Uri, header, uri::PathAndQuery},
 };
 use eyre::{Result, WrapErr};
+use futures_util::future::BoxFuture;
+use http_body_util::{BodyExt, BodyStream, StreamBody};
 use hyper::{Request, Response};
+use hyper_util::rt::TokioIo;
+use tokio_util::sync::CancellationToken;
+use tower::{ServiceExt, util::BoxCloneSyncService};
 use tracing::Instrument;
 // WebSocket proxy support (stub implementation)
 use uuid::Uuid;
 
 use crate::{
-    adapters::FileSystemAdapter,
-    config::models::{RouteConfig, ServerConfig},
-    core::GatewayService,
+    adapters::{
+        TlsSession,
+        audit::{AuditDecision, AuditEvent, AuditEventKind, AuditLog},
+        events::{EventPublisher, RequestEvent},
+        http_client::apply_socket_options,
+        middleware::{MiddlewareContext, MiddlewareRegistry, X_REQUEST_ID, is_valid_request_id},
+        notifications::{Notification, notify},
+        request_timing::{Phase, RequestTimings},
+        tap::TapRecorder,
+        transfer::{BodyKind, CountingBody, TransferStats},
+        tunnel::{self, TunnelLimits, TunnelRequest, requested_tunnel},
+    },
+    build_info,
+    config::{
+        models::{
+            BackendRemovalMode, BodyActions, BuiltinEndpoint, CoalesceConfig, DEFAULT_ROUTE_PREFIX,
+            DedupeConfig, HeaderActions, HeaderLimitsConfig, HealthStatus, NotificationEvent,
+            PathNormalizationConfig, ResponseTimeoutConfig, RouteConfig, ServerConfig,
+            ShutdownNewRequests, SniHostCheck, TapConfig,
+        },
+        validation::{MAX_TAP_BODY_BYTES, MAX_TAP_CAPACITY},
+    },
+    core::{
+        CanaryGroup, CanaryState, GatewayError, GatewayService, RollbackReason,
+        backend::BackendPermit,
+        body_rewrite::BodyRewrite,
+        client_ip::{ClientIp, request_client_ip},
+        coalesce::{Join, SharedResponse, coalesce_key},
+        condition::ConditionContext,
+        content_type,
+        dedupe::{dedupe_key, is_deduplicated_method},
+        error_pages, error_response, experiments,
+        framing::check_framing,
+        hedge::Hedger,
+        locale::match_language,
+        location_rewrite::LocationRewriter,
+        path_normalization::{PathNormalizationError, normalize_path},
+        path_template::metrics_path_label,
+        retry::{backoff, is_idempotent_method, is_retryable_method, is_safe_method},
+        schedule::ScheduleState,
+        secret::constant_time_eq,
+        waf::{Offense, ThreatLevel, ip_filter::IpNetwork},
+    },
     ports::{
-        file_system::FileSystem,
-        http_client::{HttpClient, HttpClientError},
+        file_system::{FileSystem, StaticServeOptions},
+        http_client::{
+            BackendTiming, HttpClient, HttpClientError, HttpClientResult, UpstreamRequestOptions,
+        },
     },
     tracing_setup,
     utils::ConnectionTracker,
 };
 
+/// Metric `path` label of requests that matched no route
+const UNMATCHED_METRICS_PATH: &str = "unmatched";
+
+/// Gateway phase timings added when `server_timing.enabled` is set
+const SERVER_TIMING: http::HeaderName = http::HeaderName::from_static("server-timing");
+
+/// Experiment variant headers for the backend, set after any request header
+/// allowlist so they always arrive
+#[derive(Clone)]
+struct ExperimentHeaders(Vec<(http::HeaderName, HeaderValue)>);
+
 /// Primary façade handling inbound HTTP requests and delegating to specific
 /// endpoint / proxy logic.
 pub struct HttpHandler {
     /// Holder for the active `GatewayService` that is swapped on config reload.
     gateway_service_holder: Arc<ArcSwap<GatewayService>>,
     http_client: Arc<dyn HttpClient>,
-    file_system: Arc<FileSystemAdapter>,
+    file_system: Arc<dyn FileSystem>,
     connection_tracker: Arc<ConnectionTracker>,
     config: Arc<ArcSwap<ServerConfig>>,
+    /// Middlewares available to routes through their `middlewares` list.
+    middleware_registry: Arc<MiddlewareRegistry>,
+    /// Sink for security audit records (disabled unless configured).
+    audit: AuditLog,
+    /// Publisher of per-request events (disabled unless configured).
+    events: EventPublisher,
 }
 
 impl HttpHandler {
@@ -56,7 +132,7 @@ impl HttpHandler {
     pub fn new(
         gateway_service_holder: Arc<ArcSwap<GatewayService>>,
         http_client: Arc<dyn HttpClient>,
-        file_system: Arc<FileSystemAdapter>,
+        file_system: Arc<dyn FileSystem>,
         connection_tracker: Arc<ConnectionTracker>,
         config: Arc<ArcSwap<ServerConfig>>,
     ) -> Self {
@@ -66,9 +142,31 @@ impl HttpHandler {
             file_system,
             connection_tracker,
             config,
+            middleware_registry: Arc::new(MiddlewareRegistry::with_builtins()),
+            audit: AuditLog::disabled(),
+            events: EventPublisher::disabled(),
         }
     }
 
+    /// Replace the middleware registry (e.g. to register custom middlewares
+    /// alongside the built-ins).
+    pub fn with_middleware_registry(mut self, registry: MiddlewareRegistry) -> Self {
+        self.middleware_registry = Arc::new(registry);
+        self
+    }
+
+    /// Send WAF, rate limit and authentication decisions to `audit`.
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Publish a summary of every completed request to `events`.
+    pub fn with_event_publisher(mut self, events: EventPublisher) -> Self {
+        self.events = events;
+        self
+    }
+
     /// Get the current `GatewayService` (updated after hot reload).
     fn current_gateway(&self) -> Arc<GatewayService> {
         self.gateway_service_holder.load_full()
@@ -99,114 +197,454 @@ impl HttpHandler {
     /// Entry point for Axum – wraps routing with tracing and timing.
     pub async fn handle_request(
         &self,
-        req: Request<AxumBody>,
+        mut req: Request<AxumBody>,
         client_addr: Option<SocketAddr>,
     ) -> Result<Response<AxumBody>, eyre::Error> {
         let start_time = Instant::now();
         let method = req.method().clone();
         let uri = req.uri().clone();
         let path = uri.path();
-        let request_id = Uuid::new_v4().to_string();
+        let protocol = if req.version() == http::Version::HTTP_3 {
+            "http3"
+        } else {
+            "http"
+        };
+
+        // Honor a well-formed incoming request ID, otherwise mint one; it is
+        // forwarded to backends and echoed in the response.
+        let request_id_header = match req.headers().get(X_REQUEST_ID) {
+            Some(value) if is_valid_request_id(value) => value.clone(),
+            _ => HeaderValue::from_str(&Uuid::new_v4().to_string())
+                .expect("UUID is a valid header value"),
+        };
+        req.headers_mut()
+            .insert(X_REQUEST_ID, request_id_header.clone());
+        let request_id = request_id_header.to_str().unwrap_or_default().to_string();
+        let accept = req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|h| h.to_str().ok())
+            .map(String::from);
+        let route_host = Self::extract_routing_host(req.headers());
+        // Requests to a virtual host are logged under its name, if at all
+        let (vhost, access_log) = match self.current_gateway().virtual_host(route_host.as_deref()) {
+            Some((name, vhost)) => (Some(name.to_string()), vhost.access_log),
+            None => (None, true),
+        };
+        // Bans, the WAF, rate limits, logs and audit records all attribute
+        // the request to the client behind any trusted proxies
+        let client_ip =
+            client_addr.map(|addr| self.current_gateway().client_ip(addr.ip(), req.headers()));
+        if let Some(ip) = client_ip {
+            req.extensions_mut().insert(ClientIp(ip));
+        }
+
+        // Count body bytes as they stream through; the totals are recorded
+        // once both bodies are done with. Routing labels them with the route.
+        let transfer = TransferStats::downstream(&request_id, protocol);
+        let mut req = req.map(|body| CountingBody::wrap(body, transfer.clone(), BodyKind::Request));
+        req.extensions_mut().insert(transfer.clone());
+        let timings = RequestTimings::new();
+        req.extensions_mut().insert(timings.clone());
+
+        // Assign experiment variants before routing, so every route type is
+        // counted; proxied routes forward them to the backend
+        let config = self.config.load_full();
+        let assignments = experiments::assign(
+            &config.experiments,
+            path,
+            req.headers()
+                .get_all(header::COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok()),
+        );
+        let experiment_headers: Vec<_> = assignments
+            .iter()
+            .filter_map(|assignment| {
+                Some((
+                    http::HeaderName::from_bytes(assignment.experiment.header_name().as_bytes())
+                        .ok()?,
+                    HeaderValue::from_str(assignment.variant).ok()?,
+                ))
+            })
+            .collect();
+        if !experiment_headers.is_empty() {
+            req.extensions_mut()
+                .insert(ExperimentHeaders(experiment_headers));
+        }
 
         // Extract client info for logging
-        let client_ip = client_addr.map(|addr| addr.ip().to_string());
         let user_agent = req
             .headers()
             .get(header::USER_AGENT)
             .and_then(|h| h.to_str().ok())
             .map(String::from);
+        let tenant = self.events.tenant(req.headers());
 
         // Create request span with comprehensive info
         let span = tracing_setup::create_request_span(
             method.as_str(),
             path,
             &request_id,
-            client_ip.as_deref(),
+            client_ip.map(|ip| ip.to_string()).as_deref(),
             user_agent.as_deref(),
         );
 
-        let result: Result<Response<AxumBody>, eyre::Error> =
+        let mut result: Result<Response<AxumBody>, eyre::Error> =
             async { self.route_request(req, client_addr).await }
                 .instrument(span)
-                .await;
+                .await
+                .map(|response| {
+                    self.render_error_page(
+                        response,
+                        path,
+                        route_host.as_deref(),
+                        accept.as_deref(),
+                        &request_id,
+                    )
+                });
+        let duration = start_time.elapsed();
+        let breakdown = timings.snapshot();
+        let metrics_path = breakdown
+            .metrics_path
+            .as_deref()
+            .unwrap_or(UNMATCHED_METRICS_PATH);
+        if let Ok(response) = &mut result {
+            response
+                .headers_mut()
+                .entry(X_REQUEST_ID)
+                .or_insert(request_id_header);
+            // Appended, so a backend's own Server-Timing entries are kept
+            if self.config.load().server_timing.enabled
+                && let Ok(value) = HeaderValue::from_str(&breakdown.server_timing(duration))
+            {
+                response.headers_mut().append(SERVER_TIMING, value);
+            }
+            for assignment in assignments.iter().filter(|assignment| assignment.new) {
+                if let Ok(value) = HeaderValue::from_str(&assignment.set_cookie()) {
+                    response.headers_mut().append(header::SET_COOKIE, value);
+                }
+            }
+        }
+        let status = result
+            .as_ref()
+            .map_or(500, |response| response.status().as_u16());
+        for assignment in &assignments {
+            crate::metrics::record_experiment_request(
+                &assignment.experiment.name,
+                assignment.variant,
+                status,
+                duration,
+            );
+        }
+        let result = result.map(|response| {
+            response.map(|body| CountingBody::wrap(body, transfer, BodyKind::Response))
+        });
 
         // Log request completion with timing and outcome
-        let duration = start_time.elapsed();
         match &result {
             Ok(response) => {
                 tracing::Span::current().record("http.status_code", response.status().as_u16());
                 tracing::Span::current().record("duration_ms", duration.as_millis() as u64);
 
-                tracing::info!(
-                    status = response.status().as_u16(),
-                    duration_ms = duration.as_millis(),
-                    "request completed"
-                );
+                if access_log {
+                    tracing::info!(
+                        request_id = %request_id,
+                        vhost = vhost.as_deref(),
+                        status = response.status().as_u16(),
+                        duration_ms = duration.as_millis(),
+                        "request completed"
+                    );
+                }
                 crate::metrics::increment_request_total(
-                    path,
+                    metrics_path,
                     method.as_str(),
                     response.status().as_u16(),
-                    "http",
+                    protocol,
+                );
+                if response.status().is_client_error()
+                    && let Some(ip) = client_ip
+                {
+                    self.current_gateway()
+                        .record_offense(ip, Offense::ClientError);
+                }
+                crate::metrics::record_request_duration(
+                    metrics_path,
+                    method.as_str(),
+                    protocol,
+                    duration,
                 );
-                crate::metrics::record_request_duration(path, method.as_str(), "http", duration);
             }
             Err(e) => {
                 tracing::Span::current().record("http.status_code", 500u16);
                 tracing::Span::current().record("duration_ms", duration.as_millis() as u64);
 
                 tracing::error!(
+                    request_id = %request_id,
+                    vhost = vhost.as_deref(),
                     error = %e,
                     duration_ms = duration.as_millis(),
                     "request failed"
                 );
-                crate::metrics::increment_request_total(path, method.as_str(), 500, "http");
-                crate::metrics::record_request_duration(path, method.as_str(), "http", duration);
+                crate::metrics::increment_request_total(
+                    metrics_path,
+                    method.as_str(),
+                    500,
+                    protocol,
+                );
+                crate::metrics::record_request_duration(
+                    metrics_path,
+                    method.as_str(),
+                    protocol,
+                    duration,
+                );
             }
         }
 
+        if breakdown.is_slow(duration) {
+            let route = breakdown.route.as_deref().unwrap_or_default();
+            let millis = |phase: Option<Duration>| phase.map(|d| d.as_millis() as u64);
+            tracing::warn!(
+                request_id = %request_id,
+                route,
+                method = %method,
+                path,
+                status = result.as_ref().map_or(500, |response| response.status().as_u16()),
+                threshold_ms = millis(breakdown.slow_threshold),
+                total_ms = duration.as_millis() as u64,
+                queue_ms = millis(breakdown.queue),
+                connect_ms = millis(breakdown.connect),
+                backend_ttfb_ms = millis(breakdown.backend_first_byte),
+                "slow request"
+            );
+            crate::metrics::record_slow_request(route);
+        }
+
+        if self.events.is_enabled() {
+            self.events.publish(RequestEvent {
+                timestamp: chrono::Utc::now(),
+                request_id,
+                route: breakdown.route,
+                vhost,
+                method: method.to_string(),
+                status: result
+                    .as_ref()
+                    .map_or(500, |response| response.status().as_u16()),
+                latency_ms: duration.as_millis() as u64,
+                tenant,
+                client_ip: client_ip.map(|ip| ip.to_string()),
+                threats: breakdown.threats,
+            });
+        }
+
         result
     }
 
+    /// Replace the body of a gateway-generated error with the configured error page.
+    fn render_error_page(
+        &self,
+        response: Response<AxumBody>,
+        path: &str,
+        host: Option<&str>,
+        accept: Option<&str>,
+        request_id: &str,
+    ) -> Response<AxumBody> {
+        if response.extensions().get::<GatewayError>().is_none() {
+            return response;
+        }
+
+        let route = self.current_gateway().find_matching_route(path, host);
+        let route_pages = route
+            .as_ref()
+            .and_then(|(_, route_config)| match route_config {
+                RouteConfig::Static { error_pages, .. }
+                | RouteConfig::Redirect { error_pages, .. }
+                | RouteConfig::Proxy { error_pages, .. }
+                | RouteConfig::LoadBalance { error_pages, .. }
+                | RouteConfig::Websocket { error_pages, .. } => error_pages.as_ref(),
+            });
+        error_pages::render_error_page(
+            response,
+            &self.config.load().error_pages,
+            route_pages,
+            accept,
+            request_id,
+            path,
+        )
+    }
+
+    /// Whether the request's Host fits the SNI of its TLS connection under
+    /// `tls.sni_host_check`. Plaintext requests always pass.
+    fn host_matches_sni(&self, req: &Request<AxumBody>) -> bool {
+        let Some(session) = req.extensions().get::<TlsSession>() else {
+            return true;
+        };
+        let check = self
+            .config
+            .load()
+            .tls
+            .as_ref()
+            .map(|tls| tls.sni_host_check)
+            .unwrap_or_default();
+        let server_name = match (check, session.server_name.as_deref()) {
+            (SniHostCheck::Off, _) | (SniHostCheck::Match, None) => return true,
+            (SniHostCheck::Strict, None) => return false,
+            (_, Some(server_name)) => server_name,
+        };
+        // HTTP/2 requests carry the host in the :authority pseudo-header
+        Self::extract_routing_host(req.headers())
+            .or_else(|| req.uri().host().map(str::to_string))
+            .is_some_and(|host| {
+                host.trim_end_matches('.')
+                    .eq_ignore_ascii_case(server_name.trim_end_matches('.'))
+            })
+    }
+
     /// Determine how to satisfy the request (static, proxy, redirect, etc.).
     async fn route_request(
         &self,
         req: Request<AxumBody>,
         client_addr: Option<SocketAddr>,
     ) -> Result<Response<AxumBody>, eyre::Error> {
+        if self.connection_tracker.is_shutting_down()
+            && self.config.load().shutdown.new_requests == ShutdownNewRequests::Reject
+        {
+            let mut response =
+                error_response(StatusCode::SERVICE_UNAVAILABLE, "Server shutting down");
+            // HTTP/2 connections are closed with GOAWAY instead
+            if req.version() <= http::Version::HTTP_11 {
+                response
+                    .headers_mut()
+                    .insert(header::CONNECTION, HeaderValue::from_static("close"));
+            }
+            return Ok(response);
+        }
+
+        if let Some(exceeded) =
+            header_limit_exceeded(req.headers(), &self.config.load().header_limits)
+        {
+            tracing::warn!(uri = %req.uri(), "Rejecting request: {exceeded}");
+            return Ok(error_response(
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                "Request Header Fields Too Large",
+            ));
+        }
+
+        if !self.host_matches_sni(&req) {
+            tracing::warn!(
+                host = ?req.headers().get(header::HOST),
+                server_name = ?req
+                    .extensions()
+                    .get::<TlsSession>()
+                    .and_then(|session| session.server_name.as_deref()),
+                "Host does not match TLS SNI, rejecting as misdirected"
+            );
+            return Ok(error_response(
+                StatusCode::MISDIRECTED_REQUEST,
+                "Misdirected Request",
+            ));
+        }
+
+        // Requests whose body length reads two ways could smuggle another
+        // request past the gateway; the connection is not trusted after one
+        let mut req = req;
+        if let Err(e) = check_framing(req.version(), req.headers_mut()) {
+            tracing::warn!(uri = %req.uri(), "Rejecting request: {e}");
+            crate::metrics::record_malformed_request(e.reason());
+            let mut response = error_response(StatusCode::BAD_REQUEST, "Malformed request");
+            if req.version() <= http::Version::HTTP_11 {
+                response
+                    .headers_mut()
+                    .insert(header::CONNECTION, HeaderValue::from_static("close"));
+            }
+            return Ok(response);
+        }
+
+        // Canonicalize the path so the WAF and route matching see one spelling
+        if let Err(e) = normalize_request_path(&mut req, &self.config.load().path_normalization) {
+            tracing::warn!(uri = %req.uri(), "Rejecting request: {e}");
+            return Ok(error_response(
+                StatusCode::BAD_REQUEST,
+                "Invalid request path",
+            ));
+        }
+
         let gateway = self.current_gateway();
+        let timings = req.extensions().get::<Arc<RequestTimings>>().cloned();
+        let client_ip = request_client_ip(&req);
 
         // WAF Check
-        let req = if gateway.is_waf_enabled() {
+        let waf_host = Self::extract_routing_host(req.headers());
+        let req = if gateway.is_waf_enabled(waf_host.as_deref()) {
+            let _waf_timer = timings.as_ref().map(|t| t.start(Phase::Waf));
+            // Banned clients are turned away before their body is read
+            if let Some(ip) = client_ip
+                && let Err(violation) = gateway.check_ban(ip)
+            {
+                if let Some(timings) = &timings {
+                    timings.record_threat(&violation.threat_type);
+                }
+                self.audit.record(
+                    AuditEvent::waf(
+                        &violation.threat_type,
+                        violation.threat_level,
+                        violation.blocked,
+                    )
+                    .request(req.method(), req.uri(), req.headers())
+                    .client(client_ip)
+                    .detail(violation.description),
+                );
+                return Ok(error_response(
+                    StatusCode::FORBIDDEN,
+                    "Request blocked by WAF",
+                ));
+            }
+
             let (mut parts, body) = req.into_parts();
             // Limit body size for WAF inspection (e.g., 10MB)
             let limit = 10 * 1024 * 1024;
             let bytes = match to_bytes(body, limit).await {
                 Ok(b) => b,
                 Err(_) => {
-                    return Ok(Response::builder()
-                        .status(StatusCode::PAYLOAD_TOO_LARGE)
-                        .body(AxumBody::from("Request body too large"))
-                        .expect("Failed to build payload too large response"));
+                    return Ok(error_response(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        "Request body too large",
+                    ));
                 }
             };
 
-            let client_ip = client_addr.map(|a| a.ip().to_string());
             if let Err(violation) = gateway.check_waf(
                 &parts.uri,
                 &parts.headers,
                 Some(&bytes),
-                client_ip.as_deref(),
+                client_ip.map(|ip| ip.to_string()).as_deref(),
             ) {
+                if let Some(timings) = &timings {
+                    timings.record_threat(&violation.threat_type);
+                }
+                self.audit.record(
+                    AuditEvent::waf(
+                        &violation.threat_type,
+                        violation.threat_level,
+                        violation.blocked,
+                    )
+                    .request(&parts.method, &parts.uri, &parts.headers)
+                    .client(client_ip)
+                    .detail(violation.description.clone()),
+                );
+                if let Some(ip) = client_ip {
+                    gateway.record_offense(ip, Offense::WafViolation);
+                }
                 if violation.blocked {
                     tracing::warn!(
                         uri = %parts.uri,
                         threat_type = ?violation.threat_type,
                         "WAF blocked request"
                     );
-                    return Ok(Response::builder()
-                        .status(StatusCode::FORBIDDEN)
-                        .body(AxumBody::from("Request blocked by WAF"))
-                        .expect("Failed to build WAF forbidden response"));
+                    return Ok(error_response(
+                        StatusCode::FORBIDDEN,
+                        "Request blocked by WAF",
+                    ));
                 } else {
                     // Monitor mode: log but allow request
                     tracing::warn!(
@@ -235,35 +673,152 @@ impl HttpHandler {
             req
         };
 
-        let path = req.uri().path();
+        // `CONNECT` has no path and is routed by its authority (the Host header)
+        let path = match req.uri().path() {
+            "" => "/",
+            path => path,
+        };
+
+        // Built-in endpoints have fixed metric labels
+        let builtin = self.config.load().endpoints.find(path);
+        let admin = path.starts_with("/admin/") && self.config.load().admin.enabled;
+        let builtin_label = match builtin {
+            Some(_) => Some(path),
+            None if admin => Some("/admin/"),
+            None => None,
+        };
+        if let (Some(label), Some(timings)) = (builtin_label, &timings) {
+            timings.set_metrics_path(label);
+        }
 
         // Handle special paths first
-        match path {
-            "/health" => return self.handle_health_check().await,
-            "/metrics" => return self.handle_metrics().await,
-            "/status" => return self.handle_status().await,
-            _ => {}
+        if let Some(endpoint) = builtin {
+            if let Some(rejection) = self.check_endpoint_access(endpoint, &req) {
+                return Ok(rejection);
+            }
+            return self.handle_builtin(endpoint).await;
+        }
+        if admin {
+            return self.handle_admin(req).await;
         }
 
         // Check if there's a matching route in configuration
         let gateway = self.current_gateway();
 
         let route_host = Self::extract_routing_host(req.headers());
-        if let Some((prefix, route_config)) =
-            gateway.find_matching_route(path, route_host.as_deref())
-        {
+        let route_match_timer = timings.as_ref().map(|t| t.start(Phase::RouteMatch));
+        let matched = gateway.find_matching_route(path, route_host.as_deref());
+        drop(route_match_timer);
+        // Requests no route matches go to the default route, if there is one
+        let matched = matched.or_else(|| {
+            let default_route = gateway.default_route();
+            crate::metrics::record_unmatched_request(if default_route.is_some() {
+                "default_route"
+            } else {
+                "not_found"
+            });
+            default_route.map(|route| (DEFAULT_ROUTE_PREFIX.to_string(), route.clone()))
+        });
+        if let Some((prefix, route_config)) = matched {
             tracing::Span::current().record("route.prefix", &prefix);
+            let label = route_label(&prefix, &route_config);
+            if let Some(transfer) = req.extensions().get::<Arc<TransferStats>>() {
+                transfer.set_route(label.clone());
+            }
+            if let Some(timings) = &timings {
+                let (slow_threshold, metrics) = match &route_config {
+                    RouteConfig::Static {
+                        slow_request_threshold_ms,
+                        metrics,
+                        ..
+                    }
+                    | RouteConfig::Proxy {
+                        slow_request_threshold_ms,
+                        metrics,
+                        ..
+                    }
+                    | RouteConfig::LoadBalance {
+                        slow_request_threshold_ms,
+                        metrics,
+                        ..
+                    } => (*slow_request_threshold_ms, metrics.as_ref()),
+                    RouteConfig::Redirect { .. } | RouteConfig::Websocket { .. } => (None, None),
+                };
+                timings.set_metrics_path(metrics_path_label(path, &label, metrics));
+                timings.set_route(label, slow_threshold.map(Duration::from_millis));
+            }
 
-            // Apply route-level rate limiting if configured
-            if let Some(limiter) = gateway
-                .get_rate_limiter(&prefix, route_host.as_deref())
-                .await
-                && let Err(resp) = limiter.check(&req)
+            let (RouteConfig::Static { disable_http3, .. }
+            | RouteConfig::Redirect { disable_http3, .. }
+            | RouteConfig::Proxy { disable_http3, .. }
+            | RouteConfig::LoadBalance { disable_http3, .. }
+            | RouteConfig::Websocket { disable_http3, .. }) = &route_config;
+            if *disable_http3 && req.version() == http::Version::HTTP_3 {
+                // 421 tells the client to retry on another connection (TCP)
+                return Ok(error_response(
+                    StatusCode::MISDIRECTED_REQUEST,
+                    "Route not available over HTTP/3",
+                ));
+            }
+
+            let mut req = req;
+            if let RouteConfig::Proxy {
+                health_endpoint: Some(endpoint),
+                ..
+            }
+            | RouteConfig::LoadBalance {
+                health_endpoint: Some(endpoint),
+                ..
+            } = &route_config
+                && req.uri().path() == route_health_path(&prefix, endpoint)
             {
-                return Ok(*resp);
+                req.extensions_mut().insert(RouteHealthProbe);
+            }
+
+            // Apply the route's rate limits in order; the first rejection
+            // decides, and later limits are not charged. They are kept under
+            // the route's own host, which may be a wildcard.
+            let (RouteConfig::Static { host, .. }
+            | RouteConfig::Redirect { host, .. }
+            | RouteConfig::Proxy { host, .. }
+            | RouteConfig::LoadBalance { host, .. }
+            | RouteConfig::Websocket { host, .. }) = &route_config;
+            if let Some(limits) = gateway.get_rate_limiters(&prefix, host.as_deref()).await {
+                let _rate_limit_timer = timings.as_ref().map(|t| t.start(Phase::RateLimit));
+                let route_label = route_label(&prefix, &route_config);
+                for limit in limits.iter() {
+                    let decision = limit.limiter.check(&req);
+                    crate::metrics::record_rate_limit_decision(
+                        &route_label,
+                        &limit.name,
+                        decision.is_ok(),
+                    );
+                    if let Some(counters) = limit.limiter.key_counters() {
+                        crate::metrics::set_rate_limit_keys(
+                            &route_label,
+                            &limit.name,
+                            counters.keys(),
+                            counters.evictions(),
+                        );
+                    }
+                    if let Err(resp) = decision {
+                        self.audit.record(
+                            AuditEvent::new(
+                                AuditEventKind::RateLimit,
+                                AuditDecision::Blocked,
+                                limit.name.as_str(),
+                                ThreatLevel::Low,
+                            )
+                            .request(req.method(), req.uri(), req.headers())
+                            .client(client_ip)
+                            .route(route_label),
+                        );
+                        return Ok(*resp);
+                    }
+                }
             }
 
-            // Apply configured middlewares
+            // Wrap the route handler in its configured middleware chain
             let middlewares = match &route_config {
                 RouteConfig::Static { middlewares, .. } => middlewares,
                 RouteConfig::Redirect { middlewares, .. } => middlewares,
@@ -271,72 +826,119 @@ impl HttpHandler {
                 RouteConfig::LoadBalance { middlewares, .. } => middlewares,
                 RouteConfig::Websocket { middlewares, .. } => middlewares,
             };
-
-            // Simple middleware processor (currently only supports 'strip_prefix' and 'cors')
-            // In a real implementation, this would be a proper pipeline
-            let mut req = req;
-            for mw in middlewares {
-                match mw.as_str() {
-                    "strip_prefix" => {
-                        let path = req.uri().path();
-                        if let Some(new_path) = path.strip_prefix(&prefix) {
-                            let new_path = if new_path.is_empty() { "/" } else { new_path };
-                            let mut parts = req.uri().clone().into_parts();
-                            parts.path_and_query = Some(
-                                axum::http::uri::PathAndQuery::from_str(new_path).unwrap_or_else(
-                                    |_| axum::http::uri::PathAndQuery::from_static("/"),
-                                ),
-                            );
-                            if let Ok(new_uri) = axum::http::Uri::from_parts(parts) {
-                                *req.uri_mut() = new_uri;
-                            }
-                        }
-                    }
-                    "cors" => {
-                        // CORS is usually a response header, but we can't easily modify response here
-                        // without wrapping the handler. For now, we'll just log.
-                        // To implement CORS properly, we need to wrap the response.
-                        // This requires refactoring handle_request to be composable.
-                        // For Phase 2, we'll skip complex response middlewares here.
-                    }
-                    _ => {}
-                }
+            if middlewares.is_empty() {
+                return self
+                    .dispatch_route(req, &prefix, route_config, client_addr)
+                    .await;
             }
 
-            match route_config {
-                RouteConfig::Static { .. } => {
-                    return self.handle_static_file(req, &prefix).await;
+            let names = middlewares.clone();
+            let handler = self.clone();
+            let route_prefix = prefix.clone();
+            let inner = tower::service_fn(move |req: Request<AxumBody>| {
+                let handler = handler.clone();
+                let route_prefix = route_prefix.clone();
+                let route_config = route_config.clone();
+                async move {
+                    let response = handler
+                        .dispatch_route(req, &route_prefix, route_config, client_addr)
+                        .await
+                        .unwrap_or_else(|e| {
+                            tracing::error!(error = %e, "route handler failed");
+                            error_response(
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                "Internal Server Error",
+                            )
+                        });
+                    Ok::<_, Infallible>(response)
                 }
-                RouteConfig::Proxy { target, .. } => {
-                    tracing::Span::current().record("backend.url", target);
-                    return self.handle_proxy_request(req, client_addr).await;
-                }
-                RouteConfig::LoadBalance { targets, .. } => {
-                    let target_list = targets.join(",");
-                    tracing::Span::current().record("backend.targets", &target_list);
-                    return self.handle_proxy_request(req, client_addr).await;
-                }
-                RouteConfig::Websocket { .. } => {
-                    return self.handle_websocket(req).await;
-                }
-                RouteConfig::Redirect {
-                    target,
-                    status_code,
-                    ..
-                } => {
-                    return self.handle_redirect(&target, &status_code).await;
-                }
-            }
+            });
+            let config = self.config.load();
+            let ctx = MiddlewareContext {
+                route_prefix: &prefix,
+                config: &config,
+                audit: &self.audit,
+                argument: None,
+            };
+            let chain =
+                self.middleware_registry
+                    .apply(&names, BoxCloneSyncService::new(inner), &ctx)?;
+            return chain.oneshot(req).await.map_err(|never| match never {});
         } else {
             // Downgraded from warn -> info: a 404 for an unmapped path is normal (e.g. hot_reload pre-route check)
             tracing::info!("no route match");
         }
 
         // If no route matches, return 404
-        Response::builder()
-            .status(StatusCode::NOT_FOUND)
-            .body(AxumBody::from("Route not found"))
-            .wrap_err("Failed to build 404 response")
+        Ok(error_response(StatusCode::NOT_FOUND, "Route not found"))
+    }
+
+    /// Hand a matched request to the handler for its route type.
+    async fn dispatch_route(
+        &self,
+        req: Request<AxumBody>,
+        prefix: &str,
+        route_config: RouteConfig,
+        client_addr: Option<SocketAddr>,
+    ) -> Result<Response<AxumBody>, eyre::Error> {
+        if req.extensions().get::<RouteHealthProbe>().is_some() {
+            let (RouteConfig::Static { host, .. }
+            | RouteConfig::Redirect { host, .. }
+            | RouteConfig::Proxy { host, .. }
+            | RouteConfig::LoadBalance { host, .. }
+            | RouteConfig::Websocket { host, .. }) = &route_config;
+            return self
+                .handle_route_health(prefix, &route_config, host.as_deref())
+                .await;
+        }
+
+        if let RouteConfig::Proxy {
+            tunnel: Some(tunnel),
+            ..
+        }
+        | RouteConfig::LoadBalance {
+            tunnel: Some(tunnel),
+            ..
+        } = &route_config
+            && let Some(request) = requested_tunnel(tunnel, &req)
+        {
+            return self
+                .handle_tunnel(req, client_addr, prefix, &route_config, request)
+                .await;
+        }
+
+        match route_config {
+            RouteConfig::Static { .. } => self.handle_static_file(req, prefix).await,
+            RouteConfig::Proxy {
+                target,
+                host,
+                dedupe,
+                coalesce,
+                ..
+            } => {
+                tracing::Span::current().record("backend.url", target);
+                self.handle_deduplicated_proxy(req, client_addr, prefix, host, dedupe, coalesce)
+                    .await
+            }
+            RouteConfig::LoadBalance {
+                targets,
+                host,
+                dedupe,
+                coalesce,
+                ..
+            } => {
+                let target_list = targets.join(",");
+                tracing::Span::current().record("backend.targets", &target_list);
+                self.handle_deduplicated_proxy(req, client_addr, prefix, host, dedupe, coalesce)
+                    .await
+            }
+            RouteConfig::Websocket { .. } => self.handle_websocket(req).await,
+            RouteConfig::Redirect {
+                target,
+                status_code,
+                ..
+            } => self.handle_redirect(&target, &status_code).await,
+        }
     }
 
     /// Build JSON health response summarizing backend availability.
@@ -374,15 +976,121 @@ impl HttpHandler {
         Ok(response)
     }
 
+    /// Build JSON health for one route's backend group (its `health_endpoint`).
+    async fn handle_route_health(
+        &self,
+        prefix: &str,
+        route_config: &RouteConfig,
+        host: Option<&str>,
+    ) -> Result<Response<AxumBody>, eyre::Error> {
+        let gateway = self.current_gateway();
+        let (stable_targets, locale) = match route_config {
+            RouteConfig::Proxy { target, locale, .. } => (vec![target.clone()], locale.as_ref()),
+            RouteConfig::LoadBalance {
+                targets, locale, ..
+            } => (targets.clone(), locale.as_ref()),
+            _ => (Vec::new(), None),
+        };
+        let canary = gateway.get_canary(prefix, host);
+        let canary_targets = canary
+            .as_ref()
+            .map(|state| state.targets().to_vec())
+            .unwrap_or_default();
+
+        let mut backends = Vec::new();
+        let mut healthy_stable = 0;
+        let mut healthy_total = 0;
+        let mut locale_groups: Vec<_> = locale
+            .iter()
+            .flat_map(|l| l.groups.iter())
+            .flat_map(|(language, targets)| {
+                targets
+                    .iter()
+                    .map(move |target| (target, format!("locale:{language}")))
+            })
+            .collect();
+        locale_groups.sort();
+        let groups = stable_targets
+            .iter()
+            .map(|target| (target, "stable".to_string()))
+            .chain(
+                canary_targets
+                    .iter()
+                    .map(|target| (target, "canary".to_string())),
+            )
+            .chain(locale_groups);
+        for (target, group) in groups {
+            let status = gateway.get_backend_health_status(target).await;
+            let (consecutive_failures, active_connections) = gateway
+                .backend_health()
+                .get_async(target)
+                .await
+                .map(|entry| {
+                    (
+                        entry.get().consecutive_failures(),
+                        entry.get().active_connections(),
+                    )
+                })
+                .unwrap_or_default();
+            if status == HealthStatus::Healthy {
+                healthy_total += 1;
+                if group == "stable" {
+                    healthy_stable += 1;
+                }
+            }
+            backends.push(serde_json::json!({
+                "url": target,
+                "group": group,
+                "status": status,
+                "consecutive_failures": consecutive_failures,
+                "active_connections": active_connections,
+            }));
+        }
+
+        // Stable targets serve all traffic once a canary is gone, so they decide health
+        let status = if healthy_stable > 0 {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        let mut health_data = serde_json::json!({
+            "route": prefix,
+            "status": if status == StatusCode::OK { "healthy" } else { "unhealthy" },
+            "backends": {
+                "healthy": healthy_total,
+                "total": backends.len(),
+                "targets": backends,
+            },
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        });
+        if let Some(state) = canary {
+            health_data["canary"] = serde_json::json!({
+                "weight": state.effective_weight(),
+                "rolled_back": state.is_rolled_back(),
+            });
+        }
+
+        Response::builder()
+            .status(status)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::CACHE_CONTROL, "no-store")
+            .body(AxumBody::from(health_data.to_string()))
+            .wrap_err("Failed to build route health response")
+    }
+
     /// Render a minimal Prometheus exposition format text body.
     async fn handle_metrics(&self) -> Result<Response<AxumBody>, eyre::Error> {
         // Minimal Prometheus-compatible text exposition for built-in gauges
-        use crate::metrics::{
-            AXON_ACTIVE_CONNECTIONS, AXON_ACTIVE_REQUESTS, AXON_BACKEND_REQUEST_DURATION_SECONDS,
-            AXON_BACKEND_REQUESTS_TOTAL, AXON_REQUEST_DURATION_SECONDS, AXON_REQUESTS_TOTAL,
-            get_current_metrics, init_metrics,
+        use crate::{
+            adapters::metrics_exporters::{has_prometheus_family, prometheus_exposition},
+            metrics::{
+                AXON_ACTIVE_CONNECTIONS, AXON_ACTIVE_REQUESTS,
+                AXON_BACKEND_REQUEST_DURATION_SECONDS, AXON_BACKEND_REQUESTS_TOTAL,
+                AXON_REQUEST_DURATION_SECONDS, AXON_REQUESTS_TOTAL, backend_limit_exposition,
+                get_current_metrics, init_metrics, rate_limit_exposition, telemetry_exposition,
+            },
         };
-        let _ = init_metrics().await; // idempotent
+        let _ = init_metrics(&self.config.load().metrics).await; // idempotent
 
         let mut out = String::new();
         let active_conns = self.connection_tracker.active_connection_count();
@@ -401,37 +1109,72 @@ impl HttpHandler {
         // Provide placeholder exposition lines for counters & histograms we describe elsewhere
         // so that automated checks can validate the metric families exist even without a
         // dedicated recorder installed. Values default to 0 until a metrics recorder is added.
-        out.push_str(&format!(
-            "# HELP {AXON_REQUESTS_TOTAL} Total number of HTTP requests processed by the gateway.\n"
-        ));
-        out.push_str(&format!("# TYPE {AXON_REQUESTS_TOTAL} counter\n"));
-        out.push_str(&format!("{AXON_REQUESTS_TOTAL} 0\n"));
+        // With the `prometheus` exporter these families come from its latest
+        // collection instead.
+        if !has_prometheus_family(AXON_REQUESTS_TOTAL) {
+            out.push_str(&format!(
+                "# HELP {AXON_REQUESTS_TOTAL} Total number of HTTP requests processed by the gateway.\n"
+            ));
+            out.push_str(&format!("# TYPE {AXON_REQUESTS_TOTAL} counter\n"));
+            out.push_str(&format!("{AXON_REQUESTS_TOTAL} 0\n"));
+        }
 
-        out.push_str(&format!(
-            "# HELP {AXON_REQUEST_DURATION_SECONDS} Latency of HTTP requests processed by the gateway.\n"
-        ));
-        out.push_str(&format!(
-            "# TYPE {AXON_REQUEST_DURATION_SECONDS} histogram\n"
-        ));
-        out.push_str(&format!("{AXON_REQUEST_DURATION_SECONDS}_count 0\n"));
-        out.push_str(&format!("{AXON_REQUEST_DURATION_SECONDS}_sum 0\n"));
+        if !has_prometheus_family(AXON_REQUEST_DURATION_SECONDS) {
+            out.push_str(&format!(
+                "# HELP {AXON_REQUEST_DURATION_SECONDS} Latency of HTTP requests processed by the gateway.\n"
+            ));
+            out.push_str(&format!(
+                "# TYPE {AXON_REQUEST_DURATION_SECONDS} histogram\n"
+            ));
+            out.push_str(&format!("{AXON_REQUEST_DURATION_SECONDS}_count 0\n"));
+            out.push_str(&format!("{AXON_REQUEST_DURATION_SECONDS}_sum 0\n"));
+        }
 
-        out.push_str(&format!(
-            "# HELP {AXON_BACKEND_REQUESTS_TOTAL} Total number of HTTP requests forwarded to backend services.\n"
-        ));
-        out.push_str(&format!("# TYPE {AXON_BACKEND_REQUESTS_TOTAL} counter\n"));
-        out.push_str(&format!("{AXON_BACKEND_REQUESTS_TOTAL} 0\n"));
+        if !has_prometheus_family(AXON_BACKEND_REQUESTS_TOTAL) {
+            out.push_str(&format!(
+                "# HELP {AXON_BACKEND_REQUESTS_TOTAL} Total number of HTTP requests forwarded to backend services.\n"
+            ));
+            out.push_str(&format!("# TYPE {AXON_BACKEND_REQUESTS_TOTAL} counter\n"));
+            out.push_str(&format!("{AXON_BACKEND_REQUESTS_TOTAL} 0\n"));
+        }
 
-        out.push_str(&format!(
-            "# HELP {AXON_BACKEND_REQUEST_DURATION_SECONDS} Latency of HTTP requests forwarded to backend services.\n"
-        ));
-        out.push_str(&format!(
-            "# TYPE {AXON_BACKEND_REQUEST_DURATION_SECONDS} histogram\n"
+        if !has_prometheus_family(AXON_BACKEND_REQUEST_DURATION_SECONDS) {
+            out.push_str(&format!(
+                "# HELP {AXON_BACKEND_REQUEST_DURATION_SECONDS} Latency of HTTP requests forwarded to backend services.\n"
+            ));
+            out.push_str(&format!(
+                "# TYPE {AXON_BACKEND_REQUEST_DURATION_SECONDS} histogram\n"
+            ));
+            out.push_str(&format!(
+                "{AXON_BACKEND_REQUEST_DURATION_SECONDS}_count 0\n"
+            ));
+            out.push_str(&format!("{AXON_BACKEND_REQUEST_DURATION_SECONDS}_sum 0\n"));
+        }
+
+        out.push_str(&rate_limit_exposition());
+        out.push_str(&backend_limit_exposition(
+            &self.current_gateway().backend_limiter_stats(),
         ));
-        out.push_str(&format!(
-            "{AXON_BACKEND_REQUEST_DURATION_SECONDS}_count 0\n"
+        out.push_str(&telemetry_exposition());
+        out.push_str(&crate::metrics::audit_exposition());
+        out.push_str(&crate::metrics::usage_exposition());
+        out.push_str(&crate::metrics::events_exposition());
+        out.push_str(&crate::metrics::auto_ban_exposition());
+        out.push_str(&crate::metrics::http3_exposition());
+        out.push_str(&crate::metrics::route_bytes_exposition());
+        out.push_str(&crate::metrics::slow_request_exposition());
+        out.push_str(&crate::metrics::retry_exposition());
+        out.push_str(&crate::metrics::failover_exposition());
+        out.push_str(&crate::metrics::client_disconnect_exposition());
+        out.push_str(&crate::metrics::malformed_request_exposition());
+        out.push_str(&crate::metrics::schema_validation_failure_exposition());
+        out.push_str(&crate::metrics::tunnel_exposition());
+        out.push_str(&crate::metrics::unmatched_exposition());
+        out.push_str(&crate::metrics::build_info_exposition(
+            self.current_gateway().generation(),
         ));
-        out.push_str(&format!("{AXON_BACKEND_REQUEST_DURATION_SECONDS}_sum 0\n"));
+        let collected = prometheus_exposition(&out);
+        out.push_str(&collected);
 
         for (k, v) in get_current_metrics() {
             let metric_name = k.replace(['/', ':'], "_");
@@ -447,42 +1190,407 @@ impl HttpHandler {
         Ok(response)
     }
 
-    /// Return runtime status (connections, configuration summary, counts).
-    async fn handle_status(&self) -> Result<Response<AxumBody>, eyre::Error> {
-        let stats = self.connection_tracker.get_stats().await;
-        let config = self.config.load_full();
-        let gateway = self.current_gateway();
+    /// Serve the admin API: `GET /admin/metrics` returns per route/backend
+    /// counters and `POST /admin/metrics/reset[?route=<label>]` clears their
+    /// recent windows. `GET`, `PUT` and `DELETE /admin/steering` read, push
+    /// and clear backend steering weights. `GET`, `PUT` and `DELETE
+    /// /admin/logging` read, replace and restore the log filter. `GET
+    /// /admin/taps` lists taps, and `GET`, `PUT` and `DELETE
+    /// /admin/tap?route=<label>` read, start and stop one.
+    /// `/admin/endpoints/<name>` serves built-in endpoints moved here.
+    async fn handle_admin(
+        &self,
+        req: Request<AxumBody>,
+    ) -> Result<Response<AxumBody>, eyre::Error> {
+        let config = self.config.load();
+        let authorized = config
+            .admin
+            .token
+            .as_deref()
+            .is_some_and(|token| bearer_token_matches(req.headers(), token));
+        if !authorized {
+            self.audit.record(
+                AuditEvent::new(
+                    AuditEventKind::AdminAuth,
+                    AuditDecision::Blocked,
+                    "admin_token",
+                    ThreatLevel::High,
+                )
+                .request(req.method(), req.uri(), req.headers())
+                .client(request_client_ip(&req)),
+            );
+            return Ok(error_response(
+                StatusCode::UNAUTHORIZED,
+                "Missing or invalid admin token",
+            ));
+        }
 
-        let status_data = serde_json::json!({
-            "service": "Axon API Gateway",
-            "version": env!("CARGO_PKG_VERSION"),
-            "uptime": "N/A", // TODO: Track actual uptime
-            "connections": {
-                "total": stats.total_connections,
-                "active": stats.active_connections,
-                "idle": stats.idle_connections,
-                "active_requests": stats.total_active_requests,
-                "oldest_connection_age_secs": stats.oldest_connection_age.as_secs()
-            },
-            "backends": {
-                "total": gateway.backend_count(),
-                "healthy": gateway.healthy_backend_count().await
-            },
-            "configuration": {
-                "listen_addr": &config.listen_addr,
-                "health_check_enabled": config.health_check.enabled,
-                "tls_enabled": config.tls.is_some(),
-                "protocols": {
-                    "http2_enabled": config.protocols.http2_enabled,
-                    "http3_enabled": config.protocols.http3_enabled,
-                    "websocket_enabled": config.protocols.websocket_enabled
-                }
-            },
-            "timestamp": chrono::Utc::now().to_rfc3339()
-        });
+        if let Some(endpoint) = config.endpoints.find_admin(req.uri().path()) {
+            if let Some(rejection) = self.check_endpoint_access(endpoint, &req) {
+                return Ok(rejection);
+            }
+            return self.handle_builtin(endpoint).await;
+        }
 
-        let response = Response::builder()
-            .status(StatusCode::OK)
+        let body = match (req.method(), req.uri().path()) {
+            (&http::Method::GET, "/admin/metrics") => serde_json::json!({
+                "routes": crate::metrics::traffic_snapshot(),
+                "rate_limits": crate::metrics::rate_limit_stats()
+                    .into_iter()
+                    .map(|((route, limiter), stats)| serde_json::json!({
+                        "route": route,
+                        "limiter": limiter,
+                        "allowed": stats.allowed,
+                        "rejected": stats.rejected,
+                    }))
+                    .collect::<Vec<_>>(),
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            }),
+            (&http::Method::POST, "/admin/metrics/reset") => {
+                let route = req.uri().query().and_then(|query| {
+                    url::form_urlencoded::parse(query.as_bytes())
+                        .find(|(key, _)| key == "route")
+                        .map(|(_, value)| value.into_owned())
+                });
+                let reset = crate::metrics::reset_traffic_windows(route.as_deref());
+                serde_json::json!({ "route": route, "windows_reset": reset })
+            }
+            (&http::Method::GET, "/admin/steering") => {
+                steering_json(&self.current_gateway().steering().snapshot())
+            }
+            (&http::Method::PUT, "/admin/steering") => {
+                let update = match to_bytes(req.into_body(), 1024 * 1024)
+                    .await
+                    .ok()
+                    .and_then(|body| serde_json::from_slice::<SteeringUpdate>(&body).ok())
+                {
+                    Some(update) => update,
+                    None => {
+                        return Ok(error_response(
+                            StatusCode::BAD_REQUEST,
+                            "Expected a JSON body with a \"weights\" object",
+                        ));
+                    }
+                };
+                if update.ttl_secs == 0
+                    || update
+                        .weights
+                        .values()
+                        .any(|weight| !weight.is_finite() || *weight < 0.0)
+                {
+                    return Ok(error_response(
+                        StatusCode::BAD_REQUEST,
+                        "Weights must be non-negative numbers and ttl_secs greater than 0",
+                    ));
+                }
+
+                let known = GatewayService::collect_backends(&config);
+                let steering = self.current_gateway().steering();
+                if update.replace {
+                    steering.clear();
+                }
+                let mut ignored = Vec::new();
+                for (backend, weight) in &update.weights {
+                    if known.contains(backend) {
+                        steering.set(backend, *weight, Duration::from_secs(update.ttl_secs));
+                    } else {
+                        ignored.push(backend.clone());
+                    }
+                }
+                ignored.sort();
+                let mut body = steering_json(&steering.snapshot());
+                body["ignored"] = serde_json::json!(ignored);
+                body
+            }
+            (&http::Method::DELETE, "/admin/steering") => {
+                let steering = self.current_gateway().steering();
+                steering.clear();
+                steering_json(&[])
+            }
+            (&http::Method::GET, "/admin/logging") => match log_filter_json(&config.tracing) {
+                Some(body) => body,
+                None => return Ok(logging_not_reloadable()),
+            },
+            (&http::Method::PUT, "/admin/logging") => {
+                let update = match to_bytes(req.into_body(), 64 * 1024)
+                    .await
+                    .ok()
+                    .and_then(|body| serde_json::from_slice::<LogFilterUpdate>(&body).ok())
+                {
+                    Some(update) => update,
+                    None => {
+                        return Ok(error_response(
+                            StatusCode::BAD_REQUEST,
+                            "Expected a JSON body with a \"filter\" string",
+                        ));
+                    }
+                };
+                if tracing_setup::log_filter().is_none() {
+                    return Ok(logging_not_reloadable());
+                }
+                if let Err(e) = tracing_setup::set_log_filter(&update.filter) {
+                    return Ok(error_response(StatusCode::BAD_REQUEST, format!("{e:#}")));
+                }
+                tracing::info!(filter = %update.filter, "Log filter changed via admin API");
+                log_filter_json(&config.tracing).unwrap_or_default()
+            }
+            (&http::Method::DELETE, "/admin/logging") => {
+                if tracing_setup::log_filter().is_none() {
+                    return Ok(logging_not_reloadable());
+                }
+                let configured = tracing_setup::configured_filter(&config.tracing);
+                tracing_setup::set_log_filter(&configured)?;
+                tracing::info!(filter = %configured, "Log filter restored via admin API");
+                log_filter_json(&config.tracing).unwrap_or_default()
+            }
+            (&http::Method::GET, "/admin/taps") => serde_json::json!({
+                "taps": self
+                    .current_gateway()
+                    .taps()
+                    .list()
+                    .into_iter()
+                    .map(|(route, tap)| serde_json::json!({
+                        "route": route,
+                        "source": tap.source(),
+                        "capacity": tap.config().capacity,
+                        "max_body_bytes": tap.config().max_body_bytes,
+                        "recorded": tap.len(),
+                    }))
+                    .collect::<Vec<_>>(),
+            }),
+            (&http::Method::GET | &http::Method::PUT | &http::Method::DELETE, "/admin/tap") => {
+                let params: Vec<(String, String)> = req
+                    .uri()
+                    .query()
+                    .map(|query| {
+                        url::form_urlencoded::parse(query.as_bytes())
+                            .into_owned()
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let query = |name: &str| {
+                    params
+                        .iter()
+                        .find(|(key, _)| key == name)
+                        .map(|(_, value)| value.clone())
+                };
+                let Some(route) = query("route") else {
+                    return Ok(error_response(
+                        StatusCode::BAD_REQUEST,
+                        "Missing ?route=<route label>",
+                    ));
+                };
+                let since = query("since").and_then(|since| since.parse::<u64>().ok());
+                let taps = self.current_gateway().taps();
+                let method = req.method().clone();
+                match method {
+                    http::Method::GET => {
+                        let Some(tap) = taps.get(&route) else {
+                            return Ok(error_response(
+                                StatusCode::NOT_FOUND,
+                                "Route is not tapped",
+                            ));
+                        };
+                        serde_json::json!({
+                            "route": route,
+                            "source": tap.source(),
+                            "exchanges": tap
+                                .exchanges()
+                                .into_iter()
+                                .filter(|exchange| since.is_none_or(|since| exchange.id > since))
+                                .collect::<Vec<_>>(),
+                        })
+                    }
+                    http::Method::PUT => {
+                        if !tappable_routes(&config).contains(&route) {
+                            return Ok(error_response(
+                                StatusCode::NOT_FOUND,
+                                "No proxy or load balance route has this label",
+                            ));
+                        }
+                        let body = to_bytes(req.into_body(), 64 * 1024)
+                            .await
+                            .unwrap_or_default();
+                        let tap_config = if body.iter().all(u8::is_ascii_whitespace) {
+                            TapConfig::default()
+                        } else {
+                            match serde_json::from_slice::<TapConfig>(&body) {
+                                Ok(tap_config)
+                                    if (1..=MAX_TAP_CAPACITY).contains(&tap_config.capacity)
+                                        && tap_config.max_body_bytes <= MAX_TAP_BODY_BYTES =>
+                                {
+                                    tap_config
+                                }
+                                _ => {
+                                    return Ok(error_response(
+                                        StatusCode::BAD_REQUEST,
+                                        format!(
+                                            "Expected tap settings with a capacity of 1 to \
+                                             {MAX_TAP_CAPACITY} and max_body_bytes up to \
+                                             {MAX_TAP_BODY_BYTES}"
+                                        ),
+                                    ));
+                                }
+                            }
+                        };
+                        tracing::info!(route = %route, "Tap started via admin API");
+                        taps.start(&route, tap_config);
+                        serde_json::json!({ "route": route, "started": true })
+                    }
+                    _ => {
+                        if !taps.stop(&route) {
+                            return Ok(error_response(
+                                StatusCode::NOT_FOUND,
+                                "Route is not tapped",
+                            ));
+                        }
+                        tracing::info!(route = %route, "Tap stopped via admin API");
+                        serde_json::json!({ "route": route, "stopped": true })
+                    }
+                }
+            }
+            (
+                _,
+                "/admin/metrics"
+                | "/admin/metrics/reset"
+                | "/admin/steering"
+                | "/admin/logging"
+                | "/admin/taps"
+                | "/admin/tap",
+            ) => {
+                return Ok(error_response(
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    "Method not allowed",
+                ));
+            }
+            _ => {
+                return Ok(error_response(
+                    StatusCode::NOT_FOUND,
+                    "Unknown admin endpoint",
+                ));
+            }
+        };
+
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::CACHE_CONTROL, "no-store")
+            .body(AxumBody::from(body.to_string()))
+            .wrap_err("Failed to build admin response")
+    }
+
+    /// Serve a built-in endpoint; see [`Self::check_endpoint_access`] for
+    /// its access rules.
+    async fn handle_builtin(
+        &self,
+        endpoint: BuiltinEndpoint,
+    ) -> Result<Response<AxumBody>, eyre::Error> {
+        match endpoint {
+            BuiltinEndpoint::Health => self.handle_health_check().await,
+            BuiltinEndpoint::Metrics => self.handle_metrics().await,
+            BuiltinEndpoint::Status => self.handle_status().await,
+        }
+    }
+
+    /// The response refusing `req` access to `endpoint`, if it is refused.
+    /// The allowlist is checked against the client address behind any
+    /// trusted proxies.
+    fn check_endpoint_access<B>(
+        &self,
+        endpoint: BuiltinEndpoint,
+        req: &Request<B>,
+    ) -> Option<Response<AxumBody>> {
+        let config = self.config.load();
+        let rules = config.endpoints.get(endpoint);
+        let client_ip = request_client_ip(req);
+        let refuse = |kind, rule, status, message| {
+            self.audit.record(
+                AuditEvent::new(kind, AuditDecision::Blocked, rule, ThreatLevel::Medium)
+                    .request(req.method(), req.uri(), req.headers())
+                    .client(client_ip),
+            );
+            Some(error_response(status, message))
+        };
+
+        if !rules.allow.is_empty() {
+            let allowed = client_ip.is_some_and(|ip| {
+                rules
+                    .allow
+                    .iter()
+                    .filter_map(|entry| IpNetwork::parse(entry).ok())
+                    .any(|network| network.contains(ip))
+            });
+            if !allowed {
+                return refuse(
+                    AuditEventKind::IpFilter,
+                    "endpoint_allow",
+                    StatusCode::FORBIDDEN,
+                    "Forbidden",
+                );
+            }
+        }
+        if let Some(token) = &rules.token
+            && !bearer_token_matches(req.headers(), token)
+        {
+            return refuse(
+                AuditEventKind::Auth,
+                "endpoint_token",
+                StatusCode::UNAUTHORIZED,
+                "Missing or invalid token",
+            );
+        }
+        None
+    }
+
+    /// Return runtime status (connections, configuration summary, counts).
+    async fn handle_status(&self) -> Result<Response<AxumBody>, eyre::Error> {
+        let stats = self.connection_tracker.get_stats().await;
+        let config = self.config.load_full();
+        let gateway = self.current_gateway();
+
+        let status_data = serde_json::json!({
+            "service": "Axon API Gateway",
+            "version": build_info::VERSION,
+            "build": {
+                "commit": build_info::GIT_COMMIT,
+                "timestamp": build_info::build_timestamp(),
+                "features": build_info::features(),
+            },
+            "uptime_secs": build_info::uptime().as_secs(),
+            "started_at": build_info::started_at(),
+            "config_generation": gateway.generation(),
+            "waf_rules": gateway.waf_rule_set().map(|rule_set| serde_json::json!({
+                "path": &rule_set.path,
+                "version": rule_set.version(),
+                "checksum": &rule_set.checksum,
+                "loaded_at": rule_set.loaded_at.to_rfc3339(),
+            })),
+            "connections": {
+                "total": stats.total_connections,
+                "active": stats.active_connections,
+                "idle": stats.idle_connections,
+                "active_requests": stats.total_active_requests,
+                "oldest_connection_age_secs": stats.oldest_connection_age.as_secs()
+            },
+            "backends": {
+                "total": gateway.backend_count(),
+                "healthy": gateway.healthy_backend_count().await
+            },
+            "configuration": {
+                "listen_addr": &config.listen_addr,
+                "health_check_enabled": config.health_check.enabled,
+                "tls_enabled": config.tls.is_some(),
+                "protocols": {
+                    "http2_enabled": config.protocols.http2_enabled,
+                    "http3_enabled": config.protocols.http3_enabled,
+                    "websocket_enabled": config.protocols.websocket_enabled
+                }
+            },
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        });
+
+        let response = Response::builder()
+            .status(StatusCode::OK)
             .header(header::CONTENT_TYPE, "application/json")
             .body(AxumBody::from(status_data.to_string()))
             .wrap_err("Failed to build status response")?;
@@ -500,37 +1608,78 @@ impl HttpHandler {
 
         // Find the matching static route
         let gateway = self.current_gateway();
-        if let Some((_, RouteConfig::Static { root, .. })) =
-            self.find_matching_route_for_request(&gateway, &path, req.headers())
+        if let Some((
+            _,
+            RouteConfig::Static {
+                root,
+                index_file,
+                spa_fallback,
+                not_found_page,
+                directory_listing,
+                listing_format,
+                cache_control,
+                precompressed,
+                etag,
+                ..
+            },
+        )) = self.find_matching_route_for_request(&gateway, &path, req.headers())
         {
+            // Route settings override the global static_files defaults
+            let config = self.config.load();
+            let defaults = config.static_files.as_ref();
+            let options = StaticServeOptions {
+                index_file: index_file.or_else(|| defaults.and_then(|d| d.index_file.clone())),
+                spa_fallback,
+                not_found_page,
+                directory_listing: directory_listing
+                    .or(defaults.map(|d| d.directory_listing))
+                    .unwrap_or(false),
+                listing_format: listing_format
+                    .or(defaults.map(|d| d.listing_format))
+                    .unwrap_or_default(),
+                cache_control: cache_control
+                    .or_else(|| defaults.and_then(|d| d.cache_control.clone())),
+                precompressed: precompressed
+                    .or(defaults.map(|d| d.precompressed))
+                    .unwrap_or(false),
+                etag: etag.or(defaults.map(|d| d.etag)).unwrap_or_default(),
+                content_types: config.content_types.extensions.clone(),
+            };
+
             // Extract the file path by removing the route prefix
             let file_path = path.strip_prefix(route_prefix).unwrap_or(&path);
 
             // Security check: prevent path traversal
             if file_path.contains("..") {
-                return Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(AxumBody::from("Invalid file path"))
-                    .wrap_err("Failed to build error response");
+                return Ok(error_response(StatusCode::BAD_REQUEST, "Invalid file path"));
             }
 
-            match self.file_system.serve_file(&root, file_path, req).await {
-                Ok(response) => return Ok(response),
+            match self
+                .file_system
+                .serve_file(&root, file_path, req, &options)
+                .await
+            {
+                Ok(mut response) => {
+                    if config.content_types.nosniff {
+                        set_nosniff(response.headers_mut());
+                    }
+                    return Ok(response);
+                }
                 Err(e) => {
                     tracing::warn!(error = %e, path = file_path, "static file not found");
-                    return Response::builder()
-                        .status(StatusCode::NOT_FOUND)
-                        .body(AxumBody::from("File or directory not found"))
-                        .wrap_err("Failed to build 404 response");
+                    return Ok(error_response(
+                        StatusCode::NOT_FOUND,
+                        "File or directory not found",
+                    ));
                 }
             }
         }
 
         // If no static route found, return 404
-        Response::builder()
-            .status(StatusCode::NOT_FOUND)
-            .body(AxumBody::from("Static route not found"))
-            .wrap_err("Failed to build 404 response")
+        Ok(error_response(
+            StatusCode::NOT_FOUND,
+            "Static route not found",
+        ))
     }
 
     /// Handle a WebSocket route: perform upgrade, connect to backend (ws/wss), and shuttle frames both ways.
@@ -543,7 +1692,9 @@ impl HttpHandler {
             CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_PROTOCOL, UPGRADE,
         };
         use sha1::Digest;
-        use tokio_tungstenite::tungstenite::protocol::{Message, Role};
+        use tokio_tungstenite::tungstenite::protocol::{
+            CloseFrame, Message, Role, frame::coding::CloseCode,
+        };
 
         // Basic validation
         if req
@@ -553,10 +1704,10 @@ impl HttpHandler {
             .map(|v| v.to_ascii_lowercase())
             != Some("websocket".to_string())
         {
-            return Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body(AxumBody::from("Missing or invalid Upgrade: websocket"))
-                .wrap_err("Bad WS upgrade request");
+            return Ok(error_response(
+                StatusCode::BAD_REQUEST,
+                "Missing or invalid Upgrade: websocket",
+            ));
         }
 
         // Extract route & config
@@ -572,6 +1723,8 @@ impl HttpHandler {
             max_frame_size,
             max_message_size,
             idle_timeout_secs,
+            max_session_secs,
+            session_close,
             subprotocols,
         ) = match route_config {
             RouteConfig::Websocket {
@@ -580,6 +1733,9 @@ impl HttpHandler {
                 max_frame_size,
                 max_message_size,
                 idle_timeout_secs,
+                max_session_secs,
+                session_close_code,
+                session_close_reason,
                 subprotocols,
                 ..
             } => (
@@ -588,11 +1744,22 @@ impl HttpHandler {
                 max_frame_size,
                 max_message_size,
                 idle_timeout_secs,
+                max_session_secs,
+                CloseFrame {
+                    code: CloseCode::from(session_close_code.unwrap_or(1001)),
+                    reason: session_close_reason
+                        .unwrap_or_else(|| "session expired".to_string())
+                        .into(),
+                },
                 subprotocols,
             ),
             _ => return Err(eyre::eyre!("Route not websocket")),
         };
 
+        // Sessions end early if a reload removes the backend and its policy says so
+        let removal = gateway.backend_cancellation(&target);
+        drop(gateway);
+
         // Build backend URL
         let remaining_path = path.strip_prefix(&route_prefix).unwrap_or(&path);
         let rewritten_path = if let Some(rewrite) = path_rewrite.as_ref() {
@@ -683,7 +1850,7 @@ impl HttpHandler {
                     return;
                 }
             };
-            let upgraded = hyper_util::rt::TokioIo::new(upgraded);
+            let upgraded = TokioIo::new(upgraded);
             let ws_cfg = if max_message_size.is_some() || max_frame_size.is_some() {
                 let mut c = tokio_tungstenite::tungstenite::protocol::WebSocketConfig::default();
                 if let Some(m) = max_message_size {
@@ -799,13 +1966,54 @@ impl HttpHandler {
                 let _ = c_tx.send(Message::Close(None)).await;
             };
 
-            if let Some(timeout) = idle_timeout {
-                tokio::select! {
-                    _ = tokio::time::timeout(timeout, client_to_backend) => {},
-                    _ = tokio::time::timeout(timeout, backend_to_client) => {},
+            let proxied = async {
+                if let Some(timeout) = idle_timeout {
+                    tokio::select! {
+                        _ = tokio::time::timeout(timeout, client_to_backend) => {},
+                        _ = tokio::time::timeout(timeout, backend_to_client) => {},
+                    }
+                } else {
+                    tokio::select! { _ = client_to_backend => {}, _ = backend_to_client => {}, }
                 }
-            } else {
-                tokio::select! { _ = client_to_backend => {}, _ = backend_to_client => {}, }
+            };
+            let session_expired = async {
+                match max_session_secs {
+                    Some(secs) => tokio::time::sleep(std::time::Duration::from_secs(secs)).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            let backend_removed = async {
+                match &removal {
+                    Some(token) => token.cancelled().await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            let ended = tokio::select! {
+                _ = proxied => None,
+                _ = session_expired => Some((session_close, "session expired")),
+                _ = backend_removed => Some((
+                    CloseFrame {
+                        code: CloseCode::Away,
+                        reason: "backend removed".into(),
+                    },
+                    "backend removed",
+                )),
+            };
+            if let Some((client_close, reason)) = ended {
+                // Tell the client why, then tear down both sides
+                crate::metrics::increment_ws_close_code(client_close.code.into());
+                let _ = c_tx.send(Message::Close(Some(client_close))).await;
+                let _ = b_tx
+                    .send(Message::Close(Some(CloseFrame {
+                        code: CloseCode::Away,
+                        reason: reason.into(),
+                    })))
+                    .await;
+                let _ = c_tx.close().await;
+                let _ = b_tx.close().await;
+                tracing::info!(backend_url=%backend_url, reason, "websocket session ended by gateway");
             }
             tracing::info!(backend_url=%backend_url, "websocket session closed");
         });
@@ -813,6 +2021,207 @@ impl HttpHandler {
         Ok(response)
     }
 
+    /// Tunnel an `Upgrade` or `CONNECT` request on a route that allows it.
+    ///
+    /// `Upgrade` requests are forwarded to the selected backend, and both
+    /// connections are spliced once it answers `101`; any other answer is
+    /// returned as is. `CONNECT` opens a TCP connection to the backend's
+    /// address and answers `200` once it is established.
+    async fn handle_tunnel(
+        &self,
+        mut req: Request<AxumBody>,
+        client_addr: Option<SocketAddr>,
+        prefix: &str,
+        route_config: &RouteConfig,
+        request: TunnelRequest,
+    ) -> Result<Response<AxumBody>, eyre::Error> {
+        let (
+            targets,
+            strategy,
+            path_rewrite,
+            tunnel,
+            upstream_tls,
+            upstream_socket,
+            inject_default_headers,
+        ) = match route_config {
+            RouteConfig::Proxy {
+                target,
+                path_rewrite,
+                tunnel: Some(tunnel),
+                upstream_tls,
+                upstream_socket,
+                inject_default_headers,
+                ..
+            } => (
+                vec![target.clone()],
+                None,
+                path_rewrite.as_ref(),
+                tunnel,
+                upstream_tls,
+                upstream_socket,
+                *inject_default_headers,
+            ),
+            RouteConfig::LoadBalance {
+                targets,
+                strategy,
+                path_rewrite,
+                tunnel: Some(tunnel),
+                upstream_tls,
+                upstream_socket,
+                inject_default_headers,
+                ..
+            } => (
+                targets.clone(),
+                Some(*strategy),
+                path_rewrite.as_ref(),
+                tunnel,
+                upstream_tls,
+                upstream_socket,
+                *inject_default_headers,
+            ),
+            _ => return Err(eyre::eyre!("Route does not allow tunnels")),
+        };
+
+        let gateway = self.current_gateway();
+        let Some(backend) = gateway.select_backend(&targets, strategy, None).await else {
+            return Ok(error_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "No healthy backends available",
+            ));
+        };
+        tracing::Span::current().record("backend.url", &backend);
+        // Tunnels end early if a reload removes the backend and its policy says so
+        let removal = gateway.backend_cancellation(&backend);
+        drop(gateway);
+
+        let route = route_label(prefix, route_config);
+        let limits = TunnelLimits::from(tunnel);
+        let client_upgrade = hyper::upgrade::on(&mut req);
+
+        let protocol = match request {
+            TunnelRequest::Connect => {
+                let uri: Uri = backend.parse().wrap_err("Invalid backend URL")?;
+                let host = uri
+                    .host()
+                    .ok_or_else(|| eyre::eyre!("Backend URL has no host"))?;
+                let default_port = match uri.scheme_str() {
+                    Some("https") => 443,
+                    _ => 80,
+                };
+                let stream = match tokio::net::TcpStream::connect((
+                    host,
+                    uri.port_u16().unwrap_or(default_port),
+                ))
+                .await
+                {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        tracing::error!(error = %e, backend = %backend, "tunnel connect failed");
+                        return Ok(error_response(
+                            StatusCode::BAD_GATEWAY,
+                            "Backend connection failed",
+                        ));
+                    }
+                };
+                let socket =
+                    upstream_socket
+                        .unwrap_or_default()
+                        .or(self.config.load().upstream.socket);
+                if let Err(e) = apply_socket_options(&stream, &socket) {
+                    tracing::warn!(error = %e, backend = %backend, "failed to set tunnel socket options");
+                }
+                tokio::spawn(async move {
+                    match client_upgrade.await {
+                        Ok(client) => {
+                            let reason = tunnel::splice(
+                                TokioIo::new(client),
+                                stream,
+                                &route,
+                                limits,
+                                removal,
+                            )
+                            .await;
+                            tracing::info!(backend = %backend, reason, "CONNECT tunnel closed");
+                        }
+                        Err(e) => tracing::debug!(error = %e, "client upgrade failed"),
+                    }
+                });
+                return Ok(Response::new(AxumBody::empty()));
+            }
+            TunnelRequest::Upgrade(protocol) => protocol,
+        };
+
+        let rewritten_path = match path_rewrite {
+            Some(rewrite) => rewrite_path(req.uri().path(), prefix, rewrite),
+            None => req.uri().path().to_string(),
+        };
+        let path_and_query = match req.uri().query() {
+            Some(query) => format!("{rewritten_path}?{query}"),
+            None => rewritten_path,
+        };
+        *req.uri_mut() = format!("{}{path_and_query}", backend.trim_end_matches('/'))
+            .parse()
+            .wrap_err("Failed to parse backend URI")?;
+        if let Some(addr) = client_addr {
+            let forwarded_for = match req.headers().get("X-Forwarded-For") {
+                Some(existing) => {
+                    format!("{}, {}", existing.to_str().unwrap_or_default(), addr.ip())
+                }
+                None => addr.ip().to_string(),
+            };
+            if let Ok(value) = HeaderValue::from_str(&forwarded_for) {
+                req.headers_mut().insert("X-Forwarded-For", value);
+            }
+        }
+        *req.extensions_mut() = http::Extensions::new();
+        req.extensions_mut().insert(UpstreamRequestOptions {
+            tls: upstream_tls.clone(),
+            preserve_header_case: false,
+            inject_default_headers,
+            upgrade: true,
+            response_timeout: ResponseTimeoutConfig::default(),
+            socket: upstream_socket.unwrap_or_default(),
+            fastcgi: None,
+        });
+
+        let mut response = match self.http_client.send_request(req).await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::error!(error = %e, backend = %backend, "tunnel upgrade request failed");
+                return Ok(error_response(
+                    StatusCode::BAD_GATEWAY,
+                    "Backend request failed",
+                ));
+            }
+        };
+        if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+            tracing::debug!(
+                status = response.status().as_u16(),
+                protocol = %protocol,
+                "backend declined upgrade"
+            );
+            return Ok(response);
+        }
+        let backend_upgrade = hyper::upgrade::on(&mut response);
+        tokio::spawn(async move {
+            match tokio::try_join!(client_upgrade, backend_upgrade) {
+                Ok((client, upstream)) => {
+                    let reason = tunnel::splice(
+                        TokioIo::new(client),
+                        TokioIo::new(upstream),
+                        &route,
+                        limits,
+                        removal,
+                    )
+                    .await;
+                    tracing::info!(backend = %backend, protocol = %protocol, reason, "tunnel closed");
+                }
+                Err(e) => tracing::debug!(error = %e, "upgrade failed"),
+            }
+        });
+        Ok(response)
+    }
+
     /// Issue an HTTP redirect to configured target.
     async fn handle_redirect(
         &self,
@@ -834,40 +2243,180 @@ impl HttpHandler {
         Ok(response)
     }
 
-    /// Public wrapper around proxy logic that also tracks connection/request counts.
-    async fn handle_proxy_request(
+    /// Forward a proxy request, answering repeats of a request seen within the
+    /// route's dedupe window without contacting the backend.
+    ///
+    /// The key is released again unless the backend answers with a 2xx, so a
+    /// redelivery after a failed attempt is forwarded.
+    async fn handle_deduplicated_proxy(
         &self,
         req: Request<AxumBody>,
         client_addr: Option<SocketAddr>,
+        prefix: &str,
+        route_host: Option<String>,
+        dedupe: Option<DedupeConfig>,
+        coalesce: Option<CoalesceConfig>,
     ) -> Result<Response<AxumBody>, eyre::Error> {
-        // Register connection if we have client address
-        let connection_info = if let Some(addr) = client_addr {
-            Some(self.connection_tracker.register_connection(addr).await)
+        let window = dedupe
+            .filter(|_| is_deduplicated_method(req.method()))
+            .and_then(|config| {
+                self.current_gateway()
+                    .get_dedupe_window(prefix, route_host.as_deref())
+                    .map(|window| (config, window))
+            });
+        let Some((config, window)) = window else {
+            return self
+                .handle_coalesced_proxy(req, client_addr, prefix, route_host, coalesce)
+                .await;
+        };
+
+        let (parts, body) = req.into_parts();
+        let (body, bytes) = if config.include_body {
+            match to_bytes(body, config.max_body_size).await {
+                Ok(bytes) => (AxumBody::from(bytes.clone()), Some(bytes)),
+                Err(_) => {
+                    return Ok(error_response(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        "Request body too large",
+                    ));
+                }
+            }
         } else {
-            None
+            (body, None)
+        };
+        let req = Request::from_parts(parts, body);
+
+        let Some(key) = dedupe_key(req.method(), req.headers(), &config, bytes.as_deref()) else {
+            return self.handle_proxy_request(req, client_addr).await;
         };
+        if !window.claim(&key, Instant::now()).await {
+            tracing::info!(
+                event = "duplicate_request",
+                route = prefix,
+                "Suppressed duplicate request"
+            );
+            let status = StatusCode::from_u16(config.status_code).unwrap_or(StatusCode::OK);
+            return Response::builder()
+                .status(status)
+                .body(AxumBody::from(config.response_body.unwrap_or_default()))
+                .wrap_err("Failed to build duplicate response");
+        }
 
-        // Increment request count
-        if let Some(ref conn_info) = connection_info {
-            conn_info.increment_requests();
+        let result = self.handle_proxy_request(req, client_addr).await;
+        if !matches!(&result, Ok(response) if response.status().is_success()) {
+            window.release(&key).await;
         }
+        result
+    }
 
-        let client_ip = client_addr.map(|a| a.ip().to_string());
-        let result = self.proxy_request_to_backend(req, client_ip).await;
+    /// Forward a GET or HEAD request, sharing the backend response with
+    /// identical requests that arrive while it is in flight.
+    ///
+    /// Only responses with a `Content-Length` up to `max_body_bytes` and no
+    /// `Set-Cookie` are shared; for anything else the waiters send their own
+    /// requests.
+    async fn handle_coalesced_proxy(
+        &self,
+        req: Request<AxumBody>,
+        client_addr: Option<SocketAddr>,
+        prefix: &str,
+        route_host: Option<String>,
+        coalesce: Option<CoalesceConfig>,
+    ) -> Result<Response<AxumBody>, eyre::Error> {
+        let eligible = |config: &CoalesceConfig| {
+            let headers = req.headers();
+            matches!(*req.method(), http::Method::GET | http::Method::HEAD)
+                && !headers.contains_key(header::CONTENT_LENGTH)
+                && !headers.contains_key(header::TRANSFER_ENCODING)
+                && config
+                    .bypass_header
+                    .as_ref()
+                    .is_none_or(|name| !headers.contains_key(name.as_str()))
+        };
+        let coalescer = coalesce.filter(eligible).and_then(|config| {
+            self.current_gateway()
+                .get_coalescer(prefix, route_host.as_deref())
+                .map(|coalescer| (config, coalescer))
+        });
+        let Some((config, coalescer)) = coalescer else {
+            return self.handle_proxy_request(req, client_addr).await;
+        };
 
-        // Decrement request count
-        if let Some(ref conn_info) = connection_info {
-            conn_info.decrement_requests();
+        let host = Self::extract_routing_host(req.headers());
+        let mut key = coalesce_key(
+            req.method(),
+            host.as_deref(),
+            req.uri(),
+            req.headers(),
+            &config,
+        );
+        // Experiment variants may get different responses
+        if let Some(ExperimentHeaders(variants)) = req.extensions().get::<ExperimentHeaders>() {
+            for (name, value) in variants {
+                key.push_str(&format!("\n{name}={}", value.to_str().unwrap_or_default()));
+            }
         }
-
-        // This registration is per-request (not per-socket), so clean it up after completion.
-        if let Some(conn_info) = connection_info {
-            self.connection_tracker
-                .unregister_connection(conn_info.id)
-                .await;
+        match coalescer.join(&key) {
+            Join::Leader(leader) => {
+                let response = self.handle_proxy_request(req, client_addr).await?;
+                let shareable = !response.headers().contains_key(header::SET_COOKIE)
+                    && response
+                        .headers()
+                        .get(header::CONTENT_LENGTH)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .is_some_and(|length| length <= config.max_body_bytes);
+                if !shareable {
+                    leader.finish(None);
+                    return Ok(response);
+                }
+                let (parts, body) = response.into_parts();
+                let Ok(body) = to_bytes(body, config.max_body_bytes).await else {
+                    // Dropping the leader sends the waiters to the backend
+                    return Ok(error_response(
+                        StatusCode::BAD_GATEWAY,
+                        "Backend request failed",
+                    ));
+                };
+                leader.finish(Some(Arc::new(SharedResponse {
+                    status: parts.status,
+                    headers: parts.headers.clone(),
+                    body: body.clone(),
+                })));
+                Ok(Response::from_parts(parts, AxumBody::from(body)))
+            }
+            Join::Waiter(waiter) => match waiter.wait().await {
+                Some(shared) => {
+                    crate::metrics::record_coalesced_request(prefix, "shared");
+                    let mut response = Response::new(AxumBody::from(shared.body.clone()));
+                    *response.status_mut() = shared.status;
+                    *response.headers_mut() = shared.headers.clone();
+                    // The request ID is this request's own
+                    response.headers_mut().remove(X_REQUEST_ID);
+                    Ok(response)
+                }
+                None => {
+                    crate::metrics::record_coalesced_request(prefix, "unshared");
+                    self.handle_proxy_request(req, client_addr).await
+                }
+            },
+            Join::Full => {
+                crate::metrics::record_coalesced_request(prefix, "full");
+                self.handle_proxy_request(req, client_addr).await
+            }
         }
+    }
 
-        result
+    /// Public wrapper around proxy logic.
+    ///
+    /// Connection and request counts are tracked by the server loop.
+    async fn handle_proxy_request(
+        &self,
+        req: Request<AxumBody>,
+        client_addr: Option<SocketAddr>,
+    ) -> Result<Response<AxumBody>, eyre::Error> {
+        let client_ip = client_addr.map(|a| a.ip().to_string());
+        self.proxy_request_to_backend(req, client_ip).await
     }
 
     /// Core proxy implementation: select backend, rewrite path, forward request.
@@ -876,6 +2425,7 @@ impl HttpHandler {
         mut req: Request<AxumBody>,
         client_ip: Option<String>,
     ) -> Result<Response<AxumBody>, eyre::Error> {
+        let experiment_headers = req.extensions_mut().remove::<ExperimentHeaders>();
         let path = req.uri().path();
 
         let host_header_value = Self::extract_raw_host(req.headers());
@@ -887,94 +2437,289 @@ impl HttpHandler {
             .ok_or_else(|| eyre::eyre!("No matching route found for path: {}", path))?;
 
         // Get targets and path rewrite from the route configuration
-        let (targets, strategy, path_rewrite) = match &route_config {
+        let (targets, strategy, path_rewrite, upstream_options) = match &route_config {
             RouteConfig::Proxy {
                 target,
                 path_rewrite,
+                upstream_tls,
+                response_timeout,
+                upstream_socket,
+                preserve_header_case,
+                inject_default_headers,
+                fastcgi,
                 ..
-            } => (vec![target.clone()], None, path_rewrite.as_ref()),
+            } => (
+                vec![target.clone()],
+                None,
+                path_rewrite.as_ref(),
+                UpstreamRequestOptions {
+                    tls: upstream_tls.clone(),
+                    preserve_header_case: *preserve_header_case,
+                    inject_default_headers: *inject_default_headers,
+                    upgrade: false,
+                    response_timeout: response_timeout.unwrap_or_default(),
+                    socket: upstream_socket.unwrap_or_default(),
+                    fastcgi: fastcgi.clone(),
+                },
+            ),
             RouteConfig::LoadBalance {
                 targets,
                 strategy,
                 path_rewrite,
+                upstream_tls,
+                response_timeout,
+                upstream_socket,
+                preserve_header_case,
+                inject_default_headers,
+                fastcgi,
                 ..
-            } => (targets.clone(), Some(*strategy), path_rewrite.as_ref()),
+            } => (
+                targets.clone(),
+                Some(*strategy),
+                path_rewrite.as_ref(),
+                UpstreamRequestOptions {
+                    tls: upstream_tls.clone(),
+                    preserve_header_case: *preserve_header_case,
+                    inject_default_headers: *inject_default_headers,
+                    upgrade: false,
+                    response_timeout: response_timeout.unwrap_or_default(),
+                    socket: upstream_socket.unwrap_or_default(),
+                    fastcgi: fastcgi.clone(),
+                },
+            ),
             _ => return Err(eyre::eyre!("Route is not a proxy or load balance route")),
         };
 
-        // Select a backend using the load balancer
-        let backend = gateway
-            .select_backend(&targets, strategy)
-            .await
-            .ok_or_else(|| eyre::eyre!("No healthy backends available"))?;
-
-        // Record selected backend in span
-        tracing::Span::current().record("backend.url", &backend);
-
-        // Increment active connections
-        if let Some(entry) = gateway.backend_health().get_async(&backend).await {
-            entry.get().inc_active_connections();
-        }
-
-        // Handle path rewriting
-        let original_uri = req.uri().clone();
-        let rewritten_path = if let Some(rewrite) = path_rewrite {
-            // Strip the route prefix and prepend the rewrite path. We normalise both sides to
-            // avoid accidental double slashes (e.g. "/real" + "/foo" -> "/real/foo").
-            let remaining_path = path.strip_prefix(&route_prefix).unwrap_or(path);
-
-            // Helper: ensure a path segment starts with exactly one leading '/'
-            let normalise_leading = |s: &str| -> String {
-                if s.is_empty() {
-                    "".to_string()
-                } else if s.starts_with('/') {
-                    s.to_string()
-                } else {
-                    format!("/{s}")
-                }
-            };
+        // A schedule window with its own targets takes all of the route's
+        // traffic, bypassing language and canary groups
+        let schedule = match &route_config {
+            RouteConfig::Proxy { host, .. } | RouteConfig::LoadBalance { host, .. } => {
+                gateway.get_route_schedule(&route_prefix, host.as_deref())
+            }
+            _ => None,
+        };
+        let scheduled_targets = schedule
+            .as_ref()
+            .and_then(|schedule| match schedule.state() {
+                ScheduleState::Targets(targets) => Some(targets.to_vec()),
+                _ => None,
+            });
+        let scheduled = scheduled_targets.is_some();
+        let targets = match scheduled_targets {
+            Some(scheduled) => {
+                tracing::debug!(targets = ?scheduled, "Routing to scheduled targets");
+                scheduled
+            }
+            None => targets,
+        };
 
-            // Normalise rewrite base
-            let base = if rewrite == "/" {
-                "/".to_string()
-            } else {
-                normalise_leading(rewrite.trim_end_matches('/'))
-            };
+        let slow_start = match &route_config {
+            RouteConfig::LoadBalance {
+                slow_start_secs, ..
+            } => slow_start_secs.map(std::time::Duration::from_secs),
+            _ => None,
+        };
 
-            if remaining_path.is_empty() || remaining_path == "/" {
-                // Exactly the route prefix only: use the base as-is
-                base.clone()
-            } else {
-                let remaining = remaining_path.trim_start_matches('/');
-                if base == "/" {
-                    format!("/{remaining}")
-                } else {
-                    format!("{base}/{remaining}")
-                }
+        // Header/body actions evaluate their conditions against the client's request
+        let transforms = match &route_config {
+            RouteConfig::Proxy { host, .. } | RouteConfig::LoadBalance { host, .. } => {
+                gateway.get_route_transforms(&route_prefix, host.as_deref())
             }
-        } else {
-            // No rewrite, use original path
-            original_uri.path().to_string()
+            _ => None,
         };
-
-        // Construct the backend URI with the rewritten path
-        let backend_uri = if let Some(query) = original_uri.query() {
-            format!(
-                "{}{}?{query}",
-                backend.trim_end_matches('/'),
-                rewritten_path
+        let route_label = route_label(&route_prefix, &route_config);
+        let condition_request = transforms.as_ref().map(|_| {
+            (
+                req.method().clone(),
+                req.uri().clone(),
+                req.headers().clone(),
             )
-        } else {
-            format!("{}{}", backend.trim_end_matches('/'), rewritten_path)
-        };
+        });
+        let condition_ctx =
+            condition_request
+                .as_ref()
+                .map(|(method, uri, headers)| ConditionContext {
+                    method,
+                    uri,
+                    headers,
+                });
 
-        *req.uri_mut() = backend_uri
-            .parse()
-            .wrap_err("Failed to parse backend URI")?;
+        // Send clients whose preferred language has a backend group to that group
+        let mut locale_backend = None;
+        let mut locale_header = None;
+        let mut locale_targets = None;
+        if let RouteConfig::Proxy {
+            locale: Some(locale),
+            ..
+        }
+        | RouteConfig::LoadBalance {
+            locale: Some(locale),
+            ..
+        } = &route_config
+            && !scheduled
+        {
+            let header_name = http::HeaderName::from_bytes(locale.header.as_bytes())
+                .wrap_err("Invalid locale header name")?;
+            let matched = req
+                .headers()
+                .get(header::ACCEPT_LANGUAGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|accept_language| {
+                    match_language(accept_language, |language| {
+                        locale
+                            .groups
+                            .keys()
+                            .any(|k| k.eq_ignore_ascii_case(language))
+                    })
+                });
+            let mut matched_value = None;
+            if let Some(language) = matched {
+                let group_targets = locale
+                    .groups
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(&language))
+                    .map(|(_, targets)| targets.as_slice())
+                    .unwrap_or_default();
+                locale_backend = gateway
+                    .select_backend(group_targets, strategy, slow_start)
+                    .await;
+                match locale_backend {
+                    Some(_) => {
+                        tracing::debug!(language = %language, "Routing to locale group");
+                        locale_targets = Some(group_targets.to_vec());
+                        matched_value = Some(
+                            HeaderValue::from_str(&language)
+                                .wrap_err("Invalid matched language")?,
+                        );
+                    }
+                    None => tracing::warn!(
+                        language = %language,
+                        "No healthy backends in locale group; using route targets"
+                    ),
+                }
+            }
+            locale_header = Some((header_name, matched_value));
+        }
+
+        // Split traffic to the canary group when one is configured for the route
+        let canary = match &route_config {
+            RouteConfig::LoadBalance {
+                canary: Some(_),
+                host,
+                ..
+            } if locale_backend.is_none() && !scheduled => {
+                gateway.get_canary(&route_prefix, host.as_deref())
+            }
+            _ => None,
+        };
+        let mut canary_group = canary.as_ref().map(|state| state.choose_group());
+        let mut canary_backend = None;
+        if let (Some(state), Some(CanaryGroup::Canary)) = (&canary, canary_group) {
+            canary_backend = gateway
+                .select_backend(state.targets(), strategy, slow_start)
+                .await;
+            if canary_backend.is_none() {
+                canary_group = Some(CanaryGroup::Stable);
+            }
+        }
+
+        // Select a backend using the load balancer, or from the fallback pool
+        // when none of the route's own backends is healthy
+        let fallback = match &route_config {
+            RouteConfig::Proxy { fallback, .. } | RouteConfig::LoadBalance { fallback, .. } => {
+                fallback.as_ref()
+            }
+            _ => None,
+        };
+        let mut in_fallback = false;
+        let mut backend = match locale_backend.or(canary_backend) {
+            Some(backend) => backend,
+            None => match gateway.select_backend(&targets, strategy, slow_start).await {
+                Some(backend) => backend,
+                None => {
+                    let backend = match fallback {
+                        Some(fallback) => {
+                            gateway
+                                .select_backend(&fallback.targets, strategy, None)
+                                .await
+                        }
+                        None => None,
+                    }
+                    .ok_or_else(|| eyre::eyre!("No healthy backends available"))?;
+                    tracing::warn!(backend = %backend, "no healthy backends; using fallback targets");
+                    crate::metrics::record_failover(&route_label, "unhealthy");
+                    in_fallback = true;
+                    canary_group = None;
+                    backend
+                }
+            },
+        };
+
+        // Handle path rewriting
+        let original_uri = req.uri().clone();
+        let rewritten_path = match path_rewrite {
+            Some(rewrite) => rewrite_path(path, &route_prefix, rewrite),
+            // No rewrite, use original path
+            None => original_uri.path().to_string(),
+        };
+
+        // The backend URI is the selected backend followed by this
+        let path_and_query = match original_uri.query() {
+            Some(query) => format!("{rewritten_path}?{query}"),
+            None => rewritten_path,
+        };
+
+        // Route-specific client options travel to the HTTP client as an extension.
+        // Inbound extensions carry hyper's record of the client's header casing, so
+        // they are only forwarded when the route asks to preserve it. The phase
+        // timings are kept aside first, since backend phases are recorded below.
+        let timings = req.extensions().get::<Arc<RequestTimings>>().cloned();
+        if !upstream_options.preserve_header_case {
+            *req.extensions_mut() = http::Extensions::new();
+        }
+        req.extensions_mut().insert(upstream_options);
+
+        // Client headers outside the allowlist never reach the backend; the
+        // gateway's own headers below are added afterwards
+        let header_allowlist = match &route_config {
+            RouteConfig::Proxy {
+                header_allowlist, ..
+            }
+            | RouteConfig::LoadBalance {
+                header_allowlist, ..
+            } => header_allowlist.clone(),
+            _ => None,
+        };
+        let global_allowlist = &self.config.load().header_allowlist;
+        let request_allowlist = header_allowlist
+            .as_ref()
+            .and_then(|allowlist| allowlist.request.clone())
+            .or_else(|| global_allowlist.request.clone());
+        let response_allowlist = header_allowlist
+            .as_ref()
+            .and_then(|allowlist| allowlist.response.clone())
+            .or_else(|| global_allowlist.response.clone());
+        if let Some(allowlist) = &request_allowlist {
+            retain_allowed_headers(req.headers_mut(), allowlist);
+        }
+        // Client-supplied values never override the assigned variant
+        if let Some(ExperimentHeaders(experiment_headers)) = experiment_headers {
+            for (name, value) in experiment_headers {
+                req.headers_mut().insert(name, value);
+            }
+        }
 
         // Add forwarded headers
         let headers = req.headers_mut();
 
+        // The matched language replaces any client-supplied value
+        if let Some((name, value)) = locale_header {
+            headers.remove(&name);
+            if let Some(value) = value {
+                headers.insert(name, value);
+            }
+        }
+
         // X-Forwarded-For: append client IP
         if let Some(ip) = client_ip {
             if let Some(existing) = headers.get("X-Forwarded-For") {
@@ -1025,27 +2770,433 @@ impl HttpHandler {
                 .map_err(|e| eyre::eyre!("Failed to parse host: {}", e))?,
         );
 
-        // Send request to backend
-        let backend_start = Instant::now();
-        let result = self.http_client.send_request(req).await;
+        if let (Some(transforms), Some(ctx)) = (&transforms, &condition_ctx) {
+            if let Some(actions) = transforms
+                .request_headers
+                .as_ref()
+                .filter(|a| a.applies(ctx))
+            {
+                apply_header_actions(req.headers_mut(), &actions.actions);
+            }
+            if let Some(actions) = transforms.request_body.as_ref().filter(|a| a.applies(ctx))
+                && let Some(body) = replace_body(req.headers_mut(), &actions.actions)
+            {
+                *req.body_mut() = body;
+            }
+            // Compressed responses cannot be rewritten as they stream
+            if transforms.response_rewrite.is_some()
+                && transforms
+                    .response_body
+                    .as_ref()
+                    .is_some_and(|a| a.applies(ctx))
+            {
+                req.headers_mut().remove(header::ACCEPT_ENCODING);
+            }
+        }
 
-        // Decrement active connections
-        if let Some(entry) = gateway.backend_health().get_async(&backend).await {
-            entry.get().dec_active_connections();
+        // Requests that may be retried keep their body so it can be sent again
+        let retry = match &route_config {
+            RouteConfig::Proxy { retry, host, .. }
+            | RouteConfig::LoadBalance { retry, host, .. } => retry
+                .as_ref()
+                .zip(gateway.get_retry_budget(&route_prefix, host.as_deref())),
+            _ => None,
+        };
+        if let Some((_, budget)) = &retry {
+            budget.record_request(Instant::now());
+        }
+        // A key generated here travels with every attempt, so the backend
+        // can recognise a retried non-idempotent request
+        let mut has_idempotency_key = false;
+        if let Some((config, _)) = &retry {
+            let generate = config.generate_idempotency_key && !is_idempotent_method(req.method());
+            has_idempotency_key =
+                ensure_idempotency_key(req.headers_mut(), &config.idempotency_header, generate)?;
+        }
+        let replayable = is_retryable_method(
+            req.method(),
+            retry
+                .as_ref()
+                .map_or_else(Default::default, |(config, _)| config.non_idempotent),
+            has_idempotency_key,
+        );
+        let body_size = hyper::body::Body::size_hint(req.body()).upper();
+        let fits = |limit: usize| replayable && body_size.is_some_and(|size| size <= limit as u64);
+        // Only requests that are safe to send twice at once are hedged
+        let hedger = match &route_config {
+            RouteConfig::LoadBalance {
+                hedge: Some(_),
+                host,
+                ..
+            } => gateway
+                .get_hedger(&route_prefix, host.as_deref())
+                .filter(|hedger| {
+                    is_idempotent_method(req.method())
+                        && body_size.is_some_and(|size| size <= hedger.max_body_bytes() as u64)
+                }),
+            _ => None,
+        };
+        if let Some(hedger) = &hedger {
+            hedger.record_request(Instant::now());
+        }
+        // Both copies of a hedged request with side effects carry one key
+        if let (
+            Some(_),
+            RouteConfig::LoadBalance {
+                hedge: Some(hedge), ..
+            },
+        ) = (&hedger, &route_config)
+        {
+            let generate = hedge.generate_idempotency_key && !is_safe_method(req.method());
+            ensure_idempotency_key(req.headers_mut(), &hedge.idempotency_header, generate)?;
+        }
+        let can_retry = retry
+            .as_ref()
+            .is_some_and(|(config, _)| config.attempts > 0 && fits(config.max_body_bytes));
+        let can_fail_over = !in_fallback && fallback.is_some_and(|f| fits(f.max_body_bytes));
+        let replay_limit = retry
+            .as_ref()
+            .filter(|_| can_retry)
+            .map(|(config, _)| config.max_body_bytes)
+            .max(
+                fallback
+                    .filter(|_| can_fail_over)
+                    .map(|fallback| fallback.max_body_bytes),
+            )
+            .max(hedger.as_ref().map(|hedger| hedger.max_body_bytes()));
+        let head_request = req.method() == http::Method::HEAD;
+        let (mut pending, template) = match replay_limit {
+            Some(limit) => {
+                let (parts, body) = req.into_parts();
+                let Ok(body) = to_bytes(body, limit).await else {
+                    return Ok(error_response(
+                        StatusCode::BAD_REQUEST,
+                        "Failed to read request body",
+                    ));
+                };
+                (None, Some(Request::from_parts(parts, body)))
+            }
+            None => (Some(req), None),
+        };
+        let mut reserve_pool: &[String] = &targets;
+        let mut retry_pool = locale_targets.as_deref().unwrap_or(&targets);
+        if let (true, Some(fallback)) = (in_fallback, fallback) {
+            reserve_pool = &fallback.targets;
+            retry_pool = &fallback.targets;
         }
+        let mut tried = Vec::new();
+        let mut retries = 0;
+
+        let (attempt, result) = loop {
+            let req = match (pending.take(), &template) {
+                (Some(req), _) => req,
+                (None, Some(template)) => replay_request(template),
+                (None, None) => return Err(eyre::eyre!("Request body was already sent")),
+            };
+
+            // Respect per-backend request caps
+            let reserve_start = Instant::now();
+            let reserved = self.reserve_backend(&gateway, &backend, reserve_pool).await;
+            if let Some(timings) = &timings {
+                timings.record(Phase::Queue, reserve_start.elapsed());
+            }
+            let backend_permit = match reserved {
+                Some((reserved, permit)) => {
+                    if reserved != backend && canary_group == Some(CanaryGroup::Canary) {
+                        canary_group = Some(CanaryGroup::Stable);
+                    }
+                    backend = reserved;
+                    permit
+                }
+                None => {
+                    return Ok(error_response(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "Backend saturated",
+                    ));
+                }
+            };
+
+            // Record selected backend in span
+            tracing::Span::current().record("backend.url", &backend);
+
+            let (mut attempt, send) = self.start_attempt(
+                &gateway,
+                req,
+                (backend.clone(), backend_permit),
+                canary_group,
+                &path_and_query,
+                &route_label,
+            )?;
+            let abandoned = AbandonedRequestGuard::new(&backend, &route_label);
+            // Only a request's first attempt is hedged, within its canary group
+            let (result, hedge) = match (&hedger, &template) {
+                (Some(hedger), Some(template)) if tried.is_empty() && !in_fallback => {
+                    let pool = match (&canary, attempt.group) {
+                        (Some(state), Some(CanaryGroup::Canary)) => state.targets(),
+                        _ => retry_pool,
+                    };
+                    self.race_hedge(
+                        &gateway,
+                        hedger,
+                        send,
+                        template,
+                        &attempt,
+                        HedgeTargets {
+                            pool,
+                            strategy,
+                            slow_start,
+                            path_and_query: &path_and_query,
+                            route_label: &route_label,
+                        },
+                    )
+                    .await?
+                }
+                _ => (send.await, None),
+            };
+            abandoned.disarm();
+            if let Some(hedge) = hedge {
+                attempt.supersede();
+                attempt = hedge;
+                backend = attempt.backend.clone();
+                canary_group = attempt.group;
+                tracing::Span::current().record("backend.url", &backend);
+            }
+            attempt.record_outcome(&result);
+
+            // A request body that broke off means the client went away; the
+            // backend isn't to blame and nobody is left to answer
+            if result.is_err() && attempt.client_aborted.load(Ordering::Relaxed) {
+                crate::metrics::record_client_disconnect(&route_label, "request_body");
+                tracing::Span::current().record("client.disconnected", true);
+                tracing::warn!(
+                    backend = %backend,
+                    client_disconnected = true,
+                    "client aborted the request body; backend request cancelled"
+                );
+                return Ok(error_response(
+                    StatusCode::from_u16(499).expect("valid status"),
+                    "Client closed request",
+                ));
+            }
+
+            if let (Some(state), Some(group)) = (&canary, canary_group) {
+                let is_error = !result
+                    .as_ref()
+                    .is_ok_and(|response| !response.status().is_server_error());
+                if let Some(reason) = state.record(group, is_error, attempt.start.elapsed()) {
+                    self.on_canary_rollback(&route_prefix, state, &reason);
+                }
+            }
+
+            let outcome_status = match &result {
+                Ok(response) => response.status().as_u16(),
+                Err(HttpClientError::Timeout(_)) => StatusCode::GATEWAY_TIMEOUT.as_u16(),
+                Err(_) => StatusCode::BAD_GATEWAY.as_u16(),
+            };
+            crate::metrics::record_route_traffic(
+                &route_label,
+                &backend,
+                outcome_status,
+                attempt.start.elapsed(),
+            );
+
+            // Send failed requests again, to another backend when there is one,
+            // while attempts and the route's retry budget last
+            if let Some((config, budget)) = &retry
+                && can_retry
+                && retries < config.attempts
+                && is_retryable_outcome(&result, &config.retry_on)
+            {
+                tried.push(backend.clone());
+                let untried: Vec<String> = retry_pool
+                    .iter()
+                    .filter(|target| !tried.contains(target))
+                    .cloned()
+                    .collect();
+                let next = match gateway.select_backend(&untried, strategy, slow_start).await {
+                    Some(next) => Some(next),
+                    None => {
+                        gateway
+                            .select_backend(retry_pool, strategy, slow_start)
+                            .await
+                    }
+                };
+                if let Some(next) = next {
+                    if budget.try_retry(Instant::now()) {
+                        retries += 1;
+                        crate::metrics::record_retry(&route_label, "retried");
+                        tracing::warn!(
+                            attempt = retries,
+                            backend = %backend,
+                            next_backend = %next,
+                            status = outcome_status,
+                            "retrying backend request"
+                        );
+                        backend = next;
+                        canary_group = canary_group.map(|_| CanaryGroup::Stable);
+                        tokio::time::sleep(backoff(
+                            Duration::from_millis(config.backoff_ms),
+                            retries,
+                        ))
+                        .await;
+                        continue;
+                    }
+                    crate::metrics::record_retry(&route_label, "budget_exhausted");
+                    tracing::warn!(backend = %backend, "retry budget exhausted; not retrying");
+                }
+            }
+
+            // Send what the route's own backends failed to the fallback pool
+            if can_fail_over
+                && !in_fallback
+                && let Some(fallback) = fallback
+                && is_retryable_outcome(&result, &fallback.on_status)
+                && let Some(next) = gateway
+                    .select_backend(&fallback.targets, strategy, None)
+                    .await
+            {
+                crate::metrics::record_failover(&route_label, "failed");
+                tracing::warn!(
+                    backend = %backend,
+                    next_backend = %next,
+                    status = outcome_status,
+                    "failing over to fallback targets"
+                );
+                in_fallback = true;
+                reserve_pool = &fallback.targets;
+                retry_pool = &fallback.targets;
+                backend = next;
+                canary_group = None;
+                continue;
+            }
+
+            break (attempt, result);
+        };
 
         match result {
             Ok(response) => {
-                let backend_duration = backend_start.elapsed();
+                let backend_duration = attempt.start.elapsed();
                 tracing::info!(
                     backend_status = response.status().as_u16(),
                     backend_duration_ms = backend_duration.as_millis(),
                     "backend response"
                 );
+                if let (Some(timings), Some(timing)) =
+                    (&timings, response.extensions().get::<BackendTiming>())
+                {
+                    timings.record_backend(*timing);
+                }
+                let response = self.guard_content_length(response, &attempt.backend, head_request);
+                let mut response = attempt.wrap_response(
+                    response,
+                    self.config.load().backend_removal.mode != BackendRemovalMode::Complete,
+                );
+                let route_error_pages = match &route_config {
+                    RouteConfig::Proxy { error_pages, .. }
+                    | RouteConfig::LoadBalance { error_pages, .. } => error_pages.as_ref(),
+                    _ => None,
+                };
+                if let RouteConfig::Proxy { status_map, .. }
+                | RouteConfig::LoadBalance { status_map, .. } = &route_config
+                    && let Some(mapping) = status_map
+                        .iter()
+                        .find(|mapping| mapping.from.contains(&response.status().as_u16()))
+                {
+                    tracing::debug!(
+                        backend_status = response.status().as_u16(),
+                        status = mapping.to,
+                        "backend status remapped"
+                    );
+                    if let Some(status) = mapping.to.and_then(|to| StatusCode::from_u16(to).ok()) {
+                        *response.status_mut() = status;
+                    }
+                    if let Some(body) = set_body(
+                        response.headers_mut(),
+                        mapping.set_json.as_ref(),
+                        mapping.set_text.as_deref(),
+                    ) {
+                        *response.body_mut() = body;
+                    }
+                } else if error_pages::intercepts(
+                    route_error_pages,
+                    &self.config.load().error_pages,
+                    response.status(),
+                ) {
+                    tracing::debug!(
+                        backend_status = response.status().as_u16(),
+                        "backend error intercepted"
+                    );
+                    response = error_pages::intercept_backend_error(response);
+                }
+                if let Some(allowlist) = &response_allowlist {
+                    retain_allowed_headers(response.headers_mut(), allowlist);
+                }
+                let config = self.config.load();
+                if !matches!(
+                    response.status(),
+                    StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+                ) && let Some(action) = content_type::apply_proxy_rules(
+                    &config.content_types,
+                    original_uri.path(),
+                    response.headers_mut(),
+                ) {
+                    tracing::debug!(?action, "backend Content-Type rewritten");
+                }
+                if config.content_types.nosniff {
+                    set_nosniff(response.headers_mut());
+                }
+                if let RouteConfig::Proxy {
+                    location_rewrite: Some(rewrite),
+                    ..
+                }
+                | RouteConfig::LoadBalance {
+                    location_rewrite: Some(rewrite),
+                    ..
+                } = &route_config
+                {
+                    let origins = targets
+                        .iter()
+                        .chain(fallback.iter().flat_map(|fallback| &fallback.targets))
+                        .chain(locale_targets.iter().flatten())
+                        .chain(&rewrite.internal_origins)
+                        .map(String::as_str);
+                    let names: Vec<_> = rewrite
+                        .headers
+                        .iter()
+                        .filter_map(|name| http::HeaderName::from_bytes(name.as_bytes()).ok())
+                        .collect();
+                    LocationRewriter::new(
+                        origins,
+                        &route_prefix,
+                        path_rewrite.map(String::as_str),
+                        rewrite.public_url.as_deref(),
+                    )
+                    .apply(response.headers_mut(), &names);
+                }
+                if let (Some(transforms), Some(ctx)) = (&transforms, &condition_ctx) {
+                    if let Some(actions) = transforms
+                        .response_headers
+                        .as_ref()
+                        .filter(|a| a.applies(ctx))
+                    {
+                        apply_header_actions(response.headers_mut(), &actions.actions);
+                    }
+                    if let Some(actions) =
+                        transforms.response_body.as_ref().filter(|a| a.applies(ctx))
+                    {
+                        if let Some(body) = replace_body(response.headers_mut(), &actions.actions) {
+                            *response.body_mut() = body;
+                        } else if let Some(rewrite) = &transforms.response_rewrite
+                            && !head_request
+                        {
+                            response = rewrite_body(response, rewrite);
+                        }
+                    }
+                }
                 Ok(response)
             }
             Err(e) => {
-                let backend_duration = backend_start.elapsed();
+                let backend_duration = attempt.start.elapsed();
                 tracing::error!(
                     error = %e,
                     backend_duration_ms = backend_duration.as_millis(),
@@ -1057,15 +3208,348 @@ impl HttpHandler {
                     _ => StatusCode::BAD_GATEWAY,
                 };
 
-                Ok(Response::builder()
-                    .status(status)
-                    .body(AxumBody::from("Backend request failed"))
-                    .wrap_err("Failed to build error response")?)
+                Ok(error_response(status, "Backend request failed"))
+            }
+        }
+    }
+}
+
+impl HttpHandler {
+    /// Check a streamed backend body against its declared `Content-Length`.
+    ///
+    /// A body that ends early or runs long is cut off with an error, aborting
+    /// the client response instead of delivering a truncated payload, and is
+    /// counted as a passive health failure for the backend.
+    fn guard_content_length(
+        &self,
+        response: Response<AxumBody>,
+        backend: &str,
+        head_request: bool,
+    ) -> Response<AxumBody> {
+        let status = response.status();
+        if head_request
+            || status.is_informational()
+            || status == StatusCode::NO_CONTENT
+            || status == StatusCode::NOT_MODIFIED
+        {
+            return response;
+        }
+        let Some(expected) = response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+        else {
+            return response;
+        };
+
+        let health_check = &self.config.load().health_check;
+        let check = BodyLengthCheck {
+            backend: backend.to_string(),
+            expected,
+            received: 0,
+            gateway: Arc::downgrade(&self.current_gateway()),
+            unhealthy_threshold: health_check
+                .enabled
+                .then_some(health_check.unhealthy_threshold),
+        };
+        let (parts, body) = response.into_parts();
+        let stream = futures_util::stream::unfold(
+            Some((BodyStream::new(body), check)),
+            |state| async move {
+                use futures_util::StreamExt;
+
+                let (mut frames, mut check) = state?;
+                match frames.next().await {
+                    Some(Ok(frame)) => {
+                        check.received += frame.data_ref().map_or(0, |data| data.len() as u64);
+                        if check.received > check.expected {
+                            return Some((Err(check.mismatch("overflow").await), None));
+                        }
+                        Some((Ok(frame), Some((frames, check))))
+                    }
+                    Some(Err(e)) if check.received < check.expected => {
+                        tracing::debug!(error = %e, "backend response body failed");
+                        Some((Err(check.mismatch("truncated").await), None))
+                    }
+                    Some(Err(e)) => Some((Err(std::io::Error::other(e)), None)),
+                    None if check.received < check.expected => {
+                        Some((Err(check.mismatch("truncated").await), None))
+                    }
+                    None => None,
+                }
+            },
+        );
+        Response::from_parts(parts, AxumBody::new(StreamBody::new(stream)))
+    }
+
+    /// Reserve a request slot on `backend`, or on another healthy route
+    /// target when it is at `max_requests`; when every candidate is
+    /// saturated, queue for `backend`. `None` means no slot was free.
+    async fn reserve_backend(
+        &self,
+        gateway: &GatewayService,
+        backend: &str,
+        targets: &[String],
+    ) -> Option<(String, Option<BackendPermit>)> {
+        let Some(limiter) = gateway.get_backend_limiter(backend) else {
+            return Some((backend.to_string(), None));
+        };
+        if let Some(permit) = limiter.try_acquire() {
+            return Some((backend.to_string(), Some(permit)));
+        }
+
+        for candidate in gateway.get_healthy_backends(targets).await {
+            if candidate == backend {
+                continue;
+            }
+            let permit = match gateway.get_backend_limiter(&candidate) {
+                Some(other) => match other.try_acquire() {
+                    Some(permit) => Some(permit),
+                    None => continue,
+                },
+                None => None,
+            };
+            tracing::debug!(backend, rerouted_to = %candidate, "backend saturated");
+            crate::metrics::record_backend_saturated(backend, "rerouted");
+            return Some((candidate, permit));
+        }
+
+        match limiter.acquire_pending().await {
+            Some(permit) => {
+                crate::metrics::record_backend_saturated(backend, "queued");
+                Some((backend.to_string(), Some(permit)))
+            }
+            None => {
+                tracing::warn!(backend, "backend saturated; rejecting request");
+                crate::metrics::record_backend_saturated(backend, "rejected");
+                None
+            }
+        }
+    }
+
+    /// Send `req` to the reserved backend at `path_and_query`, as one attempt
+    /// of a proxied request. Its body is counted, tapped and watched for
+    /// client aborts. The returned future holds an active connection of the
+    /// backend until it finishes or is dropped, and fails once the backend is
+    /// removed from the configuration.
+    fn start_attempt(
+        &self,
+        gateway: &Arc<GatewayService>,
+        mut req: Request<AxumBody>,
+        (backend, permit): (String, Option<BackendPermit>),
+        group: Option<CanaryGroup>,
+        path_and_query: &str,
+        route_label: &str,
+    ) -> Result<(
+        BackendAttempt,
+        BoxFuture<'static, HttpClientResult<Response<AxumBody>>>,
+    )> {
+        *req.uri_mut() = format!("{}{path_and_query}", backend.trim_end_matches('/'))
+            .parse()
+            .wrap_err("Failed to parse backend URI")?;
+        let span = tracing_setup::create_backend_span(
+            &backend,
+            req.method().as_str(),
+            req.uri().path(),
+            req.headers()
+                .get(X_REQUEST_ID)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default(),
+        );
+        let transfer = TransferStats::upstream(route_label);
+        let tap = gateway
+            .taps()
+            .get(route_label)
+            .map(|tap| TapRecorder::start(tap, &backend, &req));
+        let client_aborted = Arc::new(AtomicBool::new(false));
+        let mut req = req
+            .map(|body| watch_client_body(body, client_aborted.clone()))
+            .map(|body| CountingBody::wrap(body, transfer.clone(), BodyKind::Request));
+        if let Some(tap) = &tap {
+            req = req.map(|body| tap.wrap(body, BodyKind::Request));
+        }
+
+        let removal = gateway.backend_cancellation(&backend);
+        let active = ActiveConnection::open(gateway, &backend);
+        let http_client = self.http_client.clone();
+        let cancelled = removal.clone();
+        let send = async move {
+            let _active = active;
+            let send = http_client.send_request(req);
+            match cancelled {
+                Some(token) => tokio::select! {
+                    result = send => result,
+                    () = token.cancelled() => Err(HttpClientError::Cancelled(
+                        "backend removed from configuration".to_string(),
+                    )),
+                },
+                None => send.await,
+            }
+        }
+        .instrument(span.clone());
+
+        let attempt = BackendAttempt {
+            backend,
+            permit,
+            group,
+            removal,
+            transfer,
+            tap,
+            client_aborted,
+            span,
+            start: Instant::now(),
+        };
+        Ok((attempt, Box::pin(send)))
+    }
+
+    /// Wait on `primary` for the route's hedge delay, then send the request to
+    /// another healthy backend as well. The first successful response wins and
+    /// the other request is dropped, which cancels it. Returns the hedge
+    /// attempt when the hedge won.
+    async fn race_hedge(
+        &self,
+        gateway: &Arc<GatewayService>,
+        hedger: &Hedger,
+        primary: impl Future<Output = HttpClientResult<Response<AxumBody>>>,
+        template: &Request<bytes::Bytes>,
+        primary_attempt: &BackendAttempt,
+        targets: HedgeTargets<'_>,
+    ) -> Result<(HttpClientResult<Response<AxumBody>>, Option<BackendAttempt>)> {
+        let start = Instant::now();
+        let record = |result: &HttpClientResult<Response<AxumBody>>| {
+            if result.is_ok() {
+                hedger.record_latency(start.elapsed());
+            }
+        };
+        tokio::pin!(primary);
+        tokio::select! {
+            result = &mut primary => {
+                record(&result);
+                return Ok((result, None));
+            }
+            () = tokio::time::sleep(hedger.delay()) => {}
+        }
+
+        let HedgeTargets {
+            pool,
+            strategy,
+            slow_start,
+            path_and_query,
+            route_label,
+        } = targets;
+        let others: Vec<String> = pool
+            .iter()
+            .filter(|target| **target != primary_attempt.backend)
+            .cloned()
+            .collect();
+        let mut hedge = None;
+        if let Some(candidate) = gateway.select_backend(&others, strategy, slow_start).await {
+            // A saturated backend is not worth hedging to
+            let permit = match gateway.get_backend_limiter(&candidate) {
+                Some(limiter) => limiter.try_acquire().map(Some),
+                None => Some(None),
+            };
+            if let Some(permit) = permit {
+                if hedger.try_hedge(Instant::now()) {
+                    hedge = Some((candidate, permit));
+                } else {
+                    crate::metrics::record_hedged_request(route_label, "budget_exhausted");
+                }
             }
         }
+        let Some(reserved) = hedge else {
+            let result = primary.await;
+            record(&result);
+            return Ok((result, None));
+        };
+
+        tracing::debug!(
+            backend = %primary_attempt.backend,
+            hedge_backend = %reserved.0,
+            "sending hedged request"
+        );
+        let (hedge, mut hedged) = self.start_attempt(
+            gateway,
+            replay_request(template),
+            reserved,
+            primary_attempt.group,
+            path_and_query,
+            route_label,
+        )?;
+        // Whichever fails first leaves the other to answer
+        let (result, hedge_won) = tokio::select! {
+            result = &mut primary => match result {
+                Ok(response) => (Ok(response), false),
+                Err(_) => (hedged.await, true),
+            },
+            result = &mut hedged => match result {
+                Ok(response) => (Ok(response), true),
+                Err(_) => (primary.await, false),
+            },
+        };
+        record(&result);
+        crate::metrics::record_hedged_request(route_label, if hedge_won { "won" } else { "lost" });
+        if hedge_won {
+            return Ok((result, Some(hedge)));
+        }
+        hedge.supersede();
+        Ok((result, None))
+    }
+
+    /// Log, count and notify an automatic canary rollback.
+    fn on_canary_rollback(&self, route_prefix: &str, state: &CanaryState, reason: &RollbackReason) {
+        tracing::warn!(
+            route = route_prefix,
+            reason = reason.as_str(),
+            "Canary rolled back: {}",
+            reason
+        );
+        crate::metrics::record_canary_rollback(route_prefix, reason.as_str());
+
+        let Some(webhook_url) = state.webhook_url() else {
+            return;
+        };
+        let payload = serde_json::json!({
+            "event": "canary_rollback",
+            "route": route_prefix,
+            "reason": reason.as_str(),
+            "detail": reason.to_string(),
+            "canary_targets": state.targets(),
+        });
+        let request = Request::post(webhook_url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(AxumBody::from(payload.to_string()));
+        let http_client = self.http_client.clone();
+        tokio::spawn(async move {
+            let result = match request {
+                Ok(request) => http_client
+                    .send_request(request)
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            match result {
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) => tracing::warn!(
+                    status = response.status().as_u16(),
+                    "Canary rollback webhook returned an error status"
+                ),
+                Err(e) => tracing::warn!(error = %e, "Canary rollback webhook failed"),
+            }
+        });
     }
 }
 
+/// Request extension marking a request for a route's `health_endpoint`.
+#[derive(Clone, Copy)]
+struct RouteHealthProbe;
+
+/// Full path of a route's health endpoint (`/api/` + `/_health` -> `/api/_health`).
+fn route_health_path(prefix: &str, endpoint: &str) -> String {
+    format!("{}{}", prefix.trim_end_matches('/'), endpoint)
+}
+
 impl Clone for HttpHandler {
     fn clone(&self) -> Self {
         Self {
@@ -1074,44 +3558,738 @@ impl Clone for HttpHandler {
             file_system: self.file_system.clone(),
             connection_tracker: self.connection_tracker.clone(),
             config: self.config.clone(),
+            middleware_registry: self.middleware_registry.clone(),
+            audit: self.audit.clone(),
+            events: self.events.clone(),
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use arc_swap::ArcSwap;
+/// Serves requests through [`HttpHandler::handle_request`], so the gateway
+/// can be mounted in an Axum or Tower stack next to other routes and layers.
+///
+/// The client address is taken from the `ConnectInfo<SocketAddr>` extension
+/// when present. Handler errors become a plain 500 response.
+impl tower::Service<Request<AxumBody>> for HttpHandler {
+    type Response = Response<AxumBody>;
+    type Error = Infallible;
+    type Future = futures_util::future::BoxFuture<'static, Result<Self::Response, Infallible>>;
 
-    use super::*;
-    use crate::config::models::ServerConfig;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
 
-    fn create_test_handler() -> HttpHandler {
-        let config = Arc::new(ServerConfig::default());
-        let gateway_service = Arc::new(GatewayService::new(config.clone()));
-        let gateway_holder = Arc::new(ArcSwap::from(gateway_service));
-        let http_client = Arc::new(crate::adapters::HttpClientAdapter::new().expect("client"))
-            as Arc<dyn HttpClient>;
-        let file_system = Arc::new(FileSystemAdapter::new());
-        let connection_tracker = Arc::new(ConnectionTracker::new());
-        let config_holder = Arc::new(ArcSwap::from(config));
+    fn call(&mut self, req: Request<AxumBody>) -> Self::Future {
+        let handler = self.clone();
+        let client_addr = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr);
+        Box::pin(async move {
+            match handler.handle_request(req, client_addr).await {
+                Ok(response) => Ok(response),
+                Err(e) => {
+                    tracing::error!("Request handling error: {:?}", e);
+                    Ok(error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Internal Server Error",
+                    ))
+                }
+            }
+        })
+    }
+}
 
-        HttpHandler::new(
-            gateway_holder,
-            http_client,
-            file_system,
-            connection_tracker,
-            config_holder,
+impl HttpHandler {
+    /// A router sending every path to this handler, for merging into an
+    /// application's own `Router` (or nesting under a prefix). Requests
+    /// without a path, such as `CONNECT`, reach it as the fallback.
+    pub fn into_router(self) -> Router {
+        Router::new()
+            .route_service("/", self.clone())
+            .route_service("/{*path}", self.clone())
+            .fallback_service(self)
+    }
+}
+
+/// Whether `headers` carry `Authorization: Bearer <token>`
+fn bearer_token_matches(headers: &HeaderMap, token: &str) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|presented| constant_time_eq(token, presented))
+}
+
+/// Progress of a backend body checked by [`HttpHandler::guard_content_length`].
+struct BodyLengthCheck {
+    backend: String,
+    expected: u64,
+    received: u64,
+    /// Weak so a long-running body does not keep a retired gateway alive
+    gateway: std::sync::Weak<GatewayService>,
+    /// Mark the backend unhealthy after this many consecutive failures; only
+    /// set when active health checks can bring it back.
+    unhealthy_threshold: Option<u32>,
+}
+
+impl BodyLengthCheck {
+    /// Log, count and penalize a mismatch; returns the error ending the body.
+    async fn mismatch(&self, kind: &'static str) -> std::io::Error {
+        tracing::error!(
+            event = "content_length_mismatch",
+            backend = %self.backend,
+            expected = self.expected,
+            received = self.received,
+            kind,
+            "Backend response body does not match Content-Length"
+        );
+        crate::metrics::record_body_length_mismatch(&self.backend, kind);
+        let marked_unhealthy = match self.gateway.upgrade() {
+            Some(gateway) => gateway
+                .backend_health()
+                .read_async(&self.backend, |_, health| {
+                    health.record_passive_failure(self.unhealthy_threshold)
+                })
+                .await
+                .unwrap_or(false),
+            None => false,
+        };
+        if marked_unhealthy {
+            tracing::warn!(
+                backend = %self.backend,
+                "Backend marked unhealthy after Content-Length mismatches"
+            );
+            notify(
+                Notification::new(NotificationEvent::BackendUnhealthy)
+                    .subject(&self.backend)
+                    .detail("consecutive Content-Length mismatches"),
+            );
+        }
+        let error_kind = if self.received < self.expected {
+            std::io::ErrorKind::UnexpectedEof
+        } else {
+            std::io::ErrorKind::InvalidData
+        };
+        std::io::Error::new(
+            error_kind,
+            format!(
+                "backend body {kind}: expected {} bytes, received {}",
+                self.expected, self.received
+            ),
         )
     }
+}
 
-    #[tokio::test]
-    async fn test_health_check_handler() {
-        let handler = create_test_handler();
-        let result = handler.handle_health_check().await;
+/// Which of `limits` the request headers break, if any.
+fn header_limit_exceeded(headers: &HeaderMap, limits: &HeaderLimitsConfig) -> Option<String> {
+    if headers.len() > limits.max_count {
+        return Some(format!(
+            "{} headers, more than the limit of {}",
+            headers.len(),
+            limits.max_count
+        ));
+    }
+    let mut total = 0;
+    for (name, value) in headers {
+        let size = name.as_str().len() + value.len();
+        if size > limits.max_header_bytes {
+            return Some(format!(
+                "header '{name}' has {size} bytes, more than the limit of {}",
+                limits.max_header_bytes
+            ));
+        }
+        total += size;
+    }
+    (total > limits.max_total_bytes).then(|| {
+        format!(
+            "headers have {total} bytes, more than the limit of {}",
+            limits.max_total_bytes
+        )
+    })
+}
 
-        assert!(result.is_ok());
-        let response = result.expect("health ok");
-        assert_eq!(response.status(), StatusCode::NOT_FOUND); // No backends configured
+/// Replace the request path with its normalized form, keeping the query.
+fn normalize_request_path(
+    req: &mut Request<AxumBody>,
+    config: &PathNormalizationConfig,
+) -> Result<(), PathNormalizationError> {
+    let Cow::Owned(path) = normalize_path(req.uri().path(), config)? else {
+        return Ok(());
+    };
+    let path_and_query = match req.uri().query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path,
+    };
+    let mut parts = req.uri().clone().into_parts();
+    parts.path_and_query = PathAndQuery::try_from(path_and_query).ok();
+    if let Ok(uri) = Uri::from_parts(parts) {
+        tracing::debug!(original = %req.uri(), normalized = %uri, "Normalized request path");
+        *req.uri_mut() = uri;
+    }
+    Ok(())
+}
+
+/// Tell browsers to trust the declared `Content-Type`.
+fn set_nosniff(headers: &mut HeaderMap) {
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+}
+
+/// Remove, then add (replacing) headers per a route's header actions.
+fn apply_header_actions(headers: &mut HeaderMap, actions: &HeaderActions) {
+    for name in &actions.remove {
+        headers.remove(name.as_str());
+    }
+    for (name, value) in &actions.add {
+        match (
+            http::HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => tracing::warn!(header = %name, "Skipping invalid header action"),
+        }
+    }
+}
+
+/// Replace `route_prefix` at the start of `path` with `rewrite`.
+///
+/// Both sides are normalised to avoid accidental double slashes (e.g.
+/// "/real" + "/foo" -> "/real/foo").
+fn rewrite_path(path: &str, route_prefix: &str, rewrite: &str) -> String {
+    let remaining_path = path.strip_prefix(route_prefix).unwrap_or(path);
+
+    // Helper: ensure a path segment starts with exactly one leading '/'
+    let normalise_leading = |s: &str| -> String {
+        if s.is_empty() {
+            "".to_string()
+        } else if s.starts_with('/') {
+            s.to_string()
+        } else {
+            format!("/{s}")
+        }
+    };
+
+    // Normalise rewrite base
+    let base = if rewrite == "/" {
+        "/".to_string()
+    } else {
+        normalise_leading(rewrite.trim_end_matches('/'))
+    };
+
+    if remaining_path.is_empty() || remaining_path == "/" {
+        // Exactly the route prefix only: use the base as-is
+        base
+    } else {
+        let remaining = remaining_path.trim_start_matches('/');
+        if base == "/" {
+            format!("/{remaining}")
+        } else {
+            format!("{base}/{remaining}")
+        }
+    }
+}
+
+/// Where [`HttpHandler::race_hedge`] may send its hedge
+struct HedgeTargets<'a> {
+    pool: &'a [String],
+    strategy: Option<crate::config::LoadBalanceStrategy>,
+    slow_start: Option<Duration>,
+    path_and_query: &'a str,
+    route_label: &'a str,
+}
+
+/// Whether the request has a key in `header`, after adding a generated one
+/// when it has none and `generate` is set
+fn ensure_idempotency_key(headers: &mut HeaderMap, header: &str, generate: bool) -> Result<bool> {
+    if headers.contains_key(header) {
+        return Ok(true);
+    }
+    if !generate {
+        return Ok(false);
+    }
+    let name = http::HeaderName::from_bytes(header.as_bytes())
+        .wrap_err("Invalid idempotency header name")?;
+    let key =
+        HeaderValue::from_str(&Uuid::new_v4().to_string()).wrap_err("Invalid idempotency key")?;
+    headers.insert(name, key);
+    Ok(true)
+}
+
+/// A copy of a buffered request, for sending it again
+fn replay_request(template: &Request<bytes::Bytes>) -> Request<AxumBody> {
+    let mut req = Request::new(AxumBody::from(template.body().clone()));
+    *req.method_mut() = template.method().clone();
+    *req.uri_mut() = template.uri().clone();
+    *req.version_mut() = template.version();
+    *req.headers_mut() = template.headers().clone();
+    *req.extensions_mut() = template.extensions().clone();
+    req
+}
+
+/// Whether a backend attempt failed in a way worth retrying
+fn is_retryable_outcome(
+    result: &Result<Response<AxumBody>, HttpClientError>,
+    retry_on: &[u16],
+) -> bool {
+    match result {
+        Ok(response) => retry_on.contains(&response.status().as_u16()),
+        Err(HttpClientError::InvalidRequest(_)) => false,
+        Err(_) => true,
+    }
+}
+
+/// Set `aborted` when the client's request body fails, as it does when the
+/// client disconnects mid-upload.
+fn watch_client_body(body: AxumBody, aborted: Arc<AtomicBool>) -> AxumBody {
+    AxumBody::new(body.map_err(move |e| {
+        aborted.store(true, Ordering::Relaxed);
+        e
+    }))
+}
+
+/// One request sent to one backend, and what the response is wrapped in if
+/// it is the attempt that answers.
+struct BackendAttempt {
+    backend: String,
+    /// Request slot on the backend, held until the response body ends
+    permit: Option<BackendPermit>,
+    group: Option<CanaryGroup>,
+    /// Cancelled when the backend is removed from the configuration
+    removal: Option<CancellationToken>,
+    transfer: Arc<TransferStats>,
+    tap: Option<Arc<TapRecorder>>,
+    client_aborted: Arc<AtomicBool>,
+    span: tracing::Span,
+    start: Instant,
+}
+
+impl BackendAttempt {
+    /// Note how the backend answered on the attempt's span and tap.
+    fn record_outcome(&self, result: &HttpClientResult<Response<AxumBody>>) {
+        self.span
+            .record("duration_ms", self.start.elapsed().as_millis() as u64);
+        match result {
+            Ok(response) => {
+                self.span
+                    .record("http.status_code", response.status().as_u16());
+                if let Some(tap) = &self.tap {
+                    tap.record_response(response);
+                }
+            }
+            Err(e) => {
+                if let Some(tap) = &self.tap {
+                    tap.record_error(e);
+                }
+            }
+        }
+    }
+
+    /// The other side of a hedge answered; this attempt's request was
+    /// dropped.
+    fn supersede(self) {
+        if let Some(tap) = &self.tap {
+            tap.record_error(&"superseded by the other side of a hedged request");
+        }
+    }
+
+    /// Count and tap the response body, keep the backend's request slot until
+    /// it ends, and cut it off when the backend is removed if
+    /// `cancel_on_removal`.
+    fn wrap_response(
+        self,
+        response: Response<AxumBody>,
+        cancel_on_removal: bool,
+    ) -> Response<AxumBody> {
+        let mut response =
+            response.map(|body| CountingBody::wrap(body, self.transfer, BodyKind::Response));
+        if let Some(tap) = self.tap {
+            response = response.map(|body| tap.wrap(body, BodyKind::Response));
+        }
+        if let Some(token) = self.removal.filter(|_| cancel_on_removal) {
+            response = response.map(|body| cancellable_body(body, token));
+        }
+        if let Some(permit) = self.permit {
+            // Streaming bodies keep the backend busy until they finish
+            response = response.map(|body| {
+                AxumBody::new(body.map_frame(move |frame| {
+                    let _slot = &permit;
+                    frame
+                }))
+            });
+        }
+        response
+    }
+}
+
+/// Counts as an active connection of a backend for as long as it lives.
+struct ActiveConnection {
+    gateway: Arc<GatewayService>,
+    backend: String,
+}
+
+impl ActiveConnection {
+    fn open(gateway: &Arc<GatewayService>, backend: &str) -> Self {
+        gateway
+            .backend_health()
+            .read_sync(backend, |_, health| health.inc_active_connections());
+        Self {
+            gateway: gateway.clone(),
+            backend: backend.to_string(),
+        }
+    }
+}
+
+impl Drop for ActiveConnection {
+    fn drop(&mut self) {
+        self.gateway
+            .backend_health()
+            .read_sync(&self.backend, |_, health| health.dec_active_connections());
+    }
+}
+
+/// Armed while a backend request is in flight. When the client disconnects,
+/// the server drops the handler future and with it the backend request,
+/// releasing its [`ActiveConnection`]; the guard then records the
+/// cancellation.
+struct AbandonedRequestGuard {
+    backend: String,
+    route: String,
+    span: tracing::Span,
+    armed: bool,
+}
+
+impl AbandonedRequestGuard {
+    fn new(backend: &str, route: &str) -> Self {
+        Self {
+            backend: backend.to_string(),
+            route: route.to_string(),
+            span: tracing::Span::current(),
+            armed: true,
+        }
+    }
+
+    /// The backend request finished; nothing to clean up.
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for AbandonedRequestGuard {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        crate::metrics::record_client_disconnect(&self.route, "awaiting_response");
+        self.span.record("client.disconnected", true);
+        tracing::warn!(
+            parent: &self.span,
+            backend = %self.backend,
+            client_disconnected = true,
+            "client disconnected; backend request cancelled"
+        );
+    }
+}
+
+/// End a streaming response body with an error once `token` is cancelled.
+fn cancellable_body(body: AxumBody, token: CancellationToken) -> AxumBody {
+    let stream =
+        futures_util::stream::unfold(Some((BodyStream::new(body), token)), |state| async move {
+            use futures_util::StreamExt;
+
+            let (mut frames, token) = state?;
+            tokio::select! {
+                biased;
+                () = token.cancelled() => Some((
+                    Err(std::io::Error::other("backend removed from configuration")),
+                    None,
+                )),
+                frame = frames.next() => match frame {
+                    Some(Ok(frame)) => Some((Ok(frame), Some((frames, token)))),
+                    Some(Err(e)) => Some((Err(std::io::Error::other(e)), None)),
+                    None => None,
+                },
+            }
+        });
+    AxumBody::new(StreamBody::new(stream))
+}
+
+/// Metric label for a route: its prefix, plus `@host` for host-specific routes.
+fn route_label(prefix: &str, route_config: &RouteConfig) -> String {
+    let (RouteConfig::Static { host, .. }
+    | RouteConfig::Redirect { host, .. }
+    | RouteConfig::Proxy { host, .. }
+    | RouteConfig::LoadBalance { host, .. }
+    | RouteConfig::Websocket { host, .. }) = route_config;
+    match host {
+        Some(host) => format!("{prefix}@{}", host.to_lowercase()),
+        None => prefix.to_string(),
+    }
+}
+
+/// Replacement body for a route's body actions (`set_json` wins over
+/// `set_text`), updating the content headers to match.
+fn replace_body(headers: &mut HeaderMap, actions: &BodyActions) -> Option<AxumBody> {
+    set_body(
+        headers,
+        actions.set_json.as_ref(),
+        actions.set_text.as_deref(),
+    )
+}
+
+/// Remove headers not matched by `allowlist` (names, or prefixes ending in
+/// `*`). Framing headers are always kept.
+fn retain_allowed_headers(headers: &mut HeaderMap, allowlist: &[String]) {
+    let allowed = |name: &http::HeaderName| {
+        let name = name.as_str();
+        name == "content-length"
+            || name == "transfer-encoding"
+            || allowlist.iter().any(|entry| match entry.strip_suffix('*') {
+                Some(prefix) => name
+                    .get(..prefix.len())
+                    .is_some_and(|head| head.eq_ignore_ascii_case(prefix)),
+                None => entry.eq_ignore_ascii_case(name),
+            })
+    };
+    let removed: Vec<_> = headers
+        .keys()
+        .filter(|name| !allowed(name))
+        .cloned()
+        .collect();
+    for name in removed {
+        headers.remove(&name);
+    }
+}
+
+/// Apply streaming `replace` and `inject_html` actions to a response body.
+///
+/// Empty and compressed bodies, and content types the rewrite does not cover,
+/// pass through unchanged. The length changes, so `Content-Length` is dropped
+/// and a strong `ETag` is weakened.
+fn rewrite_body(response: Response<AxumBody>, rewrite: &BodyRewrite) -> Response<AxumBody> {
+    let headers = response.headers();
+    if matches!(
+        response.status(),
+        StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+    ) || headers
+        .get(header::CONTENT_ENCODING)
+        .is_some_and(|v| v.as_bytes() != b"identity")
+    {
+        return response;
+    }
+    let Some(rewriter) = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|content_type| rewrite.rewriter(content_type))
+    else {
+        return response;
+    };
+
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    if let Some(etag) = parts.headers.get(header::ETAG)
+        && !etag.as_bytes().starts_with(b"W/")
+        && let Ok(weak) = HeaderValue::from_bytes(&[b"W/", etag.as_bytes()].concat())
+    {
+        parts.headers.insert(header::ETAG, weak);
+    }
+    let stream = futures_util::stream::unfold(
+        Some((body.into_data_stream(), rewriter)),
+        |state| async move {
+            use futures_util::StreamExt;
+
+            let (mut chunks, mut rewriter) = state?;
+            loop {
+                match chunks.next().await {
+                    Some(Ok(chunk)) => {
+                        let out = rewriter.push(&chunk);
+                        if !out.is_empty() {
+                            return Some((Ok(bytes::Bytes::from(out)), Some((chunks, rewriter))));
+                        }
+                    }
+                    Some(Err(e)) => return Some((Err(e), None)),
+                    None => return Some((Ok(bytes::Bytes::from(rewriter.finish())), None)),
+                }
+            }
+        },
+    );
+    Response::from_parts(parts, AxumBody::from_stream(stream))
+}
+
+/// A body of `json`, or else `text`, with headers to match
+fn set_body(
+    headers: &mut HeaderMap,
+    json: Option<&serde_json::Value>,
+    text: Option<&str>,
+) -> Option<AxumBody> {
+    let (content, content_type) = match (json, text) {
+        (Some(json), _) => (json.to_string(), "application/json"),
+        (None, Some(text)) => (text.to_string(), "text/plain; charset=utf-8"),
+        (None, None) => return None,
+    };
+    headers.remove(header::TRANSFER_ENCODING);
+    headers.remove(header::CONTENT_ENCODING);
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(content.len()));
+    Some(AxumBody::from(content))
+}
+
+/// Body of `PUT /admin/steering`.
+#[derive(serde::Deserialize)]
+struct SteeringUpdate {
+    /// Relative weight per backend URL
+    weights: std::collections::HashMap<String, f64>,
+    /// Seconds the weights stay in effect
+    #[serde(default = "default_steering_ttl_secs")]
+    ttl_secs: u64,
+    /// Drop weights for backends not listed in this update
+    #[serde(default)]
+    replace: bool,
+}
+
+fn default_steering_ttl_secs() -> u64 {
+    300
+}
+
+/// Labels of the routes a tap can record, i.e. those with backends
+fn tappable_routes(config: &ServerConfig) -> HashSet<String> {
+    config
+        .all_routes()
+        .filter(|(_, route)| {
+            matches!(
+                route,
+                RouteConfig::Proxy { .. } | RouteConfig::LoadBalance { .. }
+            )
+        })
+        .map(|(prefix, route)| route_label(prefix, route))
+        .collect()
+}
+
+/// Body of `PUT /admin/logging`.
+#[derive(serde::Deserialize)]
+struct LogFilterUpdate {
+    /// `EnvFilter` directives, e.g. `info,axon::adapters::http_client=debug`
+    filter: String,
+}
+
+/// The active and configured log filters, if the filter can be changed
+fn log_filter_json(config: &crate::config::TracingConfig) -> Option<serde_json::Value> {
+    let active = tracing_setup::log_filter()?;
+    Some(serde_json::json!({
+        "filter": active,
+        "configured": tracing_setup::configured_filter(config),
+    }))
+}
+
+fn logging_not_reloadable() -> Response<AxumBody> {
+    error_response(
+        StatusCode::CONFLICT,
+        "Logging was not set up from the [tracing] configuration",
+    )
+}
+
+fn steering_json(weights: &[(String, f64, Duration)]) -> serde_json::Value {
+    serde_json::json!({
+        "weights": weights
+            .iter()
+            .map(|(backend, weight, remaining)| serde_json::json!({
+                "backend": backend,
+                "weight": weight,
+                "expires_in_secs": remaining.as_secs(),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use arc_swap::ArcSwap;
+
+    use super::*;
+    use crate::{adapters::FileSystemAdapter, config::models::ServerConfig};
+
+    /// A proxy route to `target` with the given fields set, e.g.
+    /// `proxy_route!(backend, retry: Some(retry))`
+    macro_rules! proxy_route {
+        ($target:expr, $($field:ident: $value:expr),+ $(,)?) => {{
+            let mut route = RouteConfig::proxy($target);
+            $(
+                let value = $value;
+                if let RouteConfig::Proxy { $field, .. } = &mut route {
+                    *$field = value;
+                }
+            )+
+            route
+        }};
+    }
+
+    /// A round-robin load-balanced route over `targets` with the given fields set
+    macro_rules! load_balance_route {
+        ($targets:expr, $($field:ident: $value:expr),+ $(,)?) => {{
+            let mut route = RouteConfig::load_balance(
+                $targets,
+                crate::config::LoadBalanceStrategy::RoundRobin,
+            );
+            $(
+                let value = $value;
+                if let RouteConfig::LoadBalance { $field, .. } = &mut route {
+                    *$field = value;
+                }
+            )+
+            route
+        }};
+    }
+
+    fn create_test_handler() -> HttpHandler {
+        handler_for(Arc::new(ServerConfig::default()))
+    }
+
+    /// A handler serving `config` through a gateway of its own
+    fn handler_for(config: Arc<ServerConfig>) -> HttpHandler {
+        let gateway = Arc::new(ArcSwap::from_pointee(GatewayService::new(config.clone())));
+        handler_with_gateway(gateway, config)
+    }
+
+    /// A handler serving `config` through `gateway`, for tests that inspect
+    /// or swap the gateway
+    fn handler_with_gateway(
+        gateway: Arc<ArcSwap<GatewayService>>,
+        config: Arc<ServerConfig>,
+    ) -> HttpHandler {
+        HttpHandler::new(
+            gateway,
+            Arc::new(crate::adapters::HttpClientAdapter::new().expect("client")),
+            Arc::new(FileSystemAdapter::new()),
+            Arc::new(ConnectionTracker::new()),
+            Arc::new(ArcSwap::from(config)),
+        )
+    }
+
+    /// Serve `app` on a local port as a backend, returning its URL
+    async fn spawn_backend(app: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let url = format!("http://{}", listener.local_addr().expect("addr"));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_health_check_handler() {
+        let handler = create_test_handler();
+        let result = handler.handle_health_check().await;
+
+        assert!(result.is_ok());
+        let response = result.expect("health ok");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND); // No backends configured
     }
 
     #[tokio::test]
@@ -1126,6 +4304,2712 @@ mod tests {
             response.headers().get(header::CONTENT_TYPE).expect("ct"),
             "text/plain; version=0.0.4"
         );
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains(&format!(
+            "axon_build_info{{version=\"{}\",commit=\"{}\"",
+            build_info::VERSION,
+            build_info::GIT_COMMIT
+        )));
+        assert!(body.contains("axon_config_generation 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_wrapped_response_bodies_keep_trailers() {
+        let with_trailers = || {
+            let mut trailers = HeaderMap::new();
+            trailers.insert("grpc-status", HeaderValue::from_static("0"));
+            let frames = futures_util::stream::iter([
+                Ok::<_, std::io::Error>(hyper::body::Frame::data(bytes::Bytes::from("ok"))),
+                Ok(hyper::body::Frame::trailers(trailers)),
+            ]);
+            AxumBody::new(StreamBody::new(frames))
+        };
+
+        let body = cancellable_body(with_trailers(), CancellationToken::new());
+        let collected = body.collect().await.expect("body");
+        assert_eq!(
+            collected.trailers().unwrap().get("grpc-status").unwrap(),
+            "0"
+        );
+        assert_eq!(collected.to_bytes(), "ok");
+
+        let handler = create_test_handler();
+        let response = Response::builder()
+            .header(header::CONTENT_LENGTH, "2")
+            .body(with_trailers())
+            .unwrap();
+        let response = handler.guard_content_length(response, "http://backend", false);
+        let collected = response.into_body().collect().await.expect("body");
+        assert_eq!(
+            collected.trailers().unwrap().get("grpc-status").unwrap(),
+            "0"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_uptime_and_build() {
+        let handler = create_test_handler();
+        let response = handler.handle_status().await.expect("status");
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let status: serde_json::Value = serde_json::from_slice(&body).expect("json");
+
+        assert!(status["uptime_secs"].is_u64());
+        assert_eq!(status["build"]["commit"], build_info::GIT_COMMIT);
+        assert_eq!(status["config_generation"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_honored_or_generated() {
+        let handler = create_test_handler();
+
+        let request = Request::builder()
+            .uri("/status")
+            .header(X_REQUEST_ID, "client-abc-123")
+            .body(AxumBody::empty())
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        assert_eq!(
+            response.headers().get(X_REQUEST_ID).expect("request id"),
+            "client-abc-123"
+        );
+
+        let request = Request::builder()
+            .uri("/status")
+            .header(X_REQUEST_ID, "has spaces")
+            .body(AxumBody::empty())
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        let generated = response
+            .headers()
+            .get(X_REQUEST_ID)
+            .and_then(|v| v.to_str().ok())
+            .expect("request id");
+        assert!(Uuid::parse_str(generated).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_gateway_errors_use_error_pages() {
+        let handler = create_test_handler();
+        let request = Request::builder()
+            .uri("/missing")
+            .header(X_REQUEST_ID, "req-404")
+            .body(AxumBody::empty())
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).expect("ct"),
+            "application/json"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let error: serde_json::Value = serde_json::from_slice(&body).expect("json");
+        assert_eq!(error["error"]["status"], 404);
+        assert_eq!(error["error"]["request_id"], "req-404");
+
+        let mut config = ServerConfig::default();
+        config.error_pages.templates.insert(
+            "4xx".to_string(),
+            crate::config::models::ErrorPageTemplate {
+                html: Some("<h1>{{status}} at {{path}}</h1>".to_string()),
+                ..Default::default()
+            },
+        );
+        handler.config.store(Arc::new(config));
+        let request = Request::builder()
+            .uri("/missing")
+            .header(header::ACCEPT, "text/html")
+            .body(AxumBody::empty())
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        assert_eq!(&body[..], b"<h1>404 at /missing</h1>");
+    }
+
+    #[tokio::test]
+    async fn test_builtin_endpoint_paths_and_access_rules() {
+        use crate::config::models::{AdminConfig, EndpointConfig, EndpointsConfig};
+
+        let config = Arc::new(ServerConfig {
+            admin: AdminConfig {
+                enabled: true,
+                token: Some("admin-secret".to_string()),
+            },
+            endpoints: EndpointsConfig {
+                health: EndpointConfig {
+                    enabled: false,
+                    ..EndpointConfig::default()
+                },
+                metrics: EndpointConfig {
+                    path: Some("/_gateway/metrics".to_string()),
+                    allow: vec!["10.0.0.0/8".to_string()],
+                    token: Some("scrape-secret".to_string()),
+                    ..EndpointConfig::default()
+                },
+                status: EndpointConfig {
+                    admin: true,
+                    ..EndpointConfig::default()
+                },
+            },
+            ..ServerConfig::default()
+        });
+        let handler = handler_for(config);
+        let status = |uri: &str, from: &str, token: Option<&str>| {
+            let mut request = Request::builder().uri(uri);
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+            }
+            let request = request.body(AxumBody::empty()).expect("request");
+            let handler = handler.clone();
+            let from: SocketAddr = from.parse().expect("addr");
+            async move {
+                let response = handler.handle_request(request, Some(from)).await;
+                response.expect("ok").status()
+            }
+        };
+        let inside = "10.1.2.3:40000";
+        let outside = "203.0.113.5:40000";
+
+        // The old paths now reach routing, which has nothing for them
+        for uri in ["/health", "/metrics", "/status"] {
+            assert_eq!(status(uri, inside, None).await, StatusCode::NOT_FOUND);
+        }
+        assert_eq!(
+            status("/_gateway/metrics", outside, Some("scrape-secret")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status("/_gateway/metrics", inside, Some("wrong")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status("/_gateway/metrics", inside, Some("scrape-secret")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status("/admin/endpoints/status", outside, None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status("/admin/endpoints/status", outside, Some("admin-secret")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status("/admin/endpoints/health", outside, Some("admin-secret")).await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ip_rate_limits_key_on_the_forwarded_client() {
+        use crate::config::models::{
+            ClientIpConfig, MissingKeyPolicy, RateLimitAlgorithm, RateLimitBy, RateLimitConfig,
+            RateLimits,
+        };
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .client_ip(ClientIpConfig {
+                    trusted_proxies: vec!["10.0.0.0/8".to_string()],
+                })
+                .route(
+                    "/limited-by-ip",
+                    RouteConfig::Redirect {
+                        target: "/elsewhere".to_string(),
+                        host: None,
+                        status_code: None,
+                        rate_limit: Some(RateLimits::from(RateLimitConfig {
+                            name: None,
+                            by: RateLimitBy::Ip,
+                            header_name: None,
+                            key: vec![],
+                            requests: 1,
+                            period: "1m".to_string(),
+                            status_code: 429,
+                            message: "Too Many Requests".to_string(),
+                            algorithm: RateLimitAlgorithm::TokenBucket,
+                            on_missing_key: MissingKeyPolicy::Allow,
+                            max_keys: 100_000,
+                            key_ttl: None,
+                        })),
+                        middlewares: vec![],
+                        error_pages: None,
+                        disable_http3: false,
+                        priority: None,
+                    },
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+        // No ConnectInfo extension, as on HTTP/3; the handler's address is used
+        let status = |peer: &str, forwarded_for: &str| {
+            let request = Request::builder()
+                .uri("/limited-by-ip")
+                .header("X-Forwarded-For", forwarded_for)
+                .body(AxumBody::empty())
+                .expect("request");
+            let handler = handler.clone();
+            let peer: SocketAddr = peer.parse().expect("addr");
+            async move {
+                let response = handler.handle_request(request, Some(peer)).await;
+                response.expect("ok").status()
+            }
+        };
+
+        assert_eq!(
+            status("10.0.0.1:4000", "203.0.113.1").await,
+            StatusCode::FOUND
+        );
+        assert_eq!(
+            status("10.0.0.2:4000", "203.0.113.2").await,
+            StatusCode::FOUND
+        );
+        assert_eq!(
+            status("10.0.0.2:4000", "203.0.113.1").await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        // An untrusted peer is limited by its own address, whatever it forwards
+        assert_eq!(
+            status("198.51.100.1:4000", "203.0.113.3").await,
+            StatusCode::FOUND
+        );
+        assert_eq!(
+            status("198.51.100.1:4000", "203.0.113.4").await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_decisions_are_exported() {
+        use crate::config::models::{
+            MissingKeyPolicy, RateLimitAlgorithm, RateLimitBy, RateLimitConfig, RateLimits,
+        };
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/limited-metrics",
+                    RouteConfig::Redirect {
+                        target: "/elsewhere".to_string(),
+                        host: None,
+                        status_code: None,
+                        rate_limit: Some(RateLimits::from(RateLimitConfig {
+                            name: None,
+                            by: RateLimitBy::Route,
+                            header_name: None,
+                            key: vec![],
+                            requests: 1,
+                            period: "1m".to_string(),
+                            status_code: 429,
+                            message: "Too Many Requests".to_string(),
+                            algorithm: RateLimitAlgorithm::TokenBucket,
+                            on_missing_key: MissingKeyPolicy::Allow,
+                            max_keys: 100_000,
+                            key_ttl: None,
+                        })),
+                        middlewares: vec![],
+                        error_pages: None,
+                        disable_http3: false,
+                        priority: None,
+                    },
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+
+        for expected in [StatusCode::FOUND, StatusCode::TOO_MANY_REQUESTS] {
+            let request = Request::builder()
+                .uri("/limited-metrics")
+                .body(AxumBody::empty())
+                .expect("request");
+            let response = handler.handle_request(request, None).await.expect("ok");
+            assert_eq!(response.status(), expected);
+        }
+
+        let request = Request::builder()
+            .uri("/metrics")
+            .body(AxumBody::empty())
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let text = String::from_utf8_lossy(&body);
+        for line in [
+            "axon_rate_limit_allowed_total{route=\"/limited-metrics\",limiter=\"route\"} 1",
+            "axon_rate_limit_rejected_total{route=\"/limited-metrics\",limiter=\"route\"} 1",
+            "axon_rate_limit_configured_requests{route=\"/limited-metrics\",limiter=\"route\",period=\"1m\"} 1",
+        ] {
+            assert!(text.contains(line), "missing {line} in {text}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_truncated_backend_body_is_aborted() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let backend = format!("http://{}", listener.local_addr().expect("addr"));
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.expect("accept");
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            let _ = socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\nonly ten b")
+                .await;
+        });
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route("/", RouteConfig::proxy(backend.clone()))
+                .build()
+                .expect("config"),
+        );
+        let gateway = Arc::new(GatewayService::new(config.clone()));
+        let handler = handler_with_gateway(Arc::new(ArcSwap::from(gateway.clone())), config);
+
+        let request = Request::builder()
+            .uri("/file")
+            .body(AxumBody::empty())
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(to_bytes(response.into_body(), usize::MAX).await.is_err());
+
+        let failures = gateway
+            .backend_health()
+            .get_async(&backend)
+            .await
+            .map(|entry| entry.get().consecutive_failures());
+        assert_eq!(failures, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_client_disconnects_cancel_backend_requests() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let backend = format!("http://{}", listener.local_addr().expect("addr"));
+        let (events_tx, mut events) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            // Never answers; reading until EOF shows when the gateway gives up
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let events_tx = events_tx.clone();
+                tokio::spawn(async move {
+                    let _ = events_tx.send("accepted");
+                    let mut buf = [0u8; 1024];
+                    while socket.read(&mut buf).await.is_ok_and(|n| n > 0) {}
+                    let _ = events_tx.send("closed");
+                    let _ = socket.shutdown().await;
+                });
+            }
+        });
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route("/", RouteConfig::proxy(backend.clone()))
+                .build()
+                .expect("config"),
+        );
+        let gateway = Arc::new(GatewayService::new(config.clone()));
+        let handler = handler_with_gateway(Arc::new(ArcSwap::from(gateway.clone())), config);
+        let active_connections = || {
+            gateway
+                .backend_health()
+                .read_sync(&backend, |_, health| health.active_connections())
+        };
+
+        // The server drops the handler when the client goes away
+        let request = Request::builder()
+            .uri("/slow")
+            .body(AxumBody::empty())
+            .expect("request");
+        let pending = tokio::spawn({
+            let handler = handler.clone();
+            async move { handler.handle_request(request, None).await }
+        });
+        assert_eq!(events.recv().await, Some("accepted"));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(active_connections(), Some(1));
+        pending.abort();
+        let closed = tokio::time::timeout(Duration::from_secs(2), events.recv()).await;
+        assert_eq!(closed.expect("backend connection closed"), Some("closed"));
+        assert_eq!(active_connections(), Some(0));
+
+        // An upload that breaks off is not the backend's failure
+        let chunks: Vec<Result<&str, std::io::Error>> = vec![
+            Ok("partial"),
+            Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset)),
+        ];
+        let request = Request::builder()
+            .method("POST")
+            .uri("/upload")
+            .body(AxumBody::from_stream(futures_util::stream::iter(chunks)))
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        assert_eq!(response.status().as_u16(), 499);
+        let failures = gateway
+            .backend_health()
+            .read_sync(&backend, |_, health| health.consecutive_failures());
+        assert_eq!(failures, Some(0));
+        assert_eq!(active_connections(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_body_bytes_are_counted_per_route() {
+        use axum::routing::post;
+
+        let app = axum::Router::new().fallback(post(|body: bytes::Bytes| async move {
+            format!("received {} bytes", body.len())
+        }));
+        let backend = spawn_backend(app).await;
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route("/bytes-test", RouteConfig::proxy(backend))
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/bytes-test/upload")
+            .body(AxumBody::from("0123456789"))
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        assert_eq!(body, "received 10 bytes");
+
+        let exposition = crate::metrics::route_bytes_exposition();
+        for line in [
+            "axon_request_bytes_total{route=\"/bytes-test\",direction=\"in\"} 10\n",
+            "axon_request_bytes_total{route=\"/bytes-test\",direction=\"out\"} 10\n",
+            "axon_response_bytes_total{route=\"/bytes-test\",direction=\"in\"} 17\n",
+            "axon_response_bytes_total{route=\"/bytes-test\",direction=\"out\"} 17\n",
+        ] {
+            assert!(
+                exposition.contains(line),
+                "missing {line:?} in {exposition}"
+            );
+        }
+    }
+
+    /// Formatted log output of the current thread, for asserting on events
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().expect("logs").extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl CapturedLogs {
+        fn subscribe(&self) -> tracing::subscriber::DefaultGuard {
+            let logs = self.clone();
+            tracing::subscriber::set_default(
+                tracing_subscriber::fmt()
+                    .with_ansi(false)
+                    .with_writer(move || logs.clone())
+                    .finish(),
+            )
+        }
+
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8_lossy(&self.0.lock().expect("logs"))
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_requests_are_counted() {
+        let app = axum::Router::new().fallback(|uri: http::Uri| async move {
+            if uri.path().ends_with("/slow") {
+                tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+            }
+            "done"
+        });
+        let backend = spawn_backend(app).await;
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/slow-test",
+                    proxy_route!(
+                        backend,
+                        slow_request_threshold_ms: Some(30),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+
+        let logs = CapturedLogs::default();
+        let _logging = logs.subscribe();
+        for path in ["/slow-test/fast", "/slow-test/slow", "/slow-test/fast"] {
+            let request = Request::builder()
+                .uri(path)
+                .body(AxumBody::empty())
+                .expect("request");
+            let response = handler.handle_request(request, None).await.expect("ok");
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        assert!(
+            crate::metrics::slow_request_exposition()
+                .contains("axon_slow_requests_total{route=\"/slow-test\"} 1\n")
+        );
+        // The slow request's log entry breaks down the backend phases
+        let lines = logs.lines();
+        let slow = lines
+            .iter()
+            .find(|line| line.contains("slow request"))
+            .expect("slow request logged");
+        for field in ["queue_ms=", "connect_ms=", "backend_ttfb_ms="] {
+            assert!(slow.contains(field), "missing {field} in {slow}");
+        }
+        let ttfb: u64 = slow
+            .split("backend_ttfb_ms=")
+            .nth(1)
+            .and_then(|rest| rest.split(' ').next())
+            .and_then(|ms| ms.parse().ok())
+            .expect("backend_ttfb_ms value");
+        assert!(ttfb >= 60, "{slow}");
+    }
+
+    #[tokio::test]
+    async fn test_accept_language_selects_locale_group() {
+        async fn backend(name: &'static str) -> String {
+            let app = axum::Router::new().fallback(move |headers: HeaderMap| async move {
+                let language = headers
+                    .get("x-matched-language")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("-")
+                    .to_string();
+                format!("{name}:{language}")
+            });
+            spawn_backend(app).await
+        }
+        let default_backend = backend("default").await;
+        let eu_backend = backend("eu").await;
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/",
+                    proxy_route!(
+                        default_backend,
+                        locale: Some(crate::config::models::LocaleRoutingConfig {
+                            groups: [("de".to_string(), vec![eu_backend])].into(),
+                            header: "X-Matched-Language".to_string(),
+                        }),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+
+        let fetch = |accept_language: &'static str| {
+            let request = Request::builder()
+                .uri("/page")
+                .header(header::ACCEPT_LANGUAGE, accept_language)
+                .header("X-Matched-Language", "spoofed")
+                .body(AxumBody::empty())
+                .expect("request");
+            let handler = handler.clone();
+            async move {
+                let response = handler.handle_request(request, None).await.expect("ok");
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("body");
+                String::from_utf8(body.to_vec()).expect("utf8")
+            }
+        };
+        assert_eq!(fetch("de-AT, en;q=0.5").await, "eu:de");
+        assert_eq!(fetch("en-US, de;q=0.3").await, "eu:de");
+        assert_eq!(fetch("fr, en").await, "default:-");
+    }
+
+    #[tokio::test]
+    async fn test_webhook_redeliveries_are_deduplicated() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let backend_hits = hits.clone();
+        let app = axum::Router::new().fallback(move || {
+            let hits = backend_hits.clone();
+            async move {
+                // The first delivery fails, so the provider's retry must get through
+                match hits.fetch_add(1, Ordering::SeqCst) {
+                    0 => StatusCode::INTERNAL_SERVER_ERROR,
+                    _ => StatusCode::OK,
+                }
+            }
+        });
+        let backend = spawn_backend(app).await;
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/hooks",
+                    proxy_route!(
+                        backend,
+                        dedupe: Some(DedupeConfig {
+                            headers: vec!["X-Delivery-Id".to_string()],
+                            include_body: true,
+                            ttl_secs: 60,
+                            status_code: 202,
+                            response_body: Some("duplicate".to_string()),
+                            max_body_size: 1024,
+                            max_entries: 100,
+                        }),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+
+        let deliver = |id: &'static str| {
+            let request = Request::builder()
+                .method("POST")
+                .uri("/hooks")
+                .header("X-Delivery-Id", id)
+                .body(AxumBody::from(r#"{"event":"push"}"#))
+                .expect("request");
+            let handler = handler.clone();
+            async move {
+                let response = handler.handle_request(request, None).await.expect("ok");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("body");
+                (status, String::from_utf8(body.to_vec()).expect("utf8"))
+            }
+        };
+
+        assert_eq!(deliver("1").await.0, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(deliver("1").await.0, StatusCode::OK);
+        assert_eq!(
+            deliver("1").await,
+            (StatusCode::ACCEPTED, "duplicate".to_string())
+        );
+        assert_eq!(deliver("2").await.0, StatusCode::OK);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_security_decisions_are_audited() {
+        use crate::config::models::{
+            AdminConfig, IpFilterConfig, MissingKeyPolicy, RateLimitAlgorithm, RateLimitBy,
+            RateLimitConfig, RateLimits, WafConfig, WafRuleConfig,
+        };
+
+        let mut config = ServerConfig::builder()
+            .listen_addr("127.0.0.1:8080")
+            .route(
+                "/audited",
+                RouteConfig::Redirect {
+                    target: "/elsewhere".to_string(),
+                    host: None,
+                    status_code: None,
+                    rate_limit: Some(RateLimits::from(RateLimitConfig {
+                        name: None,
+                        by: RateLimitBy::Route,
+                        header_name: None,
+                        key: vec![],
+                        requests: 1,
+                        period: "1m".to_string(),
+                        status_code: 429,
+                        message: "Too Many Requests".to_string(),
+                        algorithm: RateLimitAlgorithm::TokenBucket,
+                        on_missing_key: MissingKeyPolicy::Allow,
+                        max_keys: 100_000,
+                        key_ttl: None,
+                    })),
+                    middlewares: vec![],
+                    error_pages: None,
+                    disable_http3: false,
+                    priority: None,
+                },
+            )
+            .admin(AdminConfig {
+                enabled: true,
+                token: Some("admin-secret".to_string()),
+            })
+            .build()
+            .expect("config");
+        config.waf = Some(WafConfig {
+            enabled: true,
+            sql_injection: WafRuleConfig {
+                enabled: true,
+                block_mode: true,
+            },
+            ip_filter: IpFilterConfig {
+                enabled: true,
+                whitelist: vec![],
+                blacklist: vec!["198.51.100.0/24".to_string()],
+            },
+            ..WafConfig::default()
+        });
+        let config = Arc::new(config);
+        let (audit, mut audit_rx) = AuditLog::channel(16);
+        let handler = handler_for(config).with_audit_log(audit);
+        let client: SocketAddr = "203.0.113.5:40000".parse().expect("addr");
+
+        let cases = [
+            (
+                "/audited?id=1%20UNION%20SELECT%20*%20FROM%20users",
+                client,
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                "/audited",
+                "198.51.100.7:40000".parse().expect("addr"),
+                StatusCode::FORBIDDEN,
+            ),
+            ("/audited", client, StatusCode::FOUND),
+            ("/audited", client, StatusCode::TOO_MANY_REQUESTS),
+            ("/admin/metrics", client, StatusCode::UNAUTHORIZED),
+        ];
+        for (uri, from, expected) in cases {
+            let request = Request::builder()
+                .uri(uri)
+                .header(X_REQUEST_ID, "audit-test")
+                .body(AxumBody::empty())
+                .expect("request");
+            let response = handler
+                .handle_request(request, Some(from))
+                .await
+                .expect("ok");
+            assert_eq!(response.status(), expected, "{uri}");
+        }
+
+        let records: Vec<_> = std::iter::from_fn(|| audit_rx.try_recv().ok()).collect();
+        let summary: Vec<_> = records
+            .iter()
+            .map(|r| {
+                (
+                    r.event,
+                    r.rule.as_str(),
+                    r.client_ip.as_deref().unwrap_or(""),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (AuditEventKind::Waf, "SQL_INJECTION", "203.0.113.5"),
+                (AuditEventKind::IpFilter, "IP_BLACKLISTED", "198.51.100.7"),
+                (AuditEventKind::RateLimit, "route", "203.0.113.5"),
+                (AuditEventKind::AdminAuth, "admin_token", "203.0.113.5"),
+            ]
+        );
+        assert!(records.iter().all(|r| r.decision == AuditDecision::Blocked
+            && r.request_id.as_deref() == Some("audit-test")));
+        assert_eq!(records[0].threat_level, "critical");
+        assert_eq!(records[2].route.as_deref(), Some("/audited"));
+    }
+
+    #[tokio::test]
+    async fn test_repeat_offenders_are_banned() {
+        use crate::config::models::{AutoBanConfig, ClientIpConfig, WafConfig, WafRuleConfig};
+
+        let mut config = ServerConfig::builder()
+            .listen_addr("127.0.0.1:8080")
+            .route(
+                "/",
+                RouteConfig::Redirect {
+                    target: "/elsewhere".to_string(),
+                    host: None,
+                    status_code: None,
+                    rate_limit: None,
+                    middlewares: vec![],
+                    error_pages: None,
+                    disable_http3: false,
+                    priority: None,
+                },
+            )
+            .build()
+            .expect("config");
+        config.waf = Some(WafConfig {
+            enabled: true,
+            xss: WafRuleConfig {
+                enabled: true,
+                block_mode: true,
+            },
+            auto_ban: AutoBanConfig {
+                enabled: true,
+                waf_violations: 2,
+                ..AutoBanConfig::default()
+            },
+            ..WafConfig::default()
+        });
+        config.client_ip = ClientIpConfig {
+            trusted_proxies: vec!["10.0.0.0/8".to_string()],
+        };
+        let config = Arc::new(config);
+        let gateway = Arc::new(ArcSwap::from_pointee(GatewayService::new(config.clone())));
+        let handler = handler_with_gateway(gateway.clone(), config.clone());
+        // Both clients arrive through the same trusted proxy, and only the
+        // one behind it that attacks is banned
+        let proxy: SocketAddr = "10.0.0.1:40000".parse().expect("addr");
+        let attacker = "203.0.113.9";
+        let bystander = "203.0.113.10";
+
+        let cases = [
+            (
+                "/?q=%3Cscript%3Ealert(1)%3C/script%3E",
+                attacker,
+                StatusCode::FORBIDDEN,
+            ),
+            ("/", attacker, StatusCode::FOUND),
+            (
+                "/?q=%3Cscript%3Ealert(1)%3C/script%3E",
+                attacker,
+                StatusCode::FORBIDDEN,
+            ),
+            // Banned now, even for clean requests
+            ("/", attacker, StatusCode::FORBIDDEN),
+            ("/", bystander, StatusCode::FOUND),
+        ];
+        for (uri, from, expected) in cases {
+            let request = Request::builder()
+                .uri(uri)
+                .header("X-Forwarded-For", from)
+                .body(AxumBody::empty())
+                .expect("request");
+            let response = handler
+                .handle_request(request, Some(proxy))
+                .await
+                .expect("ok");
+            assert_eq!(response.status(), expected, "{uri} from {from}");
+        }
+
+        // Bans survive a reload
+        let current = gateway.load_full();
+        gateway.store(Arc::new(
+            GatewayService::new(config).with_bans(current.bans()),
+        ));
+        let request = Request::builder()
+            .uri("/")
+            .header("X-Forwarded-For", attacker)
+            .body(AxumBody::empty())
+            .expect("request");
+        let response = handler
+            .handle_request(request, Some(proxy))
+            .await
+            .expect("ok");
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        gateway.load().sweep_bans();
+        let response = handler.handle_metrics().await.expect("metrics");
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("axon_auto_bans_total{reason=\"waf\"}"));
+        assert!(body.contains("axon_auto_banned_ips "));
+    }
+
+    #[tokio::test]
+    async fn test_admin_metrics_snapshot_and_reset() {
+        use crate::config::models::AdminConfig;
+
+        let app = axum::Router::new().fallback(|| async { StatusCode::SERVICE_UNAVAILABLE });
+        let backend = spawn_backend(app).await;
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route("/admin-drill", RouteConfig::proxy(backend.clone()))
+                .admin(AdminConfig {
+                    enabled: true,
+                    token: Some("drill-token".to_string()),
+                })
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+        let send = |method: &str, uri: &str, token: Option<&str>| {
+            let mut builder = Request::builder().method(method).uri(uri);
+            if let Some(token) = token {
+                builder = builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
+            }
+            let request = builder.body(AxumBody::empty()).expect("request");
+            let handler = handler.clone();
+            async move {
+                let response = handler.handle_request(request, None).await.expect("ok");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("body");
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).ok(),
+                )
+            }
+        };
+
+        let (status, _) = send("GET", "/admin-drill/x", None).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let (status, _) = send("GET", "/admin/metrics", Some("wrong")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, body) = send("GET", "/admin/metrics", Some("drill-token")).await;
+        assert_eq!(status, StatusCode::OK);
+        let body = body.expect("json");
+        let route = body["routes"]
+            .as_array()
+            .expect("routes")
+            .iter()
+            .find(|r| r["route"] == "/admin-drill")
+            .expect("route stats")
+            .clone();
+        assert_eq!(route["backend"], backend);
+        assert_eq!(route["errors_total"], 1);
+        assert_eq!(route["window"]["error_rate"], 1.0);
+
+        let (status, body) = send(
+            "POST",
+            "/admin/metrics/reset?route=%2Fadmin-drill",
+            Some("drill-token"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.expect("json")["windows_reset"], 1);
+
+        let (status, _) = send("DELETE", "/admin/metrics", Some("drill-token")).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+
+        // Unit tests do not install the reloadable subscriber
+        let (status, _) = send("GET", "/admin/logging", Some("drill-token")).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = send("POST", "/admin/logging", Some("drill-token")).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[tokio::test]
+    async fn test_admin_tap_records_backend_exchanges() {
+        use crate::config::models::AdminConfig;
+
+        let app =
+            axum::Router::new().fallback(|body: String| async move { format!("echo:{body}") });
+        let backend = spawn_backend(app).await;
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route("/tapped", RouteConfig::proxy(backend.clone()))
+                .admin(AdminConfig {
+                    enabled: true,
+                    token: Some("tap-token".to_string()),
+                })
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+        let send = |method: &str, uri: &str, body: &'static str| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::AUTHORIZATION, "Bearer tap-token")
+                .body(AxumBody::from(body))
+                .expect("request");
+            let handler = handler.clone();
+            async move {
+                let response = handler.handle_request(request, None).await.expect("ok");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("body");
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).ok(),
+                )
+            }
+        };
+
+        let (status, _) = send("PUT", "/admin/tap?route=%2Fnowhere", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send("PUT", "/admin/tap?route=%2Ftapped", r#"{"capacity": 0}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send("PUT", "/admin/tap?route=%2Ftapped", "").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = send("POST", "/tapped/items", "ping").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = send("GET", "/admin/tap?route=%2Ftapped", "").await;
+        assert_eq!(status, StatusCode::OK);
+        let body = body.expect("json");
+        let exchange = &body["exchanges"][0];
+        assert_eq!(exchange["backend"], backend);
+        assert_eq!(exchange["request"]["method"], "POST");
+        assert_eq!(exchange["request"]["body"]["content"], "ping");
+        assert_eq!(exchange["response"]["status"], 200);
+        assert_eq!(exchange["response"]["body"]["content"], "echo:ping");
+        let headers = exchange["request"]["headers"].as_array().expect("headers");
+        assert!(headers.contains(&serde_json::json!(["authorization", "[redacted]"])));
+
+        let (_, body) = send("GET", "/admin/tap?route=%2Ftapped&since=1", "").await;
+        assert_eq!(body.expect("json")["exchanges"], serde_json::json!([]));
+        let (_, body) = send("GET", "/admin/taps", "").await;
+        assert_eq!(body.expect("json")["taps"][0]["recorded"], 1);
+
+        let (status, _) = send("DELETE", "/admin/tap?route=%2Ftapped", "").await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send("GET", "/admin/tap?route=%2Ftapped", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_admin_steering_weights() {
+        use crate::config::models::AdminConfig;
+
+        let targets = vec![
+            "http://steer-a:8080".to_string(),
+            "http://steer-b:8080".to_string(),
+        ];
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/steer",
+                    RouteConfig::load_balance(
+                        targets.clone(),
+                        crate::config::LoadBalanceStrategy::RoundRobin,
+                    ),
+                )
+                .admin(AdminConfig {
+                    enabled: true,
+                    token: Some("steer-token".to_string()),
+                })
+                .build()
+                .expect("config"),
+        );
+        let gateway = Arc::new(GatewayService::new(config.clone()));
+        let handler = handler_with_gateway(Arc::new(ArcSwap::from(gateway.clone())), config);
+        let send = |method: &str, body: &str| {
+            let request = Request::builder()
+                .method(method)
+                .uri("/admin/steering")
+                .header(header::AUTHORIZATION, "Bearer steer-token")
+                .body(AxumBody::from(body.to_string()))
+                .expect("request");
+            let handler = handler.clone();
+            async move {
+                let response = handler.handle_request(request, None).await.expect("ok");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("body");
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default(),
+                )
+            }
+        };
+
+        let (status, _) = send("PUT", r#"{"weights": {"http://steer-a:8080": -1}}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send(
+            "PUT",
+            r#"{"weights": {"http://steer-a:8080": 0, "http://unknown:1": 1}, "ttl_secs": 60}"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["ignored"], serde_json::json!(["http://unknown:1"]));
+        assert_eq!(body["weights"][0]["backend"], targets[0]);
+        assert_eq!(body["weights"][0]["weight"], 0.0);
+
+        // A zero weight drains the backend while another can take its traffic
+        for _ in 0..20 {
+            assert_eq!(
+                gateway
+                    .select_backend(&targets, None, None)
+                    .await
+                    .as_deref(),
+                Some(targets[1].as_str())
+            );
+        }
+        // ...but steering never leaves a route without a backend
+        assert_eq!(
+            gateway
+                .select_backend(&targets[..1], None, None)
+                .await
+                .as_deref(),
+            Some(targets[0].as_str())
+        );
+
+        // Weights survive a reload that shares the table
+        let reloaded = GatewayService::new(Arc::new(ServerConfig::default()))
+            .with_steering(gateway.steering());
+        assert_eq!(reloaded.steering().snapshot().len(), 1);
+
+        let (status, body) = send("DELETE", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["weights"], serde_json::json!([]));
+        let (_, body) = send("GET", "").await;
+        assert_eq!(body["weights"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_route_actions_apply_when_expression_matches() {
+        use crate::config::models::RequestCondition;
+
+        let app = axum::Router::new().fallback(|headers: HeaderMap| async move {
+            headers
+                .get("x-tier")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("none")
+                .to_string()
+        });
+        let backend = spawn_backend(app).await;
+
+        let condition = |expr: &str| {
+            Some(RequestCondition {
+                path_matches: None,
+                method_is: None,
+                has_header: None,
+                expr: Some(expr.to_string()),
+            })
+        };
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/docs",
+                    proxy_route!(
+                        backend,
+                        request_headers: Some(HeaderActions {
+                            add: [("X-Tier".to_string(), "gold".to_string())].into(),
+                            remove: vec![],
+                            condition: condition("req.path.ends_with('.pdf')"),
+                        }),
+                        response_headers: Some(HeaderActions {
+                            add: [("X-Client".to_string(), "mobile".to_string())].into(),
+                            remove: vec![],
+                            condition: condition(
+                                "req.header('user-agent').contains('Mobile') || req.query_param('m') == '1'",
+                            ),
+                        }),
+                        response_body: Some(BodyActions {
+                            set_text: Some("redacted".to_string()),
+                            set_json: None,
+                            replace: vec![],
+                            inject_html: None,
+                            condition: condition("req.path.matches('/secret/')"),
+                        }),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+
+        let fetch = |uri: &'static str| {
+            let request = Request::builder()
+                .uri(uri)
+                .body(AxumBody::empty())
+                .expect("request");
+            let handler = handler.clone();
+            async move {
+                let response = handler.handle_request(request, None).await.expect("ok");
+                let client = response.headers().get("x-client").cloned();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("body");
+                (String::from_utf8(body.to_vec()).expect("utf8"), client)
+            }
+        };
+
+        assert_eq!(fetch("/docs/a.pdf").await, ("gold".to_string(), None));
+        assert_eq!(
+            fetch("/docs/a.html?m=1").await,
+            ("none".to_string(), Some(HeaderValue::from_static("mobile")))
+        );
+        assert_eq!(
+            fetch("/docs/secret/b.pdf").await,
+            ("redacted".to_string(), None)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_response_body_rewrite_streams() {
+        use crate::config::models::BodyReplacement;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let backend = format!("http://{}", listener.local_addr().expect("addr"));
+        let origin = backend.clone();
+        let app = axum::Router::new().fallback(move |headers: HeaderMap| {
+            let origin = origin.clone();
+            async move {
+                let encoding = headers.contains_key(header::ACCEPT_ENCODING);
+                let chunks = [
+                    format!("<html><body><a href=\"{origin}/a\">"),
+                    format!("encoding={encoding}</a></bo"),
+                    "dy></html>".to_string(),
+                ];
+                let stream = futures_util::stream::iter(
+                    chunks.map(|chunk| Ok::<_, std::io::Error>(bytes::Bytes::from(chunk))),
+                );
+                Response::builder()
+                    .header(header::CONTENT_TYPE, "text/html")
+                    .header(header::ETAG, "\"v1\"")
+                    .body(AxumBody::from_stream(stream))
+                    .expect("response")
+            }
+        });
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/site",
+                    proxy_route!(
+                        backend.clone(),
+                        response_body: Some(BodyActions {
+                            replace: vec![BodyReplacement {
+                                find: backend,
+                                replace_with: "https://www.example.com".to_string(),
+                                regex: false,
+                            }],
+                            inject_html: Some("<script></script>".to_string()),
+                            ..BodyActions::default()
+                        }),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+
+        let request = Request::builder()
+            .uri("/site/index.html")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(AxumBody::empty())
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        assert_eq!(response.headers()[header::ETAG], "W/\"v1\"");
+        assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        assert_eq!(
+            body,
+            "<html><body><a href=\"https://www.example.com/a\">encoding=false</a><script></script></body></html>"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backend_redirects_are_rewritten() {
+        use crate::config::models::LocationRewriteConfig;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let backend = format!("http://{}", listener.local_addr().expect("addr"));
+        let location = format!("{backend}/login?next=/");
+        let app = axum::Router::new().fallback(move || {
+            let location = location.clone();
+            async move { axum::response::Redirect::temporary(&location) }
+        });
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/app",
+                    proxy_route!(
+                        backend,
+                        path_rewrite: Some("/".to_string()),
+                        location_rewrite: Some(LocationRewriteConfig::default()),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+
+        let request = Request::builder()
+            .uri("/app/account")
+            .body(AxumBody::empty())
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "/app/login?next=/");
+    }
+
+    #[tokio::test]
+    async fn test_header_allowlists() {
+        use crate::config::models::HeaderAllowlistConfig;
+
+        let app = axum::Router::new().fallback(|headers: HeaderMap| async move {
+            let mut names: Vec<_> = headers.keys().map(|name| name.to_string()).collect();
+            names.sort();
+            (
+                [("x-powered-by", "php/5.4"), ("x-trace-id", "abc")],
+                names.join(","),
+            )
+        });
+        let backend = spawn_backend(app).await;
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .header_allowlist(HeaderAllowlistConfig {
+                    request: Some(vec!["Authorization".to_string()]),
+                    response: Some(vec!["Content-Type".to_string(), "X-Trace-*".to_string()]),
+                })
+                .route(
+                    "/secure",
+                    proxy_route!(
+                        backend,
+                        header_allowlist: Some(HeaderAllowlistConfig {
+                            request: Some(vec!["X-Request-*".to_string()]),
+                            response: None,
+                        }),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+
+        let request = Request::builder()
+            .uri("/secure/data")
+            .header("authorization", "Bearer t")
+            .header("cookie", "session=1")
+            .header("x-request-id", "r1")
+            .body(AxumBody::empty())
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        // The route's request list replaces the global one; the global
+        // response list still applies
+        assert_eq!(response.headers()["x-trace-id"], "abc");
+        assert!(response.headers().get("x-powered-by").is_none());
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let forwarded = String::from_utf8(body.to_vec()).expect("utf8");
+        assert!(forwarded.contains("x-request-id"));
+        assert!(forwarded.contains("x-forwarded-host"));
+        assert!(!forwarded.contains("cookie"));
+        assert!(!forwarded.contains("authorization"));
+    }
+
+    #[tokio::test]
+    async fn test_identical_requests_are_coalesced() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::config::models::CoalesceConfig;
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let backend_hits = hits.clone();
+        let app = axum::Router::new().fallback(move || {
+            let hits = backend_hits.clone();
+            async move {
+                hits.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                "payload"
+            }
+        });
+        let backend = spawn_backend(app).await;
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/feed",
+                    proxy_route!(
+                        backend,
+                        coalesce: Some(CoalesceConfig {
+                            key_headers: vec!["Authorization".to_string()],
+                            max_waiters: 100,
+                            bypass_header: Some("X-No-Coalesce".to_string()),
+                            max_body_bytes: 1024,
+                        }),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+        let send = |bypass: bool| {
+            let mut request = Request::builder().uri("/feed/latest");
+            if bypass {
+                request = request.header("x-no-coalesce", "1");
+            }
+            handler.handle_request(request.body(AxumBody::empty()).expect("request"), None)
+        };
+
+        let responses = futures_util::future::join_all((0..5).map(|_| send(false))).await;
+        for response in responses {
+            let response = response.expect("ok");
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("body");
+            assert_eq!(&body[..], b"payload");
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // The bypass header sends requests on their own
+        let (first, second) = tokio::join!(send(true), send(true));
+        assert!(first.is_ok() && second.is_ok());
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_experiment_variant_assignment() {
+        use crate::config::models::{ExperimentConfig, ExperimentVariant};
+
+        let app = axum::Router::new().fallback(|headers: HeaderMap| async move {
+            headers
+                .get("x-experiment-checkout")
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("none")
+                .to_string()
+        });
+        let backend = spawn_backend(app).await;
+
+        let variant = |name: &str, percent| ExperimentVariant {
+            name: name.to_string(),
+            percent,
+        };
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .experiment(ExperimentConfig {
+                    name: "checkout".to_string(),
+                    routes: vec!["/shop".to_string()],
+                    variants: vec![variant("control", 0), variant("new", 100)],
+                    cookie: None,
+                    cookie_max_age_secs: 60,
+                    header: None,
+                })
+                .route("/shop", RouteConfig::proxy(backend))
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+        let send = |cookie: Option<&'static str>| {
+            let mut request = Request::builder()
+                .uri("/shop/cart")
+                .header("x-experiment-checkout", "spoofed");
+            if let Some(cookie) = cookie {
+                request = request.header("cookie", cookie);
+            }
+            handler.handle_request(request.body(AxumBody::empty()).expect("request"), None)
+        };
+
+        // A new client is assigned and gets the sticky cookie
+        let response = send(None).await.expect("ok");
+        assert_eq!(
+            response.headers()["set-cookie"],
+            "axon_exp_checkout=new; Path=/; Max-Age=60; HttpOnly; SameSite=Lax"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        assert_eq!(&body[..], b"new");
+
+        // An existing assignment is kept, even at 0%
+        let response = send(Some("axon_exp_checkout=control")).await.expect("ok");
+        assert!(response.headers().get("set-cookie").is_none());
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        assert_eq!(&body[..], b"control");
+    }
+
+    #[tokio::test]
+    async fn test_slow_backend_is_hedged() {
+        use crate::config::models::HedgeConfig;
+
+        let mut targets = Vec::new();
+        for (name, delay) in [("slow", 2000), ("fast", 0)] {
+            let app = axum::Router::new().fallback(move || async move {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                name
+            });
+            targets.push(spawn_backend(app).await);
+        }
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/search",
+                    load_balance_route!(
+                        targets.clone(),
+                        hedge: Some(Box::new(HedgeConfig {
+                            max_delay_ms: 50,
+                            ..HedgeConfig::default()
+                        })),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let gateway = Arc::new(ArcSwap::from_pointee(GatewayService::new(config.clone())));
+        gateway.load().taps().start("/search", TapConfig::default());
+        let handler = handler_with_gateway(gateway.clone(), config);
+
+        // Round robin sends one of the two requests to the slow backend first;
+        // its hedge answers from the fast one well before the slow one would
+        let started = Instant::now();
+        for _ in 0..2 {
+            let request = Request::builder()
+                .uri("/search?q=axon")
+                .body(AxumBody::empty())
+                .expect("request");
+            let response = handler.handle_request(request, None).await.expect("ok");
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("body");
+            assert_eq!(&body[..], b"fast");
+        }
+        assert!(started.elapsed() < Duration::from_millis(1500));
+
+        // The hedge is sent and tapped like any attempt, and the request it
+        // beat is recorded as superseded
+        let exchanges = gateway
+            .load()
+            .taps()
+            .get("/search")
+            .expect("tap")
+            .exchanges();
+        let answered: Vec<_> = exchanges
+            .iter()
+            .filter_map(|exchange| Some((exchange.backend.as_str(), exchange.response.as_ref()?)))
+            .collect();
+        assert_eq!(answered.len(), 2);
+        for (backend, response) in answered {
+            assert_eq!(backend, targets[1]);
+            assert_eq!(response.body.content, "fast");
+        }
+        let superseded: Vec<_> = exchanges
+            .iter()
+            .filter(|exchange| {
+                exchange
+                    .error
+                    .as_deref()
+                    .is_some_and(|e| e.contains("superseded"))
+            })
+            .collect();
+        assert_eq!(superseded.len(), 1);
+        assert_eq!(superseded[0].backend, targets[0]);
+        for target in &targets {
+            let active = gateway
+                .load()
+                .backend_health()
+                .read_sync(target, |_, health| health.active_connections());
+            assert_eq!(active, Some(0), "{target}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hedged_requests_share_an_idempotency_key() {
+        use crate::config::models::HedgeConfig;
+
+        let keys = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut targets = Vec::new();
+        for _ in 0..2 {
+            let keys = keys.clone();
+            let app = axum::Router::new().fallback(move |headers: HeaderMap| async move {
+                let key = headers
+                    .get("idempotency-key")
+                    .map(|value| value.to_str().expect("key").to_string());
+                keys.lock().expect("keys").push(key);
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                "ok"
+            });
+            targets.push(spawn_backend(app).await);
+        }
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/items",
+                    load_balance_route!(
+                        targets,
+                        hedge: Some(Box::new(HedgeConfig {
+                            max_delay_ms: 50,
+                            ..HedgeConfig::default()
+                        })),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+
+        // Both copies of a hedged PUT carry the same generated key, while a
+        // GET has no side effects to deduplicate and is sent unchanged
+        for (method, has_key) in [(http::Method::PUT, true), (http::Method::GET, false)] {
+            keys.lock().expect("keys").clear();
+            let request = Request::builder()
+                .method(method.clone())
+                .uri("/items/1")
+                .body(AxumBody::empty())
+                .expect("request");
+            let response = handler.handle_request(request, None).await.expect("ok");
+            assert_eq!(response.status(), StatusCode::OK);
+            let keys = keys.lock().expect("keys").clone();
+            assert_eq!(keys.len(), 2, "{method}");
+            assert_eq!(keys[0], keys[1], "{method}");
+            assert_eq!(keys[0].is_some(), has_key, "{method}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_saturated_backend_reroutes_then_rejects() {
+        use crate::config::models::BackendLimits;
+
+        let mut targets = Vec::new();
+        for name in ["first", "second"] {
+            let app = axum::Router::new().fallback(move || async move { name });
+            targets.push(spawn_backend(app).await);
+        }
+        let limits = BackendLimits {
+            max_requests: 1,
+            max_pending: 0,
+            pending_timeout_ms: 1000,
+        };
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/limited",
+                    RouteConfig::load_balance(
+                        targets.clone(),
+                        crate::config::LoadBalanceStrategy::RoundRobin,
+                    ),
+                )
+                .backend_limits(targets[0].clone(), limits.clone())
+                .backend_limits(targets[1].clone(), limits)
+                .build()
+                .expect("config"),
+        );
+        let gateway = Arc::new(GatewayService::new(config.clone()));
+        let handler = handler_with_gateway(Arc::new(ArcSwap::from(gateway.clone())), config);
+        let request = || {
+            let request = Request::builder()
+                .uri("/limited")
+                .body(AxumBody::empty())
+                .expect("request");
+            let handler = handler.clone();
+            async move {
+                let response = handler.handle_request(request, None).await.expect("ok");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("body");
+                (status, String::from_utf8(body.to_vec()).expect("utf8"))
+            }
+        };
+
+        // With the first backend busy, every request lands on the second
+        let first = gateway.get_backend_limiter(&targets[0]).expect("limiter");
+        let busy = first.try_acquire().expect("slot");
+        for _ in 0..2 {
+            assert_eq!(request().await, (StatusCode::OK, "second".to_string()));
+        }
+        // The slot is released once the body has been read
+        let second = gateway.get_backend_limiter(&targets[1]).expect("limiter");
+        assert_eq!(second.in_flight(), 0);
+
+        let _also_busy = second.try_acquire().expect("slot");
+        assert_eq!(request().await.0, StatusCode::SERVICE_UNAVAILABLE);
+
+        drop(busy);
+        assert_eq!(request().await, (StatusCode::OK, "first".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_failed_requests_are_retried_within_budget() {
+        use std::sync::{
+            Mutex,
+            atomic::{AtomicUsize, Ordering},
+        };
+
+        use crate::config::models::{RetryBudgetConfig, RetryConfig};
+
+        let failing_hits = Arc::new(AtomicUsize::new(0));
+        let seen_keys = Arc::new(Mutex::new(Vec::new()));
+        let (hits, keys) = (failing_hits.clone(), seen_keys.clone());
+        let failing = axum::Router::new().fallback(move |headers: HeaderMap| {
+            hits.fetch_add(1, Ordering::SeqCst);
+            if let Some(key) = headers.get("idempotency-key") {
+                keys.lock().unwrap().push(key.to_str().unwrap().to_string());
+            }
+            async { StatusCode::SERVICE_UNAVAILABLE }
+        });
+        let working = axum::Router::new().fallback(|| async { "ok" });
+        let mut targets = Vec::new();
+        for app in [failing, working] {
+            targets.push(spawn_backend(app).await);
+        }
+        let retry = RetryConfig {
+            attempts: 1,
+            backoff_ms: 1,
+            budget: RetryBudgetConfig {
+                ratio: 0.0,
+                min_retries: 1,
+                window_secs: 60,
+            },
+            ..RetryConfig::default()
+        };
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/balanced",
+                    load_balance_route!(
+                        targets.clone(),
+                        retry: Some(RetryConfig {
+                            budget: RetryBudgetConfig::default(),
+                            ..retry.clone()
+                        }),
+                    ),
+                )
+                .route(
+                    "/flaky",
+                    proxy_route!(
+                        targets[0].clone(),
+                        retry: Some(retry.clone()),
+                    ),
+                )
+                .route(
+                    "/keyed",
+                    proxy_route!(
+                        targets[0].clone(),
+                        retry: Some(RetryConfig {
+                            budget: RetryBudgetConfig::default(),
+                            generate_idempotency_key: true,
+                            ..retry
+                        }),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+        let request = |method: &str, uri: &str| {
+            let mut request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(AxumBody::from("payload"))
+                .expect("request");
+            if let Some((_, key)) = uri.split_once("?key=") {
+                request
+                    .headers_mut()
+                    .insert("idempotency-key", HeaderValue::from_str(key).expect("key"));
+            }
+            let handler = handler.clone();
+            async move {
+                let response = handler.handle_request(request, None).await.expect("ok");
+                response.status()
+            }
+        };
+
+        // Whichever backend is picked first, a failure is retried on the other
+        for _ in 0..4 {
+            assert_eq!(request("GET", "/balanced").await, StatusCode::OK);
+        }
+
+        // Non-idempotent methods are sent once
+        failing_hits.store(0, Ordering::SeqCst);
+        assert_eq!(
+            request("POST", "/flaky").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(failing_hits.load(Ordering::SeqCst), 1);
+
+        // Unless they carry an idempotency key, which every attempt repeats
+        assert_eq!(
+            request("POST", "/keyed?key=client-key").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            request("PATCH", "/keyed").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        let keys = std::mem::take(&mut *seen_keys.lock().unwrap());
+        assert_eq!(keys.len(), 4);
+        assert_eq!(keys[..2], ["client-key", "client-key"]);
+        assert_eq!(keys[2], keys[3]);
+        assert!(Uuid::parse_str(&keys[2]).is_ok());
+        failing_hits.store(1, Ordering::SeqCst);
+
+        // The budget allows one retry in the window, then failures pass through
+        assert_eq!(
+            request("GET", "/flaky").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(failing_hits.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            request("GET", "/flaky").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(failing_hits.load(Ordering::SeqCst), 4);
+
+        let exposition = crate::metrics::retry_exposition();
+        assert!(exposition.contains(r#"axon_retries_total{route="/flaky",outcome="retried"} 1"#));
+        assert!(
+            exposition
+                .contains(r#"axon_retries_total{route="/flaky",outcome="budget_exhausted"} 1"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failing_backends_fall_back_to_backup_targets() {
+        use crate::config::models::FallbackConfig;
+
+        let mut targets = Vec::new();
+        let primary =
+            axum::Router::new().fallback(|| async { (StatusCode::SERVICE_UNAVAILABLE, "primary") });
+        let backup = axum::Router::new().fallback(|| async { "backup" });
+        for app in [primary, backup] {
+            targets.push(spawn_backend(app).await);
+        }
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/api",
+                    proxy_route!(
+                        targets[0].clone(),
+                        fallback: Some(FallbackConfig {
+                            targets: vec![targets[1].clone()],
+                            on_status: vec![503],
+                            max_body_bytes: 1024,
+                        }),
+                    ),
+                )
+                .health_check(crate::config::HealthCheckConfig {
+                    enabled: true,
+                    ..Default::default()
+                })
+                .build()
+                .expect("config"),
+        );
+        let gateway = Arc::new(GatewayService::new(config.clone()));
+        let handler = handler_with_gateway(Arc::new(ArcSwap::from(gateway.clone())), config);
+        let request = |method: &str| {
+            let request = Request::builder()
+                .method(method)
+                .uri("/api/items")
+                .body(AxumBody::from("payload"))
+                .expect("request");
+            let handler = handler.clone();
+            async move {
+                let response = handler.handle_request(request, None).await.expect("ok");
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("body");
+                (status, String::from_utf8(body.to_vec()).expect("utf8"))
+            }
+        };
+
+        assert_eq!(request("GET").await, (StatusCode::OK, "backup".to_string()));
+        // Non-idempotent requests are not sent twice
+        assert_eq!(
+            request("POST").await,
+            (StatusCode::SERVICE_UNAVAILABLE, "primary".to_string())
+        );
+
+        // Without a healthy primary, every request goes to the backup pool
+        gateway
+            .backend_health()
+            .read_sync(&targets[0], |_, health| health.mark_unhealthy());
+        assert_eq!(
+            request("POST").await,
+            (StatusCode::OK, "backup".to_string())
+        );
+
+        let exposition = crate::metrics::failover_exposition();
+        assert!(exposition.contains(r#"axon_failovers_total{route="/api",reason="failed"} 1"#));
+        assert!(exposition.contains(r#"axon_failovers_total{route="/api",reason="unhealthy"} 1"#));
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_and_connect_requests_are_tunneled() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        use crate::config::models::TunnelConfig;
+
+        async fn read_head(stream: &mut tokio::net::TcpStream) -> String {
+            let mut head = Vec::new();
+            let mut byte = [0u8; 1];
+            while !head.ends_with(b"\r\n\r\n") {
+                stream.read_exact(&mut byte).await.expect("read head");
+                head.push(byte[0]);
+            }
+            String::from_utf8(head).expect("utf8").to_lowercase()
+        }
+
+        // Answers an `Upgrade: echo` request, then echoes the connection
+        let upgrading = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let upgrading_addr = upgrading.local_addr().expect("addr");
+        tokio::spawn(async move {
+            let (mut stream, _) = upgrading.accept().await.expect("accept");
+            let head = read_head(&mut stream).await;
+            assert!(head.starts_with("get /rooms/1 "));
+            assert!(head.contains("upgrade: echo\r\n"));
+            assert!(head.contains("connection: upgrade\r\n"));
+            stream
+                .write_all(b"HTTP/1.1 101 Switching Protocols\r\nconnection: upgrade\r\nupgrade: echo\r\n\r\n")
+                .await
+                .expect("write");
+            let (mut read, mut write) = stream.split();
+            let _ = tokio::io::copy(&mut read, &mut write).await;
+        });
+        // A plain TCP echo server for CONNECT
+        let echo = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let echo_addr = echo.local_addr().expect("addr");
+        tokio::spawn(async move {
+            let (mut stream, _) = echo.accept().await.expect("accept");
+            let (mut read, mut write) = stream.split();
+            let _ = tokio::io::copy(&mut read, &mut write).await;
+        });
+
+        let route = |target: String, tunnel: TunnelConfig| {
+            proxy_route!(
+                target,
+                path_rewrite: Some("/rooms".to_string()),
+                tunnel: Some(tunnel),
+            )
+        };
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/chat",
+                    route(
+                        format!("http://{upgrading_addr}"),
+                        TunnelConfig {
+                            protocols: vec!["echo".to_string()],
+                            ..TunnelConfig::default()
+                        },
+                    ),
+                )
+                .route(
+                    "/",
+                    route(
+                        format!("http://{echo_addr}"),
+                        TunnelConfig {
+                            connect: true,
+                            ..TunnelConfig::default()
+                        },
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+        let gateway = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let gateway_addr = gateway.local_addr().expect("addr");
+        tokio::spawn(crate::adapters::serve(gateway, handler.into_router()));
+
+        let mut client = tokio::net::TcpStream::connect(gateway_addr)
+            .await
+            .expect("connect");
+        client
+            .write_all(b"GET /chat/1 HTTP/1.1\r\nhost: gateway\r\nconnection: Upgrade\r\nupgrade: echo\r\n\r\n")
+            .await
+            .expect("write");
+        assert!(read_head(&mut client).await.starts_with("http/1.1 101"));
+        client.write_all(b"hello").await.expect("write");
+        let mut buf = [0u8; 5];
+        client.read_exact(&mut buf).await.expect("read");
+        assert_eq!(&buf, b"hello");
+
+        let mut client = tokio::net::TcpStream::connect(gateway_addr)
+            .await
+            .expect("connect");
+        client
+            .write_all(b"CONNECT db.internal:5432 HTTP/1.1\r\nhost: db.internal:5432\r\n\r\n")
+            .await
+            .expect("write");
+        assert!(read_head(&mut client).await.starts_with("http/1.1 200"));
+        client.write_all(b"ping").await.expect("write");
+        let mut buf = [0u8; 4];
+        client.read_exact(&mut buf).await.expect("read");
+        assert_eq!(&buf, b"ping");
+
+        let exposition = crate::metrics::tunnel_exposition();
+        assert!(exposition.contains(r#"axon_tunnels_active{route="/chat"} 1"#));
+        assert!(exposition.contains(r#"axon_tunnels_active{route="/"} 1"#));
+    }
+
+    #[tokio::test]
+    async fn test_backend_statuses_are_remapped() {
+        use crate::config::models::StatusMapping;
+
+        let app = axum::Router::new().fallback(|uri: Uri| async move {
+            match uri.path() {
+                "/api/boom" => (StatusCode::INTERNAL_SERVER_ERROR, "stack trace"),
+                "/api/missing" => (StatusCode::NOT_FOUND, "no row 42 in table users"),
+                _ => (StatusCode::OK, "fine"),
+            }
+        });
+        let backend = spawn_backend(app).await;
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/api",
+                    proxy_route!(
+                        backend,
+                        status_map: vec![
+                            StatusMapping {
+                                from: vec![500, 502],
+                                to: Some(503),
+                                set_text: None,
+                                set_json: Some(serde_json::json!({"error": "try again later"})),
+                            },
+                            StatusMapping {
+                                from: vec![404],
+                                to: None,
+                                set_text: Some("Not found".to_string()),
+                                set_json: None,
+                            },
+                        ],
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let handler = handler_for(config);
+        let request = |uri: &'static str| {
+            let request = Request::builder()
+                .uri(uri)
+                .body(AxumBody::empty())
+                .expect("request");
+            let handler = handler.clone();
+            async move {
+                let response = handler.handle_request(request, None).await.expect("ok");
+                let status = response.status();
+                let content_type = response.headers().get(header::CONTENT_TYPE).cloned();
+                let body = to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .expect("body");
+                (
+                    status,
+                    content_type,
+                    String::from_utf8(body.to_vec()).expect("utf8"),
+                )
+            }
+        };
+
+        let (status, content_type, body) = request("/api/boom").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(content_type.unwrap(), "application/json");
+        assert_eq!(body, r#"{"error":"try again later"}"#);
+
+        let (status, _, body) = request("/api/missing").await;
+        assert_eq!(
+            (status, body.as_str()),
+            (StatusCode::NOT_FOUND, "Not found")
+        );
+
+        let (status, _, body) = request("/api/ok").await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "fine"));
+    }
+
+    #[tokio::test]
+    async fn test_removed_backend_requests_fail_with_bad_gateway() {
+        use crate::config::models::{BackendRemovalConfig, BackendRemovalMode};
+
+        let app = axum::Router::new().fallback(|| async {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            "late"
+        });
+        let backend = spawn_backend(app).await;
+
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route("/slow", RouteConfig::proxy(backend.clone()))
+                .build()
+                .expect("config"),
+        );
+        let gateway = Arc::new(GatewayService::new(config.clone()));
+        let handler = handler_with_gateway(Arc::new(ArcSwap::from(gateway.clone())), config);
+
+        let request = Request::builder()
+            .uri("/slow")
+            .body(AxumBody::empty())
+            .expect("request");
+        let in_flight = tokio::spawn({
+            let handler = handler.clone();
+            async move { handler.handle_request(request, None).await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let reloaded = GatewayService::new(Arc::new(ServerConfig::default()));
+        let removed = gateway.retire_removed_backends(
+            &reloaded,
+            &BackendRemovalConfig {
+                mode: BackendRemovalMode::Fail,
+                deadline_secs: 30,
+            },
+        );
+        assert_eq!(removed, vec![backend]);
+
+        let response = tokio::time::timeout(std::time::Duration::from_secs(5), in_flight)
+            .await
+            .expect("cancelled promptly")
+            .expect("join")
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn test_requests_during_shutdown() {
+        use crate::config::models::ShutdownNewRequests;
+
+        let mut config = ServerConfig::default();
+        let config_holder = Arc::new(ArcSwap::from_pointee(config.clone()));
+        let connection_tracker = Arc::new(ConnectionTracker::new());
+        let handler = HttpHandler::new(
+            Arc::new(ArcSwap::from_pointee(GatewayService::new(Arc::new(
+                config.clone(),
+            )))),
+            Arc::new(crate::adapters::HttpClientAdapter::new().expect("client")),
+            Arc::new(FileSystemAdapter::new()),
+            connection_tracker.clone(),
+            config_holder.clone(),
+        );
+        let request = || Request::builder().uri("/").body(AxumBody::empty()).unwrap();
+
+        connection_tracker.signal_shutdown();
+        let response = handler.handle_request(request(), None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::CONNECTION], "close");
+
+        config.shutdown.new_requests = ShutdownNewRequests::Serve;
+        config_holder.store(Arc::new(config));
+        let response = handler.handle_request(request(), None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_paths_are_normalized_before_routing() {
+        use crate::config::models::RouteConfigEntry;
+
+        let mut config = ServerConfig::default();
+        config.routes.insert(
+            "/admin".to_string(),
+            RouteConfigEntry::Single(Box::new(RouteConfig::Redirect {
+                target: "/login".to_string(),
+                host: None,
+                status_code: None,
+                rate_limit: None,
+                middlewares: Vec::new(),
+                error_pages: None,
+                disable_http3: false,
+                priority: None,
+            })),
+        );
+        let config = Arc::new(config);
+        let handler = handler_for(config);
+        let status = |uri: &str| {
+            let request = Request::builder()
+                .uri(uri)
+                .body(AxumBody::empty())
+                .expect("request");
+            let handler = handler.clone();
+            async move {
+                handler
+                    .handle_request(request, None)
+                    .await
+                    .expect("response")
+                    .status()
+            }
+        };
+
+        assert_eq!(status("/admin").await, StatusCode::FOUND);
+        assert_eq!(status("//admin").await, StatusCode::FOUND);
+        assert_eq!(status("/public/%2e%2e/admin?x=1").await, StatusCode::FOUND);
+        assert_eq!(status("/%61dmin").await, StatusCode::FOUND);
+        assert_eq!(status("/public/admin").await, StatusCode::NOT_FOUND);
+        assert_eq!(status("/admin%zz").await, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_unmatched_requests_use_the_default_route() {
+        use crate::config::models::RouteConfigEntry;
+
+        let redirect = |target: &str, host: Option<&str>| RouteConfig::Redirect {
+            target: target.to_string(),
+            host: host.map(String::from),
+            status_code: None,
+            rate_limit: None,
+            middlewares: Vec::new(),
+            error_pages: None,
+            disable_http3: false,
+            priority: None,
+        };
+        let mut config = ServerConfig {
+            default_route: Some(Box::new(redirect("https://www.example.com/", None))),
+            ..ServerConfig::default()
+        };
+        config.routes.insert(
+            "/".to_string(),
+            RouteConfigEntry::Single(Box::new(redirect("/tenant", Some("tenant.example.com")))),
+        );
+        let config = Arc::new(config);
+        let handler = handler_for(config);
+        let location = |host: &str| {
+            let request = Request::builder()
+                .uri("/anything")
+                .header(header::HOST, host)
+                .body(AxumBody::empty())
+                .expect("request");
+            let handler = handler.clone();
+            async move {
+                let response = handler.handle_request(request, None).await.unwrap();
+                assert_eq!(response.status(), StatusCode::FOUND);
+                response.headers()[header::LOCATION].clone()
+            }
+        };
+
+        assert_eq!(location("tenant.example.com").await, "/tenant");
+        assert_eq!(
+            location("other.example.com").await,
+            "https://www.example.com/"
+        );
+        assert!(
+            crate::metrics::unmatched_exposition()
+                .contains(r#"axon_unmatched_requests_total{outcome="default_route"}"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handler_mounts_in_an_axum_router() {
+        use crate::config::models::RouteConfigEntry;
+
+        let mut config = ServerConfig::default();
+        config.routes.insert(
+            "/old".to_string(),
+            RouteConfigEntry::Single(Box::new(RouteConfig::Redirect {
+                target: "/new".to_string(),
+                host: None,
+                status_code: None,
+                rate_limit: None,
+                middlewares: Vec::new(),
+                error_pages: None,
+                disable_http3: false,
+                priority: None,
+            })),
+        );
+        let config = Arc::new(config);
+        let handler = handler_for(config);
+        let app = Router::new()
+            .route("/mine", axum::routing::get(|| async { "own route" }))
+            .merge(handler.into_router());
+        let call = |uri: &str| {
+            let request = Request::builder()
+                .uri(uri)
+                .body(AxumBody::empty())
+                .expect("request");
+            app.clone().oneshot(request)
+        };
+
+        let response = call("/mine").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "own route");
+
+        let response = call("/old").await.unwrap();
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers()[header::LOCATION], "/new");
+        assert_eq!(call("/").await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_header_limits() {
+        use crate::config::models::{HeaderLimitsConfig, RouteConfigEntry};
+
+        let mut config = ServerConfig {
+            header_limits: HeaderLimitsConfig {
+                max_total_bytes: 256,
+                max_header_bytes: 128,
+                max_count: 4,
+            },
+            ..ServerConfig::default()
+        };
+        config.routes.insert(
+            "/".to_string(),
+            RouteConfigEntry::Single(Box::new(RouteConfig::Redirect {
+                target: "/new".to_string(),
+                host: None,
+                status_code: None,
+                rate_limit: None,
+                middlewares: Vec::new(),
+                error_pages: None,
+                disable_http3: false,
+                priority: None,
+            })),
+        );
+        let config = Arc::new(config);
+        let handler = handler_for(config);
+        let status = |headers: Vec<(String, String)>| {
+            let mut request = Request::builder().uri("/");
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            let request = request.body(AxumBody::empty()).expect("request");
+            let handler = handler.clone();
+            async move {
+                handler
+                    .handle_request(request, None)
+                    .await
+                    .expect("ok")
+                    .status()
+            }
+        };
+        let header = |name: &str, size: usize| (name.to_string(), "v".repeat(size));
+
+        assert_eq!(
+            status(vec![header("x-a", 100), header("x-b", 100)]).await,
+            StatusCode::FOUND
+        );
+        let too_large = StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE;
+        assert_eq!(status(vec![header("x-a", 200)]).await, too_large);
+        assert_eq!(
+            status(vec![
+                header("x-a", 100),
+                header("x-b", 100),
+                header("x-c", 100)
+            ])
+            .await,
+            too_large
+        );
+        let many = (0..5).map(|i| header(&format!("x-{i}"), 1)).collect();
+        assert_eq!(status(many).await, too_large);
+    }
+
+    #[tokio::test]
+    async fn test_smuggling_attempts_are_rejected() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        use crate::config::models::RouteConfigEntry;
+
+        let mut config = ServerConfig::default();
+        config.routes.insert(
+            "/".to_string(),
+            RouteConfigEntry::Single(Box::new(RouteConfig::Redirect {
+                target: "/new".to_string(),
+                host: None,
+                status_code: None,
+                rate_limit: None,
+                middlewares: Vec::new(),
+                error_pages: None,
+                disable_http3: false,
+                priority: None,
+            })),
+        );
+        let config = Arc::new(config);
+        let handler = handler_for(config);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        tokio::spawn(crate::adapters::serve(listener, handler.into_router()));
+
+        let exchange = |request: &'static str| async move {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.expect("connect");
+            stream.write_all(request.as_bytes()).await.expect("write");
+            let mut response = Vec::new();
+            // The connection is closed after a rejection
+            tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut response))
+                .await
+                .expect("connection closed")
+                .expect("read");
+            String::from_utf8_lossy(&response).into_owned()
+        };
+
+        let smuggled = exchange(
+            "POST / HTTP/1.1\r\nHost: test\r\nContent-Length: 4\r\n\
+             Transfer-Encoding: chunked\r\n\r\n0\r\n\r\nGET /admin HTTP/1.1\r\nHost: test\r\n\r\n",
+        )
+        .await;
+        assert!(smuggled.starts_with("HTTP/1.1 400"), "{smuggled}");
+        assert!(!smuggled.contains("/admin"), "{smuggled}");
+        assert_eq!(smuggled.matches("HTTP/1.1").count(), 1, "{smuggled}");
+
+        let conflicting = exchange(
+            "POST / HTTP/1.1\r\nHost: test\r\nContent-Length: 3\r\n\
+             Content-Length: 5\r\nConnection: close\r\n\r\nhello",
+        )
+        .await;
+        assert!(conflicting.starts_with("HTTP/1.1 400"), "{conflicting}");
+
+        // Folded header lines never reach the handler
+        let folded = exchange(
+            "GET / HTTP/1.1\r\nHost: test\r\nX-Folded: a\r\n b\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        assert!(folded.starts_with("HTTP/1.1 400"), "{folded}");
+
+        let fine = exchange("GET / HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n").await;
+        assert!(fine.starts_with("HTTP/1.1 302"), "{fine}");
+    }
+
+    #[tokio::test]
+    async fn test_server_timing_header() {
+        use crate::config::models::{RouteConfigEntry, ServerTimingConfig};
+
+        for enabled in [false, true] {
+            let mut config = ServerConfig {
+                server_timing: ServerTimingConfig { enabled },
+                ..ServerConfig::default()
+            };
+            config.routes.insert(
+                "/old".to_string(),
+                RouteConfigEntry::Single(Box::new(RouteConfig::Redirect {
+                    target: "/new".to_string(),
+                    host: None,
+                    status_code: None,
+                    rate_limit: None,
+                    middlewares: Vec::new(),
+                    error_pages: None,
+                    disable_http3: false,
+                    priority: None,
+                })),
+            );
+            let config = Arc::new(config);
+            let handler = handler_for(config);
+
+            let request = Request::builder()
+                .uri("/old")
+                .body(AxumBody::empty())
+                .expect("request");
+            let response = handler.handle_request(request, None).await.expect("ok");
+            let server_timing = response
+                .headers()
+                .get(SERVER_TIMING)
+                .map(|value| value.to_str().expect("ascii").to_string());
+            if !enabled {
+                assert_eq!(server_timing, None);
+                continue;
+            }
+            let server_timing = server_timing.expect("Server-Timing header");
+            assert!(server_timing.starts_with("route;dur="));
+            assert!(server_timing.contains(", total;dur="));
+            // Not proxied, so there is no backend phase
+            assert!(!server_timing.contains("ttfb"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_timing_header_has_backend_phases() {
+        use crate::config::models::ServerTimingConfig;
+
+        let app = axum::Router::new().fallback(|| async { "done" });
+        let backend = spawn_backend(app).await;
+
+        let mut config = ServerConfig::builder()
+            .listen_addr("127.0.0.1:8080")
+            .route("/timed", RouteConfig::proxy(backend))
+            .build()
+            .expect("config");
+        config.server_timing = ServerTimingConfig { enabled: true };
+        let config = Arc::new(config);
+        let handler = handler_for(config);
+
+        let request = Request::builder()
+            .uri("/timed")
+            .body(AxumBody::empty())
+            .expect("request");
+        let response = handler.handle_request(request, None).await.expect("ok");
+        assert_eq!(response.status(), StatusCode::OK);
+        let server_timing = response
+            .headers()
+            .get(SERVER_TIMING)
+            .expect("Server-Timing header")
+            .to_str()
+            .expect("ascii");
+        for phase in ["queue;dur=", "connect;dur=", "ttfb;dur=", "total;dur="] {
+            assert!(
+                server_timing.contains(phase),
+                "missing {phase} in {server_timing}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_host_must_match_tls_sni() {
+        use crate::config::models::{SniHostCheck, TlsConfig};
+
+        let mut config = ServerConfig {
+            tls: Some(TlsConfig {
+                cert_path: Some("cert.pem".to_string()),
+                key_path: Some("key.pem".to_string()),
+                acme: None,
+                sni_host_check: SniHostCheck::Off,
+            }),
+            ..ServerConfig::default()
+        };
+        let config_holder = Arc::new(ArcSwap::from_pointee(config.clone()));
+        let handler = HttpHandler::new(
+            Arc::new(ArcSwap::from_pointee(GatewayService::new(Arc::new(
+                config.clone(),
+            )))),
+            Arc::new(crate::adapters::HttpClientAdapter::new().expect("client")),
+            Arc::new(FileSystemAdapter::new()),
+            Arc::new(ConnectionTracker::new()),
+            config_holder.clone(),
+        );
+
+        let status = |host: Option<&str>, server_name: Option<&str>| {
+            let mut builder = Request::builder().uri("/");
+            if let Some(host) = host {
+                builder = builder.header(header::HOST, host);
+            }
+            let mut request = builder.body(AxumBody::empty()).expect("request");
+            request.extensions_mut().insert(TlsSession {
+                server_name: server_name.map(str::to_string),
+            });
+            let handler = handler.clone();
+            async move {
+                handler
+                    .handle_request(request, None)
+                    .await
+                    .expect("response")
+                    .status()
+            }
+        };
+
+        // Off by default
+        assert_eq!(
+            status(Some("b.example.com"), Some("a.example.com")).await,
+            StatusCode::NOT_FOUND
+        );
+
+        for check in [SniHostCheck::Match, SniHostCheck::Strict] {
+            config.tls.as_mut().expect("tls").sni_host_check = check;
+            config_holder.store(Arc::new(config.clone()));
+            assert_eq!(
+                status(Some("b.example.com"), Some("a.example.com")).await,
+                StatusCode::MISDIRECTED_REQUEST
+            );
+            assert_eq!(
+                status(Some("A.example.com:8443"), Some("a.example.com")).await,
+                StatusCode::NOT_FOUND
+            );
+        }
+
+        // Only strict mode requires SNI
+        assert_eq!(
+            status(Some("a.example.com"), None).await,
+            StatusCode::MISDIRECTED_REQUEST
+        );
+        config.tls.as_mut().expect("tls").sni_host_check = SniHostCheck::Match;
+        config_holder.store(Arc::new(config.clone()));
+        assert_eq!(
+            status(Some("a.example.com"), None).await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_route_health_endpoint() {
+        let config = Arc::new(
+            ServerConfig::builder()
+                .listen_addr("127.0.0.1:8080")
+                .route(
+                    "/api/",
+                    load_balance_route!(
+                        vec![
+                            "http://127.0.0.1:9001".to_string(),
+                            "http://127.0.0.1:9002".to_string(),
+                        ],
+                        health_endpoint: Some("/_health".to_string()),
+                    ),
+                )
+                .build()
+                .expect("config"),
+        );
+        let gateway = Arc::new(GatewayService::new(config.clone()));
+        let handler = handler_with_gateway(Arc::new(ArcSwap::from(gateway.clone())), config);
+
+        let probe = || {
+            Request::builder()
+                .uri("/api/_health")
+                .body(AxumBody::empty())
+                .expect("request")
+        };
+        let response = handler.handle_request(probe(), None).await.expect("ok");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let health: serde_json::Value = serde_json::from_slice(&body).expect("json");
+        assert_eq!(health["route"], "/api/");
+        assert_eq!(health["backends"]["total"], 2);
+
+        for target in ["http://127.0.0.1:9001", "http://127.0.0.1:9002"] {
+            if let Some(entry) = gateway.backend_health().get_async(target).await {
+                entry.get().mark_unhealthy();
+            }
+        }
+        let response = handler.handle_request(probe(), None).await.expect("ok");
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
     }
 
     #[tokio::test]
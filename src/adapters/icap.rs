@@ -0,0 +1,258 @@
+//! Minimal ICAP (RFC 3507) client for antivirus scanning of uploads.
+//!
+//! Each file is sent in a `RESPMOD` request wrapped in a synthetic HTTP
+//! response, over a fresh connection, with `Allow: 204`. The scanner answers
+//! `204 No Content` for clean content; a `200` (content replaced, typically
+//! by a block page) or an `X-Infection-Found` / `X-Violations-Found` /
+//! `X-Virus-ID` header means the file was rejected.
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// Default ICAP port
+const DEFAULT_PORT: u16 = 1344;
+
+/// Largest ICAP response head read from a scanner
+const MAX_RESPONSE_HEAD_BYTES: usize = 64 * 1024;
+
+/// Headers scanners use to name what they found
+const THREAT_HEADERS: &[&str] = &["x-infection-found", "x-violations-found", "x-virus-id"];
+
+/// A scan that could not be completed
+#[derive(Error, Debug)]
+pub enum IcapError {
+    #[error("Invalid ICAP URL '{0}'")]
+    InvalidUrl(String),
+    #[error("ICAP I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("ICAP scan timed out after {0}s")]
+    Timeout(u64),
+    #[error("Malformed ICAP response: {0}")]
+    Malformed(&'static str),
+    #[error("ICAP server answered {0}")]
+    Status(u16),
+}
+
+/// Outcome of a completed scan
+#[derive(Debug, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    /// Rejected, with the threat name when the scanner reported one
+    Infected(Option<String>),
+}
+
+/// Client for one ICAP service
+#[derive(Debug, Clone)]
+pub struct IcapClient {
+    /// `host:port` to connect to
+    authority: String,
+    /// Request URI sent on the ICAP request line
+    service_url: String,
+    timeout: Duration,
+}
+
+impl IcapClient {
+    /// Create a client for an `icap://host[:port]/service` URL.
+    pub fn new(url: &str, timeout: Duration) -> Result<Self, IcapError> {
+        let parsed = url::Url::parse(url).map_err(|_| IcapError::InvalidUrl(url.to_string()))?;
+        let host = parsed
+            .host_str()
+            .filter(|_| parsed.scheme() == "icap")
+            .ok_or_else(|| IcapError::InvalidUrl(url.to_string()))?;
+        let port = parsed.port().unwrap_or(DEFAULT_PORT);
+        let authority = if host.contains(':') && !host.starts_with('[') {
+            format!("[{host}]:{port}")
+        } else {
+            format!("{host}:{port}")
+        };
+        let mut service_url = format!("icap://{authority}{}", parsed.path());
+        if let Some(query) = parsed.query() {
+            service_url.push('?');
+            service_url.push_str(query);
+        }
+        Ok(Self {
+            authority,
+            service_url,
+            timeout,
+        })
+    }
+
+    /// Scan one file within the configured timeout.
+    pub async fn scan(
+        &self,
+        filename: &str,
+        content_type: &str,
+        data: &[u8],
+    ) -> Result<ScanVerdict, IcapError> {
+        tokio::time::timeout(self.timeout, self.exchange(filename, content_type, data))
+            .await
+            .map_err(|_| IcapError::Timeout(self.timeout.as_secs()))?
+    }
+
+    async fn exchange(
+        &self,
+        filename: &str,
+        content_type: &str,
+        data: &[u8],
+    ) -> Result<ScanVerdict, IcapError> {
+        // Keep the synthetic headers printable whatever the client sent
+        let printable = |value: &str| {
+            value
+                .chars()
+                .filter(|c| !c.is_control())
+                .collect::<String>()
+                .replace('"', "")
+        };
+        let http_head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+             Content-Disposition: attachment; filename=\"{}\"\r\n\r\n",
+            printable(content_type),
+            data.len(),
+            printable(filename)
+        );
+        let icap_head = format!(
+            "RESPMOD {} ICAP/1.0\r\nHost: {}\r\nAllow: 204\r\nConnection: close\r\n\
+             Encapsulated: res-hdr=0, res-body={}\r\n\r\n",
+            self.service_url,
+            self.authority,
+            http_head.len()
+        );
+
+        let mut stream = TcpStream::connect(&self.authority).await?;
+        stream.write_all(icap_head.as_bytes()).await?;
+        stream.write_all(http_head.as_bytes()).await?;
+        for chunk in data.chunks(64 * 1024) {
+            stream
+                .write_all(format!("{:x}\r\n", chunk.len()).as_bytes())
+                .await?;
+            stream.write_all(chunk).await?;
+            stream.write_all(b"\r\n").await?;
+        }
+        stream.write_all(b"0\r\n\r\n").await?;
+        stream.flush().await?;
+
+        let head = read_head(&mut stream).await?;
+        parse_verdict(&head)
+    }
+}
+
+/// Read up to the blank line ending the ICAP response head.
+async fn read_head(stream: &mut TcpStream) -> Result<String, IcapError> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err(IcapError::Malformed(
+                "connection closed before the response head",
+            ));
+        }
+        head.extend_from_slice(&buf[..n]);
+        if let Some(end) = head.windows(4).position(|w| w == b"\r\n\r\n") {
+            head.truncate(end);
+            return String::from_utf8(head).map_err(|_| IcapError::Malformed("non-UTF-8 head"));
+        }
+        if head.len() > MAX_RESPONSE_HEAD_BYTES {
+            return Err(IcapError::Malformed("response head too large"));
+        }
+    }
+}
+
+fn parse_verdict(head: &str) -> Result<ScanVerdict, IcapError> {
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.strip_prefix("ICAP/1.0 "))
+        .and_then(|rest| rest.get(..3))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or(IcapError::Malformed("bad status line"))?;
+    let threat = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| THREAT_HEADERS.contains(&name.trim().to_ascii_lowercase().as_str()))
+        .map(|(_, value)| value.trim().to_string());
+
+    match (status, threat) {
+        (_, Some(threat)) => Ok(ScanVerdict::Infected(Some(threat))),
+        (204, None) => Ok(ScanVerdict::Clean),
+        (200, None) => Ok(ScanVerdict::Infected(None)),
+        (status, None) => Err(IcapError::Status(status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{io::AsyncBufReadExt, net::TcpListener};
+
+    use super::*;
+
+    /// Serve one ICAP exchange, answering `response` once the encapsulated
+    /// body has been read; returns the request received.
+    async fn icap_server(response: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = tokio::io::BufReader::new(stream);
+            let mut request = String::new();
+            while !request.ends_with("\r\n0\r\n\r\n") {
+                if reader.read_line(&mut request).await.unwrap() == 0 {
+                    break;
+                }
+            }
+            reader
+                .get_mut()
+                .write_all(response.as_bytes())
+                .await
+                .unwrap();
+            request
+        });
+        (format!("icap://{addr}/avscan"), handle)
+    }
+
+    #[tokio::test]
+    async fn test_scan_verdicts() {
+        let (url, server) = icap_server("ICAP/1.0 204 No Content\r\nISTag: \"1\"\r\n\r\n").await;
+        let client = IcapClient::new(&url, Duration::from_secs(5)).unwrap();
+        let verdict = client
+            .scan("a\r\n.txt", "text/plain", b"hello")
+            .await
+            .unwrap();
+        assert_eq!(verdict, ScanVerdict::Clean);
+        let request = server.await.unwrap();
+        assert!(request.starts_with(&format!("RESPMOD {url} ICAP/1.0\r\n")));
+        assert!(request.contains("Allow: 204\r\n"));
+        assert!(request.contains("filename=\"a.txt\""));
+        assert!(request.ends_with("\r\n\r\n5\r\nhello\r\n0\r\n\r\n"));
+        let offset: usize = request
+            .split("res-body=")
+            .nth(1)
+            .and_then(|rest| rest.split("\r\n").next())
+            .and_then(|n| n.parse().ok())
+            .unwrap();
+        let http = &request[request.find("HTTP/1.1").unwrap()..];
+        assert!(http[offset..].starts_with("5\r\nhello"));
+
+        let (url, _) = icap_server(
+            "ICAP/1.0 200 OK\r\nX-Infection-Found: Type=0; Resolution=2; Threat=Eicar-Test-Signature;\r\n\r\n",
+        )
+        .await;
+        let client = IcapClient::new(&url, Duration::from_secs(5)).unwrap();
+        assert!(matches!(
+            client.scan("eicar.com", "application/octet-stream", b"X5O!").await,
+            Ok(ScanVerdict::Infected(Some(threat))) if threat.contains("Eicar")
+        ));
+
+        let (url, _) = icap_server("ICAP/1.0 500 Server Error\r\n\r\n").await;
+        let client = IcapClient::new(&url, Duration::from_secs(5)).unwrap();
+        assert!(matches!(
+            client.scan("a", "text/plain", b"x").await,
+            Err(IcapError::Status(500))
+        ));
+
+        assert!(IcapClient::new("http://scanner/avscan", Duration::from_secs(1)).is_err());
+    }
+}
@@ -0,0 +1,368 @@
+//! JSON Schema validation of request and response bodies.
+//!
+//! A check buffers the body (up to `max_body_bytes`), parses it as JSON and
+//! validates it against the configured schema. Requests without a body are
+//! not checked; responses are checked when they are successful (2xx),
+//! uncompressed and declare a JSON media type.
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    body::{Body, Bytes, to_bytes},
+    extract::Request,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    middleware::{Next, from_fn},
+    response::Response,
+};
+use eyre::{WrapErr, eyre};
+use tower::{Layer, util::BoxCloneSyncService};
+
+use crate::{
+    adapters::middleware::MiddlewareRegistry,
+    config::models::{JSON_SCHEMA_MIDDLEWARE_PREFIX, JsonSchemaConfig, SchemaViolationAction},
+    core::{
+        error_response,
+        json_schema::{JsonSchema, SchemaViolation},
+    },
+    metrics,
+};
+
+/// Header marking a body that failed validation under the `annotate` action.
+pub const SCHEMA_VIOLATION_HEADER: HeaderName = HeaderName::from_static("x-schema-violation");
+
+/// Which body a check ran against.
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Request,
+    Response,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::Request => "request",
+            Direction::Response => "response",
+        }
+    }
+}
+
+/// Schemas compiled from a [`JsonSchemaConfig`].
+pub struct SchemaCheck {
+    name: String,
+    request: Option<JsonSchema>,
+    response: Option<JsonSchema>,
+    action: SchemaViolationAction,
+    max_body_bytes: usize,
+}
+
+fn load_schema(path: &str) -> eyre::Result<JsonSchema> {
+    let text = std::fs::read_to_string(path).wrap_err_with(|| format!("Cannot read {path}"))?;
+    let schema = serde_json::from_str(&text).wrap_err_with(|| format!("{path} is not JSON"))?;
+    JsonSchema::compile(&schema).map_err(|e| eyre!("{path}: {e}"))
+}
+
+impl SchemaCheck {
+    /// Load and compile the schemas named by `config`.
+    pub fn load(name: &str, config: &JsonSchemaConfig) -> eyre::Result<Self> {
+        Ok(Self {
+            name: name.to_string(),
+            request: config.request.as_deref().map(load_schema).transpose()?,
+            response: config.response.as_deref().map(load_schema).transpose()?,
+            action: config.action,
+            max_body_bytes: config.max_body_bytes,
+        })
+    }
+
+    /// Validate a buffered body, recording and logging any violations.
+    fn check(
+        &self,
+        schema: &JsonSchema,
+        direction: Direction,
+        route: &str,
+        body: &Bytes,
+    ) -> Result<(), SchemaViolation> {
+        let result = match serde_json::from_slice(body) {
+            Ok(document) => schema.validate(&document),
+            Err(e) => Err(vec![SchemaViolation {
+                path: String::new(),
+                message: format!("body is not valid JSON: {e}"),
+            }]),
+        };
+        let Err(mut violations) = result else {
+            return Ok(());
+        };
+
+        metrics::record_schema_validation_failure(route, &self.name, direction.as_str());
+        let summary: Vec<String> = violations.iter().take(5).map(ToString::to_string).collect();
+        tracing::warn!(
+            schema = %self.name,
+            route,
+            direction = direction.as_str(),
+            action = self.action.as_str(),
+            violations = violations.len(),
+            "Body does not match JSON Schema: {}",
+            summary.join("; ")
+        );
+        Err(violations.swap_remove(0))
+    }
+
+    /// Check the request body; `Err` carries the response to send instead.
+    async fn check_request(&self, req: Request, route: &str) -> Result<Request, Response> {
+        let (mut parts, body) = req.into_parts();
+        if self.action == SchemaViolationAction::Annotate {
+            parts.headers.remove(SCHEMA_VIOLATION_HEADER);
+        }
+        let Some(schema) = &self.request else {
+            return Ok(Request::from_parts(parts, body));
+        };
+        let bytes = to_bytes(body, self.max_body_bytes)
+            .await
+            .map_err(|_| error_response(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large"))?;
+        if !bytes.is_empty()
+            && let Err(violation) = self.check(schema, Direction::Request, route, &bytes)
+        {
+            match self.action {
+                SchemaViolationAction::Block => {
+                    return Err(error_response(
+                        StatusCode::BAD_REQUEST,
+                        format!("Request body does not match schema: {violation}"),
+                    ));
+                }
+                SchemaViolationAction::Annotate => annotate(&mut parts.headers, Direction::Request),
+                SchemaViolationAction::Log => {}
+            }
+        }
+        Ok(Request::from_parts(parts, Body::from(bytes)))
+    }
+
+    /// Check a response body from the rest of the chain.
+    async fn check_response(&self, response: Response, route: &str) -> Response {
+        let Some(schema) = &self.response else {
+            return response;
+        };
+        if !response.status().is_success()
+            || response.headers().contains_key(header::CONTENT_ENCODING)
+            || !is_json(response.headers())
+        {
+            return response;
+        }
+        let (mut parts, body) = response.into_parts();
+        let Ok(bytes) = to_bytes(body, self.max_body_bytes).await else {
+            tracing::warn!(
+                schema = %self.name,
+                "Response body exceeds max_body_bytes for JSON Schema check"
+            );
+            return error_response(StatusCode::BAD_GATEWAY, "Response body too large");
+        };
+        if let Err(violation) = self.check(schema, Direction::Response, route, &bytes) {
+            match self.action {
+                SchemaViolationAction::Block => {
+                    return error_response(
+                        StatusCode::BAD_GATEWAY,
+                        format!("Response body does not match schema: {violation}"),
+                    );
+                }
+                SchemaViolationAction::Annotate => {
+                    annotate(&mut parts.headers, Direction::Response)
+                }
+                SchemaViolationAction::Log => {}
+            }
+        }
+        Response::from_parts(parts, Body::from(bytes))
+    }
+}
+
+fn annotate(headers: &mut HeaderMap, direction: Direction) {
+    headers.insert(
+        SCHEMA_VIOLATION_HEADER,
+        HeaderValue::from_static(direction.as_str()),
+    );
+}
+
+/// Whether the declared media type is `application/json` or `*/*+json`.
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|media_type| media_type.trim().to_ascii_lowercase())
+        .is_some_and(|media_type| media_type == "application/json" || media_type.ends_with("+json"))
+}
+
+/// Check the request body, run the rest of the chain, then check its response.
+async fn json_schema_middleware(
+    req: Request,
+    next: Next,
+    check: Arc<SchemaCheck>,
+    route: Arc<str>,
+) -> Response {
+    let req = match check.check_request(req, &route).await {
+        Ok(req) => req,
+        Err(response) => return response,
+    };
+    let response = next.run(req).await;
+    check.check_response(response, &route).await
+}
+
+/// Compile every configured check and register it as `json_schema:<name>`.
+pub fn register_json_schemas(
+    registry: &mut MiddlewareRegistry,
+    checks: &HashMap<String, JsonSchemaConfig>,
+) -> eyre::Result<()> {
+    for (name, config) in checks {
+        let check = Arc::new(
+            SchemaCheck::load(name, config)
+                .wrap_err_with(|| format!("JSON Schema check '{name}'"))?,
+        );
+        registry.register(
+            format!("{JSON_SCHEMA_MIDDLEWARE_PREFIX}{name}"),
+            move |inner, ctx| {
+                let check = check.clone();
+                let route: Arc<str> = Arc::from(ctx.route_prefix);
+                BoxCloneSyncService::new(
+                    from_fn(move |req, next| {
+                        json_schema_middleware(req, next, check.clone(), route.clone())
+                    })
+                    .layer(inner),
+                )
+            },
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use axum::{Router, middleware, routing::post};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn schema_file(schema: serde_json::Value) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().expect("temp schema");
+        file.write_all(schema.to_string().as_bytes())
+            .expect("write schema");
+        file
+    }
+
+    fn app(check: SchemaCheck, response: &'static str) -> Router {
+        let check = Arc::new(check);
+        Router::new()
+            .route(
+                "/users",
+                post(move |headers: HeaderMap| async move {
+                    let annotated = headers.contains_key(SCHEMA_VIOLATION_HEADER);
+                    (
+                        [
+                            (header::CONTENT_TYPE, "application/json"),
+                            (
+                                HeaderName::from_static("x-annotated"),
+                                if annotated { "1" } else { "0" },
+                            ),
+                        ],
+                        response,
+                    )
+                }),
+            )
+            .layer(middleware::from_fn(move |req, next| {
+                json_schema_middleware(req, next, check.clone(), Arc::from("/users"))
+            }))
+    }
+
+    async fn send(app: &Router, body: &'static str) -> Response {
+        app.clone()
+            .oneshot(
+                Request::post("/users")
+                    .header(SCHEMA_VIOLATION_HEADER, "forged")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_schema_actions() {
+        let request = schema_file(serde_json::json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": { "name": { "type": "string" } }
+        }));
+        let response = schema_file(serde_json::json!({
+            "type": "object",
+            "required": ["id"]
+        }));
+        let config = |action| JsonSchemaConfig {
+            request: Some(request.path().display().to_string()),
+            response: Some(response.path().display().to_string()),
+            action,
+            max_body_bytes: 64,
+        };
+
+        let block = app(
+            SchemaCheck::load("users", &config(SchemaViolationAction::Block)).unwrap(),
+            r#"{"id":1}"#,
+        );
+        assert_eq!(
+            send(&block, r#"{"name":"ann"}"#).await.status(),
+            StatusCode::OK
+        );
+        assert_eq!(send(&block, "").await.status(), StatusCode::OK);
+        assert_eq!(
+            send(&block, r#"{"name":1}"#).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            send(&block, "not json").await.status(),
+            StatusCode::BAD_REQUEST
+        );
+        let large =
+            r#"{"name":"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"}"#;
+        assert_eq!(
+            send(&block, large).await.status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let bad_backend = app(
+            SchemaCheck::load("users", &config(SchemaViolationAction::Block)).unwrap(),
+            r#"{"name":"ann"}"#,
+        );
+        assert_eq!(
+            send(&bad_backend, r#"{"name":"ann"}"#).await.status(),
+            StatusCode::BAD_GATEWAY
+        );
+
+        let log = app(
+            SchemaCheck::load("users", &config(SchemaViolationAction::Log)).unwrap(),
+            "[]",
+        );
+        let res = send(&log, r#"{"name":1}"#).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(SCHEMA_VIOLATION_HEADER));
+        assert_eq!(
+            res.headers()["x-annotated"],
+            "1",
+            "client header is passed through"
+        );
+
+        let annotate = app(
+            SchemaCheck::load("users", &config(SchemaViolationAction::Annotate)).unwrap(),
+            "[]",
+        );
+        let res = send(&annotate, r#"{"name":"ann"}"#).await;
+        assert_eq!(
+            res.headers()["x-annotated"],
+            "0",
+            "forged header is removed"
+        );
+        assert_eq!(res.headers()[SCHEMA_VIOLATION_HEADER], "response");
+        let res = send(&annotate, r#"{"name":1}"#).await;
+        assert_eq!(res.headers()["x-annotated"], "1");
+
+        let exposition = metrics::schema_validation_failure_exposition();
+        assert!(exposition.contains(
+            "axon_schema_validation_failures_total{route=\"/users\",schema=\"users\",direction=\"request\"}"
+        ));
+    }
+}
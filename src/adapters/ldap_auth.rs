@@ -0,0 +1,431 @@
+//! HTTP Basic authentication against LDAP or Active Directory.
+//!
+//! Credentials are verified by binding to the directory, either directly as
+//! a DN built from the user name or after finding the user's entry with a
+//! service account. The user's groups then decide whether the route may be
+//! used. Connections are pooled, and successful authentications are cached
+//! for a TTL (keyed by user, with a salted password digest) so that not
+//! every request costs a bind. The user and groups are left in the request
+//! extensions as an [`AuthenticatedUser`] for `rbac:` policies.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::Request,
+    http::{HeaderValue, StatusCode, header},
+    middleware::{Next, from_fn},
+    response::Response,
+};
+use eyre::{WrapErr, eyre};
+use ldap3::{Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry, dn_escape, ldap_escape};
+use ring::rand::{SecureRandom, SystemRandom};
+use sha2::{Digest, Sha256};
+use tokio::sync::Semaphore;
+use tower::{Layer, util::BoxCloneSyncService};
+
+use crate::{
+    adapters::{
+        audit::AuditLog,
+        basic_auth::{basic_credentials, record_auth_failure},
+        middleware::MiddlewareRegistry,
+    },
+    config::models::{LDAP_AUTH_MIDDLEWARE_PREFIX, LdapAuthConfig, LdapSearchConfig},
+    core::{
+        error_response,
+        rbac::{AuthenticatedUser, group_matches},
+        secret::constant_time_eq,
+    },
+};
+
+/// LDAP result code for a failed bind
+const INVALID_CREDENTIALS: u32 = 49;
+
+/// A remembered successful authentication
+struct CachedAuth {
+    digest: [u8; 32],
+    groups: Arc<[String]>,
+    expires_at: Instant,
+}
+
+/// An [`LdapAuthConfig`] with its connection pool and cache.
+pub struct LdapAuth {
+    url: String,
+    settings: LdapConnSettings,
+    user_dn: Option<String>,
+    search: Option<LdapSearchConfig>,
+    group_attribute: String,
+    required_groups: Vec<String>,
+    timeout: Duration,
+    cache_ttl: Duration,
+    cache: scc::HashMap<String, CachedAuth>,
+    salt: [u8; 16],
+    idle: Mutex<Vec<Ldap>>,
+    permits: Semaphore,
+    challenge: HeaderValue,
+}
+
+impl LdapAuth {
+    /// Build the authenticator; no connection is made until the first request.
+    pub fn new(config: &LdapAuthConfig) -> eyre::Result<Self> {
+        let timeout = Duration::from_secs(config.timeout_secs);
+        let mut salt = [0u8; 16];
+        SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| eyre!("System random source is unavailable"))?;
+        let challenge = HeaderValue::from_str(&format!(
+            "Basic realm=\"{}\", charset=\"UTF-8\"",
+            config.realm
+        ))
+        .map_err(|_| eyre!("Invalid realm '{}'", config.realm))?;
+        if config.user_dn.is_none() && config.search.is_none() {
+            return Err(eyre!("Set user_dn or search"));
+        }
+        Ok(Self {
+            url: config.url.clone(),
+            settings: LdapConnSettings::new()
+                .set_conn_timeout(timeout)
+                .set_starttls(config.starttls),
+            user_dn: config.user_dn.clone(),
+            search: config.search.clone(),
+            group_attribute: config.group_attribute.clone(),
+            required_groups: config.required_groups.clone(),
+            timeout,
+            cache_ttl: Duration::from_secs(config.cache_ttl_secs),
+            cache: scc::HashMap::default(),
+            salt,
+            idle: Mutex::new(Vec::new()),
+            permits: Semaphore::new(config.pool_size.max(1)),
+            challenge,
+        })
+    }
+
+    /// The groups of a user whose password is correct, `None` for wrong
+    /// credentials, or an error when the directory could not be asked.
+    pub async fn authenticate(
+        &self,
+        user: &str,
+        password: &str,
+    ) -> Result<Option<Arc<[String]>>, ldap3::LdapError> {
+        // An empty password would make an unauthenticated bind, which succeeds
+        if user.is_empty() || password.is_empty() {
+            return Ok(None);
+        }
+        let digest = self.digest(password);
+        let now = Instant::now();
+        let cached = self
+            .cache
+            .read_sync(user, |_, cached| {
+                (cached.expires_at > now && constant_time_eq(cached.digest, digest))
+                    .then(|| cached.groups.clone())
+            })
+            .flatten();
+        if cached.is_some() {
+            return Ok(cached);
+        }
+
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("the semaphore is never closed");
+        let idle = self.idle.lock().expect("pool lock poisoned").pop();
+        let mut ldap = match idle {
+            Some(ldap) => ldap,
+            None => self.connect().await?,
+        };
+        let groups = self.check(&mut ldap, user, password).await?;
+        self.idle.lock().expect("pool lock poisoned").push(ldap);
+
+        if let Some(groups) = &groups
+            && !self.cache_ttl.is_zero()
+        {
+            self.cache.upsert_sync(
+                user.to_string(),
+                CachedAuth {
+                    digest,
+                    groups: groups.clone(),
+                    expires_at: now + self.cache_ttl,
+                },
+            );
+        }
+        Ok(groups)
+    }
+
+    /// Whether a user in `groups` may use the route.
+    pub fn authorized(&self, groups: &[String]) -> bool {
+        self.required_groups.is_empty()
+            || self
+                .required_groups
+                .iter()
+                .any(|required| groups.iter().any(|group| group_matches(group, required)))
+    }
+
+    fn digest(&self, password: &str) -> [u8; 32] {
+        Sha256::new()
+            .chain_update(self.salt)
+            .chain_update(password)
+            .finalize()
+            .into()
+    }
+
+    async fn connect(&self) -> Result<Ldap, ldap3::LdapError> {
+        let (conn, ldap) = LdapConnAsync::with_settings(self.settings.clone(), &self.url).await?;
+        tokio::spawn(async move {
+            if let Err(e) = conn.drive().await {
+                tracing::debug!("LDAP connection closed: {e}");
+            }
+        });
+        Ok(ldap)
+    }
+
+    /// Bind as the user and read their groups.
+    async fn check(
+        &self,
+        ldap: &mut Ldap,
+        user: &str,
+        password: &str,
+    ) -> Result<Option<Arc<[String]>>, ldap3::LdapError> {
+        let attributes = [self.group_attribute.as_str()];
+        let (dn, groups) = match (&self.search, &self.user_dn) {
+            (Some(search), _) => {
+                ldap.with_timeout(self.timeout)
+                    .simple_bind(&search.bind_dn, &search.bind_password)
+                    .await?
+                    .success()?;
+                let filter = search.filter.replace("{username}", &ldap_escape(user));
+                let (entries, _) = ldap
+                    .with_timeout(self.timeout)
+                    .search(&search.base_dn, Scope::Subtree, &filter, attributes)
+                    .await?
+                    .success()?;
+                // Unknown and ambiguous user names are rejected alike
+                let mut entries = entries.into_iter();
+                let (Some(entry), None) = (entries.next(), entries.next()) else {
+                    return Ok(None);
+                };
+                let entry = SearchEntry::construct(entry);
+                let groups = self.groups_of(&entry);
+                (entry.dn, Some(groups))
+            }
+            (None, Some(template)) => (template.replace("{username}", &dn_escape(user)), None),
+            (None, None) => unreachable!("checked in LdapAuth::new"),
+        };
+
+        let bind = ldap
+            .with_timeout(self.timeout)
+            .simple_bind(&dn, password)
+            .await?;
+        if bind.rc == INVALID_CREDENTIALS {
+            return Ok(None);
+        }
+        bind.success()?;
+
+        let groups = match groups {
+            Some(groups) => groups,
+            None if self.required_groups.is_empty() => Vec::new(),
+            None => {
+                let (entries, _) = ldap
+                    .with_timeout(self.timeout)
+                    .search(&dn, Scope::Base, "(objectClass=*)", attributes)
+                    .await?
+                    .success()?;
+                entries
+                    .into_iter()
+                    .next()
+                    .map(|entry| self.groups_of(&SearchEntry::construct(entry)))
+                    .unwrap_or_default()
+            }
+        };
+        Ok(Some(groups.into()))
+    }
+
+    fn groups_of(&self, entry: &SearchEntry) -> Vec<String> {
+        entry
+            .attrs
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&self.group_attribute))
+            .map(|(_, values)| values.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Authenticate with the directory, then check the user's groups.
+async fn ldap_auth_middleware(
+    mut req: Request,
+    next: Next,
+    auth: Arc<LdapAuth>,
+    audit: AuditLog,
+    route: Arc<str>,
+) -> Response {
+    let credentials = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(basic_credentials);
+    let rule = match credentials {
+        Some((user, password)) => match auth.authenticate(&user, &password).await {
+            Ok(Some(groups)) if auth.authorized(&groups) => {
+                req.extensions_mut()
+                    .insert(AuthenticatedUser { name: user, groups });
+                return next.run(req).await;
+            }
+            Ok(Some(_)) => {
+                record_auth_failure(&audit, &req, "insufficient_groups", &route);
+                return error_response(StatusCode::FORBIDDEN, "Forbidden");
+            }
+            Ok(None) => "invalid_credentials",
+            Err(e) => {
+                tracing::warn!(url = %auth.url, "LDAP authentication failed: {e}");
+                return error_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Authentication service unavailable",
+                );
+            }
+        },
+        None => "missing_credentials",
+    };
+    record_auth_failure(&audit, &req, rule, &route);
+    let mut response = error_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+    response
+        .headers_mut()
+        .insert(header::WWW_AUTHENTICATE, auth.challenge.clone());
+    response
+}
+
+/// Register every configured setting as `ldap_auth:<name>`.
+pub fn register_ldap_auth(
+    registry: &mut MiddlewareRegistry,
+    configs: &HashMap<String, LdapAuthConfig>,
+) -> eyre::Result<()> {
+    for (name, config) in configs {
+        let auth = Arc::new(LdapAuth::new(config).wrap_err_with(|| format!("LDAP auth '{name}'"))?);
+        registry.register(
+            format!("{LDAP_AUTH_MIDDLEWARE_PREFIX}{name}"),
+            move |inner, ctx| {
+                let auth = auth.clone();
+                let audit = ctx.audit.clone();
+                let route: Arc<str> = Arc::from(ctx.route_prefix);
+                BoxCloneSyncService::new(
+                    from_fn(move |req, next| {
+                        ldap_auth_middleware(req, next, auth.clone(), audit.clone(), route.clone())
+                    })
+                    .layer(inner),
+                )
+            },
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, body::Body, routing::get};
+    use base64::{Engine as _, engine::general_purpose::STANDARD};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn config() -> LdapAuthConfig {
+        LdapAuthConfig {
+            // Nothing listens on port 1
+            url: "ldap://127.0.0.1:1".to_string(),
+            user_dn: Some("uid={username},ou=people,dc=example,dc=com".to_string()),
+            required_groups: vec![
+                "ops".to_string(),
+                "cn=Admins,ou=groups,dc=example,dc=com".to_string(),
+            ],
+            timeout_secs: 1,
+            ..LdapAuthConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_group_authorization() {
+        let auth = LdapAuth::new(&config()).unwrap();
+        let groups = |groups: &[&str]| groups.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+        assert!(auth.authorized(&groups(&["CN=Ops,ou=groups,dc=example,dc=com"])));
+        assert!(auth.authorized(&groups(&["cn=admins,OU=groups,dc=example,dc=com"])));
+        assert!(!auth.authorized(&groups(&["cn=devops,ou=groups,dc=example,dc=com"])));
+        assert!(!auth.authorized(&groups(&["ou=ops,dc=example,dc=com"])));
+        assert!(!auth.authorized(&[]));
+
+        let open = LdapAuth::new(&LdapAuthConfig {
+            required_groups: vec![],
+            ..config()
+        })
+        .unwrap();
+        assert!(open.authorized(&[]));
+        assert!(
+            LdapAuth::new(&LdapAuthConfig {
+                user_dn: None,
+                ..config()
+            })
+            .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ldap_auth_middleware() {
+        let auth = Arc::new(LdapAuth::new(&config()).unwrap());
+        auth.cache.upsert_sync(
+            "alice".to_string(),
+            CachedAuth {
+                digest: auth.digest("secret"),
+                groups: vec!["cn=ops,ou=groups,dc=example,dc=com".to_string()].into(),
+                expires_at: Instant::now() + Duration::from_secs(60),
+            },
+        );
+        auth.cache.upsert_sync(
+            "bob".to_string(),
+            CachedAuth {
+                digest: auth.digest("secret"),
+                groups: vec!["cn=dev,ou=groups,dc=example,dc=com".to_string()].into(),
+                expires_at: Instant::now() + Duration::from_secs(60),
+            },
+        );
+        let app = {
+            let auth = auth.clone();
+            Router::new()
+                .route("/", get(|| async { "ok" }))
+                .layer(axum::middleware::from_fn(move |req, next| {
+                    ldap_auth_middleware(
+                        req,
+                        next,
+                        auth.clone(),
+                        AuditLog::disabled(),
+                        Arc::from("/"),
+                    )
+                }))
+        };
+        let status = |credentials: Option<&str>| {
+            let app = app.clone();
+            let mut req = Request::get("/");
+            if let Some(credentials) = credentials {
+                req = req.header(
+                    header::AUTHORIZATION,
+                    format!("Basic {}", STANDARD.encode(credentials)),
+                );
+            }
+            async move {
+                app.oneshot(req.body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        // Served from the cache without reaching the directory
+        assert_eq!(status(Some("alice:secret")).await, StatusCode::OK);
+        assert_eq!(status(Some("bob:secret")).await, StatusCode::FORBIDDEN);
+        assert_eq!(status(Some("alice:")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(None).await, StatusCode::UNAUTHORIZED);
+        // A cache miss needs the directory, which is down
+        assert_eq!(
+            status(Some("alice:other")).await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
@@ -0,0 +1,561 @@
+//! Metrics exporters for deployments without an OTLP collector.
+//!
+//! Each exporter is an OpenTelemetry [`PushMetricExporter`] driven by the
+//! SDK's periodic reader and sits behind its own Cargo feature:
+//!
+//! * `prometheus` keeps the latest collection as Prometheus text and appends
+//!   it to the `/metrics` exposition, so counters and histograms there carry
+//!   real values (refreshed every `metrics.interval_secs`).
+//! * `statsd` sends each collection's deltas to a StatsD agent over UDP,
+//!   with attributes as DogStatsD tags.
+//! * `pushgateway` PUTs the Prometheus text to a Pushgateway.
+//!
+//! Exports run on the reader's own thread. The StatsD exporter uses a
+//! blocking socket; the Pushgateway exporter runs its request on the Tokio
+//! runtime that initialized metrics.
+//!
+//! [`PushMetricExporter`]: opentelemetry_sdk::metrics::exporter::PushMetricExporter
+
+#[cfg(any(feature = "prometheus", feature = "pushgateway"))]
+use std::fmt::{Display, Write as _};
+#[cfg(feature = "prometheus")]
+use std::sync::Mutex;
+
+#[cfg(feature = "prometheus")]
+use once_cell::sync::Lazy;
+#[cfg(any(feature = "prometheus", feature = "pushgateway", feature = "statsd"))]
+use opentelemetry::KeyValue;
+#[cfg(any(feature = "pushgateway", feature = "statsd"))]
+use opentelemetry_sdk::error::OTelSdkError;
+#[cfg(any(feature = "prometheus", feature = "pushgateway", feature = "statsd"))]
+use opentelemetry_sdk::{
+    error::OTelSdkResult,
+    metrics::{
+        Temporality,
+        data::{AggregatedMetrics, MetricData, ResourceMetrics},
+        exporter::PushMetricExporter,
+    },
+};
+
+/// Families from the latest collection, as `(name, exposition text)`
+#[cfg(feature = "prometheus")]
+static PROMETHEUS_SNAPSHOT: Lazy<Mutex<Vec<(String, String)>>> =
+    Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Whether the latest `prometheus` collection includes the family `name`.
+pub fn has_prometheus_family(name: &str) -> bool {
+    #[cfg(feature = "prometheus")]
+    {
+        PROMETHEUS_SNAPSHOT
+            .lock()
+            .is_ok_and(|families| families.iter().any(|(family, _)| family == name))
+    }
+    #[cfg(not(feature = "prometheus"))]
+    {
+        let _ = name;
+        false
+    }
+}
+
+/// Prometheus text for the latest `prometheus` collection, leaving out
+/// families already declared (by a `# TYPE` line) in `existing`.
+pub fn prometheus_exposition(existing: &str) -> String {
+    #[cfg(feature = "prometheus")]
+    {
+        let Ok(families) = PROMETHEUS_SNAPSHOT.lock() else {
+            return String::new();
+        };
+        families
+            .iter()
+            .filter(|(name, _)| !existing.contains(&format!("# TYPE {name} ")))
+            .map(|(_, text)| text.as_str())
+            .collect()
+    }
+    #[cfg(not(feature = "prometheus"))]
+    {
+        let _ = existing;
+        String::new()
+    }
+}
+
+/// Render a collection as Prometheus text, one entry per metric family.
+///
+/// Sums become counters when monotonic and gauges otherwise; exponential
+/// histograms, which have no Prometheus text equivalent, are reduced to a
+/// summary with only `_sum` and `_count`.
+#[cfg(any(feature = "prometheus", feature = "pushgateway"))]
+pub fn render_prometheus(metrics: &ResourceMetrics) -> Vec<(String, String)> {
+    let mut families = Vec::new();
+    for metric in metrics.scope_metrics().flat_map(|scope| scope.metrics()) {
+        let name = sanitize_name(metric.name());
+        let mut text = String::new();
+        if !metric.description().is_empty() {
+            let help = metric
+                .description()
+                .replace('\\', "\\\\")
+                .replace('\n', "\\n");
+            let _ = writeln!(text, "# HELP {name} {help}");
+        }
+        match metric.data() {
+            AggregatedMetrics::F64(data) => render_prometheus_data(&mut text, &name, data),
+            AggregatedMetrics::U64(data) => render_prometheus_data(&mut text, &name, data),
+            AggregatedMetrics::I64(data) => render_prometheus_data(&mut text, &name, data),
+        }
+        families.push((name, text));
+    }
+    families
+}
+
+#[cfg(any(feature = "prometheus", feature = "pushgateway"))]
+fn render_prometheus_data<T: Copy + Display>(out: &mut String, name: &str, data: &MetricData<T>) {
+    match data {
+        MetricData::Gauge(gauge) => {
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            for point in gauge.data_points() {
+                let labels = prometheus_labels(point.attributes(), None);
+                let _ = writeln!(out, "{name}{labels} {}", point.value());
+            }
+        }
+        MetricData::Sum(sum) => {
+            let kind = if sum.is_monotonic() {
+                "counter"
+            } else {
+                "gauge"
+            };
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            for point in sum.data_points() {
+                let labels = prometheus_labels(point.attributes(), None);
+                let _ = writeln!(out, "{name}{labels} {}", point.value());
+            }
+        }
+        MetricData::Histogram(histogram) => {
+            let _ = writeln!(out, "# TYPE {name} histogram");
+            for point in histogram.data_points() {
+                let mut cumulative = 0;
+                for (bound, count) in point.bounds().zip(point.bucket_counts()) {
+                    cumulative += count;
+                    let labels = prometheus_labels(point.attributes(), Some(&bound.to_string()));
+                    let _ = writeln!(out, "{name}_bucket{labels} {cumulative}");
+                }
+                let labels = prometheus_labels(point.attributes(), Some("+Inf"));
+                let _ = writeln!(out, "{name}_bucket{labels} {}", point.count());
+                let labels = prometheus_labels(point.attributes(), None);
+                let _ = writeln!(out, "{name}_sum{labels} {}", point.sum());
+                let _ = writeln!(out, "{name}_count{labels} {}", point.count());
+            }
+        }
+        MetricData::ExponentialHistogram(histogram) => {
+            let _ = writeln!(out, "# TYPE {name} summary");
+            for point in histogram.data_points() {
+                let labels = prometheus_labels(point.attributes(), None);
+                let _ = writeln!(out, "{name}_sum{labels} {}", point.sum());
+                let _ = writeln!(out, "{name}_count{labels} {}", point.count());
+            }
+        }
+    }
+}
+
+/// `{k="v",...}` for a data point, with an optional `le` bucket label
+#[cfg(any(feature = "prometheus", feature = "pushgateway"))]
+fn prometheus_labels<'a>(
+    attributes: impl Iterator<Item = &'a KeyValue>,
+    le: Option<&str>,
+) -> String {
+    let mut labels: Vec<String> = attributes
+        .map(|kv| {
+            let value = kv
+                .value
+                .as_str()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{value}\"", sanitize_name(kv.key.as_str()))
+        })
+        .collect();
+    if let Some(le) = le {
+        labels.push(format!("le=\"{le}\""));
+    }
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", labels.join(","))
+    }
+}
+
+/// Replace characters Prometheus does not allow in names with `_`
+#[cfg(any(feature = "prometheus", feature = "pushgateway"))]
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            'a'..='z' | 'A'..='Z' | '_' | ':' => c,
+            '0'..='9' if i > 0 => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Keeps the latest collection for the `/metrics` exposition.
+#[cfg(feature = "prometheus")]
+#[derive(Debug, Default)]
+pub struct PrometheusExporter;
+
+#[cfg(feature = "prometheus")]
+impl PushMetricExporter for PrometheusExporter {
+    async fn export(&self, metrics: &ResourceMetrics) -> OTelSdkResult {
+        let families = render_prometheus(metrics);
+        if let Ok(mut snapshot) = PROMETHEUS_SNAPSHOT.lock() {
+            *snapshot = families;
+        }
+        Ok(())
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn shutdown_with_timeout(&self, _timeout: std::time::Duration) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn temporality(&self) -> Temporality {
+        Temporality::Cumulative
+    }
+}
+
+/// Render a collection as StatsD lines with DogStatsD tags.
+///
+/// Delta sums are sent as counters and everything else as gauges; histograms
+/// become `.count` and `.sum` counters plus `.min` and `.max` gauges.
+#[cfg(feature = "statsd")]
+pub fn render_statsd(metrics: &ResourceMetrics, prefix: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    for metric in metrics.scope_metrics().flat_map(|scope| scope.metrics()) {
+        let name = format!("{prefix}{}", metric.name());
+        match metric.data() {
+            AggregatedMetrics::F64(data) => render_statsd_data(&mut lines, &name, data),
+            AggregatedMetrics::U64(data) => render_statsd_data(&mut lines, &name, data),
+            AggregatedMetrics::I64(data) => render_statsd_data(&mut lines, &name, data),
+        }
+    }
+    lines
+}
+
+#[cfg(feature = "statsd")]
+fn render_statsd_data<T: Copy + std::fmt::Display>(
+    lines: &mut Vec<String>,
+    name: &str,
+    data: &MetricData<T>,
+) {
+    match data {
+        MetricData::Gauge(gauge) => {
+            for point in gauge.data_points() {
+                let tags = statsd_tags(point.attributes());
+                lines.push(format!("{name}:{}|g{tags}", point.value()));
+            }
+        }
+        MetricData::Sum(sum) => {
+            let kind = if sum.is_monotonic() && sum.temporality() == Temporality::Delta {
+                "c"
+            } else {
+                "g"
+            };
+            for point in sum.data_points() {
+                let tags = statsd_tags(point.attributes());
+                lines.push(format!("{name}:{}|{kind}{tags}", point.value()));
+            }
+        }
+        MetricData::Histogram(histogram) => {
+            for point in histogram.data_points().filter(|point| point.count() > 0) {
+                let tags = statsd_tags(point.attributes());
+                lines.push(format!("{name}.count:{}|c{tags}", point.count()));
+                lines.push(format!("{name}.sum:{}|c{tags}", point.sum()));
+                if let Some(min) = point.min() {
+                    lines.push(format!("{name}.min:{min}|g{tags}"));
+                }
+                if let Some(max) = point.max() {
+                    lines.push(format!("{name}.max:{max}|g{tags}"));
+                }
+            }
+        }
+        MetricData::ExponentialHistogram(histogram) => {
+            for point in histogram.data_points().filter(|point| point.count() > 0) {
+                let tags = statsd_tags(point.attributes());
+                lines.push(format!("{name}.count:{}|c{tags}", point.count()));
+                lines.push(format!("{name}.sum:{}|c{tags}", point.sum()));
+            }
+        }
+    }
+}
+
+/// `|#k:v,...` for a data point, with StatsD separators replaced
+#[cfg(feature = "statsd")]
+fn statsd_tags<'a>(attributes: impl Iterator<Item = &'a KeyValue>) -> String {
+    let clean = |s: &str| s.replace(['|', ',', '#', '\n'], "_");
+    let tags: Vec<String> = attributes
+        .map(|kv| format!("{}:{}", clean(kv.key.as_str()), clean(&kv.value.as_str())))
+        .collect();
+    if tags.is_empty() {
+        String::new()
+    } else {
+        format!("|#{}", tags.join(","))
+    }
+}
+
+/// Join lines into newline-separated datagrams of at most `max_bytes`; a
+/// line longer than that is sent on its own.
+#[cfg(feature = "statsd")]
+pub fn batch_datagrams(lines: &[String], max_bytes: usize) -> Vec<String> {
+    let mut datagrams = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        if !current.is_empty() && current.len() + 1 + line.len() > max_bytes {
+            datagrams.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        datagrams.push(current);
+    }
+    datagrams
+}
+
+/// Sends each collection to a StatsD agent over UDP.
+#[cfg(feature = "statsd")]
+#[derive(Debug)]
+pub struct StatsdExporter {
+    config: crate::config::StatsdConfig,
+}
+
+#[cfg(feature = "statsd")]
+impl StatsdExporter {
+    pub fn new(config: &crate::config::StatsdConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+
+    fn send(&self, datagrams: &[String]) -> std::io::Result<()> {
+        use std::net::{ToSocketAddrs, UdpSocket};
+
+        // Resolved on every export so an agent that moves is followed
+        let address = self
+            .config
+            .address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| std::io::Error::other("address resolved to nothing"))?;
+        let bind = if address.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind)?;
+        for datagram in datagrams {
+            socket.send_to(datagram.as_bytes(), address)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "statsd")]
+impl PushMetricExporter for StatsdExporter {
+    async fn export(&self, metrics: &ResourceMetrics) -> OTelSdkResult {
+        let lines = render_statsd(metrics, &self.config.prefix);
+        let datagrams = batch_datagrams(&lines, self.config.max_packet_bytes);
+        self.send(&datagrams)
+            .map_err(|e| OTelSdkError::InternalFailure(format!("statsd send failed: {e}")))
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn shutdown_with_timeout(&self, _timeout: std::time::Duration) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn temporality(&self) -> Temporality {
+        // Delta for counters and histograms, cumulative for up-down counters,
+        // which StatsD wants as absolute gauges
+        Temporality::LowMemory
+    }
+}
+
+/// Pushes each collection to a Prometheus Pushgateway.
+#[cfg(feature = "pushgateway")]
+pub struct PushgatewayExporter {
+    client: std::sync::Arc<crate::adapters::HttpClientAdapter>,
+    url: String,
+    timeout: std::time::Duration,
+    runtime: tokio::runtime::Handle,
+}
+
+#[cfg(feature = "pushgateway")]
+impl PushgatewayExporter {
+    /// Must be called within the Tokio runtime that will run the pushes.
+    pub fn new(
+        config: &crate::config::PushgatewayConfig,
+        timeout: std::time::Duration,
+    ) -> eyre::Result<Self> {
+        let mut url = format!(
+            "{}/metrics/job/{}",
+            config.url.trim_end_matches('/'),
+            config.job
+        );
+        if let Some(instance) = &config.instance {
+            url.push_str(&format!("/instance/{instance}"));
+        }
+        Ok(Self {
+            client: std::sync::Arc::new(crate::adapters::HttpClientAdapter::new()?),
+            url,
+            timeout,
+            runtime: tokio::runtime::Handle::try_current()?,
+        })
+    }
+}
+
+#[cfg(feature = "pushgateway")]
+impl PushMetricExporter for PushgatewayExporter {
+    async fn export(&self, metrics: &ResourceMetrics) -> OTelSdkResult {
+        use crate::ports::http_client::HttpClient;
+
+        let body: String = render_prometheus(metrics)
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect();
+        let request = hyper::Request::put(&self.url)
+            .header(http::header::CONTENT_TYPE, "text/plain; version=0.0.4")
+            .body(axum::body::Body::from(body))
+            .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+        let client = self.client.clone();
+        let timeout = self.timeout;
+        // The reader thread has no Tokio context, so the request runs on the
+        // runtime captured at startup
+        let response = self
+            .runtime
+            .spawn(async move { tokio::time::timeout(timeout, client.send_request(request)).await })
+            .await
+            .map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+        match response {
+            Ok(Ok(response)) if response.status().is_success() => Ok(()),
+            Ok(Ok(response)) => Err(OTelSdkError::InternalFailure(format!(
+                "pushgateway returned {}",
+                response.status()
+            ))),
+            Ok(Err(e)) => Err(OTelSdkError::InternalFailure(e.to_string())),
+            Err(_) => Err(OTelSdkError::Timeout(timeout)),
+        }
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn shutdown_with_timeout(&self, _timeout: std::time::Duration) -> OTelSdkResult {
+        Ok(())
+    }
+
+    fn temporality(&self) -> Temporality {
+        Temporality::Cumulative
+    }
+}
+
+#[cfg(all(test, any(feature = "prometheus", feature = "statsd")))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+
+    use super::*;
+
+    /// Records what it is given, so tests can render a real collection
+    #[derive(Clone)]
+    struct Capture {
+        temporality: Temporality,
+        rendered: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl PushMetricExporter for Capture {
+        async fn export(&self, metrics: &ResourceMetrics) -> OTelSdkResult {
+            let mut rendered = self.rendered.lock().unwrap();
+            #[cfg(feature = "prometheus")]
+            if self.temporality == Temporality::Cumulative {
+                rendered.extend(render_prometheus(metrics).into_iter().map(|(_, t)| t));
+            }
+            #[cfg(feature = "statsd")]
+            if self.temporality == Temporality::LowMemory {
+                rendered.extend(render_statsd(metrics, "gw."));
+            }
+            Ok(())
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(&self, _timeout: std::time::Duration) -> OTelSdkResult {
+            Ok(())
+        }
+
+        fn temporality(&self) -> Temporality {
+            self.temporality
+        }
+    }
+
+    fn collect(temporality: Temporality) -> String {
+        let capture = Capture {
+            temporality,
+            rendered: Arc::default(),
+        };
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(capture.clone()).build())
+            .build();
+        let meter = provider.meter("test");
+        meter
+            .u64_counter("requests_total")
+            .build()
+            .add(3, &[KeyValue::new("route", "/api \"v1\"")]);
+        meter
+            .f64_histogram("duration_seconds")
+            .with_boundaries(vec![0.1, 1.0])
+            .build()
+            .record(0.5, &[]);
+        meter.i64_up_down_counter("open").build().add(2, &[]);
+        provider.force_flush().unwrap();
+        capture.rendered.lock().unwrap().join("\n")
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_render_prometheus() {
+        let text = collect(Temporality::Cumulative);
+        assert!(text.contains("# TYPE requests_total counter"));
+        assert!(text.contains("requests_total{route=\"/api \\\"v1\\\"\"} 3"));
+        assert!(text.contains("# TYPE duration_seconds histogram"));
+        assert!(text.contains("duration_seconds_bucket{le=\"0.1\"} 0"));
+        assert!(text.contains("duration_seconds_bucket{le=\"1\"} 1"));
+        assert!(text.contains("duration_seconds_bucket{le=\"+Inf\"} 1"));
+        assert!(text.contains("duration_seconds_count 1"));
+        assert!(text.contains("# TYPE open gauge"));
+        assert!(text.contains("open 2"));
+    }
+
+    #[cfg(feature = "statsd")]
+    #[test]
+    fn test_render_statsd() {
+        let text = collect(Temporality::LowMemory);
+        assert!(text.contains("gw.requests_total:3|c|#route:/api \"v1\""));
+        assert!(text.contains("gw.duration_seconds.count:1|c"));
+        assert!(text.contains("gw.duration_seconds.max:0.5|g"));
+        assert!(text.contains("gw.open:2|g"));
+
+        let lines: Vec<String> = (0..5).map(|i| format!("m{i}:1|c")).collect();
+        let datagrams = batch_datagrams(&lines, 14);
+        assert_eq!(datagrams, ["m0:1|c\nm1:1|c", "m2:1|c\nm3:1|c", "m4:1|c"]);
+    }
+}
@@ -5,12 +5,33 @@
 //! cross‑cutting concerns (security headers, CORS, request timing, request ID,
 //! Alt-Svc advertising). They deliberately stay stateless (except for reading
 //! shared configuration) to minimize contention and complexity.
-use std::{sync::Arc, time::Instant};
+//!
+//! Routes opt into middlewares by name through their `middlewares` list; the
+//! [`MiddlewareRegistry`] maps those names onto layers wrapped around the
+//! route handler.
+use std::{collections::HashMap, convert::Infallible, str::FromStr, sync::Arc, time::Instant};
 
 use arc_swap::ArcSwap;
-use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
-
-use crate::config::models::ServerConfig;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, Uri, header, uri::PathAndQuery},
+    middleware::{Next, from_fn},
+    response::Response,
+};
+use eyre::eyre;
+use tower::{Layer, ServiceExt, util::BoxCloneSyncService};
+use tower_http::compression::CompressionLayer;
+
+use crate::{
+    adapters::audit::{AuditDecision, AuditEvent, AuditEventKind, AuditLog},
+    config::models::{
+        AuthMiddlewareConfig, BUILTIN_MIDDLEWARES, SecurityHeadersConfig, ServerConfig,
+    },
+    core::{
+        client_ip::request_client_ip, error_response, secret::constant_time_eq, waf::ThreatLevel,
+    },
+};
 
 /// Add an `Alt-Svc` header advertising HTTP/3 (h3) support when TLS + HTTP/3
 /// are enabled in the current configuration snapshot.
@@ -71,27 +92,99 @@ pub async fn request_timing_middleware(req: Request, next: Next) -> Response {
     response
 }
 
-/// Add common security hardening headers (best‑effort, does not overwrite if
-/// already present).
-pub async fn security_headers_middleware(req: Request, next: Next) -> Response {
-    let mut response = next.run(req).await;
-    let headers = response.headers_mut();
+/// Response header changes made by the `security_headers` middleware.
+#[derive(Debug, Clone, Default)]
+pub struct SecurityHeaders {
+    set: Vec<(HeaderName, HeaderValue)>,
+    remove: Vec<HeaderName>,
+}
 
-    // Add security headers
-    headers.insert(
-        "X-Content-Type-Options",
-        HeaderValue::from_static("nosniff"),
-    );
-    headers.insert("X-Frame-Options", HeaderValue::from_static("DENY"));
-    headers.insert(
-        "X-XSS-Protection",
-        HeaderValue::from_static("1; mode=block"),
-    );
-    headers.insert(
-        "Referrer-Policy",
-        HeaderValue::from_static("strict-origin-when-cross-origin"),
-    );
+impl SecurityHeaders {
+    /// Build the header set for `config`. Invalid values are skipped with a
+    /// warning; configuration validation reports them up front.
+    pub fn from_config(config: &SecurityHeadersConfig) -> Self {
+        let mut headers = Self::default();
+        let mut set = |name: HeaderName, value: &str| {
+            if value.is_empty() {
+                return;
+            }
+            match HeaderValue::from_str(value) {
+                Ok(value) => headers.set.push((name, value)),
+                Err(_) => tracing::warn!(header = %name, "Skipping invalid security header value"),
+            }
+        };
+
+        if config.content_type_options {
+            set(header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+        }
+        if let Some(frame_options) = &config.frame_options {
+            set(header::X_FRAME_OPTIONS, frame_options);
+        }
+        if config.xss_protection {
+            set(header::X_XSS_PROTECTION, "1; mode=block");
+        }
+        if let Some(referrer_policy) = &config.referrer_policy {
+            set(header::REFERRER_POLICY, referrer_policy);
+        }
+        if let Some(hsts) = &config.hsts {
+            let mut value = format!("max-age={}", hsts.max_age_secs);
+            if hsts.include_subdomains {
+                value.push_str("; includeSubDomains");
+            }
+            if hsts.preload {
+                value.push_str("; preload");
+            }
+            set(header::STRICT_TRANSPORT_SECURITY, &value);
+        }
+        if let Some(policy) = &config.content_security_policy {
+            let name = if config.content_security_policy_report_only {
+                header::CONTENT_SECURITY_POLICY_REPORT_ONLY
+            } else {
+                header::CONTENT_SECURITY_POLICY
+            };
+            set(name, policy);
+        }
+        if let Some(policy) = &config.permissions_policy {
+            set(HeaderName::from_static("permissions-policy"), policy);
+        }
+
+        for name in &config.remove_headers {
+            match HeaderName::from_bytes(name.as_bytes()) {
+                Ok(name) => headers.remove.push(name),
+                Err(_) => tracing::warn!(header = %name, "Skipping invalid header name"),
+            }
+        }
+        headers
+    }
+
+    /// Set and remove the configured headers on a response.
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        for name in &self.remove {
+            headers.remove(name);
+        }
+        for (name, value) in &self.set {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+}
+
+/// Add common security hardening headers with the default policy.
+pub async fn security_headers_middleware(req: Request, next: Next) -> Response {
+    static DEFAULT: std::sync::LazyLock<SecurityHeaders> =
+        std::sync::LazyLock::new(
+            || SecurityHeaders::from_config(&SecurityHeadersConfig::default()),
+        );
+    apply_security_headers(req, next, &DEFAULT).await
+}
 
+/// Add the headers of `policy` to the response and strip the ones it removes.
+pub async fn apply_security_headers(
+    req: Request,
+    next: Next,
+    policy: &SecurityHeaders,
+) -> Response {
+    let mut response = next.run(req).await;
+    policy.apply(response.headers_mut());
     response
 }
 
@@ -154,9 +247,27 @@ pub fn create_custom_headers_middleware(
     }
 }
 
-/// Generate a per‑request UUID and expose it via tracing plus `X-Request-ID`.
-pub async fn request_id_middleware(req: Request, next: Next) -> Response {
-    let request_id = uuid::Uuid::new_v4().to_string();
+/// Header carrying the per-request correlation ID.
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Whether an incoming request ID is safe to reuse: 1-128 visible ASCII characters.
+pub fn is_valid_request_id(value: &HeaderValue) -> bool {
+    let bytes = value.as_bytes();
+    !bytes.is_empty() && bytes.len() <= 128 && bytes.iter().all(|b| b.is_ascii_graphic())
+}
+
+/// Expose the request's `X-Request-ID` via tracing and the response, generating
+/// a UUID when the request does not carry a usable one.
+pub async fn request_id_middleware(mut req: Request, next: Next) -> Response {
+    let header_value = match req.headers().get(X_REQUEST_ID) {
+        Some(value) if is_valid_request_id(value) => value.clone(),
+        _ => match HeaderValue::from_str(&uuid::Uuid::new_v4().to_string()) {
+            Ok(value) => value,
+            Err(_) => return next.run(req).await,
+        },
+    };
+    req.headers_mut().insert(X_REQUEST_ID, header_value.clone());
+    let request_id = header_value.to_str().unwrap_or_default().to_string();
 
     // Add request ID to tracing span
     let span = tracing::info_span!("request", request_id = %request_id);
@@ -165,13 +276,224 @@ pub async fn request_id_middleware(req: Request, next: Next) -> Response {
     let mut response = next.run(req).await;
 
     // Add request ID to response headers
-    if let Ok(header_value) = HeaderValue::from_str(&request_id) {
-        response.headers_mut().insert("X-Request-ID", header_value);
-    }
+    response.headers_mut().insert(X_REQUEST_ID, header_value);
 
     response
 }
 
+/// Reject requests that do not carry one of the configured tokens, recording
+/// each rejection in the audit log under `route`.
+pub async fn auth_middleware(
+    req: Request,
+    next: Next,
+    auth: Option<Arc<AuthMiddlewareConfig>>,
+    audit: AuditLog,
+    route: Arc<str>,
+) -> Response {
+    let record_failure = |req: &Request, rule: &str| {
+        let client = request_client_ip(req);
+        audit.record(
+            AuditEvent::new(
+                AuditEventKind::Auth,
+                AuditDecision::Blocked,
+                rule,
+                ThreatLevel::Medium,
+            )
+            .request(req.method(), req.uri(), req.headers())
+            .client(client)
+            .route(route.as_ref()),
+        );
+    };
+
+    let Some(auth) = auth else {
+        tracing::warn!("auth middleware used without [middleware.auth]; rejecting request");
+        record_failure(&req, "unconfigured");
+        return error_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+    };
+
+    let bearer = auth
+        .header
+        .eq_ignore_ascii_case(header::AUTHORIZATION.as_str());
+    let presented = req
+        .headers()
+        .get(auth.header.as_str())
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            if bearer {
+                v.strip_prefix("Bearer ")
+                    .or_else(|| v.strip_prefix("bearer "))
+            } else {
+                Some(v)
+            }
+        });
+
+    match presented {
+        Some(token) if auth.tokens.iter().any(|t| constant_time_eq(t, token)) => {
+            next.run(req).await
+        }
+        presented => {
+            let rule = if presented.is_some() {
+                "invalid_token"
+            } else {
+                "missing_token"
+            };
+            record_failure(&req, rule);
+            let mut response = error_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+            if bearer {
+                response
+                    .headers_mut()
+                    .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            }
+            response
+        }
+    }
+}
+
+/// Remove the route prefix from the request path (`/api/users` -> `/users`).
+fn strip_route_prefix(mut req: Request, prefix: &str) -> Request {
+    let path = req.uri().path();
+    if let Some(new_path) = path.strip_prefix(prefix) {
+        let new_path = if new_path.is_empty() { "/" } else { new_path };
+        let path_and_query = match req.uri().query() {
+            Some(query) => format!("{new_path}?{query}"),
+            None => new_path.to_string(),
+        };
+        let mut parts = req.uri().clone().into_parts();
+        parts.path_and_query = Some(
+            PathAndQuery::from_str(&path_and_query)
+                .unwrap_or_else(|_| PathAndQuery::from_static("/")),
+        );
+        if let Ok(new_uri) = Uri::from_parts(parts) {
+            *req.uri_mut() = new_uri;
+        }
+    }
+    req
+}
+
+/// Request handler wrapped by a route's middleware chain.
+pub type RouteService = BoxCloneSyncService<Request, Response, Infallible>;
+
+/// Route details available when building a middleware chain.
+#[derive(Clone, Copy)]
+pub struct MiddlewareContext<'a> {
+    pub route_prefix: &'a str,
+    pub config: &'a ServerConfig,
+    pub audit: &'a AuditLog,
+    /// `arg` when the route names the middleware as `name:arg`
+    pub argument: Option<&'a str>,
+}
+
+type MiddlewareFactory =
+    Arc<dyn Fn(RouteService, &MiddlewareContext<'_>) -> RouteService + Send + Sync>;
+
+/// Named middlewares that routes reference from their `middlewares` list.
+#[derive(Clone, Default)]
+pub struct MiddlewareRegistry {
+    factories: HashMap<String, MiddlewareFactory>,
+}
+
+impl MiddlewareRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry containing every middleware in [`BUILTIN_MIDDLEWARES`].
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register("strip_prefix", |inner, ctx| {
+            let prefix = ctx.route_prefix.to_string();
+            BoxCloneSyncService::new(inner.map_request(move |req| strip_route_prefix(req, &prefix)))
+        });
+        registry.register("security_headers", |inner, ctx| {
+            let middleware = &ctx.config.middleware;
+            let config = match ctx.argument {
+                Some(name) => middleware
+                    .security_header_policies
+                    .get(name)
+                    .unwrap_or_else(|| {
+                        tracing::warn!(policy = name, "Unknown security header policy");
+                        &middleware.security_headers
+                    }),
+                None => &middleware.security_headers,
+            };
+            let policy = Arc::new(SecurityHeaders::from_config(config));
+            BoxCloneSyncService::new(
+                from_fn(move |req, next| {
+                    let policy = policy.clone();
+                    async move { apply_security_headers(req, next, &policy).await }
+                })
+                .layer(inner),
+            )
+        });
+        registry.register("cors", |inner, _| {
+            BoxCloneSyncService::new(from_fn(cors_middleware).layer(inner))
+        });
+        registry.register("request_id", |inner, _| {
+            BoxCloneSyncService::new(from_fn(request_id_middleware).layer(inner))
+        });
+        registry.register("compression", |inner, _| {
+            BoxCloneSyncService::new(
+                CompressionLayer::new()
+                    .layer(inner)
+                    .map_response(|response| response.map(Body::new)),
+            )
+        });
+        registry.register("auth", |inner, ctx| {
+            let auth = ctx.config.middleware.auth.clone().map(Arc::new);
+            let audit = ctx.audit.clone();
+            let route: Arc<str> = Arc::from(ctx.route_prefix);
+            BoxCloneSyncService::new(
+                from_fn(move |req, next| {
+                    auth_middleware(req, next, auth.clone(), audit.clone(), route.clone())
+                })
+                .layer(inner),
+            )
+        });
+        debug_assert!(BUILTIN_MIDDLEWARES.iter().all(|n| registry.contains(n)));
+        registry
+    }
+
+    /// Register (or replace) a middleware under `name`.
+    pub fn register<F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: Fn(RouteService, &MiddlewareContext<'_>) -> RouteService + Send + Sync + 'static,
+    {
+        self.factories.insert(name.into(), Arc::new(factory));
+    }
+
+    /// Whether a middleware named `name` is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Wrap `inner` with the named middlewares; the first name is the outermost layer.
+    ///
+    /// A name that is not registered as a whole is looked up as `name:arg`,
+    /// with `arg` passed to the middleware in [`MiddlewareContext::argument`].
+    pub fn apply(
+        &self,
+        names: &[String],
+        inner: RouteService,
+        ctx: &MiddlewareContext<'_>,
+    ) -> eyre::Result<RouteService> {
+        names.iter().rev().try_fold(inner, |service, name| {
+            if let Some(factory) = self.factories.get(name) {
+                return Ok(factory(service, ctx));
+            }
+            let (base, argument) = name
+                .split_once(':')
+                .filter(|(base, _)| self.factories.contains_key(*base))
+                .ok_or_else(|| eyre!("Unknown middleware '{name}'"))?;
+            let ctx = MiddlewareContext {
+                argument: Some(argument),
+                ..*ctx
+            };
+            Ok(self.factories[base](service, &ctx))
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -186,6 +508,7 @@ mod tests {
     use tower::ServiceExt; // for oneshot
 
     use super::*;
+    use crate::config::HstsConfig;
 
     #[tokio::test]
     async fn test_security_headers_middleware() {
@@ -273,4 +596,204 @@ mod tests {
         let request_id = headers.get("X-Request-ID").unwrap().to_str().unwrap();
         assert!(uuid::Uuid::parse_str(request_id).is_ok());
     }
+
+    #[tokio::test]
+    async fn test_request_id_middleware_keeps_incoming_id() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|headers: axum::http::HeaderMap| async move {
+                    headers
+                        .get(X_REQUEST_ID)
+                        .map(|v| v.to_str().unwrap().to_string())
+                        .unwrap_or_default()
+                }),
+            )
+            .layer(middleware::from_fn(request_id_middleware));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-ID", "upstream-42")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.headers().get(X_REQUEST_ID).unwrap(), "upstream-42");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"upstream-42");
+    }
+
+    fn echo_path_service() -> RouteService {
+        RouteService::new(tower::service_fn(|req: Request| async move {
+            Ok::<_, Infallible>(Response::new(Body::from(req.uri().to_string())))
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_registry_applies_named_chain() {
+        let registry = MiddlewareRegistry::with_builtins();
+        let mut config = ServerConfig::default();
+        config.middleware.auth = Some(AuthMiddlewareConfig {
+            tokens: vec!["secret".to_string()],
+            header: "Authorization".to_string(),
+        });
+        let (audit, mut audit_rx) = AuditLog::channel(8);
+        let ctx = MiddlewareContext {
+            route_prefix: "/api",
+            config: &config,
+            audit: &audit,
+            argument: None,
+        };
+        let names = vec!["request_id".to_string(), "auth".to_string()];
+
+        let chain = registry.apply(&names, echo_path_service(), &ctx).unwrap();
+        let response = chain
+            .oneshot(
+                Request::builder()
+                    .uri("/api/users")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers().contains_key("X-Request-ID"));
+        let record = audit_rx.try_recv().expect("auth failure audited");
+        assert_eq!(record.event, AuditEventKind::Auth);
+        assert_eq!(record.rule, "missing_token");
+        assert_eq!(record.route.as_deref(), Some("/api"));
+        assert!(record.request_id.is_some());
+
+        let chain = registry.apply(&names, echo_path_service(), &ctx).unwrap();
+        let response = chain
+            .oneshot(
+                Request::builder()
+                    .uri("/api/users")
+                    .header("Authorization", "Bearer secret")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(audit_rx.try_recv().is_err());
+
+        assert!(
+            registry
+                .apply(&["gzip".to_string()], echo_path_service(), &ctx)
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_strip_prefix_keeps_query() {
+        let registry = MiddlewareRegistry::with_builtins();
+        let config = ServerConfig::default();
+        let ctx = MiddlewareContext {
+            route_prefix: "/api",
+            config: &config,
+            audit: &AuditLog::disabled(),
+            argument: None,
+        };
+        let chain = registry
+            .apply(&["strip_prefix".to_string()], echo_path_service(), &ctx)
+            .unwrap();
+        let response = chain
+            .oneshot(
+                Request::builder()
+                    .uri("/api/users?page=2")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"/users?page=2");
+    }
+
+    #[tokio::test]
+    async fn test_configured_security_headers() {
+        let registry = MiddlewareRegistry::with_builtins();
+        let mut config = ServerConfig::default();
+        config.middleware.security_headers = SecurityHeadersConfig {
+            hsts: Some(HstsConfig {
+                max_age_secs: 600,
+                include_subdomains: true,
+                preload: false,
+            }),
+            content_security_policy: Some("default-src 'self'".to_string()),
+            permissions_policy: Some("camera=()".to_string()),
+            frame_options: None,
+            remove_headers: vec!["server".to_string(), "x-powered-by".to_string()],
+            ..SecurityHeadersConfig::default()
+        };
+        config.middleware.security_header_policies.insert(
+            "report".to_string(),
+            SecurityHeadersConfig {
+                content_security_policy: Some("default-src 'none'".to_string()),
+                content_security_policy_report_only: true,
+                ..SecurityHeadersConfig::default()
+            },
+        );
+        let ctx = MiddlewareContext {
+            route_prefix: "/",
+            config: &config,
+            audit: &AuditLog::disabled(),
+            argument: None,
+        };
+        let backend = || {
+            RouteService::new(tower::service_fn(|_: Request| async move {
+                let mut response = Response::new(Body::empty());
+                let headers = response.headers_mut();
+                headers.insert(header::SERVER, HeaderValue::from_static("nginx"));
+                headers.insert("x-powered-by", HeaderValue::from_static("PHP"));
+                Ok::<_, Infallible>(response)
+            }))
+        };
+        let call = |name: &str| {
+            let chain = registry
+                .apply(&[name.to_string()], backend(), &ctx)
+                .unwrap();
+            chain.oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+        };
+
+        let response = call("security_headers").await.unwrap();
+        let headers = response.headers();
+        assert_eq!(
+            headers[header::STRICT_TRANSPORT_SECURITY],
+            "max-age=600; includeSubDomains"
+        );
+        assert_eq!(
+            headers[header::CONTENT_SECURITY_POLICY],
+            "default-src 'self'"
+        );
+        assert_eq!(headers["permissions-policy"], "camera=()");
+        assert_eq!(headers[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+        assert!(!headers.contains_key(header::X_FRAME_OPTIONS));
+        assert!(!headers.contains_key(header::SERVER));
+        assert!(!headers.contains_key("x-powered-by"));
+
+        let response = call("security_headers:report").await.unwrap();
+        let headers = response.headers();
+        assert_eq!(
+            headers[header::CONTENT_SECURITY_POLICY_REPORT_ONLY],
+            "default-src 'none'"
+        );
+        assert_eq!(headers[header::X_FRAME_OPTIONS], "DENY");
+        assert!(!headers.contains_key(header::STRICT_TRANSPORT_SECURITY));
+        assert!(headers.contains_key(header::SERVER));
+
+        assert!(
+            registry
+                .apply(&["gzip:fast".to_string()], backend(), &ctx)
+                .is_err()
+        );
+    }
 }
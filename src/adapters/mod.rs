@@ -1,16 +1,47 @@
+pub mod audit;
+pub mod basic_auth;
 pub mod config_providers;
+pub mod cookie_policy;
+pub mod events;
+pub mod fastcgi;
 pub mod file_system;
+pub mod happy_eyeballs;
 pub mod health_checker;
 #[cfg(feature = "http3")]
 pub mod http3;
 pub mod http_client;
 pub mod http_handler;
+pub mod icap;
+pub mod json_schema;
+pub mod ldap_auth;
+pub mod metrics_exporters;
 pub mod middleware; // HTTP/3 (QUIC) support
+pub mod notifications;
+pub mod opa;
+pub mod rbac;
+pub mod request_timing;
+pub mod server;
+pub mod spiffe;
+pub mod tap;
+pub mod tls_certs;
+pub mod transfer;
+pub mod tunnel;
+pub mod upload_policy;
+pub mod usage;
+pub mod waf_rules;
+pub mod wasm_filter;
 
 /// Re-export commonly used types from adapters
-pub use config_providers::{file::FileConfigProvider, http::HttpConfigProvider};
+pub use audit::{AuditDecision, AuditEvent, AuditEventKind, AuditLog};
+pub use config_providers::{
+    file::FileConfigProvider,
+    http::{HttpConfigProvider, RemoteConfigOptions},
+};
 pub use file_system::FileSystemAdapter;
 pub use health_checker::HealthChecker;
 pub use http_client::HttpClientAdapter;
 pub use http_handler::HttpHandler;
 pub use middleware::*;
+pub use server::{AcceptedIo, TlsIo, TlsSession, serve, serve_until};
+pub use spiffe::SpiffeSource;
+pub use wasm_filter::WasmFilter;
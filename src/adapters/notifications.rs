@@ -0,0 +1,357 @@
+//! Webhook notifications for operational events.
+//!
+//! Backend health transitions, an opened metrics export circuit,
+//! configuration reloads and certificate renewals are reported with
+//! [`notify`] from wherever they happen. When `[notifications]` has
+//! webhooks, a background task POSTs each [`Notification`] as JSON to the
+//! webhooks subscribed to its event, retrying with exponential backoff.
+//! Deliveries that still fail are logged as dead letters with the full
+//! payload, so nothing is lost silently.
+use std::{sync::Arc, time::Duration};
+
+use arc_swap::ArcSwapOption;
+use axum::body::Body as AxumBody;
+use chrono::{DateTime, Utc};
+use eyre::{Result, eyre};
+use http::header;
+use hyper::Request;
+use ring::hmac;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    config::models::{NotificationEvent, NotificationWebhookConfig, NotificationsConfig},
+    ports::http_client::HttpClient,
+};
+
+/// Header carrying the event name
+pub const X_AXON_EVENT: &str = "x-axon-event";
+/// Header carrying the Unix time the signature covers
+pub const X_AXON_TIMESTAMP: &str = "x-axon-timestamp";
+/// Header carrying `sha256=<hex HMAC of "<timestamp>.<body>">`
+pub const X_AXON_SIGNATURE: &str = "x-axon-signature";
+
+/// The running notifier, if notifications are configured
+static NOTIFIER: ArcSwapOption<Notifier> = ArcSwapOption::const_empty();
+
+/// One operational event, serialized as a flat JSON object.
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    /// Unique per notification, the same across retries
+    pub id: Uuid,
+    pub event: NotificationEvent,
+    pub timestamp: DateTime<Utc>,
+    /// What the event is about: a backend URL, a circuit or certificate
+    /// domains
+    pub subject: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl Notification {
+    pub fn new(event: NotificationEvent) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            event,
+            timestamp: Utc::now(),
+            subject: None,
+            detail: None,
+        }
+    }
+
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// Send `notification` to the configured webhooks without waiting; does
+/// nothing unless notifications are configured.
+pub fn notify(notification: Notification) {
+    if let Some(notifier) = NOTIFIER.load().as_ref() {
+        notifier.send(notification);
+    }
+}
+
+/// Queue feeding the delivery task.
+pub struct Notifier {
+    tx: mpsc::Sender<Notification>,
+}
+
+impl Notifier {
+    /// Start the delivery task; `None` when no webhook is configured.
+    pub fn start(config: &NotificationsConfig, http_client: Arc<dyn HttpClient>) -> Option<Self> {
+        if config.webhooks.is_empty() {
+            return None;
+        }
+        let (tx, rx) = mpsc::channel(config.queue_size.max(1));
+        let delivery = Delivery {
+            client: http_client,
+            webhooks: config.webhooks.clone(),
+            max_retries: config.max_retries,
+            retry_backoff: Duration::from_millis(config.retry_backoff_ms),
+            timeout: Duration::from_millis(config.timeout_ms.max(1)),
+        };
+        tokio::spawn(delivery.run(rx));
+        Some(Self { tx })
+    }
+
+    /// Route [`notify`] to this notifier, replacing any earlier one.
+    pub fn install(self) {
+        NOTIFIER.store(Some(Arc::new(self)));
+    }
+
+    /// Queue `notification`, dead-lettering it when the queue is full.
+    pub fn send(&self, notification: Notification) {
+        if let Err(e) = self.tx.try_send(notification) {
+            let reason = match &e {
+                mpsc::error::TrySendError::Full(_) => "notification queue is full",
+                mpsc::error::TrySendError::Closed(_) => "notification task stopped",
+            };
+            dead_letter(&e.into_inner(), None, reason);
+        }
+    }
+}
+
+struct Delivery {
+    client: Arc<dyn HttpClient>,
+    webhooks: Vec<NotificationWebhookConfig>,
+    max_retries: u32,
+    retry_backoff: Duration,
+    timeout: Duration,
+}
+
+impl Delivery {
+    async fn run(self, mut rx: mpsc::Receiver<Notification>) {
+        while let Some(notification) = rx.recv().await {
+            let Ok(body) = serde_json::to_vec(&notification) else {
+                continue;
+            };
+            let deliveries = self
+                .webhooks
+                .iter()
+                .filter(|webhook| {
+                    webhook.events.is_empty() || webhook.events.contains(&notification.event)
+                })
+                .map(|webhook| self.deliver(webhook, &notification, &body));
+            futures_util::future::join_all(deliveries).await;
+        }
+    }
+
+    /// POST `body` to `webhook`, retrying failed attempts with backoff.
+    async fn deliver(
+        &self,
+        webhook: &NotificationWebhookConfig,
+        notification: &Notification,
+        body: &[u8],
+    ) {
+        let mut attempt = 0;
+        loop {
+            let error = match self.attempt(webhook, notification.event, body).await {
+                Ok(()) => return,
+                Err(e) => e,
+            };
+            if attempt >= self.max_retries {
+                let reason = format!("{error:#} after {} attempts", attempt + 1);
+                dead_letter(notification, Some(&webhook.url), &reason);
+                return;
+            }
+            tracing::debug!(
+                webhook = %webhook.url,
+                attempt = attempt + 1,
+                error = %error,
+                "Notification delivery failed; retrying"
+            );
+            tokio::time::sleep(self.retry_backoff.saturating_mul(1 << attempt.min(16))).await;
+            attempt += 1;
+        }
+    }
+
+    async fn attempt(
+        &self,
+        webhook: &NotificationWebhookConfig,
+        event: NotificationEvent,
+        body: &[u8],
+    ) -> Result<()> {
+        let mut request = Request::post(webhook.url.as_str())
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(X_AXON_EVENT, event.as_str());
+        for (name, value) in &webhook.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(secret) = &webhook.secret {
+            let timestamp = Utc::now().timestamp();
+            request = request
+                .header(X_AXON_TIMESTAMP, timestamp)
+                .header(X_AXON_SIGNATURE, signature(secret, timestamp, body));
+        }
+        let request = request.body(AxumBody::from(body.to_vec()))?;
+        let response = tokio::time::timeout(self.timeout, self.client.send_request(request))
+            .await
+            .map_err(|_| eyre!("timed out after {}ms", self.timeout.as_millis()))??;
+        if !response.status().is_success() {
+            return Err(eyre!("webhook returned status {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `<timestamp>.<body>` under `secret`
+pub fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let mut context = hmac::Context::with_key(&key);
+    context.update(format!("{timestamp}.").as_bytes());
+    context.update(body);
+    let hex: String = context
+        .sign()
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("sha256={hex}")
+}
+
+/// Log a notification that will not be delivered, with its full payload.
+fn dead_letter(notification: &Notification, webhook: Option<&str>, reason: &str) {
+    tracing::error!(
+        target: "axon::notifications::dead_letter",
+        webhook,
+        notification = %serde_json::to_string(notification).unwrap_or_default(),
+        "Notification not delivered: {reason}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use http::StatusCode;
+
+    use super::*;
+    use crate::ports::http_client::{HealthCheckOptions, HttpClientError};
+
+    /// Records requests and fails the first `failures` of them
+    struct MockClient {
+        requests: Mutex<Vec<(http::HeaderMap, Vec<u8>)>>,
+        failures: Mutex<usize>,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpClient for MockClient {
+        async fn send_request(
+            &self,
+            req: hyper::Request<AxumBody>,
+        ) -> Result<hyper::Response<AxumBody>, HttpClientError> {
+            let (parts, body) = req.into_parts();
+            let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+            self.requests
+                .lock()
+                .unwrap()
+                .push((parts.headers, body.to_vec()));
+            let mut failures = self.failures.lock().unwrap();
+            let status = if *failures > 0 {
+                *failures -= 1;
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::NO_CONTENT
+            };
+            Ok(hyper::Response::builder()
+                .status(status)
+                .body(AxumBody::empty())
+                .unwrap())
+        }
+
+        async fn health_check(
+            &self,
+            _url: &str,
+            _options: &HealthCheckOptions,
+            _timeout_secs: u64,
+        ) -> Result<bool, HttpClientError> {
+            Ok(true)
+        }
+    }
+
+    fn config(webhooks: Vec<NotificationWebhookConfig>) -> NotificationsConfig {
+        NotificationsConfig {
+            webhooks,
+            max_retries: 2,
+            retry_backoff_ms: 10,
+            ..NotificationsConfig::default()
+        }
+    }
+
+    fn webhook(url: &str, events: Vec<NotificationEvent>) -> NotificationWebhookConfig {
+        NotificationWebhookConfig {
+            url: url.to_string(),
+            events,
+            secret: Some("s3cret".to_string()),
+            headers: std::collections::HashMap::new(),
+        }
+    }
+
+    async fn wait_for_requests(client: &MockClient, count: usize) {
+        for _ in 0..200 {
+            if client.requests.lock().unwrap().len() >= count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {count} webhook requests");
+    }
+
+    #[tokio::test]
+    async fn test_signed_delivery_with_retries() {
+        let client = Arc::new(MockClient {
+            requests: Mutex::new(Vec::new()),
+            failures: Mutex::new(2),
+        });
+        let notifier = Notifier::start(
+            &config(vec![
+                webhook("http://ops.internal/all", vec![]),
+                webhook(
+                    "http://ops.internal/config",
+                    vec![NotificationEvent::ConfigReloaded],
+                ),
+            ]),
+            client.clone(),
+        )
+        .unwrap();
+        notifier.send(
+            Notification::new(NotificationEvent::BackendUnhealthy)
+                .subject("http://10.0.0.5:8080")
+                .detail("3 consecutive failed health checks"),
+        );
+
+        // Two failures, then the second retry succeeds
+        wait_for_requests(&client, 3).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let requests = client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        let (headers, body) = &requests[2];
+        assert_eq!(headers[X_AXON_EVENT], "backend_unhealthy");
+        let timestamp: i64 = headers[X_AXON_TIMESTAMP].to_str().unwrap().parse().unwrap();
+        assert_eq!(
+            headers[X_AXON_SIGNATURE].to_str().unwrap(),
+            signature("s3cret", timestamp, body)
+        );
+        let json: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(json["event"], "backend_unhealthy");
+        assert_eq!(json["subject"], "http://10.0.0.5:8080");
+        // The same notification is sent on every attempt
+        assert_eq!(requests[0].1, *body);
+    }
+
+    #[test]
+    fn test_signature() {
+        // echo -n '1700000000.{}' | openssl dgst -sha256 -hmac key
+        assert_eq!(
+            signature("key", 1_700_000_000, b"{}"),
+            "sha256=9d713ed406bb7076d4123f0dc2c39d2df5c654ed4b0cd56b52c8b4c940bd63ae"
+        );
+    }
+}
@@ -0,0 +1,447 @@
+//! Authorization decisions from Open Policy Agent.
+//!
+//! Each request's metadata and the [`AuthenticatedUser`] left by an
+//! authentication middleware are posted to OPA's Data API as `input`. The
+//! policy can answer with a bare boolean or with an object that also names
+//! headers to add to the backend request and the status and reason of a
+//! denial. Decisions can be cached for identical input.
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderName, HeaderValue, StatusCode, header},
+    middleware::{Next, from_fn},
+    response::Response,
+};
+use eyre::{WrapErr, eyre};
+use serde_json::{Value, json};
+use tower::{Layer, util::BoxCloneSyncService};
+
+use crate::{
+    adapters::{
+        audit::{AuditDecision, AuditEvent, AuditEventKind, AuditLog},
+        middleware::MiddlewareRegistry,
+    },
+    config::models::{OPA_MIDDLEWARE_PREFIX, OpaConfig},
+    core::{
+        client_ip::request_client_ip, error_response, rbac::AuthenticatedUser, waf::ThreatLevel,
+    },
+    ports::http_client::HttpClient,
+};
+
+/// Largest OPA response body read
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+
+/// A policy decision
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OpaDecision {
+    pub allow: bool,
+    /// Headers added to the backend request when allowed
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+    /// Status of the denial response (default `403`)
+    pub status: Option<StatusCode>,
+    pub reason: Option<String>,
+}
+
+impl OpaDecision {
+    /// Read a Data API response. An undefined decision (no `result`) denies.
+    pub fn from_response(body: &Value) -> eyre::Result<Self> {
+        match body.get("result") {
+            None => Ok(Self::default()),
+            Some(Value::Bool(allow)) => Ok(Self {
+                allow: *allow,
+                ..Self::default()
+            }),
+            Some(Value::Object(result)) => {
+                let mut decision = Self {
+                    allow: result
+                        .get("allow")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                    reason: result
+                        .get("reason")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    ..Self::default()
+                };
+                if let Some(status) = result.get("status") {
+                    let status = status
+                        .as_u64()
+                        .and_then(|code| StatusCode::from_u16(u16::try_from(code).ok()?).ok())
+                        .filter(|status| status.is_client_error() || status.is_server_error())
+                        .ok_or_else(|| eyre!("Invalid status {status}"))?;
+                    decision.status = Some(status);
+                }
+                if let Some(headers) = result.get("headers") {
+                    let headers = headers
+                        .as_object()
+                        .ok_or_else(|| eyre!("'headers' must be an object"))?;
+                    for (name, value) in headers {
+                        let name = HeaderName::from_bytes(name.as_bytes())
+                            .map_err(|_| eyre!("Invalid header name '{name}'"))?;
+                        let value = value
+                            .as_str()
+                            .and_then(|value| HeaderValue::from_str(value).ok())
+                            .ok_or_else(|| eyre!("Invalid value for header '{name}'"))?;
+                        decision.headers.push((name, value));
+                    }
+                }
+                Ok(decision)
+            }
+            Some(other) => Err(eyre!("Unexpected result {other}")),
+        }
+    }
+}
+
+struct CachedDecision {
+    decision: Arc<OpaDecision>,
+    expires_at: Instant,
+}
+
+/// An [`OpaConfig`] with its client and decision cache.
+pub struct OpaAuthorizer {
+    url: String,
+    include_headers: Vec<HeaderName>,
+    timeout: Duration,
+    cache_ttl: Duration,
+    cache_max_entries: usize,
+    cache: scc::HashMap<String, CachedDecision>,
+    fail_open: bool,
+    client: Arc<dyn HttpClient>,
+}
+
+impl OpaAuthorizer {
+    pub fn new(config: &OpaConfig, client: Arc<dyn HttpClient>) -> eyre::Result<Self> {
+        let include_headers = config
+            .include_headers
+            .iter()
+            .map(|name| {
+                HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| eyre!("Invalid header name '{name}'"))
+            })
+            .collect::<eyre::Result<_>>()?;
+        Ok(Self {
+            url: config.url.clone(),
+            include_headers,
+            timeout: Duration::from_millis(config.timeout_ms),
+            cache_ttl: Duration::from_secs(config.cache_ttl_secs),
+            cache_max_entries: config.cache_max_entries,
+            cache: scc::HashMap::default(),
+            fail_open: config.fail_open,
+            client,
+        })
+    }
+
+    /// The policy input describing a request.
+    pub fn input(&self, req: &Request, route: &str) -> Value {
+        let headers: serde_json::Map<String, Value> = self
+            .include_headers
+            .iter()
+            .filter_map(|name| {
+                let value = req.headers().get(name)?.to_str().ok()?;
+                Some((name.as_str().to_string(), Value::from(value)))
+            })
+            .collect();
+        let client_ip = request_client_ip(req).map(|ip| ip.to_string());
+        let user = req.extensions().get::<AuthenticatedUser>().map(|user| {
+            json!({
+                "name": user.name,
+                "groups": user.groups.as_ref(),
+            })
+        });
+        json!({
+            "method": req.method().as_str(),
+            "path": req.uri().path(),
+            "query": req.uri().query(),
+            "host": req.headers().get(header::HOST).and_then(|host| host.to_str().ok()),
+            "headers": headers,
+            "client_ip": client_ip,
+            "route": route,
+            "user": user,
+        })
+    }
+
+    /// The decision for an input, from the cache or from OPA.
+    pub async fn decide(&self, input: Value) -> eyre::Result<Arc<OpaDecision>> {
+        let body = json!({ "input": input }).to_string();
+        let now = Instant::now();
+        if !self.cache_ttl.is_zero()
+            && let Some(decision) = self
+                .cache
+                .read_sync(&body, |_, cached| {
+                    (cached.expires_at > now).then(|| cached.decision.clone())
+                })
+                .flatten()
+        {
+            return Ok(decision);
+        }
+
+        let request = Request::post(self.url.as_str())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.clone()))?;
+        let response = tokio::time::timeout(self.timeout, self.client.send_request(request))
+            .await
+            .map_err(|_| eyre!("No decision within {:?}", self.timeout))??;
+        if !response.status().is_success() {
+            return Err(eyre!("OPA returned status {}", response.status()));
+        }
+        let bytes = axum::body::to_bytes(response.into_body(), MAX_RESPONSE_BYTES)
+            .await
+            .map_err(|e| eyre!("Cannot read OPA response: {e}"))?;
+        let value: Value = serde_json::from_slice(&bytes).wrap_err("OPA response is not JSON")?;
+        let decision = Arc::new(OpaDecision::from_response(&value)?);
+
+        if !self.cache_ttl.is_zero() {
+            if self.cache.len() >= self.cache_max_entries {
+                self.cache.retain_sync(|_, cached| cached.expires_at > now);
+            }
+            if self.cache.len() < self.cache_max_entries {
+                self.cache.upsert_sync(
+                    body,
+                    CachedDecision {
+                        decision: decision.clone(),
+                        expires_at: now + self.cache_ttl,
+                    },
+                );
+            }
+        }
+        Ok(decision)
+    }
+}
+
+/// Ask OPA about each request; denials get the policy's status (default
+/// `403`), and OPA failures `503` unless the setting fails open.
+async fn opa_middleware(
+    mut req: Request,
+    next: Next,
+    opa: Arc<OpaAuthorizer>,
+    audit: AuditLog,
+    route: Arc<str>,
+) -> Response {
+    let input = opa.input(&req, &route);
+    let decision = match opa.decide(input).await {
+        Ok(decision) => decision,
+        Err(e) => {
+            tracing::warn!(url = %opa.url, "OPA decision failed: {e}");
+            if opa.fail_open {
+                return next.run(req).await;
+            }
+            return error_response(StatusCode::SERVICE_UNAVAILABLE, "Service Unavailable");
+        }
+    };
+    if decision.allow {
+        for (name, value) in &decision.headers {
+            req.headers_mut().insert(name.clone(), value.clone());
+        }
+        return next.run(req).await;
+    }
+
+    let client = request_client_ip(&req);
+    let mut event = AuditEvent::new(
+        AuditEventKind::Authorization,
+        AuditDecision::Blocked,
+        "opa_denied",
+        ThreatLevel::Medium,
+    )
+    .request(req.method(), req.uri(), req.headers())
+    .client(client)
+    .route(&*route);
+    if let Some(reason) = &decision.reason {
+        event = event.detail(reason.as_str());
+    }
+    audit.record(event);
+    let status = decision.status.unwrap_or(StatusCode::FORBIDDEN);
+    error_response(status, status.canonical_reason().unwrap_or("Forbidden"))
+}
+
+/// Register every configured setting as `opa:<name>`.
+pub fn register_opa(
+    registry: &mut MiddlewareRegistry,
+    configs: &HashMap<String, OpaConfig>,
+    client: Arc<dyn HttpClient>,
+) -> eyre::Result<()> {
+    for (name, config) in configs {
+        let opa = Arc::new(
+            OpaAuthorizer::new(config, client.clone()).wrap_err_with(|| format!("OPA '{name}'"))?,
+        );
+        registry.register(
+            format!("{OPA_MIDDLEWARE_PREFIX}{name}"),
+            move |inner, ctx| {
+                let opa = opa.clone();
+                let audit = ctx.audit.clone();
+                let route: Arc<str> = Arc::from(ctx.route_prefix);
+                BoxCloneSyncService::new(
+                    from_fn(move |req, next| {
+                        opa_middleware(req, next, opa.clone(), audit.clone(), route.clone())
+                    })
+                    .layer(inner),
+                )
+            },
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use axum::{Router, middleware, routing::any};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::ports::http_client::{HealthCheckOptions, HttpClientError};
+
+    /// Answers like an OPA policy allowing GETs by `alice`
+    #[derive(Default)]
+    struct MockOpa {
+        calls: AtomicUsize,
+        inputs: Mutex<Vec<Value>>,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpClient for MockOpa {
+        async fn send_request(
+            &self,
+            req: hyper::Request<Body>,
+        ) -> Result<hyper::Response<Body>, HttpClientError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let bytes = axum::body::to_bytes(req.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body: Value = serde_json::from_slice(&bytes).unwrap();
+            let input = body["input"].clone();
+            self.inputs.lock().unwrap().push(input.clone());
+            let result = if input["path"] == "/down" {
+                return Err(HttpClientError::ConnectionError("refused".to_string()));
+            } else if input["user"]["name"] == "alice" && input["method"] == "GET" {
+                json!({"allow": true, "headers": {"x-user-tier": "gold"}})
+            } else {
+                json!({"allow": false, "status": 401, "reason": "not alice"})
+            };
+            Ok(hyper::Response::new(Body::from(
+                json!({ "result": result }).to_string(),
+            )))
+        }
+
+        async fn health_check(
+            &self,
+            _url: &str,
+            _options: &HealthCheckOptions,
+            _timeout_secs: u64,
+        ) -> Result<bool, HttpClientError> {
+            Ok(true)
+        }
+    }
+
+    fn app(config: &OpaConfig, client: Arc<MockOpa>, user: &'static str) -> Router {
+        let opa = Arc::new(OpaAuthorizer::new(config, client).unwrap());
+        Router::new()
+            .route(
+                "/{*path}",
+                any(|req: Request| async move {
+                    req.headers()
+                        .get("x-user-tier")
+                        .map(|tier| tier.to_str().unwrap().to_string())
+                        .unwrap_or_default()
+                }),
+            )
+            .layer(middleware::from_fn(move |req, next| {
+                opa_middleware(req, next, opa.clone(), AuditLog::disabled(), Arc::from("/"))
+            }))
+            .layer(middleware::from_fn(move |mut req: Request, next: Next| {
+                req.extensions_mut().insert(AuthenticatedUser {
+                    name: user.to_string(),
+                    groups: Arc::from(["staff".to_string()]),
+                });
+                next.run(req)
+            }))
+    }
+
+    async fn call(app: &Router, method: &str, path: &str) -> (StatusCode, String) {
+        let req = Request::builder()
+            .method(method)
+            .uri(path)
+            .header("x-tenant", "acme")
+            .header("x-user-tier", "spoofed")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(req).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_opa_middleware() {
+        let config = OpaConfig {
+            url: "http://opa:8181/v1/data/httpapi/authz".to_string(),
+            include_headers: vec!["x-tenant".to_string()],
+            cache_ttl_secs: 60,
+            ..OpaConfig::default()
+        };
+        let client = Arc::new(MockOpa::default());
+        let alice = app(&config, client.clone(), "alice");
+        assert_eq!(
+            call(&alice, "GET", "/items?page=2").await,
+            (StatusCode::OK, "gold".to_string())
+        );
+        assert_eq!(call(&alice, "GET", "/items?page=2").await.0, StatusCode::OK);
+        assert_eq!(client.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            call(&alice, "DELETE", "/items").await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            call(&alice, "GET", "/down").await.0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let input = client.inputs.lock().unwrap()[0].clone();
+        assert_eq!(input["path"], "/items");
+        assert_eq!(input["query"], "page=2");
+        assert_eq!(input["headers"], json!({"x-tenant": "acme"}));
+        assert_eq!(input["user"]["groups"], json!(["staff"]));
+
+        let bob = app(
+            &OpaConfig {
+                fail_open: true,
+                ..config
+            },
+            client,
+            "bob",
+        );
+        assert_eq!(
+            call(&bob, "GET", "/items").await.0,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(call(&bob, "GET", "/down").await.0, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_opa_decision() {
+        assert!(
+            OpaDecision::from_response(&json!({"result": true}))
+                .unwrap()
+                .allow
+        );
+        assert!(!OpaDecision::from_response(&json!({})).unwrap().allow);
+        assert!(
+            OpaDecision::from_response(&json!({"result": {"allow": true, "status": 200}})).is_err()
+        );
+        assert!(
+            OpaDecision::from_response(&json!({"result": {"headers": {"bad name": "x"}}})).is_err()
+        );
+        assert!(OpaDecision::from_response(&json!({"result": [true]})).is_err());
+    }
+}
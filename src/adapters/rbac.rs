@@ -0,0 +1,281 @@
+//! Role-based authorization for routes.
+//!
+//! The identity is the [`AuthenticatedUser`] left by an authentication
+//! middleware earlier in the route's list, or else the policy's trusted
+//! headers. Denials are audited; in dry-run mode they are only logged and
+//! audited as monitored, so a policy can be tried on live traffic first.
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::Request,
+    http::{HeaderMap, StatusCode},
+    middleware::{Next, from_fn},
+    response::Response,
+};
+use tower::{Layer, util::BoxCloneSyncService};
+
+use crate::{
+    adapters::{
+        audit::{AuditDecision, AuditEvent, AuditEventKind, AuditLog},
+        middleware::MiddlewareRegistry,
+    },
+    config::models::{RBAC_MIDDLEWARE_PREFIX, RbacPolicyConfig},
+    core::{
+        client_ip::request_client_ip,
+        error_response,
+        rbac::{AuthenticatedUser, RbacPolicy},
+        waf::ThreatLevel,
+    },
+};
+
+/// An [`RbacPolicy`] with where to find identities.
+pub struct RbacAuthorizer {
+    policy: RbacPolicy,
+    dry_run: bool,
+    user_header: Option<String>,
+    groups_header: Option<String>,
+}
+
+impl RbacAuthorizer {
+    pub fn new(config: &RbacPolicyConfig) -> Self {
+        Self {
+            policy: RbacPolicy::new(config),
+            dry_run: config.dry_run,
+            user_header: config.user_header.clone(),
+            groups_header: config.groups_header.clone(),
+        }
+    }
+
+    /// The user named by the trusted headers, if configured and present.
+    fn header_identity(&self, headers: &HeaderMap) -> Option<AuthenticatedUser> {
+        let name = headers
+            .get(self.user_header.as_deref()?)?
+            .to_str()
+            .ok()?
+            .trim();
+        if name.is_empty() {
+            return None;
+        }
+        let groups = self
+            .groups_header
+            .as_deref()
+            .and_then(|header| headers.get(header))
+            .and_then(|value| value.to_str().ok())
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|group| !group.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_else(|| Arc::from([]));
+        Some(AuthenticatedUser {
+            name: name.to_string(),
+            groups,
+        })
+    }
+}
+
+/// Reject requests the policy does not allow with `403`.
+async fn rbac_middleware(
+    req: Request,
+    next: Next,
+    authorizer: Arc<RbacAuthorizer>,
+    audit: AuditLog,
+    route: Arc<str>,
+) -> Response {
+    let user = req
+        .extensions()
+        .get::<AuthenticatedUser>()
+        .cloned()
+        .or_else(|| authorizer.header_identity(req.headers()));
+    let Err(reason) = authorizer
+        .policy
+        .evaluate(user.as_ref(), req.method(), req.uri().path())
+    else {
+        return next.run(req).await;
+    };
+
+    let client = request_client_ip(&req);
+    let decision = if authorizer.dry_run {
+        tracing::warn!(route = %route, "RBAC dry run would deny: {reason}");
+        AuditDecision::Monitored
+    } else {
+        AuditDecision::Blocked
+    };
+    audit.record(
+        AuditEvent::new(
+            AuditEventKind::Authorization,
+            decision,
+            "rbac_denied",
+            ThreatLevel::Medium,
+        )
+        .request(req.method(), req.uri(), req.headers())
+        .client(client)
+        .route(&*route)
+        .detail(reason),
+    );
+    if authorizer.dry_run {
+        return next.run(req).await;
+    }
+    error_response(StatusCode::FORBIDDEN, "Forbidden")
+}
+
+/// Register every configured policy as `rbac:<name>`.
+pub fn register_rbac_policies(
+    registry: &mut MiddlewareRegistry,
+    configs: &HashMap<String, RbacPolicyConfig>,
+) {
+    for (name, config) in configs {
+        let authorizer = Arc::new(RbacAuthorizer::new(config));
+        registry.register(
+            format!("{RBAC_MIDDLEWARE_PREFIX}{name}"),
+            move |inner, ctx| {
+                let authorizer = authorizer.clone();
+                let audit = ctx.audit.clone();
+                let route: Arc<str> = Arc::from(ctx.route_prefix);
+                BoxCloneSyncService::new(
+                    from_fn(move |req, next| {
+                        rbac_middleware(req, next, authorizer.clone(), audit.clone(), route.clone())
+                    })
+                    .layer(inner),
+                )
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, body::Body, http::Method, middleware, routing::any};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::config::models::{RbacRoleBinding, RbacRule};
+
+    fn app(config: &RbacPolicyConfig) -> Router {
+        let authorizer = Arc::new(RbacAuthorizer::new(config));
+        Router::new()
+            .route("/{*path}", any(|| async { "ok" }))
+            .layer(middleware::from_fn(move |req, next| {
+                rbac_middleware(
+                    req,
+                    next,
+                    authorizer.clone(),
+                    AuditLog::disabled(),
+                    Arc::from("/"),
+                )
+            }))
+    }
+
+    async fn status(app: &Router, method: Method, path: &str, headers: &[(&str, &str)]) -> u16 {
+        let mut req = Request::builder().method(method).uri(path);
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+        app.clone()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+            .as_u16()
+    }
+
+    #[tokio::test]
+    async fn test_rbac_middleware() {
+        let mut config = RbacPolicyConfig {
+            user_header: Some("X-Forwarded-User".to_string()),
+            groups_header: Some("X-Forwarded-Groups".to_string()),
+            roles: HashMap::from([
+                (
+                    "editor".to_string(),
+                    RbacRoleBinding {
+                        users: vec![],
+                        groups: vec!["writers".to_string()],
+                    },
+                ),
+                (
+                    "viewer".to_string(),
+                    RbacRoleBinding {
+                        users: vec!["*".to_string()],
+                        groups: vec![],
+                    },
+                ),
+            ]),
+            rules: vec![
+                RbacRule {
+                    roles: vec!["viewer".to_string()],
+                    methods: vec!["GET".to_string()],
+                    ..RbacRule::default()
+                },
+                RbacRule {
+                    roles: vec!["editor".to_string()],
+                    paths: vec!["/docs/*".to_string()],
+                    ..RbacRule::default()
+                },
+            ],
+            ..RbacPolicyConfig::default()
+        };
+        let rbac = app(&config);
+        let bob = [("x-forwarded-user", "bob")];
+        let writer = [
+            ("x-forwarded-user", "carol"),
+            ("x-forwarded-groups", "readers, writers"),
+        ];
+
+        assert_eq!(status(&rbac, Method::GET, "/docs/1", &[]).await, 403);
+        assert_eq!(status(&rbac, Method::GET, "/docs/1", &bob).await, 200);
+        assert_eq!(status(&rbac, Method::PUT, "/docs/1", &bob).await, 403);
+        assert_eq!(status(&rbac, Method::PUT, "/docs/1", &writer).await, 200);
+        assert_eq!(status(&rbac, Method::PUT, "/other", &writer).await, 403);
+
+        config.dry_run = true;
+        let dry_run = app(&config);
+        assert_eq!(status(&dry_run, Method::PUT, "/docs/1", &bob).await, 200);
+    }
+
+    #[tokio::test]
+    async fn test_rbac_prefers_authenticated_user() {
+        let config = RbacPolicyConfig {
+            user_header: Some("X-Forwarded-User".to_string()),
+            roles: HashMap::from([(
+                "admin".to_string(),
+                RbacRoleBinding {
+                    users: vec!["alice".to_string()],
+                    groups: vec![],
+                },
+            )]),
+            rules: vec![RbacRule {
+                roles: vec!["admin".to_string()],
+                ..RbacRule::default()
+            }],
+            ..RbacPolicyConfig::default()
+        };
+        let authorizer = Arc::new(RbacAuthorizer::new(&config));
+        let app = Router::new()
+            .route("/", any(|| async { "ok" }))
+            .layer(middleware::from_fn(move |req, next| {
+                rbac_middleware(
+                    req,
+                    next,
+                    authorizer.clone(),
+                    AuditLog::disabled(),
+                    Arc::from("/"),
+                )
+            }))
+            .layer(middleware::from_fn(|mut req: Request, next: Next| {
+                req.extensions_mut().insert(AuthenticatedUser {
+                    name: "mallory".to_string(),
+                    groups: Arc::from([]),
+                });
+                next.run(req)
+            }));
+
+        // The authenticated user wins over a spoofed header
+        assert_eq!(
+            status(&app, Method::GET, "/", &[("x-forwarded-user", "alice")]).await,
+            403
+        );
+    }
+}
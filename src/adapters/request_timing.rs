@@ -0,0 +1,199 @@
+//! Where a request's time went.
+//!
+//! Every request carries a shared [`RequestTimings`] extension. Routing fills
+//! in the matched route and its slow-request threshold and times route
+//! matching, rate limiting and the WAF. The proxy path adds the time spent
+//! queueing for a backend slot, connecting and waiting for the backend's
+//! response head. WAF detections are noted too, for request events. The
+//! handler reads the result back once the response is ready.
+use std::{
+    fmt::Write as _,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::ports::http_client::BackendTiming;
+
+/// Gateway-side phases of a request, timed with [`RequestTimings::start`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Finding the route for the request
+    RouteMatch,
+    /// Checking the route's rate limits
+    RateLimit,
+    /// Ban check and WAF inspection, including reading the body
+    Waf,
+    /// Waiting for a slot on a backend with `backend_limits`
+    Queue,
+}
+
+/// Timings collected for one request; see the module docs.
+#[derive(Debug, Default)]
+pub struct RequestTimings {
+    breakdown: Mutex<TimingBreakdown>,
+}
+
+/// A snapshot of [`RequestTimings`]. Phases the request never went through
+/// are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimingBreakdown {
+    /// Metric label of the matched route
+    pub route: Option<String>,
+    /// The route's `slow_request_threshold_ms`
+    pub slow_threshold: Option<Duration>,
+    /// `path` label of the request metrics
+    pub metrics_path: Option<String>,
+    pub route_match: Option<Duration>,
+    pub rate_limit: Option<Duration>,
+    pub waf: Option<Duration>,
+    pub queue: Option<Duration>,
+    /// Opening a backend connection (zero for a pooled one)
+    pub connect: Option<Duration>,
+    /// From sending the request to the backend until its response head
+    pub backend_first_byte: Option<Duration>,
+    /// WAF threat types detected, whether blocked or only monitored
+    pub threats: Vec<String>,
+}
+
+/// Records the time since it was created into a [`Phase`] when dropped, so
+/// early returns are timed too.
+pub struct PhaseTimer {
+    timings: Arc<RequestTimings>,
+    phase: Phase,
+    started: Instant,
+}
+
+impl Drop for PhaseTimer {
+    fn drop(&mut self) {
+        self.timings.record(self.phase, self.started.elapsed());
+    }
+}
+
+impl RequestTimings {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Note the matched route and its slow-request threshold
+    pub fn set_route(&self, route: String, slow_threshold: Option<Duration>) {
+        self.update(|breakdown| {
+            breakdown.route = Some(route);
+            breakdown.slow_threshold = slow_threshold;
+        });
+    }
+
+    /// Note the `path` label for the request metrics
+    pub fn set_metrics_path(&self, label: impl Into<String>) {
+        let label = label.into();
+        self.update(|breakdown| breakdown.metrics_path = Some(label));
+    }
+
+    /// Start timing `phase` until the returned timer is dropped
+    pub fn start(self: &Arc<Self>, phase: Phase) -> PhaseTimer {
+        PhaseTimer {
+            timings: self.clone(),
+            phase,
+            started: Instant::now(),
+        }
+    }
+
+    /// Add `elapsed` to `phase`
+    pub fn record(&self, phase: Phase, elapsed: Duration) {
+        self.update(|breakdown| {
+            let slot = match phase {
+                Phase::RouteMatch => &mut breakdown.route_match,
+                Phase::RateLimit => &mut breakdown.rate_limit,
+                Phase::Waf => &mut breakdown.waf,
+                Phase::Queue => &mut breakdown.queue,
+            };
+            *slot = Some(slot.unwrap_or_default() + elapsed);
+        });
+    }
+
+    /// Record how the backend answered; a later attempt replaces an earlier one
+    pub fn record_backend(&self, timing: BackendTiming) {
+        self.update(|breakdown| {
+            breakdown.connect = Some(timing.connect);
+            breakdown.backend_first_byte = Some(timing.first_byte);
+        });
+    }
+
+    /// Note a WAF detection
+    pub fn record_threat(&self, threat_type: &str) {
+        self.update(|breakdown| breakdown.threats.push(threat_type.to_string()));
+    }
+
+    pub fn snapshot(&self) -> TimingBreakdown {
+        self.breakdown
+            .lock()
+            .map(|breakdown| breakdown.clone())
+            .unwrap_or_default()
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut TimingBreakdown)) {
+        if let Ok(mut breakdown) = self.breakdown.lock() {
+            apply(&mut breakdown);
+        }
+    }
+}
+
+impl TimingBreakdown {
+    /// Whether a request that took `total` is over its route's threshold
+    pub fn is_slow(&self, total: Duration) -> bool {
+        self.slow_threshold
+            .is_some_and(|threshold| total > threshold)
+    }
+
+    /// `Server-Timing` header value for the recorded phases and `total`,
+    /// with durations in milliseconds
+    pub fn server_timing(&self, total: Duration) -> String {
+        let phases = [
+            ("route", self.route_match),
+            ("ratelimit", self.rate_limit),
+            ("waf", self.waf),
+            ("queue", self.queue),
+            ("connect", self.connect),
+            ("ttfb", self.backend_first_byte),
+            ("total", Some(total)),
+        ];
+        let mut value = String::new();
+        for (name, duration) in phases {
+            if let Some(duration) = duration {
+                if !value.is_empty() {
+                    value.push_str(", ");
+                }
+                let _ = write!(value, "{name};dur={:.3}", duration.as_secs_f64() * 1000.0);
+            }
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_timing_lists_recorded_phases() {
+        let timings = RequestTimings::new();
+        drop(timings.start(Phase::Waf));
+        timings.record(Phase::RouteMatch, Duration::from_micros(20));
+        timings.record(Phase::Queue, Duration::from_millis(1));
+        timings.record(Phase::Queue, Duration::from_millis(2));
+        timings.record_backend(BackendTiming {
+            connect: Duration::ZERO,
+            first_byte: Duration::from_micros(12_500),
+        });
+
+        let breakdown = timings.snapshot();
+        assert!(breakdown.waf.is_some());
+        assert_eq!(breakdown.rate_limit, None);
+
+        let header = breakdown.server_timing(Duration::from_millis(16));
+        assert!(header.starts_with("route;dur=0.020, waf;dur="));
+        assert!(
+            header
+                .ends_with("queue;dur=3.000, connect;dur=0.000, ttfb;dur=12.500, total;dur=16.000")
+        );
+    }
+}
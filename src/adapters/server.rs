@@ -0,0 +1,273 @@
+//! Connection-level HTTP server loop.
+//!
+//! This is a small replacement for `axum::serve` that drives each accepted
+//! connection with a hyper-util `auto` builder, so that connection options which
+//! axum does not expose can be tuned. Currently it enables HTTP/1 header case
+//! capture, which routes with `preserve_header_case` rely on to forward the
+//! client's original header name casing to backends (and back).
+//!
+//! [`serve_until`] stops accepting when its token is cancelled and asks each
+//! open connection to finish its in-flight requests and close. Connections
+//! are registered with a [`ConnectionTracker`] from accept to close, and each
+//! request is counted against its connection until the response body ends.
+//! Requests on TLS connections carry a [`TlsSession`] extension with the SNI
+//! the client sent.
+use std::{
+    convert::Infallible,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{Router, body::Body, extract::ConnectInfo, serve::Listener};
+use http_body_util::BodyExt;
+use hyper::{Request, body::Incoming};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+
+use crate::utils::ConnectionTracker;
+
+/// TLS details of the connection a request arrived on.
+#[derive(Debug, Clone, Default)]
+pub struct TlsSession {
+    /// Host name the client sent as SNI in the handshake, if any
+    pub server_name: Option<String>,
+}
+
+/// A stream accepted by [`serve_until`].
+pub trait AcceptedIo: AsyncRead + AsyncWrite + Unpin + Send + 'static {
+    /// TLS details, or `None` for plaintext connections.
+    fn tls_session(&self) -> Option<TlsSession> {
+        None
+    }
+}
+
+impl AcceptedIo for tokio::net::TcpStream {}
+
+/// A TLS stream together with the SNI captured after its handshake.
+pub struct TlsIo<I> {
+    io: I,
+    session: TlsSession,
+}
+
+impl<I> TlsIo<I> {
+    pub fn new(io: I, server_name: Option<&str>) -> Self {
+        Self {
+            io,
+            session: TlsSession {
+                server_name: server_name.map(str::to_string),
+            },
+        }
+    }
+}
+
+impl<I: AsyncRead + AsyncWrite + Unpin + Send + 'static> AcceptedIo for TlsIo<I> {
+    fn tls_session(&self) -> Option<TlsSession> {
+        Some(self.session.clone())
+    }
+}
+
+impl<I: AsyncRead + Unpin> AsyncRead for TlsIo<I> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl<I: AsyncWrite + Unpin> AsyncWrite for TlsIo<I> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
+
+/// Accept connections from `listener` forever, serving each with `app`.
+///
+/// The peer address is inserted as a [`ConnectInfo<SocketAddr>`] request
+/// extension, matching `into_make_service_with_connect_info::<SocketAddr>()`.
+pub async fn serve<L>(listener: L, app: Router) -> io::Result<()>
+where
+    L: Listener<Addr = SocketAddr>,
+    L::Io: AcceptedIo,
+{
+    serve_until(
+        listener,
+        app,
+        CancellationToken::new(),
+        Arc::new(ConnectionTracker::new()),
+    )
+    .await
+}
+
+/// Like [`serve`], but returns once `shutdown` is cancelled, and records
+/// connections in `tracker`.
+///
+/// The listener is dropped on return. Connections already accepted keep
+/// running in their own tasks: each stops taking new requests (HTTP/1
+/// keep-alive is disabled, HTTP/2 sends GOAWAY) and closes once its
+/// in-flight requests complete. The same happens to a single connection
+/// when the tracker asks it to close. The connection's
+/// [`ConnectionInfo`](crate::utils::ConnectionInfo) is also inserted as a
+/// request extension.
+pub async fn serve_until<L>(
+    mut listener: L,
+    app: Router,
+    shutdown: CancellationToken,
+    tracker: Arc<ConnectionTracker>,
+) -> io::Result<()>
+where
+    L: Listener<Addr = SocketAddr>,
+    L::Io: AcceptedIo,
+{
+    let mut builder = auto::Builder::new(TokioExecutor::new());
+    builder.http1().preserve_header_case(true);
+
+    loop {
+        let (io, remote_addr) = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = shutdown.cancelled() => return Ok(()),
+        };
+        let builder = builder.clone();
+        let app = app.clone();
+        let shutdown = shutdown.clone();
+        let tracker = tracker.clone();
+
+        tokio::spawn(async move {
+            let connection_info = tracker.register_connection(remote_addr).await;
+            let tls_session = io.tls_session();
+            let service = hyper::service::service_fn({
+                let connection_info = connection_info.clone();
+                move |mut req: Request<Incoming>| {
+                    req.extensions_mut().insert(ConnectInfo(remote_addr));
+                    req.extensions_mut().insert(connection_info.clone());
+                    if let Some(tls_session) = &tls_session {
+                        req.extensions_mut().insert(tls_session.clone());
+                    }
+                    let request_guard = connection_info.track_request();
+                    let app = app.clone();
+                    async move {
+                        let response = app.oneshot(req).await?;
+                        // Keep the request counted until its body has been sent
+                        Ok::<_, Infallible>(response.map(|body| {
+                            Body::new(body.map_frame(move |frame| {
+                                let _ = &request_guard;
+                                frame
+                            }))
+                        }))
+                    }
+                }
+            });
+
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(io), service);
+            tokio::pin!(connection);
+            let result = tokio::select! {
+                result = connection.as_mut() => result,
+                _ = shutdown.cancelled() => {
+                    connection.as_mut().graceful_shutdown();
+                    connection.await
+                }
+                _ = connection_info.close_requested() => {
+                    connection.as_mut().graceful_shutdown();
+                    connection.await
+                }
+            };
+            if let Err(e) = result {
+                tracing::debug!(remote_addr = %remote_addr, "Connection closed with error: {e}");
+            }
+            tracker.unregister_connection(connection_info.id).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    async fn wait_for_connections(tracker: &ConnectionTracker, expected: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while tracker.active_connection_count() != expected {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("connection count");
+    }
+
+    #[tokio::test]
+    async fn test_connections_tracked_from_accept_to_close() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let tracker = Arc::new(ConnectionTracker::new());
+        let app = Router::new().route("/", get(|| async { "ok" }));
+        tokio::spawn(serve_until(
+            listener,
+            app,
+            CancellationToken::new(),
+            tracker.clone(),
+        ));
+
+        // One keep-alive socket serving two requests is one connection
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        for _ in 0..2 {
+            stream
+                .write_all(b"GET / HTTP/1.1\r\nHost: test\r\n\r\n")
+                .await
+                .unwrap();
+            let mut buf = [0u8; 512];
+            let read = stream.read(&mut buf).await.unwrap();
+            assert!(String::from_utf8_lossy(&buf[..read]).ends_with("ok"));
+        }
+        wait_for_connections(&tracker, 1).await;
+        let stats = tracker.get_stats().await;
+        assert_eq!(stats.idle_connections, 1);
+        assert_eq!(stats.total_active_requests, 0);
+
+        // Closing idle connections shuts the socket and unregisters it
+        tracker.close_idle_connections().await;
+        let mut buf = [0u8; 16];
+        let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+            .await
+            .expect("socket closed")
+            .unwrap();
+        assert_eq!(read, 0);
+        wait_for_connections(&tracker, 0).await;
+    }
+}
@@ -0,0 +1,493 @@
+//! SPIFFE Workload API integration for backend mTLS.
+//!
+//! [`SpiffeSource`] keeps a server-streaming `FetchX509SVID` call open to the
+//! local Workload API (SPIRE agent or compatible) and swaps in every SVID and
+//! trust bundle it receives. TLS client configurations built from the source
+//! resolve the current SVID on each handshake, so rotation needs no client
+//! rebuild. Backend certificates are authenticated as SPIFFE SVIDs: the chain
+//! is verified against the bundle of the backend's trust domain and its
+//! SPIFFE ID is checked against the configured policy; DNS names are ignored.
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use arc_swap::ArcSwapOption;
+use eyre::{Result, WrapErr, eyre};
+use hyper::Uri;
+use hyper_util::rt::TokioIo;
+use rustls::{
+    DigitallySignedStruct, RootCertStore, SignatureScheme,
+    client::{
+        ResolvesClientCert,
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        verify_server_cert_signed_by_trust_anchor,
+    },
+    crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime},
+    server::ParsedCertificate,
+    sign::CertifiedKey,
+};
+use tonic::{metadata::MetadataValue, transport::Endpoint};
+use tonic_prost::ProstCodec;
+use x509_parser::{extensions::GeneralName, prelude::parse_x509_certificate};
+
+use crate::config::models::SpiffeConfig;
+
+const FETCH_X509_SVID_PATH: &str = "/SpiffeWorkloadAPI/FetchX509SVID";
+const WORKLOAD_API_HEADER: &str = "workload.spiffe.io";
+
+// Subset of the Workload API protobuf messages (workload.proto) used by the gateway.
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct X509SvidRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct X509SvidResponse {
+    #[prost(message, repeated, tag = "1")]
+    svids: Vec<X509Svid>,
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    crl: Vec<Vec<u8>>,
+    #[prost(map = "string, bytes", tag = "3")]
+    federated_bundles: HashMap<String, Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct X509Svid {
+    #[prost(string, tag = "1")]
+    spiffe_id: String,
+    /// ASN.1 DER certificate chain, leaf first
+    #[prost(bytes = "vec", tag = "2")]
+    x509_svid: Vec<u8>,
+    /// PKCS#8 DER private key
+    #[prost(bytes = "vec", tag = "3")]
+    x509_svid_key: Vec<u8>,
+    /// ASN.1 DER CA certificates of the SVID's trust domain
+    #[prost(bytes = "vec", tag = "4")]
+    bundle: Vec<u8>,
+    #[prost(string, tag = "5")]
+    hint: String,
+}
+
+/// Identity material from the latest Workload API update.
+#[derive(Debug)]
+struct SvidState {
+    spiffe_id: String,
+    trust_domain: String,
+    certified_key: Arc<CertifiedKey>,
+    /// Trust bundles keyed by trust domain name
+    bundles: HashMap<String, Arc<RootCertStore>>,
+}
+
+/// Continuously updated X.509 SVID and trust bundles from the Workload API.
+#[derive(Debug)]
+pub struct SpiffeSource {
+    config: SpiffeConfig,
+    state: ArcSwapOption<SvidState>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl SpiffeSource {
+    /// Create the source and spawn the background task watching the Workload API.
+    pub fn start(config: &SpiffeConfig) -> Result<Arc<Self>> {
+        let runtime = tokio::runtime::Handle::try_current()
+            .wrap_err("The SPIFFE Workload API client requires a Tokio runtime")?;
+        let source = Arc::new(Self::new(config.clone()));
+        runtime.spawn(source.clone().run());
+        Ok(source)
+    }
+
+    fn new(config: SpiffeConfig) -> Self {
+        Self {
+            config,
+            state: ArcSwapOption::empty(),
+            provider: Arc::new(rustls::crypto::aws_lc_rs::default_provider()),
+        }
+    }
+
+    /// SPIFFE ID of the current SVID, if one has been received.
+    pub fn spiffe_id(&self) -> Option<String> {
+        self.state.load().as_ref().map(|s| s.spiffe_id.clone())
+    }
+
+    /// Build a rustls client configuration presenting the current SVID and
+    /// verifying backends against the SPIFFE policy.
+    pub fn client_config(self: &Arc<Self>) -> rustls::ClientConfig {
+        rustls::ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SpiffeServerVerifier(self.clone())))
+            .with_client_cert_resolver(Arc::new(SvidCertResolver(self.clone())))
+    }
+
+    async fn run(self: Arc<Self>) {
+        let backoff = Duration::from_secs(self.config.reconnect_backoff_secs.max(1));
+        loop {
+            match self.watch().await {
+                Ok(()) => tracing::warn!("SPIFFE Workload API stream closed, reconnecting"),
+                Err(e) => tracing::warn!(
+                    "SPIFFE Workload API unavailable at {}: {:#}",
+                    self.config.workload_api_socket,
+                    e
+                ),
+            }
+            tokio::time::sleep(backoff).await;
+        }
+    }
+
+    /// Stream SVID updates until the connection ends.
+    async fn watch(&self) -> Result<()> {
+        let socket_path = socket_path(&self.config.workload_api_socket)?.to_string();
+        let channel = Endpoint::from_static("http://localhost")
+            .connect_with_connector(tower::service_fn(move |_: Uri| {
+                let socket_path = socket_path.clone();
+                async move {
+                    tokio::net::UnixStream::connect(socket_path)
+                        .await
+                        .map(TokioIo::new)
+                }
+            }))
+            .await
+            .wrap_err("Failed to connect to the Workload API socket")?;
+
+        let mut grpc = tonic::client::Grpc::new(channel);
+        grpc.ready()
+            .await
+            .wrap_err("Workload API channel not ready")?;
+
+        let mut request = tonic::Request::new(X509SvidRequest {});
+        request
+            .metadata_mut()
+            .insert(WORKLOAD_API_HEADER, MetadataValue::from_static("true"));
+        let mut stream = grpc
+            .server_streaming(
+                request,
+                http::uri::PathAndQuery::from_static(FETCH_X509_SVID_PATH),
+                ProstCodec::<X509SvidRequest, X509SvidResponse>::default(),
+            )
+            .await
+            .wrap_err("FetchX509SVID call failed")?
+            .into_inner();
+
+        while let Some(response) = stream
+            .message()
+            .await
+            .wrap_err("FetchX509SVID stream failed")?
+        {
+            if let Err(e) = self.apply(response) {
+                tracing::error!(
+                    "Ignoring invalid SVID update from the Workload API: {:#}",
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Install the default (first) SVID and trust bundles from an update.
+    fn apply(&self, response: X509SvidResponse) -> Result<()> {
+        let svid = response
+            .svids
+            .into_iter()
+            .next()
+            .ok_or_else(|| eyre!("Workload API returned no SVIDs"))?;
+        let trust_domain = trust_domain_of(&svid.spiffe_id)
+            .ok_or_else(|| eyre!("Invalid SPIFFE ID '{}'", svid.spiffe_id))?
+            .to_string();
+
+        let chain = split_der_certificates(&svid.x509_svid)?;
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(svid.x509_svid_key));
+        let signing_key = self
+            .provider
+            .key_provider
+            .load_private_key(key)
+            .wrap_err("Unsupported SVID private key")?;
+
+        let mut bundles = HashMap::new();
+        bundles.insert(trust_domain.clone(), Arc::new(root_store(&svid.bundle)?));
+        for (domain, bundle) in response.federated_bundles {
+            let domain = domain.trim_start_matches("spiffe://").to_string();
+            bundles.insert(domain, Arc::new(root_store(&bundle)?));
+        }
+
+        tracing::info!(
+            spiffe_id = %svid.spiffe_id,
+            trust_bundles = bundles.len(),
+            "Loaded X.509 SVID from the Workload API"
+        );
+        self.state.store(Some(Arc::new(SvidState {
+            spiffe_id: svid.spiffe_id,
+            trust_domain,
+            certified_key: Arc::new(CertifiedKey::new(chain, signing_key)),
+            bundles,
+        })));
+        Ok(())
+    }
+
+    /// Authenticate a backend certificate chain as an allowed SVID.
+    fn verify_backend(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> Result<(), rustls::Error> {
+        let state = self.state.load_full().ok_or_else(|| {
+            rustls::Error::General("No SVID received from the Workload API yet".to_string())
+        })?;
+
+        let spiffe_id = spiffe_id_of(end_entity).ok_or_else(|| {
+            rustls::Error::General("Backend certificate has no SPIFFE ID".to_string())
+        })?;
+        let trust_domain = trust_domain_of(&spiffe_id).unwrap_or_default();
+        let domain_allowed = if self.config.trust_domains.is_empty() {
+            trust_domain == state.trust_domain
+        } else {
+            self.config.trust_domains.iter().any(|d| d == trust_domain)
+        };
+        let id_allowed =
+            self.config.allowed_ids.is_empty() || self.config.allowed_ids.contains(&spiffe_id);
+        if !domain_allowed || !id_allowed {
+            return Err(rustls::Error::General(format!(
+                "Backend SPIFFE ID '{spiffe_id}' is not allowed"
+            )));
+        }
+
+        let roots = state.bundles.get(trust_domain).ok_or_else(|| {
+            rustls::Error::General(format!("No trust bundle for trust domain '{trust_domain}'"))
+        })?;
+        let cert = ParsedCertificate::try_from(end_entity)?;
+        verify_server_cert_signed_by_trust_anchor(
+            &cert,
+            roots,
+            intermediates,
+            now,
+            self.provider.signature_verification_algorithms.all,
+        )
+    }
+}
+
+/// Presents the current SVID as the TLS client certificate.
+#[derive(Debug)]
+struct SvidCertResolver(Arc<SpiffeSource>);
+
+impl ResolvesClientCert for SvidCertResolver {
+    fn resolve(
+        &self,
+        _root_hint_subjects: &[&[u8]],
+        _sigschemes: &[SignatureScheme],
+    ) -> Option<Arc<CertifiedKey>> {
+        self.0
+            .state
+            .load()
+            .as_ref()
+            .map(|s| s.certified_key.clone())
+    }
+
+    fn has_certs(&self) -> bool {
+        true
+    }
+}
+
+/// Verifies backend certificates as SPIFFE SVIDs.
+#[derive(Debug)]
+struct SpiffeServerVerifier(Arc<SpiffeSource>);
+
+impl ServerCertVerifier for SpiffeServerVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.0.verify_backend(end_entity, intermediates, now)?;
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0
+            .provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// Filesystem path of a `unix://` Workload API endpoint.
+fn socket_path(endpoint: &str) -> Result<&str> {
+    endpoint
+        .strip_prefix("unix://")
+        .or_else(|| endpoint.strip_prefix("unix:"))
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| eyre!("Workload API endpoint must be a unix:// socket, got '{endpoint}'"))
+}
+
+/// Trust domain name of a SPIFFE ID (`spiffe://<trust-domain>/<path>`).
+fn trust_domain_of(spiffe_id: &str) -> Option<&str> {
+    spiffe_id
+        .strip_prefix("spiffe://")?
+        .split('/')
+        .next()
+        .filter(|domain| !domain.is_empty())
+}
+
+/// SPIFFE ID from the URI SAN of a certificate.
+fn spiffe_id_of(cert: &CertificateDer<'_>) -> Option<String> {
+    let (_, cert) = parse_x509_certificate(cert.as_ref()).ok()?;
+    let san = cert.subject_alternative_name().ok()??;
+    san.value.general_names.iter().find_map(|name| match name {
+        GeneralName::URI(uri) if uri.starts_with("spiffe://") => Some(uri.to_string()),
+        _ => None,
+    })
+}
+
+/// Split concatenated DER certificates.
+fn split_der_certificates(mut der: &[u8]) -> Result<Vec<CertificateDer<'static>>> {
+    let mut certs = Vec::new();
+    while !der.is_empty() {
+        let (rest, _) =
+            parse_x509_certificate(der).map_err(|e| eyre!("Invalid DER certificate: {e}"))?;
+        let len = der.len() - rest.len();
+        certs.push(CertificateDer::from(der[..len].to_vec()));
+        der = rest;
+    }
+    if certs.is_empty() {
+        return Err(eyre!("Empty certificate list"));
+    }
+    Ok(certs)
+}
+
+fn root_store(der: &[u8]) -> Result<RootCertStore> {
+    let mut store = RootCertStore::empty();
+    let (added, _) = store.add_parsable_certificates(split_der_certificates(der)?);
+    if added == 0 {
+        return Err(eyre!("Trust bundle contains no usable CA certificates"));
+    }
+    Ok(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::{
+        BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair, SanType,
+        string::Ia5String,
+    };
+
+    use super::*;
+
+    struct TestSvid {
+        leaf: CertificateDer<'static>,
+        key: Vec<u8>,
+        bundle: Vec<u8>,
+    }
+
+    fn issue_svid(spiffe_id: &str) -> TestSvid {
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = CertifiedIssuer::self_signed(ca_params, KeyPair::generate().unwrap()).unwrap();
+
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.subject_alt_names = vec![SanType::URI(Ia5String::try_from(spiffe_id).unwrap())];
+        let key = KeyPair::generate().unwrap();
+        let leaf = params.signed_by(&key, &ca).unwrap();
+
+        TestSvid {
+            leaf: leaf.der().clone(),
+            key: key.serialize_der(),
+            bundle: ca.der().to_vec(),
+        }
+    }
+
+    fn response(spiffe_id: &str, svid: &TestSvid) -> X509SvidResponse {
+        X509SvidResponse {
+            svids: vec![X509Svid {
+                spiffe_id: spiffe_id.to_string(),
+                x509_svid: svid.leaf.to_vec(),
+                x509_svid_key: svid.key.clone(),
+                bundle: svid.bundle.clone(),
+                hint: String::new(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_trust_domain_and_socket_parsing() {
+        assert_eq!(
+            trust_domain_of("spiffe://example.org/ns/web"),
+            Some("example.org")
+        );
+        assert_eq!(trust_domain_of("https://example.org"), None);
+        assert_eq!(
+            socket_path("unix:///run/spire/agent.sock").unwrap(),
+            "/run/spire/agent.sock"
+        );
+        assert!(socket_path("tcp://127.0.0.1:8081").is_err());
+    }
+
+    #[test]
+    fn test_apply_and_verify_backend_svid() {
+        let gateway = issue_svid("spiffe://example.org/gateway");
+        let source = SpiffeSource::new(SpiffeConfig::default());
+        source
+            .apply(response("spiffe://example.org/gateway", &gateway))
+            .unwrap();
+        assert_eq!(
+            source.spiffe_id().as_deref(),
+            Some("spiffe://example.org/gateway")
+        );
+
+        // The gateway's own SVID chains to the example.org bundle
+        assert!(
+            source
+                .verify_backend(&gateway.leaf, &[], UnixTime::now())
+                .is_ok()
+        );
+
+        // A certificate from another CA in the same trust domain is rejected
+        let foreign = issue_svid("spiffe://example.org/backend");
+        assert!(
+            source
+                .verify_backend(&foreign.leaf, &[], UnixTime::now())
+                .is_err()
+        );
+
+        // IDs outside the allowed list are rejected
+        let restricted = SpiffeSource::new(SpiffeConfig {
+            allowed_ids: vec!["spiffe://example.org/api".to_string()],
+            ..SpiffeConfig::default()
+        });
+        restricted
+            .apply(response("spiffe://example.org/gateway", &gateway))
+            .unwrap();
+        assert!(
+            restricted
+                .verify_backend(&gateway.leaf, &[], UnixTime::now())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Capture of backend exchanges for a route's [`Tap`].
+//!
+//! A [`TapRecorder`] is created when a tapped route sends a request to its
+//! backend. The request and response bodies are wrapped so their first
+//! `max_body_bytes` are copied as they stream past; nothing is buffered or
+//! delayed. The exchange is added to the tap once both bodies are finished
+//! with, like the byte totals in [`super::transfer`].
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use axum::body::Body as AxumBody;
+use bytes::Bytes;
+use http::{HeaderMap, Request, Response};
+use hyper::body::{Body as HttpBody, Frame, SizeHint};
+
+use super::transfer::BodyKind;
+use crate::core::tap::{Tap, TapBody, TapExchange, TapRequest, TapResponse};
+
+/// What has been seen of one exchange so far
+#[derive(Default)]
+struct Capture {
+    request_body: Vec<u8>,
+    request_size: u64,
+    response: Option<(u16, Vec<(String, String)>)>,
+    response_body: Vec<u8>,
+    response_size: u64,
+    error: Option<String>,
+}
+
+/// Records one backend exchange into a tap when the last reference drops.
+pub struct TapRecorder {
+    tap: Arc<Tap>,
+    started: Instant,
+    started_at: String,
+    request_id: Option<String>,
+    backend: String,
+    method: String,
+    uri: String,
+    request_headers: Vec<(String, String)>,
+    capture: Mutex<Capture>,
+}
+
+impl TapRecorder {
+    /// Start recording `req`, about to be sent to `backend`
+    pub fn start<B>(tap: Arc<Tap>, backend: &str, req: &Request<B>) -> Arc<Self> {
+        Arc::new(Self {
+            request_headers: header_pairs(&tap, req.headers()),
+            tap,
+            started: Instant::now(),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            request_id: req
+                .headers()
+                .get(super::middleware::X_REQUEST_ID)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from),
+            backend: backend.to_string(),
+            method: req.method().to_string(),
+            uri: req.uri().to_string(),
+            capture: Mutex::new(Capture::default()),
+        })
+    }
+
+    /// Wrap the `kind` body so its first bytes are recorded
+    pub fn wrap(self: &Arc<Self>, inner: AxumBody, kind: BodyKind) -> AxumBody {
+        AxumBody::new(TappedBody {
+            inner,
+            recorder: self.clone(),
+            kind,
+        })
+    }
+
+    /// Record the backend's response head
+    pub fn record_response<B>(&self, response: &Response<B>) {
+        let headers = header_pairs(&self.tap, response.headers());
+        self.with_capture(|capture| {
+            capture.response = Some((response.status().as_u16(), headers));
+        });
+    }
+
+    /// Record why the backend request failed
+    pub fn record_error(&self, error: &dyn std::fmt::Display) {
+        let error = error.to_string();
+        self.with_capture(|capture| capture.error = Some(error));
+    }
+
+    fn record_frame(&self, kind: BodyKind, data: &Bytes) {
+        let limit = self.tap.config().max_body_bytes;
+        self.with_capture(|capture| {
+            let (body, size) = match kind {
+                BodyKind::Request => (&mut capture.request_body, &mut capture.request_size),
+                BodyKind::Response => (&mut capture.response_body, &mut capture.response_size),
+            };
+            *size += data.len() as u64;
+            let room = limit.saturating_sub(body.len());
+            body.extend_from_slice(&data[..room.min(data.len())]);
+        });
+    }
+
+    fn with_capture(&self, f: impl FnOnce(&mut Capture)) {
+        match self.capture.lock() {
+            Ok(mut capture) => f(&mut capture),
+            Err(poisoned) => f(&mut poisoned.into_inner()),
+        }
+    }
+}
+
+impl Drop for TapRecorder {
+    fn drop(&mut self) {
+        let capture = std::mem::take(match self.capture.get_mut() {
+            Ok(capture) => capture,
+            Err(poisoned) => poisoned.into_inner(),
+        });
+        let response = capture.response.map(|(status, headers)| TapResponse {
+            status,
+            headers,
+            body: TapBody::new(&capture.response_body, capture.response_size),
+        });
+        self.tap.record(TapExchange {
+            id: 0,
+            started_at: std::mem::take(&mut self.started_at),
+            request_id: self.request_id.take(),
+            backend: std::mem::take(&mut self.backend),
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            request: TapRequest {
+                method: std::mem::take(&mut self.method),
+                uri: std::mem::take(&mut self.uri),
+                headers: std::mem::take(&mut self.request_headers),
+                body: TapBody::new(&capture.request_body, capture.request_size),
+            },
+            response,
+            error: capture.error,
+        });
+    }
+}
+
+/// Headers as name/value pairs, with the tap's redacted headers masked
+fn header_pairs(tap: &Tap, headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if tap.redacts(name.as_str()) {
+                "[redacted]".to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+/// A body whose first bytes are copied into a [`TapRecorder`]
+struct TappedBody {
+    inner: AxumBody,
+    recorder: Arc<TapRecorder>,
+    kind: BodyKind,
+}
+
+impl HttpBody for TappedBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &poll
+            && let Some(data) = frame.data_ref()
+        {
+            self.recorder.record_frame(self.kind, data);
+        }
+        poll
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+
+    use super::*;
+    use crate::{config::TapConfig, core::TapRegistry};
+
+    #[tokio::test]
+    async fn test_records_exchange_when_bodies_finish() {
+        let registry = TapRegistry::new();
+        registry.start(
+            "/api",
+            TapConfig {
+                max_body_bytes: 4,
+                ..TapConfig::default()
+            },
+        );
+        let tap = registry.get("/api").unwrap();
+
+        let req = Request::post("http://backend/api/items")
+            .header("authorization", "Bearer secret")
+            .header("x-request-id", "req-7")
+            .body(AxumBody::from("request body"))
+            .unwrap();
+        let recorder = TapRecorder::start(tap.clone(), "http://backend", &req);
+        let body = recorder.wrap(req.into_body(), BodyKind::Request);
+        to_bytes(body, usize::MAX).await.unwrap();
+
+        let response = Response::builder()
+            .status(201)
+            .body(AxumBody::from("ok"))
+            .unwrap();
+        recorder.record_response(&response);
+        let body = recorder.wrap(response.into_body(), BodyKind::Response);
+        drop(recorder);
+        assert!(tap.is_empty());
+        assert_eq!(to_bytes(body, usize::MAX).await.unwrap(), "ok");
+
+        let exchanges = tap.exchanges();
+        let exchange = &exchanges[0];
+        assert_eq!(exchange.request_id.as_deref(), Some("req-7"));
+        assert_eq!(exchange.request.method, "POST");
+        assert!(
+            exchange
+                .request
+                .headers
+                .contains(&("authorization".to_string(), "[redacted]".to_string()))
+        );
+        assert_eq!(exchange.request.body.content, "requ");
+        assert_eq!(exchange.request.body.size, 12);
+        assert!(exchange.request.body.truncated);
+        let response = exchange.response.as_ref().unwrap();
+        assert_eq!(response.status, 201);
+        assert_eq!(response.body.content, "ok");
+        assert!(!response.body.truncated);
+    }
+}
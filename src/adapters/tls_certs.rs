@@ -0,0 +1,172 @@
+//! Certificates for the TLS listeners.
+//!
+//! The `[tls]` certificate is presented by default. Virtual hosts with a
+//! `tls` section get their own, chosen by the SNI the client sends, with the
+//! same exact-before-wildcard matching as routing. Certificates are read when
+//! a listener starts.
+use std::{fs::File, io::BufReader, sync::Arc};
+
+use eyre::{Result, WrapErr, eyre};
+use rustls::{
+    crypto::CryptoProvider,
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use rustls_pemfile::{certs, pkcs8_private_keys};
+
+use crate::{config::ServerConfig, core::virtual_host::HostTable};
+
+/// Load a PEM certificate chain and its PKCS#8 private key
+pub fn load_certified_key(
+    cert_path: &str,
+    key_path: &str,
+    provider: &CryptoProvider,
+) -> Result<CertifiedKey> {
+    let cert_file = &mut BufReader::new(File::open(cert_path).context("failed to open cert file")?);
+    let key_file = &mut BufReader::new(File::open(key_path).context("failed to open key file")?);
+
+    let cert_chain = certs(cert_file).collect::<Result<Vec<_>, _>>()?;
+    if cert_chain.is_empty() {
+        return Err(eyre!("No certificates found in {cert_path}"));
+    }
+    let key = pkcs8_private_keys(key_file)
+        .next()
+        .transpose()?
+        .ok_or_else(|| eyre!("No PKCS#8 private key found in key file"))?;
+    CertifiedKey::from_der(cert_chain, key.into(), provider)
+        .map_err(|e| eyre!("Unusable certificate {cert_path}: {e}"))
+}
+
+/// Picks a virtual host's certificate by SNI, and the default one otherwise
+#[derive(Debug)]
+pub struct SniCertResolver {
+    default: Arc<CertifiedKey>,
+    hosts: HostTable<Arc<CertifiedKey>>,
+}
+
+impl SniCertResolver {
+    /// The certificate for a handshake that asked for `server_name`
+    pub fn certificate_for(&self, server_name: Option<&str>) -> Arc<CertifiedKey> {
+        server_name
+            .and_then(|name| self.hosts.get(name))
+            .unwrap_or(&self.default)
+            .clone()
+    }
+}
+
+impl ResolvesServerCert for SniCertResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.certificate_for(client_hello.server_name()))
+    }
+}
+
+/// rustls settings presenting the `[tls]` certificate, or a virtual host's
+/// when the client's SNI names one of its hostnames
+pub fn server_config(config: &ServerConfig) -> Result<rustls::ServerConfig> {
+    let (Some(cert_path), Some(key_path)) = config.tls.as_ref().map_or((None, None), |tls| {
+        (tls.cert_path.as_ref(), tls.key_path.as_ref())
+    }) else {
+        return Err(eyre!("TLS enabled but no valid config found"));
+    };
+    let builder = rustls::ServerConfig::builder().with_no_client_auth();
+    let provider = builder.crypto_provider().clone();
+    let default = Arc::new(load_certified_key(cert_path, key_path, &provider)?);
+
+    let mut hosts = HostTable::new();
+    for (name, vhost) in &config.virtual_hosts {
+        let Some(tls) = &vhost.tls else {
+            continue;
+        };
+        let key = load_certified_key(&tls.cert_path, &tls.key_path, &provider)
+            .wrap_err_with(|| format!("Failed to load certificate for virtual host '{name}'"))?;
+        let key = Arc::new(key);
+        for hostname in &vhost.hostnames {
+            hosts.insert(hostname, key.clone());
+        }
+    }
+    Ok(builder.with_cert_resolver(Arc::new(SniCertResolver { default, hosts })))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+    use crate::config::{TlsConfig, VirtualHostConfig, VirtualHostTlsConfig};
+
+    /// Certificate and key files for `names`
+    fn cert_files(names: &[&str]) -> (NamedTempFile, NamedTempFile) {
+        let cert = rcgen::generate_simple_self_signed(
+            names.iter().map(|n| n.to_string()).collect::<Vec<_>>(),
+        )
+        .unwrap();
+        let mut cert_file = NamedTempFile::new().unwrap();
+        cert_file.write_all(cert.cert.pem().as_bytes()).unwrap();
+        let mut key_file = NamedTempFile::new().unwrap();
+        key_file
+            .write_all(cert.signing_key.serialize_pem().as_bytes())
+            .unwrap();
+        (cert_file, key_file)
+    }
+
+    fn path(file: &NamedTempFile) -> String {
+        file.path().to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_certificate_chosen_by_sni() {
+        let default = cert_files(&["gateway.example.com"]);
+        let tenant = cert_files(&["*.tenant.example.com"]);
+        let mut config = ServerConfig {
+            tls: Some(TlsConfig {
+                cert_path: Some(path(&default.0)),
+                key_path: Some(path(&default.1)),
+                acme: None,
+                sni_host_check: Default::default(),
+            }),
+            ..ServerConfig::default()
+        };
+        config.virtual_hosts.insert(
+            "tenant".to_string(),
+            VirtualHostConfig {
+                hostnames: vec!["*.tenant.example.com".to_string()],
+                tls: Some(VirtualHostTlsConfig {
+                    cert_path: path(&tenant.0),
+                    key_path: path(&tenant.1),
+                }),
+                ..VirtualHostConfig::default()
+            },
+        );
+
+        let provider = rustls::ServerConfig::builder().crypto_provider().clone();
+        let resolver = SniCertResolver {
+            default: Arc::new(
+                load_certified_key(&path(&default.0), &path(&default.1), &provider).unwrap(),
+            ),
+            hosts: {
+                let mut hosts = HostTable::new();
+                hosts.insert(
+                    "*.tenant.example.com",
+                    Arc::new(
+                        load_certified_key(&path(&tenant.0), &path(&tenant.1), &provider).unwrap(),
+                    ),
+                );
+                hosts
+            },
+        };
+        let tenant_cert = resolver.certificate_for(Some("api.tenant.example.com"));
+        let default_cert = resolver.certificate_for(Some("other.example.com"));
+        assert_ne!(tenant_cert.cert[0], default_cert.cert[0]);
+        assert_eq!(resolver.certificate_for(None).cert[0], default_cert.cert[0]);
+
+        assert!(server_config(&config).is_ok());
+        config.virtual_hosts.get_mut("tenant").unwrap().tls = Some(VirtualHostTlsConfig {
+            cert_path: "missing.pem".to_string(),
+            key_path: path(&tenant.1),
+        });
+        let err = server_config(&config).unwrap_err();
+        assert!(format!("{err:#}").contains("virtual host 'tenant'"));
+    }
+}
@@ -0,0 +1,223 @@
+//! Per-request body byte accounting.
+//!
+//! Request and response bodies are wrapped in a [`CountingBody`] that adds
+//! the size of each data frame to a shared [`TransferStats`] as it streams
+//! past. Nothing is buffered: frames are passed through unchanged, and the
+//! wrapper forwards the inner body's size hint so `Content-Length` framing
+//! is preserved. The totals are reported once both bodies are finished with.
+//!
+//! Each request gets one [`TransferStats`] for the client connection and,
+//! when it is proxied, one for the backend connection. Body transformations
+//! can make the two differ.
+use std::{
+    pin::Pin,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+};
+
+use axum::body::Body as AxumBody;
+use bytes::Bytes;
+use hyper::body::{Body as HttpBody, Frame, SizeHint};
+
+/// Which body of a request a [`CountingBody`] wraps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyKind {
+    Request,
+    Response,
+}
+
+/// The connection whose bytes a [`TransferStats`] counts
+#[derive(Debug)]
+enum Leg {
+    /// Between the client and the gateway
+    Downstream {
+        request_id: String,
+        protocol: &'static str,
+    },
+    /// Between the gateway and a backend
+    Upstream,
+}
+
+/// Bytes moved for one request on one connection.
+///
+/// Shared by the request and response body wrappers; dropping the last one
+/// records the totals.
+#[derive(Debug)]
+pub struct TransferStats {
+    leg: Leg,
+    route: OnceLock<String>,
+    request_bytes: AtomicU64,
+    response_bytes: AtomicU64,
+}
+
+impl TransferStats {
+    /// Bytes exchanged with the client. The route is filled in with
+    /// [`TransferStats::set_route`] once the request has been matched.
+    pub fn downstream(request_id: impl Into<String>, protocol: &'static str) -> Arc<Self> {
+        Self::with_leg(Leg::Downstream {
+            request_id: request_id.into(),
+            protocol,
+        })
+    }
+
+    /// Bytes exchanged with the backend serving `route`
+    pub fn upstream(route: impl Into<String>) -> Arc<Self> {
+        let stats = Self::with_leg(Leg::Upstream);
+        stats.set_route(route.into());
+        stats
+    }
+
+    fn with_leg(leg: Leg) -> Arc<Self> {
+        Arc::new(Self {
+            leg,
+            route: OnceLock::new(),
+            request_bytes: AtomicU64::new(0),
+            response_bytes: AtomicU64::new(0),
+        })
+    }
+
+    /// Label the per-route byte counters with `route`; only the first call
+    /// has an effect.
+    pub fn set_route(&self, route: String) {
+        let _ = self.route.set(route);
+    }
+
+    /// Request body bytes moved so far
+    pub fn request_bytes(&self) -> u64 {
+        self.request_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Response body bytes moved so far
+    pub fn response_bytes(&self) -> u64 {
+        self.response_bytes.load(Ordering::Relaxed)
+    }
+
+    fn counter(&self, kind: BodyKind) -> &AtomicU64 {
+        match kind {
+            BodyKind::Request => &self.request_bytes,
+            BodyKind::Response => &self.response_bytes,
+        }
+    }
+}
+
+impl Drop for TransferStats {
+    fn drop(&mut self) {
+        let (request_bytes, response_bytes) = (self.request_bytes(), self.response_bytes());
+        // The gateway reads requests from and writes responses to the client,
+        // and the other way round with backends
+        let (request_direction, response_direction) = match &self.leg {
+            Leg::Downstream {
+                request_id,
+                protocol,
+            } => {
+                crate::metrics::record_body_sizes(protocol, request_bytes, response_bytes);
+                tracing::debug!(
+                    request_id = %request_id,
+                    bytes_in = request_bytes,
+                    bytes_out = response_bytes,
+                    "request transfer finished"
+                );
+                ("in", "out")
+            }
+            Leg::Upstream => ("out", "in"),
+        };
+        if let Some(route) = self.route.get() {
+            crate::metrics::record_request_bytes(route, request_direction, request_bytes);
+            crate::metrics::record_response_bytes(route, response_direction, response_bytes);
+        }
+    }
+}
+
+/// A body that counts its data bytes into [`TransferStats`].
+pub struct CountingBody {
+    inner: AxumBody,
+    stats: Arc<TransferStats>,
+    kind: BodyKind,
+}
+
+impl CountingBody {
+    /// Wrap `inner`, counting its frames as the `kind` body
+    pub fn wrap(inner: AxumBody, stats: Arc<TransferStats>, kind: BodyKind) -> AxumBody {
+        AxumBody::new(Self { inner, stats, kind })
+    }
+}
+
+impl HttpBody for CountingBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &poll
+            && let Some(data) = frame.data_ref()
+        {
+            self.stats
+                .counter(self.kind)
+                .fetch_add(data.len() as u64, Ordering::Relaxed);
+        }
+        poll
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+    use futures_util::stream;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_counts_streamed_bytes() {
+        let stats = TransferStats::downstream("req-1", "http");
+
+        let body = CountingBody::wrap(AxumBody::from("hello"), stats.clone(), BodyKind::Response);
+        assert_eq!(body.size_hint().exact(), Some(5));
+        to_bytes(body, usize::MAX).await.unwrap();
+
+        let chunks = stream::iter(["ab", "cde", ""].map(Ok::<_, axum::Error>));
+        let body = CountingBody::wrap(
+            AxumBody::from_stream(chunks),
+            stats.clone(),
+            BodyKind::Request,
+        );
+        assert_eq!(to_bytes(body, usize::MAX).await.unwrap(), "abcde");
+
+        assert_eq!(stats.request_bytes(), 5);
+        assert_eq!(stats.response_bytes(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_route_byte_counters() {
+        let stats = TransferStats::upstream("/transfer-test");
+        let body = CountingBody::wrap(AxumBody::from("ping"), stats.clone(), BodyKind::Request);
+        to_bytes(body, usize::MAX).await.unwrap();
+        let body = CountingBody::wrap(AxumBody::from("pong!"), stats, BodyKind::Response);
+        to_bytes(body, usize::MAX).await.unwrap();
+
+        let exposition = crate::metrics::route_bytes_exposition();
+        assert!(
+            exposition.contains(
+                "axon_request_bytes_total{route=\"/transfer-test\",direction=\"out\"} 4\n"
+            )
+        );
+        assert!(
+            exposition.contains(
+                "axon_response_bytes_total{route=\"/transfer-test\",direction=\"in\"} 5\n"
+            )
+        );
+    }
+}
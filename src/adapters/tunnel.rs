@@ -0,0 +1,317 @@
+//! Byte tunnels for upgraded connections and `CONNECT`.
+//!
+//! Once both the client and the backend have switched away from HTTP, the
+//! gateway stops interpreting the traffic and copies bytes between the two
+//! connections. A tunnel ends when either side closes, when it has been idle
+//! or open for longer than its route allows, or when a reload removes the
+//! backend.
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use http::{Method, Request, Version, header};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_util::sync::CancellationToken;
+
+use crate::config::TunnelConfig;
+
+/// What a request asks a route's tunnel to carry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TunnelRequest {
+    /// `CONNECT`: a TCP connection to the selected target
+    Connect,
+    /// An `Upgrade` to this protocol, negotiated with the backend
+    Upgrade(String),
+}
+
+/// The tunnel `req` asks for, if `tunnel` allows it. Only HTTP/1.1 can
+/// upgrade a connection; WebSocket upgrades are left to the normal proxy
+/// path unless listed explicitly.
+pub fn requested_tunnel<B>(tunnel: &TunnelConfig, req: &Request<B>) -> Option<TunnelRequest> {
+    if req.version() != Version::HTTP_11 {
+        return None;
+    }
+    if req.method() == Method::CONNECT {
+        return tunnel.connect.then_some(TunnelRequest::Connect);
+    }
+    let connection_upgrade = req
+        .headers()
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+    if !connection_upgrade {
+        return None;
+    }
+    let protocol = req
+        .headers()
+        .get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(str::trim)
+        .filter(|p| !p.is_empty())?;
+    let name = protocol.split('/').next().unwrap_or(protocol);
+    tunnel
+        .protocols
+        .iter()
+        .any(|allowed| {
+            (allowed == "*" && !name.eq_ignore_ascii_case("websocket"))
+                || allowed.eq_ignore_ascii_case(protocol)
+                || allowed.eq_ignore_ascii_case(name)
+        })
+        .then(|| TunnelRequest::Upgrade(protocol.to_string()))
+}
+
+/// When a tunnel is closed by the gateway
+#[derive(Debug, Clone, Copy)]
+pub struct TunnelLimits {
+    pub idle_timeout: Duration,
+    pub max_session: Option<Duration>,
+}
+
+impl From<&TunnelConfig> for TunnelLimits {
+    fn from(config: &TunnelConfig) -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(config.idle_timeout_secs),
+            max_session: config.max_session_secs.map(Duration::from_secs),
+        }
+    }
+}
+
+/// Copy bytes between `client` and `backend` until the tunnel ends, and
+/// return why it did: `closed`, `error`, `idle_timeout`, `session_expired`
+/// or `backend_removed`.
+pub async fn splice<C, B>(
+    client: C,
+    backend: B,
+    route: &str,
+    limits: TunnelLimits,
+    removal: Option<CancellationToken>,
+) -> &'static str
+where
+    C: AsyncRead + AsyncWrite + Unpin,
+    B: AsyncRead + AsyncWrite + Unpin,
+{
+    let activity = Arc::new(Activity::new());
+    let mut client = Metered {
+        inner: client,
+        activity: activity.clone(),
+        upstream: true,
+    };
+    let mut backend = Metered {
+        inner: backend,
+        activity: activity.clone(),
+        upstream: false,
+    };
+    crate::metrics::record_tunnel_opened(route);
+
+    let idle = async {
+        loop {
+            let idle_for = activity.idle_for();
+            if idle_for >= limits.idle_timeout {
+                break;
+            }
+            tokio::time::sleep(limits.idle_timeout - idle_for).await;
+        }
+    };
+    let session_expired = async {
+        match limits.max_session {
+            Some(max_session) => tokio::time::sleep(max_session).await,
+            None => std::future::pending().await,
+        }
+    };
+    let backend_removed = async {
+        match &removal {
+            Some(token) => token.cancelled().await,
+            None => std::future::pending().await,
+        }
+    };
+
+    let reason = tokio::select! {
+        result = tokio::io::copy_bidirectional(&mut client, &mut backend) => match result {
+            Ok(_) => "closed",
+            Err(e) => {
+                tracing::debug!(error = %e, route, "tunnel failed");
+                "error"
+            }
+        },
+        () = idle => "idle_timeout",
+        () = session_expired => "session_expired",
+        () = backend_removed => "backend_removed",
+    };
+    crate::metrics::record_tunnel_closed(
+        route,
+        reason,
+        activity.upstream.load(Ordering::Relaxed),
+        activity.downstream.load(Ordering::Relaxed),
+    );
+    reason
+}
+
+/// Bytes carried by a tunnel, and when it last carried any
+struct Activity {
+    started: Instant,
+    /// Milliseconds after `started` of the last read on either side
+    last: AtomicU64,
+    upstream: AtomicU64,
+    downstream: AtomicU64,
+}
+
+impl Activity {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            last: AtomicU64::new(0),
+            upstream: AtomicU64::new(0),
+            downstream: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, upstream: bool, bytes: u64) {
+        let counter = if upstream {
+            &self.upstream
+        } else {
+            &self.downstream
+        };
+        counter.fetch_add(bytes, Ordering::Relaxed);
+        self.last
+            .store(self.started.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.started
+            .elapsed()
+            .saturating_sub(Duration::from_millis(self.last.load(Ordering::Relaxed)))
+    }
+}
+
+/// One side of a tunnel, counting the bytes read from it
+struct Metered<S> {
+    inner: S,
+    activity: Arc<Activity>,
+    /// Whether this is the client side, whose bytes travel upstream
+    upstream: bool,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Metered<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = buf.filled().len() - before;
+        if read > 0 {
+            self.activity.record(self.upstream, read as u64);
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Metered<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[test]
+    fn test_requested_tunnel() {
+        let tunnel = TunnelConfig {
+            protocols: vec!["h2c".to_string(), "custom/2".to_string()],
+            connect: true,
+            ..TunnelConfig::default()
+        };
+        let upgrade = |protocol: &str| {
+            Request::get("/")
+                .header(header::CONNECTION, "keep-alive, Upgrade")
+                .header(header::UPGRADE, protocol)
+                .body(())
+                .unwrap()
+        };
+
+        assert_eq!(
+            requested_tunnel(&tunnel, &upgrade("H2C")),
+            Some(TunnelRequest::Upgrade("H2C".to_string()))
+        );
+        assert!(requested_tunnel(&tunnel, &upgrade("custom/1")).is_none());
+        assert!(requested_tunnel(&tunnel, &upgrade("websocket")).is_none());
+        let connect = Request::connect("db.internal:5432").body(()).unwrap();
+        assert_eq!(
+            requested_tunnel(&tunnel, &connect),
+            Some(TunnelRequest::Connect)
+        );
+
+        // `*` allows any protocol but WebSocket, which has its own routes
+        let any = TunnelConfig {
+            protocols: vec!["*".to_string()],
+            ..TunnelConfig::default()
+        };
+        assert!(requested_tunnel(&any, &upgrade("custom/1")).is_some());
+        assert!(requested_tunnel(&any, &upgrade("websocket")).is_none());
+        assert!(requested_tunnel(&any, &connect).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_splice_copies_bytes_until_idle() {
+        let (client, mut client_peer) = tokio::io::duplex(64);
+        let (backend, mut backend_peer) = tokio::io::duplex(64);
+        let limits = TunnelLimits {
+            idle_timeout: Duration::from_millis(100),
+            max_session: None,
+        };
+        let tunnel =
+            tokio::spawn(async move { splice(client, backend, "/splice", limits, None).await });
+
+        client_peer.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        backend_peer.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        backend_peer.write_all(b"pong!").await.unwrap();
+        let mut buf = [0u8; 5];
+        client_peer.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"pong!");
+
+        assert_eq!(tunnel.await.unwrap(), "idle_timeout");
+        let exposition = crate::metrics::tunnel_exposition();
+        assert!(exposition.contains(r#"axon_tunnels_active{route="/splice"} 0"#));
+        assert!(
+            exposition
+                .contains(r#"axon_tunnels_closed_total{route="/splice",reason="idle_timeout"} 1"#)
+        );
+        assert!(
+            exposition
+                .contains(r#"axon_tunnel_bytes_total{route="/splice",direction="upstream"} 4"#)
+        );
+        assert!(
+            exposition
+                .contains(r#"axon_tunnel_bytes_total{route="/splice",direction="downstream"} 5"#)
+        );
+    }
+}
@@ -0,0 +1,443 @@
+//! File upload policies for `multipart/form-data` requests.
+//!
+//! A policy buffers the form (up to `max_body_bytes`), splits it into parts
+//! and checks every file against the configured count, size, media type and
+//! extension limits before the request reaches the backend. Files that pass
+//! can then be sent to an ICAP antivirus scanner.
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    http::{StatusCode, Uri},
+    middleware::{Next, from_fn},
+    response::Response,
+};
+use eyre::WrapErr;
+use thiserror::Error;
+use tower::{Layer, util::BoxCloneSyncService};
+
+use crate::{
+    adapters::{
+        icap::{IcapClient, ScanVerdict},
+        middleware::MiddlewareRegistry,
+    },
+    config::models::{UPLOAD_POLICY_MIDDLEWARE_PREFIX, UploadPolicyConfig, UploadScanConfig},
+    core::{
+        error_response,
+        multipart::{self, Part},
+    },
+    metrics,
+};
+
+/// Why an upload was refused
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum UploadRejection {
+    #[error("Too many files (at most {max} allowed)")]
+    TooManyFiles { max: usize },
+    #[error("File '{filename}' is larger than {max} bytes")]
+    FileTooLarge { filename: String, max: u64 },
+    #[error("File '{filename}' has a disallowed content type '{content_type}'")]
+    ContentTypeNotAllowed {
+        filename: String,
+        content_type: String,
+    },
+    #[error("File '{filename}' has a blocked extension '{extension}'")]
+    ExtensionBlocked { filename: String, extension: String },
+}
+
+impl UploadRejection {
+    /// Status answered for the rejection: 413 for size limits, 415 for type
+    /// and extension limits
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::TooManyFiles { .. } | Self::FileTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::ContentTypeNotAllowed { .. } | Self::ExtensionBlocked { .. } => {
+                StatusCode::UNSUPPORTED_MEDIA_TYPE
+            }
+        }
+    }
+}
+
+/// An [`UploadPolicyConfig`] with its lists normalized for matching.
+pub struct UploadPolicy {
+    allowed_content_types: Vec<String>,
+    max_file_bytes: Option<u64>,
+    max_files: Option<usize>,
+    blocked_extensions: Vec<String>,
+    max_body_bytes: usize,
+    scanner: Option<UploadScanner>,
+}
+
+impl UploadPolicy {
+    /// Build a policy from configuration.
+    pub fn new(config: &UploadPolicyConfig) -> eyre::Result<Self> {
+        Ok(Self {
+            allowed_content_types: config
+                .allowed_content_types
+                .iter()
+                .map(|media_type| media_type.trim().to_ascii_lowercase())
+                .collect(),
+            max_file_bytes: config.max_file_bytes,
+            max_files: config.max_files,
+            blocked_extensions: config
+                .blocked_extensions
+                .iter()
+                .map(|extension| {
+                    extension
+                        .trim()
+                        .trim_start_matches('.')
+                        .to_ascii_lowercase()
+                })
+                .collect(),
+            max_body_bytes: config.max_body_bytes,
+            scanner: config.scan.as_ref().map(UploadScanner::new).transpose()?,
+        })
+    }
+
+    /// Check the files of a parsed form.
+    pub fn check(&self, parts: &[Part<'_>]) -> Result<(), UploadRejection> {
+        let files: Vec<&Part<'_>> = parts.iter().filter(|part| part.is_file()).collect();
+        if let Some(max) = self.max_files
+            && files.len() > max
+        {
+            return Err(UploadRejection::TooManyFiles { max });
+        }
+        for file in files {
+            let filename = file.filename.clone().unwrap_or_default();
+            if let Some(max) = self.max_file_bytes
+                && file.data.len() as u64 > max
+            {
+                return Err(UploadRejection::FileTooLarge { filename, max });
+            }
+            // RFC 7578: files without a Content-Type are application/octet-stream
+            let content_type = file
+                .content_type
+                .as_deref()
+                .and_then(|value| value.split(';').next())
+                .unwrap_or("application/octet-stream")
+                .trim()
+                .to_ascii_lowercase();
+            if !self.allowed_content_types.is_empty()
+                && !self
+                    .allowed_content_types
+                    .iter()
+                    .any(|allowed| media_type_matches(allowed, &content_type))
+            {
+                return Err(UploadRejection::ContentTypeNotAllowed {
+                    filename,
+                    content_type,
+                });
+            }
+            // Every extension counts, so `shell.php.jpg` is caught by `php`
+            let basename = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+            if let Some(extension) = basename
+                .split('.')
+                .skip(1)
+                .map(str::to_ascii_lowercase)
+                .find(|extension| self.blocked_extensions.contains(extension))
+            {
+                return Err(UploadRejection::ExtensionBlocked {
+                    filename,
+                    extension,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Antivirus scanning of uploaded files.
+struct UploadScanner {
+    client: IcapClient,
+    max_file_bytes: u64,
+    fail_open: bool,
+}
+
+impl UploadScanner {
+    fn new(config: &UploadScanConfig) -> eyre::Result<Self> {
+        let timeout = Duration::from_secs(config.timeout_secs);
+        Ok(Self {
+            client: IcapClient::new(&config.icap_url, timeout)?,
+            max_file_bytes: config.max_file_bytes,
+            fail_open: config.fail_open,
+        })
+    }
+
+    /// Scan every file of a form; `Err` carries the response to send instead.
+    async fn scan(&self, parts: &[Part<'_>], uri: &Uri) -> Result<(), Response> {
+        for file in parts.iter().filter(|part| part.is_file()) {
+            let filename = file.filename.as_deref().unwrap_or_default();
+            let started = Instant::now();
+            let result = if file.data.len() as u64 > self.max_file_bytes {
+                Err(format!("file is larger than {} bytes", self.max_file_bytes))
+            } else {
+                let content_type = file
+                    .content_type
+                    .as_deref()
+                    .unwrap_or("application/octet-stream");
+                self.client
+                    .scan(filename, content_type, file.data)
+                    .await
+                    .map_err(|e| e.to_string())
+            };
+            let outcome = match &result {
+                Ok(ScanVerdict::Clean) => "clean",
+                Ok(ScanVerdict::Infected(_)) => "infected",
+                Err(_) => "error",
+            };
+            metrics::record_upload_scan(outcome, started.elapsed());
+
+            match result {
+                Ok(ScanVerdict::Clean) => {}
+                Ok(ScanVerdict::Infected(threat)) => {
+                    tracing::warn!(
+                        uri = %uri,
+                        filename,
+                        threat = threat.as_deref().unwrap_or("unknown"),
+                        "Rejecting upload: virus scanner found a threat"
+                    );
+                    return Err(error_response(
+                        StatusCode::FORBIDDEN,
+                        format!("File '{filename}' was rejected by the virus scanner"),
+                    ));
+                }
+                Err(e) if self.fail_open => {
+                    tracing::warn!(uri = %uri, filename, "Upload not scanned (fail open): {e}");
+                }
+                Err(e) => {
+                    tracing::warn!(uri = %uri, filename, "Rejecting upload, scan failed: {e}");
+                    return Err(error_response(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "Upload could not be scanned",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Match `type/subtype` against an allowed `type/subtype`, `type/*` or `*/*`.
+fn media_type_matches(allowed: &str, media_type: &str) -> bool {
+    match allowed.strip_suffix("/*") {
+        Some("*") => true,
+        Some(prefix) => media_type
+            .split_once('/')
+            .is_some_and(|(kind, _)| kind == prefix),
+        None => allowed == media_type,
+    }
+}
+
+/// Check the files of a form request before passing it on.
+async fn upload_policy_middleware(req: Request, next: Next, policy: Arc<UploadPolicy>) -> Response {
+    let Some(boundary) = multipart::form_boundary(req.headers()) else {
+        return next.run(req).await;
+    };
+    let (parts, body) = req.into_parts();
+    let Ok(bytes) = to_bytes(body, policy.max_body_bytes).await else {
+        return error_response(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large");
+    };
+    let form = match multipart::parse(&bytes, &boundary) {
+        Ok(form) => form,
+        Err(e) => {
+            tracing::debug!(uri = %parts.uri, "Rejecting upload: {e}");
+            return error_response(StatusCode::BAD_REQUEST, "Malformed multipart body");
+        }
+    };
+    if let Err(rejection) = policy.check(&form) {
+        tracing::warn!(uri = %parts.uri, "Rejecting upload: {rejection}");
+        return error_response(rejection.status(), rejection.to_string());
+    }
+    if let Some(scanner) = &policy.scanner
+        && let Err(response) = scanner.scan(&form, &parts.uri).await
+    {
+        return response;
+    }
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+/// Register every configured policy as `upload_policy:<name>`.
+pub fn register_upload_policies(
+    registry: &mut MiddlewareRegistry,
+    policies: &HashMap<String, UploadPolicyConfig>,
+) -> eyre::Result<()> {
+    for (name, config) in policies {
+        let policy = Arc::new(
+            UploadPolicy::new(config).wrap_err_with(|| format!("Upload policy '{name}'"))?,
+        );
+        registry.register(
+            format!("{UPLOAD_POLICY_MIDDLEWARE_PREFIX}{name}"),
+            move |inner, _| {
+                let policy = policy.clone();
+                BoxCloneSyncService::new(
+                    from_fn(move |req, next| upload_policy_middleware(req, next, policy.clone()))
+                        .layer(inner),
+                )
+            },
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, http::header, middleware, routing::post};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn form(files: &[(&str, &str, &str)]) -> Request {
+        let mut body = String::from(
+            "--b\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nholiday\r\n",
+        );
+        for (filename, content_type, data) in files {
+            body.push_str(&format!(
+                "--b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{filename}\"\r\n\
+                 Content-Type: {content_type}\r\n\r\n{data}\r\n"
+            ));
+        }
+        body.push_str("--b--\r\n");
+        Request::post("/upload")
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=b")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    fn app(config: &UploadPolicyConfig) -> Router {
+        let policy = Arc::new(UploadPolicy::new(config).unwrap());
+        Router::new()
+            .route(
+                "/upload",
+                post(|body: String| async move { body.len().to_string() }),
+            )
+            .layer(middleware::from_fn(move |req, next| {
+                upload_policy_middleware(req, next, policy.clone())
+            }))
+    }
+
+    #[tokio::test]
+    async fn test_upload_policy() {
+        let app = app(&UploadPolicyConfig {
+            allowed_content_types: vec!["image/*".to_string(), "application/pdf".to_string()],
+            max_file_bytes: Some(8),
+            max_files: Some(2),
+            blocked_extensions: vec![".PHP".to_string()],
+            max_body_bytes: 1024,
+            scan: None,
+        });
+        let status = |req: Request| {
+            let app = app.clone();
+            async move { app.oneshot(req).await.unwrap().status() }
+        };
+
+        assert_eq!(
+            status(form(&[
+                ("a.png", "image/png", "png"),
+                ("b.pdf", "application/pdf", "pdf")
+            ]))
+            .await,
+            StatusCode::OK
+        );
+        let plain = Request::post("/upload")
+            .body(Body::from("x".repeat(2048)))
+            .unwrap();
+        assert_eq!(status(plain).await, StatusCode::OK, "not a form");
+
+        let three = [("a.png", "image/png", "1"); 3];
+        assert_eq!(status(form(&three)).await, StatusCode::PAYLOAD_TOO_LARGE);
+        let large = [("a.png", "image/png", "123456789")];
+        assert_eq!(status(form(&large)).await, StatusCode::PAYLOAD_TOO_LARGE);
+        let svg = [("a.svg", "text/html", "<svg>")];
+        assert_eq!(status(form(&svg)).await, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let double = [("shell.PHP.png", "image/png", "<?php")];
+        assert_eq!(
+            status(form(&double)).await,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+
+        let broken = Request::post("/upload")
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=b")
+            .body(Body::from("--b\r\n\r\nno end"))
+            .unwrap();
+        assert_eq!(status(broken).await, StatusCode::BAD_REQUEST);
+    }
+
+    /// An ICAP scanner that flags content containing `EICAR`.
+    async fn scanner() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut reader = tokio::io::BufReader::new(stream);
+                    let mut request = String::new();
+                    while !request.ends_with("\r\n0\r\n\r\n") {
+                        if reader.read_line(&mut request).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                    }
+                    let response = if request.contains("EICAR") {
+                        "ICAP/1.0 200 OK\r\nX-Virus-ID: Eicar-Test-Signature\r\n\r\n"
+                    } else {
+                        "ICAP/1.0 204 No Content\r\n\r\n"
+                    };
+                    let _ = reader.get_mut().write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        format!("icap://{addr}/avscan")
+    }
+
+    #[tokio::test]
+    async fn test_upload_scanning() {
+        let scan = UploadScanConfig {
+            icap_url: scanner().await,
+            timeout_secs: 5,
+            max_file_bytes: 16,
+            fail_open: false,
+        };
+        let config = |scan: UploadScanConfig| UploadPolicyConfig {
+            scan: Some(scan),
+            ..UploadPolicyConfig::default()
+        };
+        let status = |app: Router, files: &'static [(&'static str, &'static str, &'static str)]| async move {
+            app.oneshot(form(files)).await.unwrap().status()
+        };
+
+        let scanned = app(&config(scan.clone()));
+        let clean = &[("a.txt", "text/plain", "hello")];
+        assert_eq!(status(scanned.clone(), clean).await, StatusCode::OK);
+        let infected = &[
+            ("a.txt", "text/plain", "hello"),
+            ("e.com", "application/octet-stream", "EICAR"),
+        ];
+        assert_eq!(
+            status(scanned.clone(), infected).await,
+            StatusCode::FORBIDDEN
+        );
+        let large = &[("big.bin", "application/octet-stream", "0123456789abcdefg")];
+        assert_eq!(
+            status(scanned, large).await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        let unreachable = UploadScanConfig {
+            icap_url: "icap://127.0.0.1:1/avscan".to_string(),
+            ..scan
+        };
+        let closed = app(&config(unreachable.clone()));
+        assert_eq!(status(closed, clean).await, StatusCode::SERVICE_UNAVAILABLE);
+        let open = app(&config(UploadScanConfig {
+            fail_open: true,
+            ..unreachable
+        }));
+        assert_eq!(status(open, clean).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,577 @@
+//! Usage metering for billing API consumers.
+//!
+//! The `usage` middleware reports every request it sees (consumer, route,
+//! status, latency and body bytes) once the response body is finished with.
+//! A background task sums the reports per consumer and route and, every
+//! flush interval, turns the sums into [`UsageRecord`]s for the sink.
+//! Records stay queued until the sink accepts them and are retried on the
+//! next flush otherwise, so delivery is at least once; each record carries
+//! a unique `id` for consumers to drop duplicates.
+use std::{
+    collections::{HashMap, VecDeque},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Body as AxumBody,
+    extract::Request,
+    http::{HeaderName, header},
+    middleware::{Next, from_fn},
+    response::Response,
+};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use eyre::{Result, WrapErr, eyre};
+use hyper::body::{Body as HttpBody, Frame, SizeHint};
+use serde::Serialize;
+use tokio::{
+    fs::{File, OpenOptions},
+    io::AsyncWriteExt,
+    sync::mpsc,
+};
+use tower::{Layer, util::BoxCloneSyncService};
+use uuid::Uuid;
+
+use crate::{
+    adapters::{middleware::MiddlewareRegistry, transfer::TransferStats},
+    config::models::{USAGE_MIDDLEWARE, UsageConfig, UsageSinkConfig},
+    core::rbac::AuthenticatedUser,
+    ports::http_client::HttpClient,
+};
+
+/// Consumer of requests without a user or key header
+const ANONYMOUS: &str = "anonymous";
+
+/// One metered request
+#[derive(Debug, Clone)]
+struct UsageSample {
+    key: String,
+    route: Arc<str>,
+    status: u16,
+    latency: Duration,
+    bytes_in: u64,
+    bytes_out: u64,
+}
+
+/// Sums for one consumer and route within a flush window
+#[derive(Debug, Default)]
+struct UsageTotals {
+    requests: u64,
+    client_errors: u64,
+    server_errors: u64,
+    bytes_in: u64,
+    bytes_out: u64,
+    latency_ms_total: u64,
+    latency_ms_max: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, sample: &UsageSample) {
+        let latency_ms = sample.latency.as_millis() as u64;
+        self.requests += 1;
+        self.client_errors += u64::from((400..500).contains(&sample.status));
+        self.server_errors += u64::from(sample.status >= 500);
+        self.bytes_in += sample.bytes_in;
+        self.bytes_out += sample.bytes_out;
+        self.latency_ms_total += latency_ms;
+        self.latency_ms_max = self.latency_ms_max.max(latency_ms);
+    }
+}
+
+/// Usage of one consumer on one route during one flush window.
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageRecord {
+    /// Unique per record; repeated when a record is delivered again
+    pub id: Uuid,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub key: String,
+    pub route: String,
+    pub requests: u64,
+    /// `4xx` responses
+    pub client_errors: u64,
+    /// `5xx` responses
+    pub server_errors: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub latency_ms_total: u64,
+    pub latency_ms_max: u64,
+}
+
+/// Handle for metering requests; cheap to clone.
+#[derive(Clone)]
+pub struct UsageMeter {
+    tx: mpsc::Sender<UsageSample>,
+    key_header: Option<HeaderName>,
+}
+
+impl UsageMeter {
+    /// Open the configured sink and start the aggregating task, which
+    /// flushes a last time once every handle is dropped.
+    pub async fn start(config: &UsageConfig, http_client: Arc<dyn HttpClient>) -> Result<Self> {
+        let sink_config = config
+            .sink
+            .as_ref()
+            .ok_or_else(|| eyre!("middleware.usage.sink is required"))?;
+        let key_header = config
+            .key_header
+            .as_deref()
+            .map(|name| {
+                HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| eyre!("Invalid header name '{name}'"))
+            })
+            .transpose()?;
+        let sink = Sink::open(sink_config, http_client).await?;
+        let (tx, rx) = mpsc::channel(config.queue_size.max(1));
+        tokio::spawn(run_meter(
+            sink,
+            rx,
+            Duration::from_secs(config.flush_interval_secs.max(1)),
+            config.max_pending_records.max(1),
+        ));
+        Ok(Self { tx, key_header })
+    }
+
+    /// The consumer of a request: the authenticated user, else the key
+    /// header's value.
+    fn key(&self, req: &Request) -> String {
+        if let Some(user) = req.extensions().get::<AuthenticatedUser>() {
+            return user.name.clone();
+        }
+        self.key_header
+            .as_ref()
+            .and_then(|name| req.headers().get(name))
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty())
+            .unwrap_or(ANONYMOUS)
+            .to_string()
+    }
+
+    fn record(&self, sample: UsageSample) {
+        if self.tx.try_send(sample).is_err() {
+            crate::metrics::record_usage_dropped("queue_full", 1);
+        }
+    }
+}
+
+/// A response body that reports its request's usage when dropped.
+struct MeteredBody {
+    inner: AxumBody,
+    meter: UsageMeter,
+    sample: Option<UsageSample>,
+    /// Counts the request body, which is read before this one is dropped
+    transfer: Option<Arc<TransferStats>>,
+}
+
+impl HttpBody for MeteredBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &poll
+            && let Some(data) = frame.data_ref()
+            && let Some(sample) = &mut self.sample
+        {
+            sample.bytes_out += data.len() as u64;
+        }
+        poll
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for MeteredBody {
+    fn drop(&mut self) {
+        if let Some(mut sample) = self.sample.take() {
+            sample.bytes_in = self
+                .transfer
+                .as_ref()
+                .map_or(0, |transfer| transfer.request_bytes());
+            self.meter.record(sample);
+        }
+    }
+}
+
+/// Meter the request; latency is measured to the response head.
+async fn usage_middleware(
+    req: Request,
+    next: Next,
+    meter: UsageMeter,
+    route: Arc<str>,
+) -> Response {
+    let start = Instant::now();
+    let key = meter.key(&req);
+    let transfer = req.extensions().get::<Arc<TransferStats>>().cloned();
+    let response = next.run(req).await;
+    let sample = UsageSample {
+        key,
+        route,
+        status: response.status().as_u16(),
+        latency: start.elapsed(),
+        bytes_in: 0,
+        bytes_out: 0,
+    };
+    response.map(|inner| {
+        AxumBody::new(MeteredBody {
+            inner,
+            meter,
+            sample: Some(sample),
+            transfer,
+        })
+    })
+}
+
+/// Start metering and register the `usage` middleware when configured.
+pub async fn register_usage(
+    registry: &mut MiddlewareRegistry,
+    config: Option<&UsageConfig>,
+    http_client: Arc<dyn HttpClient>,
+) -> Result<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+    let meter = UsageMeter::start(config, http_client).await?;
+    registry.register(USAGE_MIDDLEWARE, move |inner, ctx| {
+        let meter = meter.clone();
+        let route: Arc<str> = Arc::from(ctx.route_prefix);
+        BoxCloneSyncService::new(
+            from_fn(move |req, next| usage_middleware(req, next, meter.clone(), route.clone()))
+                .layer(inner),
+        )
+    });
+    Ok(())
+}
+
+enum Sink {
+    File(File),
+    Webhook {
+        client: Arc<dyn HttpClient>,
+        url: String,
+        headers: Vec<(String, String)>,
+        batch_size: usize,
+    },
+}
+
+impl Sink {
+    async fn open(config: &UsageSinkConfig, client: Arc<dyn HttpClient>) -> Result<Self> {
+        match config {
+            UsageSinkConfig::File { path } => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await
+                    .wrap_err_with(|| format!("Failed to open usage file '{path}'"))?;
+                Ok(Sink::File(file))
+            }
+            UsageSinkConfig::Webhook {
+                url,
+                headers,
+                batch_size,
+            } => Ok(Sink::Webhook {
+                client,
+                url: url.clone(),
+                headers: headers
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+                batch_size: (*batch_size).max(1),
+            }),
+        }
+    }
+
+    /// Most records written at once
+    fn batch_size(&self) -> usize {
+        match self {
+            Sink::File(_) => usize::MAX,
+            Sink::Webhook { batch_size, .. } => *batch_size,
+        }
+    }
+
+    async fn write(&mut self, batch: &[UsageRecord]) -> Result<()> {
+        match self {
+            Sink::File(file) => {
+                let mut out = Vec::new();
+                for record in batch {
+                    serde_json::to_writer(&mut out, record)?;
+                    out.push(b'\n');
+                }
+                file.write_all(&out).await?;
+                file.flush().await?;
+            }
+            Sink::Webhook {
+                client,
+                url,
+                headers,
+                ..
+            } => {
+                let mut request =
+                    Request::post(url.as_str()).header(header::CONTENT_TYPE, "application/json");
+                for (name, value) in headers.iter() {
+                    request = request.header(name.as_str(), value.as_str());
+                }
+                let request = request.body(AxumBody::from(serde_json::to_vec(batch)?))?;
+                let response = client.send_request(request).await?;
+                if !response.status().is_success() {
+                    return Err(eyre!("Webhook returned status {}", response.status()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sum samples and export them every `interval` until every handle is gone.
+async fn run_meter(
+    mut sink: Sink,
+    mut rx: mpsc::Receiver<UsageSample>,
+    interval: Duration,
+    max_pending: usize,
+) {
+    let mut totals: HashMap<(String, Arc<str>), UsageTotals> = HashMap::new();
+    let mut pending = VecDeque::new();
+    let mut window_start = Utc::now();
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            sample = rx.recv() => match sample {
+                Some(sample) => totals
+                    .entry((sample.key.clone(), sample.route.clone()))
+                    .or_default()
+                    .add(&sample),
+                None => break,
+            },
+            _ = ticker.tick() => {
+                window_start = close_window(&mut totals, &mut pending, window_start, max_pending);
+                export(&mut sink, &mut pending).await;
+            }
+        }
+    }
+    close_window(&mut totals, &mut pending, window_start, max_pending);
+    export(&mut sink, &mut pending).await;
+}
+
+/// Turn the window's totals into pending records; returns the next window's
+/// start.
+fn close_window(
+    totals: &mut HashMap<(String, Arc<str>), UsageTotals>,
+    pending: &mut VecDeque<UsageRecord>,
+    window_start: DateTime<Utc>,
+    max_pending: usize,
+) -> DateTime<Utc> {
+    let window_end = Utc::now();
+    for ((key, route), totals) in totals.drain() {
+        pending.push_back(UsageRecord {
+            id: Uuid::new_v4(),
+            window_start,
+            window_end,
+            key,
+            route: route.to_string(),
+            requests: totals.requests,
+            client_errors: totals.client_errors,
+            server_errors: totals.server_errors,
+            bytes_in: totals.bytes_in,
+            bytes_out: totals.bytes_out,
+            latency_ms_total: totals.latency_ms_total,
+            latency_ms_max: totals.latency_ms_max,
+        });
+    }
+    if pending.len() > max_pending {
+        let excess = pending.len() - max_pending;
+        pending.drain(..excess);
+        crate::metrics::record_usage_dropped("pending_full", excess as u64);
+    }
+    window_end
+}
+
+/// Write pending records in batches, keeping the rest when the sink fails.
+async fn export(sink: &mut Sink, pending: &mut VecDeque<UsageRecord>) {
+    while !pending.is_empty() {
+        let batch: Vec<_> = pending.iter().take(sink.batch_size()).cloned().collect();
+        if let Err(e) = sink.write(&batch).await {
+            tracing::warn!(
+                error = %e,
+                pending = pending.len(),
+                "Failed to export usage records; retrying on the next flush"
+            );
+            return;
+        }
+        pending.drain(..batch.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use axum::{Router, http::StatusCode, middleware, response::IntoResponse, routing::any};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::ports::http_client::{HealthCheckOptions, HttpClientError};
+
+    /// Webhook that fails its first request and keeps the bodies of the rest
+    #[derive(Default)]
+    struct FlakyWebhook {
+        calls: AtomicUsize,
+        received: Mutex<Vec<serde_json::Value>>,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpClient for FlakyWebhook {
+        async fn send_request(
+            &self,
+            req: hyper::Request<AxumBody>,
+        ) -> Result<hyper::Response<AxumBody>, HttpClientError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Err(HttpClientError::ConnectionError("refused".to_string()));
+            }
+            let bytes = axum::body::to_bytes(req.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let records: Vec<serde_json::Value> = serde_json::from_slice(&bytes).unwrap();
+            self.received.lock().unwrap().extend(records);
+            Ok(hyper::Response::new(AxumBody::empty()))
+        }
+
+        async fn health_check(
+            &self,
+            _url: &str,
+            _options: &HealthCheckOptions,
+            _timeout_secs: u64,
+        ) -> Result<bool, HttpClientError> {
+            Ok(true)
+        }
+    }
+
+    fn app(meter: UsageMeter) -> Router {
+        Router::new()
+            .route(
+                "/{*path}",
+                any(|req: Request| async move {
+                    if req.uri().path() == "/missing" {
+                        return (StatusCode::NOT_FOUND, "").into_response();
+                    }
+                    "hello".into_response()
+                }),
+            )
+            .layer(middleware::from_fn(move |req, next| {
+                usage_middleware(req, next, meter.clone(), Arc::from("/api"))
+            }))
+    }
+
+    async fn call(app: &Router, path: &str, key: Option<&str>) {
+        let mut req = Request::get(path);
+        if let Some(key) = key {
+            req = req.header("x-api-key", key);
+        }
+        let response = app
+            .clone()
+            .oneshot(req.body(AxumBody::empty()).unwrap())
+            .await
+            .unwrap();
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_usage_file_sink() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("usage.jsonl");
+        let config = UsageConfig {
+            key_header: Some("X-Api-Key".to_string()),
+            sink: Some(UsageSinkConfig::File {
+                path: path.to_string_lossy().into_owned(),
+            }),
+            ..UsageConfig::default()
+        };
+        let meter = UsageMeter::start(&config, Arc::new(FlakyWebhook::default()))
+            .await
+            .unwrap();
+        let app = app(meter);
+        call(&app, "/a", Some("key-1")).await;
+        call(&app, "/missing", Some("key-1")).await;
+        call(&app, "/a", None).await;
+        // Dropping the last handle flushes the open window
+        drop(app);
+
+        let mut records = Vec::new();
+        for _ in 0..100 {
+            let contents = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+            records = contents
+                .lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+                .collect();
+            if records.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        records.sort_by_key(|record| record["key"].as_str().unwrap().to_string());
+        assert_eq!(records.len(), 2, "{records:?}");
+        assert_eq!(records[0]["key"], "anonymous");
+        assert_eq!(records[0]["requests"], 1);
+        assert_eq!(records[1]["key"], "key-1");
+        assert_eq!(records[1]["route"], "/api");
+        assert_eq!(records[1]["requests"], 2);
+        assert_eq!(records[1]["client_errors"], 1);
+        assert_eq!(records[1]["bytes_out"], 5);
+    }
+
+    #[tokio::test]
+    async fn test_usage_export_is_retried() {
+        let webhook = Arc::new(FlakyWebhook::default());
+        let config = UsageConfig {
+            flush_interval_secs: 1,
+            sink: Some(UsageSinkConfig::Webhook {
+                url: "http://billing.internal/usage".to_string(),
+                headers: HashMap::new(),
+                batch_size: 100,
+            }),
+            ..UsageConfig::default()
+        };
+        let meter = UsageMeter::start(&config, webhook.clone()).await.unwrap();
+        let app = app(meter);
+        call(&app, "/a", None).await;
+
+        for _ in 0..250 {
+            if webhook.calls.load(Ordering::SeqCst) > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(webhook.calls.load(Ordering::SeqCst), 1);
+        assert!(webhook.received.lock().unwrap().is_empty());
+
+        // The failed window is sent again with the next one
+        call(&app, "/b", None).await;
+        for _ in 0..250 {
+            if !webhook.received.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(webhook.calls.load(Ordering::SeqCst), 2);
+        let received = webhook.received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert!(received.iter().all(|record| record["requests"] == 1));
+    }
+}
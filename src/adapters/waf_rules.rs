@@ -0,0 +1,210 @@
+//! Loading and hot-reloading of external WAF rule files.
+//!
+//! `waf.rules_file` points at a TOML, YAML or JSON file of extra rules (see
+//! [`WafRuleSet`]). It is read when a configuration is applied and then
+//! polled; when its content changes the gateway's WAF engine is rebuilt
+//! without touching the rest of the configuration. A file that fails to
+//! parse or compile is reported and the previous rules stay active.
+use std::{sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use config::{Config, File, FileFormat};
+use eyre::{Context, Result};
+use tokio::task::JoinHandle;
+
+use crate::{
+    adapters::config_providers::http::{document_format, sha256_hex},
+    config::WafRuleSet,
+    core::{GatewayService, waf::LoadedRuleSet},
+};
+
+/// Poll interval used when `rules_poll_interval_secs` is not set.
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 10;
+
+/// Read and parse the rule file at `path`.
+pub async fn load_rule_set(path: &str) -> Result<LoadedRuleSet> {
+    let document = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read WAF rules from {path}"))?;
+    parse_rule_set(path, &document)
+}
+
+fn parse_rule_set(path: &str, document: &str) -> Result<LoadedRuleSet> {
+    let format = document_format(path);
+    let rules: WafRuleSet = if format == FileFormat::Json {
+        serde_json::from_str(document).context("Failed to parse JSON WAF rules")?
+    } else {
+        Config::builder()
+            .add_source(File::from_str(document, format))
+            .build()
+            .context("Failed to build WAF rules")?
+            .try_deserialize()
+            .context("Failed to deserialize WAF rules")?
+    };
+    Ok(LoadedRuleSet {
+        path: path.to_string(),
+        checksum: sha256_hex(document),
+        loaded_at: chrono::Utc::now(),
+        rules,
+    })
+}
+
+/// Give a freshly built gateway its external WAF rules.
+///
+/// Rules already loaded by `previous` from the same file are reused, so a
+/// configuration reload does not re-read an unchanged rule file; otherwise
+/// the file is loaded now.
+pub async fn prepare_waf_rules(
+    gateway: &GatewayService,
+    previous: Option<&GatewayService>,
+) -> Result<()> {
+    let Some(path) = gateway
+        .waf_config()
+        .and_then(|config| config.rules_file.as_deref())
+    else {
+        return Ok(());
+    };
+    if let Some(rule_set) = previous.and_then(|previous| previous.waf_rule_set())
+        && rule_set.path == path
+    {
+        return gateway.apply_waf_rules(rule_set);
+    }
+    let rule_set = load_rule_set(path).await?;
+    gateway.apply_waf_rules(Arc::new(rule_set))
+}
+
+/// Reload the gateway's rule file if its content changed since it was last
+/// applied. Returns the new rule set, or `None` when nothing changed.
+pub async fn reload_waf_rules(gateway: &GatewayService) -> Result<Option<Arc<LoadedRuleSet>>> {
+    let Some(path) = gateway
+        .waf_config()
+        .and_then(|config| config.rules_file.as_deref())
+    else {
+        return Ok(None);
+    };
+    let document = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read WAF rules from {path}"))?;
+    if gateway
+        .waf_rule_set()
+        .is_some_and(|current| current.path == path && current.checksum == sha256_hex(&document))
+    {
+        return Ok(None);
+    }
+    let rule_set = Arc::new(parse_rule_set(path, &document)?);
+    gateway.apply_waf_rules(rule_set.clone())?;
+    Ok(Some(rule_set))
+}
+
+/// Poll the current gateway's rule file and apply changes as they appear.
+pub fn spawn_waf_rules_watcher(gateway: Arc<ArcSwap<GatewayService>>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        // Last reported error, so a broken file is logged once rather than
+        // on every poll
+        let mut last_error: Option<String> = None;
+        loop {
+            let current = gateway.load_full();
+            let interval = current
+                .waf_config()
+                .and_then(|config| config.rules_poll_interval_secs)
+                .unwrap_or(DEFAULT_POLL_INTERVAL_SECS);
+            match reload_waf_rules(&current).await {
+                Ok(Some(rule_set)) => {
+                    tracing::info!(
+                        path = %rule_set.path,
+                        version = rule_set.version(),
+                        "WAF rules reloaded"
+                    );
+                    last_error = None;
+                }
+                Ok(None) => last_error = None,
+                Err(e) => {
+                    let message = format!("{e:#}");
+                    if last_error.as_ref() != Some(&message) {
+                        tracing::error!(
+                            "Failed to reload WAF rules: {message}. Keeping previous rules."
+                        );
+                        last_error = Some(message);
+                    }
+                }
+            }
+            drop(current);
+            tokio::time::sleep(Duration::from_secs(interval.max(1))).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use axum::http::{HeaderMap, Uri};
+
+    use super::*;
+    use crate::config::{ServerConfig, WafConfig};
+
+    #[tokio::test]
+    async fn test_rule_file_reload() {
+        let mut file = tempfile::NamedTempFile::with_suffix(".toml").unwrap();
+        write!(
+            file,
+            r#"
+version = "2026-01"
+
+[[custom_rules]]
+name = "block_admin"
+pattern = "^/wp-admin"
+"#
+        )
+        .unwrap();
+        let path = file.path().to_str().unwrap().to_string();
+
+        let config = ServerConfig {
+            waf: Some(WafConfig {
+                enabled: true,
+                rules_file: Some(path.clone()),
+                ..WafConfig::default()
+            }),
+            ..ServerConfig::default()
+        };
+        let gateway = GatewayService::new(Arc::new(config.clone()));
+        prepare_waf_rules(&gateway, None).await.unwrap();
+
+        let rule_set = gateway.waf_rule_set().unwrap();
+        assert_eq!(rule_set.version(), "2026-01");
+        let blocked = |gateway: &GatewayService, uri: &str| {
+            let uri: Uri = uri.parse().unwrap();
+            gateway
+                .check_waf(&uri, &HeaderMap::new(), None, None)
+                .is_err()
+        };
+        assert!(blocked(&gateway, "/wp-admin/"));
+        assert!(!blocked(&gateway, "/phpmyadmin/"));
+        assert!(reload_waf_rules(&gateway).await.unwrap().is_none());
+
+        // A new configuration reuses the loaded rules
+        let next = GatewayService::new(Arc::new(config));
+        prepare_waf_rules(&next, Some(&gateway)).await.unwrap();
+        assert!(Arc::ptr_eq(&next.waf_rule_set().unwrap(), &rule_set));
+
+        // Changed content replaces the rules
+        std::fs::write(
+            &path,
+            r#"
+[[custom_rules]]
+name = "block_pma"
+pattern = "^/phpmyadmin"
+"#,
+        )
+        .unwrap();
+        let reloaded = reload_waf_rules(&next).await.unwrap().unwrap();
+        assert_eq!(reloaded.version(), &reloaded.checksum[..12]);
+        assert!(!blocked(&next, "/wp-admin/"));
+        assert!(blocked(&next, "/phpmyadmin/"));
+
+        // A broken file keeps the previous rules
+        std::fs::write(&path, "[[custom_rules]]\nname = \"bad\"\npattern = \"(\"\n").unwrap();
+        assert!(reload_waf_rules(&next).await.is_err());
+        assert!(blocked(&next, "/phpmyadmin/"));
+    }
+}
@@ -0,0 +1,614 @@
+//! WebAssembly request/response filters.
+//!
+//! A filter is a WASM module (binary or text format) that exports:
+//!
+//! - `memory`: its linear memory
+//! - `alloc(len: i32) -> i32`: returns a buffer of `len` bytes for the host to
+//!   write the filter input into
+//! - `on_request(ptr: i32, len: i32) -> i64` and/or `on_response(ptr: i32, len: i32) -> i64`
+//!
+//! The input is a JSON document describing the request (`method`, `uri`,
+//! `headers`) or response (`status`, `headers`); `body` is included as base64
+//! when the filter sets `include_body`. Headers are `[name, value]` pairs.
+//!
+//! A phase function returns `0` to continue unchanged, or `(ptr << 32) | len`
+//! of a JSON action in its memory:
+//!
+//! ```json
+//! {
+//!   "set_headers": [["x-filtered", "1"]],
+//!   "remove_headers": ["x-internal"],
+//!   "body": "<base64>",
+//!   "respond": { "status": 403, "headers": [], "body": "<base64>" }
+//! }
+//! ```
+//!
+//! `respond` answers immediately (in the request phase the backend is never
+//! called); otherwise the header and body edits are applied. Every invocation
+//! runs in a fresh instance with its own fuel and memory budget.
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use axum::{
+    body::{Body, Bytes, to_bytes},
+    extract::Request,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    middleware::{Next, from_fn},
+    response::Response,
+};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use eyre::{WrapErr, eyre};
+use serde::{Deserialize, Serialize};
+use tower::{Layer, util::BoxCloneSyncService};
+use wasmtime::{Engine, InstancePre, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::{
+    adapters::middleware::MiddlewareRegistry,
+    config::models::{WASM_MIDDLEWARE_PREFIX, WasmFilterConfig},
+    core::error_response,
+    metrics,
+};
+
+/// Filter hook being invoked.
+#[derive(Debug, Clone, Copy)]
+enum Phase {
+    Request,
+    Response,
+}
+
+impl Phase {
+    fn as_str(self) -> &'static str {
+        match self {
+            Phase::Request => "request",
+            Phase::Response => "response",
+        }
+    }
+
+    fn export(self) -> &'static str {
+        match self {
+            Phase::Request => "on_request",
+            Phase::Response => "on_response",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct FilterInput<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    headers: Vec<(String, String)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct FilterAction {
+    set_headers: Vec<(String, String)>,
+    remove_headers: Vec<String>,
+    body: Option<String>,
+    respond: Option<FilterResponse>,
+}
+
+#[derive(Deserialize)]
+struct FilterResponse {
+    status: u16,
+    #[serde(default)]
+    headers: Vec<(String, String)>,
+    #[serde(default)]
+    body: Option<String>,
+}
+
+/// Header and body changes requested by a filter, already validated.
+#[derive(Default)]
+struct Edits {
+    remove_headers: Vec<HeaderName>,
+    set_headers: Vec<(HeaderName, HeaderValue)>,
+    body: Option<Bytes>,
+}
+
+impl Edits {
+    fn apply(self, headers: &mut HeaderMap, body: Body) -> Body {
+        for name in &self.remove_headers {
+            headers.remove(name);
+        }
+        for (name, value) in self.set_headers {
+            headers.insert(name, value);
+        }
+        match self.body {
+            Some(bytes) => {
+                headers.remove(header::TRANSFER_ENCODING);
+                headers.insert(header::CONTENT_LENGTH, HeaderValue::from(bytes.len()));
+                Body::from(bytes)
+            }
+            None => body,
+        }
+    }
+}
+
+enum FilterVerdict {
+    Continue(Edits),
+    Respond(Response),
+}
+
+impl TryFrom<FilterAction> for FilterVerdict {
+    type Error = eyre::Report;
+
+    fn try_from(action: FilterAction) -> eyre::Result<Self> {
+        if let Some(respond) = action.respond {
+            let mut response =
+                Response::new(Body::from(decode_body(respond.body)?.unwrap_or_default()));
+            *response.status_mut() = StatusCode::from_u16(respond.status)
+                .wrap_err_with(|| format!("invalid response status {}", respond.status))?;
+            for (name, value) in parse_headers(respond.headers)? {
+                response.headers_mut().append(name, value);
+            }
+            return Ok(FilterVerdict::Respond(response));
+        }
+
+        let remove_headers = action
+            .remove_headers
+            .iter()
+            .map(|name| {
+                HeaderName::from_bytes(name.as_bytes())
+                    .wrap_err_with(|| format!("invalid header name '{name}'"))
+            })
+            .collect::<eyre::Result<_>>()?;
+        Ok(FilterVerdict::Continue(Edits {
+            remove_headers,
+            set_headers: parse_headers(action.set_headers)?,
+            body: decode_body(action.body)?,
+        }))
+    }
+}
+
+fn parse_headers(pairs: Vec<(String, String)>) -> eyre::Result<Vec<(HeaderName, HeaderValue)>> {
+    pairs
+        .into_iter()
+        .map(|(name, value)| {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .wrap_err_with(|| format!("invalid header name '{name}'"))?;
+            let header_value = HeaderValue::from_str(&value)
+                .wrap_err_with(|| format!("invalid value for header '{name}'"))?;
+            Ok((header_name, header_value))
+        })
+        .collect()
+}
+
+fn decode_body(body: Option<String>) -> eyre::Result<Option<Bytes>> {
+    body.map(|encoded| {
+        STANDARD
+            .decode(encoded)
+            .map(Bytes::from)
+            .wrap_err("body is not valid base64")
+    })
+    .transpose()
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.as_str().to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
+fn wasm_error(e: wasmtime::Error) -> eyre::Report {
+    eyre!("{e:#}")
+}
+
+struct FilterState {
+    limits: StoreLimits,
+}
+
+/// A compiled WASM filter.
+pub struct WasmFilter {
+    name: String,
+    config: WasmFilterConfig,
+    engine: Engine,
+    instance_pre: InstancePre<FilterState>,
+    has_on_request: bool,
+    has_on_response: bool,
+}
+
+impl WasmFilter {
+    /// Compile the module at `config.path`.
+    pub fn load(name: &str, config: &WasmFilterConfig) -> eyre::Result<Self> {
+        let bytes = std::fs::read(&config.path).wrap_err_with(|| {
+            format!("Failed to read WASM filter '{name}' from {}", config.path)
+        })?;
+        Self::from_bytes(name, config, &bytes)
+    }
+
+    fn from_bytes(name: &str, config: &WasmFilterConfig, bytes: &[u8]) -> eyre::Result<Self> {
+        let mut engine_config = wasmtime::Config::new();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config).map_err(wasm_error)?;
+        let module = Module::new(&engine, bytes)
+            .map_err(wasm_error)
+            .wrap_err_with(|| format!("Failed to compile WASM filter '{name}'"))?;
+
+        let has_export = |export: &str| module.exports().any(|e| e.name() == export);
+        for required in ["memory", "alloc"] {
+            if !has_export(required) {
+                return Err(eyre!("WASM filter '{name}' does not export '{required}'"));
+            }
+        }
+        let has_on_request = has_export(Phase::Request.export());
+        let has_on_response = has_export(Phase::Response.export());
+        if !has_on_request && !has_on_response {
+            return Err(eyre!(
+                "WASM filter '{name}' exports neither 'on_request' nor 'on_response'"
+            ));
+        }
+
+        let instance_pre = Linker::new(&engine)
+            .instantiate_pre(&module)
+            .map_err(wasm_error)
+            .wrap_err_with(|| format!("WASM filter '{name}' has unsatisfied imports"))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            config: config.clone(),
+            engine,
+            instance_pre,
+            has_on_request,
+            has_on_response,
+        })
+    }
+
+    /// Name the filter is configured under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Run one phase in a fresh instance limited by the configured fuel and memory.
+    fn invoke(&self, phase: Phase, input: &[u8]) -> eyre::Result<FilterVerdict> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.config.max_memory_bytes)
+            .trap_on_grow_failure(true)
+            .build();
+        let mut store = Store::new(&self.engine, FilterState { limits });
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.config.fuel).map_err(wasm_error)?;
+
+        let instance = self
+            .instance_pre
+            .instantiate(&mut store)
+            .map_err(wasm_error)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| eyre!("'memory' export is not a memory"))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "alloc")
+            .map_err(wasm_error)?;
+        let hook = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, phase.export())
+            .map_err(wasm_error)?;
+
+        let len = i32::try_from(input.len()).wrap_err("filter input too large")?;
+        let ptr = alloc.call(&mut store, len).map_err(wasm_error)?;
+        memory
+            .write(&mut store, ptr as u32 as usize, input)
+            .map_err(|e| eyre!("alloc returned an invalid buffer: {e}"))?;
+
+        let packed = hook.call(&mut store, (ptr, len)).map_err(wasm_error)? as u64;
+        if packed == 0 {
+            return Ok(FilterVerdict::Continue(Edits::default()));
+        }
+        let out_ptr = (packed >> 32) as usize;
+        let out_len = (packed & 0xffff_ffff) as usize;
+        let output = memory
+            .data(&store)
+            .get(out_ptr..out_ptr.saturating_add(out_len))
+            .ok_or_else(|| eyre!("filter returned an out-of-bounds result"))?;
+        let action: FilterAction =
+            serde_json::from_slice(output).wrap_err("filter returned invalid JSON")?;
+        action.try_into()
+    }
+
+    /// Run a phase off the async runtime, recording metrics; `None` means the
+    /// filter failed.
+    async fn run(self: &Arc<Self>, phase: Phase, input: &FilterInput<'_>) -> Option<FilterVerdict> {
+        let started = Instant::now();
+        let result = match serde_json::to_vec(input) {
+            Ok(input) => {
+                let filter = self.clone();
+                tokio::task::spawn_blocking(move || filter.invoke(phase, &input))
+                    .await
+                    .map_err(|e| eyre!("filter task failed: {e}"))
+                    .and_then(|result| result)
+            }
+            Err(e) => Err(e.into()),
+        };
+
+        let outcome = match &result {
+            Ok(FilterVerdict::Continue(_)) => "continue",
+            Ok(FilterVerdict::Respond(_)) => "respond",
+            Err(_) => "error",
+        };
+        metrics::record_wasm_filter_call(&self.name, phase.as_str(), outcome, started.elapsed());
+
+        result
+            .map_err(|e| {
+                tracing::warn!(
+                    filter = %self.name,
+                    phase = phase.as_str(),
+                    "WASM filter failed: {:#}",
+                    e
+                );
+            })
+            .ok()
+    }
+
+    /// Buffer a body for the filter when `include_body` is set.
+    async fn buffer_body(&self, body: Body) -> Result<(Body, Option<String>), axum::Error> {
+        if !self.config.include_body {
+            return Ok((body, None));
+        }
+        let bytes = to_bytes(body, self.config.max_body_bytes).await?;
+        let encoded = STANDARD.encode(&bytes);
+        Ok((Body::from(bytes), Some(encoded)))
+    }
+
+    /// Run `on_request`; `Err` carries the response to send instead of proxying.
+    async fn filter_request(self: &Arc<Self>, req: Request) -> Result<Request, Response> {
+        let (mut parts, body) = req.into_parts();
+        let (body, encoded) = self
+            .buffer_body(body)
+            .await
+            .map_err(|_| error_response(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large"))?;
+        let input = FilterInput {
+            method: Some(parts.method.as_str()),
+            uri: Some(parts.uri.to_string()),
+            status: None,
+            headers: header_pairs(&parts.headers),
+            body: encoded,
+        };
+
+        match self.run(Phase::Request, &input).await {
+            Some(FilterVerdict::Continue(edits)) => {
+                let body = edits.apply(&mut parts.headers, body);
+                Ok(Request::from_parts(parts, body))
+            }
+            Some(FilterVerdict::Respond(response)) => Err(response),
+            None if self.config.fail_open => Ok(Request::from_parts(parts, body)),
+            None => Err(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Request filter failed",
+            )),
+        }
+    }
+
+    /// Run `on_response` over the response produced by the rest of the chain.
+    async fn filter_response(self: &Arc<Self>, response: Response) -> Response {
+        let (mut parts, body) = response.into_parts();
+        let Ok((body, encoded)) = self.buffer_body(body).await else {
+            tracing::warn!(
+                filter = %self.name,
+                "Response body exceeds max_body_bytes for WASM filter"
+            );
+            return error_response(StatusCode::BAD_GATEWAY, "Response body too large");
+        };
+        let input = FilterInput {
+            method: None,
+            uri: None,
+            status: Some(parts.status.as_u16()),
+            headers: header_pairs(&parts.headers),
+            body: encoded,
+        };
+
+        match self.run(Phase::Response, &input).await {
+            Some(FilterVerdict::Continue(edits)) => {
+                let body = edits.apply(&mut parts.headers, body);
+                Response::from_parts(parts, body)
+            }
+            Some(FilterVerdict::Respond(response)) => response,
+            None if self.config.fail_open => Response::from_parts(parts, body),
+            None => error_response(StatusCode::INTERNAL_SERVER_ERROR, "Response filter failed"),
+        }
+    }
+}
+
+/// Run a WASM filter's request and response hooks around the rest of the chain.
+pub async fn wasm_filter_middleware(req: Request, next: Next, filter: Arc<WasmFilter>) -> Response {
+    let req = if filter.has_on_request {
+        match filter.filter_request(req).await {
+            Ok(req) => req,
+            Err(response) => return response,
+        }
+    } else {
+        req
+    };
+
+    let response = next.run(req).await;
+    if filter.has_on_response {
+        filter.filter_response(response).await
+    } else {
+        response
+    }
+}
+
+/// Compile every configured filter and register it as `wasm:<name>`.
+pub fn register_wasm_filters(
+    registry: &mut MiddlewareRegistry,
+    filters: &HashMap<String, WasmFilterConfig>,
+) -> eyre::Result<()> {
+    for (name, config) in filters {
+        let filter = Arc::new(WasmFilter::load(name, config)?);
+        tracing::info!("Loaded WASM filter '{}' from {}", name, config.path);
+        registry.register(
+            format!("{WASM_MIDDLEWARE_PREFIX}{name}"),
+            move |inner, _| {
+                let filter = filter.clone();
+                BoxCloneSyncService::new(
+                    from_fn(move |req, next| wasm_filter_middleware(req, next, filter.clone()))
+                        .layer(inner),
+                )
+            },
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, middleware, routing::get};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn filter_config() -> WasmFilterConfig {
+        WasmFilterConfig {
+            path: "test.wasm".to_string(),
+            fuel: 1_000_000,
+            max_memory_bytes: 1024 * 1024,
+            include_body: false,
+            max_body_bytes: 1024,
+            fail_open: false,
+        }
+    }
+
+    /// A filter whose `export` hook always returns `action`.
+    fn action_filter(export: &str, action: &str) -> String {
+        let escaped = action.replace('"', "\\\"");
+        format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (global $next (mut i32) (i32.const 4096))
+                (data (i32.const 0) "{escaped}")
+                (func (export "alloc") (param $len i32) (result i32)
+                    (local $ptr i32)
+                    global.get $next
+                    local.set $ptr
+                    global.get $next
+                    local.get $len
+                    i32.add
+                    global.set $next
+                    local.get $ptr)
+                (func (export "{export}") (param i32 i32) (result i64)
+                    i64.const {len}))"#,
+            len = action.len()
+        )
+    }
+
+    fn app(filter: WasmFilter) -> Router {
+        let filter = Arc::new(filter);
+        Router::new()
+            .route(
+                "/",
+                get(|headers: HeaderMap| async move {
+                    headers
+                        .get("x-filtered")
+                        .map(|v| v.to_str().unwrap_or_default().to_string())
+                        .unwrap_or_else(|| "unfiltered".to_string())
+                }),
+            )
+            .layer(middleware::from_fn(move |req, next| {
+                wasm_filter_middleware(req, next, filter.clone())
+            }))
+    }
+
+    async fn call(app: Router) -> (StatusCode, String) {
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_request_filter_sets_header() {
+        let wat = action_filter("on_request", r#"{"set_headers":[["x-filtered","yes"]]}"#);
+        let filter = WasmFilter::from_bytes("headers", &filter_config(), wat.as_bytes()).unwrap();
+
+        assert_eq!(call(app(filter)).await, (StatusCode::OK, "yes".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_request_filter_responds_early() {
+        // "ZGVuaWVk" = base64("denied")
+        let wat = action_filter(
+            "on_request",
+            r#"{"respond":{"status":403,"body":"ZGVuaWVk"}}"#,
+        );
+        let filter = WasmFilter::from_bytes("deny", &filter_config(), wat.as_bytes()).unwrap();
+
+        assert_eq!(
+            call(app(filter)).await,
+            (StatusCode::FORBIDDEN, "denied".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_response_filter_replaces_body() {
+        // "cmV3cml0dGVu" = base64("rewritten")
+        let wat = action_filter("on_response", r#"{"body":"cmV3cml0dGVu"}"#);
+        let filter = WasmFilter::from_bytes("body", &filter_config(), wat.as_bytes()).unwrap();
+
+        assert_eq!(
+            call(app(filter)).await,
+            (StatusCode::OK, "rewritten".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fuel_exhaustion_fails_closed_or_open() {
+        let wat = r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) i32.const 0)
+            (func (export "on_request") (param i32 i32) (result i64)
+                (loop $spin (br $spin))
+                i64.const 0))"#;
+
+        let filter = WasmFilter::from_bytes("spin", &filter_config(), wat.as_bytes()).unwrap();
+        assert_eq!(call(app(filter)).await.0, StatusCode::INTERNAL_SERVER_ERROR);
+
+        let config = WasmFilterConfig {
+            fail_open: true,
+            ..filter_config()
+        };
+        let filter = WasmFilter::from_bytes("spin", &config, wat.as_bytes()).unwrap();
+        assert_eq!(
+            call(app(filter)).await,
+            (StatusCode::OK, "unfiltered".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_limit_is_enforced() {
+        let wat = action_filter("on_request", "{}").replace(
+            "(memory (export \"memory\") 1)",
+            "(memory (export \"memory\") 4)",
+        );
+        let config = WasmFilterConfig {
+            max_memory_bytes: 2 * 65536,
+            ..filter_config()
+        };
+        let filter = WasmFilter::from_bytes("big", &config, wat.as_bytes()).unwrap();
+
+        assert_eq!(call(app(filter)).await.0, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_load_requires_abi_exports() {
+        let err = WasmFilter::from_bytes("empty", &filter_config(), b"(module)")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("memory"));
+
+        let wat = r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) i32.const 0))"#;
+        assert!(WasmFilter::from_bytes("no-hooks", &filter_config(), wat.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+//! Build and process information reported by `/status` and `/metrics`.
+//!
+//! The commit and build time are embedded by `build.rs`. Uptime counts from
+//! [`mark_started`], which the binary calls first thing in `main`.
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+/// Crate version.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Short git commit the binary was built from, or `unknown`.
+pub const GIT_COMMIT: &str = env!("AXON_GIT_COMMIT");
+/// Build time in seconds since the Unix epoch.
+const BUILD_EPOCH: &str = env!("AXON_BUILD_EPOCH");
+
+static STARTED_AT: Lazy<(Instant, chrono::DateTime<chrono::Utc>)> =
+    Lazy::new(|| (Instant::now(), chrono::Utc::now()));
+
+/// Record the process start time. Later calls have no effect.
+pub fn mark_started() {
+    Lazy::force(&STARTED_AT);
+}
+
+/// Time since [`mark_started`] (or since the first uptime query).
+pub fn uptime() -> Duration {
+    STARTED_AT.0.elapsed()
+}
+
+/// RFC 3339 process start time.
+pub fn started_at() -> String {
+    STARTED_AT.1.to_rfc3339()
+}
+
+/// RFC 3339 build time, or `unknown`.
+pub fn build_timestamp() -> String {
+    BUILD_EPOCH
+        .parse()
+        .ok()
+        .and_then(|epoch| chrono::DateTime::from_timestamp(epoch, 0))
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Cargo features compiled into this binary.
+pub fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    for (feature, enabled) in [
+        ("http3", cfg!(feature = "http3")),
+        ("prometheus", cfg!(feature = "prometheus")),
+        ("statsd", cfg!(feature = "statsd")),
+        ("pushgateway", cfg!(feature = "pushgateway")),
+    ] {
+        if enabled {
+            features.push(feature);
+        }
+    }
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info_is_populated() {
+        mark_started();
+        assert!(!GIT_COMMIT.is_empty());
+        assert_ne!(build_timestamp(), "unknown");
+        assert!(chrono::DateTime::parse_from_rfc3339(&started_at()).is_ok());
+        assert!(uptime() < Duration::from_secs(3600));
+    }
+}
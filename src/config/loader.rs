@@ -18,9 +18,10 @@ pub async fn load_config(config_path: &str) -> Result<ServerConfig> {
         .build()
         .with_context(|| format!("Failed to build config from {}", path.display()))?;
 
-    let server_config: ServerConfig = settings
+    let mut server_config: ServerConfig = settings
         .try_deserialize()
         .with_context(|| format!("Failed to deserialize config from {}", path.display()))?;
+    server_config.expand_virtual_hosts();
 
     Ok(server_config)
 }
@@ -32,7 +33,7 @@ mod tests {
     use tempfile::NamedTempFile;
 
     use super::*;
-    use crate::config::models::{RouteConfig, RouteConfigEntry};
+    use crate::config::models::{RateLimitBy, RateLimitKeyPart, RouteConfig, RouteConfigEntry};
 
     #[tokio::test]
     async fn test_load_toml_config() {
@@ -125,6 +126,108 @@ target = "http://fallback:5000"
         }
     }
 
+    #[tokio::test]
+    async fn test_load_virtual_hosts() {
+        let toml_content = r#"
+listen_addr = "127.0.0.1:3000"
+
+[routes."/"]
+type = "proxy"
+target = "http://fallback:5000"
+
+[virtual_hosts.tenant-a]
+hostnames = ["A.example.com", "*.a.example.com"]
+access_log = false
+
+[virtual_hosts.tenant-a.routes."/"]
+type = "proxy"
+target = "http://tenant-a:3000"
+"#;
+
+        let mut temp_file = NamedTempFile::with_suffix(".toml").unwrap();
+        write!(temp_file, "{}", toml_content).unwrap();
+
+        let config = load_config(temp_file.path().to_str().unwrap())
+            .await
+            .unwrap();
+        // The virtual host's routes join the others, once per hostname
+        let hosts: Vec<Option<&str>> = config.routes["/"]
+            .iter()
+            .map(|route| match route {
+                RouteConfig::Proxy { host, .. } => host.as_deref(),
+                _ => panic!("Expected Proxy route"),
+            })
+            .collect();
+        assert_eq!(
+            hosts,
+            [None, Some("a.example.com"), Some("*.a.example.com")]
+        );
+        let vhost = &config.virtual_hosts["tenant-a"];
+        assert!(vhost.routes.is_empty());
+        assert!(!vhost.access_log);
+    }
+
+    #[tokio::test]
+    async fn test_load_multiple_rate_limits() {
+        let toml_content = r#"
+listen_addr = "127.0.0.1:3000"
+
+[routes."/api"]
+type = "proxy"
+target = "http://backend:8080"
+
+[[routes."/api".rate_limit]]
+name = "per_ip"
+by = "ip"
+requests = 100
+period = "1m"
+
+[[routes."/api".rate_limit]]
+name = "per_ip_path"
+by = "composite"
+key = ["ip", "path"]
+requests = 10
+period = "1s"
+
+[routes."/single"]
+type = "proxy"
+target = "http://backend:8080"
+
+[routes."/single".rate_limit]
+by = "route"
+requests = 5
+period = "1s"
+"#;
+
+        let mut temp_file = NamedTempFile::with_suffix(".toml").unwrap();
+        write!(temp_file, "{}", toml_content).unwrap();
+
+        let config = load_config(temp_file.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let rate_limits = |path: &str| match config.routes.get(path) {
+            Some(RouteConfigEntry::Single(route)) => match route.as_ref() {
+                RouteConfig::Proxy { rate_limit, .. } => rate_limit.clone().expect("rate limit").0,
+                _ => panic!("Expected Proxy route"),
+            },
+            _ => panic!("Expected single route"),
+        };
+
+        let limits = rate_limits("/api");
+        assert_eq!(limits.len(), 2);
+        assert_eq!(limits[0].label(), "per_ip");
+        assert_eq!(limits[1].by, RateLimitBy::Composite);
+        assert_eq!(
+            limits[1].key,
+            vec![RateLimitKeyPart::Ip, RateLimitKeyPart::Path]
+        );
+
+        let limits = rate_limits("/single");
+        assert_eq!(limits.len(), 1);
+        assert_eq!(limits[0].label(), "route");
+    }
+
     #[tokio::test]
     async fn test_load_json_config() {
         let json_content = r#"
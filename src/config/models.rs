@@ -14,11 +14,14 @@ pub struct IpFilterConfig {
 // These types map directly to TOML (also JSON / YAML) configuration files. They are
 // intentionally serde‑friendly and include defaults so that minimal configs remain concise.
 // Builders and enums here are considered part of the public API for embedding.
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use serde::{Deserialize, Serialize};
 
-/// Configuration for static file serving
+/// Defaults for static file serving
+///
+/// `directory_listing`, `listing_format`, `index_file` and `cache_control`
+/// apply to every `static` route that does not set its own value.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct StaticFilesConfig {
@@ -28,10 +31,40 @@ pub struct StaticFilesConfig {
     pub url_prefix: String,
     /// Whether to enable directory listing
     pub directory_listing: bool,
+    /// Body format of generated directory listings
+    pub listing_format: ListingFormat,
     /// Default file to serve for directories (e.g., "index.html")
     pub index_file: Option<String>,
     /// Cache control header value
     pub cache_control: Option<String>,
+    /// Serve `.br` / `.gz` siblings to clients that accept them
+    pub precompressed: bool,
+    /// Entity tags sent with files
+    pub etag: EtagMode,
+}
+
+/// How entity tags are generated for static files.
+///
+/// Both modes derive the tag from the file's modification time and size, so
+/// no file content is read; `strong` tags also validate `If-Range` requests.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EtagMode {
+    #[default]
+    Strong,
+    Weak,
+    Off,
+}
+
+/// Body format of generated directory listings.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingFormat {
+    #[default]
+    Html,
+    Json,
+    /// JSON when the request's `Accept` header prefers `application/json`
+    Auto,
 }
 
 impl Default for StaticFilesConfig {
@@ -40,8 +73,11 @@ impl Default for StaticFilesConfig {
             root: "./static".to_string(),
             url_prefix: "/static".to_string(),
             directory_listing: false,
+            listing_format: ListingFormat::default(),
             index_file: Some("index.html".to_string()),
             cache_control: Some("public, max-age=3600".to_string()),
+            precompressed: false,
+            etag: EtagMode::default(),
         }
     }
 }
@@ -136,10 +172,28 @@ pub struct BodyActions {
     pub set_text: Option<String>, // Set the entire body to this text
     #[serde(default)]
     pub set_json: Option<serde_json::Value>, // Set the entire body to this JSON value
+    /// Substitutions applied to text response bodies as they stream
+    #[serde(default)]
+    pub replace: Vec<BodyReplacement>,
+    /// HTML inserted before `</body>` in HTML responses
+    #[serde(default)]
+    pub inject_html: Option<String>,
     #[serde(default)]
     pub condition: Option<RequestCondition>,
 }
 
+/// One streaming substitution in a response body.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BodyReplacement {
+    /// Text to find, or a regex when `regex` is set
+    pub find: String,
+    /// Replacement; regexes can refer to groups as `$1` or `${name}`
+    #[serde(default)]
+    pub replace_with: String,
+    #[serde(default)]
+    pub regex: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RequestCondition {
     #[serde(default)]
@@ -148,6 +202,9 @@ pub struct RequestCondition {
     pub method_is: Option<String>, // Exact match for request method (e.g., "GET", "POST")
     #[serde(default)]
     pub has_header: Option<HeaderCondition>,
+    /// Inline expression, e.g. `req.header('x-tier') == 'gold' && req.path.ends_with('.pdf')`
+    #[serde(default)]
+    pub expr: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -294,6 +351,10 @@ impl From<Vec<RouteConfig>> for RouteConfigEntry {
     }
 }
 
+/// Prefix the default route is served under, for path handling and per-route
+/// state
+pub const DEFAULT_ROUTE_PREFIX: &str = "/";
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ServerConfig {
     pub listen_addr: String,
@@ -301,7 +362,15 @@ pub struct ServerConfig {
     pub host: Option<String>,
     #[serde(default)]
     pub port: Option<u16>,
+    #[serde(default)]
     pub routes: HashMap<String, RouteConfigEntry>,
+    /// Route for requests no other route matches, served under `/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_route: Option<Box<RouteConfig>>,
+    /// Route tables and per-host settings for groups of hostnames, keyed by
+    /// a name used in logs
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub virtual_hosts: HashMap<String, VirtualHostConfig>,
     #[serde(default)]
     pub tls: Option<TlsConfig>,
     #[serde(default)]
@@ -309,11 +378,56 @@ pub struct ServerConfig {
     #[serde(default)]
     pub backend_health_paths: HashMap<String, String>,
     #[serde(default)]
+    pub backend_limits: HashMap<String, BackendLimits>,
+    #[serde(default)]
+    pub backend_health_checks: HashMap<String, BackendHealthCheckConfig>,
+    #[serde(default)]
+    pub backend_removal: BackendRemovalConfig,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+    #[serde(default)]
     pub protocols: ProtocolConfig,
     #[serde(default)]
     pub static_files: Option<StaticFilesConfig>,
     #[serde(default)]
     pub waf: Option<WafConfig>,
+    #[serde(default)]
+    pub upstream: UpstreamConfig,
+    #[serde(default)]
+    pub middleware: MiddlewareConfig,
+    #[serde(default)]
+    pub config_watch: ConfigWatchConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub error_pages: ErrorPagesConfig,
+    #[serde(default)]
+    pub admin: AdminConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub events: EventsConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub content_types: ContentTypeConfig,
+    #[serde(default)]
+    pub path_normalization: PathNormalizationConfig,
+    #[serde(default)]
+    pub header_limits: HeaderLimitsConfig,
+    #[serde(default)]
+    pub header_allowlist: HeaderAllowlistConfig,
+    /// A/B experiments splitting requests between variants
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub experiments: Vec<ExperimentConfig>,
+    #[serde(default)]
+    pub server_timing: ServerTimingConfig,
+    #[serde(default)]
+    pub tracing: TracingConfig,
+    #[serde(default)]
+    pub client_ip: ClientIpConfig,
+    #[serde(default)]
+    pub endpoints: EndpointsConfig,
 }
 
 impl ServerConfig {
@@ -321,6 +435,50 @@ impl ServerConfig {
     pub fn builder() -> ServerConfigBuilder {
         ServerConfigBuilder::default()
     }
+
+    /// Every route with its prefix, followed by the default route under
+    /// [`DEFAULT_ROUTE_PREFIX`]
+    pub fn all_routes(&self) -> impl Iterator<Item = (&str, &RouteConfig)> {
+        self.routes
+            .iter()
+            .flat_map(|(prefix, entry)| entry.iter().map(move |route| (prefix.as_str(), route)))
+            .chain(
+                self.default_route
+                    .as_deref()
+                    .map(|route| (DEFAULT_ROUTE_PREFIX, route)),
+            )
+    }
+
+    /// Move each virtual host's routes into `routes`, once for every one of
+    /// its hostnames, with `host` set to that hostname. Loaders call this
+    /// after deserializing, so routing only has to know about host-specific
+    /// routes. A route's own `host` is replaced.
+    pub fn expand_virtual_hosts(&mut self) {
+        for vhost in self.virtual_hosts.values_mut() {
+            for (prefix, entry) in std::mem::take(&mut vhost.routes) {
+                let mut routes = self
+                    .routes
+                    .remove(&prefix)
+                    .map(|existing| existing.as_slice().to_vec())
+                    .unwrap_or_default();
+                for hostname in &vhost.hostnames {
+                    for route in entry.iter() {
+                        let mut route = route.clone();
+                        let (RouteConfig::Static { host, .. }
+                        | RouteConfig::Redirect { host, .. }
+                        | RouteConfig::Proxy { host, .. }
+                        | RouteConfig::LoadBalance { host, .. }
+                        | RouteConfig::Websocket { host, .. }) = &mut route;
+                        *host = Some(hostname.to_lowercase());
+                        routes.push(route);
+                    }
+                }
+                if !routes.is_empty() {
+                    self.routes.insert(prefix, RouteConfigEntry::from(routes));
+                }
+            }
+        }
+    }
 }
 
 impl Default for ServerConfig {
@@ -330,12 +488,36 @@ impl Default for ServerConfig {
             host: None,
             port: None,
             routes: HashMap::new(),
+            default_route: None,
+            virtual_hosts: HashMap::new(),
             tls: None,
             health_check: HealthCheckConfig::default(),
             backend_health_paths: HashMap::new(),
+            backend_limits: HashMap::new(),
+            backend_health_checks: HashMap::new(),
+            backend_removal: BackendRemovalConfig::default(),
+            shutdown: ShutdownConfig::default(),
             protocols: ProtocolConfig::default(),
             static_files: None,
             waf: None,
+            upstream: UpstreamConfig::default(),
+            middleware: MiddlewareConfig::default(),
+            config_watch: ConfigWatchConfig::default(),
+            metrics: MetricsConfig::default(),
+            error_pages: ErrorPagesConfig::default(),
+            admin: AdminConfig::default(),
+            audit: AuditConfig::default(),
+            events: EventsConfig::default(),
+            notifications: NotificationsConfig::default(),
+            content_types: ContentTypeConfig::default(),
+            path_normalization: PathNormalizationConfig::default(),
+            header_limits: HeaderLimitsConfig::default(),
+            header_allowlist: HeaderAllowlistConfig::default(),
+            experiments: Vec::new(),
+            server_timing: ServerTimingConfig::default(),
+            tracing: TracingConfig::default(),
+            client_ip: ClientIpConfig::default(),
+            endpoints: EndpointsConfig::default(),
         }
     }
 }
@@ -347,12 +529,36 @@ pub struct ServerConfigBuilder {
     host: Option<String>,
     port: Option<u16>,
     routes: HashMap<String, RouteConfigEntry>,
+    default_route: Option<RouteConfig>,
+    virtual_hosts: HashMap<String, VirtualHostConfig>,
     tls: Option<TlsConfig>,
     health_check: Option<HealthCheckConfig>,
     backend_health_paths: HashMap<String, String>,
+    backend_limits: HashMap<String, BackendLimits>,
+    backend_health_checks: HashMap<String, BackendHealthCheckConfig>,
+    backend_removal: Option<BackendRemovalConfig>,
+    shutdown: Option<ShutdownConfig>,
     protocols: Option<ProtocolConfig>,
     static_files: Option<StaticFilesConfig>,
     waf: Option<WafConfig>,
+    upstream: Option<UpstreamConfig>,
+    middleware: Option<MiddlewareConfig>,
+    config_watch: Option<ConfigWatchConfig>,
+    metrics: Option<MetricsConfig>,
+    error_pages: Option<ErrorPagesConfig>,
+    admin: Option<AdminConfig>,
+    audit: Option<AuditConfig>,
+    events: Option<EventsConfig>,
+    notifications: Option<NotificationsConfig>,
+    content_types: Option<ContentTypeConfig>,
+    path_normalization: Option<PathNormalizationConfig>,
+    header_limits: Option<HeaderLimitsConfig>,
+    header_allowlist: Option<HeaderAllowlistConfig>,
+    experiments: Vec<ExperimentConfig>,
+    server_timing: Option<ServerTimingConfig>,
+    tracing: Option<TracingConfig>,
+    client_ip: Option<ClientIpConfig>,
+    endpoints: Option<EndpointsConfig>,
 }
 
 impl ServerConfigBuilder {
@@ -401,12 +607,25 @@ impl ServerConfigBuilder {
         self
     }
 
+    /// Set the route for requests no other route matches
+    pub fn default_route(mut self, config: RouteConfig) -> Self {
+        self.default_route = Some(config);
+        self
+    }
+
+    /// Add a virtual host; its routes are expanded by [`Self::build`]
+    pub fn virtual_host(mut self, name: impl Into<String>, config: VirtualHostConfig) -> Self {
+        self.virtual_hosts.insert(name.into(), config);
+        self
+    }
+
     /// Set TLS configuration with manual certificate paths
     pub fn tls(mut self, cert_path: impl Into<String>, key_path: impl Into<String>) -> Self {
         self.tls = Some(TlsConfig {
             cert_path: Some(cert_path.into()),
             key_path: Some(key_path.into()),
             acme: None,
+            sni_host_check: SniHostCheck::default(),
         });
         self
     }
@@ -428,6 +647,34 @@ impl ServerConfigBuilder {
         self
     }
 
+    /// Set how requests to backends removed by a reload are handled
+    pub fn backend_removal(mut self, config: BackendRemovalConfig) -> Self {
+        self.backend_removal = Some(config);
+        self
+    }
+
+    /// Set shutdown drain behavior
+    pub fn shutdown(mut self, config: ShutdownConfig) -> Self {
+        self.shutdown = Some(config);
+        self
+    }
+
+    /// Cap concurrent and queued requests to a backend
+    pub fn backend_limits(mut self, backend: impl Into<String>, limits: BackendLimits) -> Self {
+        self.backend_limits.insert(backend.into(), limits);
+        self
+    }
+
+    /// Set the Host, TLS and credentials of a backend's health probes
+    pub fn backend_health_check(
+        mut self,
+        backend: impl Into<String>,
+        config: BackendHealthCheckConfig,
+    ) -> Self {
+        self.backend_health_checks.insert(backend.into(), config);
+        self
+    }
+
     /// Set protocol configuration
     pub fn protocols(mut self, config: ProtocolConfig) -> Self {
         self.protocols = Some(config);
@@ -440,31 +687,218 @@ impl ServerConfigBuilder {
         self
     }
 
+    /// Set upstream HTTP client configuration
+    pub fn upstream(mut self, config: UpstreamConfig) -> Self {
+        self.upstream = Some(config);
+        self
+    }
+
+    /// Set settings for built-in route middlewares
+    pub fn middleware(mut self, config: MiddlewareConfig) -> Self {
+        self.middleware = Some(config);
+        self
+    }
+
+    /// Set how the configuration file is watched for changes
+    pub fn config_watch(mut self, config: ConfigWatchConfig) -> Self {
+        self.config_watch = Some(config);
+        self
+    }
+
+    /// Set metrics export settings
+    pub fn metrics(mut self, config: MetricsConfig) -> Self {
+        self.metrics = Some(config);
+        self
+    }
+
+    /// Set the error pages configuration
+    pub fn error_pages(mut self, config: ErrorPagesConfig) -> Self {
+        self.error_pages = Some(config);
+        self
+    }
+
+    /// Set the admin API configuration
+    pub fn admin(mut self, config: AdminConfig) -> Self {
+        self.admin = Some(config);
+        self
+    }
+
+    /// Set the security audit log configuration
+    pub fn audit(mut self, config: AuditConfig) -> Self {
+        self.audit = Some(config);
+        self
+    }
+
+    /// Set the request event sink configuration
+    pub fn events(mut self, config: EventsConfig) -> Self {
+        self.events = Some(config);
+        self
+    }
+
+    /// Set the operational event webhook configuration
+    pub fn notifications(mut self, config: NotificationsConfig) -> Self {
+        self.notifications = Some(config);
+        self
+    }
+
+    /// Set the Content-Type enforcement configuration
+    pub fn content_types(mut self, config: ContentTypeConfig) -> Self {
+        self.content_types = Some(config);
+        self
+    }
+
+    /// Set the request path normalization configuration
+    pub fn path_normalization(mut self, config: PathNormalizationConfig) -> Self {
+        self.path_normalization = Some(config);
+        self
+    }
+
+    /// Set the inbound request header limits
+    pub fn header_limits(mut self, config: HeaderLimitsConfig) -> Self {
+        self.header_limits = Some(config);
+        self
+    }
+
+    /// Set the proxied header allowlists
+    pub fn header_allowlist(mut self, config: HeaderAllowlistConfig) -> Self {
+        self.header_allowlist = Some(config);
+        self
+    }
+
+    /// Add an A/B experiment
+    pub fn experiment(mut self, config: ExperimentConfig) -> Self {
+        self.experiments.push(config);
+        self
+    }
+
+    /// Set the `Server-Timing` response header configuration
+    pub fn server_timing(mut self, config: ServerTimingConfig) -> Self {
+        self.server_timing = Some(config);
+        self
+    }
+
+    /// Set log filtering, output format and trace export
+    pub fn tracing(mut self, config: TracingConfig) -> Self {
+        self.tracing = Some(config);
+        self
+    }
+
+    /// Set how client addresses behind proxies are found
+    pub fn client_ip(mut self, config: ClientIpConfig) -> Self {
+        self.client_ip = Some(config);
+        self
+    }
+
+    /// Set the paths and access rules of the built-in endpoints
+    pub fn endpoints(mut self, config: EndpointsConfig) -> Self {
+        self.endpoints = Some(config);
+        self
+    }
+
     /// Build the final ServerConfig
     pub fn build(self) -> Result<ServerConfig, String> {
         let listen_addr = self
             .listen_addr
             .ok_or_else(|| "listen_addr is required".to_string())?;
 
-        if self.routes.is_empty() {
+        if self.routes.is_empty()
+            && self.default_route.is_none()
+            && self.virtual_hosts.values().all(|v| v.routes.is_empty())
+        {
             return Err("At least one route must be configured".to_string());
         }
 
-        Ok(ServerConfig {
+        let mut config = ServerConfig {
             listen_addr,
             host: self.host,
             port: self.port,
             waf: self.waf,
             routes: self.routes,
+            default_route: self.default_route.map(Box::new),
+            virtual_hosts: self.virtual_hosts,
             tls: self.tls,
             health_check: self.health_check.unwrap_or_default(),
             backend_health_paths: self.backend_health_paths,
+            backend_limits: self.backend_limits,
+            backend_health_checks: self.backend_health_checks,
+            backend_removal: self.backend_removal.unwrap_or_default(),
+            shutdown: self.shutdown.unwrap_or_default(),
             protocols: self.protocols.unwrap_or_default(),
             static_files: self.static_files,
-        })
+            upstream: self.upstream.unwrap_or_default(),
+            middleware: self.middleware.unwrap_or_default(),
+            config_watch: self.config_watch.unwrap_or_default(),
+            metrics: self.metrics.unwrap_or_default(),
+            error_pages: self.error_pages.unwrap_or_default(),
+            admin: self.admin.unwrap_or_default(),
+            audit: self.audit.unwrap_or_default(),
+            events: self.events.unwrap_or_default(),
+            notifications: self.notifications.unwrap_or_default(),
+            content_types: self.content_types.unwrap_or_default(),
+            path_normalization: self.path_normalization.unwrap_or_default(),
+            header_limits: self.header_limits.unwrap_or_default(),
+            header_allowlist: self.header_allowlist.unwrap_or_default(),
+            experiments: self.experiments,
+            server_timing: self.server_timing.unwrap_or_default(),
+            tracing: self.tracing.unwrap_or_default(),
+            client_ip: self.client_ip.unwrap_or_default(),
+            endpoints: self.endpoints.unwrap_or_default(),
+        };
+        config.expand_virtual_hosts();
+        Ok(config)
+    }
+}
+
+/// A virtual host: the routes, certificate and request handling settings for
+/// a group of hostnames.
+///
+/// ```toml
+/// [virtual_hosts.tenant-a]
+/// hostnames = ["tenant-a.example.com", "*.tenant-a.example.com"]
+/// access_log = false
+/// tls = { cert_path = "certs/tenant-a.pem", key_path = "certs/tenant-a.key" }
+///
+/// [virtual_hosts.tenant-a.routes."/api"]
+/// type = "proxy"
+/// target = "http://tenant-a:8080"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct VirtualHostConfig {
+    /// Exact hostnames, or `*.example.com` for any single label before `.example.com`
+    pub hostnames: Vec<String>,
+    /// Routes served for these hostnames, like the top-level `routes`
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub routes: HashMap<String, RouteConfigEntry>,
+    /// Certificate presented when the client's SNI is one of the hostnames
+    pub tls: Option<VirtualHostTlsConfig>,
+    /// Whether the WAF inspects these hostnames' requests (default: `waf.enabled`)
+    pub waf: Option<bool>,
+    /// Whether completed requests are logged (default true)
+    pub access_log: bool,
+}
+
+impl Default for VirtualHostConfig {
+    fn default() -> Self {
+        Self {
+            hostnames: Vec::new(),
+            routes: HashMap::new(),
+            tls: None,
+            waf: None,
+            access_log: true,
+        }
     }
 }
 
+/// Certificate for a virtual host, chosen by SNI
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VirtualHostTlsConfig {
+    /// Path to PEM encoded certificate
+    pub cert_path: String,
+    /// Path to PEM encoded private key
+    pub key_path: String,
+}
+
 /// TLS configuration via manual certificate/key pair or ACME.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TlsConfig {
@@ -474,6 +908,25 @@ pub struct TlsConfig {
     pub key_path: Option<String>,
     /// ACME (Let's Encrypt) configuration
     pub acme: Option<AcmeConfig>,
+    /// Whether the Host header must match the connection's TLS SNI
+    #[serde(default)]
+    pub sni_host_check: SniHostCheck,
+}
+
+/// How a request's Host must relate to the SNI sent on its TLS connection.
+///
+/// Mismatched requests are rejected with 421 Misdirected Request, so a client
+/// that reuses a connection for another host retries on a new one.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SniHostCheck {
+    /// Host and SNI are not compared (default)
+    #[default]
+    Off,
+    /// When the client sent SNI, the Host must match it
+    Match,
+    /// SNI is required and the Host must match it
+    Strict,
 }
 
 /// ACME configuration for automatic HTTPS
@@ -504,9 +957,132 @@ pub struct WafConfig {
     pub xss: WafRuleConfig,
     pub path_traversal: WafRuleConfig,
     pub command_injection: WafRuleConfig,
+    /// XML/SOAP bodies: flag document type declarations (XXE) and run the
+    /// other checks on decoded text instead of markup
+    pub xml: WafRuleConfig,
     pub bot_detection: BotDetectionConfig,
     pub ip_filter: IpFilterConfig,
-    // Add more fields as needed (e.g., max_body_size, hot_reload, etc.)
+    /// Regex rules matched against each request
+    pub custom_rules: Vec<WafCustomRule>,
+    /// File with additional rules, reloaded whenever its content changes
+    pub rules_file: Option<String>,
+    /// How often `rules_file` is checked for changes (default 10)
+    pub rules_poll_interval_secs: Option<u64>,
+    /// Temporary bans for clients that keep tripping the WAF or getting 4xx
+    pub auto_ban: AutoBanConfig,
+}
+
+/// Automatic, temporary banning of misbehaving client IPs.
+///
+/// Offenses are counted per IP in fixed windows of `window_secs`. An IP that
+/// reaches a threshold is rejected like a blacklisted one for `ban_secs`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AutoBanConfig {
+    pub enabled: bool,
+    /// Length of the counting window
+    pub window_secs: u64,
+    /// WAF detections within a window that trigger a ban (0 disables)
+    pub waf_violations: u32,
+    /// 4xx responses, including 429, within a window that trigger a ban (0 disables)
+    pub client_errors: u32,
+    /// How long a ban lasts
+    pub ban_secs: u64,
+    /// Upper bound on IPs with offenses being counted; others are not tracked
+    pub max_tracked_ips: usize,
+    /// IPs or CIDR ranges that are never banned
+    pub exempt: Vec<String>,
+}
+
+impl Default for AutoBanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_secs: 60,
+            waf_violations: 5,
+            client_errors: 0,
+            ban_secs: 600,
+            max_tracked_ips: 100_000,
+            exempt: Vec::new(),
+        }
+    }
+}
+
+/// A user-defined WAF rule.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WafCustomRule {
+    /// Name reported in logs and audit records
+    pub name: String,
+    /// Regex matched against the target
+    pub pattern: String,
+    /// Part of the request the pattern is matched against
+    #[serde(default)]
+    pub target: WafRuleTarget,
+    /// Block matching requests (true, default) or just log (false)
+    #[serde(default = "default_waf_rule_block_mode")]
+    pub block_mode: bool,
+}
+
+fn default_waf_rule_block_mode() -> bool {
+    true
+}
+
+/// Part of a request a custom WAF rule inspects.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WafRuleTarget {
+    /// URL-decoded path and query (default)
+    #[default]
+    Uri,
+    /// Every header value
+    Headers,
+    /// The User-Agent header
+    UserAgent,
+    /// The request body, when it is buffered for inspection
+    Body,
+}
+
+/// Rules kept in a separate file referenced by `waf.rules_file`.
+///
+/// They are added to the inline WAF configuration: IP entries apply when
+/// `ip_filter` is enabled and bot entries when `bot_detection` is enabled.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct WafRuleSet {
+    /// Operator-assigned version, reported in `/status`
+    pub version: Option<String>,
+    pub custom_rules: Vec<WafCustomRule>,
+    pub ip_whitelist: Vec<String>,
+    pub ip_blacklist: Vec<String>,
+    pub bad_bot_patterns: Vec<String>,
+    pub good_bot_identifiers: Vec<String>,
+}
+
+impl WafRuleSet {
+    /// `base` with this rule set's entries appended.
+    pub fn apply(&self, base: &WafConfig) -> WafConfig {
+        let mut config = base.clone();
+        config
+            .custom_rules
+            .extend(self.custom_rules.iter().cloned());
+        config
+            .ip_filter
+            .whitelist
+            .extend(self.ip_whitelist.iter().cloned());
+        config
+            .ip_filter
+            .blacklist
+            .extend(self.ip_blacklist.iter().cloned());
+        config
+            .bot_detection
+            .custom_bad_patterns
+            .extend(self.bad_bot_patterns.iter().cloned());
+        config
+            .bot_detection
+            .custom_good_identifiers
+            .extend(self.good_bot_identifiers.iter().cloned());
+        config
+    }
 }
 
 /// Configuration for individual WAF rules
@@ -545,99 +1121,2245 @@ pub struct HealthCheckConfig {
     pub path: String,
     pub unhealthy_threshold: u32,
     pub healthy_threshold: u32,
+    /// Maximum number of backends probed at once (default 16)
+    pub max_concurrency: Option<usize>,
 }
 
-fn default_status_code() -> u16 {
-    429
+/// Aggregation used for a latency histogram.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HistogramConfig {
+    /// OpenTelemetry SDK default buckets
+    #[default]
+    Default,
+    /// Explicit bucket upper bounds, in seconds
+    Explicit { buckets: Vec<f64> },
+    /// Base-2 exponential ("native") histogram
+    Exponential {
+        #[serde(default = "default_exponential_max_size")]
+        max_size: u32,
+        #[serde(default = "default_exponential_max_scale")]
+        max_scale: i8,
+    },
 }
 
-fn default_message() -> String {
-    "Too Many Requests".to_string()
+fn default_exponential_max_size() -> u32 {
+    160
 }
 
-/// Rate limiting discriminator for how keys are derived.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
-#[serde(rename_all = "snake_case")]
-pub enum RateLimitBy {
-    Ip,
-    Header,
-    Route,
+fn default_exponential_max_scale() -> i8 {
+    20
 }
 
-/// Algorithm used to enforce the quota semantics.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
-#[serde(rename_all = "snake_case")]
-pub enum RateLimitAlgorithm {
-    TokenBucket,
-    FixedWindow,
-    SlidingWindow,
+/// OpenTelemetry metrics export settings.
+///
+/// Applied when metrics are initialized at startup; changing them requires a
+/// restart to take effect.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Aggregation for `axon_request_duration_seconds`
+    pub request_duration: HistogramConfig,
+    /// Aggregation for `axon_backend_request_duration_seconds`
+    pub backend_duration: HistogramConfig,
+    /// Where metrics go; each exporter other than OTLP needs its Cargo feature
+    pub exporters: Vec<MetricsExporter>,
+    /// Seconds between collections; defaults to `OTEL_METRIC_EXPORT_INTERVAL`
+    /// or 60
+    pub interval_secs: Option<u64>,
+    /// Failure handling for the push exporters
+    pub export: MetricsExportConfig,
+    /// Required by the `statsd` exporter
+    pub statsd: Option<StatsdConfig>,
+    /// Required by the `pushgateway` exporter
+    pub pushgateway: Option<PushgatewayConfig>,
 }
 
-/// Behaviour when a key could not be extracted (missing IP/header etc.).
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            request_duration: HistogramConfig::default(),
+            backend_duration: HistogramConfig::default(),
+            exporters: vec![MetricsExporter::Otlp],
+            interval_secs: None,
+            export: MetricsExportConfig::default(),
+            statsd: None,
+            pushgateway: None,
+        }
+    }
+}
+
+/// A destination for metrics
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
-pub enum MissingKeyPolicy {
-    Allow,
-    Deny,
+pub enum MetricsExporter {
+    /// Push to an OpenTelemetry collector (`OTEL_EXPORTER_OTLP_*` settings)
+    Otlp,
+    /// Add the OpenTelemetry instruments to the `/metrics` exposition
+    Prometheus,
+    /// Send to a StatsD agent over UDP, with DogStatsD tags
+    Statsd,
+    /// Push the Prometheus exposition to a Pushgateway
+    Pushgateway,
 }
 
-fn default_on_missing_key() -> MissingKeyPolicy {
-    MissingKeyPolicy::Allow
+impl MetricsExporter {
+    /// Cargo feature the exporter is built with, if it needs one
+    pub fn feature(self) -> Option<&'static str> {
+        match self {
+            Self::Otlp => None,
+            Self::Prometheus => Some("prometheus"),
+            Self::Statsd => Some("statsd"),
+            Self::Pushgateway => Some("pushgateway"),
+        }
+    }
+
+    /// Whether this build includes the exporter
+    pub fn is_available(self) -> bool {
+        match self {
+            Self::Otlp => true,
+            Self::Prometheus => cfg!(feature = "prometheus"),
+            Self::Statsd => cfg!(feature = "statsd"),
+            Self::Pushgateway => cfg!(feature = "pushgateway"),
+        }
+    }
 }
 
-/// Per‑route rate limit configuration.
+/// StatsD agent for the `statsd` exporter
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct RateLimitConfig {
-    pub by: RateLimitBy,
+pub struct StatsdConfig {
+    /// `host:port` of the agent
+    pub address: String,
+    /// Prepended to every metric name (e.g. `gateway.`)
     #[serde(default)]
-    pub header_name: Option<String>, // Should be Some if by == Header
-    pub requests: u64,
-    pub period: String, // Parsed by humantime, e.g., "1s", "5m", "1h"
-    #[serde(default = "default_status_code")]
-    pub status_code: u16,
-    #[serde(default = "default_message")]
-    pub message: String,
-    #[serde(default = "default_rate_limit_algorithm")]
-    pub algorithm: RateLimitAlgorithm,
-    #[serde(default = "default_on_missing_key")]
-    pub on_missing_key: MissingKeyPolicy,
+    pub prefix: String,
+    /// Largest datagram sent; lines are batched up to this size
+    #[serde(default = "default_statsd_max_packet_bytes")]
+    pub max_packet_bytes: usize,
 }
 
-fn default_rate_limit_algorithm() -> RateLimitAlgorithm {
-    RateLimitAlgorithm::TokenBucket
+fn default_statsd_max_packet_bytes() -> usize {
+    1432
 }
 
-/// Route definitions (tagged enum) describing how incoming request paths are handled.
+/// Pushgateway for the `pushgateway` exporter
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(tag = "type")]
+pub struct PushgatewayConfig {
+    /// Base URL, e.g. `http://pushgateway:9091`
+    pub url: String,
+    /// `job` grouping label
+    #[serde(default = "default_pushgateway_job")]
+    pub job: String,
+    /// Optional `instance` grouping label
+    #[serde(default)]
+    pub instance: Option<String>,
+}
+
+fn default_pushgateway_job() -> String {
+    "axon".to_string()
+}
+
+/// How OTLP export failures are reported.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
-pub enum RouteConfig {
-    Static {
-        root: String,
-        /// Optional host header to match (e.g., "api.example.com")
-        #[serde(default)]
-        host: Option<String>,
-        rate_limit: Option<RateLimitConfig>,
-        #[serde(default)]
-        middlewares: Vec<String>,
+pub enum ExportErrorLog {
+    /// Only count failures in `axon_telemetry_dropped_total`
+    Silent,
+    /// Log at most one warning per `error_log_interval_secs`
+    #[default]
+    Interval,
+}
+
+/// Protects request handling from an unreachable metrics collector, for the
+/// OTLP, StatsD and Pushgateway exporters.
+///
+/// Exports run on the SDK's reader thread, never on the request path. A
+/// failing batch is dropped and counted; after `failure_threshold` failures
+/// in a row, exports are skipped for `circuit_open_secs`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MetricsExportConfig {
+    /// Seconds before an export attempt is abandoned
+    pub timeout_secs: u64,
+    /// Consecutive failed exports that open the circuit
+    pub failure_threshold: u32,
+    /// Seconds exports are skipped once the circuit is open
+    pub circuit_open_secs: u64,
+    pub error_log: ExportErrorLog,
+    /// Minimum seconds between failure warnings
+    pub error_log_interval_secs: u64,
+}
+
+impl Default for MetricsExportConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 10,
+            failure_threshold: 3,
+            circuit_open_secs: 60,
+            error_log: ExportErrorLog::default(),
+            error_log_interval_secs: 60,
+        }
+    }
+}
+
+/// Caps on the requests one backend receives, keyed by target URL in
+/// `[backend_limits]`.
+///
+/// Requests beyond `max_requests` go to another healthy target of the route;
+/// when every target is saturated they wait in a queue of up to
+/// `max_pending`, and are answered with 503 when the queue is full or the
+/// wait times out.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BackendLimits {
+    /// Requests forwarded to the backend at once
+    pub max_requests: usize,
+    /// Requests allowed to wait for a free slot (0 disables queueing)
+    #[serde(default)]
+    pub max_pending: usize,
+    /// Milliseconds a queued request waits for a slot
+    #[serde(default = "default_pending_timeout_ms")]
+    pub pending_timeout_ms: u64,
+}
+
+fn default_pending_timeout_ms() -> u64 {
+    1000
+}
+
+/// Health probe request settings for one backend, keyed by target URL in
+/// `[backend_health_checks]`, for backends that only answer probes sent with
+/// the right Host, SNI or credentials.
+///
+/// Without `tls`, probes use the `upstream_tls` of a route proxying to the
+/// backend, so a private CA trusted for traffic is trusted for probes too.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct BackendHealthCheckConfig {
+    /// Host header sent instead of the target's authority; also the SNI of
+    /// HTTPS probes unless `tls.server_name` is set
+    pub host: Option<String>,
+    /// TLS settings for HTTPS backends
+    pub tls: Option<UpstreamTlsConfig>,
+    /// Credentials sent as `Authorization: Basic`
+    pub basic_auth: Option<BasicAuthCredentials>,
+}
+
+/// A username and password for HTTP basic authentication.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BasicAuthCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// What happens to requests and WebSocket sessions still running on a
+/// backend when a configuration reload removes it.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendRemovalMode {
+    /// Let them finish
+    #[default]
+    Complete,
+    /// Let them run for `deadline_secs`, then cancel them
+    Deadline,
+    /// Cancel them right away
+    Fail,
+}
+
+/// Handling of in-flight traffic to backends removed by a reload.
+///
+/// Cancelled requests that have no response yet are answered with 502;
+/// responses already streaming are cut off and WebSocket sessions closed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BackendRemovalConfig {
+    pub mode: BackendRemovalMode,
+    /// Seconds in-flight traffic may continue with `mode = "deadline"`
+    pub deadline_secs: u64,
+}
+
+impl Default for BackendRemovalConfig {
+    fn default() -> Self {
+        Self {
+            mode: BackendRemovalMode::default(),
+            deadline_secs: 30,
+        }
+    }
+}
+
+/// Requests arriving on open connections after shutdown has begun.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownNewRequests {
+    /// Answer 503 and close the connection
+    #[default]
+    Reject,
+    /// Route them normally while draining
+    Serve,
+}
+
+/// How the gateway drains on SIGTERM, SIGINT or a binary upgrade.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// Seconds to wait for in-flight requests to finish
+    pub timeout_secs: u64,
+    /// Milliseconds between checks for remaining requests
+    pub drain_poll_interval_ms: u64,
+    /// Seconds after the drain that still-open connections (WebSockets,
+    /// slow clients) get before they are closed forcibly
+    pub force_close_after_secs: u64,
+    pub new_requests: ShutdownNewRequests,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            drain_poll_interval_ms: 100,
+            force_close_after_secs: 5,
+            new_requests: ShutdownNewRequests::default(),
+        }
+    }
+}
+
+/// Admin API served by the gateway under `/admin/`.
+///
+/// Disabled by default; when enabled every request must carry
+/// `Authorization: Bearer <token>`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// Serve the admin endpoints
+    pub enabled: bool,
+    /// Bearer token required on admin requests
+    pub token: Option<String>,
+}
+
+/// Endpoints the gateway serves itself instead of routing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinEndpoint {
+    Health,
+    Metrics,
+    Status,
+}
+
+impl BuiltinEndpoint {
+    pub const ALL: [Self; 3] = [Self::Health, Self::Metrics, Self::Status];
+
+    /// The endpoint's key under `[endpoints]`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Health => "health",
+            Self::Metrics => "metrics",
+            Self::Status => "status",
+        }
+    }
+
+    fn default_path(self) -> &'static str {
+        match self {
+            Self::Health => "/health",
+            Self::Metrics => "/metrics",
+            Self::Status => "/status",
+        }
+    }
+
+    /// Where the admin API serves the endpoint when it is moved there
+    pub fn admin_path(self) -> String {
+        format!("/admin/endpoints/{}", self.name())
+    }
+}
+
+/// Paths and access rules of the built-in `/health`, `/metrics` and
+/// `/status` endpoints.
+///
+/// ```toml
+/// [endpoints.metrics]
+/// path = "/_gateway/metrics"
+/// allow = ["10.0.0.0/8"]
+/// token = "scrape-secret"
+///
+/// [endpoints.status]
+/// admin = true   # only at /admin/endpoints/status, with the admin token
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct EndpointsConfig {
+    pub health: EndpointConfig,
+    pub metrics: EndpointConfig,
+    pub status: EndpointConfig,
+}
+
+impl EndpointsConfig {
+    pub fn get(&self, endpoint: BuiltinEndpoint) -> &EndpointConfig {
+        match endpoint {
+            BuiltinEndpoint::Health => &self.health,
+            BuiltinEndpoint::Metrics => &self.metrics,
+            BuiltinEndpoint::Status => &self.status,
+        }
+    }
+
+    /// The path `endpoint` is served at, unless it is disabled or moved to
+    /// the admin API
+    pub fn public_path(&self, endpoint: BuiltinEndpoint) -> Option<&str> {
+        let config = self.get(endpoint);
+        (config.enabled && !config.admin)
+            .then(|| config.path.as_deref().unwrap_or(endpoint.default_path()))
+    }
+
+    /// The endpoint served at `path`, outside the admin API
+    pub fn find(&self, path: &str) -> Option<BuiltinEndpoint> {
+        BuiltinEndpoint::ALL
+            .into_iter()
+            .find(|&endpoint| self.public_path(endpoint) == Some(path))
+    }
+
+    /// The endpoint the admin API serves at `path`
+    pub fn find_admin(&self, path: &str) -> Option<BuiltinEndpoint> {
+        BuiltinEndpoint::ALL.into_iter().find(|&endpoint| {
+            let config = self.get(endpoint);
+            config.enabled && config.admin && endpoint.admin_path() == path
+        })
+    }
+}
+
+/// One built-in endpoint. Requests must pass both `allow` and `token` when
+/// they are set.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct EndpointConfig {
+    /// Serve the endpoint (default true)
+    pub enabled: bool,
+    /// Path on the public listener; defaults to `/health`, `/metrics` or
+    /// `/status`
+    pub path: Option<String>,
+    /// Client addresses or CIDR ranges allowed to call it; empty allows all
+    pub allow: Vec<String>,
+    /// Bearer token required on requests
+    pub token: Option<String>,
+    /// Serve it only through the admin API, at `/admin/endpoints/<name>`
+    pub admin: bool,
+}
+
+impl Default for EndpointConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: None,
+            allow: Vec::new(),
+            token: None,
+            admin: false,
+        }
+    }
+}
+
+/// Structured records of security decisions (WAF, IP filter, rate limit and
+/// authentication), written apart from access logs for SIEM ingestion.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    /// Where records are sent; required when enabled
+    pub sink: Option<AuditSinkConfig>,
+    /// Records buffered for the sink before new ones are dropped
+    pub queue_size: usize,
+    /// Also record WAF detections that monitor mode let through
+    pub include_monitored: bool,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sink: None,
+            queue_size: 1024,
+            include_monitored: true,
+        }
+    }
+}
+
+/// Destination for audit records.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditSinkConfig {
+    /// Append one JSON object per line to a file
+    File { path: String },
+    /// Send RFC 5424 messages with a JSON body over UDP
+    Syslog {
+        #[serde(default = "default_syslog_address")]
+        address: String,
+        /// Syslog facility code (default 13, "log audit")
+        #[serde(default = "default_syslog_facility")]
+        facility: u8,
+    },
+    /// POST batches of records as a JSON array
+    Webhook {
+        url: String,
+        /// Extra request headers, e.g. an ingestion token
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        /// Most records sent in one request
+        #[serde(default = "default_webhook_batch_size")]
+        batch_size: usize,
+    },
+}
+
+/// Summaries of completed requests published to a message broker for
+/// analytics pipelines.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct EventsConfig {
+    pub enabled: bool,
+    /// Where events are published; required when enabled
+    pub sink: Option<EventSinkConfig>,
+    /// Fraction of requests published (0.0-1.0); requests with WAF
+    /// detections are always published
+    pub sample_rate: f64,
+    /// Events buffered for the sink before new ones are dropped
+    pub queue_size: usize,
+    /// Most events handed to the sink at once
+    pub batch_size: usize,
+    /// Header naming the tenant, copied into events
+    pub tenant_header: Option<String>,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sink: None,
+            sample_rate: 1.0,
+            queue_size: 8192,
+            batch_size: 100,
+            tenant_header: None,
+        }
+    }
+}
+
+/// Broker receiving request events.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventSinkConfig {
+    /// Produce to a Kafka topic, keyed by tenant (or route)
+    Kafka {
+        /// Bootstrap servers as `host:port`
+        brokers: Vec<String>,
+        topic: String,
+        /// Extra librdkafka producer properties, e.g. `compression.type`
+        #[serde(default)]
+        properties: HashMap<String, String>,
+    },
+    /// Publish to a NATS subject
+    Nats {
+        /// Server URL, e.g. `nats://127.0.0.1:4222`
+        url: String,
+        subject: String,
+    },
+}
+
+impl EventSinkConfig {
+    /// Cargo feature the sink is built with
+    pub fn feature(&self) -> &'static str {
+        match self {
+            Self::Kafka { .. } => "kafka",
+            Self::Nats { .. } => "nats",
+        }
+    }
+
+    /// Whether this build includes the sink
+    pub fn is_available(&self) -> bool {
+        match self {
+            Self::Kafka { .. } => cfg!(feature = "kafka"),
+            Self::Nats { .. } => cfg!(feature = "nats"),
+        }
+    }
+}
+
+/// Webhooks told about operational events: backend health changes, opened
+/// circuits, configuration reloads and certificate renewals.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Notifications are sent when at least one webhook is configured
+    pub webhooks: Vec<NotificationWebhookConfig>,
+    /// Further attempts after a failed delivery
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for each one after it
+    pub retry_backoff_ms: u64,
+    /// Time allowed for one delivery attempt
+    pub timeout_ms: u64,
+    /// Notifications buffered before new ones are dropped
+    pub queue_size: usize,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            webhooks: Vec::new(),
+            max_retries: 3,
+            retry_backoff_ms: 1000,
+            timeout_ms: 5000,
+            queue_size: 256,
+        }
+    }
+}
+
+/// One webhook receiving operational events.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NotificationWebhookConfig {
+    pub url: String,
+    /// Events sent to this webhook; all of them when empty
+    #[serde(default)]
+    pub events: Vec<NotificationEvent>,
+    /// Signs each delivery with HMAC-SHA256 in `X-Axon-Signature`
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Extra request headers
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// Operational event a webhook can subscribe to.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    /// A backend failed enough health checks, or requests, to be taken out
+    BackendUnhealthy,
+    /// An unhealthy backend passed enough health checks to be used again
+    BackendHealthy,
+    /// Repeated metrics export failures opened the exporter's circuit
+    CircuitOpen,
+    ConfigReloaded,
+    /// A changed configuration could not be loaded or applied
+    ConfigReloadFailed,
+    /// ACME issued a new certificate
+    CertificateRenewed,
+}
+
+impl NotificationEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::BackendUnhealthy => "backend_unhealthy",
+            Self::BackendHealthy => "backend_healthy",
+            Self::CircuitOpen => "circuit_open",
+            Self::ConfigReloaded => "config_reloaded",
+            Self::ConfigReloadFailed => "config_reload_failed",
+            Self::CertificateRenewed => "certificate_renewed",
+        }
+    }
+}
+
+fn default_syslog_address() -> String {
+    "127.0.0.1:514".to_string()
+}
+
+fn default_syslog_facility() -> u8 {
+    13
+}
+
+fn default_webhook_batch_size() -> usize {
+    100
+}
+
+/// Content-Type enforcement for static and proxied responses.
+///
+/// Complements `X-Content-Type-Options: nosniff`: with sniffing disabled,
+/// browsers trust the declared type, so it has to be right.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ContentTypeConfig {
+    /// Add `X-Content-Type-Options: nosniff` to static and proxied responses
+    pub nosniff: bool,
+    /// Media types by file extension (without the dot), overriding the
+    /// built-in table for static files and `correct` rules
+    pub extensions: HashMap<String, String>,
+    /// Rules checked in order against proxied responses; the first match wins
+    pub proxy_rules: Vec<ContentTypeRule>,
+}
+
+/// Request path normalization applied before the WAF and routing.
+///
+/// Requests whose path differs only in encoding or redundant segments are
+/// treated alike, so `/admin`, `//admin` and `/public/%2e%2e/admin` all match
+/// the same route and are inspected in the same form.
+///
+/// Backends receive the normalized path, and paths with malformed
+/// percent-encoding are rejected, so turning this off keeps the raw path.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PathNormalizationConfig {
+    /// Normalize request paths (default true)
+    pub enabled: bool,
+    /// Decode percent-encoded unreserved characters (`A-Z a-z 0-9 - . _ ~`);
+    /// other escapes such as `%2F` stay encoded (default true)
+    pub decode_unreserved: bool,
+    /// Collapse repeated slashes (default true)
+    pub merge_slashes: bool,
+    /// Resolve `.` and `..` segments, never above the root (default true)
+    pub resolve_dot_segments: bool,
+    /// Lowercase the path (default false)
+    pub lowercase: bool,
+}
+
+impl Default for PathNormalizationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            decode_unreserved: true,
+            merge_slashes: true,
+            resolve_dot_segments: true,
+            lowercase: false,
+        }
+    }
+}
+
+/// Limits on the headers of inbound requests. Requests over any of them are
+/// answered with `431 Request Header Fields Too Large`.
+///
+/// Sizes count the bytes of each header's name and value.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct HeaderLimitsConfig {
+    /// Bytes of all headers together (default 65536)
+    pub max_total_bytes: usize,
+    /// Bytes of any single header (default 16384)
+    pub max_header_bytes: usize,
+    /// Number of header fields (default 100)
+    pub max_count: usize,
+}
+
+impl Default for HeaderLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_total_bytes: 64 * 1024,
+            max_header_bytes: 16 * 1024,
+            max_count: 100,
+        }
+    }
+}
+
+/// Headers allowed through proxy and load-balance routes; all others are
+/// stripped. Unset lists allow everything.
+///
+/// Entries are header names, or prefixes ending in `*` (`X-Request-*`).
+/// Framing headers always pass. A route's lists replace the global ones.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct HeaderAllowlistConfig {
+    /// Client request headers forwarded to backends
+    pub request: Option<Vec<String>>,
+    /// Backend response headers returned to clients
+    pub response: Option<Vec<String>>,
+}
+
+/// An A/B experiment assigning requests to variants by percentage.
+///
+/// The assignment is kept in a cookie so a client stays in its variant, and
+/// sent to backends in a header so applications can branch on it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ExperimentConfig {
+    /// Experiment name, used in metrics and the default cookie and header names
+    pub name: String,
+    /// Path prefixes the experiment applies to (default: all requests)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<String>,
+    /// Variants with their share of traffic; percentages add up to 100
+    pub variants: Vec<ExperimentVariant>,
+    /// Cookie holding the assigned variant (default `axon_exp_<name>`)
+    #[serde(default)]
+    pub cookie: Option<String>,
+    /// Cookie lifetime in seconds (default 30 days)
+    #[serde(default = "default_experiment_cookie_max_age")]
+    pub cookie_max_age_secs: u64,
+    /// Request header carrying the variant to backends (default
+    /// `X-Experiment-<name>`)
+    #[serde(default)]
+    pub header: Option<String>,
+}
+
+impl ExperimentConfig {
+    pub fn cookie_name(&self) -> String {
+        self.cookie
+            .clone()
+            .unwrap_or_else(|| format!("axon_exp_{}", self.name))
+    }
+
+    pub fn header_name(&self) -> String {
+        self.header
+            .clone()
+            .unwrap_or_else(|| format!("X-Experiment-{}", self.name))
+    }
+}
+
+fn default_experiment_cookie_max_age() -> u64 {
+    30 * 24 * 60 * 60
+}
+
+/// One arm of an [`ExperimentConfig`].
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ExperimentVariant {
+    pub name: String,
+    /// Percentage of newly assigned requests (0-100)
+    pub percent: u8,
+}
+
+/// `Server-Timing` response header with the gateway's share of each request.
+///
+/// Off by default: the header tells clients how long routing, WAF checks and
+/// the backend took, which is meant for your own frontends.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ServerTimingConfig {
+    /// Add the header to every response (default false)
+    pub enabled: bool,
+}
+
+/// How the client address of a request is found when the gateway sits
+/// behind proxies or load balancers.
+///
+/// A request from a trusted proxy is attributed to the address its
+/// `X-Forwarded-For` header names: the rightmost entry that isn't itself a
+/// trusted proxy. Other requests are attributed to their peer address, and
+/// their `X-Forwarded-For` is ignored. IP rate limits key on this address.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ClientIpConfig {
+    /// Proxy addresses or CIDR ranges (e.g. `10.0.0.0/8`) whose
+    /// `X-Forwarded-For` is believed
+    pub trusted_proxies: Vec<String>,
+}
+
+/// Logging and trace export.
+///
+/// Filters apply at startup and again on each reload that changes them, and
+/// can be overridden at runtime through `PUT /admin/logging`. `RUST_LOG`,
+/// when set, replaces `level` and `modules`. The output format and the OTLP
+/// exporter need a restart to change.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct TracingConfig {
+    /// Level for everything not listed in `modules` (default `info`)
+    pub level: String,
+    /// Levels for individual modules, e.g. `"axon::adapters::http_client" = "debug"`
+    pub modules: BTreeMap<String, String>,
+    /// Log output format
+    pub format: LogFormat,
+    /// Export spans to an OpenTelemetry collector
+    pub otlp: Option<TracingOtlpConfig>,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            modules: BTreeMap::new(),
+            format: LogFormat::default(),
+            otlp: None,
+        }
+    }
+}
+
+impl TracingConfig {
+    /// `level` and `modules` as an `EnvFilter` directive string
+    pub fn filter_directives(&self) -> String {
+        std::iter::once(self.level.clone())
+            .chain(
+                self.modules
+                    .iter()
+                    .map(|(module, level)| format!("{module}={level}")),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Log line format.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// `compact` when stderr is a terminal, otherwise `json`
+    #[default]
+    Auto,
+    /// One JSON object per line
+    Json,
+    /// Multi-line output with source locations, for local development
+    Pretty,
+    /// One human-readable line per event
+    Compact,
+}
+
+/// OTLP span export.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TracingOtlpConfig {
+    /// gRPC endpoint of the collector, e.g. `http://otel-collector:4317`
+    pub endpoint: String,
+    /// Metadata sent with every export, e.g. an API key
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Fraction of traces kept, from 0.0 to 1.0 (default 1.0)
+    #[serde(default = "default_sample_ratio")]
+    pub sample_ratio: f64,
+    /// Seconds before an export attempt is abandoned
+    #[serde(default = "default_export_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_sample_ratio() -> f64 {
+    1.0
+}
+
+fn default_export_timeout_secs() -> u64 {
+    10
+}
+
+/// Fix for a proxied response whose `Content-Type` matched a rule.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentTypeAction {
+    /// Replace the type with `content_type` or the one mapped from the
+    /// request path's extension
+    #[default]
+    Correct,
+    /// Remove the `Content-Type` header
+    Strip,
+}
+
+/// A rule matching proxied responses by request extension and backend type.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ContentTypeRule {
+    /// Request path extensions the rule applies to; any path when empty
+    pub extensions: Vec<String>,
+    /// Backend media types that trigger the rule (`text/*` wildcards, `none`
+    /// for a missing header); any declared type when empty
+    pub backend_types: Vec<String>,
+    pub action: ContentTypeAction,
+    /// Type set by `correct` instead of the extension mapping
+    pub content_type: Option<String>,
+}
+
+/// Body format for gateway-generated error responses.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorPageFormat {
+    /// Negotiate from the request's `Accept` header, preferring JSON
+    #[default]
+    Auto,
+    Json,
+    Html,
+    Text,
+}
+
+/// Error page templates for one status key, one per format.
+///
+/// Templates may reference `{{status}}`, `{{reason}}`, `{{message}}`,
+/// `{{request_id}}`, `{{path}}` and `{{timestamp}}`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ErrorPageTemplate {
+    pub html: Option<String>,
+    pub json: Option<String>,
+    pub text: Option<String>,
+}
+
+/// Custom bodies for errors produced by the gateway itself.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ErrorPagesConfig {
+    /// Response format; inherits the global setting (or `auto`) when unset
+    pub format: Option<ErrorPageFormat>,
+    /// Templates keyed by status code (`"404"`), class (`"5xx"`) or `"default"`
+    pub templates: HashMap<String, ErrorPageTemplate>,
+    /// Backend statuses (`"502"`) or classes (`"5xx"`) whose bodies are
+    /// replaced with these error pages; a route's list replaces the global one
+    pub intercept: Option<Vec<String>>,
+}
+
+/// How changes to a local configuration file are detected.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigWatchMode {
+    /// Filesystem notifications, switching to polling if the watcher fails
+    #[default]
+    Auto,
+    /// Filesystem notifications only
+    Notify,
+    /// Periodically hash the file and compare
+    Poll,
+}
+
+/// Hot reload change detection for file-based configuration.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ConfigWatchConfig {
+    pub mode: ConfigWatchMode,
+    /// Seconds between content checks while polling
+    pub poll_interval_secs: u64,
+}
+
+impl Default for ConfigWatchConfig {
+    fn default() -> Self {
+        Self {
+            mode: ConfigWatchMode::Auto,
+            poll_interval_secs: 5,
+        }
+    }
+}
+
+/// Middleware names accepted in a route's `middlewares` list.
+pub const BUILTIN_MIDDLEWARES: &[&str] = &[
+    "strip_prefix",
+    "security_headers",
+    "cors",
+    "request_id",
+    "compression",
+    "auth",
+];
+
+/// Settings for built-in route middlewares.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct MiddlewareConfig {
+    /// Settings for the `auth` middleware (required when a route uses it)
+    pub auth: Option<AuthMiddlewareConfig>,
+    /// Settings for the `usage` middleware (required when a route uses it)
+    pub usage: Option<UsageConfig>,
+    /// WASM filters by name; routes reference them as `wasm:<name>`
+    pub wasm: HashMap<String, WasmFilterConfig>,
+    /// JSON Schema checks by name; routes reference them as `json_schema:<name>`
+    pub json_schema: HashMap<String, JsonSchemaConfig>,
+    /// File upload policies by name; routes reference them as
+    /// `upload_policy:<name>`
+    pub upload_policy: HashMap<String, UploadPolicyConfig>,
+    /// Cookie policies by name; routes reference them as
+    /// `cookie_policy:<name>`
+    pub cookie_policy: HashMap<String, CookiePolicyConfig>,
+    /// HTTP Basic authentication settings by name; routes reference them as
+    /// `basic_auth:<name>`
+    pub basic_auth: HashMap<String, BasicAuthConfig>,
+    /// LDAP/Active Directory authentication settings by name; routes
+    /// reference them as `ldap_auth:<name>`
+    pub ldap_auth: HashMap<String, LdapAuthConfig>,
+    /// Role-based authorization policies by name; routes reference them as
+    /// `rbac:<name>`
+    pub rbac: HashMap<String, RbacPolicyConfig>,
+    /// Open Policy Agent authorization by name; routes reference them as
+    /// `opa:<name>`
+    pub opa: HashMap<String, OpaConfig>,
+    /// Headers set by the `security_headers` middleware
+    pub security_headers: SecurityHeadersConfig,
+    /// Alternative header policies; routes reference them as
+    /// `security_headers:<name>`
+    pub security_header_policies: HashMap<String, SecurityHeadersConfig>,
+}
+
+/// Response headers set (and removed) by the `security_headers` middleware.
+///
+/// Optional string headers are omitted when unset or empty.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SecurityHeadersConfig {
+    /// `Strict-Transport-Security`
+    pub hsts: Option<HstsConfig>,
+    /// `Content-Security-Policy`
+    pub content_security_policy: Option<String>,
+    /// Send the policy as `Content-Security-Policy-Report-Only` instead
+    pub content_security_policy_report_only: bool,
+    /// `Permissions-Policy`
+    pub permissions_policy: Option<String>,
+    /// `X-Frame-Options` (default `DENY`)
+    pub frame_options: Option<String>,
+    /// `Referrer-Policy` (default `strict-origin-when-cross-origin`)
+    pub referrer_policy: Option<String>,
+    /// `X-Content-Type-Options: nosniff` (default true)
+    pub content_type_options: bool,
+    /// `X-XSS-Protection: 1; mode=block` (default true)
+    pub xss_protection: bool,
+    /// Headers stripped from responses, e.g. `Server` and `X-Powered-By`
+    pub remove_headers: Vec<String>,
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        Self {
+            hsts: None,
+            content_security_policy: None,
+            content_security_policy_report_only: false,
+            permissions_policy: None,
+            frame_options: Some("DENY".to_string()),
+            referrer_policy: Some("strict-origin-when-cross-origin".to_string()),
+            content_type_options: true,
+            xss_protection: true,
+            remove_headers: Vec::new(),
+        }
+    }
+}
+
+/// HTTP Strict Transport Security settings.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HstsConfig {
+    /// `max-age` in seconds (default one year)
+    #[serde(default = "default_hsts_max_age_secs")]
+    pub max_age_secs: u64,
+    #[serde(default)]
+    pub include_subdomains: bool,
+    #[serde(default)]
+    pub preload: bool,
+}
+
+fn default_hsts_max_age_secs() -> u64 {
+    31_536_000
+}
+
+/// Static token authentication used by the `auth` route middleware.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuthMiddlewareConfig {
+    /// Accepted tokens
+    pub tokens: Vec<String>,
+    /// Header carrying the token; `Authorization` expects the `Bearer` scheme,
+    /// any other header carries the raw token
+    #[serde(default = "default_auth_header")]
+    pub header: String,
+}
+
+fn default_auth_header() -> String {
+    "Authorization".to_string()
+}
+
+/// Name of the route middleware metering usage per consumer.
+pub const USAGE_MIDDLEWARE: &str = "usage";
+
+/// Usage metering for billing API consumers.
+///
+/// Requests through routes using the `usage` middleware are counted per
+/// consumer and route, and the totals are exported as one record per
+/// consumer, route and flush window. Read at startup.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct UsageConfig {
+    /// Header identifying the consumer (e.g. an API key or tenant ID) when
+    /// no authentication middleware accepted a user
+    pub key_header: Option<String>,
+    /// How often usage records are exported
+    pub flush_interval_secs: u64,
+    /// Requests buffered for the aggregator before new ones are dropped
+    pub queue_size: usize,
+    /// Records kept for retry while the sink fails; the oldest are dropped
+    /// beyond this
+    pub max_pending_records: usize,
+    /// Where records are exported
+    pub sink: Option<UsageSinkConfig>,
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        Self {
+            key_header: None,
+            flush_interval_secs: 60,
+            queue_size: 4096,
+            max_pending_records: 100_000,
+            sink: None,
+        }
+    }
+}
+
+/// Destination for usage records.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UsageSinkConfig {
+    /// Append one JSON object per line to a file
+    File { path: String },
+    /// POST batches of records as a JSON array; any non-2xx answer is retried
+    Webhook {
+        url: String,
+        /// Extra request headers, e.g. an ingestion token
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        /// Most records sent in one request
+        #[serde(default = "default_webhook_batch_size")]
+        batch_size: usize,
+    },
+}
+
+/// Prefix marking a route middleware name as a reference to a WASM filter.
+pub const WASM_MIDDLEWARE_PREFIX: &str = "wasm:";
+
+/// A request/response filter compiled from a WebAssembly module.
+///
+/// Filters are loaded once at startup; changing them requires a restart.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WasmFilterConfig {
+    /// Path to the `.wasm` (or `.wat`) module
+    pub path: String,
+    /// Fuel (roughly, WASM instructions) available to each filter invocation
+    #[serde(default = "default_wasm_fuel")]
+    pub fuel: u64,
+    /// Maximum linear memory a filter instance may grow to, in bytes
+    #[serde(default = "default_wasm_max_memory_bytes")]
+    pub max_memory_bytes: usize,
+    /// Pass request/response bodies to the filter (buffers them in memory)
+    #[serde(default)]
+    pub include_body: bool,
+    /// Largest body buffered for the filter; larger requests are rejected with 413
+    #[serde(default = "default_wasm_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// Continue unfiltered when the filter traps or runs out of fuel instead of
+    /// answering 500
+    #[serde(default)]
+    pub fail_open: bool,
+}
+
+fn default_wasm_fuel() -> u64 {
+    10_000_000
+}
+
+fn default_wasm_max_memory_bytes() -> usize {
+    16 * 1024 * 1024
+}
+
+fn default_wasm_max_body_bytes() -> usize {
+    1024 * 1024
+}
+
+/// Prefix marking a route middleware name as a reference to a JSON Schema check.
+pub const JSON_SCHEMA_MIDDLEWARE_PREFIX: &str = "json_schema:";
+
+/// Request and/or response body validation against JSON Schema files.
+///
+/// Schemas are loaded once at startup; changing them requires a restart.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JsonSchemaConfig {
+    /// Schema file that request bodies must match
+    #[serde(default)]
+    pub request: Option<String>,
+    /// Schema file that successful (2xx) JSON response bodies must match
+    #[serde(default)]
+    pub response: Option<String>,
+    /// What to do with a body that does not match
+    #[serde(default)]
+    pub action: SchemaViolationAction,
+    /// Largest body buffered for validation; larger request bodies get 413
+    #[serde(default = "default_json_schema_max_body_bytes")]
+    pub max_body_bytes: usize,
+}
+
+fn default_json_schema_max_body_bytes() -> usize {
+    1024 * 1024
+}
+
+/// Handling of a body that fails JSON Schema validation
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaViolationAction {
+    /// Answer 400 for requests and 502 for responses
+    #[default]
+    Block,
+    /// Log the violation and pass the body through
+    Log,
+    /// Pass the body through with an `X-Schema-Violation` header
+    Annotate,
+}
+
+impl SchemaViolationAction {
+    /// Metric label for the action
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Block => "block",
+            Self::Log => "log",
+            Self::Annotate => "annotate",
+        }
+    }
+}
+
+/// Prefix marking a route middleware name as a reference to an upload policy.
+pub const UPLOAD_POLICY_MIDDLEWARE_PREFIX: &str = "upload_policy:";
+
+/// Limits on the files in `multipart/form-data` requests.
+///
+/// Unset limits are not enforced. Requests that are not multipart forms pass
+/// through unchecked.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct UploadPolicyConfig {
+    /// Allowed file media types, e.g. `image/png` or `image/*` (empty = any)
+    pub allowed_content_types: Vec<String>,
+    /// Largest allowed file, in bytes (larger files get 413)
+    pub max_file_bytes: Option<u64>,
+    /// Most files allowed in one request (more get 413)
+    pub max_files: Option<usize>,
+    /// File name extensions rejected with 415, e.g. `exe`
+    pub blocked_extensions: Vec<String>,
+    /// Largest form body buffered for checking (larger bodies get 413)
+    pub max_body_bytes: usize,
+    /// Send each file to an antivirus scanner before forwarding the request
+    pub scan: Option<UploadScanConfig>,
+}
+
+impl Default for UploadPolicyConfig {
+    fn default() -> Self {
+        Self {
+            allowed_content_types: Vec::new(),
+            max_file_bytes: None,
+            max_files: None,
+            blocked_extensions: Vec::new(),
+            max_body_bytes: 10 * 1024 * 1024,
+            scan: None,
+        }
+    }
+}
+
+/// An ICAP antivirus scanner (e.g. ClamAV behind c-icap) that uploaded files
+/// are sent to.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UploadScanConfig {
+    /// ICAP service, e.g. `icap://clamav:1344/avscan`
+    pub icap_url: String,
+    /// Seconds allowed for connecting and scanning one file
+    #[serde(default = "default_upload_scan_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Largest file sent to the scanner; larger files count as a scan failure
+    #[serde(default = "default_upload_scan_max_file_bytes")]
+    pub max_file_bytes: u64,
+    /// Forward uploads unscanned when the scanner fails (times out, is
+    /// unreachable, answers with an error) instead of answering 503
+    #[serde(default)]
+    pub fail_open: bool,
+}
+
+fn default_upload_scan_timeout_secs() -> u64 {
+    30
+}
+
+fn default_upload_scan_max_file_bytes() -> u64 {
+    25 * 1024 * 1024
+}
+
+/// Prefix marking a route middleware name as a reference to Basic
+/// authentication settings.
+pub const BASIC_AUTH_MIDDLEWARE_PREFIX: &str = "basic_auth:";
+
+/// HTTP Basic authentication against htpasswd-style password hashes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BasicAuthConfig {
+    /// Realm sent in the `WWW-Authenticate` challenge
+    pub realm: String,
+    /// Users and their bcrypt or `$apr1$` password hashes
+    pub users: HashMap<String, String>,
+    /// htpasswd file with more users; inline `users` take precedence
+    pub htpasswd_file: Option<String>,
+}
+
+impl Default for BasicAuthConfig {
+    fn default() -> Self {
+        Self {
+            realm: "Restricted".to_string(),
+            users: HashMap::new(),
+            htpasswd_file: None,
+        }
+    }
+}
+
+/// Prefix marking a route middleware name as a reference to LDAP
+/// authentication settings.
+pub const LDAP_AUTH_MIDDLEWARE_PREFIX: &str = "ldap_auth:";
+
+/// HTTP Basic credentials checked against an LDAP or Active Directory server.
+///
+/// Users are authenticated either by binding directly as `user_dn` or, with
+/// `search`, by finding their entry with a service account and binding as
+/// it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LdapAuthConfig {
+    /// `ldap://` or `ldaps://` server URL
+    pub url: String,
+    /// Upgrade `ldap://` connections with StartTLS
+    pub starttls: bool,
+    /// Realm sent in the `WWW-Authenticate` challenge
+    pub realm: String,
+    /// DN to bind as, with `{username}` replaced by the escaped user name,
+    /// e.g. `uid={username},ou=people,dc=example,dc=com` or
+    /// `{username}@corp.example.com` for Active Directory
+    pub user_dn: Option<String>,
+    /// Search for the user's entry instead of building its DN
+    pub search: Option<LdapSearchConfig>,
+    /// Attribute listing the user's groups
+    pub group_attribute: String,
+    /// Groups (DNs or common names) of which the user must be in at least one;
+    /// empty allows every authenticated user
+    pub required_groups: Vec<String>,
+    /// How long a successful authentication is remembered (0 disables)
+    pub cache_ttl_secs: u64,
+    /// Most connections kept open to the server
+    pub pool_size: usize,
+    /// Timeout for connecting and for each LDAP operation
+    pub timeout_secs: u64,
+}
+
+impl Default for LdapAuthConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            starttls: false,
+            realm: "Restricted".to_string(),
+            user_dn: None,
+            search: None,
+            group_attribute: "memberOf".to_string(),
+            required_groups: Vec::new(),
+            cache_ttl_secs: 300,
+            pool_size: 4,
+            timeout_secs: 5,
+        }
+    }
+}
+
+/// Service account search used to find a user's entry.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LdapSearchConfig {
+    pub bind_dn: String,
+    pub bind_password: String,
+    pub base_dn: String,
+    /// Filter with `{username}` replaced by the escaped user name
+    #[serde(default = "default_ldap_search_filter")]
+    pub filter: String,
+}
+
+fn default_ldap_search_filter() -> String {
+    "(uid={username})".to_string()
+}
+
+/// Prefix marking a route middleware name as a reference to an RBAC policy.
+pub const RBAC_MIDDLEWARE_PREFIX: &str = "rbac:";
+
+/// Role-based authorization of authenticated requests.
+///
+/// Identities come from `basic_auth:` / `ldap_auth:` middlewares earlier in
+/// the route's list or, without one, from trusted headers set by an
+/// authenticating proxy in front of the gateway.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RbacPolicyConfig {
+    /// Log and audit denials without enforcing them
+    pub dry_run: bool,
+    /// Trusted header carrying the user name
+    pub user_header: Option<String>,
+    /// Trusted header carrying comma-separated groups (or role claims)
+    pub groups_header: Option<String>,
+    /// Who holds each role, by role name
+    pub roles: HashMap<String, RbacRoleBinding>,
+    /// What roles may do; a request is allowed when any rule allows it
+    pub rules: Vec<RbacRule>,
+}
+
+/// Users and groups holding a role.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RbacRoleBinding {
+    /// User names; `*` is any authenticated user
+    pub users: Vec<String>,
+    /// Group DNs or names
+    pub groups: Vec<String>,
+}
+
+/// Paths and methods allowed to a set of roles.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RbacRule {
+    pub roles: Vec<String>,
+    /// Exact paths, or prefixes ending in `*` (empty = every path)
+    pub paths: Vec<String>,
+    /// Methods (empty = every method)
+    pub methods: Vec<String>,
+}
+
+/// Prefix marking a route middleware name as a reference to an OPA setting.
+pub const OPA_MIDDLEWARE_PREFIX: &str = "opa:";
+
+/// Authorization decisions delegated to an Open Policy Agent server.
+///
+/// The request's method, path, query, selected headers, client address and
+/// authenticated user are posted to the policy's Data API endpoint as
+/// `input`. The result is `true`/`false`, or an object with `allow` and
+/// optional `headers` to add to the backend request, `status` and `reason`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct OpaConfig {
+    /// Decision URL, e.g. `http://127.0.0.1:8181/v1/data/httpapi/authz`
+    pub url: String,
+    /// Request headers included in the input (lowercased names)
+    pub include_headers: Vec<String>,
+    /// Timeout for one decision
+    pub timeout_ms: u64,
+    /// How long a decision is reused for identical input (0 disables)
+    pub cache_ttl_secs: u64,
+    /// Most cached decisions
+    pub cache_max_entries: usize,
+    /// Allow requests when OPA cannot be reached or answers with an error,
+    /// instead of rejecting them with `503`
+    pub fail_open: bool,
+}
+
+impl Default for OpaConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            include_headers: Vec::new(),
+            timeout_ms: 500,
+            cache_ttl_secs: 0,
+            cache_max_entries: 10_000,
+            fail_open: false,
+        }
+    }
+}
+
+/// Prefix marking a route middleware name as a reference to a cookie policy.
+pub const COOKIE_POLICY_MIDDLEWARE_PREFIX: &str = "cookie_policy:";
+
+/// Cookie handling between clients and a route's backends.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CookiePolicyConfig {
+    /// Cookies removed from requests before they reach the backend
+    pub strip_request_cookies: Vec<String>,
+    /// Attributes added to or changed on backend `Set-Cookie` headers
+    pub set_cookie: SetCookieAttributes,
+    /// Cookies whose values are encrypted towards clients and decrypted
+    /// towards backends
+    pub encrypt: Vec<String>,
+    /// Cookies whose values are signed towards clients and verified towards
+    /// backends
+    pub sign: Vec<String>,
+    /// Base64 secret of at least 32 bytes; required by `encrypt` and `sign`
+    pub secret: Option<String>,
+    /// Previous secrets, still accepted for cookies issued before a key
+    /// rotation
+    pub retired_secrets: Vec<RetiredCookieSecret>,
+}
+
+/// A rotated-out cookie secret and the end of its grace period.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RetiredCookieSecret {
+    pub secret: String,
+    /// Cookies sealed with this secret are rejected from this time on
+    pub accept_until: chrono::DateTime<chrono::Utc>,
+}
+
+/// `Set-Cookie` attribute overrides; unset fields leave the attribute as the
+/// backend sent it.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SetCookieAttributes {
+    /// Cookies the overrides apply to (empty = all)
+    pub cookies: Vec<String>,
+    /// Add (true) or remove (false) `Secure`
+    pub secure: Option<bool>,
+    /// Add (true) or remove (false) `HttpOnly`
+    pub http_only: Option<bool>,
+    /// Set `SameSite`
+    pub same_site: Option<CookieSameSite>,
+    /// Replace `Domain`; an empty string removes it (host-only cookie)
+    pub domain: Option<String>,
+}
+
+/// `SameSite` cookie attribute
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CookieSameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl CookieSameSite {
+    /// Attribute value as sent in `Set-Cookie`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Strict => "Strict",
+            Self::Lax => "Lax",
+            Self::None => "None",
+        }
+    }
+}
+
+/// Upstream (backend) HTTP client connection pool and keep-alive tuning.
+///
+/// These settings are applied when the shared HTTP client is created at startup;
+/// changing them requires a restart to take effect.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct UpstreamConfig {
+    /// Maximum idle connections kept per backend host (None = unlimited)
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Seconds an idle pooled connection is kept before being closed (0 = never expire)
+    pub pool_idle_timeout_secs: u64,
+    /// TCP connect timeout in seconds (None = no timeout)
+    pub connect_timeout_secs: Option<u64>,
+    /// Address families tried, and in which order, for backends with both
+    /// A and AAAA records
+    pub ip_preference: IpPreference,
+    /// Milliseconds a connection attempt runs before the next address is
+    /// tried alongside it (RFC 8305 Connection Attempt Delay)
+    pub happy_eyeballs_delay_ms: u64,
+    /// TCP keepalive probe interval in seconds (None = disabled)
+    pub tcp_keepalive_secs: Option<u64>,
+    /// Socket options of backend connections; routes may override them
+    pub socket: UpstreamSocketConfig,
+    /// Negotiate HTTP/2 with TLS backends via ALPN
+    pub http2_enabled: bool,
+    /// Interval between HTTP/2 keep-alive PING frames in seconds (None = disabled)
+    pub http2_keep_alive_interval_secs: Option<u64>,
+    /// Seconds to wait for a PING acknowledgement before closing the connection
+    pub http2_keep_alive_timeout_secs: u64,
+    /// Send HTTP/2 keep-alive pings even when no requests are in flight
+    pub http2_keep_alive_while_idle: bool,
+    /// Milliseconds a request with `Expect: 100-continue` holds back its body
+    /// while waiting for the backend's `100 Continue`
+    pub expect_continue_timeout_ms: u64,
+    /// Headers added to outbound requests when absent. Proxied requests only receive
+    /// them on routes with `inject_default_headers = true`.
+    pub default_headers: HashMap<String, String>,
+    /// Headers removed from forwarded requests and backend responses, in
+    /// addition to the hop-by-hop headers that always are
+    pub strip_headers: Vec<String>,
+    /// Response timeouts of proxied requests; routes may override them
+    pub response_timeout: ResponseTimeoutConfig,
+    /// Add `default_headers` to active health check probes
+    pub default_headers_on_health_checks: bool,
+    /// SPIFFE Workload API source for backend mTLS identities
+    pub spiffe: Option<SpiffeConfig>,
+}
+
+impl Default for UpstreamConfig {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: None,
+            pool_idle_timeout_secs: 90,
+            connect_timeout_secs: None,
+            ip_preference: IpPreference::default(),
+            happy_eyeballs_delay_ms: 250,
+            tcp_keepalive_secs: None,
+            socket: UpstreamSocketConfig::default(),
+            http2_enabled: false,
+            http2_keep_alive_interval_secs: None,
+            http2_keep_alive_timeout_secs: 20,
+            http2_keep_alive_while_idle: false,
+            expect_continue_timeout_ms: 1000,
+            strip_headers: Vec::new(),
+            response_timeout: ResponseTimeoutConfig::default(),
+            default_headers: default_upstream_headers(),
+            default_headers_on_health_checks: true,
+            spiffe: None,
+        }
+    }
+}
+
+/// Address family order of backend connection attempts.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IpPreference {
+    /// Start with the family of the resolver's first address
+    #[default]
+    Auto,
+    /// Start with IPv6, then alternate with IPv4
+    Ipv6,
+    /// Start with IPv4, then alternate with IPv6
+    Ipv4,
+    /// Connect over IPv6 only
+    Ipv6Only,
+    /// Connect over IPv4 only
+    Ipv4Only,
+}
+
+/// How long a backend may take to answer a proxied request. Exceeding
+/// either limit before the response head arrives gives the client a `504`;
+/// running out of `total_secs` while the body streams aborts the response.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct ResponseTimeoutConfig {
+    /// Seconds until the response head arrives (None = no limit)
+    pub header_secs: Option<u64>,
+    /// Seconds until the response body is complete (None = no limit)
+    pub total_secs: Option<u64>,
+}
+
+impl ResponseTimeoutConfig {
+    /// These timeouts with unset limits taken from `fallback`
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            header_secs: self.header_secs.or(fallback.header_secs),
+            total_secs: self.total_secs.or(fallback.total_secs),
+        }
+    }
+}
+
+/// Socket options of new TCP connections to backends. Unset options keep
+/// the operating system's defaults.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct UpstreamSocketConfig {
+    /// Disable Nagle's algorithm (`TCP_NODELAY`) so small writes go out at once
+    pub nodelay: Option<bool>,
+    /// Send buffer size in bytes (`SO_SNDBUF`)
+    pub send_buffer_bytes: Option<usize>,
+    /// Receive buffer size in bytes (`SO_RCVBUF`)
+    pub recv_buffer_bytes: Option<usize>,
+    /// DSCP code point (0-63) marked on outgoing packets, e.g. 46 for
+    /// Expedited Forwarding; sets `IP_TOS` or `IPV6_TCLASS`
+    pub dscp: Option<u8>,
+}
+
+impl UpstreamSocketConfig {
+    /// These options with unset ones taken from `fallback`
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            nodelay: self.nodelay.or(fallback.nodelay),
+            send_buffer_bytes: self.send_buffer_bytes.or(fallback.send_buffer_bytes),
+            recv_buffer_bytes: self.recv_buffer_bytes.or(fallback.recv_buffer_bytes),
+            dscp: self.dscp.or(fallback.dscp),
+        }
+    }
+}
+
+fn default_upstream_headers() -> HashMap<String, String> {
+    [
+        ("User-Agent", "Axon-Gateway/1.0"),
+        (
+            "Accept",
+            "text/html,application/xhtml+xml,application/xml;q=0.9,image/webp,*/*;q=0.8",
+        ),
+        ("Accept-Language", "en-US,en;q=0.5"),
+        ("Cache-Control", "max-age=0"),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect()
+}
+
+/// TLS settings used when connecting to HTTPS backends of a route.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct UpstreamTlsConfig {
+    /// PEM bundle of additional CA certificates trusted alongside the native roots
+    pub ca_bundle: Option<String>,
+    /// Override the server name used for SNI and certificate hostname verification
+    pub server_name: Option<String>,
+    /// PEM client certificate chain presented to the backend (mTLS)
+    pub client_cert: Option<String>,
+    /// PEM private key matching `client_cert`
+    pub client_key: Option<String>,
+    /// Skip backend certificate verification entirely (development only!)
+    pub insecure_skip_verify: bool,
+    /// Authenticate with the gateway's SPIFFE SVID and verify the backend's SVID
+    /// against `upstream.spiffe` policy instead of web PKI
+    pub spiffe: bool,
+}
+
+/// SPIFFE Workload API integration.
+///
+/// The gateway streams its X.509 SVID and trust bundles from the Workload API,
+/// rotating them as the agent issues new ones.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SpiffeConfig {
+    /// Workload API endpoint (`unix:///path/to/agent.sock`)
+    pub workload_api_socket: String,
+    /// Trust domains accepted for backend SVIDs (empty = the gateway's own trust domain)
+    pub trust_domains: Vec<String>,
+    /// Exact SPIFFE IDs accepted for backends (empty = any ID in an accepted trust domain)
+    pub allowed_ids: Vec<String>,
+    /// Seconds to wait before reconnecting after the Workload API stream fails
+    pub reconnect_backoff_secs: u64,
+}
+
+impl Default for SpiffeConfig {
+    fn default() -> Self {
+        Self {
+            workload_api_socket: "unix:///tmp/spire-agent/public/api.sock".to_string(),
+            trust_domains: Vec::new(),
+            allowed_ids: Vec::new(),
+            reconnect_backoff_secs: 5,
+        }
+    }
+}
+
+fn default_status_code() -> u16 {
+    429
+}
+
+fn default_message() -> String {
+    "Too Many Requests".to_string()
+}
+
+/// Rate limiting discriminator for how keys are derived.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitBy {
+    Ip,
+    Header,
+    Route,
+    /// A key built from several request attributes (see `key`)
+    Composite,
+}
+
+impl RateLimitBy {
+    /// Lowercase name, used as a limit's default metrics label.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitBy::Ip => "ip",
+            RateLimitBy::Header => "header",
+            RateLimitBy::Route => "route",
+            RateLimitBy::Composite => "composite",
+        }
+    }
+}
+
+/// Request attribute contributing to a composite rate limit key.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKeyPart {
+    Ip,
+    Path,
+    Method,
+    Host,
+    /// The value of `header_name`
+    Header,
+}
+
+/// Algorithm used to enforce the quota semantics.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitAlgorithm {
+    TokenBucket,
+    FixedWindow,
+    SlidingWindow,
+}
+
+/// Behaviour when a key could not be extracted (missing IP/header etc.).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingKeyPolicy {
+    Allow,
+    Deny,
+}
+
+fn default_on_missing_key() -> MissingKeyPolicy {
+    MissingKeyPolicy::Allow
+}
+
+/// Per‑route rate limit configuration.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RateLimitConfig {
+    /// Label for this limit in metrics and audit records; defaults to `by`
+    #[serde(default)]
+    pub name: Option<String>,
+    pub by: RateLimitBy,
+    #[serde(default)]
+    pub header_name: Option<String>, // Should be Some if by == Header
+    /// Attributes combined into the key when `by = "composite"`
+    #[serde(default)]
+    pub key: Vec<RateLimitKeyPart>,
+    pub requests: u64,
+    pub period: String, // Parsed by humantime, e.g., "1s", "5m", "1h"
+    #[serde(default = "default_status_code")]
+    pub status_code: u16,
+    #[serde(default = "default_message")]
+    pub message: String,
+    #[serde(default = "default_rate_limit_algorithm")]
+    pub algorithm: RateLimitAlgorithm,
+    #[serde(default = "default_on_missing_key")]
+    pub on_missing_key: MissingKeyPolicy,
+    /// Most keys (client IPs or header values) tracked by a keyed limiter;
+    /// the least recently used are evicted beyond this
+    #[serde(default = "default_rate_limit_max_keys")]
+    pub max_keys: usize,
+    /// Drop keys not seen for this long (humantime); defaults to the time a
+    /// key needs to refill its whole burst
+    #[serde(default)]
+    pub key_ttl: Option<String>,
+}
+
+impl RateLimitConfig {
+    /// The configured `name`, or the key kind when unnamed.
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(self.by.as_str())
+    }
+}
+
+/// A route's rate limits, checked in order.
+///
+/// Written either as one `[routes."/api".rate_limit]` table or as an array
+/// of `[[routes."/api".rate_limit]]` tables.
+#[derive(Debug, Clone)]
+pub struct RateLimits(pub Vec<RateLimitConfig>);
+
+impl RateLimits {
+    pub fn iter(&self) -> impl Iterator<Item = &RateLimitConfig> {
+        self.0.iter()
+    }
+}
+
+impl From<RateLimitConfig> for RateLimits {
+    fn from(config: RateLimitConfig) -> Self {
+        RateLimits(vec![config])
+    }
+}
+
+impl Serialize for RateLimits {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self.0.as_slice() {
+            [single] => single.serialize(serializer),
+            limits => limits.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RateLimits {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::{self, SeqAccess, Visitor};
+
+        struct RateLimitsVisitor;
+
+        impl<'de> Visitor<'de> for RateLimitsVisitor {
+            type Value = RateLimits;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a rate limit or array of rate limits")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut limits = Vec::new();
+                while let Some(limit) = seq.next_element()? {
+                    limits.push(limit);
+                }
+                Ok(RateLimits(limits))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                let limit =
+                    RateLimitConfig::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                Ok(RateLimits(vec![limit]))
+            }
+        }
+
+        deserializer.deserialize_any(RateLimitsVisitor)
+    }
+}
+
+fn default_rate_limit_max_keys() -> usize {
+    100_000
+}
+
+fn default_rate_limit_algorithm() -> RateLimitAlgorithm {
+    RateLimitAlgorithm::TokenBucket
+}
+
+/// The `path` label of a route's request metrics. By default it is the
+/// route prefix (plus `@host` for host-specific routes), which keeps the
+/// number of series bounded.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct RouteMetricsConfig {
+    /// Templates such as `/users/{id}/orders`; a request is labelled with
+    /// the first one its path matches, otherwise with the route.
+    pub path_templates: Vec<String>,
+    /// Label with the raw request path. Only for routes with a small, fixed
+    /// set of paths: every distinct path becomes a new series.
+    pub raw_path: bool,
+}
+
+/// Recording of a route's backend exchanges for debugging.
+///
+/// The last `capacity` request/response pairs, as sent to and received from
+/// the backend, are kept in memory and served by `GET /admin/tap`. Bodies
+/// are cut off after `max_body_bytes`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct TapConfig {
+    /// Exchanges kept; older ones are dropped (default 20)
+    pub capacity: usize,
+    /// Bytes of each body kept (default 4096)
+    pub max_body_bytes: usize,
+    /// Headers recorded as `[redacted]` (case-insensitive)
+    pub redact_headers: Vec<String>,
+}
+
+impl Default for TapConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 20,
+            max_body_bytes: 4096,
+            redact_headers: [
+                "authorization",
+                "proxy-authorization",
+                "cookie",
+                "set-cookie",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+/// Retries of failed backend requests on a proxy or load-balanced route.
+///
+/// A request is sent again when its backend cannot be reached, times out or
+/// answers with one of `retry_on`. Each retry goes to a backend that has not
+/// been tried yet when the route has one. Idempotent methods are always
+/// retried; others as `non_idempotent` allows. Requests are only retried when
+/// their body is small enough to keep.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Retries after the first attempt (default 2)
+    pub attempts: u32,
+    /// Backend statuses that are retried (default 502, 503, 504)
+    pub retry_on: Vec<u16>,
+    /// Delay before the first retry in milliseconds, doubled for each further
+    /// one (default 25)
+    pub backoff_ms: u64,
+    /// Largest request body kept for sending again; requests with larger or
+    /// unknown-length bodies are not retried (default 64 KiB)
+    pub max_body_bytes: usize,
+    /// Limit on retries relative to the route's traffic
+    pub budget: RetryBudgetConfig,
+    /// When POST, PATCH and other non-idempotent requests are retried
+    pub non_idempotent: NonIdempotentRetry,
+    /// Header carrying the client's idempotency key (default
+    /// `Idempotency-Key`)
+    pub idempotency_header: String,
+    /// Add a generated key to non-idempotent requests that have none, so the
+    /// backend can recognise retried attempts
+    pub generate_idempotency_key: bool,
+}
+
+/// Retry policy for requests whose method is not idempotent
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NonIdempotentRetry {
+    /// Never retry them
+    Never,
+    /// Retry them when they carry an idempotency key
+    #[default]
+    WithKey,
+    /// Retry them like idempotent requests
+    Always,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            attempts: 2,
+            retry_on: vec![502, 503, 504],
+            backoff_ms: 25,
+            max_body_bytes: 64 * 1024,
+            budget: RetryBudgetConfig::default(),
+            non_idempotent: NonIdempotentRetry::default(),
+            idempotency_header: "Idempotency-Key".to_string(),
+            generate_idempotency_key: false,
+        }
+    }
+}
+
+/// Share of a route's requests that may be retries.
+///
+/// Over the last `window_secs`, retries may make up at most `ratio` of the
+/// route's requests, and `min_retries` are always allowed so routes with
+/// little traffic can still retry.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryBudgetConfig {
+    /// Retries allowed per request in the window (default 0.2)
+    pub ratio: f64,
+    /// Retries allowed in the window regardless of `ratio` (default 10)
+    pub min_retries: u32,
+    /// Length of the sliding window in seconds (default 10)
+    pub window_secs: u64,
+}
+
+impl Default for RetryBudgetConfig {
+    fn default() -> Self {
+        Self {
+            ratio: 0.2,
+            min_retries: 10,
+            window_secs: 10,
+        }
+    }
+}
+
+/// Backup pool for a proxy or load-balanced route.
+///
+/// Requests go to `targets` when none of the route's own backends is
+/// healthy, and are sent there again when the route's backend cannot be
+/// reached, times out or answers with one of `on_status`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FallbackConfig {
+    /// Backup backends, e.g. a maintenance page service or another region
+    pub targets: Vec<String>,
+    /// Backend statuses that send the request to the backup pool (default
+    /// 502, 503, 504)
+    #[serde(default = "default_fallback_on_status")]
+    pub on_status: Vec<u16>,
+    /// Largest request body kept for sending again; requests with larger or
+    /// unknown-length bodies only fail over when no backend is healthy
+    /// (default 64 KiB)
+    #[serde(default = "default_fallback_max_body_bytes")]
+    pub max_body_bytes: usize,
+}
+
+fn default_fallback_on_status() -> Vec<u16> {
+    vec![502, 503, 504]
+}
+
+fn default_fallback_max_body_bytes() -> usize {
+    64 * 1024
+}
+
+/// Rewrites a backend status, and optionally the body, before the response
+/// reaches the client, e.g. to turn a backend 500 into a 503 with a friendly
+/// JSON error, or to hide the details of a 404.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StatusMapping {
+    /// Backend statuses the rule applies to
+    pub from: Vec<u16>,
+    /// Status sent to the client (default: the backend's)
+    #[serde(default)]
+    pub to: Option<u16>,
+    /// Replace the body with this text
+    #[serde(default)]
+    pub set_text: Option<String>,
+    /// Replace the body with this JSON value
+    #[serde(default)]
+    pub set_json: Option<serde_json::Value>,
+}
+
+/// Tunneling of connections that switch away from HTTP on a proxy or
+/// load-balance route. After the backend accepts, bytes are copied both ways
+/// without being interpreted.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct TunnelConfig {
+    /// `Upgrade` protocols passed to the backend (case-insensitive, `*` for
+    /// any)
+    pub protocols: Vec<String>,
+    /// Answer `CONNECT` by opening a TCP connection to the selected target
+    pub connect: bool,
+    /// Close a tunnel after this many seconds without traffic either way
+    pub idle_timeout_secs: u64,
+    /// Close a tunnel after this many seconds in total (None = no limit)
+    pub max_session_secs: Option<u64>,
+}
+
+impl Default for TunnelConfig {
+    fn default() -> Self {
+        Self {
+            protocols: Vec::new(),
+            connect: false,
+            idle_timeout_secs: 300,
+            max_session_secs: None,
+        }
+    }
+}
+
+/// Route definitions (tagged enum) describing how incoming request paths are handled.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum RouteConfig {
+    Static {
+        root: String,
+        /// Optional host header to match (e.g., "api.example.com")
+        #[serde(default)]
+        host: Option<String>,
+        /// Evaluation order among routes matching the same request; higher
+        /// goes first, and routes without one count as 0
+        #[serde(default)]
+        priority: Option<i32>,
+        rate_limit: Option<RateLimits>,
+        #[serde(default)]
+        middlewares: Vec<String>,
+        /// Error page overrides for this route
+        #[serde(default)]
+        error_pages: Option<ErrorPagesConfig>,
+        /// Refuse this route over HTTP/3 with 421, so clients retry over TCP
+        #[serde(default)]
+        disable_http3: bool,
+        /// Log and count requests taking longer than this many milliseconds
+        #[serde(default)]
+        slow_request_threshold_ms: Option<u64>,
+        /// Label request metrics by path template or raw path instead of the route
+        #[serde(default)]
+        metrics: Option<RouteMetricsConfig>,
+        /// File served for directory requests; falls back to `static_files.index_file`
+        #[serde(default)]
+        index_file: Option<String>,
+        /// Serve the root index file for unmatched GET/HEAD paths (single-page apps)
+        #[serde(default)]
+        spa_fallback: bool,
+        /// File (relative to `root`) served with status 404 for missing paths
+        #[serde(default)]
+        not_found_page: Option<String>,
+        /// Generate listings for directories without an index file; falls back
+        /// to `static_files.directory_listing`
+        #[serde(default)]
+        directory_listing: Option<bool>,
+        /// Directory listing body format; falls back to
+        /// `static_files.listing_format`
+        #[serde(default)]
+        listing_format: Option<ListingFormat>,
+        /// `Cache-Control` value for successful responses; falls back to
+        /// `static_files.cache_control`
+        #[serde(default)]
+        cache_control: Option<String>,
+        /// Serve precompressed `.br` / `.gz` siblings; falls back to
+        /// `static_files.precompressed`
+        #[serde(default)]
+        precompressed: Option<bool>,
+        /// Entity tag generation; falls back to `static_files.etag`
+        #[serde(default)]
+        etag: Option<EtagMode>,
     },
     Redirect {
         target: String,
         /// Optional host header to match (e.g., "api.example.com")
         #[serde(default)]
         host: Option<String>,
+        /// Evaluation order among routes matching the same request; higher
+        /// goes first, and routes without one count as 0
+        #[serde(default)]
+        priority: Option<i32>,
         status_code: Option<u16>,
-        rate_limit: Option<RateLimitConfig>,
+        rate_limit: Option<RateLimits>,
         #[serde(default)]
         middlewares: Vec<String>,
+        /// Error page overrides for this route
+        #[serde(default)]
+        error_pages: Option<ErrorPagesConfig>,
+        /// Refuse this route over HTTP/3 with 421, so clients retry over TCP
+        #[serde(default)]
+        disable_http3: bool,
     },
     Proxy {
         target: String,
         /// Optional host header to match (e.g., "api.example.com")
         #[serde(default)]
         host: Option<String>,
+        /// Evaluation order among routes matching the same request; higher
+        /// goes first, and routes without one count as 0
+        #[serde(default)]
+        priority: Option<i32>,
         path_rewrite: Option<String>,
-        rate_limit: Option<RateLimitConfig>,
+        rate_limit: Option<RateLimits>,
         #[serde(default)]
         request_headers: Option<HeaderActions>,
         #[serde(default)]
@@ -648,15 +3370,87 @@ pub enum RouteConfig {
         response_body: Option<BodyActions>,
         #[serde(default)]
         middlewares: Vec<String>,
+        /// TLS settings for HTTPS backends (custom CA, SNI override, client certificate)
+        #[serde(default)]
+        upstream_tls: Option<UpstreamTlsConfig>,
+        /// Backend response timeouts, overriding `upstream.response_timeout`
+        #[serde(default)]
+        response_timeout: Option<ResponseTimeoutConfig>,
+        /// Backend socket options, overriding those of `upstream.socket`
+        #[serde(default)]
+        upstream_socket: Option<UpstreamSocketConfig>,
+        /// Keep original HTTP/1 header name casing on forwarded requests and responses
+        #[serde(default)]
+        preserve_header_case: bool,
+        /// Add `upstream.default_headers` to forwarded requests that lack them
+        #[serde(default)]
+        inject_default_headers: bool,
+        /// Gateway-served path under the route prefix reporting this route's backend health
+        #[serde(default)]
+        health_endpoint: Option<String>,
+        /// Error page overrides for this route
+        #[serde(default)]
+        error_pages: Option<ErrorPagesConfig>,
+        /// Refuse this route over HTTP/3 with 421, so clients retry over TCP
+        #[serde(default)]
+        disable_http3: bool,
+        /// Log and count requests taking longer than this many milliseconds
+        #[serde(default)]
+        slow_request_threshold_ms: Option<u64>,
+        /// Label request metrics by path template or raw path instead of the route
+        #[serde(default)]
+        metrics: Option<RouteMetricsConfig>,
+        /// Record recent backend exchanges for debugging
+        #[serde(default)]
+        tap: Option<TapConfig>,
+        /// Retry failed backend requests
+        #[serde(default)]
+        retry: Option<RetryConfig>,
+        /// Backup backends used when the route's own backends fail
+        #[serde(default)]
+        fallback: Option<FallbackConfig>,
+        /// Backend statuses rewritten for the client; the first matching rule
+        /// applies
+        #[serde(default)]
+        status_map: Vec<StatusMapping>,
+        /// Byte tunnels for `Upgrade` protocols other than WebSocket, and for
+        /// `CONNECT`
+        #[serde(default)]
+        tunnel: Option<TunnelConfig>,
+        /// Backend groups chosen by the client's Accept-Language
+        #[serde(default)]
+        locale: Option<LocaleRoutingConfig>,
+        /// Suppress redelivered requests (webhooks) within a time window
+        #[serde(default)]
+        dedupe: Option<DedupeConfig>,
+        /// Script mapping for `fastcgi://` targets
+        #[serde(default)]
+        fastcgi: Option<FastCgiConfig>,
+        /// Map backend URLs in `Location` and `Link` headers to public ones
+        #[serde(default)]
+        location_rewrite: Option<LocationRewriteConfig>,
+        /// Headers passed to and from the backend; replaces the global lists
+        #[serde(default)]
+        header_allowlist: Option<HeaderAllowlistConfig>,
+        /// Time windows switching the route off or to other targets
+        #[serde(default)]
+        schedule: Option<RouteScheduleConfig>,
+        /// Share one backend request among identical concurrent GET/HEAD requests
+        #[serde(default)]
+        coalesce: Option<CoalesceConfig>,
     },
     LoadBalance {
         targets: Vec<String>,
         /// Optional host header to match (e.g., "api.example.com")
         #[serde(default)]
         host: Option<String>,
+        /// Evaluation order among routes matching the same request; higher
+        /// goes first, and routes without one count as 0
+        #[serde(default)]
+        priority: Option<i32>,
         strategy: LoadBalanceStrategy,
         path_rewrite: Option<String>,
-        rate_limit: Option<RateLimitConfig>,
+        rate_limit: Option<RateLimits>,
         #[serde(default)]
         request_headers: Option<HeaderActions>,
         #[serde(default)]
@@ -667,14 +3461,96 @@ pub enum RouteConfig {
         response_body: Option<BodyActions>,
         #[serde(default)]
         middlewares: Vec<String>,
+        /// TLS settings for HTTPS backends (custom CA, SNI override, client certificate)
+        #[serde(default)]
+        upstream_tls: Option<UpstreamTlsConfig>,
+        /// Backend response timeouts, overriding `upstream.response_timeout`
+        #[serde(default)]
+        response_timeout: Option<ResponseTimeoutConfig>,
+        /// Backend socket options, overriding those of `upstream.socket`
+        #[serde(default)]
+        upstream_socket: Option<UpstreamSocketConfig>,
+        /// Keep original HTTP/1 header name casing on forwarded requests and responses
+        #[serde(default)]
+        preserve_header_case: bool,
+        /// Add `upstream.default_headers` to forwarded requests that lack them
+        #[serde(default)]
+        inject_default_headers: bool,
+        /// Canary group receiving a share of traffic, with optional automatic rollback
+        #[serde(default)]
+        canary: Option<CanaryConfig>,
+        /// Gateway-served path under the route prefix reporting this route's backend health
+        #[serde(default)]
+        health_endpoint: Option<String>,
+        /// Error page overrides for this route
+        #[serde(default)]
+        error_pages: Option<ErrorPagesConfig>,
+        /// Refuse this route over HTTP/3 with 421, so clients retry over TCP
+        #[serde(default)]
+        disable_http3: bool,
+        /// Log and count requests taking longer than this many milliseconds
+        #[serde(default)]
+        slow_request_threshold_ms: Option<u64>,
+        /// Label request metrics by path template or raw path instead of the route
+        #[serde(default)]
+        metrics: Option<RouteMetricsConfig>,
+        /// Record recent backend exchanges for debugging
+        #[serde(default)]
+        tap: Option<TapConfig>,
+        /// Retry failed backend requests
+        #[serde(default)]
+        retry: Option<RetryConfig>,
+        /// Backup backends used when the route's own backends fail
+        #[serde(default)]
+        fallback: Option<FallbackConfig>,
+        /// Backend statuses rewritten for the client; the first matching rule
+        /// applies
+        #[serde(default)]
+        status_map: Vec<StatusMapping>,
+        /// Byte tunnels for `Upgrade` protocols other than WebSocket, and for
+        /// `CONNECT`
+        #[serde(default)]
+        tunnel: Option<TunnelConfig>,
+        /// Backend groups chosen by the client's Accept-Language
+        #[serde(default)]
+        locale: Option<LocaleRoutingConfig>,
+        /// Suppress redelivered requests (webhooks) within a time window
+        #[serde(default)]
+        dedupe: Option<DedupeConfig>,
+        /// Script mapping for `fastcgi://` targets
+        #[serde(default)]
+        fastcgi: Option<FastCgiConfig>,
+        /// Map backend URLs in `Location` and `Link` headers to public ones
+        #[serde(default)]
+        location_rewrite: Option<LocationRewriteConfig>,
+        /// Headers passed to and from the backend; replaces the global lists
+        #[serde(default)]
+        header_allowlist: Option<HeaderAllowlistConfig>,
+        /// Time windows switching the route off or to other targets
+        #[serde(default)]
+        schedule: Option<RouteScheduleConfig>,
+        /// Share one backend request among identical concurrent GET/HEAD requests
+        #[serde(default)]
+        coalesce: Option<CoalesceConfig>,
+        /// Send a second request to another backend when the first is slow
+        /// (boxed to keep the route variants close in size)
+        #[serde(default)]
+        hedge: Option<Box<HedgeConfig>>,
+        /// Seconds over which a recovered backend's traffic share ramps up linearly
+        #[serde(default)]
+        slow_start_secs: Option<u64>,
     },
     Websocket {
         target: String,
         /// Optional host header to match (e.g., "ws.example.com")
         #[serde(default)]
         host: Option<String>,
+        /// Evaluation order among routes matching the same request; higher
+        /// goes first, and routes without one count as 0
+        #[serde(default)]
+        priority: Option<i32>,
         path_rewrite: Option<String>,
-        rate_limit: Option<RateLimitConfig>,
+        rate_limit: Option<RateLimits>,
         /// Maximum WebSocket frame size (in bytes)
         #[serde(default)]
         max_frame_size: Option<usize>,
@@ -684,14 +3560,122 @@ pub enum RouteConfig {
         /// Idle timeout in seconds (closes connection if no frame seen in either direction)
         #[serde(default)]
         idle_timeout_secs: Option<u64>,
+        /// Maximum session lifetime in seconds; longer sessions are closed
+        #[serde(default)]
+        max_session_secs: Option<u64>,
+        /// Close code sent when `max_session_secs` elapses (default 1001)
+        #[serde(default)]
+        session_close_code: Option<u16>,
+        /// Close reason sent when `max_session_secs` elapses
+        #[serde(default)]
+        session_close_reason: Option<String>,
         /// Allowed subprotocols (Sec-WebSocket-Protocol negotiation)
         #[serde(default)]
         subprotocols: Option<Vec<String>>,
         #[serde(default)]
         middlewares: Vec<String>,
+        /// Error page overrides for this route
+        #[serde(default)]
+        error_pages: Option<ErrorPagesConfig>,
+        /// Refuse this route over HTTP/3 with 421, so clients retry over TCP
+        #[serde(default)]
+        disable_http3: bool,
     },
 }
 
+impl RouteConfig {
+    /// The route's `priority`, if it sets one
+    pub fn priority(&self) -> Option<i32> {
+        match self {
+            RouteConfig::Static { priority, .. }
+            | RouteConfig::Redirect { priority, .. }
+            | RouteConfig::Proxy { priority, .. }
+            | RouteConfig::LoadBalance { priority, .. }
+            | RouteConfig::Websocket { priority, .. } => *priority,
+        }
+    }
+
+    /// A proxy route to `target` with every optional setting unset
+    pub fn proxy(target: impl Into<String>) -> Self {
+        RouteConfig::Proxy {
+            target: target.into(),
+            host: None,
+            priority: None,
+            path_rewrite: None,
+            rate_limit: None,
+            request_headers: None,
+            response_headers: None,
+            request_body: None,
+            response_body: None,
+            middlewares: Vec::new(),
+            upstream_tls: None,
+            response_timeout: None,
+            upstream_socket: None,
+            preserve_header_case: false,
+            inject_default_headers: false,
+            health_endpoint: None,
+            error_pages: None,
+            disable_http3: false,
+            slow_request_threshold_ms: None,
+            metrics: None,
+            tap: None,
+            retry: None,
+            fallback: None,
+            status_map: Vec::new(),
+            tunnel: None,
+            locale: None,
+            dedupe: None,
+            fastcgi: None,
+            location_rewrite: None,
+            header_allowlist: None,
+            schedule: None,
+            coalesce: None,
+        }
+    }
+
+    /// A load-balanced route over `targets` with every optional setting unset
+    pub fn load_balance(targets: Vec<String>, strategy: LoadBalanceStrategy) -> Self {
+        RouteConfig::LoadBalance {
+            targets,
+            host: None,
+            priority: None,
+            strategy,
+            path_rewrite: None,
+            rate_limit: None,
+            request_headers: None,
+            response_headers: None,
+            request_body: None,
+            response_body: None,
+            middlewares: Vec::new(),
+            upstream_tls: None,
+            response_timeout: None,
+            upstream_socket: None,
+            preserve_header_case: false,
+            inject_default_headers: false,
+            canary: None,
+            health_endpoint: None,
+            error_pages: None,
+            disable_http3: false,
+            slow_request_threshold_ms: None,
+            metrics: None,
+            tap: None,
+            retry: None,
+            fallback: None,
+            status_map: Vec::new(),
+            tunnel: None,
+            locale: None,
+            dedupe: None,
+            fastcgi: None,
+            location_rewrite: None,
+            header_allowlist: None,
+            schedule: None,
+            coalesce: None,
+            hedge: None,
+            slow_start_secs: None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum LoadBalanceStrategy {
@@ -703,6 +3687,330 @@ pub enum LoadBalanceStrategy {
     LeastConnections,
 }
 
+/// Canary backend group for a load-balanced route.
+///
+/// `weight` percent of requests go to the canary targets; the remainder use the
+/// route's regular (stable) targets.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CanaryConfig {
+    pub targets: Vec<String>,
+    /// Percentage of traffic (0-100) sent to the canary group
+    pub weight: u8,
+    #[serde(default)]
+    pub rollback: Option<CanaryRollbackConfig>,
+}
+
+/// Backend groups selected by the primary language of `Accept-Language`.
+///
+/// Requests whose preferred languages match no group use the route's regular
+/// targets.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LocaleRoutingConfig {
+    /// Primary language subtag (e.g. `de`) to backend targets
+    pub groups: HashMap<String, Vec<String>>,
+    /// Header carrying the matched language to the backend
+    #[serde(default = "default_locale_header")]
+    pub header: String,
+}
+
+fn default_locale_header() -> String {
+    "X-Matched-Language".to_string()
+}
+
+/// Time windows in which a route is switched off, switched on, or served by
+/// other targets.
+///
+/// Windows are checked in order at request time and the first one covering
+/// the current time applies. A switched-off route is skipped by routing, as
+/// if it were not configured.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RouteScheduleConfig {
+    /// UTC offset the windows' times are in: `UTC` (default) or `+HH:MM`
+    #[serde(default = "default_schedule_timezone")]
+    pub timezone: String,
+    /// Whether the route serves requests when no window applies (default true)
+    #[serde(default = "default_schedule_active")]
+    pub active_outside_windows: bool,
+    pub windows: Vec<ScheduleWindow>,
+}
+
+fn default_schedule_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_schedule_active() -> bool {
+    true
+}
+
+/// A recurring daily window, an absolute RFC 3339 range, or both, in which
+/// case both must cover the current time.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ScheduleWindow {
+    /// Weekdays (`mon` to `sun`) the daily window starts on; empty means every day
+    pub days: Vec<String>,
+    /// Daily start time (`HH:MM`)
+    pub start: Option<String>,
+    /// Daily end time (`HH:MM`); an end at or before `start` runs past midnight
+    pub end: Option<String>,
+    /// Start of the absolute range (RFC 3339)
+    pub from: Option<String>,
+    /// End of the absolute range (RFC 3339), exclusive
+    pub until: Option<String>,
+    /// Serve the route during the window (default false: switched off)
+    pub active: bool,
+    /// Targets serving the route during the window instead of its own;
+    /// implies `active`
+    pub targets: Vec<String>,
+}
+
+/// Rewriting of backend URLs in response headers.
+///
+/// URLs under any route target (or `internal_origins`) become root-relative
+/// paths under the route prefix, undoing `path_rewrite`, or absolute URLs
+/// under `public_url` when it is set.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LocationRewriteConfig {
+    /// Response headers to rewrite
+    #[serde(default = "default_location_rewrite_headers")]
+    pub headers: Vec<String>,
+    /// Public origin, optionally with a path, e.g. `https://www.example.com`
+    #[serde(default)]
+    pub public_url: Option<String>,
+    /// Other base URLs the backends call themselves, e.g. `http://app.internal`
+    #[serde(default)]
+    pub internal_origins: Vec<String>,
+}
+
+impl Default for LocationRewriteConfig {
+    fn default() -> Self {
+        Self {
+            headers: default_location_rewrite_headers(),
+            public_url: None,
+            internal_origins: Vec::new(),
+        }
+    }
+}
+
+fn default_location_rewrite_headers() -> Vec<String> {
+    ["Location", "Content-Location", "Link"]
+        .map(String::from)
+        .to_vec()
+}
+
+/// How requests to `fastcgi://host:port` targets (e.g. PHP-FPM) name the
+/// script to run.
+///
+/// The path up to and including the first segment ending in
+/// `script_extension` is the script (`SCRIPT_NAME`) and the rest is
+/// `PATH_INFO`; paths ending in `/` run `index`. With `script` set, every
+/// request runs that front controller instead. `SCRIPT_FILENAME` is the
+/// script under `root`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct FastCgiConfig {
+    /// Document root on the FastCGI server
+    pub root: String,
+    /// Script run for directory paths
+    #[serde(default = "default_fastcgi_index")]
+    pub index: String,
+    /// Front controller run for every request, e.g. `/index.php`
+    #[serde(default)]
+    pub script: Option<String>,
+    /// Extension ending the script part of a path
+    #[serde(default = "default_fastcgi_script_extension")]
+    pub script_extension: String,
+    /// Extra FastCGI params, overriding the generated ones
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+}
+
+fn default_fastcgi_index() -> String {
+    "index.php".to_string()
+}
+
+fn default_fastcgi_script_extension() -> String {
+    ".php".to_string()
+}
+
+/// Duplicate suppression for webhook-receiving routes.
+///
+/// Requests are keyed by the listed headers and, optionally, a hash of the
+/// body. A repeat of a key within `ttl_secs` is answered by the gateway
+/// without reaching the backend. Safe methods (GET, HEAD, OPTIONS) are never
+/// deduplicated.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DedupeConfig {
+    /// Headers identifying a delivery (e.g. `X-GitHub-Delivery`)
+    #[serde(default)]
+    pub headers: Vec<String>,
+    /// Include a SHA-256 of the request body in the key
+    #[serde(default = "default_dedupe_include_body")]
+    pub include_body: bool,
+    /// How long a key is remembered, in seconds
+    #[serde(default = "default_dedupe_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Status returned for duplicates
+    #[serde(default = "default_dedupe_status_code")]
+    pub status_code: u16,
+    /// Body returned for duplicates
+    #[serde(default)]
+    pub response_body: Option<String>,
+    /// Largest body buffered for hashing; larger requests are rejected with 413
+    #[serde(default = "default_dedupe_max_body_size")]
+    pub max_body_size: usize,
+    /// Upper bound on remembered keys; when full, new keys are not remembered
+    #[serde(default = "default_dedupe_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_dedupe_include_body() -> bool {
+    true
+}
+
+fn default_dedupe_ttl_secs() -> u64 {
+    300
+}
+
+fn default_dedupe_status_code() -> u16 {
+    200
+}
+
+fn default_dedupe_max_body_size() -> usize {
+    1024 * 1024
+}
+
+fn default_dedupe_max_entries() -> usize {
+    100_000
+}
+
+/// Request coalescing (singleflight) for GET and HEAD requests.
+///
+/// While a request is in flight, identical requests wait for its response
+/// instead of reaching the backend. Requests are identical when method, host,
+/// path, query and the `key_headers` values match.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct CoalesceConfig {
+    /// Request headers that must also match; anything the response may vary
+    /// on or that identifies the user belongs here
+    #[serde(default = "default_coalesce_key_headers")]
+    pub key_headers: Vec<String>,
+    /// Requests waiting on one backend request; later ones are sent on their own
+    #[serde(default = "default_coalesce_max_waiters")]
+    pub max_waiters: usize,
+    /// Requests carrying this header are never coalesced
+    #[serde(default)]
+    pub bypass_header: Option<String>,
+    /// Largest response body shared with waiters; waiters send their own
+    /// request when the response is larger or has no `Content-Length`
+    #[serde(default = "default_coalesce_max_body_bytes")]
+    pub max_body_bytes: usize,
+}
+
+fn default_coalesce_key_headers() -> Vec<String> {
+    [
+        "Accept",
+        "Accept-Encoding",
+        "Accept-Language",
+        "Authorization",
+        "Cookie",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+fn default_coalesce_max_waiters() -> usize {
+    1000
+}
+
+fn default_coalesce_max_body_bytes() -> usize {
+    1024 * 1024
+}
+
+/// Hedged requests on a load-balanced route.
+///
+/// When the first backend has not answered within the route's recent
+/// `percentile` latency, the request is sent to another healthy backend as
+/// well and whichever answers first is used; the other request is cancelled.
+/// Only idempotent requests whose body fits in `max_body_bytes` are hedged.
+/// `PUT` and `DELETE` requests without an idempotency key get a generated
+/// one, so a backend that deduplicates by key applies only one of the copies.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct HedgeConfig {
+    /// Latency percentile of recent responses after which a hedge is sent
+    /// (default 95.0)
+    pub percentile: f64,
+    /// Lower bound on the hedge delay in milliseconds (default 10)
+    pub min_delay_ms: u64,
+    /// Upper bound on the hedge delay in milliseconds, also used until
+    /// `min_samples` responses have been seen (default 1000)
+    pub max_delay_ms: u64,
+    /// Responses needed before the percentile is trusted (default 20)
+    pub min_samples: usize,
+    /// Recent response latencies the percentile is taken over (default 1000)
+    pub window: usize,
+    /// Largest request body kept for the hedge; larger or unknown-length
+    /// bodies are not hedged (default 64 KiB)
+    pub max_body_bytes: usize,
+    /// Limit on hedges relative to the route's traffic (default ratio 0.1)
+    pub budget: RetryBudgetConfig,
+    /// Header carrying the idempotency key (default `Idempotency-Key`)
+    pub idempotency_header: String,
+    /// Add a generated key to hedged `PUT` and `DELETE` requests that have
+    /// none (default true)
+    pub generate_idempotency_key: bool,
+}
+
+impl Default for HedgeConfig {
+    fn default() -> Self {
+        Self {
+            percentile: 95.0,
+            min_delay_ms: 10,
+            max_delay_ms: 1000,
+            min_samples: 20,
+            window: 1000,
+            max_body_bytes: 64 * 1024,
+            budget: RetryBudgetConfig {
+                ratio: 0.1,
+                ..RetryBudgetConfig::default()
+            },
+            idempotency_header: "Idempotency-Key".to_string(),
+            generate_idempotency_key: true,
+        }
+    }
+}
+
+/// Automatic rollback thresholds comparing the canary group against stable.
+///
+/// Once tripped, the canary weight stays at zero until the canary's
+/// configuration changes.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CanaryRollbackConfig {
+    /// Length of the comparison window in seconds
+    #[serde(default = "default_canary_window_secs")]
+    pub window_secs: u64,
+    /// Minimum requests each group must see in a window before comparing
+    #[serde(default = "default_canary_min_requests")]
+    pub min_requests: u64,
+    /// Roll back when the canary error rate exceeds stable by more than this (0.0-1.0)
+    #[serde(default)]
+    pub max_error_rate_increase: Option<f64>,
+    /// Roll back when canary mean latency exceeds stable mean latency times this factor
+    #[serde(default)]
+    pub max_latency_ratio: Option<f64>,
+    /// URL receiving a JSON POST when a rollback happens
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+fn default_canary_window_secs() -> u64 {
+    60
+}
+
+fn default_canary_min_requests() -> u64 {
+    20
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum HealthStatus {
     #[serde(rename = "healthy")]
@@ -5,11 +5,47 @@ use std::net::SocketAddr;
 use eyre::Result;
 use regex::Regex;
 
-use crate::config::models::{
-    HealthCheckConfig, LoadBalanceStrategy, RateLimitConfig, RouteConfig, RouteConfigEntry,
-    ServerConfig, TlsConfig,
+use crate::{
+    config::models::{
+        AuditConfig, AuditSinkConfig, BASIC_AUTH_MIDDLEWARE_PREFIX, BUILTIN_MIDDLEWARES,
+        BackendRemovalMode, BodyActions, BuiltinEndpoint, COOKIE_POLICY_MIDDLEWARE_PREFIX,
+        CanaryConfig, CoalesceConfig, ConfigWatchMode, ContentTypeAction, ContentTypeConfig,
+        CookieSameSite, DEFAULT_ROUTE_PREFIX, DedupeConfig, ErrorPagesConfig, EventSinkConfig,
+        EventsConfig, ExperimentConfig, FastCgiConfig, HeaderActions, HeaderAllowlistConfig,
+        HealthCheckConfig, HistogramConfig, Http3Config, JSON_SCHEMA_MIDDLEWARE_PREFIX,
+        LDAP_AUTH_MIDDLEWARE_PREFIX, LoadBalanceStrategy, LocaleRoutingConfig,
+        LocationRewriteConfig, MetricsConfig, MetricsExporter, MiddlewareConfig,
+        NonIdempotentRetry, NotificationsConfig, OPA_MIDDLEWARE_PREFIX, RBAC_MIDDLEWARE_PREFIX,
+        RateLimitBy, RateLimitConfig, RateLimitKeyPart, ResponseTimeoutConfig, RouteConfig,
+        RouteConfigEntry, RouteScheduleConfig, SecurityHeadersConfig, ServerConfig, TlsConfig,
+        TracingConfig, UPLOAD_POLICY_MIDDLEWARE_PREFIX, USAGE_MIDDLEWARE, UpstreamConfig,
+        UpstreamSocketConfig, UpstreamTlsConfig, UsageConfig, UsageSinkConfig,
+        WASM_MIDDLEWARE_PREFIX, WafConfig,
+    },
+    core::{
+        GatewayService,
+        condition::RouteTransforms,
+        gateway::prefix_patterns,
+        htpasswd::{Htpasswd, PasswordHash},
+        json_schema::JsonSchema,
+        path_template::validate_template,
+        schedule::RouteSchedule,
+        virtual_host::host_candidates,
+        waf::ip_filter::IpNetwork,
+    },
+    utils::CookieSealer,
 };
 
+/// Longest reason that fits a WebSocket Close frame's 125-byte payload
+const MAX_CLOSE_REASON_LEN: usize = 123;
+
+/// Most exchanges a tap keeps; each can hold two `max_body_bytes` bodies
+pub(crate) const MAX_TAP_CAPACITY: usize = 1000;
+/// Largest body prefix a tap records
+pub(crate) const MAX_TAP_BODY_BYTES: usize = 1024 * 1024;
+/// Most retries after a request's first attempt
+const MAX_RETRY_ATTEMPTS: u32 = 10;
+
 /// Validation result type alias
 pub type ValidationResult<T> = Result<T, ValidationError>;
 
@@ -49,36 +85,354 @@ impl ServerConfigValidator {
         }
 
         // Validate routes
-        if config.routes.is_empty() {
+        if config.routes.is_empty() && config.default_route.is_none() {
             errors.push(ValidationError::MissingField {
                 field: "routes".to_string(),
             });
         } else {
-            for (path, entry) in &config.routes {
-                for route_config in entry.iter() {
-                    if let Err(mut route_errors) = Self::validate_single_route(path, route_config) {
-                        errors.append(&mut route_errors);
+            for (path, route_config) in config.all_routes() {
+                if let Err(mut route_errors) = Self::validate_single_route(path, route_config) {
+                    errors.append(&mut route_errors);
+                }
+                if let Err(mut middleware_errors) =
+                    Self::validate_route_middlewares(path, route_config, &config.middleware)
+                {
+                    errors.append(&mut middleware_errors);
+                }
+                let route_error_pages = match route_config {
+                    RouteConfig::Static { error_pages, .. }
+                    | RouteConfig::Redirect { error_pages, .. }
+                    | RouteConfig::Proxy { error_pages, .. }
+                    | RouteConfig::LoadBalance { error_pages, .. }
+                    | RouteConfig::Websocket { error_pages, .. } => error_pages,
+                };
+                if let Some(error_pages) = route_error_pages {
+                    if let Err(mut page_errors) = Self::validate_error_pages(
+                        &format!("route '{path}' error_pages"),
+                        error_pages,
+                    ) {
+                        errors.append(&mut page_errors);
                     }
                 }
             }
         }
 
+        if config.config_watch.mode != ConfigWatchMode::Notify
+            && config.config_watch.poll_interval_secs == 0
+        {
+            errors.push(ValidationError::InvalidField {
+                field: "config_watch.poll_interval_secs".to_string(),
+                message: "Must be greater than 0".to_string(),
+            });
+        }
+
+        let export = &config.metrics.export;
+        for (field, value) in [
+            ("metrics.export.timeout_secs", export.timeout_secs),
+            (
+                "metrics.export.failure_threshold",
+                u64::from(export.failure_threshold),
+            ),
+            ("metrics.export.circuit_open_secs", export.circuit_open_secs),
+            (
+                "metrics.export.error_log_interval_secs",
+                export.error_log_interval_secs,
+            ),
+        ] {
+            if value == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: field.to_string(),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+        }
+
+        for (field, histogram) in [
+            ("metrics.request_duration", &config.metrics.request_duration),
+            ("metrics.backend_duration", &config.metrics.backend_duration),
+        ] {
+            if let Err(e) = Self::validate_histogram(field, histogram) {
+                errors.push(e);
+            }
+        }
+
+        if let Err(mut exporter_errors) = Self::validate_metrics_exporters(&config.metrics) {
+            errors.append(&mut exporter_errors);
+        }
+
+        if let Err(mut tracing_errors) = Self::validate_tracing(&config.tracing) {
+            errors.append(&mut tracing_errors);
+        }
+
+        if let Err(mut page_errors) = Self::validate_error_pages("error_pages", &config.error_pages)
+        {
+            errors.append(&mut page_errors);
+        }
+
+        errors.extend(Self::validate_header_allowlist(
+            "header_allowlist",
+            &config.header_allowlist,
+        ));
+
+        errors.extend(Self::validate_experiments(&config.experiments));
+
+        if let Some(static_files) = &config.static_files {
+            if let Err(mut static_errors) = Self::validate_static_options(
+                "static_files",
+                static_files.index_file.as_deref(),
+                None,
+                static_files.cache_control.as_deref(),
+            ) {
+                errors.append(&mut static_errors);
+            }
+        }
+
+        if let Err(mut wasm_errors) = Self::validate_wasm_filters(&config.middleware) {
+            errors.append(&mut wasm_errors);
+        }
+        errors.extend(Self::validate_json_schemas(&config.middleware));
+        errors.extend(Self::validate_upload_policies(&config.middleware));
+        errors.extend(Self::validate_cookie_policies(&config.middleware));
+        errors.extend(Self::validate_basic_auth(&config.middleware));
+        errors.extend(Self::validate_ldap_auth(&config.middleware));
+        errors.extend(Self::validate_rbac_policies(&config.middleware));
+        errors.extend(Self::validate_opa(&config.middleware));
+        if let Some(usage) = &config.middleware.usage {
+            errors.extend(Self::validate_usage(usage));
+        }
+
+        if let Some(http3) = &config.protocols.http3_config {
+            errors.extend(Self::validate_http3_config(http3));
+        }
+
+        errors.extend(Self::validate_security_headers(
+            "middleware.security_headers",
+            &config.middleware.security_headers,
+        ));
+        for (name, policy) in &config.middleware.security_header_policies {
+            errors.extend(Self::validate_security_headers(
+                &format!("middleware.security_header_policies.{name}"),
+                policy,
+            ));
+        }
+
         if let Err(mut health_check_errors) =
             Self::validate_health_check_config(&config.health_check)
         {
             errors.append(&mut health_check_errors);
         }
 
+        if let Err(mut upstream_errors) = Self::validate_upstream_config(&config.upstream) {
+            errors.append(&mut upstream_errors);
+        }
+
+        let limits = &config.header_limits;
+        for (field, value) in [
+            ("header_limits.max_total_bytes", limits.max_total_bytes),
+            ("header_limits.max_header_bytes", limits.max_header_bytes),
+            ("header_limits.max_count", limits.max_count),
+        ] {
+            if value == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: field.to_string(),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+        }
+        if limits.max_header_bytes > limits.max_total_bytes {
+            errors.push(ValidationError::InvalidField {
+                field: "header_limits.max_header_bytes".to_string(),
+                message: format!(
+                    "Must not exceed header_limits.max_total_bytes ({})",
+                    limits.max_total_bytes
+                ),
+            });
+        }
+
+        let backends = GatewayService::collect_backends(config);
+        for (backend, limits) in &config.backend_limits {
+            let field = format!("backend_limits.\"{backend}\"");
+            if !backends.contains(backend) {
+                errors.push(ValidationError::InvalidField {
+                    field: field.clone(),
+                    message: "Not a target of any proxy or load balance route".to_string(),
+                });
+            }
+            if limits.max_requests == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.max_requests"),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+            if limits.max_pending > 0 && limits.pending_timeout_ms == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.pending_timeout_ms"),
+                    message: "Must be greater than 0 when max_pending is set".to_string(),
+                });
+            }
+        }
+
+        for (backend, settings) in &config.backend_health_checks {
+            let field = format!("backend_health_checks.\"{backend}\"");
+            if !backends.contains(backend) {
+                errors.push(ValidationError::InvalidField {
+                    field: field.clone(),
+                    message: "Not a target of any proxy or load balance route".to_string(),
+                });
+            }
+            if let Some(host) = &settings.host
+                && host.parse::<http::uri::Authority>().is_err()
+            {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.host"),
+                    message: format!("Invalid host: '{host}'"),
+                });
+            }
+            if let Some(tls) = &settings.tls
+                && let Err(mut tls_errors) =
+                    Self::validate_upstream_tls(&format!("{field}.tls"), tls)
+            {
+                errors.append(&mut tls_errors);
+            }
+            if let Some(credentials) = &settings.basic_auth
+                && (credentials.username.is_empty() || credentials.username.contains(':'))
+            {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.basic_auth.username"),
+                    message: "Must be non-empty and not contain ':'".to_string(),
+                });
+            }
+        }
+
+        if config.backend_removal.mode == BackendRemovalMode::Deadline
+            && config.backend_removal.deadline_secs == 0
+        {
+            errors.push(ValidationError::InvalidField {
+                field: "backend_removal.deadline_secs".to_string(),
+                message: "Must be greater than 0 with mode = \"deadline\"".to_string(),
+            });
+        }
+
+        if config.shutdown.drain_poll_interval_ms == 0 {
+            errors.push(ValidationError::InvalidField {
+                field: "shutdown.drain_poll_interval_ms".to_string(),
+                message: "Must be greater than 0".to_string(),
+            });
+        }
+
+        if let Err(mut audit_errors) = Self::validate_audit(&config.audit) {
+            errors.append(&mut audit_errors);
+        }
+
+        if let Err(mut events_errors) = Self::validate_events(&config.events) {
+            errors.append(&mut events_errors);
+        }
+
+        errors.extend(Self::validate_notifications(&config.notifications));
+
+        if let Some(waf) = &config.waf
+            && let Err(mut waf_errors) = Self::validate_waf(waf)
+        {
+            errors.append(&mut waf_errors);
+        }
+
+        if let Err(mut content_type_errors) = Self::validate_content_types(&config.content_types) {
+            errors.append(&mut content_type_errors);
+        }
+
+        if config.upstream.spiffe.is_none() {
+            for (path, entry) in &config.routes {
+                let uses_spiffe = entry.iter().any(|route| match route {
+                    RouteConfig::Proxy { upstream_tls, .. }
+                    | RouteConfig::LoadBalance { upstream_tls, .. } => {
+                        upstream_tls.as_ref().is_some_and(|tls| tls.spiffe)
+                    }
+                    _ => false,
+                });
+                if uses_spiffe {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' upstream_tls.spiffe"),
+                        message: "Requires an [upstream.spiffe] section".to_string(),
+                    });
+                }
+            }
+        }
+
         if let Some(tls_config) = &config.tls {
             if let Err(e) = Self::validate_tls_config(tls_config) {
                 errors.push(e);
             }
         }
 
+        if let Err(mut vhost_errors) = Self::validate_virtual_hosts(config) {
+            errors.append(&mut vhost_errors);
+        }
+
+        if let Err(mut endpoint_errors) = Self::validate_endpoints(config) {
+            errors.append(&mut endpoint_errors);
+        }
+
+        if config.admin.enabled
+            && config
+                .admin
+                .token
+                .as_deref()
+                .is_none_or(|token| token.trim().is_empty())
+        {
+            errors.push(ValidationError::InvalidField {
+                field: "admin.token".to_string(),
+                message: "A token is required when the admin API is enabled".to_string(),
+            });
+        }
+
+        for entry in &config.client_ip.trusted_proxies {
+            if let Err(e) = IpNetwork::parse(entry) {
+                errors.push(ValidationError::InvalidField {
+                    field: "client_ip.trusted_proxies".to_string(),
+                    message: format!("'{entry}': {e}"),
+                });
+            }
+        }
+
         if let Err(conflict_error_list) = Self::check_route_conflicts(&config.routes) {
             errors.extend(conflict_error_list);
         }
 
+        if let Err(priority_error_list) = Self::check_route_priorities(&config.routes) {
+            errors.extend(priority_error_list);
+        }
+
+        if let Some(default_route) = &config.default_route {
+            if Self::get_route_host(default_route).is_some() {
+                errors.push(ValidationError::InvalidField {
+                    field: "default_route.host".to_string(),
+                    message: "The default route serves every host".to_string(),
+                });
+            }
+            if default_route.priority().is_some() {
+                errors.push(ValidationError::InvalidField {
+                    field: "default_route.priority".to_string(),
+                    message: "The default route is only tried after every other route".to_string(),
+                });
+            }
+            let catch_all = config
+                .routes
+                .get(DEFAULT_ROUTE_PREFIX)
+                .is_some_and(|entry| {
+                    entry
+                        .iter()
+                        .any(|route| Self::get_route_host(route).is_none())
+                });
+            if catch_all {
+                errors.push(ValidationError::InvalidField {
+                    field: "default_route".to_string(),
+                    message: format!(
+                        "Never used, route '{DEFAULT_ROUTE_PREFIX}' already matches every path"
+                    ),
+                });
+            }
+        }
+
         if errors.is_empty() {
             Ok(())
         } else {
@@ -100,169 +454,145 @@ impl ServerConfigValidator {
         Ok(())
     }
 
-    /// Validate a single route configuration
-    fn validate_single_route(path: &str, config: &RouteConfig) -> Result<(), Vec<ValidationError>> {
+    /// Check that the selected metrics exporters are built in and configured
+    fn validate_metrics_exporters(metrics: &MetricsConfig) -> Result<(), Vec<ValidationError>> {
         let mut errors = Vec::new();
-
-        if !path.starts_with('/') {
+        for exporter in &metrics.exporters {
+            if let Some(feature) = exporter.feature()
+                && !exporter.is_available()
+            {
+                errors.push(ValidationError::InvalidField {
+                    field: "metrics.exporters".to_string(),
+                    message: format!("'{feature}' requires building with the `{feature}` feature"),
+                });
+            }
+        }
+        if metrics.interval_secs == Some(0) {
             errors.push(ValidationError::InvalidField {
-                field: format!("route path: {path}"),
-                message: "Route paths must start with '/'".to_string(),
+                field: "metrics.interval_secs".to_string(),
+                message: "Must be greater than 0".to_string(),
             });
         }
 
-        // Validate route-specific configurations
-        match config {
-            RouteConfig::Proxy { target, host, .. } => {
-                if let Err(e) = Self::validate_url(target, &format!("route '{path}' target")) {
-                    errors.push(e);
-                }
-                if let Some(h) = host {
-                    if let Err(e) = Self::validate_host(h, path) {
-                        errors.push(e);
-                    }
-                }
-            }
-            RouteConfig::LoadBalance {
-                targets,
-                strategy,
-                host,
-                ..
-            } => {
-                if targets.is_empty() {
-                    errors.push(ValidationError::InvalidField {
-                        field: format!("route '{path}' targets"),
-                        message: "Load balance routes must have at least one target".to_string(),
-                    });
-                } else {
-                    for (i, target) in targets.iter().enumerate() {
-                        if let Err(e) =
-                            Self::validate_url(target, &format!("route '{path}' target {}", i + 1))
-                        {
-                            errors.push(e);
-                        }
-                    }
-                }
-
-                // Validate load balance strategy
-                if let Err(e) = Self::validate_load_balance_strategy(path, strategy) {
-                    errors.push(e);
-                }
-
-                if let Some(h) = host {
-                    if let Err(e) = Self::validate_host(h, path) {
-                        errors.push(e);
-                    }
-                }
-            }
-            RouteConfig::Static { root, host, .. } => {
-                if !std::path::Path::new(root).exists() {
-                    errors.push(ValidationError::InvalidField {
-                        field: format!("route '{path}' root"),
-                        message: format!("Static root directory '{root}' does not exist"),
-                    });
-                }
-                if let Some(h) = host {
-                    if let Err(e) = Self::validate_host(h, path) {
-                        errors.push(e);
-                    }
-                }
-            }
-            RouteConfig::Redirect {
-                target,
-                status_code,
-                host,
-                ..
-            } => {
-                if target.starts_with("http://") || target.starts_with("https://") {
-                    if let Err(e) =
-                        Self::validate_url(target, &format!("route '{path}' redirect target"))
+        if metrics.exporters.contains(&MetricsExporter::Statsd) {
+            match &metrics.statsd {
+                None => errors.push(ValidationError::MissingField {
+                    field: "metrics.statsd".to_string(),
+                }),
+                Some(statsd) => {
+                    if statsd
+                        .address
+                        .rsplit_once(':')
+                        .is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err())
                     {
-                        errors.push(e);
-                    }
-                }
-
-                if let Some(code) = status_code {
-                    if !Self::is_valid_redirect_status_code(*code) {
                         errors.push(ValidationError::InvalidField {
-                            field: format!("route '{path}' redirect status_code"),
-                            message: format!("Status code {code} is not a valid redirect code. Use 301, 302, 307, or 308"),
+                            field: "metrics.statsd.address".to_string(),
+                            message: format!("'{}' is not a host:port address", statsd.address),
                         });
                     }
-                }
-
-                if let Some(h) = host {
-                    if let Err(e) = Self::validate_host(h, path) {
-                        errors.push(e);
+                    if statsd.max_packet_bytes < 512 {
+                        errors.push(ValidationError::InvalidField {
+                            field: "metrics.statsd.max_packet_bytes".to_string(),
+                            message: "Must be at least 512".to_string(),
+                        });
                     }
                 }
             }
-            RouteConfig::Websocket {
-                target,
-                max_frame_size,
-                max_message_size,
-                host,
-                ..
-            } => {
-                if let Err(e) = Self::validate_websocket_url(
-                    target,
-                    &format!("route '{path}' websocket target"),
-                ) {
-                    errors.push(e);
-                }
+        }
 
-                if let Some(frame_size) = max_frame_size {
-                    if *frame_size == 0 {
+        if metrics.exporters.contains(&MetricsExporter::Pushgateway) {
+            match &metrics.pushgateway {
+                None => errors.push(ValidationError::MissingField {
+                    field: "metrics.pushgateway".to_string(),
+                }),
+                Some(pushgateway) => {
+                    if !pushgateway.url.starts_with("http://")
+                        && !pushgateway.url.starts_with("https://")
+                    {
                         errors.push(ValidationError::InvalidField {
-                            field: format!("route '{path}' max_frame_size"),
-                            message: "WebSocket max frame size must be greater than 0".to_string(),
+                            field: "metrics.pushgateway.url".to_string(),
+                            message: "Must be an http:// or https:// URL".to_string(),
                         });
                     }
-                }
-
-                if let Some(message_size) = max_message_size {
-                    if *message_size == 0 {
+                    if pushgateway.job.is_empty() || pushgateway.job.contains('/') {
                         errors.push(ValidationError::InvalidField {
-                            field: format!("route '{path}' max_message_size"),
-                            message: "WebSocket max message size must be greater than 0"
-                                .to_string(),
+                            field: "metrics.pushgateway.job".to_string(),
+                            message: "Must be non-empty and contain no '/'".to_string(),
                         });
                     }
-                }
-
-                if let Some(h) = host {
-                    if let Err(e) = Self::validate_host(h, path) {
-                        errors.push(e);
+                    if pushgateway
+                        .instance
+                        .as_ref()
+                        .is_some_and(|instance| instance.is_empty() || instance.contains('/'))
+                    {
+                        errors.push(ValidationError::InvalidField {
+                            field: "metrics.pushgateway.instance".to_string(),
+                            message: "Must be non-empty and contain no '/'".to_string(),
+                        });
                     }
                 }
             }
         }
 
-        let rate_limit = match config {
-            RouteConfig::Proxy { rate_limit, .. } => rate_limit,
-            RouteConfig::LoadBalance { rate_limit, .. } => rate_limit,
-            RouteConfig::Static { rate_limit, .. } => rate_limit,
-            RouteConfig::Redirect { rate_limit, .. } => rate_limit,
-            RouteConfig::Websocket { rate_limit, .. } => rate_limit,
-        };
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 
-        if let Some(rate_limit) = rate_limit {
-            if let Err(e) = Self::validate_rate_limit(path, rate_limit) {
-                errors.push(e);
+    /// Check log levels and the OTLP span exporter settings
+    fn validate_tracing(tracing: &TracingConfig) -> Result<(), Vec<ValidationError>> {
+        use crate::tracing_setup::validate_level;
+
+        let mut errors = Vec::new();
+        if let Err(message) = validate_level(&tracing.level) {
+            errors.push(ValidationError::InvalidField {
+                field: "tracing.level".to_string(),
+                message,
+            });
+        }
+        for (module, level) in &tracing.modules {
+            let field = format!("tracing.modules.{module}");
+            if module.is_empty() || module.contains([',', '=', '[', '{']) {
+                errors.push(ValidationError::InvalidField {
+                    field,
+                    message: "Module names must be non-empty paths like `axon::adapters`"
+                        .to_string(),
+                });
+            } else if let Err(message) = validate_level(level) {
+                errors.push(ValidationError::InvalidField { field, message });
             }
         }
 
-        let path_rewrite = match config {
-            RouteConfig::Proxy { path_rewrite, .. } => path_rewrite,
-            RouteConfig::LoadBalance { path_rewrite, .. } => path_rewrite,
-            RouteConfig::Static { .. } => &None,
-            RouteConfig::Redirect { .. } => &None,
-            RouteConfig::Websocket { path_rewrite, .. } => path_rewrite,
-        };
-
-        if let Some(path_rewrite) = path_rewrite {
-            if let Err(e) = Self::validate_path_rewrite(path, path_rewrite) {
-                errors.push(e);
+        if let Some(otlp) = &tracing.otlp {
+            if !otlp.endpoint.starts_with("http://") && !otlp.endpoint.starts_with("https://") {
+                errors.push(ValidationError::InvalidField {
+                    field: "tracing.otlp.endpoint".to_string(),
+                    message: "Must be an http:// or https:// URL".to_string(),
+                });
+            }
+            if !(0.0..=1.0).contains(&otlp.sample_ratio) {
+                errors.push(ValidationError::InvalidField {
+                    field: "tracing.otlp.sample_ratio".to_string(),
+                    message: "Must be between 0.0 and 1.0".to_string(),
+                });
+            }
+            if otlp.timeout_secs == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: "tracing.otlp.timeout_secs".to_string(),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+            for (name, value) in &otlp.headers {
+                if http::HeaderName::from_bytes(name.as_bytes()).is_err()
+                    || http::HeaderValue::from_str(value).is_err()
+                {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("tracing.otlp.headers.{name}"),
+                        message: "Not a valid header name and value".to_string(),
+                    });
+                }
             }
         }
 
@@ -273,429 +603,5423 @@ impl ServerConfigValidator {
         }
     }
 
-    /// Validate URL format
-    fn validate_url(url_str: &str, context: &str) -> ValidationResult<()> {
-        match url::Url::parse(url_str) {
-            Ok(url) => {
-                if url.scheme() != "http" && url.scheme() != "https" {
-                    return Err(ValidationError::InvalidField {
-                        field: context.to_string(),
-                        message: format!(
-                            "URL scheme must be 'http' or 'https', got '{}'",
-                            url.scheme()
-                        ),
+    /// Validate a single route configuration
+    fn validate_single_route(path: &str, config: &RouteConfig) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        if !path.starts_with('/') {
+            errors.push(ValidationError::InvalidField {
+                field: format!("route path: {path}"),
+                message: "Route paths must start with '/'".to_string(),
+            });
+        }
+
+        if let RouteConfig::Static {
+            slow_request_threshold_ms: Some(0),
+            ..
+        }
+        | RouteConfig::Proxy {
+            slow_request_threshold_ms: Some(0),
+            ..
+        }
+        | RouteConfig::LoadBalance {
+            slow_request_threshold_ms: Some(0),
+            ..
+        } = config
+        {
+            errors.push(ValidationError::InvalidField {
+                field: format!("route '{path}' slow_request_threshold_ms"),
+                message: "Slow request threshold must be greater than 0".to_string(),
+            });
+        }
+
+        if let RouteConfig::Static {
+            metrics: Some(metrics),
+            ..
+        }
+        | RouteConfig::Proxy {
+            metrics: Some(metrics),
+            ..
+        }
+        | RouteConfig::LoadBalance {
+            metrics: Some(metrics),
+            ..
+        } = config
+        {
+            if metrics.raw_path && !metrics.path_templates.is_empty() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' metrics"),
+                    message: "raw_path and path_templates cannot be combined".to_string(),
+                });
+            }
+            for template in &metrics.path_templates {
+                if let Err(message) = validate_template(template) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' metrics.path_templates '{template}'"),
+                        message,
+                    });
+                }
+            }
+        }
+
+        if let RouteConfig::Proxy { tap: Some(tap), .. }
+        | RouteConfig::LoadBalance { tap: Some(tap), .. } = config
+        {
+            if tap.capacity == 0 || tap.capacity > MAX_TAP_CAPACITY {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' tap.capacity"),
+                    message: format!("Must be between 1 and {MAX_TAP_CAPACITY}"),
+                });
+            }
+            if tap.max_body_bytes > MAX_TAP_BODY_BYTES {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' tap.max_body_bytes"),
+                    message: format!("Must be at most {MAX_TAP_BODY_BYTES}"),
+                });
+            }
+        }
+
+        if let RouteConfig::Proxy {
+            retry: Some(retry), ..
+        }
+        | RouteConfig::LoadBalance {
+            retry: Some(retry), ..
+        } = config
+        {
+            if retry.attempts > MAX_RETRY_ATTEMPTS {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' retry.attempts"),
+                    message: format!("Must be at most {MAX_RETRY_ATTEMPTS}"),
+                });
+            }
+            for status in &retry.retry_on {
+                if !(400..=599).contains(status) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' retry.retry_on"),
+                        message: format!("{status} is not an error status (400-599)"),
+                    });
+                }
+            }
+            if !(0.0..=1.0).contains(&retry.budget.ratio) {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' retry.budget.ratio"),
+                    message: "Must be between 0.0 and 1.0".to_string(),
+                });
+            }
+            if retry.budget.window_secs == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' retry.budget.window_secs"),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+            if http::HeaderName::from_bytes(retry.idempotency_header.as_bytes()).is_err() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' retry.idempotency_header"),
+                    message: format!("'{}' is not a valid header name", retry.idempotency_header),
+                });
+            }
+            if retry.generate_idempotency_key && retry.non_idempotent == NonIdempotentRetry::Never {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' retry.generate_idempotency_key"),
+                    message: "Has no effect with non_idempotent = \"never\", which only retries \
+                              GET, HEAD, OPTIONS, TRACE, PUT and DELETE; use \"with_key\" to retry \
+                              requests carrying a key"
+                        .to_string(),
+                });
+            }
+        }
+
+        if let RouteConfig::LoadBalance {
+            hedge: Some(hedge),
+            targets,
+            retry,
+            ..
+        } = config
+        {
+            let field = |name: &str| format!("route '{path}' hedge.{name}");
+            if targets.len() < 2 {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' hedge"),
+                    message: "Hedging needs at least two targets".to_string(),
+                });
+            }
+            if !(hedge.percentile > 0.0 && hedge.percentile <= 100.0) {
+                errors.push(ValidationError::InvalidField {
+                    field: field("percentile"),
+                    message: "Must be greater than 0 and at most 100".to_string(),
+                });
+            }
+            if hedge.min_delay_ms > hedge.max_delay_ms {
+                errors.push(ValidationError::InvalidField {
+                    field: field("min_delay_ms"),
+                    message: "Must not exceed max_delay_ms".to_string(),
+                });
+            }
+            if hedge.window == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: field("window"),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+            if !(0.0..=1.0).contains(&hedge.budget.ratio) {
+                errors.push(ValidationError::InvalidField {
+                    field: field("budget.ratio"),
+                    message: "Must be between 0.0 and 1.0".to_string(),
+                });
+            }
+            if hedge.budget.window_secs == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: field("budget.window_secs"),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+            if http::HeaderName::from_bytes(hedge.idempotency_header.as_bytes()).is_err() {
+                errors.push(ValidationError::InvalidField {
+                    field: field("idempotency_header"),
+                    message: format!("'{}' is not a valid header name", hedge.idempotency_header),
+                });
+            }
+            // Retries and hedges of one request must agree on where its key goes
+            if let Some(retry) = retry
+                && !retry
+                    .idempotency_header
+                    .eq_ignore_ascii_case(&hedge.idempotency_header)
+            {
+                errors.push(ValidationError::InvalidField {
+                    field: field("idempotency_header"),
+                    message: format!(
+                        "Must match retry.idempotency_header ('{}')",
+                        retry.idempotency_header
+                    ),
+                });
+            }
+        }
+
+        if let RouteConfig::Proxy {
+            fallback: Some(fallback),
+            ..
+        }
+        | RouteConfig::LoadBalance {
+            fallback: Some(fallback),
+            ..
+        } = config
+        {
+            if fallback.targets.is_empty() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' fallback.targets"),
+                    message: "At least one fallback target is required".to_string(),
+                });
+            }
+            for target in &fallback.targets {
+                if let Err(e) = Self::validate_url(target, &format!("route '{path}' fallback")) {
+                    errors.push(e);
+                }
+            }
+            for status in &fallback.on_status {
+                if !(400..=599).contains(status) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' fallback.on_status"),
+                        message: format!("{status} is not an error status (400-599)"),
+                    });
+                }
+            }
+        }
+
+        if let RouteConfig::Proxy { status_map, .. } | RouteConfig::LoadBalance { status_map, .. } =
+            config
+        {
+            for (i, mapping) in status_map.iter().enumerate() {
+                let field = format!("route '{path}' status_map[{i}]");
+                if mapping.from.is_empty() {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.from"),
+                        message: "At least one backend status is required".to_string(),
+                    });
+                }
+                for status in &mapping.from {
+                    if !(100..=599).contains(status) {
+                        errors.push(ValidationError::InvalidField {
+                            field: format!("{field}.from"),
+                            message: format!("{status} is not a valid status code"),
+                        });
+                    }
+                }
+                if let Some(to) = mapping.to
+                    && !(200..=599).contains(&to)
+                {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.to"),
+                        message: format!("{to} is not a final status code (200-599)"),
+                    });
+                }
+                let has_body = mapping.set_text.is_some() || mapping.set_json.is_some();
+                if mapping.to.is_none() && !has_body {
+                    errors.push(ValidationError::InvalidField {
+                        field,
+                        message: "Set at least one of to, set_text or set_json".to_string(),
+                    });
+                } else if has_body && matches!(mapping.to, Some(204 | 304)) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.to"),
+                        message: "A 204 or 304 response cannot have a body".to_string(),
+                    });
+                }
+            }
+        }
+
+        if let RouteConfig::Proxy {
+            tunnel: Some(tunnel),
+            ..
+        }
+        | RouteConfig::LoadBalance {
+            tunnel: Some(tunnel),
+            ..
+        } = config
+        {
+            if tunnel.protocols.is_empty() && !tunnel.connect {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' tunnel"),
+                    message: "List at least one protocol or enable connect".to_string(),
+                });
+            }
+            for protocol in &tunnel.protocols {
+                if protocol.is_empty()
+                    || protocol.contains(|c: char| c == ',' || !c.is_ascii_graphic())
+                {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' tunnel.protocols"),
+                        message: format!("Invalid Upgrade protocol '{protocol}'"),
+                    });
+                }
+            }
+            if tunnel.idle_timeout_secs == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' tunnel.idle_timeout_secs"),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+            if tunnel.max_session_secs == Some(0) {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' tunnel.max_session_secs"),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+        }
+
+        // Validate route-specific configurations
+        match config {
+            RouteConfig::Proxy {
+                target,
+                host,
+                upstream_tls,
+                response_timeout,
+                upstream_socket,
+                health_endpoint,
+                locale,
+                dedupe,
+                fastcgi,
+                location_rewrite,
+                header_allowlist,
+                schedule,
+                coalesce,
+                request_headers,
+                response_headers,
+                request_body,
+                response_body,
+                ..
+            } => {
+                if let Err(mut action_errors) = Self::validate_route_actions(
+                    path,
+                    request_headers.as_ref(),
+                    response_headers.as_ref(),
+                    request_body.as_ref(),
+                    response_body.as_ref(),
+                ) {
+                    errors.append(&mut action_errors);
+                }
+                if let Some(locale) = locale {
+                    if let Err(mut locale_errors) = Self::validate_locale(path, locale) {
+                        errors.append(&mut locale_errors);
+                    }
+                }
+                if let Some(dedupe) = dedupe {
+                    if let Err(mut dedupe_errors) = Self::validate_dedupe(path, dedupe) {
+                        errors.append(&mut dedupe_errors);
+                    }
+                }
+                if let Some(rewrite) = location_rewrite {
+                    errors.extend(Self::validate_location_rewrite(path, rewrite));
+                }
+                if let Some(allowlist) = header_allowlist {
+                    errors.extend(Self::validate_header_allowlist(
+                        &format!("route '{path}' header_allowlist"),
+                        allowlist,
+                    ));
+                }
+                if let Some(schedule) = schedule {
+                    errors.extend(Self::validate_schedule(path, schedule));
+                }
+                if let Some(coalesce) = coalesce {
+                    errors.extend(Self::validate_coalesce(path, coalesce));
+                }
+                if let Err(mut fastcgi_errors) =
+                    Self::validate_fastcgi(path, fastcgi.as_ref(), std::slice::from_ref(target))
+                {
+                    errors.append(&mut fastcgi_errors);
+                }
+                if !target.starts_with("fastcgi://")
+                    && let Err(e) = Self::validate_url(target, &format!("route '{path}' target"))
+                {
+                    errors.push(e);
+                }
+                if let Some(endpoint) = health_endpoint {
+                    if let Err(e) = Self::validate_health_endpoint(path, endpoint) {
+                        errors.push(e);
+                    }
+                }
+                if let Some(h) = host {
+                    if let Err(e) = Self::validate_host(h, path) {
+                        errors.push(e);
+                    }
+                }
+                if let Some(tls) = upstream_tls {
+                    if let Err(mut tls_errors) =
+                        Self::validate_upstream_tls(&format!("route '{path}' upstream_tls"), tls)
+                    {
+                        errors.append(&mut tls_errors);
+                    }
+                }
+                if let Some(timeout) = response_timeout
+                    && let Err(mut timeout_errors) = Self::validate_response_timeout(
+                        &format!("route '{path}' response_timeout"),
+                        timeout,
+                    )
+                {
+                    errors.append(&mut timeout_errors);
+                }
+                if let Some(socket) = upstream_socket {
+                    errors.extend(Self::validate_upstream_socket(
+                        &format!("route '{path}' upstream_socket"),
+                        socket,
+                    ));
+                }
+            }
+            RouteConfig::LoadBalance {
+                targets,
+                strategy,
+                host,
+                upstream_tls,
+                response_timeout,
+                upstream_socket,
+                canary,
+                health_endpoint,
+                locale,
+                dedupe,
+                fastcgi,
+                location_rewrite,
+                header_allowlist,
+                schedule,
+                coalesce,
+                request_headers,
+                response_headers,
+                request_body,
+                response_body,
+                slow_start_secs,
+                ..
+            } => {
+                if *slow_start_secs == Some(0) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' slow_start_secs"),
+                        message: "Slow start window must be greater than 0".to_string(),
+                    });
+                }
+                if let Err(mut action_errors) = Self::validate_route_actions(
+                    path,
+                    request_headers.as_ref(),
+                    response_headers.as_ref(),
+                    request_body.as_ref(),
+                    response_body.as_ref(),
+                ) {
+                    errors.append(&mut action_errors);
+                }
+                if let Some(locale) = locale {
+                    if let Err(mut locale_errors) = Self::validate_locale(path, locale) {
+                        errors.append(&mut locale_errors);
+                    }
+                }
+                if let Some(dedupe) = dedupe {
+                    if let Err(mut dedupe_errors) = Self::validate_dedupe(path, dedupe) {
+                        errors.append(&mut dedupe_errors);
+                    }
+                }
+                if let Some(rewrite) = location_rewrite {
+                    errors.extend(Self::validate_location_rewrite(path, rewrite));
+                }
+                if let Some(allowlist) = header_allowlist {
+                    errors.extend(Self::validate_header_allowlist(
+                        &format!("route '{path}' header_allowlist"),
+                        allowlist,
+                    ));
+                }
+                if let Some(schedule) = schedule {
+                    errors.extend(Self::validate_schedule(path, schedule));
+                }
+                if let Some(coalesce) = coalesce {
+                    errors.extend(Self::validate_coalesce(path, coalesce));
+                }
+                if let Some(endpoint) = health_endpoint {
+                    if let Err(e) = Self::validate_health_endpoint(path, endpoint) {
+                        errors.push(e);
+                    }
+                }
+                if targets.is_empty() {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' targets"),
+                        message: "Load balance routes must have at least one target".to_string(),
+                    });
+                } else {
+                    for (i, target) in targets.iter().enumerate() {
+                        if !target.starts_with("fastcgi://")
+                            && let Err(e) = Self::validate_url(
+                                target,
+                                &format!("route '{path}' target {}", i + 1),
+                            )
+                        {
+                            errors.push(e);
+                        }
+                    }
+                }
+                if let Err(mut fastcgi_errors) =
+                    Self::validate_fastcgi(path, fastcgi.as_ref(), targets)
+                {
+                    errors.append(&mut fastcgi_errors);
+                }
+
+                // Validate load balance strategy
+                if let Err(e) = Self::validate_load_balance_strategy(path, strategy) {
+                    errors.push(e);
+                }
+
+                if let Some(h) = host {
+                    if let Err(e) = Self::validate_host(h, path) {
+                        errors.push(e);
+                    }
+                }
+
+                if let Some(tls) = upstream_tls {
+                    if let Err(mut tls_errors) =
+                        Self::validate_upstream_tls(&format!("route '{path}' upstream_tls"), tls)
+                    {
+                        errors.append(&mut tls_errors);
+                    }
+                }
+                if let Some(timeout) = response_timeout
+                    && let Err(mut timeout_errors) = Self::validate_response_timeout(
+                        &format!("route '{path}' response_timeout"),
+                        timeout,
+                    )
+                {
+                    errors.append(&mut timeout_errors);
+                }
+                if let Some(socket) = upstream_socket {
+                    errors.extend(Self::validate_upstream_socket(
+                        &format!("route '{path}' upstream_socket"),
+                        socket,
+                    ));
+                }
+
+                if let Some(canary) = canary {
+                    if let Err(mut canary_errors) = Self::validate_canary(path, canary) {
+                        errors.append(&mut canary_errors);
+                    }
+                }
+            }
+            RouteConfig::Static {
+                root,
+                host,
+                index_file,
+                not_found_page,
+                cache_control,
+                ..
+            } => {
+                if !std::path::Path::new(root).exists() {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' root"),
+                        message: format!("Static root directory '{root}' does not exist"),
                     });
                 }
+                if let Err(mut static_errors) = Self::validate_static_options(
+                    &format!("route '{path}'"),
+                    index_file.as_deref(),
+                    not_found_page.as_deref(),
+                    cache_control.as_deref(),
+                ) {
+                    errors.append(&mut static_errors);
+                }
+                if let Some(h) = host {
+                    if let Err(e) = Self::validate_host(h, path) {
+                        errors.push(e);
+                    }
+                }
+            }
+            RouteConfig::Redirect {
+                target,
+                status_code,
+                host,
+                ..
+            } => {
+                if target.starts_with("http://") || target.starts_with("https://") {
+                    if let Err(e) =
+                        Self::validate_url(target, &format!("route '{path}' redirect target"))
+                    {
+                        errors.push(e);
+                    }
+                }
+
+                if let Some(code) = status_code {
+                    if !Self::is_valid_redirect_status_code(*code) {
+                        errors.push(ValidationError::InvalidField {
+                            field: format!("route '{path}' redirect status_code"),
+                            message: format!("Status code {code} is not a valid redirect code. Use 301, 302, 307, or 308"),
+                        });
+                    }
+                }
+
+                if let Some(h) = host {
+                    if let Err(e) = Self::validate_host(h, path) {
+                        errors.push(e);
+                    }
+                }
+            }
+            RouteConfig::Websocket {
+                target,
+                max_frame_size,
+                max_message_size,
+                max_session_secs,
+                session_close_code,
+                session_close_reason,
+                host,
+                ..
+            } => {
+                if let Err(e) = Self::validate_websocket_url(
+                    target,
+                    &format!("route '{path}' websocket target"),
+                ) {
+                    errors.push(e);
+                }
+
+                if let Some(frame_size) = max_frame_size {
+                    if *frame_size == 0 {
+                        errors.push(ValidationError::InvalidField {
+                            field: format!("route '{path}' max_frame_size"),
+                            message: "WebSocket max frame size must be greater than 0".to_string(),
+                        });
+                    }
+                }
+
+                if let Some(message_size) = max_message_size {
+                    if *message_size == 0 {
+                        errors.push(ValidationError::InvalidField {
+                            field: format!("route '{path}' max_message_size"),
+                            message: "WebSocket max message size must be greater than 0"
+                                .to_string(),
+                        });
+                    }
+                }
+
+                if *max_session_secs == Some(0) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' max_session_secs"),
+                        message: "WebSocket max session duration must be greater than 0"
+                            .to_string(),
+                    });
+                }
+
+                if let Some(code) = session_close_code {
+                    // Registered codes that may appear on the wire, plus the
+                    // library/application ranges
+                    if !matches!(code, 1000..=1003 | 1007..=1013 | 3000..=4999) {
+                        errors.push(ValidationError::InvalidField {
+                            field: format!("route '{path}' session_close_code"),
+                            message: format!("{code} cannot be sent in a WebSocket Close frame"),
+                        });
+                    }
+                }
+
+                if session_close_reason
+                    .as_ref()
+                    .is_some_and(|reason| reason.len() > MAX_CLOSE_REASON_LEN)
+                {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' session_close_reason"),
+                        message: format!(
+                            "WebSocket close reasons are limited to {MAX_CLOSE_REASON_LEN} bytes"
+                        ),
+                    });
+                }
+
+                if let Some(h) = host {
+                    if let Err(e) = Self::validate_host(h, path) {
+                        errors.push(e);
+                    }
+                }
+            }
+        }
+
+        let rate_limit = match config {
+            RouteConfig::Proxy { rate_limit, .. } => rate_limit,
+            RouteConfig::LoadBalance { rate_limit, .. } => rate_limit,
+            RouteConfig::Static { rate_limit, .. } => rate_limit,
+            RouteConfig::Redirect { rate_limit, .. } => rate_limit,
+            RouteConfig::Websocket { rate_limit, .. } => rate_limit,
+        };
+
+        if let Some(rate_limits) = rate_limit {
+            if rate_limits.0.is_empty() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' rate_limit"),
+                    message: "At least one rate limit is required".to_string(),
+                });
+            }
+            let mut names = std::collections::HashSet::new();
+            for rate_limit in rate_limits.iter() {
+                if let Err(e) = Self::validate_rate_limit(path, rate_limit) {
+                    errors.push(e);
+                }
+                if !names.insert(rate_limit.label()) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' rate_limit.name"),
+                        message: format!(
+                            "Rate limit name '{}' is used twice; give each limit a distinct `name`",
+                            rate_limit.label()
+                        ),
+                    });
+                }
+            }
+        }
+
+        let path_rewrite = match config {
+            RouteConfig::Proxy { path_rewrite, .. } => path_rewrite,
+            RouteConfig::LoadBalance { path_rewrite, .. } => path_rewrite,
+            RouteConfig::Static { .. } => &None,
+            RouteConfig::Redirect { .. } => &None,
+            RouteConfig::Websocket { path_rewrite, .. } => path_rewrite,
+        };
+
+        if let Some(path_rewrite) = path_rewrite {
+            if let Err(e) = Self::validate_path_rewrite(path, path_rewrite) {
+                errors.push(e);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate a route's middleware names and the settings they depend on
+    fn validate_route_middlewares(
+        path: &str,
+        route: &RouteConfig,
+        middleware_config: &MiddlewareConfig,
+    ) -> Result<(), Vec<ValidationError>> {
+        let middlewares = match route {
+            RouteConfig::Static { middlewares, .. } => middlewares,
+            RouteConfig::Redirect { middlewares, .. } => middlewares,
+            RouteConfig::Proxy { middlewares, .. } => middlewares,
+            RouteConfig::LoadBalance { middlewares, .. } => middlewares,
+            RouteConfig::Websocket { middlewares, .. } => middlewares,
+        };
+        let mut errors = Vec::new();
+
+        for name in middlewares {
+            if let Some(filter) = name.strip_prefix(WASM_MIDDLEWARE_PREFIX) {
+                if !middleware_config.wasm.contains_key(filter) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' middlewares"),
+                        message: format!(
+                            "WASM filter '{filter}' is not defined in [middleware.wasm]"
+                        ),
+                    });
+                }
+            } else if let Some(schema) = name.strip_prefix(JSON_SCHEMA_MIDDLEWARE_PREFIX) {
+                if !middleware_config.json_schema.contains_key(schema) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' middlewares"),
+                        message: format!(
+                            "JSON Schema check '{schema}' is not defined in [middleware.json_schema]"
+                        ),
+                    });
+                }
+            } else if let Some(policy) = name.strip_prefix(UPLOAD_POLICY_MIDDLEWARE_PREFIX) {
+                if !middleware_config.upload_policy.contains_key(policy) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' middlewares"),
+                        message: format!(
+                            "Upload policy '{policy}' is not defined in [middleware.upload_policy]"
+                        ),
+                    });
+                }
+            } else if let Some(auth) = name.strip_prefix(BASIC_AUTH_MIDDLEWARE_PREFIX) {
+                if !middleware_config.basic_auth.contains_key(auth) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' middlewares"),
+                        message: format!(
+                            "Basic auth '{auth}' is not defined in [middleware.basic_auth]"
+                        ),
+                    });
+                }
+            } else if let Some(auth) = name.strip_prefix(LDAP_AUTH_MIDDLEWARE_PREFIX) {
+                if !middleware_config.ldap_auth.contains_key(auth) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' middlewares"),
+                        message: format!(
+                            "LDAP auth '{auth}' is not defined in [middleware.ldap_auth]"
+                        ),
+                    });
+                }
+            } else if let Some(policy) = name.strip_prefix(RBAC_MIDDLEWARE_PREFIX) {
+                if !middleware_config.rbac.contains_key(policy) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' middlewares"),
+                        message: format!(
+                            "RBAC policy '{policy}' is not defined in [middleware.rbac]"
+                        ),
+                    });
+                }
+            } else if let Some(opa) = name.strip_prefix(OPA_MIDDLEWARE_PREFIX) {
+                if !middleware_config.opa.contains_key(opa) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' middlewares"),
+                        message: format!("OPA '{opa}' is not defined in [middleware.opa]"),
+                    });
+                }
+            } else if let Some(policy) = name.strip_prefix(COOKIE_POLICY_MIDDLEWARE_PREFIX) {
+                if !middleware_config.cookie_policy.contains_key(policy) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' middlewares"),
+                        message: format!(
+                            "Cookie policy '{policy}' is not defined in [middleware.cookie_policy]"
+                        ),
+                    });
+                }
+            } else if let Some(policy) = name.strip_prefix("security_headers:") {
+                if !middleware_config
+                    .security_header_policies
+                    .contains_key(policy)
+                {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' middlewares"),
+                        message: format!(
+                            "Security header policy '{policy}' is not defined in \
+                             [middleware.security_header_policies]"
+                        ),
+                    });
+                }
+            } else if name == USAGE_MIDDLEWARE {
+                if middleware_config.usage.is_none() {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' middlewares"),
+                        message: "The 'usage' middleware requires a [middleware.usage] section"
+                            .to_string(),
+                    });
+                }
+            } else if !BUILTIN_MIDDLEWARES.contains(&name.as_str()) {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' middlewares"),
+                    message: format!(
+                        "Unknown middleware '{name}'. Available: {}",
+                        BUILTIN_MIDDLEWARES.join(", ")
+                    ),
+                });
+            }
+        }
+
+        if middlewares.iter().any(|name| name == "auth") {
+            match &middleware_config.auth {
+                None => errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' middlewares"),
+                    message: "The 'auth' middleware requires a [middleware.auth] section"
+                        .to_string(),
+                }),
+                Some(auth) => {
+                    if auth.tokens.is_empty() || auth.tokens.iter().any(|t| t.is_empty()) {
+                        errors.push(ValidationError::InvalidField {
+                            field: "middleware.auth.tokens".to_string(),
+                            message: "At least one non-empty token is required".to_string(),
+                        });
+                    }
+                    if http::HeaderName::from_bytes(auth.header.as_bytes()).is_err() {
+                        errors.push(ValidationError::InvalidField {
+                            field: "middleware.auth.header".to_string(),
+                            message: format!("Invalid header name '{}'", auth.header),
+                        });
+                    }
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate a route's gateway-served health endpoint
+    fn validate_health_endpoint(path: &str, endpoint: &str) -> ValidationResult<()> {
+        if !endpoint.starts_with('/') || endpoint.len() < 2 {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' health_endpoint"),
+                message: "Must start with '/' and name a path below the route prefix".to_string(),
+            });
+        }
+        if endpoint.contains(['?', '#']) {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' health_endpoint"),
+                message: "Must not contain a query or fragment".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Validate static file options: served files must be plain relative
+    /// paths and `cache_control` a valid header value
+    fn validate_static_options(
+        field: &str,
+        index_file: Option<&str>,
+        not_found_page: Option<&str>,
+        cache_control: Option<&str>,
+    ) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        for (name, file) in [
+            ("index_file", index_file),
+            ("not_found_page", not_found_page),
+        ] {
+            let Some(file) = file else { continue };
+            let relative = !file.is_empty()
+                && std::path::Path::new(file)
+                    .components()
+                    .all(|c| matches!(c, std::path::Component::Normal(_)));
+            if !relative {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field} {name}"),
+                    message: format!(
+                        "'{file}' must be a path relative to the static root without '..'"
+                    ),
+                });
+            }
+        }
+        if let Some(value) = cache_control {
+            if http::HeaderValue::from_str(value).is_err() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field} cache_control"),
+                    message: format!("Invalid Cache-Control value '{value}'"),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate header/body actions and compile their conditions
+    fn validate_route_actions(
+        path: &str,
+        request_headers: Option<&HeaderActions>,
+        response_headers: Option<&HeaderActions>,
+        request_body: Option<&BodyActions>,
+        response_body: Option<&BodyActions>,
+    ) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        for (slot, actions) in [
+            ("request_headers", request_headers),
+            ("response_headers", response_headers),
+        ] {
+            let Some(actions) = actions else { continue };
+            for (name, value) in &actions.add {
+                if http::HeaderName::from_bytes(name.as_bytes()).is_err()
+                    || http::HeaderValue::from_str(value).is_err()
+                {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' {slot}.add"),
+                        message: format!("Invalid header '{name}: {value}'"),
+                    });
+                }
+            }
+            for name in &actions.remove {
+                if http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' {slot}.remove"),
+                        message: format!("Invalid header name '{name}'"),
+                    });
+                }
+            }
+        }
+
+        if let Err(e) = RouteTransforms::compile(
+            request_headers,
+            response_headers,
+            request_body,
+            response_body,
+        ) {
+            let (slot, message) = e.split_once(": ").unwrap_or(("condition", &e));
+            errors.push(ValidationError::InvalidField {
+                field: format!("route '{path}' {slot}"),
+                message: message.to_string(),
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate request and response header allowlists
+    fn validate_experiments(experiments: &[ExperimentConfig]) -> Vec<ValidationError> {
+        // Names end up in cookie and header names and values
+        let is_token = |name: &str| {
+            !name.is_empty()
+                && name
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        };
+        let mut errors = Vec::new();
+        let mut names = std::collections::HashSet::new();
+        for (i, experiment) in experiments.iter().enumerate() {
+            let field = format!("experiments[{i}]");
+            let mut invalid = |field: String, message: String| {
+                errors.push(ValidationError::InvalidField { field, message });
+            };
+            if !is_token(&experiment.name) {
+                invalid(
+                    format!("{field}.name"),
+                    "Experiment names may only contain letters, digits, '-' and '_'".to_string(),
+                );
+            } else if !names.insert(experiment.name.as_str()) {
+                invalid(
+                    format!("{field}.name"),
+                    format!("Duplicate experiment '{}'", experiment.name),
+                );
+            }
+            if let Some(route) = experiment.routes.iter().find(|r| !r.starts_with('/')) {
+                invalid(
+                    format!("{field}.routes"),
+                    format!("Route prefix '{route}' must start with '/'"),
+                );
+            }
+            if experiment.variants.is_empty() {
+                invalid(
+                    format!("{field}.variants"),
+                    "At least one variant is required".to_string(),
+                );
+            }
+            let mut variants = std::collections::HashSet::new();
+            for variant in &experiment.variants {
+                if !is_token(&variant.name) || !variants.insert(variant.name.as_str()) {
+                    invalid(
+                        format!("{field}.variants"),
+                        format!("Invalid or duplicate variant name '{}'", variant.name),
+                    );
+                }
+            }
+            let total: u32 = experiment
+                .variants
+                .iter()
+                .map(|variant| u32::from(variant.percent))
+                .sum();
+            if !experiment.variants.is_empty() && total != 100 {
+                invalid(
+                    format!("{field}.variants"),
+                    format!("Variant percentages must add up to 100, got {total}"),
+                );
+            }
+            if experiment
+                .cookie
+                .as_deref()
+                .is_some_and(|cookie| !is_token(cookie))
+            {
+                invalid(
+                    format!("{field}.cookie"),
+                    "Cookie names may only contain letters, digits, '-' and '_'".to_string(),
+                );
+            }
+            if http::HeaderName::from_bytes(experiment.header_name().as_bytes()).is_err() {
+                invalid(
+                    format!("{field}.header"),
+                    format!("Invalid header name '{}'", experiment.header_name()),
+                );
+            }
+        }
+        errors
+    }
+
+    fn validate_header_allowlist(
+        field: &str,
+        config: &HeaderAllowlistConfig,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        for (side, entries) in [("request", &config.request), ("response", &config.response)] {
+            for entry in entries.iter().flatten() {
+                let name = entry.strip_suffix('*').unwrap_or(entry);
+                if name.is_empty() || http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.{side}"),
+                        message: format!(
+                            "Invalid entry '{entry}': expected a header name or a prefix \
+                             ending in '*'"
+                        ),
+                    });
+                }
+            }
+        }
+        errors
+    }
+
+    /// Validate `Location` / `Link` header rewriting
+    fn validate_schedule(path: &str, config: &RouteScheduleConfig) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let field = format!("route '{path}' schedule");
+        if config.windows.is_empty() {
+            errors.push(ValidationError::InvalidField {
+                field: field.clone(),
+                message: "At least one window is required".to_string(),
+            });
+        }
+        if let Err(e) = RouteSchedule::compile(config) {
+            errors.push(ValidationError::InvalidField {
+                field: field.clone(),
+                message: e,
+            });
+        }
+        for (i, window) in config.windows.iter().enumerate() {
+            for target in &window.targets {
+                if !target.starts_with("fastcgi://")
+                    && let Err(e) =
+                        Self::validate_url(target, &format!("{field} windows[{i}].targets"))
+                {
+                    errors.push(e);
+                }
+            }
+        }
+        errors
+    }
+
+    fn validate_location_rewrite(
+        path: &str,
+        config: &LocationRewriteConfig,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let field = |name: &str| format!("route '{path}' location_rewrite.{name}");
+
+        for name in &config.headers {
+            if http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                errors.push(ValidationError::InvalidField {
+                    field: field("headers"),
+                    message: format!("Invalid header name '{name}'"),
+                });
+            }
+        }
+        if let Some(url) = &config.public_url
+            && let Err(e) = Self::validate_url(url, &field("public_url"))
+        {
+            errors.push(e);
+        }
+        for origin in &config.internal_origins {
+            if let Err(e) = Self::validate_url(origin, &field("internal_origins")) {
+                errors.push(e);
+            }
+        }
+        errors
+    }
+
+    /// Validate `fastcgi://` targets against the route's `fastcgi` section
+    fn validate_fastcgi(
+        path: &str,
+        config: Option<&FastCgiConfig>,
+        targets: &[String],
+    ) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let field = |name: &str| format!("route '{path}' fastcgi.{name}");
+
+        let fastcgi_targets: Vec<_> = targets
+            .iter()
+            .filter(|target| target.starts_with("fastcgi://"))
+            .collect();
+        if !fastcgi_targets.is_empty() && fastcgi_targets.len() != targets.len() {
+            errors.push(ValidationError::InvalidField {
+                field: format!("route '{path}' targets"),
+                message: "Targets cannot mix fastcgi:// and HTTP backends".to_string(),
+            });
+        }
+        for target in &fastcgi_targets {
+            let valid = url::Url::parse(target).is_ok_and(|url| {
+                url.host().is_some() && url.port().is_some() && matches!(url.path(), "" | "/")
+            });
+            if !valid {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' target"),
+                    message: format!("FastCGI target '{target}' must be fastcgi://host:port"),
+                });
+            }
+        }
+
+        let Some(config) = config else {
+            if !fastcgi_targets.is_empty() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' fastcgi"),
+                    message: "fastcgi:// targets require a `fastcgi` section".to_string(),
+                });
+            }
+            return if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors)
+            };
+        };
+        if fastcgi_targets.is_empty() {
+            errors.push(ValidationError::InvalidField {
+                field: format!("route '{path}' fastcgi"),
+                message: "The `fastcgi` section requires fastcgi:// targets".to_string(),
+            });
+        }
+        if !config.root.starts_with('/') {
+            errors.push(ValidationError::InvalidField {
+                field: field("root"),
+                message: "Document root must be an absolute path".to_string(),
+            });
+        }
+        if config.index.is_empty() || config.index.contains('/') {
+            errors.push(ValidationError::InvalidField {
+                field: field("index"),
+                message: "Index must be a file name".to_string(),
+            });
+        }
+        if let Some(script) = &config.script
+            && !script.starts_with('/')
+        {
+            errors.push(ValidationError::InvalidField {
+                field: field("script"),
+                message: "Script must start with '/'".to_string(),
+            });
+        }
+        if !config.script_extension.starts_with('.') || config.script_extension.contains('/') {
+            errors.push(ValidationError::InvalidField {
+                field: field("script_extension"),
+                message: "Script extension must start with '.'".to_string(),
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_coalesce(path: &str, config: &CoalesceConfig) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let field = |name: &str| format!("route '{path}' coalesce.{name}");
+
+        for (name, header) in config
+            .key_headers
+            .iter()
+            .map(|header| ("key_headers", header))
+            .chain(
+                config
+                    .bypass_header
+                    .iter()
+                    .map(|header| ("bypass_header", header)),
+            )
+        {
+            if http::HeaderName::from_bytes(header.as_bytes()).is_err() {
+                errors.push(ValidationError::InvalidField {
+                    field: field(name),
+                    message: format!("Invalid header name '{header}'"),
+                });
+            }
+        }
+        if config.max_waiters == 0 {
+            errors.push(ValidationError::InvalidField {
+                field: field("max_waiters"),
+                message: "max_waiters must be greater than 0".to_string(),
+            });
+        }
+        if config.max_body_bytes == 0 {
+            errors.push(ValidationError::InvalidField {
+                field: field("max_body_bytes"),
+                message: "max_body_bytes must be greater than 0".to_string(),
+            });
+        }
+        errors
+    }
+
+    /// Validate a webhook dedupe window
+    fn validate_dedupe(path: &str, config: &DedupeConfig) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let field = |name: &str| format!("route '{path}' dedupe.{name}");
+
+        if config.headers.is_empty() && !config.include_body {
+            errors.push(ValidationError::InvalidField {
+                field: field("headers"),
+                message: "Set key headers or enable include_body".to_string(),
+            });
+        }
+        for name in &config.headers {
+            if http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                errors.push(ValidationError::InvalidField {
+                    field: field("headers"),
+                    message: format!("Invalid header name '{name}'"),
+                });
+            }
+        }
+        if config.ttl_secs == 0 {
+            errors.push(ValidationError::InvalidField {
+                field: field("ttl_secs"),
+                message: "ttl_secs must be greater than 0".to_string(),
+            });
+        }
+        if config.max_entries == 0 {
+            errors.push(ValidationError::InvalidField {
+                field: field("max_entries"),
+                message: "max_entries must be greater than 0".to_string(),
+            });
+        }
+        if !(200..=599).contains(&config.status_code) {
+            errors.push(ValidationError::InvalidField {
+                field: field("status_code"),
+                message: format!(
+                    "Status code {} must be between 200 and 599",
+                    config.status_code
+                ),
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate locale-based backend groups
+    fn validate_locale(
+        path: &str,
+        config: &LocaleRoutingConfig,
+    ) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        if config.groups.is_empty() {
+            errors.push(ValidationError::InvalidField {
+                field: format!("route '{path}' locale.groups"),
+                message: "At least one language group is required".to_string(),
+            });
+        }
+        for (language, targets) in &config.groups {
+            if language.is_empty()
+                || language.len() > 8
+                || !language.chars().all(|c| c.is_ascii_alphabetic())
+            {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' locale.groups"),
+                    message: format!(
+                        "Invalid language '{language}': expected a primary language subtag such \
+                         as 'de'"
+                    ),
+                });
+            }
+            if targets.is_empty() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' locale.groups.{language}"),
+                    message: "Language groups must have at least one target".to_string(),
+                });
+            }
+            for target in targets {
+                if let Err(e) =
+                    Self::validate_url(target, &format!("route '{path}' locale.groups.{language}"))
+                {
+                    errors.push(e);
+                }
+            }
+        }
+        if http::HeaderName::from_bytes(config.header.as_bytes()).is_err() {
+            errors.push(ValidationError::InvalidField {
+                field: format!("route '{path}' locale.header"),
+                message: format!("Invalid header name '{}'", config.header),
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate error page template keys
+    fn validate_error_pages(
+        field: &str,
+        config: &ErrorPagesConfig,
+    ) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        for key in config.intercept.iter().flatten() {
+            let valid = match key.as_bytes() {
+                [b'4' | b'5', b'x', b'x'] => true,
+                [b'4' | b'5', tens, ones] => tens.is_ascii_digit() && ones.is_ascii_digit(),
+                _ => false,
+            };
+            if !valid {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.intercept"),
+                    message: format!(
+                        "Invalid status '{key}': expected a 4xx or 5xx code (e.g. '502') or \
+                         class ('4xx', '5xx')"
+                    ),
+                });
+            }
+        }
+
+        for key in config.templates.keys() {
+            let valid = match key.as_bytes() {
+                b"default" | [b'1'..=b'5', b'x', b'x'] => true,
+                [b'1'..=b'5', tens, ones] => tens.is_ascii_digit() && ones.is_ascii_digit(),
+                _ => false,
+            };
+            if !valid {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.templates"),
+                    message: format!(
+                        "Invalid key '{key}': expected a status code (e.g. '404'), a class \
+                         (e.g. '5xx') or 'default'"
+                    ),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate a latency histogram aggregation
+    fn validate_histogram(field: &str, histogram: &HistogramConfig) -> ValidationResult<()> {
+        match histogram {
+            HistogramConfig::Default => Ok(()),
+            HistogramConfig::Explicit { buckets } => {
+                if buckets.is_empty() {
+                    return Err(ValidationError::InvalidField {
+                        field: format!("{field}.buckets"),
+                        message: "At least one bucket boundary is required".to_string(),
+                    });
+                }
+                if buckets.iter().any(|b| !b.is_finite() || *b < 0.0)
+                    || buckets.windows(2).any(|w| w[0] >= w[1])
+                {
+                    return Err(ValidationError::InvalidField {
+                        field: format!("{field}.buckets"),
+                        message: "Boundaries must be finite, non-negative and strictly increasing"
+                            .to_string(),
+                    });
+                }
+                Ok(())
+            }
+            HistogramConfig::Exponential {
+                max_size,
+                max_scale,
+            } => {
+                if *max_size < 2 {
+                    return Err(ValidationError::InvalidField {
+                        field: format!("{field}.max_size"),
+                        message: "Must be at least 2".to_string(),
+                    });
+                }
+                if !(-10..=20).contains(max_scale) {
+                    return Err(ValidationError::InvalidField {
+                        field: format!("{field}.max_scale"),
+                        message: "Must be between -10 and 20".to_string(),
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Validate QUIC transport settings against the limits of the protocol
+    fn validate_http3_config(config: &Http3Config) -> Vec<ValidationError> {
+        // Largest value a QUIC variable-length integer can hold
+        const MAX_VAR_INT: u64 = (1 << 62) - 1;
+        let field = |key: &str| format!("protocols.http3_config.{key}");
+        let mut errors = Vec::new();
+
+        for (key, value) in [
+            ("max_data", config.max_data),
+            ("max_stream_data", config.max_stream_data),
+            ("max_streams_bidi", config.max_streams_bidi),
+        ] {
+            if value == 0 || value > MAX_VAR_INT {
+                errors.push(ValidationError::InvalidField {
+                    field: field(key),
+                    message: format!("Must be between 1 and {MAX_VAR_INT}"),
+                });
+            }
+        }
+        if config.max_stream_data > config.max_data {
+            errors.push(ValidationError::InvalidField {
+                field: field("max_stream_data"),
+                message: "Must not exceed max_data".to_string(),
+            });
+        }
+        if let Some(size) = config.max_packet_size
+            && size < 1200
+        {
+            errors.push(ValidationError::InvalidField {
+                field: field("max_packet_size"),
+                message: "QUIC requires at least 1200 bytes".to_string(),
+            });
+        }
+        errors
+    }
+
+    /// Validate the header values and names of a security header policy
+    fn validate_security_headers(
+        field: &str,
+        config: &SecurityHeadersConfig,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let values = [
+            ("frame_options", &config.frame_options),
+            ("referrer_policy", &config.referrer_policy),
+            ("content_security_policy", &config.content_security_policy),
+            ("permissions_policy", &config.permissions_policy),
+        ];
+        for (key, value) in values {
+            if let Some(value) = value
+                && http::HeaderValue::from_str(value).is_err()
+            {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.{key}"),
+                    message: "Not a valid header value".to_string(),
+                });
+            }
+        }
+        if config
+            .hsts
+            .as_ref()
+            .is_some_and(|hsts| hsts.preload && !hsts.include_subdomains)
+        {
+            errors.push(ValidationError::InvalidField {
+                field: format!("{field}.hsts.preload"),
+                message: "HSTS preload requires include_subdomains".to_string(),
+            });
+        }
+        for name in &config.remove_headers {
+            if http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.remove_headers"),
+                    message: format!("Invalid header name '{name}'"),
+                });
+            }
+        }
+        errors
+    }
+
+    /// Validate WASM filter definitions
+    fn validate_wasm_filters(
+        middleware_config: &MiddlewareConfig,
+    ) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        for (name, filter) in &middleware_config.wasm {
+            let field = |key: &str| format!("middleware.wasm.{name}.{key}");
+            if !std::path::Path::new(&filter.path).exists() {
+                errors.push(ValidationError::InvalidField {
+                    field: field("path"),
+                    message: format!("WASM module does not exist: {}", filter.path),
+                });
+            }
+            if filter.fuel == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: field("fuel"),
+                    message: "Fuel must be greater than 0".to_string(),
+                });
+            }
+            if filter.max_memory_bytes < 65536 {
+                errors.push(ValidationError::InvalidField {
+                    field: field("max_memory_bytes"),
+                    message: "Must be at least one WASM page (65536 bytes)".to_string(),
+                });
+            }
+            if filter.include_body && filter.max_body_bytes == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: field("max_body_bytes"),
+                    message: "Must be greater than 0 when include_body is set".to_string(),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate JSON Schema checks, compiling each schema file
+    fn validate_json_schemas(middleware_config: &MiddlewareConfig) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        for (name, check) in &middleware_config.json_schema {
+            let field = |key: &str| format!("middleware.json_schema.{name}.{key}");
+            if check.request.is_none() && check.response.is_none() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("middleware.json_schema.{name}"),
+                    message: "Set a request and/or response schema".to_string(),
+                });
+            }
+            for (key, path) in [("request", &check.request), ("response", &check.response)] {
+                let Some(path) = path else { continue };
+                let loaded = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Cannot read schema {path}: {e}"))
+                    .and_then(|text| {
+                        serde_json::from_str(&text)
+                            .map_err(|e| format!("Schema {path} is not valid JSON: {e}"))
+                    })
+                    .and_then(|schema| {
+                        JsonSchema::compile(&schema).map_err(|e| format!("{path}: {e}"))
+                    });
+                if let Err(message) = loaded {
+                    errors.push(ValidationError::InvalidField {
+                        field: field(key),
+                        message,
+                    });
+                }
+            }
+            if check.max_body_bytes == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: field("max_body_bytes"),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+        }
+        errors
+    }
+
+    /// Validate file upload policies
+    fn validate_upload_policies(middleware_config: &MiddlewareConfig) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        for (name, policy) in &middleware_config.upload_policy {
+            let field = |key: &str| format!("middleware.upload_policy.{name}.{key}");
+            for media_type in &policy.allowed_content_types {
+                let valid = media_type.split_once('/').is_some_and(|(kind, subtype)| {
+                    !kind.is_empty()
+                        && !subtype.is_empty()
+                        && (kind != "*" || subtype == "*")
+                        && !media_type.contains([';', ' '])
+                });
+                if !valid {
+                    errors.push(ValidationError::InvalidField {
+                        field: field("allowed_content_types"),
+                        message: format!(
+                            "'{media_type}' is not a media type like 'image/png' or 'image/*'"
+                        ),
+                    });
+                }
+            }
+            for extension in &policy.blocked_extensions {
+                let extension = extension.trim().trim_start_matches('.');
+                if extension.is_empty() || extension.contains(['.', '/', '\\']) {
+                    errors.push(ValidationError::InvalidField {
+                        field: field("blocked_extensions"),
+                        message: format!("'{extension}' is not a file name extension"),
+                    });
+                }
+            }
+            if let Some(scan) = &policy.scan {
+                let valid_url = url::Url::parse(&scan.icap_url)
+                    .is_ok_and(|url| url.scheme() == "icap" && url.host_str().is_some());
+                if !valid_url {
+                    errors.push(ValidationError::InvalidField {
+                        field: field("scan.icap_url"),
+                        message: format!(
+                            "'{}' is not an ICAP URL like 'icap://clamav:1344/avscan'",
+                            scan.icap_url
+                        ),
+                    });
+                }
+            }
+            let scan = policy.scan.as_ref();
+            for (key, value) in [
+                ("max_file_bytes", policy.max_file_bytes),
+                ("max_files", policy.max_files.map(|max| max as u64)),
+                ("max_body_bytes", Some(policy.max_body_bytes as u64)),
+                ("scan.timeout_secs", scan.map(|scan| scan.timeout_secs)),
+                ("scan.max_file_bytes", scan.map(|scan| scan.max_file_bytes)),
+            ] {
+                if value == Some(0) {
+                    errors.push(ValidationError::InvalidField {
+                        field: field(key),
+                        message: "Must be greater than 0".to_string(),
+                    });
+                }
+            }
+        }
+        errors
+    }
+
+    /// Validate cookie policies
+    fn validate_cookie_policies(middleware_config: &MiddlewareConfig) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        for (name, policy) in &middleware_config.cookie_policy {
+            let field = |key: &str| format!("middleware.cookie_policy.{name}.{key}");
+            for (key, cookies) in [
+                ("strip_request_cookies", &policy.strip_request_cookies),
+                ("set_cookie.cookies", &policy.set_cookie.cookies),
+                ("encrypt", &policy.encrypt),
+                ("sign", &policy.sign),
+            ] {
+                for cookie in cookies {
+                    if cookie.is_empty()
+                        || cookie
+                            .chars()
+                            .any(|c| c.is_ascii_control() || " \t\"(),/:;<=>?@[\\]{}".contains(c))
+                    {
+                        errors.push(ValidationError::InvalidField {
+                            field: field(key),
+                            message: format!("'{cookie}' is not a valid cookie name"),
+                        });
+                    }
+                }
+            }
+            if let Some(cookie) = policy.encrypt.iter().find(|c| policy.sign.contains(c)) {
+                errors.push(ValidationError::InvalidField {
+                    field: field("sign"),
+                    message: format!("Cookie '{cookie}' is already encrypted"),
+                });
+            }
+            match &policy.secret {
+                Some(secret) => {
+                    if let Err(e) = CookieSealer::new(secret) {
+                        errors.push(ValidationError::InvalidField {
+                            field: field("secret"),
+                            message: e.to_string(),
+                        });
+                    }
+                }
+                None if !policy.encrypt.is_empty()
+                    || !policy.sign.is_empty()
+                    || !policy.retired_secrets.is_empty() =>
+                {
+                    errors.push(ValidationError::MissingField {
+                        field: field("secret"),
+                    });
+                }
+                None => {}
+            }
+            for (index, retired) in policy.retired_secrets.iter().enumerate() {
+                if let Err(e) = CookieSealer::new(&retired.secret) {
+                    errors.push(ValidationError::InvalidField {
+                        field: field(&format!("retired_secrets[{index}].secret")),
+                        message: e.to_string(),
+                    });
+                }
+            }
+            if policy.set_cookie.same_site == Some(CookieSameSite::None)
+                && policy.set_cookie.secure == Some(false)
+            {
+                errors.push(ValidationError::InvalidField {
+                    field: field("set_cookie.same_site"),
+                    message: "SameSite=None cookies must be Secure".to_string(),
+                });
+            }
+        }
+        errors
+    }
+
+    /// Validate Basic authentication settings
+    fn validate_basic_auth(middleware_config: &MiddlewareConfig) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        for (name, auth) in &middleware_config.basic_auth {
+            let field = |key: &str| format!("middleware.basic_auth.{name}.{key}");
+            if auth.realm.is_empty() || auth.realm.chars().any(|c| c == '"' || c.is_control()) {
+                errors.push(ValidationError::InvalidField {
+                    field: field("realm"),
+                    message: "Must be non-empty, without quotes or control characters".to_string(),
+                });
+            }
+            for (user, hash) in &auth.users {
+                if user.is_empty() || user.contains(':') {
+                    errors.push(ValidationError::InvalidField {
+                        field: field("users"),
+                        message: format!("'{user}' is not a valid user name"),
+                    });
+                }
+                if let Err(e) = hash.parse::<PasswordHash>() {
+                    errors.push(ValidationError::InvalidField {
+                        field: field(&format!("users.{user}")),
+                        message: e.to_string(),
+                    });
+                }
+            }
+            match &auth.htpasswd_file {
+                Some(path) => {
+                    let loaded = std::fs::read_to_string(path)
+                        .map_err(|e| format!("Cannot read htpasswd file {path}: {e}"))
+                        .and_then(|contents| {
+                            Htpasswd::parse(&contents).map_err(|e| format!("{path}: {e}"))
+                        });
+                    if let Err(message) = loaded {
+                        errors.push(ValidationError::InvalidField {
+                            field: field("htpasswd_file"),
+                            message,
+                        });
+                    }
+                }
+                None if auth.users.is_empty() => {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("middleware.basic_auth.{name}"),
+                        message: "Set users and/or htpasswd_file".to_string(),
+                    });
+                }
+                None => {}
+            }
+        }
+        errors
+    }
+
+    /// Validate LDAP authentication settings
+    fn validate_ldap_auth(middleware_config: &MiddlewareConfig) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        for (name, auth) in &middleware_config.ldap_auth {
+            let field = |key: &str| format!("middleware.ldap_auth.{name}.{key}");
+            match url::Url::parse(&auth.url) {
+                Ok(url) if url.scheme() == "ldap" || url.scheme() == "ldaps" => {
+                    if auth.starttls && url.scheme() == "ldaps" {
+                        errors.push(ValidationError::InvalidField {
+                            field: field("starttls"),
+                            message: "StartTLS applies to ldap:// URLs only".to_string(),
+                        });
+                    }
+                }
+                _ => errors.push(ValidationError::InvalidField {
+                    field: field("url"),
+                    message: format!(
+                        "'{}' is not an LDAP URL like 'ldaps://ldap.example.com'",
+                        auth.url
+                    ),
+                }),
+            }
+            errors.extend(Self::validate_realm(&auth.realm, &field("realm")));
+            match (&auth.user_dn, &auth.search) {
+                (None, None) => errors.push(ValidationError::InvalidField {
+                    field: format!("middleware.ldap_auth.{name}"),
+                    message: "Set user_dn or search".to_string(),
+                }),
+                (Some(_), Some(_)) => errors.push(ValidationError::InvalidField {
+                    field: format!("middleware.ldap_auth.{name}"),
+                    message: "Set only one of user_dn and search".to_string(),
+                }),
+                (Some(user_dn), None) => {
+                    if !user_dn.contains("{username}") {
+                        errors.push(ValidationError::InvalidField {
+                            field: field("user_dn"),
+                            message: "Must contain {username}".to_string(),
+                        });
+                    }
+                    // Groups are read from the user's entry, found by its DN
+                    if !auth.required_groups.is_empty() && !user_dn.contains('=') {
+                        errors.push(ValidationError::InvalidField {
+                            field: field("required_groups"),
+                            message: "Group checks need a DN user_dn template or a search"
+                                .to_string(),
+                        });
+                    }
+                }
+                (None, Some(search)) => {
+                    if !search.filter.contains("{username}") {
+                        errors.push(ValidationError::InvalidField {
+                            field: field("search.filter"),
+                            message: "Must contain {username}".to_string(),
+                        });
+                    }
+                    if search.base_dn.is_empty() {
+                        errors.push(ValidationError::InvalidField {
+                            field: field("search.base_dn"),
+                            message: "Must not be empty".to_string(),
+                        });
+                    }
+                }
+            }
+            for (key, value) in [
+                ("pool_size", auth.pool_size as u64),
+                ("timeout_secs", auth.timeout_secs),
+            ] {
+                if value == 0 {
+                    errors.push(ValidationError::InvalidField {
+                        field: field(key),
+                        message: "Must be greater than 0".to_string(),
+                    });
+                }
+            }
+        }
+        errors
+    }
+
+    /// Validate RBAC policies: headers, role references, methods and paths
+    fn validate_rbac_policies(middleware_config: &MiddlewareConfig) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        for (name, policy) in &middleware_config.rbac {
+            let field = |key: &str| format!("middleware.rbac.{name}.{key}");
+            for (key, header) in [
+                ("user_header", &policy.user_header),
+                ("groups_header", &policy.groups_header),
+            ] {
+                if let Some(header) = header
+                    && http::HeaderName::from_bytes(header.as_bytes()).is_err()
+                {
+                    errors.push(ValidationError::InvalidField {
+                        field: field(key),
+                        message: format!("Invalid header name '{header}'"),
+                    });
+                }
+            }
+            if policy.groups_header.is_some() && policy.user_header.is_none() {
+                errors.push(ValidationError::InvalidField {
+                    field: field("groups_header"),
+                    message: "Requires user_header".to_string(),
+                });
+            }
+            if policy.rules.is_empty() {
+                errors.push(ValidationError::InvalidField {
+                    field: field("rules"),
+                    message: "At least one rule is required; without one every request is denied"
+                        .to_string(),
+                });
+            }
+            for (index, rule) in policy.rules.iter().enumerate() {
+                let field = |key: &str| field(&format!("rules[{index}].{key}"));
+                if rule.roles.is_empty() {
+                    errors.push(ValidationError::InvalidField {
+                        field: field("roles"),
+                        message: "Must not be empty".to_string(),
+                    });
+                }
+                for role in &rule.roles {
+                    if !policy.roles.contains_key(role) {
+                        errors.push(ValidationError::InvalidField {
+                            field: field("roles"),
+                            message: format!("Role '{role}' is not defined in roles"),
+                        });
+                    }
+                }
+                for method in &rule.methods {
+                    if http::Method::from_bytes(method.to_ascii_uppercase().as_bytes()).is_err() {
+                        errors.push(ValidationError::InvalidField {
+                            field: field("methods"),
+                            message: format!("Invalid method '{method}'"),
+                        });
+                    }
+                }
+                for path in &rule.paths {
+                    if !path.starts_with('/') {
+                        errors.push(ValidationError::InvalidField {
+                            field: field("paths"),
+                            message: format!("Path '{path}' must start with '/'"),
+                        });
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    /// Validate OPA settings: decision URL, headers, timeout and cache size
+    fn validate_opa(middleware_config: &MiddlewareConfig) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        for (name, opa) in &middleware_config.opa {
+            let field = |key: &str| format!("middleware.opa.{name}.{key}");
+            if !matches!(url::Url::parse(&opa.url), Ok(url) if url.scheme() == "http" || url.scheme() == "https")
+            {
+                errors.push(ValidationError::InvalidField {
+                    field: field("url"),
+                    message: format!(
+                        "'{}' is not an HTTP URL like 'http://127.0.0.1:8181/v1/data/httpapi/authz'",
+                        opa.url
+                    ),
+                });
+            }
+            for header in &opa.include_headers {
+                if http::HeaderName::from_bytes(header.as_bytes()).is_err() {
+                    errors.push(ValidationError::InvalidField {
+                        field: field("include_headers"),
+                        message: format!("Invalid header name '{header}'"),
+                    });
+                }
+            }
+            if opa.timeout_ms == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: field("timeout_ms"),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+            if opa.cache_ttl_secs > 0 && opa.cache_max_entries == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: field("cache_max_entries"),
+                    message: "Must be greater than 0 when decisions are cached".to_string(),
+                });
+            }
+        }
+        errors
+    }
+
+    /// Validate usage metering: sink, key header and buffer sizes
+    fn validate_usage(config: &UsageConfig) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        match &config.sink {
+            None => errors.push(ValidationError::InvalidField {
+                field: "middleware.usage.sink".to_string(),
+                message: "A sink is required for usage metering".to_string(),
+            }),
+            Some(UsageSinkConfig::File { path }) => {
+                if path.trim().is_empty() {
+                    errors.push(ValidationError::InvalidField {
+                        field: "middleware.usage.sink.path".to_string(),
+                        message: "Must not be empty".to_string(),
+                    });
+                }
+            }
+            Some(UsageSinkConfig::Webhook {
+                url,
+                headers,
+                batch_size,
+            }) => {
+                if let Err(e) = Self::validate_url(url, "middleware.usage.sink.url") {
+                    errors.push(e);
+                }
+                for name in headers.keys() {
+                    if http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                        errors.push(ValidationError::InvalidField {
+                            field: "middleware.usage.sink.headers".to_string(),
+                            message: format!("Invalid header name '{name}'"),
+                        });
+                    }
+                }
+                if *batch_size == 0 {
+                    errors.push(ValidationError::InvalidField {
+                        field: "middleware.usage.sink.batch_size".to_string(),
+                        message: "Must be greater than 0".to_string(),
+                    });
+                }
+            }
+        }
+        if let Some(name) = &config.key_header
+            && http::HeaderName::from_bytes(name.as_bytes()).is_err()
+        {
+            errors.push(ValidationError::InvalidField {
+                field: "middleware.usage.key_header".to_string(),
+                message: format!("Invalid header name '{name}'"),
+            });
+        }
+        for (field, value) in [
+            ("flush_interval_secs", config.flush_interval_secs),
+            ("queue_size", config.queue_size as u64),
+            ("max_pending_records", config.max_pending_records as u64),
+        ] {
+            if value == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("middleware.usage.{field}"),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+        }
+        errors
+    }
+
+    /// Validate a `WWW-Authenticate` realm
+    fn validate_realm(realm: &str, field: &str) -> Option<ValidationError> {
+        (realm.is_empty() || realm.chars().any(|c| c == '"' || c.is_control())).then(|| {
+            ValidationError::InvalidField {
+                field: field.to_string(),
+                message: "Must be non-empty, without quotes or control characters".to_string(),
+            }
+        })
+    }
+
+    /// Validate URL format
+    fn validate_url(url_str: &str, context: &str) -> ValidationResult<()> {
+        match url::Url::parse(url_str) {
+            Ok(url) => {
+                if url.scheme() != "http" && url.scheme() != "https" {
+                    return Err(ValidationError::InvalidField {
+                        field: context.to_string(),
+                        message: format!(
+                            "URL scheme must be 'http' or 'https', got '{}'",
+                            url.scheme()
+                        ),
+                    });
+                }
+
+                if url.host().is_none() {
+                    return Err(ValidationError::InvalidField {
+                        field: context.to_string(),
+                        message: "URL must have a valid host".to_string(),
+                    });
+                }
+
+                Ok(())
+            }
+            Err(e) => Err(ValidationError::InvalidField {
+                field: context.to_string(),
+                message: format!("Invalid URL format: {e}"),
+            }),
+        }
+    }
+
+    /// Validate WebSocket URL format
+    fn validate_websocket_url(url_str: &str, context: &str) -> ValidationResult<()> {
+        match url::Url::parse(url_str) {
+            Ok(url) => {
+                if url.scheme() != "ws" && url.scheme() != "wss" {
+                    return Err(ValidationError::InvalidField {
+                        field: context.to_string(),
+                        message: format!(
+                            "WebSocket URL scheme must be 'ws' or 'wss', got '{}'",
+                            url.scheme()
+                        ),
+                    });
+                }
+
+                if url.host().is_none() {
+                    return Err(ValidationError::InvalidField {
+                        field: context.to_string(),
+                        message: "WebSocket URL must have a valid host".to_string(),
+                    });
+                }
+
+                Ok(())
+            }
+            Err(e) => Err(ValidationError::InvalidField {
+                field: context.to_string(),
+                message: format!("Invalid WebSocket URL format: {e}"),
+            }),
+        }
+    }
+
+    /// Validate load balance strategy
+    fn validate_load_balance_strategy(
+        _path: &str,
+        _strategy: &LoadBalanceStrategy,
+    ) -> ValidationResult<()> {
+        // All available strategies are valid
+        Ok(())
+    }
+
+    /// Validate rate limit configuration
+    fn validate_rate_limit(path: &str, config: &RateLimitConfig) -> ValidationResult<()> {
+        if config.requests == 0 {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' rate_limit.requests"),
+                message: "Rate limit requests must be greater than 0".to_string(),
+            });
+        }
+
+        // Validate period format (e.g., "1s", "1m", "1h")
+        let period_regex = Regex::new(r"^\d+[smh]$").expect("Invalid regex pattern");
+        if !period_regex.is_match(&config.period) {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' rate_limit.period"),
+                message: "Period must be in format like '1s', '1m', or '1h'".to_string(),
+            });
+        }
+
+        if config.by == RateLimitBy::Composite && config.key.is_empty() {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' rate_limit.key"),
+                message: "A composite limit needs at least one key part".to_string(),
+            });
+        }
+
+        let uses_header = config.by == RateLimitBy::Header
+            || (config.by == RateLimitBy::Composite
+                && config.key.contains(&RateLimitKeyPart::Header));
+        if uses_header && config.header_name.is_none() {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' rate_limit.header_name"),
+                message: "Required when the limit is keyed by a header".to_string(),
+            });
+        }
+
+        if config.max_keys == 0 {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' rate_limit.max_keys"),
+                message: "Must be greater than 0".to_string(),
+            });
+        }
+
+        if let Some(ttl) = &config.key_ttl
+            && humantime::parse_duration(ttl).is_err()
+        {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' rate_limit.key_ttl"),
+                message: format!("'{ttl}' is not a valid duration"),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Validate path rewrite configuration
+    fn validate_path_rewrite(path: &str, rewrite: &str) -> ValidationResult<()> {
+        if rewrite.is_empty() {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' path_rewrite"),
+                message: "Path rewrite cannot be empty".to_string(),
+            });
+        }
+
+        if !rewrite.starts_with('/') {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' path_rewrite"),
+                message: "Path rewrite must start with '/'".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_health_check_config(
+        config: &HealthCheckConfig,
+    ) -> Result<(), Vec<ValidationError>> {
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let mut errors = Vec::new();
+
+        if config.interval_secs == 0 {
+            errors.push(ValidationError::InvalidField {
+                field: "health_check.interval_secs".to_string(),
+                message: "Must be greater than 0 when health checks are enabled".to_string(),
+            });
+        }
+
+        if config.timeout_secs == 0 {
+            errors.push(ValidationError::InvalidField {
+                field: "health_check.timeout_secs".to_string(),
+                message: "Must be greater than 0 when health checks are enabled".to_string(),
+            });
+        }
+
+        if config.unhealthy_threshold == 0 {
+            errors.push(ValidationError::InvalidField {
+                field: "health_check.unhealthy_threshold".to_string(),
+                message: "Must be greater than 0 when health checks are enabled".to_string(),
+            });
+        }
+
+        if config.healthy_threshold == 0 {
+            errors.push(ValidationError::InvalidField {
+                field: "health_check.healthy_threshold".to_string(),
+                message: "Must be greater than 0 when health checks are enabled".to_string(),
+            });
+        }
+
+        if config.max_concurrency == Some(0) {
+            errors.push(ValidationError::InvalidField {
+                field: "health_check.max_concurrency".to_string(),
+                message: "Must be greater than 0 when set".to_string(),
+            });
+        }
+
+        if config.path.trim().is_empty() {
+            errors.push(ValidationError::InvalidField {
+                field: "health_check.path".to_string(),
+                message: "Cannot be empty when health checks are enabled".to_string(),
+            });
+        } else if !config.path.starts_with('/') {
+            errors.push(ValidationError::InvalidField {
+                field: "health_check.path".to_string(),
+                message: "Must start with '/' when health checks are enabled".to_string(),
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate upstream connection pool and keep-alive settings
+    /// Validate extension mappings and proxy rules of the `[content_types]`
+    /// section
+    /// Validate the security audit log sink
+    fn validate_waf(config: &WafConfig) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        for (i, rule) in config.custom_rules.iter().enumerate() {
+            if rule.name.trim().is_empty() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("waf.custom_rules[{i}].name"),
+                    message: "Must not be empty".to_string(),
+                });
+            }
+            if let Err(e) = regex::Regex::new(&rule.pattern) {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("waf.custom_rules[{i}].pattern"),
+                    message: format!("Invalid regex: {e}"),
+                });
+            }
+        }
+
+        if config
+            .rules_file
+            .as_deref()
+            .is_some_and(|path| path.trim().is_empty())
+        {
+            errors.push(ValidationError::InvalidField {
+                field: "waf.rules_file".to_string(),
+                message: "Must not be empty".to_string(),
+            });
+        }
+
+        let auto_ban = &config.auto_ban;
+        if auto_ban.enabled {
+            for (field, value) in [
+                ("window_secs", auto_ban.window_secs),
+                ("ban_secs", auto_ban.ban_secs),
+                ("max_tracked_ips", auto_ban.max_tracked_ips as u64),
+            ] {
+                if value == 0 {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("waf.auto_ban.{field}"),
+                        message: "Must be greater than 0".to_string(),
+                    });
+                }
+            }
+            if auto_ban.waf_violations == 0 && auto_ban.client_errors == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: "waf.auto_ban".to_string(),
+                    message: "Set waf_violations or client_errors to a threshold above 0"
+                        .to_string(),
+                });
+            }
+            for entry in &auto_ban.exempt {
+                if let Err(e) = IpNetwork::parse(entry) {
+                    errors.push(ValidationError::InvalidField {
+                        field: "waf.auto_ban.exempt".to_string(),
+                        message: format!("'{entry}': {e}"),
+                    });
+                }
+            }
+        }
+
+        if config.rules_poll_interval_secs == Some(0) {
+            errors.push(ValidationError::InvalidField {
+                field: "waf.rules_poll_interval_secs".to_string(),
+                message: "Must be greater than 0".to_string(),
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_audit(config: &AuditConfig) -> Result<(), Vec<ValidationError>> {
+        if !config.enabled {
+            return Ok(());
+        }
+        let mut errors = Vec::new();
+
+        if config.queue_size == 0 {
+            errors.push(ValidationError::InvalidField {
+                field: "audit.queue_size".to_string(),
+                message: "Must be greater than 0".to_string(),
+            });
+        }
+
+        match &config.sink {
+            None => errors.push(ValidationError::InvalidField {
+                field: "audit.sink".to_string(),
+                message: "A sink is required when the audit log is enabled".to_string(),
+            }),
+            Some(AuditSinkConfig::File { path }) => {
+                if path.trim().is_empty() {
+                    errors.push(ValidationError::InvalidField {
+                        field: "audit.sink.path".to_string(),
+                        message: "Must not be empty".to_string(),
+                    });
+                }
+            }
+            Some(AuditSinkConfig::Syslog { address, facility }) => {
+                if address
+                    .rsplit_once(':')
+                    .is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err())
+                {
+                    errors.push(ValidationError::InvalidField {
+                        field: "audit.sink.address".to_string(),
+                        message: format!("'{address}' must be in format 'HOST:PORT'"),
+                    });
+                }
+                if *facility > 23 {
+                    errors.push(ValidationError::InvalidField {
+                        field: "audit.sink.facility".to_string(),
+                        message: "Must be a syslog facility code between 0 and 23".to_string(),
+                    });
+                }
+            }
+            Some(AuditSinkConfig::Webhook {
+                url,
+                headers,
+                batch_size,
+            }) => {
+                if let Err(e) = Self::validate_url(url, "audit.sink.url") {
+                    errors.push(e);
+                }
+                for (name, value) in headers {
+                    if http::HeaderName::from_bytes(name.as_bytes()).is_err()
+                        || http::HeaderValue::from_str(value).is_err()
+                    {
+                        errors.push(ValidationError::InvalidField {
+                            field: format!("audit.sink.headers.{name}"),
+                            message: "Invalid header name or value".to_string(),
+                        });
+                    }
+                }
+                if *batch_size == 0 {
+                    errors.push(ValidationError::InvalidField {
+                        field: "audit.sink.batch_size".to_string(),
+                        message: "Must be greater than 0".to_string(),
+                    });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_events(config: &EventsConfig) -> Result<(), Vec<ValidationError>> {
+        if !config.enabled {
+            return Ok(());
+        }
+        let mut errors = Vec::new();
+
+        if !(0.0..=1.0).contains(&config.sample_rate) {
+            errors.push(ValidationError::InvalidField {
+                field: "events.sample_rate".to_string(),
+                message: "Must be between 0.0 and 1.0".to_string(),
+            });
+        }
+        for (field, value) in [
+            ("events.queue_size", config.queue_size),
+            ("events.batch_size", config.batch_size),
+        ] {
+            if value == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: field.to_string(),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+        }
+        if let Some(name) = &config.tenant_header
+            && http::HeaderName::from_bytes(name.as_bytes()).is_err()
+        {
+            errors.push(ValidationError::InvalidField {
+                field: "events.tenant_header".to_string(),
+                message: format!("'{name}' is not a valid header name"),
+            });
+        }
+
+        let Some(sink) = &config.sink else {
+            errors.push(ValidationError::InvalidField {
+                field: "events.sink".to_string(),
+                message: "A sink is required when events are enabled".to_string(),
+            });
+            return Err(errors);
+        };
+        if !sink.is_available() {
+            let feature = sink.feature();
+            errors.push(ValidationError::InvalidField {
+                field: "events.sink".to_string(),
+                message: format!("'{feature}' requires building with the `{feature}` feature"),
+            });
+        }
+        match sink {
+            EventSinkConfig::Kafka { brokers, topic, .. } => {
+                if brokers.is_empty() || brokers.iter().any(|broker| broker.trim().is_empty()) {
+                    errors.push(ValidationError::InvalidField {
+                        field: "events.sink.brokers".to_string(),
+                        message: "At least one broker is required and none may be empty"
+                            .to_string(),
+                    });
+                }
+                if topic.trim().is_empty() {
+                    errors.push(ValidationError::InvalidField {
+                        field: "events.sink.topic".to_string(),
+                        message: "Must not be empty".to_string(),
+                    });
+                }
+            }
+            EventSinkConfig::Nats { url, subject } => {
+                if !["nats://", "tls://"]
+                    .iter()
+                    .any(|scheme| url.starts_with(scheme))
+                {
+                    errors.push(ValidationError::InvalidField {
+                        field: "events.sink.url".to_string(),
+                        message: format!("'{url}' must be a nats:// or tls:// URL"),
+                    });
+                }
+                if subject.trim().is_empty() || subject.contains(char::is_whitespace) {
+                    errors.push(ValidationError::InvalidField {
+                        field: "events.sink.subject".to_string(),
+                        message: "Must be a non-empty subject without whitespace".to_string(),
+                    });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_notifications(config: &NotificationsConfig) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        if config.webhooks.is_empty() {
+            return errors;
+        }
+        for (field, value) in [
+            ("notifications.timeout_ms", config.timeout_ms),
+            ("notifications.queue_size", config.queue_size as u64),
+        ] {
+            if value == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: field.to_string(),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+        }
+        for (i, webhook) in config.webhooks.iter().enumerate() {
+            let field = format!("notifications.webhooks[{i}]");
+            if let Err(e) = Self::validate_url(&webhook.url, &format!("{field}.url")) {
+                errors.push(e);
+            }
+            if webhook.secret.as_deref().is_some_and(str::is_empty) {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.secret"),
+                    message: "Must not be empty; omit it to send unsigned notifications"
+                        .to_string(),
+                });
+            }
+            for (name, value) in &webhook.headers {
+                if http::HeaderName::from_bytes(name.as_bytes()).is_err()
+                    || http::HeaderValue::from_str(value).is_err()
+                {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.headers.{name}"),
+                        message: "Invalid header name or value".to_string(),
+                    });
+                }
+            }
+        }
+        errors
+    }
+
+    fn validate_content_types(config: &ContentTypeConfig) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        // `type/subtype`, optionally followed by parameters
+        let is_media_type = |value: &str| {
+            let essence = value.split(';').next().unwrap_or_default().trim();
+            http::HeaderValue::from_str(value).is_ok()
+                && essence.split_once('/').is_some_and(|(kind, subtype)| {
+                    !kind.is_empty()
+                        && !subtype.is_empty()
+                        && !essence.contains(char::is_whitespace)
+                })
+        };
+
+        for (extension, media_type) in &config.extensions {
+            if extension.trim_start_matches('.').is_empty() {
+                errors.push(ValidationError::InvalidField {
+                    field: "content_types.extensions".to_string(),
+                    message: "Extensions must not be empty".to_string(),
+                });
+            }
+            if !is_media_type(media_type) {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("content_types.extensions.{extension}"),
+                    message: format!("'{media_type}' is not a valid media type"),
+                });
+            }
+        }
+
+        for (index, rule) in config.proxy_rules.iter().enumerate() {
+            let field = format!("content_types.proxy_rules[{index}]");
+            for pattern in &rule.backend_types {
+                let valid = pattern.eq_ignore_ascii_case("none")
+                    || pattern
+                        .split_once('/')
+                        .is_some_and(|(kind, subtype)| !kind.is_empty() && !subtype.is_empty());
+                if !valid {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.backend_types"),
+                        message: format!(
+                            "'{pattern}' must be a media type, a 'type/*' wildcard or 'none'"
+                        ),
+                    });
+                }
+            }
+            match (&rule.content_type, rule.action) {
+                (Some(media_type), ContentTypeAction::Correct) if !is_media_type(media_type) => {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.content_type"),
+                        message: format!("'{media_type}' is not a valid media type"),
+                    });
+                }
+                (Some(_), ContentTypeAction::Strip) => {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.content_type"),
+                        message: "Only applies to the 'correct' action".to_string(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_upstream_config(config: &UpstreamConfig) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        let non_zero_fields = [
+            ("upstream.connect_timeout_secs", config.connect_timeout_secs),
+            ("upstream.tcp_keepalive_secs", config.tcp_keepalive_secs),
+            (
+                "upstream.http2_keep_alive_interval_secs",
+                config.http2_keep_alive_interval_secs,
+            ),
+        ];
+        if let Err(mut timeout_errors) =
+            Self::validate_response_timeout("upstream.response_timeout", &config.response_timeout)
+        {
+            errors.append(&mut timeout_errors);
+        }
+        errors.extend(Self::validate_upstream_socket(
+            "upstream.socket",
+            &config.socket,
+        ));
+        // RFC 8305 section 5 sets a 10 ms floor
+        if config.happy_eyeballs_delay_ms < 10 {
+            errors.push(ValidationError::InvalidField {
+                field: "upstream.happy_eyeballs_delay_ms".to_string(),
+                message: "Must be at least 10".to_string(),
+            });
+        }
+        for (field, value) in non_zero_fields {
+            if value == Some(0) {
+                errors.push(ValidationError::InvalidField {
+                    field: field.to_string(),
+                    message: "Must be greater than 0 when set".to_string(),
+                });
+            }
+        }
+
+        if config.http2_keep_alive_interval_secs.is_some() {
+            if !config.http2_enabled {
+                errors.push(ValidationError::InvalidField {
+                    field: "upstream.http2_keep_alive_interval_secs".to_string(),
+                    message: "Requires upstream.http2_enabled = true".to_string(),
+                });
+            }
+            if config.http2_keep_alive_timeout_secs == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: "upstream.http2_keep_alive_timeout_secs".to_string(),
+                    message: "Must be greater than 0 when keep-alive pings are enabled".to_string(),
+                });
+            }
+        }
+
+        if let Some(spiffe) = &config.spiffe {
+            if !spiffe.workload_api_socket.starts_with("unix:")
+                || spiffe
+                    .workload_api_socket
+                    .trim_start_matches("unix:")
+                    .trim_start_matches("//")
+                    .is_empty()
+            {
+                errors.push(ValidationError::InvalidField {
+                    field: "upstream.spiffe.workload_api_socket".to_string(),
+                    message: "Must be a unix:// socket path".to_string(),
+                });
+            }
+            for domain in &spiffe.trust_domains {
+                if domain.is_empty()
+                    || !domain
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || ".-_".contains(c))
+                {
+                    errors.push(ValidationError::InvalidField {
+                        field: "upstream.spiffe.trust_domains".to_string(),
+                        message: format!("Invalid trust domain name '{domain}'"),
+                    });
+                }
+            }
+            for id in &spiffe.allowed_ids {
+                if !id.starts_with("spiffe://") {
+                    errors.push(ValidationError::InvalidField {
+                        field: "upstream.spiffe.allowed_ids".to_string(),
+                        message: format!("SPIFFE ID must start with 'spiffe://': '{id}'"),
+                    });
+                }
+            }
+        }
+
+        for name in &config.strip_headers {
+            if http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                errors.push(ValidationError::InvalidField {
+                    field: "upstream.strip_headers".to_string(),
+                    message: format!("Invalid header name '{name}'"),
+                });
+            }
+        }
+
+        for (name, value) in &config.default_headers {
+            if http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("upstream.default_headers.{name}"),
+                    message: "Invalid header name".to_string(),
+                });
+            } else if http::HeaderValue::from_str(value).is_err() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("upstream.default_headers.{name}"),
+                    message: "Invalid header value".to_string(),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate response timeouts, reported under `field`
+    fn validate_response_timeout(
+        field: &str,
+        config: &ResponseTimeoutConfig,
+    ) -> Result<(), Vec<ValidationError>> {
+        let errors: Vec<_> = [
+            ("header_secs", config.header_secs),
+            ("total_secs", config.total_secs),
+        ]
+        .into_iter()
+        .filter(|(_, value)| *value == Some(0))
+        .map(|(name, _)| ValidationError::InvalidField {
+            field: format!("{field}.{name}"),
+            message: "Must be greater than 0 when set".to_string(),
+        })
+        .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate backend socket options, reported under `field`
+    fn validate_upstream_socket(
+        field: &str,
+        config: &UpstreamSocketConfig,
+    ) -> Vec<ValidationError> {
+        let mut errors: Vec<_> = [
+            ("send_buffer_bytes", config.send_buffer_bytes),
+            ("recv_buffer_bytes", config.recv_buffer_bytes),
+        ]
+        .into_iter()
+        .filter(|(_, value)| *value == Some(0))
+        .map(|(name, _)| ValidationError::InvalidField {
+            field: format!("{field}.{name}"),
+            message: "Must be greater than 0 when set".to_string(),
+        })
+        .collect();
+        if config.dscp.is_some_and(|dscp| dscp > 63) {
+            errors.push(ValidationError::InvalidField {
+                field: format!("{field}.dscp"),
+                message: "DSCP code points range from 0 to 63".to_string(),
+            });
+        }
+        errors
+    }
+
+    /// Validate backend TLS settings, reported under `field` (e.g. `route
+    /// '/api' upstream_tls`)
+    fn validate_upstream_tls(
+        field: &str,
+        config: &UpstreamTlsConfig,
+    ) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        let files = [
+            ("ca_bundle", &config.ca_bundle),
+            ("client_cert", &config.client_cert),
+            ("client_key", &config.client_key),
+        ];
+        for (name, file) in files {
+            if let Some(file) = file {
+                if !std::path::Path::new(file).exists() {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.{name}"),
+                        message: format!("File does not exist: {file}"),
+                    });
+                }
+            }
+        }
+
+        if config.spiffe
+            && (config.ca_bundle.is_some()
+                || config.client_cert.is_some()
+                || config.client_key.is_some()
+                || config.insecure_skip_verify)
+        {
+            errors.push(ValidationError::InvalidField {
+                field: format!("{field}.spiffe"),
+                message: "SPIFFE identities cannot be combined with ca_bundle, client_cert/client_key or insecure_skip_verify".to_string(),
+            });
+        }
+
+        if config.client_cert.is_some() != config.client_key.is_some() {
+            errors.push(ValidationError::InvalidField {
+                field: field.to_string(),
+                message: "client_cert and client_key must be configured together".to_string(),
+            });
+        }
+
+        if let Some(name) = &config.server_name {
+            if rustls::pki_types::ServerName::try_from(name.as_str()).is_err() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.server_name"),
+                    message: format!("Invalid server name: '{name}'"),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate the canary group and its rollback thresholds
+    fn validate_canary(path: &str, config: &CanaryConfig) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        if config.targets.is_empty() {
+            errors.push(ValidationError::InvalidField {
+                field: format!("route '{path}' canary.targets"),
+                message: "Canary must have at least one target".to_string(),
+            });
+        }
+        for (i, target) in config.targets.iter().enumerate() {
+            if let Err(e) =
+                Self::validate_url(target, &format!("route '{path}' canary target {}", i + 1))
+            {
+                errors.push(e);
+            }
+        }
+
+        if config.weight > 100 {
+            errors.push(ValidationError::InvalidField {
+                field: format!("route '{path}' canary.weight"),
+                message: "Weight must be a percentage between 0 and 100".to_string(),
+            });
+        }
+
+        if let Some(rollback) = &config.rollback {
+            if rollback.window_secs == 0 {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' canary.rollback.window_secs"),
+                    message: "Must be greater than 0".to_string(),
+                });
+            }
+            if rollback.max_error_rate_increase.is_none() && rollback.max_latency_ratio.is_none() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("route '{path}' canary.rollback"),
+                    message: "Set max_error_rate_increase and/or max_latency_ratio".to_string(),
+                });
+            }
+            if let Some(increase) = rollback.max_error_rate_increase {
+                if !(0.0..=1.0).contains(&increase) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' canary.rollback.max_error_rate_increase"),
+                        message: "Must be between 0.0 and 1.0".to_string(),
+                    });
+                }
+            }
+            if let Some(ratio) = rollback.max_latency_ratio {
+                if !(ratio >= 1.0 && ratio.is_finite()) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("route '{path}' canary.rollback.max_latency_ratio"),
+                        message: "Must be a finite value of at least 1.0".to_string(),
+                    });
+                }
+            }
+            if let Some(url) = &rollback.webhook_url {
+                if let Err(e) =
+                    Self::validate_url(url, &format!("route '{path}' canary.rollback.webhook_url"))
+                {
+                    errors.push(e);
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate TLS configuration
+    fn validate_tls_config(config: &TlsConfig) -> ValidationResult<()> {
+        match (&config.cert_path, &config.key_path) {
+            (Some(cert), Some(key)) => {
+                // Manual certificate configuration
+                if !std::path::Path::new(cert).exists() {
+                    return Err(ValidationError::InvalidTls {
+                        message: format!("Certificate file does not exist: {cert}"),
+                    });
+                }
+
+                if !std::path::Path::new(key).exists() {
+                    return Err(ValidationError::InvalidTls {
+                        message: format!("Private key file does not exist: {key}"),
+                    });
+                }
+
+                Ok(())
+            }
+            _ => {
+                if let Some(acme) = &config.acme {
+                    if acme.domains.is_empty() {
+                        return Err(ValidationError::InvalidTls {
+                            message: "ACME configuration must include at least one domain"
+                                .to_string(),
+                        });
+                    }
+
+                    if acme.email.trim().is_empty() {
+                        return Err(ValidationError::InvalidTls {
+                            message: "ACME configuration must include a contact email".to_string(),
+                        });
+                    }
+
+                    Ok(())
+                } else {
+                    Err(ValidationError::InvalidTls {
+                        message: "TLS configuration must specify either certificate and private key paths, or ACME configuration".to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    /// Extract host from a RouteConfig
+    fn get_route_host(route: &RouteConfig) -> Option<&String> {
+        match route {
+            RouteConfig::Static { host, .. } => host.as_ref(),
+            RouteConfig::Redirect { host, .. } => host.as_ref(),
+            RouteConfig::Proxy { host, .. } => host.as_ref(),
+            RouteConfig::LoadBalance { host, .. } => host.as_ref(),
+            RouteConfig::Websocket { host, .. } => host.as_ref(),
+        }
+    }
+
+    /// Validate the built-in endpoints' paths, which must not collide, and
+    /// their access rules.
+    fn validate_endpoints(config: &ServerConfig) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let mut seen: std::collections::HashMap<&str, &str> = std::collections::HashMap::new();
+        for endpoint in BuiltinEndpoint::ALL {
+            let rules = config.endpoints.get(endpoint);
+            let field = format!("endpoints.{}", endpoint.name());
+            if let Some(path) = &rules.path
+                && (!path.starts_with('/') || path.starts_with("/admin/"))
+            {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.path"),
+                    message: "Must start with '/' and not be under /admin/".to_string(),
+                });
+            }
+            if let Some(path) = config.endpoints.public_path(endpoint)
+                && let Some(other) = seen.insert(path, endpoint.name())
+            {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.path"),
+                    message: format!("'{path}' is already used by endpoints.{other}"),
+                });
+            }
+            for entry in &rules.allow {
+                if let Err(e) = IpNetwork::parse(entry) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.allow"),
+                        message: format!("'{entry}': {e}"),
+                    });
+                }
+            }
+            if rules.token.as_deref().is_some_and(|t| t.trim().is_empty()) {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.token"),
+                    message: "Must not be empty".to_string(),
+                });
+            }
+            if rules.enabled && rules.admin && !config.admin.enabled {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.admin"),
+                    message: "Requires the admin API to be enabled".to_string(),
+                });
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate virtual hosts: their hostnames, which may belong to only one
+    /// of them, and the global settings their overrides build on.
+    fn validate_virtual_hosts(config: &ServerConfig) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let mut names: Vec<&String> = config.virtual_hosts.keys().collect();
+        names.sort();
+        let mut seen: std::collections::HashMap<String, &str> = std::collections::HashMap::new();
+        for name in names {
+            let vhost = &config.virtual_hosts[name];
+            let field = format!("virtual_hosts.{name}");
+            if vhost.hostnames.is_empty() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.hostnames"),
+                    message: "List at least one hostname".to_string(),
+                });
+            }
+            for hostname in &vhost.hostnames {
+                if let Err(e) = Self::validate_host(hostname, &field) {
+                    errors.push(e);
+                }
+                if let Some(other) = seen.insert(hostname.to_lowercase(), name) {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.hostnames"),
+                        message: format!("'{hostname}' is already served by '{other}'"),
+                    });
+                }
+            }
+            if let Some(tls) = &vhost.tls {
+                let manual = config
+                    .tls
+                    .as_ref()
+                    .is_some_and(|tls| tls.acme.is_none() && tls.cert_path.is_some());
+                if !manual {
+                    errors.push(ValidationError::InvalidField {
+                        field: format!("{field}.tls"),
+                        message: "Requires a [tls] section with cert_path and key_path".to_string(),
+                    });
+                }
+                for path in [&tls.cert_path, &tls.key_path] {
+                    if !std::path::Path::new(path).exists() {
+                        errors.push(ValidationError::InvalidField {
+                            field: format!("{field}.tls"),
+                            message: format!("File does not exist: {path}"),
+                        });
+                    }
+                }
+            }
+            if vhost.waf == Some(true) && config.waf.is_none() {
+                errors.push(ValidationError::InvalidField {
+                    field: format!("{field}.waf"),
+                    message: "Requires a [waf] section".to_string(),
+                });
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check for conflicting route paths
+    ///
+    /// Conflicts occur when:
+    /// 1. Same path with same host (or both without host)
+    /// 2. The router can't hold both paths for the same host, as with
+    ///    `/users/{id}` and `/users/{name}`, so one would never match
+    ///
+    /// Nested prefixes such as `/api` and `/api/v2` don't conflict; the
+    /// longest matching prefix wins.
+    fn check_route_conflicts(
+        routes: &std::collections::HashMap<String, RouteConfigEntry>,
+    ) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        // Collect all (path, host) pairs
+        let mut route_entries: Vec<(&String, Option<String>)> = Vec::new();
+        for (path, entry) in routes {
+            for route in entry.iter() {
+                route_entries.push((path, Self::get_route_host(route).map(|h| h.to_lowercase())));
+            }
+        }
+        route_entries.sort();
+
+        // Same path + same host = conflict
+        for pair in route_entries.windows(2) {
+            if pair[0] == pair[1] {
+                let (path, host) = &pair[0];
+                let host_desc = host
+                    .as_ref()
+                    .map(|h| format!(" (host: {h})"))
+                    .unwrap_or_else(|| " (no host)".to_string());
+                errors.push(ValidationError::RouteConflict {
+                    message: format!("Duplicate route configuration for path '{path}'{host_desc}"),
+                });
+            }
+        }
+        route_entries.dedup();
+
+        // Build the routers the gateway builds, one per host, and report the
+        // paths they refuse
+        let mut routers: std::collections::HashMap<&Option<String>, matchit::Router<&String>> =
+            std::collections::HashMap::new();
+        for (path, host) in &route_entries {
+            if !path.starts_with('/') {
+                continue;
+            }
+            let router = routers.entry(host).or_default();
+            for pattern in prefix_patterns(path) {
+                let message = match router.insert(pattern, *path) {
+                    Ok(()) => continue,
+                    Err(matchit::InsertError::Conflict { with }) => {
+                        let other = router.at(&with).map_or(with.as_str(), |m| m.value.as_str());
+                        format!(
+                            "Routes '{other}' and '{path}' have conflicting paths, requests can't tell them apart"
+                        )
+                    }
+                    Err(e) => format!("Route '{path}' is not a valid path pattern: {e}"),
+                };
+                let host_desc = host
+                    .as_ref()
+                    .map(|h| format!(" (host: {h})"))
+                    .unwrap_or_default();
+                errors.push(ValidationError::RouteConflict {
+                    message: format!("{message}{host_desc}"),
+                });
+                break;
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check that routes able to match the same request don't share an
+    /// explicit priority, which would leave their order unclear
+    fn check_route_priorities(
+        routes: &std::collections::HashMap<String, RouteConfigEntry>,
+    ) -> Result<(), Vec<ValidationError>> {
+        let mut ranked: Vec<(&String, Option<&String>, i32)> = routes
+            .iter()
+            .flat_map(|(path, entry)| entry.iter().map(move |route| (path, route)))
+            .filter_map(|(path, route)| {
+                Some((path, Self::get_route_host(route), route.priority()?))
+            })
+            .collect();
+        ranked.sort();
+
+        let mut errors = Vec::new();
+        for (i, (path1, host1, priority)) in ranked.iter().enumerate() {
+            for (path2, host2, _) in ranked[i + 1..].iter().filter(|r| r.2 == *priority) {
+                let duplicate = path1 == path2
+                    && host1.map(|h| h.to_lowercase()) == host2.map(|h| h.to_lowercase());
+                if duplicate
+                    || !Self::prefixes_overlap(path1, path2)
+                    || !Self::hosts_overlap(*host1, *host2)
+                {
+                    continue;
+                }
+                let describe = |path: &str, host: Option<&String>| match host {
+                    Some(h) => format!("'{path}' (host: {h})"),
+                    None => format!("'{path}'"),
+                };
+                errors.push(ValidationError::RouteConflict {
+                    message: format!(
+                        "Routes {} and {} can match the same requests and share priority {priority}",
+                        describe(path1, *host1),
+                        describe(path2, *host2)
+                    ),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Whether some request path falls under both route prefixes: one prefix
+    /// covers the other, with `{param}` segments matching any segment
+    fn prefixes_overlap(prefix1: &str, prefix2: &str) -> bool {
+        let segments = |prefix: &'_ str| -> Vec<String> {
+            prefix
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        let (p1, p2) = (segments(prefix1), segments(prefix2));
+        p1.iter()
+            .zip(&p2)
+            .all(|(a, b)| a == b || a.starts_with('{') || b.starts_with('{'))
+    }
+
+    /// Whether some Host header is served by both route hosts, where a route
+    /// without a host serves every host
+    fn hosts_overlap(host1: Option<&String>, host2: Option<&String>) -> bool {
+        let (Some(host1), Some(host2)) = (host1, host2) else {
+            return true;
+        };
+        let (host1, host2) = (host1.to_lowercase(), host2.to_lowercase());
+        host_candidates(&host1).any(|candidate| candidate == host2)
+            || host_candidates(&host2).any(|candidate| candidate == host1)
+    }
+
+    /// Check if status code is valid for redirects
+    fn is_valid_redirect_status_code(code: u16) -> bool {
+        matches!(code, 301 | 302 | 307 | 308)
+    }
+
+    /// Validate host field format
+    fn validate_host(host: &str, path: &str) -> ValidationResult<()> {
+        if host.is_empty() {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' host"),
+                message: "Host cannot be empty".to_string(),
+            });
+        }
+
+        // Check for invalid characters
+        if host.contains("://") {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' host"),
+                message: "Host should not contain protocol (e.g., use 'example.com' not 'http://example.com')".to_string(),
+            });
+        }
+
+        // `*.example.com` covers one label below example.com
+        let host = host.strip_prefix("*.").unwrap_or(host);
+
+        // Simple hostname validation
+        let hostname_regex = Regex::new(r"^[a-zA-Z0-9]([a-zA-Z0-9\-]{0,61}[a-zA-Z0-9])?(\.[a-zA-Z0-9]([a-zA-Z0-9\-]{0,61}[a-zA-Z0-9])?)*$").expect("invalid hostname regex");
+
+        if !hostname_regex.is_match(host) {
+            return Err(ValidationError::InvalidField {
+                field: format!("route '{path}' host"),
+                message: format!("Invalid hostname format: '{host}'"),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Format multiple validation errors into a single message
+    fn format_multiple_errors(errors: Vec<ValidationError>) -> String {
+        if errors.is_empty() {
+            return "No errors".to_string();
+        }
+
+        if errors.len() == 1 {
+            return errors[0].to_string();
+        }
+
+        let mut message = format!("Found {} validation errors:\n", errors.len());
+        for (i, error) in errors.iter().enumerate() {
+            message.push_str(&format!("  {}. {}\n", i + 1, error));
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::models::{
+        AcmeConfig, AuthMiddlewareConfig, BackendHealthCheckConfig, BackendLimits, BasicAuthConfig,
+        BasicAuthCredentials, BodyReplacement, CanaryRollbackConfig, ContentTypeRule,
+        CookiePolicyConfig, ErrorPageTemplate, ExperimentVariant, FallbackConfig,
+        HealthCheckConfig, HstsConfig, JsonSchemaConfig, LdapAuthConfig, LdapSearchConfig,
+        NotificationEvent, NotificationWebhookConfig, OpaConfig, RbacPolicyConfig, RbacRoleBinding,
+        RbacRule, RequestCondition, RetiredCookieSecret, RetryBudgetConfig, RetryConfig,
+        RouteMetricsConfig, ScheduleWindow, SchemaViolationAction, SpiffeConfig, StatusMapping,
+        TapConfig, TunnelConfig, UploadPolicyConfig, UploadScanConfig, VirtualHostConfig,
+        VirtualHostTlsConfig, WafCustomRule, WafRuleTarget, WasmFilterConfig,
+    };
+
+    fn minimal_valid_config() -> ServerConfig {
+        ServerConfig {
+            listen_addr: "127.0.0.1:8080".to_string(),
+            routes: [(
+                "/".to_string(),
+                RouteConfig::proxy("http://localhost:3000").into(),
+            )]
+            .into_iter()
+            .collect(),
+            ..ServerConfig::default()
+        }
+    }
+
+    fn base_config_with_tls(tls: TlsConfig) -> ServerConfig {
+        let mut config = minimal_valid_config();
+        config.tls = Some(tls);
+        config
+    }
+
+    fn make_valid_enabled_health_check() -> HealthCheckConfig {
+        HealthCheckConfig {
+            enabled: true,
+            interval_secs: 10,
+            timeout_secs: 5,
+            path: "/health".to_string(),
+            unhealthy_threshold: 3,
+            healthy_threshold: 2,
+            max_concurrency: None,
+        }
+    }
+
+    #[test]
+    fn validate_accepts_acme_tls_config() {
+        let config = base_config_with_tls(TlsConfig {
+            cert_path: None,
+            key_path: None,
+            acme: Some(AcmeConfig {
+                domains: vec!["example.com".to_string()],
+                email: "admin@example.com".to_string(),
+                cache_dir: ".axon/acme_cache".to_string(),
+                production: false,
+            }),
+            sni_host_check: Default::default(),
+        });
+
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_empty_acme_domains() {
+        let config = base_config_with_tls(TlsConfig {
+            cert_path: None,
+            key_path: None,
+            acme: Some(AcmeConfig {
+                domains: vec![],
+                email: "admin@example.com".to_string(),
+                cache_dir: ".axon/acme_cache".to_string(),
+                production: false,
+            }),
+            sni_host_check: Default::default(),
+        });
+
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_zero_health_check_interval_when_enabled() {
+        let mut config = minimal_valid_config();
+        config.health_check = make_valid_enabled_health_check();
+        config.health_check.interval_secs = 0;
+
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_admin_requires_token() {
+        let mut config = minimal_valid_config();
+        config.admin.enabled = true;
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        config.admin.token = Some("change-me".to_string());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+    }
+
+    #[test]
+    fn validate_content_type_rules() {
+        let mut config = minimal_valid_config();
+        config
+            .content_types
+            .extensions
+            .insert("wasm".to_string(), "application/wasm".to_string());
+        config.content_types.proxy_rules.push(ContentTypeRule {
+            extensions: vec!["json".to_string()],
+            backend_types: vec!["text/*".to_string(), "none".to_string()],
+            action: ContentTypeAction::Correct,
+            content_type: None,
+        });
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.content_types.proxy_rules[0].backend_types = vec!["html".to_string()];
+        config
+            .content_types
+            .extensions
+            .insert("md".to_string(), "markdown".to_string());
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("proxy_rules[0].backend_types"));
+        assert!(err.to_string().contains("content_types.extensions.md"));
+    }
+
+    #[test]
+    fn validate_rejects_zero_health_check_concurrency() {
+        let mut config = minimal_valid_config();
+        config.health_check = make_valid_enabled_health_check();
+        config.health_check.max_concurrency = Some(0);
+
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_http2_keep_alive_without_http2() {
+        let mut config = minimal_valid_config();
+        config.upstream.http2_keep_alive_interval_secs = Some(30);
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        config.upstream.http2_enabled = true;
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.upstream.connect_timeout_secs = Some(0);
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_invalid_default_header() {
+        let mut config = minimal_valid_config();
+        config
+            .upstream
+            .default_headers
+            .insert("X-Gateway".to_string(), "axon".to_string());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config
+            .upstream
+            .default_headers
+            .insert("bad header".to_string(), "x".to_string());
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_canary_weight_and_thresholds() {
+        let mut canary = CanaryConfig {
+            targets: vec!["http://canary:3000".to_string()],
+            weight: 10,
+            rollback: Some(CanaryRollbackConfig {
+                window_secs: 60,
+                min_requests: 20,
+                max_error_rate_increase: Some(0.05),
+                max_latency_ratio: None,
+                webhook_url: Some("https://hooks.example.com/rollback".to_string()),
+            }),
+        };
+        assert!(ServerConfigValidator::validate_canary("/api", &canary).is_ok());
+
+        canary.weight = 101;
+        assert!(ServerConfigValidator::validate_canary("/api", &canary).is_err());
+
+        canary.weight = 10;
+        if let Some(rollback) = canary.rollback.as_mut() {
+            rollback.max_error_rate_increase = None;
+        }
+        assert!(ServerConfigValidator::validate_canary("/api", &canary).is_err());
+    }
+
+    #[test]
+    fn validate_requires_spiffe_section_for_spiffe_routes() {
+        let mut config = minimal_valid_config();
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { upstream_tls, .. } = route.as_mut()
+        {
+            *upstream_tls = Some(UpstreamTlsConfig {
+                spiffe: true,
+                ..UpstreamTlsConfig::default()
+            });
+        }
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        config.upstream.spiffe = Some(SpiffeConfig::default());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.upstream.spiffe = Some(SpiffeConfig {
+            allowed_ids: vec!["example.org/api".to_string()],
+            ..SpiffeConfig::default()
+        });
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_unknown_middleware() {
+        let mut config = minimal_valid_config();
+        let set_middlewares = |config: &mut ServerConfig, names: &[&str]| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { middlewares, .. } = route.as_mut()
+            {
+                *middlewares = names.iter().map(|n| n.to_string()).collect();
+            }
+        };
+
+        set_middlewares(&mut config, &["request_id", "compression"]);
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_middlewares(&mut config, &["gzip"]);
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        set_middlewares(&mut config, &["auth"]);
+        assert!(ServerConfigValidator::validate(&config).is_err());
+        config.middleware.auth = Some(AuthMiddlewareConfig {
+            tokens: vec!["secret".to_string()],
+            header: "Authorization".to_string(),
+        });
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+    }
+
+    #[test]
+    fn validate_route_health_endpoint() {
+        let mut config = minimal_valid_config();
+        let set_endpoint = |config: &mut ServerConfig, endpoint: &str| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy {
+                    health_endpoint, ..
+                } = route.as_mut()
+            {
+                *health_endpoint = Some(endpoint.to_string());
+            }
+        };
+
+        set_endpoint(&mut config, "/_health");
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_endpoint(&mut config, "_health");
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        set_endpoint(&mut config, "/");
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_locale_groups() {
+        let mut config = minimal_valid_config();
+        let set_groups = |config: &mut ServerConfig, language: &str, targets: Vec<&str>| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { locale, .. } = route.as_mut()
+            {
+                *locale = Some(LocaleRoutingConfig {
+                    groups: [(
+                        language.to_string(),
+                        targets.into_iter().map(String::from).collect(),
+                    )]
+                    .into(),
+                    header: "X-Matched-Language".to_string(),
+                });
+            }
+        };
+
+        set_groups(&mut config, "de", vec!["http://eu-backend:3000"]);
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_groups(&mut config, "de-DE", vec!["http://eu-backend:3000"]);
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        set_groups(&mut config, "de", vec![]);
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_error_page_template_keys() {
+        let mut config = minimal_valid_config();
+        for key in ["404", "5xx", "default"] {
+            config
+                .error_pages
+                .templates
+                .insert(key.to_string(), ErrorPageTemplate::default());
+        }
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config
+            .error_pages
+            .templates
+            .insert("600".to_string(), ErrorPageTemplate::default());
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        config.error_pages.templates.remove("600");
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { error_pages, .. } = route.as_mut()
+        {
+            *error_pages = Some(ErrorPagesConfig {
+                format: None,
+                templates: [("40x".to_string(), ErrorPageTemplate::default())].into(),
+                intercept: None,
+            });
+        }
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        let mut config = minimal_valid_config();
+        config.error_pages.intercept = Some(vec!["5xx".to_string(), "404".to_string()]);
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+        config.error_pages.intercept = Some(vec!["302".to_string()]);
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_action_condition_expressions() {
+        let mut config = minimal_valid_config();
+        let set_expr = |config: &mut ServerConfig, expr: &str| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy {
+                    response_headers, ..
+                } = route.as_mut()
+            {
+                *response_headers = Some(HeaderActions {
+                    add: [("X-Tier".to_string(), "gold".to_string())].into(),
+                    remove: vec![],
+                    condition: Some(RequestCondition {
+                        path_matches: None,
+                        method_is: None,
+                        has_header: None,
+                        expr: Some(expr.to_string()),
+                    }),
+                });
+            }
+        };
+
+        set_expr(&mut config, "req.header('x-tier') == 'gold'");
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_expr(&mut config, "req.header('x-tier') = 'gold'");
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("response_headers.condition"));
+    }
+
+    #[test]
+    fn validate_body_rewrites() {
+        let mut config = minimal_valid_config();
+        let set_rewrite = |config: &mut ServerConfig, find: &str, on_request: bool| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy {
+                    request_body,
+                    response_body,
+                    ..
+                } = route.as_mut()
+            {
+                let actions = BodyActions {
+                    replace: vec![BodyReplacement {
+                        find: find.to_string(),
+                        replace_with: "https://api.example.com".to_string(),
+                        regex: true,
+                    }],
+                    inject_html: Some("<script src=\"/rum.js\"></script>".to_string()),
+                    ..BodyActions::default()
+                };
+                (*request_body, *response_body) = if on_request {
+                    (Some(actions), None)
+                } else {
+                    (None, Some(actions))
+                };
+            }
+        };
+
+        set_rewrite(&mut config, r"https?://backend:\d+", false);
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_rewrite(&mut config, "(unclosed", false);
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("response_body"));
+
+        set_rewrite(&mut config, "backend", true);
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_websocket_session_limits() {
+        let mut config = minimal_valid_config();
+        let set_session = |config: &mut ServerConfig, secs: u64, code: u16, reason: &str| {
+            config.routes.insert(
+                "/ws".to_string(),
+                RouteConfigEntry::Single(Box::new(RouteConfig::Websocket {
+                    target: "ws://localhost:3001".to_string(),
+                    host: None,
+                    path_rewrite: None,
+                    rate_limit: None,
+                    max_frame_size: None,
+                    max_message_size: None,
+                    idle_timeout_secs: None,
+                    max_session_secs: Some(secs),
+                    session_close_code: Some(code),
+                    session_close_reason: Some(reason.to_string()),
+                    subprotocols: None,
+                    middlewares: vec![],
+                    error_pages: None,
+                    disable_http3: false,
+                    priority: None,
+                })),
+            );
+        };
+
+        set_session(&mut config, 3600, 4001, "reauthenticate");
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_session(&mut config, 0, 4001, "reauthenticate");
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        set_session(&mut config, 3600, 1006, "reauthenticate");
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        set_session(&mut config, 3600, 1001, &"x".repeat(124));
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_dedupe_window() {
+        let mut config = minimal_valid_config();
+        let set_dedupe = |config: &mut ServerConfig, headers: &[&str], include_body: bool| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { dedupe, .. } = route.as_mut()
+            {
+                *dedupe = Some(DedupeConfig {
+                    headers: headers.iter().map(|h| h.to_string()).collect(),
+                    include_body,
+                    ttl_secs: 300,
+                    status_code: 200,
+                    response_body: None,
+                    max_body_size: 1024,
+                    max_entries: 100,
+                });
+            }
+        };
+
+        set_dedupe(&mut config, &["X-GitHub-Delivery"], false);
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_dedupe(&mut config, &[], false);
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        set_dedupe(&mut config, &["bad header"], true);
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_fastcgi_routes() {
+        let mut config = minimal_valid_config();
+        let set_fastcgi = |config: &mut ServerConfig, url: &str, root: Option<&str>| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy {
+                    target, fastcgi, ..
+                } = route.as_mut()
+            {
+                *target = url.to_string();
+                *fastcgi = root.map(|root| FastCgiConfig {
+                    root: root.to_string(),
+                    index: "index.php".to_string(),
+                    script: None,
+                    script_extension: ".php".to_string(),
+                    params: std::collections::BTreeMap::new(),
+                });
+            }
+        };
+
+        set_fastcgi(&mut config, "fastcgi://127.0.0.1:9000", Some("/var/www"));
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        // Missing section, missing port, relative root, section without target
+        set_fastcgi(&mut config, "fastcgi://127.0.0.1:9000", None);
+        assert!(ServerConfigValidator::validate(&config).is_err());
+        set_fastcgi(&mut config, "fastcgi://127.0.0.1", Some("/var/www"));
+        assert!(ServerConfigValidator::validate(&config).is_err());
+        set_fastcgi(&mut config, "fastcgi://127.0.0.1:9000", Some("www"));
+        assert!(ServerConfigValidator::validate(&config).is_err());
+        set_fastcgi(&mut config, "http://127.0.0.1:8080", Some("/var/www"));
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_header_allowlist() {
+        let mut config = minimal_valid_config();
+        config.header_allowlist.request =
+            Some(vec!["Authorization".to_string(), "X-Request-*".to_string()]);
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy {
+                header_allowlist, ..
+            } = route.as_mut()
+        {
+            *header_allowlist = Some(HeaderAllowlistConfig {
+                request: None,
+                response: Some(vec!["*".to_string(), "Bad Header".to_string()]),
+            });
+        }
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("header_allowlist.response"));
+    }
+
+    #[test]
+    fn validate_request_coalescing() {
+        let mut config = minimal_valid_config();
+        let set_coalesce = |config: &mut ServerConfig, bypass_header: &str, max_waiters| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { coalesce, .. } = route.as_mut()
+            {
+                *coalesce = Some(CoalesceConfig {
+                    key_headers: vec!["Authorization".to_string()],
+                    max_waiters,
+                    bypass_header: Some(bypass_header.to_string()),
+                    max_body_bytes: 65536,
+                });
+            }
+        };
+
+        set_coalesce(&mut config, "X-No-Coalesce", 100);
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_coalesce(&mut config, "bad header", 0);
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("coalesce.bypass_header"));
+        assert!(err.contains("coalesce.max_waiters"));
+    }
+
+    #[test]
+    fn validate_hedge_policy() {
+        let mut config = minimal_valid_config();
+        let set_route = |config: &mut ServerConfig, targets: &[&str], hedge| {
+            let route = serde_json::from_value(serde_json::json!({
+                "type": "load_balance",
+                "targets": targets,
+                "strategy": "round_robin",
+                "hedge": hedge,
+            }))
+            .unwrap();
+            config.routes.insert(
+                "/api".to_string(),
+                RouteConfigEntry::Single(Box::new(route)),
+            );
+        };
+
+        set_route(
+            &mut config,
+            &["http://a:3000", "http://b:3000"],
+            serde_json::json!({ "percentile": 99.0, "max_delay_ms": 200 }),
+        );
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_route(
+            &mut config,
+            &["http://a:3000"],
+            serde_json::json!({ "percentile": 0.0, "min_delay_ms": 500, "max_delay_ms": 200 }),
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("at least two targets"));
+        assert!(err.contains("hedge.percentile"));
+        assert!(err.contains("hedge.min_delay_ms"));
+
+        set_route(
+            &mut config,
+            &["http://a:3000", "http://b:3000"],
+            serde_json::json!({ "idempotency_header": "X-Request-Key" }),
+        );
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/api")
+            && let RouteConfig::LoadBalance { retry, .. } = route.as_mut()
+        {
+            *retry = Some(crate::config::models::RetryConfig::default());
+        }
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Must match retry.idempotency_header ('Idempotency-Key')"));
+    }
+
+    #[test]
+    fn validate_route_schedule() {
+        let mut config = minimal_valid_config();
+        let set_schedule = |config: &mut ServerConfig, timezone: &str, target: &str| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { schedule, .. } = route.as_mut()
+            {
+                *schedule = Some(RouteScheduleConfig {
+                    timezone: timezone.to_string(),
+                    active_outside_windows: true,
+                    windows: vec![ScheduleWindow {
+                        days: vec!["sun".to_string()],
+                        start: Some("02:00".to_string()),
+                        end: Some("04:00".to_string()),
+                        targets: vec![target.to_string()],
+                        ..ScheduleWindow::default()
+                    }],
+                });
+            }
+        };
+
+        set_schedule(&mut config, "+01:00", "http://maintenance:8080");
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_schedule(&mut config, "Europe/Paris", "http://maintenance:8080");
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("invalid timezone"));
+
+        set_schedule(&mut config, "UTC", "maintenance:8080");
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("windows[0].targets"));
+    }
+
+    #[test]
+    fn validate_experiments() {
+        let mut config = minimal_valid_config();
+        let variant = |name: &str, percent| ExperimentVariant {
+            name: name.to_string(),
+            percent,
+        };
+        config.experiments.push(ExperimentConfig {
+            name: "checkout".to_string(),
+            routes: vec!["/shop".to_string()],
+            variants: vec![variant("control", 50), variant("new", 50)],
+            cookie: None,
+            cookie_max_age_secs: 3600,
+            header: None,
+        });
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.experiments[0].variants[1].percent = 40;
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("add up to 100, got 90"));
+
+        config.experiments[0].variants[1] = variant("control", 50);
+        config.experiments[0].header = Some("Bad Header".to_string());
+        config.experiments.push(ExperimentConfig {
+            name: "bad name".to_string(),
+            ..config.experiments[0].clone()
+        });
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("experiments[0].variants"));
+        assert!(err.contains("experiments[0].header"));
+        assert!(err.contains("experiments[1].name"));
+    }
+
+    #[test]
+    fn validate_location_rewrite() {
+        let mut config = minimal_valid_config();
+        let set_rewrite = |config: &mut ServerConfig, header: &str, public_url: &str| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy {
+                    location_rewrite, ..
+                } = route.as_mut()
+            {
+                *location_rewrite = Some(LocationRewriteConfig {
+                    headers: vec![header.to_string()],
+                    public_url: Some(public_url.to_string()),
+                    internal_origins: vec!["http://app.internal".to_string()],
+                });
+            }
+        };
+
+        set_rewrite(&mut config, "Location", "https://www.example.com");
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_rewrite(&mut config, "bad header", "https://www.example.com");
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        set_rewrite(&mut config, "Location", "www.example.com");
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_static_route_options() {
+        let mut config = minimal_valid_config();
+        let root = std::env::temp_dir().to_string_lossy().into_owned();
+        let set_static = |config: &mut ServerConfig, page: &str, cache: &str| {
+            config.routes.insert(
+                "/assets".to_string(),
+                RouteConfigEntry::Single(Box::new(RouteConfig::Static {
+                    root: root.clone(),
+                    host: None,
+                    rate_limit: None,
+                    middlewares: vec![],
+                    error_pages: None,
+                    disable_http3: false,
+                    priority: None,
+                    slow_request_threshold_ms: None,
+                    metrics: None,
+                    index_file: None,
+                    spa_fallback: true,
+                    not_found_page: Some(page.to_string()),
+                    directory_listing: None,
+                    listing_format: None,
+                    cache_control: Some(cache.to_string()),
+                    precompressed: None,
+                    etag: None,
+                })),
+            );
+        };
+
+        set_static(&mut config, "errors/404.html", "public, max-age=60");
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_static(&mut config, "../404.html", "public, max-age=60");
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        set_static(&mut config, "404.html", "no-cache\n");
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_backend_limits() {
+        let mut config = minimal_valid_config();
+        let backend = GatewayService::collect_backends(&config)
+            .pop()
+            .expect("route target");
+        config.backend_limits.insert(
+            backend.clone(),
+            BackendLimits {
+                max_requests: 10,
+                max_pending: 5,
+                pending_timeout_ms: 500,
+            },
+        );
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.backend_limits.insert(
+            "http://unknown:9000".to_string(),
+            BackendLimits {
+                max_requests: 0,
+                max_pending: 0,
+                pending_timeout_ms: 500,
+            },
+        );
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("Not a target"));
+        assert!(err.to_string().contains("max_requests"));
+    }
+
+    #[test]
+    fn validate_response_timeouts() {
+        let mut config = minimal_valid_config();
+        config.upstream.response_timeout.header_secs = Some(10);
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy {
+                response_timeout, ..
+            } = route.as_mut()
+        {
+            *response_timeout = Some(ResponseTimeoutConfig {
+                header_secs: None,
+                total_secs: Some(60),
+            });
+        }
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.upstream.response_timeout.total_secs = Some(0);
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy {
+                response_timeout: Some(timeout),
+                ..
+            } = route.as_mut()
+        {
+            timeout.header_secs = Some(0);
+        }
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("upstream.response_timeout.total_secs"));
+        assert!(err.contains("route '/' response_timeout.header_secs"));
+    }
+
+    #[test]
+    fn validate_upstream_socket_options() {
+        let mut config = minimal_valid_config();
+        config.upstream.socket.dscp = Some(46);
+        config.upstream.socket.nodelay = Some(true);
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.upstream.socket.send_buffer_bytes = Some(0);
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy {
+                upstream_socket, ..
+            } = route.as_mut()
+        {
+            *upstream_socket = Some(UpstreamSocketConfig {
+                dscp: Some(64),
+                ..UpstreamSocketConfig::default()
+            });
+        }
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("upstream.socket.send_buffer_bytes"));
+        assert!(err.contains("route '/' upstream_socket.dscp"));
+    }
+
+    #[test]
+    fn validate_happy_eyeballs_delay() {
+        let mut config = minimal_valid_config();
+        config.upstream.happy_eyeballs_delay_ms = 5;
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("upstream.happy_eyeballs_delay_ms"));
+    }
+
+    #[test]
+    fn validate_backend_health_checks() {
+        let mut config = minimal_valid_config();
+        let backend = GatewayService::collect_backends(&config)
+            .pop()
+            .expect("route target");
+        config.backend_health_checks.insert(
+            backend.clone(),
+            BackendHealthCheckConfig {
+                host: Some("api.internal:8443".to_string()),
+                basic_auth: Some(BasicAuthCredentials {
+                    username: "probe".to_string(),
+                    password: "secret".to_string(),
+                }),
+                ..BackendHealthCheckConfig::default()
+            },
+        );
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.backend_health_checks.insert(
+            backend,
+            BackendHealthCheckConfig {
+                host: Some("bad host".to_string()),
+                tls: Some(UpstreamTlsConfig {
+                    ca_bundle: Some("missing-ca.pem".to_string()),
+                    ..UpstreamTlsConfig::default()
+                }),
+                basic_auth: Some(BasicAuthCredentials {
+                    username: "a:b".to_string(),
+                    password: String::new(),
+                }),
+            },
+        );
+        config.backend_health_checks.insert(
+            "http://unknown:9000".to_string(),
+            BackendHealthCheckConfig::default(),
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Not a target"));
+        assert!(err.contains(".host"));
+        assert!(err.contains(".tls.ca_bundle"));
+        assert!(err.contains(".basic_auth.username"));
+    }
+
+    #[test]
+    fn validate_metrics_export_settings() {
+        let mut config = minimal_valid_config();
+        config.metrics.export.failure_threshold = 0;
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("metrics.export.failure_threshold"));
+
+        config.metrics.export.failure_threshold = 5;
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+    }
+
+    #[test]
+    fn validate_tracing_settings() {
+        use crate::config::TracingOtlpConfig;
+
+        let mut config = minimal_valid_config();
+        config.tracing.level = "verbose".to_string();
+        config
+            .tracing
+            .modules
+            .insert("axon::adapters".to_string(), "debug".to_string());
+        config
+            .tracing
+            .modules
+            .insert("hyper=debug".to_string(), "info".to_string());
+        config.tracing.otlp = Some(TracingOtlpConfig {
+            endpoint: "collector:4317".to_string(),
+            headers: [("x-api-key".to_string(), "a\nb".to_string())].into(),
+            sample_ratio: 1.5,
+            timeout_secs: 10,
+        });
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("tracing.level"));
+        assert!(!err.contains("tracing.modules.axon::adapters"));
+        assert!(err.contains("tracing.modules.hyper=debug"));
+        assert!(err.contains("tracing.otlp.endpoint"));
+        assert!(err.contains("tracing.otlp.sample_ratio"));
+        assert!(err.contains("tracing.otlp.headers.x-api-key"));
+
+        config.tracing.level = "warn".to_string();
+        config.tracing.modules.remove("hyper=debug");
+        config.tracing.otlp = Some(TracingOtlpConfig {
+            endpoint: "http://collector:4317".to_string(),
+            headers: [("x-api-key".to_string(), "secret".to_string())].into(),
+            sample_ratio: 0.1,
+            timeout_secs: 10,
+        });
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+        assert_eq!(
+            config.tracing.filter_directives(),
+            "warn,axon::adapters=debug"
+        );
+    }
+
+    #[test]
+    fn validate_metrics_exporters() {
+        use crate::config::{PushgatewayConfig, StatsdConfig};
+
+        let mut config = minimal_valid_config();
+        config.metrics.exporters = vec![MetricsExporter::Statsd, MetricsExporter::Pushgateway];
+        config.metrics.interval_secs = Some(0);
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("metrics.statsd"));
+        assert!(err.contains("metrics.pushgateway"));
+        assert!(err.contains("metrics.interval_secs"));
+        assert_eq!(err.contains("`statsd` feature"), !cfg!(feature = "statsd"));
+
+        config.metrics.interval_secs = Some(15);
+        config.metrics.statsd = Some(StatsdConfig {
+            address: "localhost".to_string(),
+            prefix: String::new(),
+            max_packet_bytes: 1432,
+        });
+        config.metrics.pushgateway = Some(PushgatewayConfig {
+            url: "pushgateway:9091".to_string(),
+            job: "axon".to_string(),
+            instance: Some("a/b".to_string()),
+        });
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("metrics.statsd.address"));
+        assert!(err.contains("metrics.pushgateway.url"));
+        assert!(err.contains("metrics.pushgateway.instance"));
+
+        config.metrics.statsd = Some(StatsdConfig {
+            address: "localhost:8125".to_string(),
+            prefix: "gw.".to_string(),
+            max_packet_bytes: 1432,
+        });
+        config.metrics.pushgateway = Some(PushgatewayConfig {
+            url: "http://pushgateway:9091".to_string(),
+            job: "axon".to_string(),
+            instance: None,
+        });
+        let result = ServerConfigValidator::validate(&config);
+        assert_eq!(
+            result.is_ok(),
+            cfg!(all(feature = "statsd", feature = "pushgateway"))
+        );
+    }
+
+    #[test]
+    fn validate_backend_removal_deadline() {
+        let mut config = minimal_valid_config();
+        config.backend_removal.mode = BackendRemovalMode::Deadline;
+        config.backend_removal.deadline_secs = 0;
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("backend_removal.deadline_secs"));
+
+        config.backend_removal.mode = BackendRemovalMode::Fail;
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+    }
+
+    #[test]
+    fn validate_shutdown_poll_interval() {
+        let mut config = minimal_valid_config();
+        config.shutdown.drain_poll_interval_ms = 0;
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("shutdown.drain_poll_interval_ms"));
+
+        config.shutdown.drain_poll_interval_ms = 250;
+        config.shutdown.timeout_secs = 0;
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+    }
+
+    #[test]
+    fn validate_audit_sink() {
+        let mut config = minimal_valid_config();
+        config.audit.enabled = true;
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("audit.sink"));
 
-                if url.host().is_none() {
-                    return Err(ValidationError::InvalidField {
-                        field: context.to_string(),
-                        message: "URL must have a valid host".to_string(),
-                    });
-                }
+        config.audit.sink = Some(AuditSinkConfig::Syslog {
+            address: "syslog.internal".to_string(),
+            facility: 13,
+        });
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("audit.sink.address"));
 
-                Ok(())
-            }
-            Err(e) => Err(ValidationError::InvalidField {
-                field: context.to_string(),
-                message: format!("Invalid URL format: {e}"),
-            }),
-        }
+        config.audit.sink = Some(AuditSinkConfig::Webhook {
+            url: "https://siem.example.com/ingest".to_string(),
+            headers: std::collections::HashMap::from([(
+                "X-Token".to_string(),
+                "secret".to_string(),
+            )]),
+            batch_size: 50,
+        });
+        assert!(ServerConfigValidator::validate(&config).is_ok());
     }
 
-    /// Validate WebSocket URL format
-    fn validate_websocket_url(url_str: &str, context: &str) -> ValidationResult<()> {
-        match url::Url::parse(url_str) {
-            Ok(url) => {
-                if url.scheme() != "ws" && url.scheme() != "wss" {
-                    return Err(ValidationError::InvalidField {
-                        field: context.to_string(),
-                        message: format!(
-                            "WebSocket URL scheme must be 'ws' or 'wss', got '{}'",
-                            url.scheme()
-                        ),
-                    });
-                }
+    #[test]
+    fn validate_events() {
+        let mut config = minimal_valid_config();
+        config.events.enabled = true;
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("events.sink"));
 
-                if url.host().is_none() {
-                    return Err(ValidationError::InvalidField {
-                        field: context.to_string(),
-                        message: "WebSocket URL must have a valid host".to_string(),
-                    });
-                }
+        config.events.sample_rate = 1.5;
+        config.events.sink = Some(EventSinkConfig::Nats {
+            url: "http://nats.internal:4222".to_string(),
+            subject: "gateway.requests".to_string(),
+        });
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("events.sample_rate"));
+        assert!(err.to_string().contains("events.sink.url"));
 
-                Ok(())
-            }
-            Err(e) => Err(ValidationError::InvalidField {
-                field: context.to_string(),
-                message: format!("Invalid WebSocket URL format: {e}"),
-            }),
-        }
+        config.events.sample_rate = 0.1;
+        config.events.sink = Some(EventSinkConfig::Kafka {
+            brokers: vec!["kafka-1:9092".to_string()],
+            topic: "gateway-requests".to_string(),
+            properties: std::collections::HashMap::new(),
+        });
+        assert_eq!(
+            ServerConfigValidator::validate(&config).is_ok(),
+            cfg!(feature = "kafka")
+        );
     }
 
-    /// Validate load balance strategy
-    fn validate_load_balance_strategy(
-        _path: &str,
-        _strategy: &LoadBalanceStrategy,
-    ) -> ValidationResult<()> {
-        // All available strategies are valid
-        Ok(())
+    #[test]
+    fn validate_notifications() {
+        let mut config = minimal_valid_config();
+        config.notifications.timeout_ms = 0;
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.notifications.webhooks = vec![NotificationWebhookConfig {
+            url: "ops.example.com/hooks".to_string(),
+            events: vec![NotificationEvent::BackendUnhealthy],
+            secret: Some(String::new()),
+            headers: std::collections::HashMap::new(),
+        }];
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("notifications.timeout_ms"));
+        assert!(err.to_string().contains("notifications.webhooks[0].url"));
+        assert!(err.to_string().contains("notifications.webhooks[0].secret"));
+
+        config.notifications.timeout_ms = 2000;
+        let webhook = &mut config.notifications.webhooks[0];
+        webhook.url = "https://ops.example.com/hooks".to_string();
+        webhook.secret = Some("s3cret".to_string());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
     }
 
-    /// Validate rate limit configuration
-    fn validate_rate_limit(path: &str, config: &RateLimitConfig) -> ValidationResult<()> {
-        if config.requests == 0 {
-            return Err(ValidationError::InvalidField {
-                field: format!("route '{path}' rate_limit.requests"),
-                message: "Rate limit requests must be greater than 0".to_string(),
-            });
-        }
+    #[test]
+    fn validate_waf_rules() {
+        let mut config = minimal_valid_config();
+        config.waf = Some(WafConfig {
+            enabled: true,
+            custom_rules: vec![WafCustomRule {
+                name: "probe".to_string(),
+                pattern: "(wp-admin".to_string(),
+                target: WafRuleTarget::Uri,
+                block_mode: true,
+            }],
+            rules_file: Some("/etc/axon/waf-rules.toml".to_string()),
+            rules_poll_interval_secs: Some(0),
+            ..WafConfig::default()
+        });
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("waf.custom_rules[0].pattern"));
+        assert!(err.to_string().contains("waf.rules_poll_interval_secs"));
 
-        // Validate period format (e.g., "1s", "1m", "1h")
-        let period_regex = Regex::new(r"^\d+[smh]$").expect("Invalid regex pattern");
-        if !period_regex.is_match(&config.period) {
-            return Err(ValidationError::InvalidField {
-                field: format!("route '{path}' rate_limit.period"),
-                message: "Period must be in format like '1s', '1m', or '1h'".to_string(),
-            });
+        let waf = config.waf.as_mut().unwrap();
+        waf.custom_rules[0].pattern = "^/wp-admin".to_string();
+        waf.rules_poll_interval_secs = Some(30);
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        let waf = config.waf.as_mut().unwrap();
+        waf.auto_ban.enabled = true;
+        waf.auto_ban.waf_violations = 0;
+        waf.auto_ban.exempt = vec!["10.0.0.0/33".to_string()];
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("waf_violations or client_errors"));
+        assert!(err.to_string().contains("waf.auto_ban.exempt"));
+    }
+
+    #[test]
+    fn validate_histogram_buckets() {
+        let mut config = minimal_valid_config();
+        config.metrics.request_duration = HistogramConfig::Explicit {
+            buckets: vec![0.001, 0.005, 0.025, 0.1, 1.0, 10.0],
+        };
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.metrics.request_duration = HistogramConfig::Explicit {
+            buckets: vec![0.1, 0.01],
+        };
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        config.metrics.request_duration = HistogramConfig::Default;
+        config.metrics.backend_duration = HistogramConfig::Exponential {
+            max_size: 160,
+            max_scale: 42,
+        };
+        assert!(ServerConfigValidator::validate(&config).is_err());
+    }
+
+    #[test]
+    fn validate_wasm_filter_references() {
+        let module = tempfile::NamedTempFile::new().expect("temp module");
+        let mut config = minimal_valid_config();
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { middlewares, .. } = route.as_mut()
+        {
+            *middlewares = vec!["wasm:headers".to_string()];
         }
+        assert!(ServerConfigValidator::validate(&config).is_err());
 
-        Ok(())
+        let filter = WasmFilterConfig {
+            path: module.path().display().to_string(),
+            fuel: 1_000_000,
+            max_memory_bytes: 1024 * 1024,
+            include_body: false,
+            max_body_bytes: 1024,
+            fail_open: false,
+        };
+        config
+            .middleware
+            .wasm
+            .insert("headers".to_string(), filter.clone());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.middleware.wasm.insert(
+            "headers".to_string(),
+            WasmFilterConfig { fuel: 0, ..filter },
+        );
+        assert!(ServerConfigValidator::validate(&config).is_err());
     }
 
-    /// Validate path rewrite configuration
-    fn validate_path_rewrite(path: &str, rewrite: &str) -> ValidationResult<()> {
-        if rewrite.is_empty() {
-            return Err(ValidationError::InvalidField {
-                field: format!("route '{path}' path_rewrite"),
-                message: "Path rewrite cannot be empty".to_string(),
-            });
+    #[test]
+    fn validate_json_schema_checks() {
+        let mut schema = tempfile::NamedTempFile::new().expect("temp schema");
+        std::io::Write::write_all(&mut schema, br#"{"type": "object"}"#).expect("write schema");
+        let mut config = minimal_valid_config();
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { middlewares, .. } = route.as_mut()
+        {
+            *middlewares = vec!["json_schema:orders".to_string()];
         }
+        assert!(ServerConfigValidator::validate(&config).is_err());
 
-        if !rewrite.starts_with('/') {
-            return Err(ValidationError::InvalidField {
-                field: format!("route '{path}' path_rewrite"),
-                message: "Path rewrite must start with '/'".to_string(),
-            });
+        let check = JsonSchemaConfig {
+            request: Some(schema.path().display().to_string()),
+            response: None,
+            action: SchemaViolationAction::Block,
+            max_body_bytes: 1024,
+        };
+        config
+            .middleware
+            .json_schema
+            .insert("orders".to_string(), check.clone());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        for invalid in [
+            JsonSchemaConfig {
+                request: None,
+                ..check.clone()
+            },
+            JsonSchemaConfig {
+                response: Some("/nonexistent/schema.json".to_string()),
+                ..check.clone()
+            },
+            JsonSchemaConfig {
+                max_body_bytes: 0,
+                ..check.clone()
+            },
+        ] {
+            config
+                .middleware
+                .json_schema
+                .insert("orders".to_string(), invalid);
+            assert!(ServerConfigValidator::validate(&config).is_err());
         }
 
-        Ok(())
+        std::io::Write::write_all(&mut schema, b"trailing").expect("write schema");
+        config
+            .middleware
+            .json_schema
+            .insert("orders".to_string(), check);
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("middleware.json_schema.orders.request"),
+            "{err}"
+        );
     }
 
-    fn validate_health_check_config(
-        config: &HealthCheckConfig,
-    ) -> Result<(), Vec<ValidationError>> {
-        if !config.enabled {
-            return Ok(());
+    #[test]
+    fn validate_upload_policies() {
+        let mut config = minimal_valid_config();
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { middlewares, .. } = route.as_mut()
+        {
+            *middlewares = vec!["upload_policy:images".to_string()];
         }
+        assert!(ServerConfigValidator::validate(&config).is_err());
 
-        let mut errors = Vec::new();
+        let policy = UploadPolicyConfig {
+            allowed_content_types: vec!["image/*".to_string()],
+            max_file_bytes: Some(1024),
+            blocked_extensions: vec![".exe".to_string()],
+            ..UploadPolicyConfig::default()
+        };
+        config
+            .middleware
+            .upload_policy
+            .insert("images".to_string(), policy.clone());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
 
-        if config.interval_secs == 0 {
-            errors.push(ValidationError::InvalidField {
-                field: "health_check.interval_secs".to_string(),
-                message: "Must be greater than 0 when health checks are enabled".to_string(),
-            });
+        config.middleware.upload_policy.insert(
+            "images".to_string(),
+            UploadPolicyConfig {
+                allowed_content_types: vec!["*/png".to_string(), "image".to_string()],
+                blocked_extensions: vec!["tar.gz".to_string()],
+                max_files: Some(0),
+                ..policy
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'*/png'"), "{err}");
+        assert!(err.contains("'image'"), "{err}");
+        assert!(err.contains("'tar.gz'"), "{err}");
+        assert!(
+            err.contains("middleware.upload_policy.images.max_files"),
+            "{err}"
+        );
+
+        config.middleware.upload_policy.insert(
+            "images".to_string(),
+            UploadPolicyConfig {
+                scan: Some(UploadScanConfig {
+                    icap_url: "http://clamav:1344/avscan".to_string(),
+                    timeout_secs: 0,
+                    max_file_bytes: 1024,
+                    fail_open: false,
+                }),
+                ..UploadPolicyConfig::default()
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("images.scan.icap_url"), "{err}");
+        assert!(err.contains("images.scan.timeout_secs"), "{err}");
+    }
+
+    #[test]
+    fn validate_cookie_policies() {
+        use base64::{Engine as _, engine::general_purpose::STANDARD};
+
+        let mut config = minimal_valid_config();
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { middlewares, .. } = route.as_mut()
+        {
+            *middlewares = vec!["cookie_policy:app".to_string()];
         }
+        assert!(ServerConfigValidator::validate(&config).is_err());
 
-        if config.timeout_secs == 0 {
-            errors.push(ValidationError::InvalidField {
-                field: "health_check.timeout_secs".to_string(),
-                message: "Must be greater than 0 when health checks are enabled".to_string(),
-            });
+        let policy = CookiePolicyConfig {
+            strip_request_cookies: vec!["_ga".to_string()],
+            encrypt: vec!["session".to_string()],
+            secret: Some(STANDARD.encode([1u8; 32])),
+            ..CookiePolicyConfig::default()
+        };
+        config
+            .middleware
+            .cookie_policy
+            .insert("app".to_string(), policy.clone());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        let mut invalid = CookiePolicyConfig {
+            sign: vec!["session".to_string(), "bad name".to_string()],
+            secret: Some(STANDARD.encode([1u8; 16])),
+            retired_secrets: vec![RetiredCookieSecret {
+                secret: "not base64!".to_string(),
+                accept_until: chrono::Utc::now(),
+            }],
+            ..policy
+        };
+        invalid.set_cookie.same_site = Some(CookieSameSite::None);
+        invalid.set_cookie.secure = Some(false);
+        config
+            .middleware
+            .cookie_policy
+            .insert("app".to_string(), invalid);
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'bad name'"), "{err}");
+        assert!(err.contains("'session' is already encrypted"), "{err}");
+        assert!(err.contains("middleware.cookie_policy.app.secret"), "{err}");
+        assert!(err.contains("retired_secrets[0].secret"), "{err}");
+        assert!(err.contains("must be Secure"), "{err}");
+
+        config.middleware.cookie_policy.insert(
+            "app".to_string(),
+            CookiePolicyConfig {
+                sign: vec!["cart".to_string()],
+                ..CookiePolicyConfig::default()
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("middleware.cookie_policy.app.secret"), "{err}");
+    }
+
+    #[test]
+    fn validate_basic_auth() {
+        let mut config = minimal_valid_config();
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { middlewares, .. } = route.as_mut()
+        {
+            *middlewares = vec!["basic_auth:ops".to_string()];
         }
+        assert!(ServerConfigValidator::validate(&config).is_err());
 
-        if config.unhealthy_threshold == 0 {
-            errors.push(ValidationError::InvalidField {
-                field: "health_check.unhealthy_threshold".to_string(),
-                message: "Must be greater than 0 when health checks are enabled".to_string(),
-            });
+        let auth = BasicAuthConfig {
+            users: std::collections::HashMap::from([(
+                "alice".to_string(),
+                "$apr1$r31.....$HqJZimcKQFAMYayBlzkrA/".to_string(),
+            )]),
+            ..BasicAuthConfig::default()
+        };
+        config
+            .middleware
+            .basic_auth
+            .insert("ops".to_string(), auth.clone());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.middleware.basic_auth.insert(
+            "ops".to_string(),
+            BasicAuthConfig {
+                realm: "say \"hi\"".to_string(),
+                users: std::collections::HashMap::from([(
+                    "bob".to_string(),
+                    "plaintext".to_string(),
+                )]),
+                htpasswd_file: Some("/nonexistent/htpasswd".to_string()),
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("middleware.basic_auth.ops.realm"), "{err}");
+        assert!(err.contains("middleware.basic_auth.ops.users.bob"), "{err}");
+        assert!(err.contains("Cannot read htpasswd file"), "{err}");
+
+        config.middleware.basic_auth.insert(
+            "ops".to_string(),
+            BasicAuthConfig {
+                users: std::collections::HashMap::new(),
+                ..auth
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Set users and/or htpasswd_file"), "{err}");
+    }
+
+    #[test]
+    fn validate_ldap_auth() {
+        let mut config = minimal_valid_config();
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { middlewares, .. } = route.as_mut()
+        {
+            *middlewares = vec!["ldap_auth:corp".to_string()];
         }
+        assert!(ServerConfigValidator::validate(&config).is_err());
 
-        if config.healthy_threshold == 0 {
-            errors.push(ValidationError::InvalidField {
-                field: "health_check.healthy_threshold".to_string(),
-                message: "Must be greater than 0 when health checks are enabled".to_string(),
-            });
+        let auth = LdapAuthConfig {
+            url: "ldap://ldap.example.com".to_string(),
+            starttls: true,
+            search: Some(LdapSearchConfig {
+                bind_dn: "cn=gateway,dc=example,dc=com".to_string(),
+                bind_password: "secret".to_string(),
+                base_dn: "ou=people,dc=example,dc=com".to_string(),
+                filter: "(sAMAccountName={username})".to_string(),
+            }),
+            required_groups: vec!["ops".to_string()],
+            ..LdapAuthConfig::default()
+        };
+        config
+            .middleware
+            .ldap_auth
+            .insert("corp".to_string(), auth.clone());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.middleware.ldap_auth.insert(
+            "corp".to_string(),
+            LdapAuthConfig {
+                url: "ldaps://ldap.example.com".to_string(),
+                search: None,
+                user_dn: Some("{username}@corp.example.com".to_string()),
+                pool_size: 0,
+                ..auth.clone()
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("middleware.ldap_auth.corp.starttls"), "{err}");
+        assert!(
+            err.contains("middleware.ldap_auth.corp.required_groups"),
+            "{err}"
+        );
+        assert!(err.contains("middleware.ldap_auth.corp.pool_size"), "{err}");
+
+        config.middleware.ldap_auth.insert(
+            "corp".to_string(),
+            LdapAuthConfig {
+                url: "http://ldap.example.com".to_string(),
+                search: None,
+                ..auth
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("is not an LDAP URL"), "{err}");
+        assert!(err.contains("Set user_dn or search"), "{err}");
+    }
+
+    #[test]
+    fn validate_rbac_policies() {
+        let mut config = minimal_valid_config();
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { middlewares, .. } = route.as_mut()
+        {
+            *middlewares = vec!["rbac:admin".to_string()];
         }
+        assert!(ServerConfigValidator::validate(&config).is_err());
 
-        if config.path.trim().is_empty() {
-            errors.push(ValidationError::InvalidField {
-                field: "health_check.path".to_string(),
-                message: "Cannot be empty when health checks are enabled".to_string(),
-            });
-        } else if !config.path.starts_with('/') {
-            errors.push(ValidationError::InvalidField {
-                field: "health_check.path".to_string(),
-                message: "Must start with '/' when health checks are enabled".to_string(),
-            });
+        let policy = RbacPolicyConfig {
+            user_header: Some("X-Forwarded-User".to_string()),
+            roles: std::collections::HashMap::from([(
+                "admin".to_string(),
+                RbacRoleBinding {
+                    users: vec!["alice".to_string()],
+                    groups: vec![],
+                },
+            )]),
+            rules: vec![RbacRule {
+                roles: vec!["admin".to_string()],
+                paths: vec!["/admin/*".to_string()],
+                methods: vec!["get".to_string()],
+            }],
+            ..RbacPolicyConfig::default()
+        };
+        config
+            .middleware
+            .rbac
+            .insert("admin".to_string(), policy.clone());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.middleware.rbac.insert(
+            "admin".to_string(),
+            RbacPolicyConfig {
+                user_header: None,
+                groups_header: Some("bad header".to_string()),
+                rules: vec![RbacRule {
+                    roles: vec!["root".to_string()],
+                    paths: vec!["admin".to_string()],
+                    methods: vec!["G T".to_string()],
+                }],
+                ..policy
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Invalid header name 'bad header'"), "{err}");
+        assert!(err.contains("Requires user_header"), "{err}");
+        assert!(err.contains("Role 'root' is not defined"), "{err}");
+        assert!(err.contains("Invalid method 'G T'"), "{err}");
+        assert!(err.contains("must start with '/'"), "{err}");
+    }
+
+    #[test]
+    fn validate_opa() {
+        let mut config = minimal_valid_config();
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { middlewares, .. } = route.as_mut()
+        {
+            *middlewares = vec!["opa:authz".to_string()];
         }
+        assert!(ServerConfigValidator::validate(&config).is_err());
+
+        config.middleware.opa.insert(
+            "authz".to_string(),
+            OpaConfig {
+                url: "http://127.0.0.1:8181/v1/data/httpapi/authz".to_string(),
+                include_headers: vec!["x-tenant".to_string()],
+                ..OpaConfig::default()
+            },
+        );
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.middleware.opa.insert(
+            "authz".to_string(),
+            OpaConfig {
+                url: "opa:8181".to_string(),
+                include_headers: vec!["bad header".to_string()],
+                timeout_ms: 0,
+                cache_ttl_secs: 10,
+                cache_max_entries: 0,
+                fail_open: false,
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("is not an HTTP URL"), "{err}");
+        assert!(err.contains("Invalid header name 'bad header'"), "{err}");
+        assert!(err.contains("middleware.opa.authz.timeout_ms"), "{err}");
+        assert!(
+            err.contains("middleware.opa.authz.cache_max_entries"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn validate_usage() {
+        let mut config = minimal_valid_config();
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { middlewares, .. } = route.as_mut()
+        {
+            *middlewares = vec!["usage".to_string()];
+        }
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("requires a [middleware.usage] section"),
+            "{err}"
+        );
+
+        config.middleware.usage = Some(UsageConfig {
+            key_header: Some("X-Api-Key".to_string()),
+            sink: Some(UsageSinkConfig::File {
+                path: "/var/log/axon/usage.jsonl".to_string(),
+            }),
+            ..UsageConfig::default()
+        });
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.middleware.usage = Some(UsageConfig {
+            key_header: Some("bad header".to_string()),
+            flush_interval_secs: 0,
+            sink: Some(UsageSinkConfig::Webhook {
+                url: "billing.internal".to_string(),
+                headers: std::collections::HashMap::new(),
+                batch_size: 0,
+            }),
+            ..UsageConfig::default()
+        });
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("middleware.usage.key_header"), "{err}");
+        assert!(
+            err.contains("middleware.usage.flush_interval_secs"),
+            "{err}"
+        );
+        assert!(err.contains("middleware.usage.sink.url"), "{err}");
+        assert!(err.contains("middleware.usage.sink.batch_size"), "{err}");
+    }
+
+    #[test]
+    fn validate_http3_transport_limits() {
+        let mut config = minimal_valid_config();
+        config.protocols.http3_config = Some(Http3Config::default());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.protocols.http3_config = Some(Http3Config {
+            max_data: 1_000,
+            max_stream_data: 10_000,
+            max_streams_bidi: 0,
+            max_packet_size: Some(576),
+            ..Http3Config::default()
+        });
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("max_streams_bidi"));
+        assert!(err.contains("Must not exceed max_data"));
+        assert!(err.contains("at least 1200 bytes"));
+    }
+
+    #[test]
+    fn validate_slow_request_threshold() {
+        let mut config = minimal_valid_config();
+        let set_threshold = |config: &mut ServerConfig, threshold| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy {
+                    slow_request_threshold_ms,
+                    ..
+                } = route.as_mut()
+            {
+                *slow_request_threshold_ms = threshold;
+            }
+        };
+
+        set_threshold(&mut config, Some(500));
+        assert!(ServerConfigValidator::validate(&config).is_ok());
 
-        if errors.is_empty() {
-            Ok(())
-        } else {
-            Err(errors)
-        }
+        set_threshold(&mut config, Some(0));
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("slow_request_threshold_ms"));
     }
 
-    /// Validate TLS configuration
-    fn validate_tls_config(config: &TlsConfig) -> ValidationResult<()> {
-        match (&config.cert_path, &config.key_path) {
-            (Some(cert), Some(key)) => {
-                // Manual certificate configuration
-                if !std::path::Path::new(cert).exists() {
-                    return Err(ValidationError::InvalidTls {
-                        message: format!("Certificate file does not exist: {cert}"),
-                    });
-                }
+    #[test]
+    fn validate_route_metrics_templates() {
+        let mut config = minimal_valid_config();
+        let set_metrics = |config: &mut ServerConfig, value| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { metrics, .. } = route.as_mut()
+            {
+                *metrics = Some(value);
+            }
+        };
 
-                if !std::path::Path::new(key).exists() {
-                    return Err(ValidationError::InvalidTls {
-                        message: format!("Private key file does not exist: {key}"),
-                    });
-                }
+        set_metrics(
+            &mut config,
+            RouteMetricsConfig {
+                path_templates: vec!["/users/{id}".to_string()],
+                raw_path: false,
+            },
+        );
+        assert!(ServerConfigValidator::validate(&config).is_ok());
 
-                Ok(())
+        set_metrics(
+            &mut config,
+            RouteMetricsConfig {
+                path_templates: vec!["/users/{id".to_string()],
+                raw_path: true,
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("cannot be combined"));
+        assert!(err.contains("whole '{name}' placeholder"));
+    }
+
+    #[test]
+    fn validate_route_tap() {
+        let mut config = minimal_valid_config();
+        let set_tap = |config: &mut ServerConfig, value| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { tap, .. } = route.as_mut()
+            {
+                *tap = Some(value);
             }
-            _ => {
-                if let Some(acme) = &config.acme {
-                    if acme.domains.is_empty() {
-                        return Err(ValidationError::InvalidTls {
-                            message: "ACME configuration must include at least one domain"
-                                .to_string(),
-                        });
-                    }
+        };
 
-                    if acme.email.trim().is_empty() {
-                        return Err(ValidationError::InvalidTls {
-                            message: "ACME configuration must include a contact email".to_string(),
-                        });
-                    }
+        set_tap(&mut config, TapConfig::default());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
 
-                    Ok(())
-                } else {
-                    Err(ValidationError::InvalidTls {
-                        message: "TLS configuration must specify either certificate and private key paths, or ACME configuration".to_string(),
-                    })
-                }
-            }
-        }
+        set_tap(
+            &mut config,
+            TapConfig {
+                capacity: 0,
+                max_body_bytes: 64 * 1024 * 1024,
+                ..TapConfig::default()
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("tap.capacity"));
+        assert!(err.contains("tap.max_body_bytes"));
     }
 
-    /// Extract host from a RouteConfig
-    fn get_route_host(route: &RouteConfig) -> Option<&String> {
-        match route {
-            RouteConfig::Static { host, .. } => host.as_ref(),
-            RouteConfig::Redirect { host, .. } => host.as_ref(),
-            RouteConfig::Proxy { host, .. } => host.as_ref(),
-            RouteConfig::LoadBalance { host, .. } => host.as_ref(),
-            RouteConfig::Websocket { host, .. } => host.as_ref(),
-        }
-    }
+    #[test]
+    fn validate_route_retry() {
+        let mut config = minimal_valid_config();
+        let set_retry = |config: &mut ServerConfig, value| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { retry, .. } = route.as_mut()
+            {
+                *retry = Some(value);
+            }
+        };
 
-    /// Check for conflicting route paths
-    /// Routes with the same path are allowed if they have different hosts.
-    /// Conflicts occur when:
-    /// 1. Same path with same host (or both without host)
-    /// 2. Path prefix conflicts between routes without hosts
-    fn check_route_conflicts(
-        routes: &std::collections::HashMap<String, RouteConfigEntry>,
-    ) -> Result<(), Vec<ValidationError>> {
-        let mut errors = Vec::new();
+        set_retry(&mut config, RetryConfig::default());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
 
-        // Collect all (path, host) pairs
-        let mut route_entries: Vec<(&String, Option<&String>)> = Vec::new();
-        for (path, entry) in routes {
-            for route in entry.iter() {
-                route_entries.push((path, Self::get_route_host(route)));
-            }
-        }
-
-        // Check for duplicate path+host combinations
-        for (i, (path1, host1)) in route_entries.iter().enumerate() {
-            for (path2, host2) in route_entries.iter().skip(i + 1) {
-                // Same path + same host = conflict
-                if path1 == path2 {
-                    let host1_lower = host1.map(|h| h.to_lowercase());
-                    let host2_lower = host2.map(|h| h.to_lowercase());
-                    if host1_lower == host2_lower {
-                        let host_desc = host1
-                            .map(|h| format!(" (host: {h})"))
-                            .unwrap_or_else(|| " (no host)".to_string());
-                        errors.push(ValidationError::RouteConflict {
-                            message: format!(
-                                "Duplicate route configuration for path '{path1}'{host_desc}"
-                            ),
-                        });
-                    }
-                }
-                // Check path prefix conflicts only for routes without hosts
-                else if host1.is_none() && host2.is_none() && Self::paths_conflict(path1, path2) {
-                    errors.push(ValidationError::RouteConflict {
-                        message: format!("Routes '{path1}' and '{path2}' have conflicting paths"),
-                    });
-                }
-            }
-        }
+        set_retry(
+            &mut config,
+            RetryConfig {
+                attempts: 50,
+                retry_on: vec![200, 503],
+                budget: RetryBudgetConfig {
+                    ratio: 1.5,
+                    window_secs: 0,
+                    ..RetryBudgetConfig::default()
+                },
+                ..RetryConfig::default()
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("retry.attempts"));
+        assert!(err.contains("200 is not an error status"));
+        assert!(!err.contains("503"));
+        assert!(err.contains("retry.budget.ratio"));
+        assert!(err.contains("retry.budget.window_secs"));
 
-        if errors.is_empty() {
-            Ok(())
-        } else {
-            Err(errors)
-        }
+        set_retry(
+            &mut config,
+            RetryConfig {
+                non_idempotent: NonIdempotentRetry::Never,
+                generate_idempotency_key: true,
+                idempotency_header: "Idempotency Key".to_string(),
+                ..RetryConfig::default()
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("retry.idempotency_header"));
+        assert!(err.contains("non_idempotent = \"never\""));
     }
 
-    /// Check if two paths conflict (one is a prefix of another)
-    fn paths_conflict(path1: &str, path2: &str) -> bool {
-        if path1 == path2 {
-            return true;
-        }
+    #[test]
+    fn validate_route_fallback() {
+        let mut config = minimal_valid_config();
+        let set_fallback = |config: &mut ServerConfig, targets: Vec<&str>, on_status| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { fallback, .. } = route.as_mut()
+            {
+                *fallback = Some(FallbackConfig {
+                    targets: targets.into_iter().map(String::from).collect(),
+                    on_status,
+                    max_body_bytes: 1024,
+                });
+            }
+        };
 
-        let p1_segments: Vec<&str> = path1.trim_matches('/').split('/').collect();
-        let p2_segments: Vec<&str> = path2.trim_matches('/').split('/').collect();
+        set_fallback(&mut config, vec!["http://backup:8080"], vec![500, 503]);
+        assert!(ServerConfigValidator::validate(&config).is_ok());
 
-        let min_len = p1_segments.len().min(p2_segments.len());
+        set_fallback(&mut config, vec![], vec![302]);
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("At least one fallback target"));
+        assert!(err.contains("302 is not an error status"));
 
-        // Check if one path is a prefix of another
-        p1_segments[..min_len] == p2_segments[..min_len]
+        set_fallback(&mut config, vec!["not a url"], vec![]);
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("fallback"));
     }
 
-    /// Check if status code is valid for redirects
-    fn is_valid_redirect_status_code(code: u16) -> bool {
-        matches!(code, 301 | 302 | 307 | 308)
-    }
+    #[test]
+    fn validate_route_status_map() {
+        let mut config = minimal_valid_config();
+        let set_mapping = |config: &mut ServerConfig, value: StatusMapping| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { status_map, .. } = route.as_mut()
+            {
+                *status_map = vec![value];
+            }
+        };
+        let mapping = StatusMapping {
+            from: vec![500, 502],
+            to: Some(503),
+            set_text: None,
+            set_json: Some(serde_json::json!({"error": "unavailable"})),
+        };
 
-    /// Validate host field format
-    fn validate_host(host: &str, path: &str) -> ValidationResult<()> {
-        if host.is_empty() {
-            return Err(ValidationError::InvalidField {
-                field: format!("route '{path}' host"),
-                message: "Host cannot be empty".to_string(),
-            });
-        }
+        set_mapping(&mut config, mapping.clone());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
 
-        // Check for invalid characters
-        if host.contains("://") {
-            return Err(ValidationError::InvalidField {
-                field: format!("route '{path}' host"),
-                message: "Host should not contain protocol (e.g., use 'example.com' not 'http://example.com')".to_string(),
-            });
-        }
+        set_mapping(
+            &mut config,
+            StatusMapping {
+                from: vec![],
+                to: Some(100),
+                ..mapping.clone()
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("status_map[0].from"));
+        assert!(err.contains("100 is not a final status code"));
 
-        // Simple hostname validation
-        let hostname_regex = Regex::new(r"^[a-zA-Z0-9]([a-zA-Z0-9\-]{0,61}[a-zA-Z0-9])?(\.[a-zA-Z0-9]([a-zA-Z0-9\-]{0,61}[a-zA-Z0-9])?)*$").expect("invalid hostname regex");
+        set_mapping(
+            &mut config,
+            StatusMapping {
+                to: Some(204),
+                ..mapping.clone()
+            },
+        );
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("cannot have a body"));
 
-        if !hostname_regex.is_match(host) {
-            return Err(ValidationError::InvalidField {
-                field: format!("route '{path}' host"),
-                message: format!("Invalid hostname format: '{host}'"),
-            });
-        }
+        set_mapping(
+            &mut config,
+            StatusMapping {
+                to: None,
+                set_json: None,
+                ..mapping
+            },
+        );
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("Set at least one of"));
+    }
 
-        Ok(())
+    #[test]
+    fn validate_route_tunnel() {
+        let mut config = minimal_valid_config();
+        let set_tunnel = |config: &mut ServerConfig, value: TunnelConfig| {
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { tunnel, .. } = route.as_mut()
+            {
+                *tunnel = Some(value);
+            }
+        };
+
+        set_tunnel(
+            &mut config,
+            TunnelConfig {
+                protocols: vec!["h2c".to_string(), "TLS/1.3".to_string()],
+                ..TunnelConfig::default()
+            },
+        );
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        set_tunnel(
+            &mut config,
+            TunnelConfig {
+                protocols: vec!["a, b".to_string()],
+                idle_timeout_secs: 0,
+                ..TunnelConfig::default()
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Invalid Upgrade protocol 'a, b'"));
+        assert!(err.contains("tunnel.idle_timeout_secs"));
+
+        set_tunnel(&mut config, TunnelConfig::default());
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("enable connect"));
     }
 
-    /// Format multiple validation errors into a single message
-    fn format_multiple_errors(errors: Vec<ValidationError>) -> String {
-        if errors.is_empty() {
-            return "No errors".to_string();
-        }
+    #[test]
+    fn validate_default_route() {
+        let mut config = minimal_valid_config();
+        let redirect = |host: Option<&str>| RouteConfig::Redirect {
+            target: "https://www.example.com/".to_string(),
+            host: host.map(String::from),
+            status_code: None,
+            rate_limit: None,
+            middlewares: vec![],
+            error_pages: None,
+            disable_http3: false,
+            priority: None,
+        };
 
-        if errors.len() == 1 {
-            return errors[0].to_string();
-        }
+        // Route '/' already catches everything the default route would get
+        config.default_route = Some(Box::new(redirect(Some("example.com"))));
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("default_route.host"));
+        assert!(err.contains("already matches every path"));
 
-        let mut message = format!("Found {} validation errors:\n", errors.len());
-        for (i, error) in errors.iter().enumerate() {
-            message.push_str(&format!("  {}. {}\n", i + 1, error));
-        }
-        message
+        let route = config.routes.remove("/").unwrap();
+        config.routes.insert("/api".to_string(), route);
+        config.default_route = Some(Box::new(redirect(None)));
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.routes.clear();
+        assert!(ServerConfigValidator::validate(&config).is_ok());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::models::{AcmeConfig, HealthCheckConfig};
+    #[test]
+    fn validate_endpoints() {
+        let mut config = minimal_valid_config();
+        config.endpoints.health.path = Some("/metrics".to_string());
+        config.endpoints.status.path = Some("/admin/status".to_string());
+        config.endpoints.status.admin = true;
+        config.endpoints.metrics.allow = vec!["10.0.0.0/33".to_string()];
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'/metrics' is already used by endpoints.health"));
+        assert!(err.contains("endpoints.status.path"));
+        assert!(err.contains("endpoints.status.admin"));
+        assert!(err.contains("endpoints.metrics.allow"));
 
-    fn minimal_valid_config() -> ServerConfig {
-        ServerConfig {
-            listen_addr: "127.0.0.1:8080".to_string(),
-            routes: [(
-                "/".to_string(),
-                RouteConfig::Proxy {
-                    target: "http://localhost:3000".to_string(),
-                    host: None,
-                    path_rewrite: None,
-                    rate_limit: None,
-                    request_headers: None,
-                    response_headers: None,
-                    request_body: None,
-                    response_body: None,
-                    middlewares: vec![],
-                }
-                .into(),
-            )]
-            .into_iter()
-            .collect(),
-            ..ServerConfig::default()
-        }
+        config.endpoints.metrics.enabled = false;
+        config.endpoints.metrics.allow.clear();
+        config.endpoints.status.path = None;
+        config.admin.enabled = true;
+        config.admin.token = Some("change-me".to_string());
+        assert!(ServerConfigValidator::validate(&config).is_ok());
     }
 
-    fn base_config_with_tls(tls: TlsConfig) -> ServerConfig {
+    #[test]
+    fn validate_route_conflicts() {
         let mut config = minimal_valid_config();
-        config.tls = Some(tls);
+        let route = config.routes["/"].clone();
+        for path in [
+            "/api",
+            "/api/v2",
+            "/users/{id}",
+            "/users/{id}/posts",
+            "/users/me",
+        ] {
+            config.routes.insert(path.to_string(), route.clone());
+        }
+        // Nested prefixes are resolved by the longest match
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
         config
-    }
+            .routes
+            .insert("/users/{name}".to_string(), route.clone());
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Routes '/users/{id}' and '/users/{name}' have conflicting paths"));
 
-    fn make_valid_enabled_health_check() -> HealthCheckConfig {
-        HealthCheckConfig {
-            enabled: true,
-            interval_secs: 10,
-            timeout_secs: 5,
-            path: "/health".to_string(),
-            unhealthy_threshold: 3,
-            healthy_threshold: 2,
+        // The same paths don't meet on different hosts
+        let RouteConfigEntry::Single(mut hosted) = route else {
+            unreachable!()
+        };
+        if let RouteConfig::Proxy { host, .. } = hosted.as_mut() {
+            *host = Some("api.example.com".to_string());
         }
+        config.routes.insert(
+            "/users/{name}".to_string(),
+            RouteConfigEntry::Single(hosted),
+        );
+        assert!(ServerConfigValidator::validate(&config).is_ok());
     }
 
     #[test]
-    fn validate_accepts_acme_tls_config() {
-        let config = base_config_with_tls(TlsConfig {
-            cert_path: None,
-            key_path: None,
-            acme: Some(AcmeConfig {
-                domains: vec!["example.com".to_string()],
-                email: "admin@example.com".to_string(),
-                cache_dir: ".axon/acme_cache".to_string(),
-                production: false,
-            }),
-        });
+    fn validate_route_priorities() {
+        let mut config = minimal_valid_config();
+        let redirect = |host: Option<&str>, priority: Option<i32>| {
+            RouteConfigEntry::Single(Box::new(RouteConfig::Redirect {
+                target: "https://www.example.com/".to_string(),
+                host: host.map(String::from),
+                status_code: None,
+                rate_limit: None,
+                middlewares: vec![],
+                error_pages: None,
+                disable_http3: false,
+                priority,
+            }))
+        };
+        config.routes.clear();
+        config
+            .routes
+            .insert("/api".to_string(), redirect(None, Some(10)));
+        config.routes.insert(
+            "/api/v2".to_string(),
+            redirect(Some("api.example.com"), Some(5)),
+        );
+        config
+            .routes
+            .insert("/users/{id}".to_string(), redirect(None, Some(5)));
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        // A wildcard host and a parameter segment still overlap
+        config.routes.insert(
+            "/users/admin".to_string(),
+            redirect(Some("*.example.com"), Some(5)),
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(
+            "Routes '/users/admin' (host: *.example.com) and '/users/{id}' can match the same requests and share priority 5"
+        ));
+        assert!(!err.contains("'/api/v2'"));
 
+        config.routes.insert(
+            "/users/admin".to_string(),
+            redirect(Some("*.example.com"), Some(6)),
+        );
         assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.default_route = Some(Box::new(RouteConfig::Redirect {
+            target: "https://www.example.com/".to_string(),
+            host: None,
+            status_code: None,
+            rate_limit: None,
+            middlewares: vec![],
+            error_pages: None,
+            disable_http3: false,
+            priority: Some(1),
+        }));
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("default_route.priority"));
     }
 
     #[test]
-    fn validate_rejects_empty_acme_domains() {
-        let config = base_config_with_tls(TlsConfig {
-            cert_path: None,
-            key_path: None,
-            acme: Some(AcmeConfig {
-                domains: vec![],
-                email: "admin@example.com".to_string(),
-                cache_dir: ".axon/acme_cache".to_string(),
-                production: false,
-            }),
-        });
+    fn validate_virtual_hosts() {
+        let mut config = minimal_valid_config();
+        let vhost = |hostnames: &[&str]| VirtualHostConfig {
+            hostnames: hostnames.iter().map(|h| h.to_string()).collect(),
+            ..VirtualHostConfig::default()
+        };
+        config.virtual_hosts.insert(
+            "a".to_string(),
+            vhost(&["a.example.com", "*.a.example.com"]),
+        );
+        assert!(ServerConfigValidator::validate(&config).is_ok());
 
-        assert!(ServerConfigValidator::validate(&config).is_err());
+        config.virtual_hosts.insert(
+            "b".to_string(),
+            vhost(&["*.A.example.com", "b..example.com"]),
+        );
+        config.virtual_hosts.insert(
+            "c".to_string(),
+            VirtualHostConfig {
+                tls: Some(VirtualHostTlsConfig {
+                    cert_path: "missing.pem".to_string(),
+                    key_path: "missing.key".to_string(),
+                }),
+                waf: Some(true),
+                ..vhost(&[])
+            },
+        );
+        let err = ServerConfigValidator::validate(&config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'*.A.example.com' is already served by 'a'"));
+        assert!(err.contains("Invalid hostname format: 'b..example.com'"));
+        assert!(err.contains("virtual_hosts.c.hostnames"));
+        assert!(err.contains("Requires a [tls] section with cert_path and key_path"));
+        assert!(err.contains("File does not exist: missing.pem"));
+        assert!(err.contains("virtual_hosts.c.waf"));
     }
 
     #[test]
-    fn validate_rejects_zero_health_check_interval_when_enabled() {
+    fn validate_security_header_policies() {
         let mut config = minimal_valid_config();
-        config.health_check = make_valid_enabled_health_check();
-        config.health_check.interval_secs = 0;
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { middlewares, .. } = route.as_mut()
+        {
+            *middlewares = vec!["security_headers:api".to_string()];
+        }
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(err.to_string().contains("'api' is not defined"));
+
+        config.middleware.security_header_policies.insert(
+            "api".to_string(),
+            SecurityHeadersConfig {
+                hsts: Some(HstsConfig {
+                    max_age_secs: 600,
+                    include_subdomains: true,
+                    preload: false,
+                }),
+                content_security_policy: Some("default-src 'none'".to_string()),
+                remove_headers: vec!["server".to_string()],
+                ..SecurityHeadersConfig::default()
+            },
+        );
+        assert!(ServerConfigValidator::validate(&config).is_ok());
+
+        config.middleware.security_headers.permissions_policy = Some("camera=()\n".to_string());
+        config.middleware.security_headers.remove_headers = vec!["bad header".to_string()];
+        let err = ServerConfigValidator::validate(&config).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("security_headers.permissions_policy")
+        );
+        assert!(err.to_string().contains("Invalid header name 'bad header'"));
+    }
+
+    #[test]
+    fn validate_rejects_upstream_client_cert_without_key() {
+        let cert = tempfile::NamedTempFile::new().expect("temp cert");
+        let mut config = minimal_valid_config();
+        if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+            && let RouteConfig::Proxy { upstream_tls, .. } = route.as_mut()
+        {
+            *upstream_tls = Some(UpstreamTlsConfig {
+                client_cert: Some(cert.path().display().to_string()),
+                ..UpstreamTlsConfig::default()
+            });
+        }
 
         assert!(ServerConfigValidator::validate(&config).is_err());
     }
@@ -708,4 +6032,45 @@ mod tests {
 
         assert!(ServerConfigValidator::validate(&config).is_err());
     }
+
+    #[test]
+    fn validate_multiple_rate_limits() {
+        let with_limits = |limits: serde_json::Value| {
+            let mut config = minimal_valid_config();
+            if let Some(RouteConfigEntry::Single(route)) = config.routes.get_mut("/")
+                && let RouteConfig::Proxy { rate_limit, .. } = route.as_mut()
+            {
+                *rate_limit = Some(serde_json::from_value(limits).unwrap());
+            }
+            ServerConfigValidator::validate(&config)
+        };
+
+        assert!(
+            with_limits(serde_json::json!([
+                { "name": "per_ip", "by": "ip", "requests": 100, "period": "1m" },
+                { "name": "per_key", "by": "composite", "key": ["header", "path"],
+                  "header_name": "x-api-key", "requests": 10000, "period": "1h" },
+            ]))
+            .is_ok()
+        );
+
+        let err = with_limits(serde_json::json!([
+            { "by": "ip", "requests": 100, "period": "1m" },
+            { "by": "ip", "requests": 10, "period": "1s" },
+        ]))
+        .unwrap_err();
+        assert!(err.to_string().contains("used twice"));
+
+        let err = with_limits(serde_json::json!(
+            { "by": "composite", "requests": 10, "period": "1s" }
+        ))
+        .unwrap_err();
+        assert!(err.to_string().contains("rate_limit.key"));
+
+        let err = with_limits(serde_json::json!(
+            { "by": "composite", "key": ["ip", "header"], "requests": 10, "period": "1s" }
+        ))
+        .unwrap_err();
+        assert!(err.to_string().contains("rate_limit.header_name"));
+    }
 }
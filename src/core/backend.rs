@@ -8,12 +8,20 @@
 use std::{
     fmt,
     str::FromStr,
-    sync::atomic::{AtomicU8, AtomicU32, AtomicUsize, Ordering},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU8, AtomicU32, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
 use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
-use crate::{config::HealthStatus, metrics::set_backend_health_status};
+use crate::{
+    config::{HealthStatus, models::BackendLimits},
+    metrics::{set_backend_health_status, set_backend_saturation},
+};
 
 // Constants for health status to replace magic numbers
 const HEALTH_STATUS_UNHEALTHY: u8 = 0;
@@ -107,6 +115,8 @@ pub struct BackendHealth {
     pub consecutive_failures: AtomicU32,
     /// Counter for active connections
     pub active_connections: AtomicUsize,
+    /// When the backend last went from unhealthy to healthy
+    recovered_at: Mutex<Option<Instant>>,
 }
 
 impl BackendHealth {
@@ -124,6 +134,7 @@ impl BackendHealth {
             consecutive_successes: AtomicU32::new(0),
             consecutive_failures: AtomicU32::new(0),
             active_connections: AtomicUsize::new(0),
+            recovered_at: Mutex::new(None),
         }
     }
 
@@ -142,8 +153,13 @@ impl BackendHealth {
 
     /// Mark healthy (resets failure counter).
     pub fn mark_healthy(&self) {
-        // Use Release ordering for updates to ensure visibility to other threads
-        self.status.store(HEALTH_STATUS_HEALTHY, Ordering::Release);
+        // Use AcqRel ordering so the previous status is observed and the update is visible
+        let previous = self.status.swap(HEALTH_STATUS_HEALTHY, Ordering::AcqRel);
+        if previous == HEALTH_STATUS_UNHEALTHY
+            && let Ok(mut recovered_at) = self.recovered_at.lock()
+        {
+            *recovered_at = Some(Instant::now());
+        }
         // Reset failure counter; do not change success counter here.
         self.consecutive_failures.store(0, Ordering::Release);
         set_backend_health_status(self.target_url.as_str(), true);
@@ -159,6 +175,36 @@ impl BackendHealth {
         set_backend_health_status(self.target_url.as_str(), false);
     }
 
+    /// Count a failure observed on live traffic.
+    ///
+    /// With `unhealthy_threshold` set, the backend is marked unhealthy once
+    /// consecutive failures reach it; active health checks mark it healthy
+    /// again. Returns true when this call changed the status.
+    pub fn record_passive_failure(&self, unhealthy_threshold: Option<u32>) -> bool {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::AcqRel) + 1;
+        self.consecutive_successes.store(0, Ordering::Release);
+        match unhealthy_threshold {
+            Some(threshold) if failures >= threshold && self.status() == HealthStatus::Healthy => {
+                self.mark_unhealthy();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Share of its normal traffic the backend should receive during a
+    /// slow-start `window`: ramps linearly from 0 to 1 after recovery, and is
+    /// 1 for backends that never recovered or finished ramping.
+    pub fn slow_start_factor(&self, window: Duration, now: Instant) -> f64 {
+        let recovered_at = self.recovered_at.lock().ok().and_then(|r| *r);
+        match recovered_at {
+            Some(at) if !window.is_zero() => {
+                (now.saturating_duration_since(at).as_secs_f64() / window.as_secs_f64()).min(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+
     /// Get active connections count
     pub fn active_connections(&self) -> usize {
         self.active_connections.load(Ordering::Relaxed)
@@ -185,6 +231,105 @@ impl BackendHealth {
     }
 }
 
+/// Enforces a backend's `max_requests` and `max_pending` caps.
+#[derive(Debug)]
+pub struct BackendLimiter {
+    backend: String,
+    slots: Arc<Semaphore>,
+    max_requests: usize,
+    max_pending: usize,
+    pending: AtomicUsize,
+    pending_timeout: Duration,
+}
+
+/// A request slot on a limited backend, released on drop.
+#[derive(Debug)]
+pub struct BackendPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    limiter: Arc<BackendLimiter>,
+}
+
+impl Drop for BackendPermit {
+    fn drop(&mut self) {
+        drop(self.permit.take());
+        self.limiter.publish();
+    }
+}
+
+/// Decrements the pending count when a queued wait ends or is cancelled.
+struct PendingGuard<'a>(&'a BackendLimiter);
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.pending.fetch_sub(1, Ordering::AcqRel);
+        self.0.publish();
+    }
+}
+
+impl BackendLimiter {
+    pub fn new(backend: &str, limits: &BackendLimits) -> Self {
+        Self {
+            backend: backend.to_string(),
+            slots: Arc::new(Semaphore::new(limits.max_requests)),
+            max_requests: limits.max_requests,
+            max_pending: limits.max_pending,
+            pending: AtomicUsize::new(0),
+            pending_timeout: Duration::from_millis(limits.pending_timeout_ms),
+        }
+    }
+
+    /// Take a free slot without waiting.
+    pub fn try_acquire(self: &Arc<Self>) -> Option<BackendPermit> {
+        let permit = self.slots.clone().try_acquire_owned().ok()?;
+        Some(self.permit(permit))
+    }
+
+    /// Wait for a slot as a pending request. Returns `None` when the pending
+    /// queue is full or no slot frees up within the pending timeout.
+    pub async fn acquire_pending(self: &Arc<Self>) -> Option<BackendPermit> {
+        self.pending
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |pending| {
+                (pending < self.max_pending).then_some(pending + 1)
+            })
+            .ok()?;
+        let guard = PendingGuard(self);
+        self.publish();
+
+        let permit = tokio::time::timeout(self.pending_timeout, self.slots.clone().acquire_owned())
+            .await
+            .ok()?
+            .ok()?;
+        drop(guard);
+        Some(self.permit(permit))
+    }
+
+    /// Requests currently holding a slot.
+    pub fn in_flight(&self) -> usize {
+        self.max_requests - self.slots.available_permits()
+    }
+
+    /// Requests waiting for a slot.
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::Acquire)
+    }
+
+    pub fn max_requests(&self) -> usize {
+        self.max_requests
+    }
+
+    fn permit(self: &Arc<Self>, permit: OwnedSemaphorePermit) -> BackendPermit {
+        self.publish();
+        BackendPermit {
+            permit: Some(permit),
+            limiter: self.clone(),
+        }
+    }
+
+    fn publish(&self) {
+        set_backend_saturation(&self.backend, self.in_flight(), self.pending());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -230,6 +375,31 @@ mod tests {
         assert_eq!(health.consecutive_failures(), 0);
     }
 
+    #[test]
+    fn test_slow_start_factor_ramps_after_recovery() {
+        let health = BackendHealth::new(BackendUrl::new("http://example.com").unwrap());
+        let window = Duration::from_secs(60);
+        let now = Instant::now();
+        assert_eq!(health.slow_start_factor(window, now), 1.0);
+
+        health.mark_unhealthy();
+        health.mark_healthy();
+        let recovered = health.recovered_at.lock().unwrap().unwrap();
+        assert_eq!(health.slow_start_factor(window, recovered), 0.0);
+        assert_eq!(
+            health.slow_start_factor(window, recovered + Duration::from_secs(15)),
+            0.25
+        );
+        assert_eq!(
+            health.slow_start_factor(window, recovered + Duration::from_secs(90)),
+            1.0
+        );
+
+        // Marking an already healthy backend healthy does not restart the ramp
+        health.mark_healthy();
+        assert_eq!(health.recovered_at.lock().unwrap().unwrap(), recovered);
+    }
+
     #[test]
     fn test_backend_health_mark_unhealthy() {
         let url = BackendUrl::new("http://example.com").unwrap();
@@ -259,4 +429,53 @@ mod tests {
         assert_eq!(health.consecutive_successes(), 0);
         assert_eq!(health.consecutive_failures(), 0);
     }
+
+    #[test]
+    fn test_backend_health_passive_failures() {
+        let url = BackendUrl::new("http://example.com").unwrap();
+        let health = BackendHealth::new(url);
+
+        // Without a threshold failures are only counted
+        assert!(!health.record_passive_failure(None));
+        assert_eq!(health.status(), HealthStatus::Healthy);
+
+        assert!(health.record_passive_failure(Some(2)));
+        assert_eq!(health.status(), HealthStatus::Unhealthy);
+        assert_eq!(health.consecutive_failures(), 2);
+        assert!(!health.record_passive_failure(Some(2)));
+    }
+
+    #[tokio::test]
+    async fn test_backend_limiter_caps_in_flight_and_pending() {
+        let limiter = Arc::new(BackendLimiter::new(
+            "http://limited:8080",
+            &BackendLimits {
+                max_requests: 1,
+                max_pending: 1,
+                pending_timeout_ms: 50,
+            },
+        ));
+
+        let first = limiter.try_acquire().expect("free slot");
+        assert_eq!(limiter.in_flight(), 1);
+        assert!(limiter.try_acquire().is_none());
+
+        // One request may queue; it times out while the slot stays taken
+        assert!(limiter.acquire_pending().await.is_none());
+        assert_eq!(limiter.pending(), 0);
+
+        let queued = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire_pending().await.is_some() })
+        };
+        tokio::task::yield_now().await;
+        assert_eq!(limiter.pending(), 1);
+        // The queue is full
+        assert!(limiter.acquire_pending().await.is_none());
+
+        drop(first);
+        assert!(queued.await.expect("join"));
+        assert_eq!(limiter.in_flight(), 0);
+        assert_eq!(limiter.pending(), 0);
+    }
 }
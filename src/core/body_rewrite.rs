@@ -0,0 +1,304 @@
+//! Streaming substitutions in response bodies.
+//!
+//! A [`BodyRewrite`] is compiled from a route's `response_body` actions when
+//! the gateway is built. Each response then gets a [`BodyRewriter`], which
+//! applies the `replace` rules in order and injects the `inject_html` snippet
+//! before `</body>`, chunk by chunk. A rule only holds back the trailing
+//! bytes a match could still need, so bodies are never buffered whole; regex
+//! matches are therefore limited to [`MAX_REGEX_MATCH`] bytes.
+use std::sync::Arc;
+
+use regex::bytes::Regex;
+
+use crate::config::models::BodyActions;
+
+/// Longest body span a regex rule is guaranteed to match
+pub const MAX_REGEX_MATCH: usize = 4096;
+
+/// Bytes held back while looking for `</body>`, which may contain spaces
+const BODY_CLOSE_WINDOW: usize = 64;
+
+#[derive(Debug)]
+enum Replacement {
+    /// Literal bytes
+    Literal(Vec<u8>),
+    /// Regex template with `$1` / `${name}` references
+    Template(Vec<u8>),
+    /// Bytes inserted before the match, which is kept
+    Before(Vec<u8>),
+}
+
+#[derive(Debug)]
+struct Rule {
+    pattern: Regex,
+    replacement: Replacement,
+    /// Trailing bytes held back between chunks
+    window: usize,
+    /// Stop after the first match
+    once: bool,
+    html_only: bool,
+}
+
+/// Compiled `replace` and `inject_html` actions of a route.
+#[derive(Debug, Clone)]
+pub struct BodyRewrite {
+    rules: Arc<[Rule]>,
+}
+
+impl BodyRewrite {
+    /// Compile the streaming actions; `None` when there are none.
+    pub fn compile(actions: &BodyActions) -> Result<Option<Self>, String> {
+        let mut rules = Vec::with_capacity(actions.replace.len() + 1);
+        for (i, replacement) in actions.replace.iter().enumerate() {
+            if replacement.find.is_empty() {
+                return Err(format!("replace[{i}].find must not be empty"));
+            }
+            let rule = if replacement.regex {
+                Rule {
+                    pattern: Regex::new(&replacement.find)
+                        .map_err(|e| format!("replace[{i}]: invalid regex: {e}"))?,
+                    replacement: Replacement::Template(replacement.replace_with.clone().into()),
+                    window: MAX_REGEX_MATCH,
+                    once: false,
+                    html_only: false,
+                }
+            } else {
+                Rule {
+                    pattern: Regex::new(&regex::escape(&replacement.find))
+                        .map_err(|e| format!("replace[{i}]: {e}"))?,
+                    replacement: Replacement::Literal(replacement.replace_with.clone().into()),
+                    window: replacement.find.len() - 1,
+                    once: false,
+                    html_only: false,
+                }
+            };
+            rules.push(rule);
+        }
+        if let Some(html) = &actions.inject_html {
+            rules.push(Rule {
+                pattern: Regex::new(r"(?i)</body\s*>").expect("valid regex"),
+                replacement: Replacement::Before(html.clone().into()),
+                window: BODY_CLOSE_WINDOW,
+                once: true,
+                html_only: true,
+            });
+        }
+        Ok((!rules.is_empty()).then(|| Self {
+            rules: rules.into(),
+        }))
+    }
+
+    /// A rewriter for one response body, or `None` when no rule applies to
+    /// its content type. Only text, JSON, JavaScript and XML bodies are
+    /// rewritten, and HTML is injected into `text/html` only.
+    pub fn rewriter(&self, content_type: &str) -> Option<BodyRewriter> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let text = mime.starts_with("text/")
+            || matches!(
+                mime.as_str(),
+                "application/json" | "application/javascript" | "application/xml"
+            )
+            || mime.ends_with("+json")
+            || mime.ends_with("+xml");
+        if !text {
+            return None;
+        }
+        let html = mime == "text/html" || mime == "application/xhtml+xml";
+        let stages: Vec<_> = self
+            .rules
+            .iter()
+            .map(|rule| Stage {
+                carry: Vec::new(),
+                done: rule.html_only && !html,
+            })
+            .collect();
+        stages
+            .iter()
+            .any(|stage| !stage.done)
+            .then(|| BodyRewriter {
+                rules: self.rules.clone(),
+                stages,
+            })
+    }
+}
+
+#[derive(Debug)]
+struct Stage {
+    carry: Vec<u8>,
+    done: bool,
+}
+
+/// Rewrites one response body as it streams.
+#[derive(Debug)]
+pub struct BodyRewriter {
+    rules: Arc<[Rule]>,
+    stages: Vec<Stage>,
+}
+
+impl BodyRewriter {
+    /// Rewrite the next chunk; bytes a match may still span are held back.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.run(chunk.to_vec(), false)
+    }
+
+    /// Flush the held-back bytes at the end of the body.
+    pub fn finish(&mut self) -> Vec<u8> {
+        self.run(Vec::new(), true)
+    }
+
+    fn run(&mut self, mut data: Vec<u8>, last: bool) -> Vec<u8> {
+        for (rule, stage) in self.rules.iter().zip(&mut self.stages) {
+            data = stage.apply(rule, data, last);
+        }
+        data
+    }
+}
+
+impl Stage {
+    fn apply(&mut self, rule: &Rule, input: Vec<u8>, last: bool) -> Vec<u8> {
+        if self.done && self.carry.is_empty() {
+            return input;
+        }
+        let mut buf = std::mem::take(&mut self.carry);
+        buf.extend_from_slice(&input);
+        if self.done {
+            return buf;
+        }
+
+        // Matches starting before `cut` are complete; later ones may not be
+        let cut = if last {
+            buf.len()
+        } else {
+            buf.len().saturating_sub(rule.window)
+        };
+        let mut out = Vec::with_capacity(buf.len());
+        let mut pos = 0;
+        for captures in rule.pattern.captures_iter(&buf) {
+            let found = captures.get(0).expect("group 0 always matches");
+            if found.start() >= cut {
+                break;
+            }
+            if found.is_empty() {
+                continue;
+            }
+            out.extend_from_slice(&buf[pos..found.start()]);
+            match &rule.replacement {
+                Replacement::Literal(bytes) => out.extend_from_slice(bytes),
+                Replacement::Template(template) => captures.expand(template, &mut out),
+                Replacement::Before(bytes) => {
+                    out.extend_from_slice(bytes);
+                    out.extend_from_slice(found.as_bytes());
+                }
+            }
+            pos = found.end();
+            if rule.once {
+                self.done = true;
+                break;
+            }
+        }
+        let keep = if self.done { buf.len() } else { cut.max(pos) };
+        out.extend_from_slice(&buf[pos..keep]);
+        self.carry = buf[keep..].to_vec();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::models::BodyReplacement;
+
+    fn rewrite(replace: &[(&str, &str, bool)], inject_html: Option<&str>) -> BodyRewrite {
+        let actions = BodyActions {
+            replace: replace
+                .iter()
+                .map(|&(find, replace_with, regex)| BodyReplacement {
+                    find: find.to_string(),
+                    replace_with: replace_with.to_string(),
+                    regex,
+                })
+                .collect(),
+            inject_html: inject_html.map(str::to_string),
+            ..BodyActions::default()
+        };
+        BodyRewrite::compile(&actions).unwrap().unwrap()
+    }
+
+    /// Feed `body` in chunks of `size` bytes
+    fn run(rewriter: &mut BodyRewriter, body: &str, size: usize) -> String {
+        let mut out = Vec::new();
+        for chunk in body.as_bytes().chunks(size) {
+            out.extend(rewriter.push(chunk));
+        }
+        out.extend(rewriter.finish());
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_replacements_across_chunks() {
+        let rewrite = rewrite(
+            &[
+                ("http://backend:8080", "https://api.example.com", false),
+                (r"user-(\d+)", "u/$1", true),
+            ],
+            None,
+        );
+        let body = r#"{"self":"http://backend:8080/users/1","id":"user-42","next":"http://backend:8080/users/2"}"#;
+        let expected = r#"{"self":"https://api.example.com/users/1","id":"u/42","next":"https://api.example.com/users/2"}"#;
+        for size in [1, 3, 7, 64, body.len()] {
+            let mut rewriter = rewrite.rewriter("application/json").unwrap();
+            assert_eq!(
+                run(&mut rewriter, body, size),
+                expected,
+                "chunk size {size}"
+            );
+        }
+        assert!(rewrite.rewriter("image/png").is_none());
+    }
+
+    #[test]
+    fn test_html_injection() {
+        let rewrite = rewrite(&[], Some("<script src=\"/rum.js\"></script>"));
+        let body = "<html><body><p>hi</p></BODY ></html>";
+        let mut rewriter = rewrite.rewriter("text/html; charset=utf-8").unwrap();
+        assert_eq!(
+            run(&mut rewriter, body, 5),
+            "<html><body><p>hi</p><script src=\"/rum.js\"></script></BODY ></html>"
+        );
+        // Injection only applies to HTML
+        assert!(rewrite.rewriter("application/json").is_none());
+
+        // Without `</body>` the body passes through unchanged
+        let mut rewriter = rewrite.rewriter("text/html").unwrap();
+        assert_eq!(run(&mut rewriter, "<p>partial", 4), "<p>partial");
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let actions = |find: &str, regex| BodyActions {
+            replace: vec![BodyReplacement {
+                find: find.to_string(),
+                replace_with: String::new(),
+                regex,
+            }],
+            ..BodyActions::default()
+        };
+        assert!(BodyRewrite::compile(&actions("(", true)).is_err());
+        assert!(BodyRewrite::compile(&actions("", false)).is_err());
+        assert!(
+            BodyRewrite::compile(&actions("(", false))
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            BodyRewrite::compile(&BodyActions::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! Canary traffic splitting with automatic rollback.
+//!
+//! A [`CanaryState`] decides per request whether the canary or the stable
+//! group serves it and accumulates outcome statistics for both groups over a
+//! tumbling window. When the canary's error rate or mean latency exceeds the
+//! stable group's by the configured thresholds, the canary is rolled back: its
+//! effective weight drops to zero and stays there until the canary's
+//! configuration changes. Reloads that leave it unchanged keep the state.
+use std::{
+    fmt,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use rand::RngExt;
+
+use crate::config::models::CanaryConfig;
+
+/// Backend group chosen for a single request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanaryGroup {
+    Stable,
+    Canary,
+}
+
+/// Why a canary was rolled back, with the observed values for both groups.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RollbackReason {
+    ErrorRate { canary: f64, stable: f64 },
+    Latency { canary_ms: f64, stable_ms: f64 },
+}
+
+impl RollbackReason {
+    /// Short label used for metrics and webhook payloads.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RollbackReason::ErrorRate { .. } => "error_rate",
+            RollbackReason::Latency { .. } => "latency",
+        }
+    }
+}
+
+impl fmt::Display for RollbackReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RollbackReason::ErrorRate { canary, stable } => write!(
+                f,
+                "canary error rate {:.2}% vs stable {:.2}%",
+                canary * 100.0,
+                stable * 100.0
+            ),
+            RollbackReason::Latency {
+                canary_ms,
+                stable_ms,
+            } => write!(
+                f,
+                "canary mean latency {canary_ms:.1}ms vs stable {stable_ms:.1}ms"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct GroupStats {
+    requests: u64,
+    errors: u64,
+    latency_total: Duration,
+}
+
+impl GroupStats {
+    fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.requests as f64
+        }
+    }
+
+    fn mean_latency_ms(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.latency_total.as_secs_f64() * 1000.0 / self.requests as f64
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Window {
+    started: Instant,
+    stable: GroupStats,
+    canary: GroupStats,
+}
+
+impl Window {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            stable: GroupStats::default(),
+            canary: GroupStats::default(),
+        }
+    }
+}
+
+/// Runtime state of one route's canary group.
+#[derive(Debug)]
+pub struct CanaryState {
+    config: CanaryConfig,
+    rolled_back: AtomicBool,
+    window: Mutex<Window>,
+}
+
+impl CanaryState {
+    pub fn new(config: CanaryConfig) -> Self {
+        Self {
+            config,
+            rolled_back: AtomicBool::new(false),
+            window: Mutex::new(Window::new()),
+        }
+    }
+
+    /// The configuration this state was built from.
+    pub fn config(&self) -> &CanaryConfig {
+        &self.config
+    }
+
+    /// Canary backend targets.
+    pub fn targets(&self) -> &[String] {
+        &self.config.targets
+    }
+
+    /// Webhook notified when a rollback happens, if configured.
+    pub fn webhook_url(&self) -> Option<&str> {
+        self.config
+            .rollback
+            .as_ref()
+            .and_then(|r| r.webhook_url.as_deref())
+    }
+
+    /// Whether the canary has been rolled back.
+    pub fn is_rolled_back(&self) -> bool {
+        self.rolled_back.load(Ordering::Relaxed)
+    }
+
+    /// Percentage of traffic currently sent to the canary group.
+    pub fn effective_weight(&self) -> u8 {
+        if self.is_rolled_back() {
+            0
+        } else {
+            self.config.weight.min(100)
+        }
+    }
+
+    /// Pick the group for a new request according to the effective weight.
+    pub fn choose_group(&self) -> CanaryGroup {
+        let weight = self.effective_weight();
+        if weight > 0 && rand::rng().random_range(0..100u8) < weight {
+            CanaryGroup::Canary
+        } else {
+            CanaryGroup::Stable
+        }
+    }
+
+    /// Record the outcome of a request served by `group`.
+    ///
+    /// Returns the reason when this sample trips the rollback; later calls
+    /// return `None` because the rollback is sticky.
+    pub fn record(
+        &self,
+        group: CanaryGroup,
+        is_error: bool,
+        latency: Duration,
+    ) -> Option<RollbackReason> {
+        let rollback = self.config.rollback.as_ref()?;
+        if self.is_rolled_back() {
+            return None;
+        }
+
+        let Ok(mut window) = self.window.lock() else {
+            return None;
+        };
+        if window.started.elapsed() >= Duration::from_secs(rollback.window_secs) {
+            *window = Window::new();
+        }
+
+        let stats = match group {
+            CanaryGroup::Stable => &mut window.stable,
+            CanaryGroup::Canary => &mut window.canary,
+        };
+        stats.requests += 1;
+        stats.latency_total += latency;
+        if is_error {
+            stats.errors += 1;
+        }
+
+        if window.canary.requests < rollback.min_requests
+            || window.stable.requests < rollback.min_requests
+        {
+            return None;
+        }
+
+        let reason = rollback
+            .max_error_rate_increase
+            .and_then(|max_increase| {
+                let canary = window.canary.error_rate();
+                let stable = window.stable.error_rate();
+                (canary - stable > max_increase)
+                    .then_some(RollbackReason::ErrorRate { canary, stable })
+            })
+            .or_else(|| {
+                rollback.max_latency_ratio.and_then(|max_ratio| {
+                    let canary_ms = window.canary.mean_latency_ms();
+                    let stable_ms = window.stable.mean_latency_ms();
+                    (stable_ms > 0.0 && canary_ms > stable_ms * max_ratio).then_some(
+                        RollbackReason::Latency {
+                            canary_ms,
+                            stable_ms,
+                        },
+                    )
+                })
+            })?;
+
+        if self.rolled_back.swap(true, Ordering::Relaxed) {
+            None
+        } else {
+            Some(reason)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::models::CanaryRollbackConfig;
+
+    fn canary_state(rollback: Option<CanaryRollbackConfig>) -> CanaryState {
+        CanaryState::new(CanaryConfig {
+            targets: vec!["http://canary:3000".to_string()],
+            weight: 100,
+            rollback,
+        })
+    }
+
+    fn rollback_config() -> CanaryRollbackConfig {
+        CanaryRollbackConfig {
+            window_secs: 60,
+            min_requests: 5,
+            max_error_rate_increase: Some(0.1),
+            max_latency_ratio: Some(2.0),
+            webhook_url: None,
+        }
+    }
+
+    #[test]
+    fn test_weight_selects_group() {
+        let state = canary_state(None);
+        assert_eq!(state.choose_group(), CanaryGroup::Canary);
+
+        let state = CanaryState::new(CanaryConfig {
+            weight: 0,
+            ..state.config.clone()
+        });
+        assert_eq!(state.choose_group(), CanaryGroup::Stable);
+    }
+
+    #[test]
+    fn test_error_rate_triggers_sticky_rollback() {
+        let state = canary_state(Some(rollback_config()));
+        let latency = Duration::from_millis(10);
+
+        for _ in 0..5 {
+            assert!(state.record(CanaryGroup::Stable, false, latency).is_none());
+        }
+        for _ in 0..4 {
+            assert!(state.record(CanaryGroup::Canary, true, latency).is_none());
+        }
+        let reason = state.record(CanaryGroup::Canary, true, latency);
+        assert!(matches!(reason, Some(RollbackReason::ErrorRate { .. })));
+
+        assert!(state.is_rolled_back());
+        assert_eq!(state.effective_weight(), 0);
+        assert_eq!(state.choose_group(), CanaryGroup::Stable);
+        assert!(state.record(CanaryGroup::Canary, true, latency).is_none());
+    }
+
+    #[test]
+    fn test_latency_triggers_rollback() {
+        let state = canary_state(Some(rollback_config()));
+
+        for _ in 0..5 {
+            state.record(CanaryGroup::Stable, false, Duration::from_millis(10));
+        }
+        let mut reason = None;
+        for _ in 0..5 {
+            reason = reason.or(state.record(CanaryGroup::Canary, false, Duration::from_millis(50)));
+        }
+        assert!(matches!(reason, Some(RollbackReason::Latency { .. })));
+    }
+
+    #[test]
+    fn test_healthy_canary_is_kept() {
+        let state = canary_state(Some(rollback_config()));
+        let latency = Duration::from_millis(10);
+
+        for _ in 0..20 {
+            assert!(state.record(CanaryGroup::Stable, false, latency).is_none());
+            assert!(state.record(CanaryGroup::Canary, false, latency).is_none());
+        }
+        assert!(!state.is_rolled_back());
+        assert_eq!(state.effective_weight(), 100);
+    }
+}
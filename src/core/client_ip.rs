@@ -0,0 +1,146 @@
+//! Client addresses of requests arriving through trusted proxies.
+//!
+//! See [`ClientIpConfig`] for which address is chosen.
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::ConnectInfo,
+    http::{HeaderMap, Request},
+};
+
+use crate::{config::ClientIpConfig, core::waf::ip_filter::IpNetwork};
+
+/// The address a request is attributed to, inserted as a request extension
+/// before bans, the WAF and rate limits are checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+/// Finds the client address of a request from its peer address and
+/// `X-Forwarded-For` header
+#[derive(Debug, Clone, Default)]
+pub struct ClientIpResolver {
+    trusted_proxies: Vec<IpNetwork>,
+}
+
+impl ClientIpResolver {
+    pub fn from_config(config: &ClientIpConfig) -> Result<Self, String> {
+        let trusted_proxies = config
+            .trusted_proxies
+            .iter()
+            .map(|entry| {
+                IpNetwork::parse(entry).map_err(|e| format!("Invalid trusted proxy '{entry}': {e}"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { trusted_proxies })
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies
+            .iter()
+            .any(|network| network.contains(ip))
+    }
+
+    /// The client address of a request that arrived from `peer`: walking
+    /// `X-Forwarded-For` from the right while the hop it came through is a
+    /// trusted proxy. A malformed entry ends the walk at the proxy that
+    /// added it.
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+        let forwarded: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+
+        let mut client = peer;
+        for entry in forwarded.iter().rev() {
+            let Some(ip) = parse_forwarded_entry(entry) else {
+                break;
+            };
+            client = ip;
+            if !self.is_trusted(ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// The [`ClientIp`] the handler resolved for `req`, or its peer address
+/// when it was not resolved, as in middleware mounted outside the handler
+pub fn request_client_ip<T>(req: &Request<T>) -> Option<IpAddr> {
+    let extensions = req.extensions();
+    extensions
+        .get::<ClientIp>()
+        .map(|ClientIp(ip)| *ip)
+        .or_else(|| {
+            extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+        })
+}
+
+/// An address as proxies write them, with or without a port
+fn parse_forwarded_entry(entry: &str) -> Option<IpAddr> {
+    entry
+        .parse::<IpAddr>()
+        .or_else(|_| entry.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_forwarded_for_only_believed_from_trusted_proxies() {
+        let resolver = ClientIpResolver::from_config(&ClientIpConfig {
+            trusted_proxies: vec!["10.0.0.0/8".to_string(), "192.0.2.1".to_string()],
+        })
+        .unwrap();
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let forwarded = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-forwarded-for", HeaderValue::from_str(value).unwrap());
+            headers
+        };
+
+        // The rightmost untrusted hop is the client; entries to its left
+        // could have been written by anyone
+        let headers = forwarded("1.1.1.1, 203.0.113.7:4711, 10.1.2.3");
+        assert_eq!(
+            resolver.resolve(ip("192.0.2.1"), &headers),
+            ip("203.0.113.7")
+        );
+        // Untrusted peers can't claim another address
+        assert_eq!(
+            resolver.resolve(ip("198.51.100.9"), &headers),
+            ip("198.51.100.9")
+        );
+
+        assert_eq!(
+            resolver.resolve(ip("10.0.0.1"), &forwarded("[2001:db8::1]:443")),
+            ip("2001:db8::1")
+        );
+        assert_eq!(
+            resolver.resolve(ip("10.0.0.1"), &forwarded("203.0.113.7, unknown")),
+            ip("10.0.0.1")
+        );
+        assert_eq!(
+            resolver.resolve(ip("10.0.0.1"), &HeaderMap::new()),
+            ip("10.0.0.1")
+        );
+
+        let err = ClientIpResolver::from_config(&ClientIpConfig {
+            trusted_proxies: vec!["10.0.0.0/33".to_string()],
+        })
+        .unwrap_err();
+        assert!(err.contains("'10.0.0.0/33'"));
+    }
+}
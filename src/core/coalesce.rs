@@ -0,0 +1,262 @@
+//! Request coalescing for identical GET and HEAD requests.
+//!
+//! The first request for a key becomes the [`Leader`] and is forwarded; later
+//! identical requests join its flight as [`Waiter`]s and receive a copy of
+//! its response. When the leader cannot share its response (the backend
+//! failed, or the body is too large to buffer), waiters are told so and send
+//! their own requests.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use http::{HeaderMap, Method, StatusCode, Uri};
+use sha2::{Digest, Sha256};
+use tokio::sync::watch;
+
+use crate::config::models::CoalesceConfig;
+
+/// A buffered response handed to every waiter of a flight.
+#[derive(Debug)]
+pub struct SharedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// What a flight ended with; `None` when waiters must send their own request
+type Outcome = Option<Arc<SharedResponse>>;
+
+#[derive(Debug)]
+struct Flight {
+    waiters: usize,
+    outcome: watch::Receiver<Option<Outcome>>,
+}
+
+/// In-flight requests of one route, by key.
+#[derive(Debug)]
+pub struct Coalescer {
+    max_waiters: usize,
+    flights: Mutex<HashMap<String, Flight>>,
+}
+
+/// How a request takes part in coalescing.
+#[derive(Debug)]
+pub enum Join<'a> {
+    /// Forward the request and [`Leader::finish`] with its response
+    Leader(Leader<'a>),
+    /// Wait for the leader's response
+    Waiter(Waiter),
+    /// The flight has `max_waiters` waiters; forward the request on its own
+    Full,
+}
+
+impl Coalescer {
+    pub fn new(config: &CoalesceConfig) -> Self {
+        Self {
+            max_waiters: config.max_waiters,
+            flights: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Lead a new flight for `key` or wait on the one in progress.
+    pub fn join(&self, key: &str) -> Join<'_> {
+        let mut flights = self.flights.lock().expect("coalescer lock poisoned");
+        if let Some(flight) = flights.get_mut(key) {
+            if flight.waiters >= self.max_waiters {
+                return Join::Full;
+            }
+            flight.waiters += 1;
+            return Join::Waiter(Waiter {
+                outcome: flight.outcome.clone(),
+            });
+        }
+        let (tx, rx) = watch::channel(None);
+        flights.insert(
+            key.to_string(),
+            Flight {
+                waiters: 0,
+                outcome: rx,
+            },
+        );
+        Join::Leader(Leader {
+            coalescer: self,
+            key: key.to_string(),
+            outcome: tx,
+        })
+    }
+
+    /// Number of flights in progress.
+    pub fn in_flight(&self) -> usize {
+        self.flights.lock().expect("coalescer lock poisoned").len()
+    }
+}
+
+/// The request a flight's waiters depend on. Dropping it without calling
+/// [`Leader::finish`] sends the waiters off on their own.
+#[derive(Debug)]
+pub struct Leader<'a> {
+    coalescer: &'a Coalescer,
+    key: String,
+    outcome: watch::Sender<Option<Outcome>>,
+}
+
+impl Leader<'_> {
+    /// Hand `response` to the waiters, or `None` to have them send their own
+    /// requests.
+    pub fn finish(self, response: Option<Arc<SharedResponse>>) {
+        self.outcome.send_replace(Some(response));
+    }
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        self.coalescer
+            .flights
+            .lock()
+            .expect("coalescer lock poisoned")
+            .remove(&self.key);
+    }
+}
+
+/// A request waiting for its flight's leader.
+#[derive(Debug)]
+pub struct Waiter {
+    outcome: watch::Receiver<Option<Outcome>>,
+}
+
+impl Waiter {
+    /// The leader's response, or `None` when it could not be shared.
+    pub async fn wait(mut self) -> Option<Arc<SharedResponse>> {
+        self.outcome
+            .wait_for(Option::is_some)
+            .await
+            .ok()
+            .and_then(|outcome| outcome.clone().flatten())
+    }
+}
+
+/// Hex SHA-256 key over the method, host, path and query, and the configured
+/// header values.
+pub fn coalesce_key(
+    method: &Method,
+    host: Option<&str>,
+    uri: &Uri,
+    headers: &HeaderMap,
+    config: &CoalesceConfig,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(host.unwrap_or_default().to_ascii_lowercase().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(
+        uri.path_and_query()
+            .map(|pq| pq.as_str())
+            .unwrap_or("/")
+            .as_bytes(),
+    );
+    for name in &config.key_headers {
+        hasher.update(b"\n");
+        for value in headers.get_all(name.as_str()) {
+            hasher.update(value.as_bytes());
+            hasher.update(b"\0");
+        }
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_waiters: usize) -> CoalesceConfig {
+        CoalesceConfig {
+            key_headers: vec!["Authorization".to_string()],
+            max_waiters,
+            bypass_header: None,
+            max_body_bytes: 1024,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_waiters_share_the_leader_response() {
+        let coalescer = Coalescer::new(&config(1));
+        let Join::Leader(leader) = coalescer.join("k") else {
+            panic!("first request leads");
+        };
+        let Join::Waiter(waiter) = coalescer.join("k") else {
+            panic!("second request waits");
+        };
+        assert!(matches!(coalescer.join("k"), Join::Full));
+        assert!(matches!(coalescer.join("other"), Join::Leader(_)));
+
+        let waiting = tokio::spawn(waiter.wait());
+        leader.finish(Some(Arc::new(SharedResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(b"hello"),
+        })));
+        let shared = waiting.await.unwrap().expect("shared response");
+        assert_eq!(shared.body, "hello");
+        assert_eq!(coalescer.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_leader_releases_waiters() {
+        let coalescer = Coalescer::new(&config(10));
+        let Join::Leader(leader) = coalescer.join("k") else {
+            panic!("first request leads");
+        };
+        let Join::Waiter(waiter) = coalescer.join("k") else {
+            panic!("second request waits");
+        };
+        drop(leader);
+        assert!(waiter.wait().await.is_none());
+        assert!(matches!(coalescer.join("k"), Join::Leader(_)));
+    }
+
+    #[test]
+    fn test_coalesce_key() {
+        let config = config(1);
+        let uri: Uri = "/items?page=2".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        let key = |headers: &HeaderMap, uri: &Uri| {
+            coalesce_key(&Method::GET, Some("Example.com"), uri, headers, &config)
+        };
+        let anonymous = key(&headers, &uri);
+        headers.insert("x-ignored", "1".parse().unwrap());
+        assert_eq!(key(&headers, &uri), anonymous);
+        headers.insert("authorization", "Bearer a".parse().unwrap());
+        assert_ne!(key(&headers, &uri), anonymous);
+        assert_ne!(
+            key(&HeaderMap::new(), &"/items".parse().unwrap()),
+            anonymous
+        );
+        assert_ne!(
+            coalesce_key(
+                &Method::HEAD,
+                Some("example.com"),
+                &uri,
+                &HeaderMap::new(),
+                &config
+            ),
+            anonymous
+        );
+        assert_eq!(
+            coalesce_key(
+                &Method::GET,
+                Some("example.com"),
+                &uri,
+                &HeaderMap::new(),
+                &config
+            ),
+            anonymous
+        );
+    }
+}
@@ -0,0 +1,600 @@
+//! Request conditions for route header and body actions.
+//!
+//! A [`RequestCondition`] combines declarative checks (`path_matches`,
+//! `method_is`, `has_header`) with an optional inline expression such as
+//! `req.header('x-tier') == 'gold' && req.path.ends_with('.pdf')`. Conditions
+//! are compiled once when the gateway is built from a configuration, so regexes
+//! and expressions are never parsed on the request path.
+//!
+//! # Expression language
+//! * Values: `'text'` / `"text"` strings, `true`, `false`, `null`
+//! * Request fields: `req.path`, `req.method`, `req.query`, `req.host`,
+//!   `req.header('name')`, `req.query_param('name')` (missing values are `null`)
+//! * String methods: `starts_with(s)`, `ends_with(s)`, `contains(s)`,
+//!   `matches('regex')`, `to_lowercase()`, `to_uppercase()`
+//! * Operators: `==`, `!=`, `!`, `&&`, `||` and parentheses
+//!
+//! Strings are truthy when non-empty and `null` is falsy, so a bare
+//! `req.header('x-debug')` tests for presence.
+use http::{HeaderMap, HeaderName, Method, Uri};
+use regex::Regex;
+
+use crate::{
+    config::models::{BodyActions, HeaderActions, RequestCondition},
+    core::body_rewrite::BodyRewrite,
+};
+
+/// The request a condition is evaluated against.
+#[derive(Debug, Clone, Copy)]
+pub struct ConditionContext<'a> {
+    pub method: &'a Method,
+    pub uri: &'a Uri,
+    pub headers: &'a HeaderMap,
+}
+
+impl ConditionContext<'_> {
+    fn header(&self, name: &HeaderName) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    fn host(&self) -> Option<&str> {
+        self.uri.host().or_else(|| {
+            self.header(&http::header::HOST)
+                .map(|host| host.rsplit_once(':').map_or(host, |(name, _)| name))
+        })
+    }
+
+    fn query_param(&self, name: &str) -> Option<String> {
+        self.uri.query()?.split('&').find_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (urlencoding::decode(key).ok()? == name)
+                .then(|| urlencoding::decode(value).ok().map(|v| v.into_owned()))
+                .flatten()
+        })
+    }
+}
+
+/// A compiled [`RequestCondition`]; all configured checks must match.
+#[derive(Debug, Clone)]
+pub struct Condition {
+    path: Option<Regex>,
+    method: Option<Method>,
+    header: Option<(HeaderName, Option<Regex>)>,
+    expr: Option<Expr>,
+}
+
+impl Condition {
+    pub fn compile(condition: &RequestCondition) -> Result<Self, String> {
+        let regex = |pattern: &str| Regex::new(pattern).map_err(|e| format!("invalid regex: {e}"));
+        Ok(Self {
+            path: condition.path_matches.as_deref().map(regex).transpose()?,
+            method: condition
+                .method_is
+                .as_deref()
+                .map(|m| Method::from_bytes(m.to_ascii_uppercase().as_bytes()))
+                .transpose()
+                .map_err(|e| format!("invalid method: {e}"))?,
+            header: condition
+                .has_header
+                .as_ref()
+                .map(|h| {
+                    let name = HeaderName::from_bytes(h.name.as_bytes())
+                        .map_err(|e| format!("invalid header name '{}': {e}", h.name))?;
+                    Ok::<_, String>((name, h.value_matches.as_deref().map(regex).transpose()?))
+                })
+                .transpose()?,
+            expr: condition.expr.as_deref().map(Expr::parse).transpose()?,
+        })
+    }
+
+    pub fn matches(&self, ctx: &ConditionContext<'_>) -> bool {
+        self.path
+            .as_ref()
+            .is_none_or(|re| re.is_match(ctx.uri.path()))
+            && self.method.as_ref().is_none_or(|m| m == ctx.method)
+            && self.header.as_ref().is_none_or(|(name, value)| {
+                ctx.header(name)
+                    .is_some_and(|v| value.as_ref().is_none_or(|re| re.is_match(v)))
+            })
+            && self.expr.as_ref().is_none_or(|expr| expr.evaluate(ctx))
+    }
+}
+
+/// Actions paired with their compiled condition.
+#[derive(Debug, Clone)]
+pub struct ConditionalActions<A> {
+    pub actions: A,
+    condition: Option<Condition>,
+}
+
+impl<A> ConditionalActions<A> {
+    fn compile(actions: A, condition: Option<&RequestCondition>) -> Result<Self, String> {
+        Ok(Self {
+            actions,
+            condition: condition.map(Condition::compile).transpose()?,
+        })
+    }
+
+    /// Whether the actions apply to the request.
+    pub fn applies(&self, ctx: &ConditionContext<'_>) -> bool {
+        self.condition.as_ref().is_none_or(|c| c.matches(ctx))
+    }
+}
+
+/// Header and body actions of a proxy route, compiled at configuration load.
+#[derive(Debug, Clone, Default)]
+pub struct RouteTransforms {
+    pub request_headers: Option<ConditionalActions<HeaderActions>>,
+    pub response_headers: Option<ConditionalActions<HeaderActions>>,
+    pub request_body: Option<ConditionalActions<BodyActions>>,
+    pub response_body: Option<ConditionalActions<BodyActions>>,
+    /// Streaming `replace` and `inject_html` actions of `response_body`
+    pub response_rewrite: Option<BodyRewrite>,
+}
+
+impl RouteTransforms {
+    /// Compile a route's actions; `None` when the route defines none.
+    pub fn compile(
+        request_headers: Option<&HeaderActions>,
+        response_headers: Option<&HeaderActions>,
+        request_body: Option<&BodyActions>,
+        response_body: Option<&BodyActions>,
+    ) -> Result<Option<Self>, String> {
+        if request_headers.is_none()
+            && response_headers.is_none()
+            && request_body.is_none()
+            && response_body.is_none()
+        {
+            return Ok(None);
+        }
+        let headers = |actions: Option<&HeaderActions>, slot: &str| {
+            actions
+                .map(|a| ConditionalActions::compile(a.clone(), a.condition.as_ref()))
+                .transpose()
+                .map_err(|e| format!("{slot}.condition: {e}"))
+        };
+        let body = |actions: Option<&BodyActions>, slot: &str| {
+            actions
+                .map(|a| ConditionalActions::compile(a.clone(), a.condition.as_ref()))
+                .transpose()
+                .map_err(|e| format!("{slot}.condition: {e}"))
+        };
+        if request_body.is_some_and(|a| !a.replace.is_empty() || a.inject_html.is_some()) {
+            return Err(
+                "request_body: replace and inject_html only apply to response_body".to_string(),
+            );
+        }
+        let response_rewrite = response_body
+            .map(BodyRewrite::compile)
+            .transpose()
+            .map_err(|e| format!("response_body: {e}"))?
+            .flatten();
+        Ok(Some(Self {
+            request_headers: headers(request_headers, "request_headers")?,
+            response_headers: headers(response_headers, "response_headers")?,
+            request_body: body(request_body, "request_body")?,
+            response_body: body(response_body, "response_body")?,
+            response_rewrite,
+        }))
+    }
+}
+
+/// A parsed condition expression.
+#[derive(Debug, Clone)]
+pub struct Expr(Node);
+
+#[derive(Debug, Clone)]
+enum Node {
+    Literal(Value),
+    Path,
+    Method,
+    Query,
+    Host,
+    Header(HeaderName),
+    QueryParam(String),
+    StartsWith(Box<Node>, Box<Node>),
+    EndsWith(Box<Node>, Box<Node>),
+    Contains(Box<Node>, Box<Node>),
+    Matches(Box<Node>, Regex),
+    Lowercase(Box<Node>),
+    Uppercase(Box<Node>),
+    Eq(Box<Node>, Box<Node>),
+    Ne(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Str(s) => !s.is_empty(),
+            Value::Bool(b) => *b,
+            Value::Null => false,
+        }
+    }
+}
+
+impl Expr {
+    /// Parse an expression, rejecting unknown fields, methods and syntax errors.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let node = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(Self(node)),
+            Some(token) => Err(format!("unexpected {token:?} in expression")),
+        }
+    }
+
+    pub fn evaluate(&self, ctx: &ConditionContext<'_>) -> bool {
+        eval(&self.0, ctx).truthy()
+    }
+}
+
+fn eval(node: &Node, ctx: &ConditionContext<'_>) -> Value {
+    let text = |node: &Node| match eval(node, ctx) {
+        Value::Str(s) => Some(s),
+        _ => None,
+    };
+    let string_test =
+        |target: &Node, arg: &Node, test: fn(&str, &str) -> bool| match (text(target), text(arg)) {
+            (Some(target), Some(arg)) => Value::Bool(test(&target, &arg)),
+            _ => Value::Null,
+        };
+    let optional = |value: Option<String>| value.map_or(Value::Null, Value::Str);
+
+    match node {
+        Node::Literal(value) => value.clone(),
+        Node::Path => Value::Str(ctx.uri.path().to_string()),
+        Node::Method => Value::Str(ctx.method.as_str().to_string()),
+        Node::Query => optional(ctx.uri.query().map(str::to_string)),
+        Node::Host => optional(ctx.host().map(str::to_string)),
+        Node::Header(name) => optional(ctx.header(name).map(str::to_string)),
+        Node::QueryParam(name) => optional(ctx.query_param(name)),
+        Node::StartsWith(target, arg) => string_test(target, arg, |t, a| t.starts_with(a)),
+        Node::EndsWith(target, arg) => string_test(target, arg, |t, a| t.ends_with(a)),
+        Node::Contains(target, arg) => string_test(target, arg, |t, a| t.contains(a)),
+        Node::Matches(target, re) => {
+            text(target).map_or(Value::Null, |t| Value::Bool(re.is_match(&t)))
+        }
+        Node::Lowercase(target) => optional(text(target).map(|t| t.to_lowercase())),
+        Node::Uppercase(target) => optional(text(target).map(|t| t.to_uppercase())),
+        Node::Eq(a, b) => Value::Bool(eval(a, ctx) == eval(b, ctx)),
+        Node::Ne(a, b) => Value::Bool(eval(a, ctx) != eval(b, ctx)),
+        Node::Not(a) => Value::Bool(!eval(a, ctx).truthy()),
+        Node::And(a, b) => Value::Bool(eval(a, ctx).truthy() && eval(b, ctx).truthy()),
+        Node::Or(a, b) => Value::Bool(eval(a, ctx).truthy() || eval(b, ctx).truthy()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    LParen,
+    RParen,
+    Dot,
+    Comma,
+    Eq,
+    Ne,
+    Not,
+    And,
+    Or,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '.' => Token::Dot,
+            ',' => Token::Comma,
+            '\'' | '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => value.push(escaped),
+                            None => return Err("unterminated string".to_string()),
+                        },
+                        Some((_, end)) if end == c => break,
+                        Some((_, ch)) => value.push(ch),
+                        None => return Err(format!("unterminated string at {i}")),
+                    }
+                }
+                Token::Str(value)
+            }
+            '=' | '!' | '&' | '|' => {
+                let doubled = match (c, chars.peek().map(|(_, n)| *n)) {
+                    ('=', Some('=')) => Some(Token::Eq),
+                    ('!', Some('=')) => Some(Token::Ne),
+                    ('&', Some('&')) => Some(Token::And),
+                    ('|', Some('|')) => Some(Token::Or),
+                    _ => None,
+                };
+                match doubled {
+                    Some(token) => {
+                        chars.next();
+                        token
+                    }
+                    None if c == '!' => Token::Not,
+                    None => return Err(format!("unexpected '{c}' at {i}")),
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some((_, n)) = chars.peek().copied()
+                    && (n.is_ascii_alphanumeric() || n == '_')
+                {
+                    ident.push(n);
+                    chars.next();
+                }
+                Token::Ident(ident)
+            }
+            _ => return Err(format!("unexpected '{c}' at {i}")),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        if self.eat(&token) {
+            Ok(())
+        } else {
+            Err(format!("expected {token:?}, found {:?}", self.peek()))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                Ok(name)
+            }
+            other => Err(format!("expected a name, found {other:?}")),
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut node = self.and()?;
+        while self.eat(&Token::Or) {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        while self.eat(&Token::And) {
+            node = Node::And(Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.eat(&Token::Not) {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        let left = self.postfix()?;
+        if self.eat(&Token::Eq) {
+            Ok(Node::Eq(Box::new(left), Box::new(self.postfix()?)))
+        } else if self.eat(&Token::Ne) {
+            Ok(Node::Ne(Box::new(left), Box::new(self.postfix()?)))
+        } else {
+            Ok(left)
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Node, String> {
+        let mut node = self.primary()?;
+        while self.eat(&Token::Dot) {
+            let method = self.ident()?;
+            self.expect(Token::LParen)?;
+            let target = Box::new(node);
+            node = match method.as_str() {
+                "to_lowercase" => Node::Lowercase(target),
+                "to_uppercase" => Node::Uppercase(target),
+                "matches" => {
+                    let pattern = self.string_arg()?;
+                    let re = Regex::new(&pattern).map_err(|e| format!("invalid regex: {e}"))?;
+                    Node::Matches(target, re)
+                }
+                "starts_with" | "ends_with" | "contains" => {
+                    let arg = Box::new(self.or()?);
+                    match method.as_str() {
+                        "starts_with" => Node::StartsWith(target, arg),
+                        "ends_with" => Node::EndsWith(target, arg),
+                        _ => Node::Contains(target, arg),
+                    }
+                }
+                other => return Err(format!("unknown method '{other}'")),
+            };
+            self.expect(Token::RParen)?;
+        }
+        Ok(node)
+    }
+
+    fn string_arg(&mut self) -> Result<String, String> {
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::Str(value)) => {
+                self.pos += 1;
+                Ok(value)
+            }
+            other => Err(format!("expected a string literal, found {other:?}")),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node, String> {
+        if self.eat(&Token::LParen) {
+            let node = self.or()?;
+            self.expect(Token::RParen)?;
+            return Ok(node);
+        }
+        if let Some(Token::Str(_)) = self.peek() {
+            return Ok(Node::Literal(Value::Str(self.string_arg()?)));
+        }
+        match self.ident()?.as_str() {
+            "true" => Ok(Node::Literal(Value::Bool(true))),
+            "false" => Ok(Node::Literal(Value::Bool(false))),
+            "null" => Ok(Node::Literal(Value::Null)),
+            "req" => {
+                self.expect(Token::Dot)?;
+                match self.ident()?.as_str() {
+                    "path" => Ok(Node::Path),
+                    "method" => Ok(Node::Method),
+                    "query" => Ok(Node::Query),
+                    "host" => Ok(Node::Host),
+                    "header" => {
+                        self.expect(Token::LParen)?;
+                        let name = self.string_arg()?;
+                        self.expect(Token::RParen)?;
+                        HeaderName::from_bytes(name.as_bytes())
+                            .map(Node::Header)
+                            .map_err(|e| format!("invalid header name '{name}': {e}"))
+                    }
+                    "query_param" => {
+                        self.expect(Token::LParen)?;
+                        let name = self.string_arg()?;
+                        self.expect(Token::RParen)?;
+                        Ok(Node::QueryParam(name))
+                    }
+                    other => Err(format!("unknown request field 'req.{other}'")),
+                }
+            }
+            other => Err(format!("unknown name '{other}'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: Method, uri: &str, headers: &[(&str, &str)]) -> (Method, Uri, HeaderMap) {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        (method, uri.parse().unwrap(), map)
+    }
+
+    fn eval_expr(source: &str, req: &(Method, Uri, HeaderMap)) -> bool {
+        let ctx = ConditionContext {
+            method: &req.0,
+            uri: &req.1,
+            headers: &req.2,
+        };
+        Expr::parse(source).unwrap().evaluate(&ctx)
+    }
+
+    #[test]
+    fn test_expression_evaluation() {
+        let gold_pdf = request(
+            Method::GET,
+            "/docs/report.PDF?lang=de&x=1",
+            &[("x-tier", "gold"), ("host", "files.example.com:8443")],
+        );
+        let source = "req.header('x-tier') == 'gold' && req.path.to_lowercase().ends_with('.pdf')";
+        assert!(eval_expr(source, &gold_pdf));
+        assert!(!eval_expr(
+            source,
+            &request(Method::GET, "/docs/report.pdf", &[("x-tier", "silver")])
+        ));
+
+        assert!(eval_expr(
+            "req.query_param('lang') == 'de' && req.host == \"files.example.com\"",
+            &gold_pdf
+        ));
+        assert!(eval_expr(
+            "!(req.method == 'POST') && req.path.matches('^/docs/[a-z]+')",
+            &gold_pdf
+        ));
+        // Missing values are null: falsy, and method calls on them stay null
+        assert!(eval_expr(
+            "!req.header('x-debug') && req.header('x-debug') == null",
+            &gold_pdf
+        ));
+        assert!(!eval_expr(
+            "req.header('x-debug').starts_with('1') || false",
+            &gold_pdf
+        ));
+    }
+
+    #[test]
+    fn test_expression_parse_errors() {
+        for source in [
+            "req.header('x') ==",
+            "req.body == 'x'",
+            "req.path.trim()",
+            "req.path.matches('(')",
+            "req.path == 'unterminated",
+            "req.path = 'x'",
+            "req.header('bad header')",
+        ] {
+            assert!(Expr::parse(source).is_err(), "{source} should not parse");
+        }
+    }
+
+    #[test]
+    fn test_condition_combines_declarative_fields_and_expr() {
+        let condition = Condition::compile(&RequestCondition {
+            path_matches: Some("^/api/".to_string()),
+            method_is: Some("post".to_string()),
+            has_header: None,
+            expr: Some("req.header('x-tier') != 'free'".to_string()),
+        })
+        .unwrap();
+        let matches = |req: &(Method, Uri, HeaderMap)| {
+            condition.matches(&ConditionContext {
+                method: &req.0,
+                uri: &req.1,
+                headers: &req.2,
+            })
+        };
+        assert!(matches(&request(
+            Method::POST,
+            "/api/x",
+            &[("x-tier", "gold")]
+        )));
+        assert!(!matches(&request(
+            Method::POST,
+            "/api/x",
+            &[("x-tier", "free")]
+        )));
+        assert!(!matches(&request(Method::GET, "/api/x", &[])));
+    }
+}
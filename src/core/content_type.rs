@@ -0,0 +1,205 @@
+//! Content-Type enforcement for static and proxied responses.
+//!
+//! Static files are typed from the request path's extension, using the
+//! configured `[content_types.extensions]` before the built-in table. Proxied
+//! responses are checked against `proxy_rules`, which strip or correct types
+//! that cannot be right for the requested resource (an HTML error page
+//! served for `/data.json`, say). With `X-Content-Type-Options: nosniff` the
+//! browser trusts the declared type, so both sides have to be accurate.
+use std::collections::HashMap;
+
+use http::{HeaderMap, HeaderValue, header};
+
+use crate::config::models::{ContentTypeAction, ContentTypeConfig, ContentTypeRule};
+
+/// Type used by `correct` rules when the extension is unknown.
+const FALLBACK_MEDIA_TYPE: &str = "application/octet-stream";
+
+/// Lowercased extension of the last path segment, if it has one.
+pub fn path_extension(path: &str) -> Option<String> {
+    let segment = path.rsplit('/').next()?;
+    let (stem, extension) = segment.rsplit_once('.')?;
+    (!stem.is_empty() && !extension.is_empty()).then(|| extension.to_ascii_lowercase())
+}
+
+/// Media type for `path`: a configured mapping, else the built-in table.
+pub fn media_type_for_path(path: &str, mappings: &HashMap<String, String>) -> Option<String> {
+    let extension = path_extension(path)?;
+    mappings
+        .iter()
+        .find(|(mapped, _)| {
+            mapped
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(&extension)
+        })
+        .map(|(_, media_type)| media_type.clone())
+        .or_else(|| {
+            mime_guess::from_ext(&extension)
+                .first()
+                .map(|mime| mime.essence_str().to_string())
+        })
+}
+
+/// Apply the first proxy rule matching the request `path` and the backend's
+/// declared type, returning the action taken.
+///
+/// Returns `None` when no rule matched or the header already had the
+/// corrected value.
+pub fn apply_proxy_rules(
+    config: &ContentTypeConfig,
+    path: &str,
+    headers: &mut HeaderMap,
+) -> Option<ContentTypeAction> {
+    let extension = path_extension(path);
+    let declared = headers.get(header::CONTENT_TYPE).map(essence);
+    let rule = config
+        .proxy_rules
+        .iter()
+        .find(|rule| rule_matches(rule, extension.as_deref(), declared.as_deref()))?;
+
+    match rule.action {
+        ContentTypeAction::Strip => {
+            headers.remove(header::CONTENT_TYPE)?;
+        }
+        ContentTypeAction::Correct => {
+            let media_type = rule
+                .content_type
+                .clone()
+                .or_else(|| media_type_for_path(path, &config.extensions))
+                .unwrap_or_else(|| FALLBACK_MEDIA_TYPE.to_string());
+            let value = HeaderValue::from_str(&media_type).ok()?;
+            if headers.get(header::CONTENT_TYPE) == Some(&value) {
+                return None;
+            }
+            headers.insert(header::CONTENT_TYPE, value);
+        }
+    }
+    Some(rule.action)
+}
+
+/// Lowercased `type/subtype` of a header value, without parameters.
+fn essence(value: &HeaderValue) -> String {
+    value
+        .to_str()
+        .unwrap_or_default()
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn rule_matches(rule: &ContentTypeRule, extension: Option<&str>, declared: Option<&str>) -> bool {
+    let extension_matches = rule.extensions.is_empty()
+        || extension.is_some_and(|extension| {
+            rule.extensions
+                .iter()
+                .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(extension))
+        });
+    let type_matches = match declared {
+        None => rule
+            .backend_types
+            .iter()
+            .any(|pattern| pattern.eq_ignore_ascii_case("none")),
+        Some(declared) => {
+            rule.backend_types.is_empty()
+                || rule.backend_types.iter().any(|pattern| {
+                    let pattern = pattern.to_ascii_lowercase();
+                    match pattern.strip_suffix("/*") {
+                        Some("*") => true,
+                        Some(kind) => declared
+                            .split_once('/')
+                            .is_some_and(|(declared_kind, _)| declared_kind == kind),
+                        None => pattern == declared,
+                    }
+                })
+        }
+    };
+    extension_matches && type_matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_type_prefers_configured_mapping() {
+        let mappings = HashMap::from([(".JS".to_string(), "text/javascript".to_string())]);
+
+        assert_eq!(
+            media_type_for_path("/app/main.js", &mappings).as_deref(),
+            Some("text/javascript")
+        );
+        assert_eq!(
+            media_type_for_path("/data.json", &mappings).as_deref(),
+            Some("application/json")
+        );
+        assert_eq!(media_type_for_path("/.hidden", &mappings), None);
+        assert_eq!(media_type_for_path("/dir.d/README", &mappings), None);
+    }
+
+    #[test]
+    fn test_proxy_rules_correct_and_strip() {
+        let config = ContentTypeConfig {
+            nosniff: true,
+            extensions: HashMap::new(),
+            proxy_rules: vec![
+                ContentTypeRule {
+                    extensions: vec!["json".to_string()],
+                    backend_types: vec!["text/*".to_string(), "none".to_string()],
+                    action: ContentTypeAction::Correct,
+                    content_type: None,
+                },
+                ContentTypeRule {
+                    extensions: vec![],
+                    backend_types: vec!["application/x-unknown".to_string()],
+                    action: ContentTypeAction::Strip,
+                    content_type: None,
+                },
+            ],
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+        assert_eq!(
+            apply_proxy_rules(&config, "/api/data.json", &mut headers),
+            Some(ContentTypeAction::Correct)
+        );
+        assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+
+        // Already correct, and other extensions are left alone
+        assert_eq!(
+            apply_proxy_rules(&config, "/api/data.json", &mut headers),
+            None
+        );
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        assert_eq!(
+            apply_proxy_rules(&config, "/index.html", &mut headers),
+            None
+        );
+
+        // A missing type only matches rules listing `none`
+        headers.clear();
+        assert_eq!(
+            apply_proxy_rules(&config, "/report.json", &mut headers),
+            Some(ContentTypeAction::Correct)
+        );
+        assert_eq!(
+            apply_proxy_rules(&config, "/report.bin", &mut headers),
+            None
+        );
+
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("Application/X-Unknown"),
+        );
+        assert_eq!(
+            apply_proxy_rules(&config, "/file", &mut headers),
+            Some(ContentTypeAction::Strip)
+        );
+        assert!(!headers.contains_key(header::CONTENT_TYPE));
+    }
+}
@@ -0,0 +1,141 @@
+//! `Cookie` and `Set-Cookie` header handling
+//!
+//! Parses request cookies into name/value pairs and rewrites the attributes
+//! of `Set-Cookie` values (RFC 6265) for cookie policies.
+
+use crate::config::SetCookieAttributes;
+
+/// Name/value pairs of a `Cookie` header value, in order.
+pub fn parse_cookie_header(value: &str) -> Vec<(&str, &str)> {
+    value
+        .split(';')
+        .filter_map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            let name = name.trim();
+            (!name.is_empty()).then(|| (name, value.trim()))
+        })
+        .collect()
+}
+
+/// Name and value of a `Set-Cookie` value.
+pub fn set_cookie_pair(set_cookie: &str) -> Option<(&str, &str)> {
+    let pair = set_cookie.split(';').next()?;
+    let (name, value) = pair.split_once('=')?;
+    let name = name.trim();
+    (!name.is_empty()).then(|| (name, value.trim()))
+}
+
+/// Rebuild a `Set-Cookie` value with a new cookie value, keeping its
+/// attributes.
+pub fn replace_set_cookie_value(set_cookie: &str, value: &str) -> String {
+    let (name, _) = set_cookie_pair(set_cookie).unwrap_or_default();
+    match set_cookie.split_once(';') {
+        Some((_, attributes)) => format!("{name}={value};{attributes}"),
+        None => format!("{name}={value}"),
+    }
+}
+
+/// Apply attribute overrides to a `Set-Cookie` value; cookies not selected by
+/// `attributes.cookies` are returned unchanged.
+pub fn rewrite_set_cookie(set_cookie: &str, attributes: &SetCookieAttributes) -> String {
+    let Some((name, _)) = set_cookie_pair(set_cookie) else {
+        return set_cookie.to_string();
+    };
+    if !attributes.cookies.is_empty() && !attributes.cookies.iter().any(|c| c == name) {
+        return set_cookie.to_string();
+    }
+
+    let mut segments = set_cookie.split(';').map(str::trim);
+    let pair = segments.next().unwrap_or_default();
+    let overridden = |attribute: &str| {
+        let key = attribute
+            .split('=')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match key.as_str() {
+            "secure" => attributes.secure.is_some(),
+            "httponly" => attributes.http_only.is_some(),
+            "samesite" => attributes.same_site.is_some(),
+            "domain" => attributes.domain.is_some(),
+            _ => false,
+        }
+    };
+    let mut out: Vec<String> = std::iter::once(pair.to_string())
+        .chain(
+            segments
+                .filter(|attribute| !attribute.is_empty() && !overridden(attribute))
+                .map(str::to_string),
+        )
+        .collect();
+
+    if let Some(domain) = attributes.domain.as_deref().filter(|d| !d.is_empty()) {
+        out.push(format!("Domain={domain}"));
+    }
+    if attributes.secure == Some(true) {
+        out.push("Secure".to_string());
+    }
+    if attributes.http_only == Some(true) {
+        out.push("HttpOnly".to_string());
+    }
+    if let Some(same_site) = attributes.same_site {
+        out.push(format!("SameSite={}", same_site.as_str()));
+    }
+    out.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CookieSameSite;
+
+    #[test]
+    fn test_parse_cookies() {
+        assert_eq!(
+            parse_cookie_header("a=1; b = 2 ;c=x=y; bogus; =z"),
+            vec![("a", "1"), ("b", "2"), ("c", "x=y")]
+        );
+        assert_eq!(
+            set_cookie_pair("sid=abc; Path=/; HttpOnly"),
+            Some(("sid", "abc"))
+        );
+        assert_eq!(
+            replace_set_cookie_value("sid=abc; Path=/", "xyz"),
+            "sid=xyz; Path=/"
+        );
+        assert_eq!(replace_set_cookie_value("sid=abc", "xyz"), "sid=xyz");
+    }
+
+    #[test]
+    fn test_rewrite_set_cookie() {
+        let attributes = SetCookieAttributes {
+            cookies: vec![],
+            secure: Some(true),
+            http_only: Some(false),
+            same_site: Some(CookieSameSite::Lax),
+            domain: Some("example.com".to_string()),
+        };
+        assert_eq!(
+            rewrite_set_cookie(
+                "sid=abc; Domain=backend.internal; Path=/; HttpOnly; samesite=none",
+                &attributes
+            ),
+            "sid=abc; Path=/; Domain=example.com; Secure; SameSite=Lax"
+        );
+
+        let host_only = SetCookieAttributes {
+            cookies: vec!["sid".to_string()],
+            domain: Some(String::new()),
+            ..SetCookieAttributes::default()
+        };
+        assert_eq!(
+            rewrite_set_cookie("sid=abc; Domain=backend.internal; Secure", &host_only),
+            "sid=abc; Secure"
+        );
+        assert_eq!(
+            rewrite_set_cookie("other=1; Domain=backend.internal", &host_only),
+            "other=1; Domain=backend.internal"
+        );
+    }
+}
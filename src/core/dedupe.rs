@@ -0,0 +1,165 @@
+//! Duplicate request suppression for webhook endpoints.
+//!
+//! A [`DedupeWindow`] remembers request keys for a fixed TTL. Keys are claimed
+//! before a request is forwarded and released again when the backend does not
+//! accept it, so a provider's redelivery after a failure still gets through.
+use std::time::{Duration, Instant};
+
+use http::{HeaderMap, Method};
+use scc::hash_map::Entry;
+use sha2::{Digest, Sha256};
+
+use crate::config::models::DedupeConfig;
+
+/// Keys seen on one route within the configured TTL.
+#[derive(Debug)]
+pub struct DedupeWindow {
+    ttl: Duration,
+    max_entries: usize,
+    seen: scc::HashMap<String, Instant>,
+}
+
+impl DedupeWindow {
+    pub fn new(config: &DedupeConfig) -> Self {
+        Self {
+            ttl: Duration::from_secs(config.ttl_secs),
+            max_entries: config.max_entries,
+            seen: scc::HashMap::new(),
+        }
+    }
+
+    /// Claim `key`; returns `false` when it was already claimed within the TTL.
+    ///
+    /// When the window is full after purging expired keys, the request is let
+    /// through without being remembered.
+    pub async fn claim(&self, key: &str, now: Instant) -> bool {
+        if self.seen.len() >= self.max_entries {
+            self.seen
+                .retain_async(|_, seen| now.duration_since(*seen) < self.ttl)
+                .await;
+            if self.seen.len() >= self.max_entries {
+                return true;
+            }
+        }
+
+        match self.seen.entry_async(key.to_string()).await {
+            Entry::Occupied(mut entry) => {
+                if now.duration_since(*entry.get()) < self.ttl {
+                    false
+                } else {
+                    *entry.get_mut() = now;
+                    true
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert_entry(now);
+                true
+            }
+        }
+    }
+
+    /// Forget `key` so the next delivery is forwarded again.
+    pub async fn release(&self, key: &str) {
+        self.seen.remove_async(key).await;
+    }
+}
+
+/// Whether requests with `method` take part in deduplication.
+pub fn is_deduplicated_method(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Hex SHA-256 key over the method, the configured header values and the body.
+///
+/// Returns `None` when the request carries none of the configured headers and
+/// no body hash, since every such request would share one key.
+pub fn dedupe_key(
+    method: &Method,
+    headers: &HeaderMap,
+    config: &DedupeConfig,
+    body: Option<&[u8]>,
+) -> Option<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str().as_bytes());
+
+    let mut identified = false;
+    for name in &config.headers {
+        hasher.update(b"\n");
+        if let Some(value) = headers.get(name.as_str()) {
+            identified = true;
+            hasher.update(name.to_ascii_lowercase().as_bytes());
+            hasher.update(b":");
+            hasher.update(value.as_bytes());
+        }
+    }
+    if let Some(body) = body {
+        identified = true;
+        hasher.update(b"\n\n");
+        hasher.update(Sha256::digest(body));
+    }
+
+    identified.then(|| {
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(headers: &[&str], include_body: bool) -> DedupeConfig {
+        DedupeConfig {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            include_body,
+            ttl_secs: 60,
+            status_code: 200,
+            response_body: None,
+            max_body_size: 1024,
+            max_entries: 2,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_claim_expires_and_releases() {
+        let window = DedupeWindow::new(&config(&[], true));
+        let start = Instant::now();
+
+        assert!(window.claim("a", start).await);
+        assert!(!window.claim("a", start + Duration::from_secs(59)).await);
+        assert!(window.claim("a", start + Duration::from_secs(61)).await);
+
+        window.release("a").await;
+        assert!(window.claim("a", start + Duration::from_secs(62)).await);
+
+        // Full window: keys are let through without being remembered
+        assert!(window.claim("b", start + Duration::from_secs(62)).await);
+        assert!(window.claim("c", start + Duration::from_secs(62)).await);
+        assert!(window.claim("c", start + Duration::from_secs(62)).await);
+    }
+
+    #[test]
+    fn test_dedupe_key_uses_headers_and_body() {
+        let config = config(&["X-Delivery-Id"], false);
+        let mut headers = HeaderMap::new();
+        assert_eq!(dedupe_key(&Method::POST, &headers, &config, None), None);
+
+        headers.insert("x-delivery-id", "42".parse().unwrap());
+        let key = dedupe_key(&Method::POST, &headers, &config, None).unwrap();
+        assert_eq!(
+            dedupe_key(&Method::POST, &headers, &config, None),
+            Some(key.clone())
+        );
+        assert_ne!(
+            dedupe_key(&Method::POST, &headers, &config, Some(b"{}")),
+            Some(key)
+        );
+        assert_ne!(
+            dedupe_key(&Method::POST, &HeaderMap::new(), &config, Some(b"a")),
+            dedupe_key(&Method::POST, &HeaderMap::new(), &config, Some(b"b"))
+        );
+    }
+}
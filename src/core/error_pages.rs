@@ -0,0 +1,433 @@
+//! Gateway-generated error responses and their rendering.
+//!
+//! Errors produced by the gateway itself (no route, WAF block, rate limit,
+//! backend failure, ...) are built with [`error_response`], which marks them
+//! with a [`GatewayError`] extension. Before the response leaves the gateway,
+//! [`render_error_page`] replaces the placeholder body with the configured
+//! template for the negotiated format. Backend responses are only rewritten
+//! when their status is listed in `intercept`, which turns them into gateway
+//! errors with [`intercept_backend_error`].
+use axum::{
+    body::Body,
+    http::{HeaderValue, Response, StatusCode, header},
+};
+
+use crate::config::models::{ErrorPageFormat, ErrorPagesConfig};
+
+/// Response extension marking a gateway-generated error.
+#[derive(Debug, Clone)]
+pub struct GatewayError {
+    pub message: String,
+}
+
+/// Build a gateway error response; the plain-text body is replaced when error
+/// pages are rendered.
+pub fn error_response(status: StatusCode, message: impl Into<String>) -> Response<Body> {
+    let message = message.into();
+    let mut response = Response::new(Body::from(message.clone()));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    response.extensions_mut().insert(GatewayError { message });
+    response
+}
+
+/// Backend headers kept on an intercepted error; they tell the client how
+/// to retry or authenticate rather than describe the backend's body.
+const INTERCEPT_KEPT_HEADERS: [header::HeaderName; 3] =
+    [header::RETRY_AFTER, header::WWW_AUTHENTICATE, header::ALLOW];
+
+/// Whether a backend response with `status` is replaced with an error page,
+/// by the route's `intercept` list or else the global one.
+pub fn intercepts(
+    route: Option<&ErrorPagesConfig>,
+    global: &ErrorPagesConfig,
+    status: StatusCode,
+) -> bool {
+    let Some(intercept) = route
+        .and_then(|r| r.intercept.as_ref())
+        .or(global.intercept.as_ref())
+    else {
+        return false;
+    };
+    let code = status.as_str();
+    let class = format!("{}xx", status.as_u16() / 100);
+    intercept.iter().any(|key| key == code || *key == class)
+}
+
+/// Replace a backend error with a gateway error of the same status, dropping
+/// its body and all headers but `Retry-After`, `WWW-Authenticate` and `Allow`.
+pub fn intercept_backend_error(response: Response<Body>) -> Response<Body> {
+    let status = response.status();
+    let mut intercepted = error_response(status, status.canonical_reason().unwrap_or("Error"));
+    for name in INTERCEPT_KEPT_HEADERS {
+        for value in response.headers().get_all(&name) {
+            intercepted.headers_mut().append(&name, value.clone());
+        }
+    }
+    intercepted
+}
+
+/// Values available to error page templates.
+#[derive(Debug, Clone)]
+pub struct ErrorContext<'a> {
+    pub status: StatusCode,
+    pub message: &'a str,
+    pub request_id: &'a str,
+    pub path: &'a str,
+    pub timestamp: &'a str,
+}
+
+const DEFAULT_JSON: &str = r#"{"error":{"status":{{status}},"reason":"{{reason}}","message":"{{message}}","request_id":"{{request_id}}","timestamp":"{{timestamp}}"}}"#;
+const DEFAULT_HTML: &str = "<!DOCTYPE html>\n<html>\n<head><title>{{status}} {{reason}}</title></head>\n<body>\n<h1>{{status}} {{reason}}</h1>\n<p>{{message}}</p>\n<hr>\n<small>Request ID: {{request_id}}</small>\n</body>\n</html>\n";
+const DEFAULT_TEXT: &str = "{{message}}";
+
+/// Render a marked error response with the route's (then the global) error pages.
+///
+/// Responses without a [`GatewayError`] marker are returned unchanged.
+pub fn render_error_page(
+    response: Response<Body>,
+    global: &ErrorPagesConfig,
+    route: Option<&ErrorPagesConfig>,
+    accept: Option<&str>,
+    request_id: &str,
+    path: &str,
+) -> Response<Body> {
+    let Some(error) = response.extensions().get::<GatewayError>().cloned() else {
+        return response;
+    };
+
+    let format = match route
+        .and_then(|r| r.format)
+        .or(global.format)
+        .unwrap_or_default()
+    {
+        ErrorPageFormat::Auto => negotiate_format(accept),
+        format => format,
+    };
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let ctx = ErrorContext {
+        status: response.status(),
+        message: &error.message,
+        request_id,
+        path,
+        timestamp: &timestamp,
+    };
+    let template = find_template(route, global, ctx.status, format);
+    let body = render_template(template, format, &ctx);
+
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(content_type(format)),
+    );
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Pick HTML, JSON or text from an `Accept` header; JSON wins ties and wildcards.
+pub fn negotiate_format(accept: Option<&str>) -> ErrorPageFormat {
+    let Some(accept) = accept else {
+        return ErrorPageFormat::Json;
+    };
+
+    let mut best = (ErrorPageFormat::Json, 0.0f32);
+    for range in accept.split(',') {
+        let mut params = range.split(';');
+        let media = params
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let quality = params
+            .filter_map(|p| p.trim().strip_prefix("q="))
+            .find_map(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        let format = match media.as_str() {
+            "application/json" | "application/problem+json" | "*/*" | "application/*" => {
+                ErrorPageFormat::Json
+            }
+            "text/html" | "application/xhtml+xml" => ErrorPageFormat::Html,
+            "text/plain" | "text/*" => ErrorPageFormat::Text,
+            _ => continue,
+        };
+        let preferred = quality > best.1
+            || (quality == best.1 && quality > 0.0 && format == ErrorPageFormat::Json);
+        if preferred {
+            best = (format, quality);
+        }
+    }
+    best.0
+}
+
+fn content_type(format: ErrorPageFormat) -> &'static str {
+    match format {
+        ErrorPageFormat::Html => "text/html; charset=utf-8",
+        ErrorPageFormat::Text => "text/plain; charset=utf-8",
+        ErrorPageFormat::Json | ErrorPageFormat::Auto => "application/json",
+    }
+}
+
+/// Template for `status`: exact code, then class (`5xx`), then `default`;
+/// route templates take precedence over global ones.
+fn find_template<'a>(
+    route: Option<&'a ErrorPagesConfig>,
+    global: &'a ErrorPagesConfig,
+    status: StatusCode,
+    format: ErrorPageFormat,
+) -> &'a str {
+    let code = status.as_str().to_string();
+    let class = format!("{}xx", status.as_u16() / 100);
+    let keys = [code.as_str(), class.as_str(), "default"];
+
+    route
+        .into_iter()
+        .chain(std::iter::once(global))
+        .flat_map(|config| keys.iter().filter_map(|key| config.templates.get(*key)))
+        .find_map(|template| match format {
+            ErrorPageFormat::Html => template.html.as_deref(),
+            ErrorPageFormat::Text => template.text.as_deref(),
+            ErrorPageFormat::Json | ErrorPageFormat::Auto => template.json.as_deref(),
+        })
+        .unwrap_or(match format {
+            ErrorPageFormat::Html => DEFAULT_HTML,
+            ErrorPageFormat::Text => DEFAULT_TEXT,
+            ErrorPageFormat::Json | ErrorPageFormat::Auto => DEFAULT_JSON,
+        })
+}
+
+/// Substitute `{{variable}}` placeholders, escaping values for the format.
+///
+/// Substitution is single-pass, so values containing placeholders are not
+/// expanded again; unknown placeholders are left as-is.
+fn render_template(template: &str, format: ErrorPageFormat, ctx: &ErrorContext<'_>) -> String {
+    let escape = |value: &str| match format {
+        ErrorPageFormat::Html => escape_html(value),
+        ErrorPageFormat::Json | ErrorPageFormat::Auto => escape_json(value),
+        ErrorPageFormat::Text => value.to_string(),
+    };
+
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        let value = match after[..end].trim() {
+            "status" => Some(ctx.status.as_str().to_string()),
+            "reason" => Some(escape(ctx.status.canonical_reason().unwrap_or("Error"))),
+            "message" => Some(escape(ctx.message)),
+            "request_id" => Some(escape(ctx.request_id)),
+            "path" => Some(escape(ctx.path)),
+            "timestamp" => Some(escape(ctx.timestamp)),
+            _ => None,
+        };
+        match value {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Escape a value for use in HTML text or attribute values.
+pub(crate) fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape a value for use inside a JSON string literal.
+fn escape_json(value: &str) -> String {
+    let quoted = serde_json::Value::from(value).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::config::models::ErrorPageTemplate;
+
+    async fn body_of(response: Response<Body>) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_negotiate_format() {
+        assert_eq!(negotiate_format(None), ErrorPageFormat::Json);
+        assert_eq!(negotiate_format(Some("*/*")), ErrorPageFormat::Json);
+        assert_eq!(
+            negotiate_format(Some("text/html,application/xhtml+xml,*/*;q=0.8")),
+            ErrorPageFormat::Html
+        );
+        assert_eq!(
+            negotiate_format(Some("text/html;q=0.5, application/json")),
+            ErrorPageFormat::Json
+        );
+        assert_eq!(negotiate_format(Some("text/plain")), ErrorPageFormat::Text);
+    }
+
+    #[tokio::test]
+    async fn test_default_json_body_is_escaped() {
+        let response = error_response(StatusCode::NOT_FOUND, "Route \"x\" not found");
+        let rendered = render_error_page(
+            response,
+            &ErrorPagesConfig::default(),
+            None,
+            Some("application/json"),
+            "req-1",
+            "/x",
+        );
+        assert_eq!(rendered.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            rendered.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let body: serde_json::Value = serde_json::from_str(&body_of(rendered).await).unwrap();
+        assert_eq!(body["error"]["status"], 404);
+        assert_eq!(body["error"]["message"], "Route \"x\" not found");
+        assert_eq!(body["error"]["request_id"], "req-1");
+    }
+
+    #[tokio::test]
+    async fn test_route_template_overrides_global() {
+        let global = ErrorPagesConfig {
+            format: Some(ErrorPageFormat::Html),
+            templates: HashMap::from([(
+                "5xx".to_string(),
+                ErrorPageTemplate {
+                    html: Some("<p>global {{status}}</p>".to_string()),
+                    ..ErrorPageTemplate::default()
+                },
+            )]),
+            intercept: None,
+        };
+        let route = ErrorPagesConfig {
+            format: None,
+            templates: HashMap::from([(
+                "502".to_string(),
+                ErrorPageTemplate {
+                    html: Some("<p>route {{message}}</p>".to_string()),
+                    ..ErrorPageTemplate::default()
+                },
+            )]),
+            intercept: None,
+        };
+
+        let render = |status, message: &str| {
+            render_error_page(
+                error_response(status, message),
+                &global,
+                Some(&route),
+                None,
+                "req-1",
+                "/",
+            )
+        };
+        assert_eq!(
+            body_of(render(StatusCode::BAD_GATEWAY, "<down>")).await,
+            "<p>route &lt;down&gt;</p>"
+        );
+        assert_eq!(
+            body_of(render(StatusCode::GATEWAY_TIMEOUT, "slow")).await,
+            "<p>global 504</p>"
+        );
+    }
+
+    #[test]
+    fn test_placeholders_are_expanded_once() {
+        let ctx = ErrorContext {
+            status: StatusCode::NOT_FOUND,
+            message: "{{path}}",
+            request_id: "req-1",
+            path: "/secret",
+            timestamp: "now",
+        };
+        assert_eq!(
+            render_template(
+                "{{status}} {{ message }} {{unknown}} {{",
+                ErrorPageFormat::Text,
+                &ctx
+            ),
+            "404 {{path}} {{unknown}} {{"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unmarked_response_is_untouched() {
+        let response = Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from("backend 404"))
+            .unwrap();
+        let rendered = render_error_page(
+            response,
+            &ErrorPagesConfig::default(),
+            None,
+            None,
+            "req-1",
+            "/",
+        );
+        assert_eq!(body_of(rendered).await, "backend 404");
+    }
+
+    #[tokio::test]
+    async fn test_intercepted_backend_error() {
+        let global = ErrorPagesConfig {
+            intercept: Some(vec!["5xx".to_string()]),
+            ..ErrorPagesConfig::default()
+        };
+        let route = ErrorPagesConfig {
+            intercept: Some(vec!["404".to_string()]),
+            ..ErrorPagesConfig::default()
+        };
+        assert!(intercepts(None, &global, StatusCode::BAD_GATEWAY));
+        assert!(!intercepts(None, &global, StatusCode::NOT_FOUND));
+        assert!(intercepts(Some(&route), &global, StatusCode::NOT_FOUND));
+        assert!(!intercepts(Some(&route), &global, StatusCode::BAD_GATEWAY));
+        assert!(intercepts(
+            Some(&ErrorPagesConfig::default()),
+            &global,
+            StatusCode::BAD_GATEWAY
+        ));
+
+        let response = Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header(header::RETRY_AFTER, "30")
+            .header("x-debug", "pool exhausted")
+            .body(Body::from("Traceback (most recent call last): ..."))
+            .unwrap();
+        let rendered = render_error_page(
+            intercept_backend_error(response),
+            &global,
+            None,
+            Some("text/plain"),
+            "req-1",
+            "/",
+        );
+        assert_eq!(rendered.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(rendered.headers()[header::RETRY_AFTER], "30");
+        assert!(rendered.headers().get("x-debug").is_none());
+        assert_eq!(body_of(rendered).await, "Service Unavailable");
+    }
+}
@@ -0,0 +1,161 @@
+//! A/B experiment variant assignment.
+//!
+//! Each [`ExperimentConfig`] that applies to a request assigns it a variant:
+//! the one named in the experiment's cookie when that is still a configured
+//! variant, otherwise a weighted random pick that the gateway then stores in
+//! the cookie. Assignment happens before routing, so backends see the variant
+//! in a request header and metrics can be labelled with it.
+use rand::RngExt;
+
+use crate::{config::models::ExperimentConfig, core::cookies::parse_cookie_header};
+
+/// The variant of one experiment chosen for a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment<'a> {
+    pub experiment: &'a ExperimentConfig,
+    pub variant: &'a str,
+    /// Not taken from the cookie, which must be set on the response
+    pub new: bool,
+}
+
+impl Assignment<'_> {
+    /// `Set-Cookie` value recording the variant.
+    pub fn set_cookie(&self) -> String {
+        format!(
+            "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+            self.experiment.cookie_name(),
+            self.variant,
+            self.experiment.cookie_max_age_secs
+        )
+    }
+}
+
+/// Assign a variant for every experiment covering `path`, honouring the
+/// variants recorded in the request's `Cookie` headers.
+pub fn assign<'a, 'c>(
+    experiments: &'a [ExperimentConfig],
+    path: &str,
+    cookie_headers: impl IntoIterator<Item = &'c str>,
+) -> Vec<Assignment<'a>> {
+    let applicable: Vec<_> = experiments
+        .iter()
+        .filter(|experiment| {
+            experiment.routes.is_empty()
+                || experiment
+                    .routes
+                    .iter()
+                    .any(|prefix| path.starts_with(prefix.as_str()))
+        })
+        .collect();
+    if applicable.is_empty() {
+        return Vec::new();
+    }
+    let cookies: Vec<_> = cookie_headers
+        .into_iter()
+        .flat_map(parse_cookie_header)
+        .collect();
+    applicable
+        .into_iter()
+        .filter_map(|experiment| {
+            let name = experiment.cookie_name();
+            let sticky = cookies
+                .iter()
+                .filter(|(cookie, _)| *cookie == name)
+                .find_map(|(_, value)| {
+                    experiment
+                        .variants
+                        .iter()
+                        .find(|variant| variant.name == *value)
+                });
+            match sticky {
+                Some(variant) => Some(Assignment {
+                    experiment,
+                    variant: &variant.name,
+                    new: false,
+                }),
+                None => {
+                    pick(experiment, rand::rng().random_range(0..100u8)).map(|variant| Assignment {
+                        experiment,
+                        variant,
+                        new: true,
+                    })
+                }
+            }
+        })
+        .collect()
+}
+
+/// The variant whose percentage range contains `roll` (0-99).
+fn pick(experiment: &ExperimentConfig, roll: u8) -> Option<&str> {
+    let mut upper = 0u16;
+    experiment
+        .variants
+        .iter()
+        .find(|variant| {
+            upper += u16::from(variant.percent);
+            u16::from(roll) < upper
+        })
+        .map(|variant| variant.name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::models::ExperimentVariant;
+
+    fn experiment(name: &str, routes: &[&str], variants: &[(&str, u8)]) -> ExperimentConfig {
+        ExperimentConfig {
+            name: name.to_string(),
+            routes: routes.iter().map(|route| route.to_string()).collect(),
+            variants: variants
+                .iter()
+                .map(|&(name, percent)| ExperimentVariant {
+                    name: name.to_string(),
+                    percent,
+                })
+                .collect(),
+            cookie: None,
+            cookie_max_age_secs: 3600,
+            header: None,
+        }
+    }
+
+    #[test]
+    fn test_pick_by_percentage() {
+        let checkout = experiment("checkout", &[], &[("control", 90), ("new", 10)]);
+        assert_eq!(pick(&checkout, 0), Some("control"));
+        assert_eq!(pick(&checkout, 89), Some("control"));
+        assert_eq!(pick(&checkout, 90), Some("new"));
+        assert_eq!(pick(&checkout, 99), Some("new"));
+
+        let off = experiment("off", &[], &[("control", 100), ("new", 0)]);
+        assert!((0..100).all(|roll| pick(&off, roll) == Some("control")));
+    }
+
+    #[test]
+    fn test_assign_sticky_cookie() {
+        let experiments = [
+            experiment("checkout", &["/shop"], &[("a", 50), ("b", 50)]),
+            experiment("search", &["/search"], &[("a", 100)]),
+        ];
+
+        let assigned = assign(&experiments, "/shop/cart", ["axon_exp_checkout=b"]);
+        assert_eq!(assigned.len(), 1);
+        assert_eq!(assigned[0].variant, "b");
+        assert!(!assigned[0].new);
+
+        // Unknown variants are reassigned and the cookie replaced
+        let assigned = assign(&experiments, "/shop", ["x=1; axon_exp_checkout=gone"]);
+        assert!(assigned[0].new);
+        assert!(["a", "b"].contains(&assigned[0].variant));
+        assert_eq!(
+            assigned[0].set_cookie(),
+            format!(
+                "axon_exp_checkout={}; Path=/; Max-Age=3600; HttpOnly; SameSite=Lax",
+                assigned[0].variant
+            )
+        );
+
+        assert!(assign(&experiments, "/about", []).is_empty());
+    }
+}
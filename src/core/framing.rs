@@ -0,0 +1,214 @@
+//! Request message framing checks
+//!
+//! A request whose body length can be read two ways (`Content-Length` next
+//! to `Transfer-Encoding`, or several different lengths) may be split
+//! differently by the gateway and a backend, letting a second request be
+//! smuggled inside the first. Such requests are rejected before routing;
+//! those that pass are forwarded with a single `Content-Length`.
+//!
+//! Headers that may only appear once, such as `Host` or `Authorization`,
+//! are ambiguous in the same way when repeated: the gateway and a backend
+//! may each act on a different value. Repeats of one value are folded into
+//! a single header and different values are rejected.
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Version, header};
+use thiserror::Error;
+
+/// Why a request's framing was rejected
+#[derive(Error, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FramingError {
+    /// `Content-Length` and `Transfer-Encoding` both present
+    #[error("Request has both Content-Length and Transfer-Encoding")]
+    LengthWithTransferEncoding,
+    /// `Content-Length` values that are not numbers or disagree
+    #[error("Request has a malformed or conflicting Content-Length")]
+    InvalidContentLength,
+    /// `Transfer-Encoding` not ending in a single `chunked`, or sent where
+    /// the protocol has none (HTTP/1.0, HTTP/2, HTTP/3)
+    #[error("Request has an unsupported Transfer-Encoding")]
+    InvalidTransferEncoding,
+    /// A single-valued header repeated with different values
+    #[error("Request has conflicting {0} headers")]
+    DuplicateHeader(HeaderName),
+}
+
+/// Request headers that carry a single value. `Content-Length` is checked
+/// on its own, since its values may also be comma-separated.
+const SINGLETON_HEADERS: [HeaderName; 10] = [
+    header::HOST,
+    header::AUTHORIZATION,
+    header::CONTENT_TYPE,
+    header::IF_MODIFIED_SINCE,
+    header::IF_UNMODIFIED_SINCE,
+    header::IF_RANGE,
+    header::MAX_FORWARDS,
+    header::RANGE,
+    header::REFERER,
+    header::USER_AGENT,
+];
+
+impl FramingError {
+    /// Metric label for the rejection
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::LengthWithTransferEncoding => "length_with_transfer_encoding",
+            Self::InvalidContentLength => "invalid_content_length",
+            Self::InvalidTransferEncoding => "invalid_transfer_encoding",
+            Self::DuplicateHeader(_) => "duplicate_header",
+        }
+    }
+}
+
+/// Check that the body length of a request can only be read one way and
+/// that single-valued headers are not repeated with different values. Repeated
+/// identical `Content-Length` and single-valued headers are folded into one.
+pub fn check_framing(version: Version, headers: &mut HeaderMap) -> Result<(), FramingError> {
+    for name in SINGLETON_HEADERS {
+        let mut values = headers.get_all(&name).iter();
+        let Some(first) = values.next() else {
+            continue;
+        };
+        let first = first.clone();
+        let mut repeated = false;
+        for value in values {
+            if value != first {
+                return Err(FramingError::DuplicateHeader(name));
+            }
+            repeated = true;
+        }
+        if repeated {
+            headers.insert(name, first);
+        }
+    }
+
+    let has_transfer_encoding = headers.contains_key(header::TRANSFER_ENCODING);
+    if has_transfer_encoding && headers.contains_key(header::CONTENT_LENGTH) {
+        return Err(FramingError::LengthWithTransferEncoding);
+    }
+
+    if has_transfer_encoding {
+        if version != Version::HTTP_11 {
+            return Err(FramingError::InvalidTransferEncoding);
+        }
+        let codings: Vec<String> = headers
+            .get_all(header::TRANSFER_ENCODING)
+            .iter()
+            .map(|value| {
+                value
+                    .to_str()
+                    .map_err(|_| FramingError::InvalidTransferEncoding)
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .map(|coding| coding.trim().to_ascii_lowercase())
+            .collect();
+        let chunked = codings.iter().filter(|coding| *coding == "chunked").count();
+        if chunked != 1 || codings.last().is_none_or(|last| last != "chunked") {
+            return Err(FramingError::InvalidTransferEncoding);
+        }
+        return Ok(());
+    }
+
+    let mut length = None;
+    for value in headers.get_all(header::CONTENT_LENGTH) {
+        let value = value
+            .to_str()
+            .map_err(|_| FramingError::InvalidContentLength)?;
+        for item in value.split(',') {
+            let item = item.trim();
+            if item.is_empty() || !item.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(FramingError::InvalidContentLength);
+            }
+            let parsed: u64 = item
+                .parse()
+                .map_err(|_| FramingError::InvalidContentLength)?;
+            if length.is_some_and(|length| length != parsed) {
+                return Err(FramingError::InvalidContentLength);
+            }
+            length = Some(parsed);
+        }
+    }
+    if let Some(length) = length {
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(length));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(version: Version, headers: &[(&str, &str)]) -> Result<HeaderMap, FramingError> {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(
+                header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        check_framing(version, &mut map).map(|()| map)
+    }
+
+    #[test]
+    fn test_check_framing() {
+        let http11 = Version::HTTP_11;
+        assert!(check(http11, &[]).is_ok());
+        assert!(check(http11, &[("content-length", "5")]).is_ok());
+        assert!(check(http11, &[("transfer-encoding", "gzip, chunked")]).is_ok());
+
+        // Repeated identical lengths are folded into one
+        let headers = check(
+            http11,
+            &[("content-length", "5, 005"), ("content-length", "5")],
+        )
+        .expect("consistent lengths");
+        assert_eq!(headers.get_all(header::CONTENT_LENGTH).iter().count(), 1);
+        assert_eq!(headers[header::CONTENT_LENGTH], "5");
+
+        assert_eq!(
+            check(
+                http11,
+                &[("content-length", "5"), ("transfer-encoding", "chunked")]
+            )
+            .unwrap_err(),
+            FramingError::LengthWithTransferEncoding
+        );
+        for length in ["5, 6", "+5", "0x5", "", "5 5"] {
+            assert_eq!(
+                check(http11, &[("content-length", length)]).unwrap_err(),
+                FramingError::InvalidContentLength,
+                "{length:?}"
+            );
+        }
+        for coding in ["chunked, gzip", "chunked, chunked", "identity"] {
+            assert_eq!(
+                check(http11, &[("transfer-encoding", coding)]).unwrap_err(),
+                FramingError::InvalidTransferEncoding,
+                "{coding:?}"
+            );
+        }
+        // Single-valued headers may repeat a value, which is folded, but not
+        // disagree
+        let headers =
+            check(http11, &[("host", "a.example"), ("host", "a.example")]).expect("repeated host");
+        assert_eq!(headers.get_all(header::HOST).iter().count(), 1);
+        assert_eq!(
+            check(
+                http11,
+                &[("authorization", "Bearer a"), ("authorization", "Bearer b")]
+            )
+            .unwrap_err(),
+            FramingError::DuplicateHeader(header::AUTHORIZATION)
+        );
+        assert!(check(http11, &[("accept", "text/html"), ("accept", "*/*")]).is_ok());
+
+        for version in [Version::HTTP_10, Version::HTTP_2, Version::HTTP_3] {
+            assert_eq!(
+                check(version, &[("transfer-encoding", "chunked")]).unwrap_err(),
+                FramingError::InvalidTransferEncoding
+            );
+        }
+    }
+}
@@ -7,21 +7,58 @@
 //! * Health status queries & filtering
 //! * Load‑balancing backend selection (simple round‑robin over healthy set)
 //! * Access to constructed rate limiters
+//! * Per‑route canary state for weighted traffic splitting
+//! * Per‑route retry budgets
+//! * Per‑backend request limiters
+//! * Cancellation of traffic to backends a reload removed
+//! * Externally pushed steering weights, carried across reloads
+//! * The WAF engine, rebuilt when its external rule file changes
+//! * Automatic IP bans, carried across reloads
+//! * Recordings of backend exchanges ("taps"), carried across reloads
+//! * The configuration generation, counting successful reloads
+//! * Virtual host lookup, with single-label `*.example.com` wildcard hostnames
 //!
 //! This layer deliberately avoids I/O and only manipulates in‑memory data so
 //! it remains fast and easily testable in isolation.
-use std::{collections::HashMap as StdHashMap, sync::Arc};
+use std::{
+    cmp::Reverse,
+    collections::HashMap as StdHashMap,
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use arc_swap::ArcSwapOption;
 use axum::http::{HeaderMap, Uri};
 use matchit::Router;
 use scc::HashMap;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    config::{HealthCheckConfig, HealthStatus, RouteConfig, RouteConfigEntry, ServerConfig},
+    config::{
+        BackendHealthCheckConfig, HealthCheckConfig, HealthStatus, RouteConfig, ServerConfig,
+        TapConfig, UpstreamTlsConfig, VirtualHostConfig, WafConfig,
+        models::{BackendRemovalConfig, BackendRemovalMode},
+    },
     core::{
-        backend::{BackendHealth, BackendUrl},
-        rate_limiter::RouteRateLimiter,
-        waf::{SecurityViolation, WafEngine},
+        backend::{BackendHealth, BackendLimiter, BackendUrl},
+        canary::CanaryState,
+        client_ip::ClientIpResolver,
+        coalesce::Coalescer,
+        condition::RouteTransforms,
+        dedupe::DedupeWindow,
+        hedge::Hedger,
+        load_balancer::{select_least_loaded, select_weighted},
+        rate_limiter::{NamedRateLimiter, RouteRateLimiter},
+        retry::RetryBudget,
+        schedule::RouteSchedule,
+        steering::SteeringTable,
+        tap::TapRegistry,
+        virtual_host::{HostTable, host_candidates},
+        waf::{
+            AutoBanPolicy, BanList, LoadedRuleSet, Offense, SecurityViolation, ThreatLevel,
+            WafEngine,
+        },
     },
 };
 
@@ -48,6 +85,30 @@ impl RouteKey {
     }
 }
 
+/// A single route's matcher, used instead of the routers once any route sets
+/// a `priority`, since a higher priority can beat a longer prefix
+struct RankedRoute {
+    prefix: String,
+    /// Position among the routes sharing `prefix`
+    index: usize,
+    host: Option<String>,
+    priority: i32,
+    matcher: Router<()>,
+}
+
+/// The matchit patterns for a route prefix: the prefix itself and its
+/// sub-paths
+pub(crate) fn prefix_patterns(prefix: &str) -> [String; 2] {
+    // If prefix is "/", wildcard is "/{*rest}"
+    // If prefix is "/api", wildcard is "/api/{*rest}"
+    let wildcard = if prefix == "/" {
+        "/{*rest}".to_string()
+    } else {
+        format!("{}/{{*rest}}", prefix.trim_end_matches('/'))
+    };
+    [prefix.to_string(), wildcard]
+}
+
 /// Central orchestrator for routing, backend selection, health status lookup
 /// and per‑route rate limiting. An instance is cheap to clone (Arc inside).
 ///
@@ -57,10 +118,27 @@ impl RouteKey {
 pub struct GatewayService {
     config: Arc<ServerConfig>,
     backend_health: Arc<HashMap<String, BackendHealth>>,
-    rate_limiters: Arc<HashMap<String, RouteRateLimiter>>, // keyed by route prefix + host
-    waf_engine: Option<Arc<WafEngine>>,
+    backend_limiters: Arc<HashMap<String, Arc<BackendLimiter>>>, // keyed by backend URL
+    removal_tokens: Arc<HashMap<String, CancellationToken>>,     // keyed by backend URL
+    rate_limiters: Arc<HashMap<String, Arc<[NamedRateLimiter]>>>, // keyed by route prefix + host
+    canaries: Arc<HashMap<String, Arc<CanaryState>>>,            // keyed by route prefix + host
+    dedupe_windows: Arc<HashMap<String, Arc<DedupeWindow>>>,     // keyed by route prefix + host
+    coalescers: Arc<HashMap<String, Arc<Coalescer>>>,            // keyed by route prefix + host
+    retry_budgets: Arc<HashMap<String, Arc<RetryBudget>>>,       // keyed by route prefix + host
+    hedgers: Arc<HashMap<String, Arc<Hedger>>>,                  // keyed by route prefix + host
+    transforms: Arc<HashMap<String, Arc<RouteTransforms>>>,      // keyed by route prefix + host
+    schedules: Arc<HashMap<String, Arc<RouteSchedule>>>,         // keyed by route prefix + host
+    steering: Arc<SteeringTable>,
+    taps: Arc<TapRegistry>,
+    generation: u64,
+    waf_engine: ArcSwapOption<WafEngine>,
+    bans: Arc<BanList>,
+    auto_ban: Option<AutoBanPolicy>,
     host_routers: Arc<StdHashMap<String, Router<String>>>,
     global_router: Arc<Router<String>>,
+    ranked_routes: Vec<RankedRoute>, // empty unless a route sets a priority
+    virtual_hosts: HostTable<String>, // virtual host name by hostname pattern
+    client_ips: ClientIpResolver,
 }
 
 impl GatewayService {
@@ -71,8 +149,17 @@ impl GatewayService {
     pub fn new(config: Arc<ServerConfig>) -> Self {
         let backend_health = Arc::new(HashMap::new());
         let rate_limiters = Arc::new(HashMap::new());
+        let canaries = Arc::new(HashMap::new());
+        let dedupe_windows = Arc::new(HashMap::new());
+        let coalescers = Arc::new(HashMap::new());
+        let retry_budgets = Arc::new(HashMap::new());
+        let hedgers = Arc::new(HashMap::new());
+        let transforms = Arc::new(HashMap::new());
+        let schedules = Arc::new(HashMap::new());
+        let taps = Arc::new(TapRegistry::new());
+        taps.sync_config(&Self::configured_taps(&config));
 
-        let backends = Self::collect_backends(&config.routes);
+        let backends = Self::collect_backends(&config);
 
         for backend in &backends {
             if let Ok(backend_url) = BackendUrl::new(backend) {
@@ -83,31 +170,59 @@ impl GatewayService {
             }
         }
 
+        // Proxy and WebSocket backends can be cancelled when a reload drops them
+        let removal_tokens = Arc::new(HashMap::new());
+        let websocket_targets = config.all_routes().filter_map(|(_, route)| match route {
+            RouteConfig::Websocket { target, .. } => Some(target),
+            _ => None,
+        });
+        for backend in backends.iter().chain(websocket_targets) {
+            let _ = removal_tokens.insert_sync(backend.clone(), CancellationToken::new());
+        }
+
+        let backend_limiters = Arc::new(HashMap::new());
+        for (backend, limits) in &config.backend_limits {
+            let _ = backend_limiters.insert_sync(
+                backend.clone(),
+                Arc::new(BackendLimiter::new(backend, limits)),
+            );
+        }
+
         // Build route-level rate limiters
-        for (prefix, entry) in &config.routes {
-            for route in entry.iter() {
-                let (rate_limit_cfg_opt, route_host) = match route {
-                    RouteConfig::Proxy {
-                        rate_limit, host, ..
-                    } => (rate_limit, host),
-                    RouteConfig::LoadBalance {
-                        rate_limit, host, ..
-                    } => (rate_limit, host),
-                    RouteConfig::Static {
-                        rate_limit, host, ..
-                    } => (rate_limit, host),
-                    RouteConfig::Redirect {
-                        rate_limit, host, ..
-                    } => (rate_limit, host),
-                    RouteConfig::Websocket {
-                        rate_limit, host, ..
-                    } => (rate_limit, host),
-                };
-                if let Some(rate_cfg) = rate_limit_cfg_opt {
-                    let key = RouteKey::new(prefix.clone(), route_host.clone());
+        for (prefix, route) in config.all_routes() {
+            let (rate_limit_cfg_opt, route_host) = match route {
+                RouteConfig::Proxy {
+                    rate_limit, host, ..
+                } => (rate_limit, host),
+                RouteConfig::LoadBalance {
+                    rate_limit, host, ..
+                } => (rate_limit, host),
+                RouteConfig::Static {
+                    rate_limit, host, ..
+                } => (rate_limit, host),
+                RouteConfig::Redirect {
+                    rate_limit, host, ..
+                } => (rate_limit, host),
+                RouteConfig::Websocket {
+                    rate_limit, host, ..
+                } => (rate_limit, host),
+            };
+            if let Some(rate_limits) = rate_limit_cfg_opt {
+                let key = RouteKey::new(prefix.to_string(), route_host.clone());
+                let mut limiters = Vec::new();
+                for rate_cfg in rate_limits.iter() {
                     match RouteRateLimiter::new(rate_cfg) {
                         Ok(limiter) => {
-                            let _ = rate_limiters.insert_sync(key.to_rate_limiter_key(), limiter);
+                            crate::metrics::set_rate_limit_configured(
+                                &key.to_rate_limiter_key(),
+                                rate_cfg.label(),
+                                rate_cfg.requests,
+                                &rate_cfg.period,
+                            );
+                            limiters.push(NamedRateLimiter {
+                                name: rate_cfg.label().to_string(),
+                                limiter,
+                            });
                         }
                         Err(e) => {
                             tracing::error!(
@@ -118,6 +233,131 @@ impl GatewayService {
                         }
                     }
                 }
+                if !limiters.is_empty() {
+                    let _ = rate_limiters.insert_sync(key.to_rate_limiter_key(), limiters.into());
+                }
+            }
+            if let RouteConfig::Proxy {
+                request_headers,
+                response_headers,
+                request_body,
+                response_body,
+                ..
+            }
+            | RouteConfig::LoadBalance {
+                request_headers,
+                response_headers,
+                request_body,
+                response_body,
+                ..
+            } = route
+            {
+                // Conditions are compiled once here, never per request
+                match RouteTransforms::compile(
+                    request_headers.as_ref(),
+                    response_headers.as_ref(),
+                    request_body.as_ref(),
+                    response_body.as_ref(),
+                ) {
+                    Ok(Some(compiled)) => {
+                        let key = RouteKey::new(prefix.to_string(), route_host.clone());
+                        let _ =
+                            transforms.insert_sync(key.to_rate_limiter_key(), Arc::new(compiled));
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        tracing::error!(
+                            "Failed to compile header/body actions for route '{}': {}",
+                            prefix,
+                            e
+                        );
+                    }
+                }
+            }
+            if let RouteConfig::Proxy {
+                dedupe: Some(dedupe_cfg),
+                ..
+            }
+            | RouteConfig::LoadBalance {
+                dedupe: Some(dedupe_cfg),
+                ..
+            } = route
+            {
+                let key = RouteKey::new(prefix.to_string(), route_host.clone());
+                let _ = dedupe_windows.insert_sync(
+                    key.to_rate_limiter_key(),
+                    Arc::new(DedupeWindow::new(dedupe_cfg)),
+                );
+            }
+            if let RouteConfig::Proxy {
+                coalesce: Some(coalesce_cfg),
+                ..
+            }
+            | RouteConfig::LoadBalance {
+                coalesce: Some(coalesce_cfg),
+                ..
+            } = route
+            {
+                let key = RouteKey::new(prefix.to_string(), route_host.clone());
+                let _ = coalescers.insert_sync(
+                    key.to_rate_limiter_key(),
+                    Arc::new(Coalescer::new(coalesce_cfg)),
+                );
+            }
+            if let RouteConfig::Proxy {
+                retry: Some(retry_cfg),
+                ..
+            }
+            | RouteConfig::LoadBalance {
+                retry: Some(retry_cfg),
+                ..
+            } = route
+            {
+                let key = RouteKey::new(prefix.to_string(), route_host.clone());
+                let _ = retry_budgets.insert_sync(
+                    key.to_rate_limiter_key(),
+                    Arc::new(RetryBudget::new(&retry_cfg.budget)),
+                );
+            }
+            if let RouteConfig::LoadBalance {
+                hedge: Some(hedge_cfg),
+                ..
+            } = route
+            {
+                let key = RouteKey::new(prefix.to_string(), route_host.clone());
+                let _ = hedgers
+                    .insert_sync(key.to_rate_limiter_key(), Arc::new(Hedger::new(hedge_cfg)));
+            }
+            if let RouteConfig::Proxy {
+                schedule: Some(schedule_cfg),
+                ..
+            }
+            | RouteConfig::LoadBalance {
+                schedule: Some(schedule_cfg),
+                ..
+            } = route
+            {
+                match RouteSchedule::compile(schedule_cfg) {
+                    Ok(schedule) => {
+                        let key = RouteKey::new(prefix.to_string(), route_host.clone());
+                        let _ =
+                            schedules.insert_sync(key.to_rate_limiter_key(), Arc::new(schedule));
+                    }
+                    Err(e) => {
+                        tracing::error!("Invalid schedule for route '{}': {}", prefix, e);
+                    }
+                }
+            }
+            if let RouteConfig::LoadBalance {
+                canary: Some(canary_cfg),
+                ..
+            } = route
+            {
+                let key = RouteKey::new(prefix.to_string(), route_host.clone());
+                let _ = canaries.insert_sync(
+                    key.to_rate_limiter_key(),
+                    Arc::new(CanaryState::new(canary_cfg.clone())),
+                );
             }
         }
 
@@ -133,12 +373,26 @@ impl GatewayService {
             None
         };
 
+        let auto_ban = config.waf.as_ref().and_then(|waf_config| {
+            AutoBanPolicy::from_config(&waf_config.auto_ban)
+                .inspect_err(|e| tracing::error!("Invalid auto-ban configuration: {}", e))
+                .ok()
+                .flatten()
+        });
+
         // Build matchit routers for O(1) lookup
         let mut host_routers: StdHashMap<String, Router<String>> = StdHashMap::new();
         let mut global_router = Router::new();
 
+        let mut ranked_routes = Vec::new();
+        let prioritized = config
+            .routes
+            .values()
+            .flat_map(|entry| entry.iter())
+            .any(|route| route.priority().is_some());
+
         for (prefix, entry) in &config.routes {
-            for route_config in entry.iter() {
+            for (index, route_config) in entry.iter().enumerate() {
                 let route_host = match route_config {
                     RouteConfig::Static { host, .. } => host,
                     RouteConfig::Redirect { host, .. } => host,
@@ -153,41 +407,180 @@ impl GatewayService {
                     &mut global_router
                 };
 
-                // Insert exact match (ignore error if already exists for same prefix in global)
-                let _ = router.insert(prefix, prefix.clone());
+                // Insert exact and sub-path matches (ignore error if already
+                // exists for same prefix in global)
+                for pattern in prefix_patterns(prefix) {
+                    let _ = router.insert(pattern, prefix.clone());
+                }
 
-                // Insert wildcard match for sub-paths
-                // If prefix is "/", wildcard is "/{*rest}"
-                // If prefix is "/api", wildcard is "/api/{*rest}"
-                let wildcard = if prefix == "/" {
-                    "/{*rest}".to_string()
-                } else {
-                    format!("{}/{{*rest}}", prefix.trim_end_matches('/'))
-                };
+                if prioritized {
+                    let mut matcher = Router::new();
+                    for pattern in prefix_patterns(prefix) {
+                        let _ = matcher.insert(pattern, ());
+                    }
+                    ranked_routes.push(RankedRoute {
+                        prefix: prefix.clone(),
+                        index,
+                        host: route_host.as_ref().map(|h| h.to_lowercase()),
+                        priority: route_config.priority().unwrap_or_default(),
+                        matcher,
+                    });
+                }
+            }
+        }
+
+        let client_ips = ClientIpResolver::from_config(&config.client_ip)
+            .inspect_err(|e| tracing::error!("Invalid client_ip configuration: {}", e))
+            .unwrap_or_default();
 
-                let _ = router.insert(&wildcard, prefix.clone());
+        let mut virtual_hosts = HostTable::new();
+        for (name, vhost) in &config.virtual_hosts {
+            for hostname in &vhost.hostnames {
+                virtual_hosts.insert(hostname, name.clone());
             }
         }
 
         Self {
             config,
             backend_health,
+            backend_limiters,
+            removal_tokens,
             rate_limiters,
-            waf_engine,
+            dedupe_windows,
+            coalescers,
+            retry_budgets,
+            hedgers,
+            transforms,
+            schedules,
+            canaries,
+            steering: Arc::new(SteeringTable::new()),
+            taps,
+            generation: 1,
+            waf_engine: ArcSwapOption::new(waf_engine),
+            bans: Arc::new(BanList::new()),
+            auto_ban,
             host_routers: Arc::new(host_routers),
             global_router: Arc::new(global_router),
+            ranked_routes,
+            virtual_hosts,
+            client_ips,
         }
     }
 
-    /// Check if WAF is enabled
-    pub fn is_waf_enabled(&self) -> bool {
-        self.waf_engine
-            .as_ref()
-            .map(|e| e.is_enabled())
-            .unwrap_or(false)
+    /// Share `steering` instead of starting with an empty table, so weights
+    /// pushed before a reload keep applying.
+    pub fn with_steering(mut self, steering: Arc<SteeringTable>) -> Self {
+        self.steering = steering;
+        self
+    }
+
+    /// Steering weights applied on top of local load balancing.
+    pub fn steering(&self) -> Arc<SteeringTable> {
+        self.steering.clone()
     }
 
-    /// Check request against WAF rules
+    /// Share `taps` instead of starting with an empty registry, so taps
+    /// started through the admin API and their exchanges survive a reload.
+    pub fn with_taps(mut self, taps: Arc<TapRegistry>) -> Self {
+        taps.sync_config(&Self::configured_taps(&self.config));
+        self.taps = taps;
+        self
+    }
+
+    /// Taps recording backend exchanges, keyed by route label.
+    pub fn taps(&self) -> Arc<TapRegistry> {
+        self.taps.clone()
+    }
+
+    /// Routes with a `tap` setting, by label
+    fn configured_taps(config: &ServerConfig) -> Vec<(String, TapConfig)> {
+        config
+            .all_routes()
+            .filter_map(|(prefix, route)| match route {
+                RouteConfig::Proxy { host, tap, .. }
+                | RouteConfig::LoadBalance { host, tap, .. } => {
+                    let key = RouteKey::new(prefix.to_string(), host.clone());
+                    tap.clone().map(|tap| (key.to_rate_limiter_key(), tap))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Share `bans` instead of starting with an empty list, so bans and
+    /// offense counts survive a reload.
+    pub fn with_bans(mut self, bans: Arc<BanList>) -> Self {
+        self.bans = bans;
+        self
+    }
+
+    /// IPs banned for repeated offenses.
+    pub fn bans(&self) -> Arc<BanList> {
+        self.bans.clone()
+    }
+
+    /// Keep the canary states of `previous` whose configuration is
+    /// unchanged, so a rollback and the current window survive a reload.
+    /// Canaries whose configuration changed start over.
+    pub fn with_canaries(self, previous: &GatewayService) -> Self {
+        let mut keys = Vec::new();
+        self.canaries.iter_sync(|key, _| {
+            keys.push(key.clone());
+            true
+        });
+        for key in keys {
+            let Some(state) = self.canaries.read_sync(&key, |_, state| state.clone()) else {
+                continue;
+            };
+            let kept = previous
+                .canaries
+                .read_sync(&key, |_, old| old.clone())
+                .filter(|old| old.config() == state.config());
+            if let Some(old) = kept {
+                self.canaries.upsert_sync(key, old);
+            }
+        }
+        self
+    }
+
+    /// Set the configuration generation; a reload uses the previous one + 1.
+    pub fn with_generation(mut self, generation: u64) -> Self {
+        self.generation = generation;
+        self
+    }
+
+    /// Configuration generation, starting at 1 for the initial config.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The virtual host serving `host`, with its name
+    pub fn virtual_host(&self, host: Option<&str>) -> Option<(&str, &VirtualHostConfig)> {
+        let name = self.virtual_hosts.get(host?)?;
+        let vhost = self.config.virtual_hosts.get(name)?;
+        Some((name.as_str(), vhost))
+    }
+
+    /// The address a request from `peer` is attributed to, following
+    /// `X-Forwarded-For` through `client_ip.trusted_proxies`
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        self.client_ips.resolve(peer, headers)
+    }
+
+    /// Check if the WAF inspects requests for `host`: its virtual host's
+    /// `waf` setting if there is one, otherwise `waf.enabled`
+    pub fn is_waf_enabled(&self, host: Option<&str>) -> bool {
+        let engine = self.waf_engine.load();
+        let Some(engine) = engine.as_ref() else {
+            return false;
+        };
+        self.virtual_host(host)
+            .and_then(|(_, vhost)| vhost.waf)
+            .unwrap_or_else(|| engine.is_enabled())
+    }
+
+    /// Check request against WAF rules, whether or not `waf.enabled` is set;
+    /// callers decide with [`Self::is_waf_enabled`]
     pub fn check_waf(
         &self,
         uri: &Uri,
@@ -195,25 +588,101 @@ impl GatewayService {
         body: Option<&[u8]>,
         client_ip: Option<&str>,
     ) -> Result<(), SecurityViolation> {
-        if let Some(engine) = &self.waf_engine {
-            engine.check_request(uri, headers, body, client_ip)
+        if let Some(engine) = self.waf_engine.load().as_ref() {
+            engine.inspect_request(uri, headers, body, client_ip)
         } else {
             Ok(())
         }
     }
 
+    /// Reject `ip` if it is serving an automatic ban.
+    pub fn check_ban(&self, ip: IpAddr) -> Result<(), SecurityViolation> {
+        if self.auto_ban.is_none() {
+            return Ok(());
+        }
+        match self.bans.banned_until(ip, Instant::now()) {
+            Some(until) => Err(SecurityViolation::new(
+                "IP_BANNED",
+                ThreatLevel::High,
+                format!(
+                    "IP {ip} is banned for another {}s",
+                    until.saturating_duration_since(Instant::now()).as_secs()
+                ),
+                true,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Count an offense by `ip` towards an automatic ban; returns true if
+    /// this offense got it banned.
+    pub fn record_offense(&self, ip: IpAddr, offense: Offense) -> bool {
+        let Some(policy) = &self.auto_ban else {
+            return false;
+        };
+        let banned = self.bans.record(ip, offense, policy, Instant::now());
+        if banned {
+            tracing::warn!(ip = %ip, reason = offense.as_str(), "IP automatically banned");
+            crate::metrics::record_auto_ban(offense.as_str());
+        }
+        banned
+    }
+
+    /// Lift expired bans and forget stale offense counts, updating the
+    /// banned IP gauge.
+    pub fn sweep_bans(&self) {
+        let window = self
+            .auto_ban
+            .as_ref()
+            .map_or(Duration::ZERO, AutoBanPolicy::window);
+        let banned = self.bans.sweep(window, Instant::now());
+        crate::metrics::set_auto_banned_ips(banned as u64);
+    }
+
+    /// The WAF configuration, if one is present.
+    pub fn waf_config(&self) -> Option<&WafConfig> {
+        self.config.waf.as_ref()
+    }
+
+    /// External WAF rule set the current engine was built with.
+    pub fn waf_rule_set(&self) -> Option<Arc<LoadedRuleSet>> {
+        self.waf_engine
+            .load()
+            .as_ref()
+            .and_then(|engine| engine.rule_set().cloned())
+    }
+
+    /// Rebuild the WAF engine with `rule_set` added to the configured rules.
+    ///
+    /// The rule set must come from the configured `rules_file`. On error the
+    /// current engine is kept.
+    pub fn apply_waf_rules(&self, rule_set: Arc<LoadedRuleSet>) -> eyre::Result<()> {
+        let Some(config) = &self.config.waf else {
+            eyre::bail!("WAF is not configured");
+        };
+        if config.rules_file.as_deref() != Some(rule_set.path.as_str()) {
+            eyre::bail!(
+                "Rule set {} is not the configured rules_file",
+                rule_set.path
+            );
+        }
+        let engine = WafEngine::with_rule_set(config, rule_set)?;
+        self.waf_engine.store(Some(Arc::new(engine)));
+        Ok(())
+    }
+
     /// Access the concurrent backend health map (mainly for adapters / diagnostics).
     pub fn backend_health(&self) -> &HashMap<String, BackendHealth> {
         &self.backend_health
     }
 
-    /// Get a cloned route-level rate limiter for a given prefix and optional host, if configured
-    /// Fetch a cloned per‑route rate limiter if present.
-    pub async fn get_rate_limiter(
+    /// Fetch a route's rate limits, in the order they are checked, for a
+    /// given prefix and optional host.
+    pub async fn get_rate_limiters(
         &self,
         route_prefix: &str,
         host: Option<&str>,
-    ) -> Option<RouteRateLimiter> {
+    ) -> Option<Arc<[NamedRateLimiter]>> {
         let key = RouteKey::new(route_prefix.to_string(), host.map(|h| h.to_string()));
         self.rate_limiters
             .get_async(&key.to_rate_limiter_key())
@@ -221,17 +690,166 @@ impl GatewayService {
             .map(|entry| entry.get().clone())
     }
 
-    /// Collect all unique backend target URLs defined in the set of routes.
-    pub fn collect_backends(routes: &StdHashMap<String, RouteConfigEntry>) -> Vec<String> {
-        let mut backends = routes
-            .values()
-            .flat_map(|entry| {
-                entry.iter().flat_map(|route_config| match route_config {
-                    RouteConfig::LoadBalance { targets, .. } => targets.clone(),
-                    RouteConfig::Proxy { target, .. } => vec![target.clone()],
-                    _ => Vec::new(),
-                })
+    /// Fetch the canary state for a route, if the route defines a canary group.
+    pub fn get_canary(&self, route_prefix: &str, host: Option<&str>) -> Option<Arc<CanaryState>> {
+        let key = RouteKey::new(route_prefix.to_string(), host.map(|h| h.to_string()));
+        self.canaries
+            .read_sync(&key.to_rate_limiter_key(), |_, state| state.clone())
+    }
+
+    /// Fetch the duplicate suppression window for a route, if configured.
+    pub fn get_dedupe_window(
+        &self,
+        route_prefix: &str,
+        host: Option<&str>,
+    ) -> Option<Arc<DedupeWindow>> {
+        let key = RouteKey::new(route_prefix.to_string(), host.map(|h| h.to_string()));
+        self.dedupe_windows
+            .read_sync(&key.to_rate_limiter_key(), |_, window| window.clone())
+    }
+
+    /// Fetch the request coalescer for a route, if configured.
+    pub fn get_coalescer(&self, route_prefix: &str, host: Option<&str>) -> Option<Arc<Coalescer>> {
+        let key = RouteKey::new(route_prefix.to_string(), host.map(|h| h.to_string()));
+        self.coalescers
+            .read_sync(&key.to_rate_limiter_key(), |_, coalescer| coalescer.clone())
+    }
+
+    /// Fetch the retry budget for a route, if the route retries.
+    pub fn get_retry_budget(
+        &self,
+        route_prefix: &str,
+        host: Option<&str>,
+    ) -> Option<Arc<RetryBudget>> {
+        let key = RouteKey::new(route_prefix.to_string(), host.map(|h| h.to_string()));
+        self.retry_budgets
+            .read_sync(&key.to_rate_limiter_key(), |_, budget| budget.clone())
+    }
+
+    /// Fetch the hedging state for a load-balanced route, if any.
+    pub fn get_hedger(&self, route_prefix: &str, host: Option<&str>) -> Option<Arc<Hedger>> {
+        let key = RouteKey::new(route_prefix.to_string(), host.map(|h| h.to_string()));
+        self.hedgers
+            .read_sync(&key.to_rate_limiter_key(), |_, hedger| hedger.clone())
+    }
+
+    /// Fetch the compiled header and body actions for a route, if any.
+    pub fn get_route_transforms(
+        &self,
+        route_prefix: &str,
+        host: Option<&str>,
+    ) -> Option<Arc<RouteTransforms>> {
+        let key = RouteKey::new(route_prefix.to_string(), host.map(|h| h.to_string()));
+        self.transforms
+            .read_sync(&key.to_rate_limiter_key(), |_, transforms| {
+                transforms.clone()
             })
+    }
+
+    /// Fetch the compiled schedule for a route, if it has one.
+    pub fn get_route_schedule(
+        &self,
+        route_prefix: &str,
+        host: Option<&str>,
+    ) -> Option<Arc<RouteSchedule>> {
+        let key = RouteKey::new(route_prefix.to_string(), host.map(|h| h.to_string()));
+        self.schedules
+            .read_sync(&key.to_rate_limiter_key(), |_, schedule| schedule.clone())
+    }
+
+    /// Whether a route's schedule currently switches it off
+    fn is_scheduled_off(&self, route_prefix: &str, host: Option<&str>) -> bool {
+        self.get_route_schedule(route_prefix, host)
+            .is_some_and(|schedule| !schedule.is_active())
+    }
+
+    /// Fetch the request limiter for a backend listed in `backend_limits`.
+    pub fn get_backend_limiter(&self, backend: &str) -> Option<Arc<BackendLimiter>> {
+        self.backend_limiters
+            .read_sync(backend, |_, limiter| limiter.clone())
+    }
+
+    /// `(backend, in_flight, pending, max_requests)` for every limited
+    /// backend, sorted by backend.
+    pub fn backend_limiter_stats(&self) -> Vec<(String, usize, usize, usize)> {
+        let mut stats = Vec::with_capacity(self.backend_limiters.len());
+        self.backend_limiters.iter_sync(|backend, limiter| {
+            stats.push((
+                backend.clone(),
+                limiter.in_flight(),
+                limiter.pending(),
+                limiter.max_requests(),
+            ));
+            true
+        });
+        stats.sort();
+        stats
+    }
+
+    /// Token cancelled when a reload removes `backend` and its removal policy
+    /// says in-flight traffic must stop.
+    pub fn backend_cancellation(&self, backend: &str) -> Option<CancellationToken> {
+        self.removal_tokens
+            .read_sync(backend, |_, token| token.clone())
+    }
+
+    /// Apply `policy` to backends this service routes to that `next` does
+    /// not, returning them sorted.
+    ///
+    /// A deadline is enforced by a task holding only the cancellation
+    /// tokens, so the retired service and its health and limiter state are
+    /// freed as soon as in-flight requests release it.
+    pub fn retire_removed_backends(
+        &self,
+        next: &GatewayService,
+        policy: &BackendRemovalConfig,
+    ) -> Vec<String> {
+        let mut removed = Vec::new();
+        let mut tokens = Vec::new();
+        self.removal_tokens.iter_sync(|backend, token| {
+            if !next.removal_tokens.contains_sync(backend) {
+                removed.push(backend.clone());
+                tokens.push(token.clone());
+            }
+            true
+        });
+        removed.sort();
+
+        for backend in &removed {
+            let in_flight = self
+                .backend_health
+                .read_sync(backend, |_, health| health.active_connections())
+                .unwrap_or_default();
+            tracing::info!(
+                backend = %backend,
+                in_flight,
+                mode = ?policy.mode,
+                "backend removed by reload"
+            );
+        }
+
+        match policy.mode {
+            BackendRemovalMode::Complete => {}
+            BackendRemovalMode::Fail => tokens.iter().for_each(CancellationToken::cancel),
+            BackendRemovalMode::Deadline if !tokens.is_empty() => {
+                let deadline = Duration::from_secs(policy.deadline_secs);
+                tokio::spawn(async move {
+                    tokio::time::sleep(deadline).await;
+                    tokens.iter().for_each(CancellationToken::cancel);
+                });
+            }
+            BackendRemovalMode::Deadline => {}
+        }
+        removed
+    }
+
+    /// Collect all unique backend target URLs defined in the configuration's
+    /// routes, including the default route.
+    pub fn collect_backends(config: &ServerConfig) -> Vec<String> {
+        let mut backends = config
+            .all_routes()
+            .flat_map(|(_, route_config)| Self::route_backends(route_config))
+            .cloned()
             .collect::<Vec<_>>();
 
         backends.sort();
@@ -239,6 +857,48 @@ impl GatewayService {
         backends
     }
 
+    /// Every backend a proxy or load-balance route can forward to
+    fn route_backends(route_config: &RouteConfig) -> Vec<&String> {
+        match route_config {
+            RouteConfig::LoadBalance {
+                targets,
+                canary,
+                locale,
+                fallback,
+                schedule,
+                ..
+            } => targets
+                .iter()
+                .chain(canary.iter().flat_map(|c| c.targets.iter()))
+                .chain(locale.iter().flat_map(|l| l.groups.values().flatten()))
+                .chain(fallback.iter().flat_map(|f| f.targets.iter()))
+                .chain(
+                    schedule
+                        .iter()
+                        .flat_map(|s| &s.windows)
+                        .flat_map(|w| &w.targets),
+                )
+                .collect(),
+            RouteConfig::Proxy {
+                target,
+                locale,
+                fallback,
+                schedule,
+                ..
+            } => std::iter::once(target)
+                .chain(locale.iter().flat_map(|l| l.groups.values().flatten()))
+                .chain(fallback.iter().flat_map(|f| f.targets.iter()))
+                .chain(
+                    schedule
+                        .iter()
+                        .flat_map(|s| &s.windows)
+                        .flat_map(|w| &w.targets),
+                )
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
     /// Longest‑prefix match to find a route configuration for an incoming path.
     /// If host is provided, will also filter by matching host header.
     /// Routes with host specified take priority over routes without host.
@@ -247,27 +907,41 @@ impl GatewayService {
         path: &str,
         host: Option<&str>,
     ) -> Option<(String, RouteConfig)> {
-        // 1. Try to find a match in the host-specific router
-        if let Some(req_host) = host
-            && let Some(router) = self.host_routers.get(&req_host.to_lowercase())
-            && let Ok(match_) = router.at(path)
-        {
-            let prefix = match_.value;
-            if let Some(entry) = self.config.routes.get(prefix) {
-                // Find the route with matching host
-                for route_config in entry.iter() {
-                    let route_host = match route_config {
-                        RouteConfig::Static { host, .. } => host,
-                        RouteConfig::Redirect { host, .. } => host,
-                        RouteConfig::Proxy { host, .. } => host,
-                        RouteConfig::LoadBalance { host, .. } => host,
-                        RouteConfig::Websocket { host, .. } => host,
-                    };
-                    if route_host
-                        .as_ref()
-                        .is_some_and(|h| h.eq_ignore_ascii_case(req_host))
-                    {
-                        return Some((prefix.clone(), route_config.clone()));
+        if !self.ranked_routes.is_empty() {
+            return self.find_ranked_route(path, host);
+        }
+
+        // 1. Try the host-specific routers, exact hostname before wildcards
+        if let Some(req_host) = host {
+            let vhost = self.virtual_hosts.get(req_host);
+            for candidate in host_candidates(&req_host.to_lowercase()) {
+                if !self.pattern_serves(&candidate, vhost) {
+                    continue;
+                }
+                let Some(router) = self.host_routers.get(&candidate) else {
+                    continue;
+                };
+                let Ok(match_) = router.at(path) else {
+                    continue;
+                };
+                let prefix = match_.value;
+                if let Some(entry) = self.config.routes.get(prefix) {
+                    // Find the route with matching host
+                    for route_config in entry.iter() {
+                        let route_host = match route_config {
+                            RouteConfig::Static { host, .. } => host,
+                            RouteConfig::Redirect { host, .. } => host,
+                            RouteConfig::Proxy { host, .. } => host,
+                            RouteConfig::LoadBalance { host, .. } => host,
+                            RouteConfig::Websocket { host, .. } => host,
+                        };
+                        if route_host
+                            .as_ref()
+                            .is_some_and(|h| h.eq_ignore_ascii_case(&candidate))
+                            && !self.is_scheduled_off(prefix, route_host.as_deref())
+                        {
+                            return Some((prefix.clone(), route_config.clone()));
+                        }
                     }
                 }
             }
@@ -286,7 +960,7 @@ impl GatewayService {
                         RouteConfig::LoadBalance { host, .. } => host,
                         RouteConfig::Websocket { host, .. } => host,
                     };
-                    if route_host.is_none() {
+                    if route_host.is_none() && !self.is_scheduled_off(prefix, None) {
                         return Some((prefix.clone(), route_config.clone()));
                     }
                 }
@@ -296,6 +970,61 @@ impl GatewayService {
         None
     }
 
+    /// Route matching once priorities are configured: the matching route with
+    /// the highest priority wins, and equal priorities fall back to the usual
+    /// order of exact host, wildcard host, any host, then longest prefix.
+    fn find_ranked_route(&self, path: &str, host: Option<&str>) -> Option<(String, RouteConfig)> {
+        let vhost = host.and_then(|h| self.virtual_hosts.get(h));
+        let candidates: Vec<String> = host
+            .map(|h| host_candidates(&h.to_lowercase()).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|candidate| self.pattern_serves(candidate, vhost))
+            .collect();
+        let best = self
+            .ranked_routes
+            .iter()
+            .filter_map(|route| {
+                let host_rank = match &route.host {
+                    Some(h) => candidates.iter().position(|c| c == h)?,
+                    None => candidates.len(),
+                };
+                route.matcher.at(path).ok()?;
+                if self.is_scheduled_off(&route.prefix, route.host.as_deref()) {
+                    return None;
+                }
+                Some((
+                    (route.priority, Reverse(host_rank), route.prefix.len()),
+                    route,
+                ))
+            })
+            .max_by_key(|(rank, _)| *rank)?
+            .1;
+        let route_config = self
+            .config
+            .routes
+            .get(&best.prefix)?
+            .as_slice()
+            .get(best.index)?;
+        Some((best.prefix.clone(), route_config.clone()))
+    }
+
+    /// Whether routes for the hostname pattern `pattern` may serve a request
+    /// of the virtual host `vhost`. A virtual host's patterns serve only its
+    /// own requests, so its WAF and logging settings cover every route that
+    /// answers them.
+    fn pattern_serves(&self, pattern: &str, vhost: Option<&String>) -> bool {
+        self.virtual_hosts
+            .get_pattern(pattern)
+            .is_none_or(|owner| Some(owner) == vhost)
+    }
+
+    /// The route for requests [`Self::find_matching_route`] finds no route
+    /// for, served under [`crate::config::DEFAULT_ROUTE_PREFIX`]
+    pub fn default_route(&self) -> Option<&RouteConfig> {
+        self.config.default_route.as_deref()
+    }
+
     /// Return the global health check configuration.
     pub fn health_config(&self) -> &HealthCheckConfig {
         &self.config.health_check
@@ -310,6 +1039,37 @@ impl GatewayService {
             .unwrap_or_else(|| self.config.health_check.path.clone())
     }
 
+    /// Host, TLS and credential settings for probing a backend, if any are
+    /// configured in `[backend_health_checks]`
+    pub fn backend_health_check(&self, target: &str) -> Option<&BackendHealthCheckConfig> {
+        self.config.backend_health_checks.get(target)
+    }
+
+    /// The `upstream_tls` of a route forwarding to `target`; with several,
+    /// the one with the first prefix in sort order
+    pub fn backend_upstream_tls(&self, target: &str) -> Option<&UpstreamTlsConfig> {
+        self.config
+            .all_routes()
+            .filter_map(|(prefix, route_config)| match route_config {
+                RouteConfig::Proxy {
+                    upstream_tls: Some(tls),
+                    ..
+                }
+                | RouteConfig::LoadBalance {
+                    upstream_tls: Some(tls),
+                    ..
+                } if Self::route_backends(route_config)
+                    .iter()
+                    .any(|backend| *backend == target) =>
+                {
+                    Some((prefix, tls))
+                }
+                _ => None,
+            })
+            .min_by_key(|(prefix, _)| *prefix)
+            .map(|(_, tls)| tls)
+    }
+
     /// Return the last observed health status for a backend (Healthy if unknown / untracked).
     pub async fn get_backend_health_status(&self, target: &str) -> HealthStatus {
         self.backend_health
@@ -360,16 +1120,56 @@ impl GatewayService {
 
     /// Select a backend from a set of (already matched) targets. Applies health filtering
     /// then a simple static round‑robin counter.
+    ///
+    /// With a `slow_start` window, backends that recently recovered receive a
+    /// share of traffic that grows linearly over the window. Steering weights
+    /// scale each backend's share on top of that; if every weight is zero the
+    /// healthy backends are used as if no weights were set.
     pub async fn select_backend(
         &self,
         targets: &[String],
         strategy: Option<crate::config::LoadBalanceStrategy>,
+        slow_start: Option<Duration>,
     ) -> Option<String> {
         let healthy_backends = self.get_healthy_backends(targets).await;
         if healthy_backends.is_empty() {
             return None;
         }
 
+        let now = Instant::now();
+        let mut weights = Vec::with_capacity(healthy_backends.len());
+        for backend in &healthy_backends {
+            let slow_start_factor = match slow_start {
+                Some(window) => self
+                    .backend_health
+                    .read_async(backend, |_, health| health.slow_start_factor(window, now))
+                    .await
+                    .unwrap_or(1.0),
+                None => 1.0,
+            };
+            let steering_weight = self.steering.weight(backend, now).unwrap_or(1.0);
+            weights.push(slow_start_factor * steering_weight);
+        }
+        if weights.iter().any(|w| (w - 1.0).abs() > f64::EPSILON) {
+            let selected = match strategy {
+                Some(crate::config::LoadBalanceStrategy::LeastConnections) => {
+                    let mut loads = Vec::with_capacity(healthy_backends.len());
+                    for backend in &healthy_backends {
+                        let conns = self
+                            .backend_health
+                            .read_async(backend, |_, health| health.active_connections())
+                            .await;
+                        loads.push(conns.unwrap_or_default());
+                    }
+                    select_least_loaded(&healthy_backends, &loads, &weights)
+                }
+                _ => select_weighted(&healthy_backends, &weights),
+            };
+            if selected.is_some() {
+                return selected;
+            }
+        }
+
         match strategy.unwrap_or(crate::config::LoadBalanceStrategy::RoundRobin) {
             crate::config::LoadBalanceStrategy::RoundRobin => {
                 use std::sync::atomic::{AtomicUsize, Ordering};
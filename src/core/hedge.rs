@@ -0,0 +1,133 @@
+//! Hedged requests for load-balanced routes.
+//!
+//! A [`Hedger`] keeps the latencies of a route's recent responses. When a
+//! request has waited longer than their configured percentile, the gateway
+//! sends it to a second backend and uses whichever answers first. Hedges are
+//! taken from a [`RetryBudget`], so a route whose backends are all slow does
+//! not double its load.
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{config::models::HedgeConfig, core::retry::RetryBudget};
+
+/// Recent latencies and hedge budget of one route.
+#[derive(Debug)]
+pub struct Hedger {
+    percentile: f64,
+    min_delay: Duration,
+    max_delay: Duration,
+    min_samples: usize,
+    window: usize,
+    max_body_bytes: usize,
+    latencies: Mutex<VecDeque<Duration>>,
+    budget: RetryBudget,
+}
+
+impl Hedger {
+    pub fn new(config: &HedgeConfig) -> Self {
+        let window = config.window.max(1);
+        Self {
+            percentile: config.percentile.clamp(0.0, 100.0),
+            min_delay: Duration::from_millis(config.min_delay_ms),
+            max_delay: Duration::from_millis(config.max_delay_ms.max(config.min_delay_ms)),
+            min_samples: config.min_samples,
+            window,
+            max_body_bytes: config.max_body_bytes,
+            latencies: Mutex::new(VecDeque::with_capacity(window)),
+            budget: RetryBudget::new(&config.budget),
+        }
+    }
+
+    /// Largest request body that is kept for a hedge.
+    pub fn max_body_bytes(&self) -> usize {
+        self.max_body_bytes
+    }
+
+    /// Record how long a response took, counting it towards the percentile.
+    pub fn record_latency(&self, latency: Duration) {
+        let mut latencies = self.latencies.lock().expect("hedger lock poisoned");
+        if latencies.len() == self.window {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    /// How long to wait for the first backend before hedging.
+    pub fn delay(&self) -> Duration {
+        let mut latencies: Vec<Duration> = {
+            let latencies = self.latencies.lock().expect("hedger lock poisoned");
+            if latencies.is_empty() || latencies.len() < self.min_samples {
+                return self.max_delay;
+            }
+            latencies.iter().copied().collect()
+        };
+        let rank = ((self.percentile / 100.0) * latencies.len() as f64).ceil() as usize;
+        let index = rank.clamp(1, latencies.len()) - 1;
+        let (_, latency, _) = latencies.select_nth_unstable(index);
+        (*latency).clamp(self.min_delay, self.max_delay)
+    }
+
+    /// Count a request that may be hedged.
+    pub fn record_request(&self, now: Instant) {
+        self.budget.record_request(now);
+    }
+
+    /// Take a hedge from the budget; returns `false` when it is used up.
+    pub fn try_hedge(&self, now: Instant) -> bool {
+        self.budget.try_retry(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::models::RetryBudgetConfig;
+
+    fn hedger(min_samples: usize) -> Hedger {
+        Hedger::new(&HedgeConfig {
+            percentile: 90.0,
+            min_delay_ms: 5,
+            max_delay_ms: 500,
+            min_samples,
+            window: 10,
+            budget: RetryBudgetConfig {
+                ratio: 0.0,
+                min_retries: 1,
+                window_secs: 10,
+            },
+            ..HedgeConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_delay_follows_percentile() {
+        let hedger = hedger(5);
+        assert_eq!(hedger.delay(), Duration::from_millis(500));
+        for ms in 1..=10 {
+            hedger.record_latency(Duration::from_millis(ms * 10));
+        }
+        assert_eq!(hedger.delay(), Duration::from_millis(90));
+
+        // Old samples leave the window
+        for _ in 0..10 {
+            hedger.record_latency(Duration::from_millis(1));
+        }
+        assert_eq!(hedger.delay(), Duration::from_millis(5));
+        for _ in 0..10 {
+            hedger.record_latency(Duration::from_secs(2));
+        }
+        assert_eq!(hedger.delay(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_hedges_use_the_budget() {
+        let hedger = hedger(0);
+        let now = Instant::now();
+        hedger.record_request(now);
+        assert!(hedger.try_hedge(now));
+        assert!(!hedger.try_hedge(now));
+    }
+}
@@ -0,0 +1,248 @@
+//! htpasswd password verification
+//!
+//! Parses `user:hash` lines as written by Apache `htpasswd` and verifies
+//! passwords against bcrypt (`$2a$`/`$2b$`/`$2y$`, from `htpasswd -B`) and
+//! APR1 MD5-crypt (`$apr1$`, from `htpasswd -m`) hashes. Comparisons are
+//! constant-time, and unknown users cost as much to reject as a wrong
+//! password, so response timing does not reveal which users exist.
+
+use std::{collections::HashMap, str::FromStr};
+
+use md5::{Digest, Md5};
+use thiserror::Error;
+
+use crate::core::secret::constant_time_eq;
+
+/// A hash in a format that cannot be verified
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Unsupported password hash (expected bcrypt or $apr1$)")]
+pub struct UnsupportedHash;
+
+/// An htpasswd file that could not be parsed
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Line {line}: {message}")]
+pub struct HtpasswdError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// A stored password hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordHash {
+    Bcrypt(String),
+    Apr1 { salt: String, hash: String },
+}
+
+impl FromStr for PasswordHash {
+    type Err = UnsupportedHash;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(rest) = value.strip_prefix("$apr1$") {
+            let (salt, hash) = rest.split_once('$').ok_or(UnsupportedHash)?;
+            if salt.is_empty() || salt.len() > 8 || hash.len() != 22 {
+                return Err(UnsupportedHash);
+            }
+            return Ok(Self::Apr1 {
+                salt: salt.to_string(),
+                hash: value.to_string(),
+            });
+        }
+        bcrypt::HashParts::from_str(value).map_err(|_| UnsupportedHash)?;
+        Ok(Self::Bcrypt(value.to_string()))
+    }
+}
+
+impl PasswordHash {
+    /// Whether `password` matches the hash.
+    pub fn verify(&self, password: &str) -> bool {
+        match self {
+            Self::Bcrypt(hash) => bcrypt::verify(password, hash).unwrap_or(false),
+            Self::Apr1 { salt, hash } => {
+                constant_time_eq(apr1(password.as_bytes(), salt.as_bytes()), hash)
+            }
+        }
+    }
+}
+
+/// Users and their password hashes
+#[derive(Debug, Clone, Default)]
+pub struct Htpasswd {
+    users: HashMap<String, PasswordHash>,
+}
+
+impl Htpasswd {
+    /// Parse htpasswd file contents; blank lines and `#` comments are skipped.
+    pub fn parse(contents: &str) -> Result<Self, HtpasswdError> {
+        let mut htpasswd = Self::default();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| HtpasswdError {
+                line: index + 1,
+                message,
+            };
+            let (user, hash) = line
+                .split_once(':')
+                .ok_or_else(|| error("expected 'user:hash'".to_string()))?;
+            let hash = hash
+                .parse()
+                .map_err(|e: UnsupportedHash| error(e.to_string()))?;
+            htpasswd.insert(user, hash);
+        }
+        Ok(htpasswd)
+    }
+
+    /// Add or replace a user.
+    pub fn insert(&mut self, user: &str, hash: PasswordHash) {
+        self.users.insert(user.to_string(), hash);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    /// Whether `user` exists and `password` matches their hash.
+    pub fn verify(&self, user: &str, password: &str) -> bool {
+        match self.users.get(user) {
+            Some(hash) => hash.verify(password),
+            None => {
+                // Spend the same effort as for a known user
+                if let Some(hash) = self.users.values().next() {
+                    hash.verify(password);
+                }
+                false
+            }
+        }
+    }
+}
+
+/// APR1 MD5-crypt, as implemented by Apache's `apr_md5_encode`.
+fn apr1(password: &[u8], salt: &[u8]) -> String {
+    const MAGIC: &[u8] = b"$apr1$";
+    const ITOA64: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+    let alternate = Md5::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+    let mut ctx = Md5::new()
+        .chain_update(password)
+        .chain_update(MAGIC)
+        .chain_update(salt);
+    for chunk in password.chunks(16) {
+        ctx.update(&alternate[..chunk.len()]);
+    }
+    let mut length = password.len();
+    while length > 0 {
+        if length & 1 == 1 {
+            ctx.update([0u8]);
+        } else {
+            ctx.update(&password[..1]);
+        }
+        length >>= 1;
+    }
+    let mut digest = ctx.finalize();
+
+    for round in 0..1000 {
+        let mut ctx = Md5::new();
+        if round % 2 == 1 {
+            ctx.update(password);
+        } else {
+            ctx.update(digest);
+        }
+        if round % 3 != 0 {
+            ctx.update(salt);
+        }
+        if round % 7 != 0 {
+            ctx.update(password);
+        }
+        if round % 2 == 1 {
+            ctx.update(digest);
+        } else {
+            ctx.update(password);
+        }
+        digest = ctx.finalize();
+    }
+
+    let mut out = String::from_utf8_lossy(MAGIC).into_owned();
+    out.push_str(&String::from_utf8_lossy(salt));
+    out.push('$');
+    let d = digest;
+    for (value, chars) in [
+        (
+            u32::from(d[0]) << 16 | u32::from(d[6]) << 8 | u32::from(d[12]),
+            4,
+        ),
+        (
+            u32::from(d[1]) << 16 | u32::from(d[7]) << 8 | u32::from(d[13]),
+            4,
+        ),
+        (
+            u32::from(d[2]) << 16 | u32::from(d[8]) << 8 | u32::from(d[14]),
+            4,
+        ),
+        (
+            u32::from(d[3]) << 16 | u32::from(d[9]) << 8 | u32::from(d[15]),
+            4,
+        ),
+        (
+            u32::from(d[4]) << 16 | u32::from(d[10]) << 8 | u32::from(d[5]),
+            4,
+        ),
+        (u32::from(d[11]), 2),
+    ] {
+        let mut value = value;
+        for _ in 0..chars {
+            out.push(ITOA64[(value & 0x3f) as usize] as char);
+            value >>= 6;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_hashes() {
+        // From the Apache htpasswd documentation
+        let apr1: PasswordHash = "$apr1$r31.....$HqJZimcKQFAMYayBlzkrA/".parse().unwrap();
+        assert!(apr1.verify("myPassword"));
+        assert!(!apr1.verify("mypassword"));
+
+        let bcrypt: PasswordHash = bcrypt::hash("s3cret", 4).unwrap().parse().unwrap();
+        assert!(bcrypt.verify("s3cret"));
+        assert!(!bcrypt.verify("s3cret "));
+
+        for unsupported in [
+            "plain",
+            "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=",
+            "$1$abc$def",
+            "$apr1$x",
+        ] {
+            assert_eq!(unsupported.parse::<PasswordHash>(), Err(UnsupportedHash));
+        }
+    }
+
+    #[test]
+    fn test_parse_htpasswd() {
+        let contents = format!(
+            "# users\n\nalice:$apr1$r31.....$HqJZimcKQFAMYayBlzkrA/\nbob:{}\n",
+            bcrypt::hash("hunter2", 4).unwrap()
+        );
+        let htpasswd = Htpasswd::parse(&contents).unwrap();
+        assert!(htpasswd.verify("alice", "myPassword"));
+        assert!(htpasswd.verify("bob", "hunter2"));
+        assert!(!htpasswd.verify("bob", "myPassword"));
+        assert!(!htpasswd.verify("carol", "myPassword"));
+
+        let err =
+            Htpasswd::parse("alice:$apr1$r31.....$HqJZimcKQFAMYayBlzkrA/\nbob\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(Htpasswd::parse("carol:plaintext").is_err());
+    }
+}
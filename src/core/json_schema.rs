@@ -0,0 +1,552 @@
+//! JSON Schema validation
+//!
+//! A small validator for the commonly used subset of JSON Schema (draft
+//! 2020-12 keyword semantics): `type`, `enum`, `const`, `properties`,
+//! `required`, `additionalProperties`, `items`, `minItems`, `maxItems`,
+//! `minLength`, `maxLength`, `pattern`, `minimum`, `maximum`,
+//! `exclusiveMinimum`, `exclusiveMaximum`, `allOf`, `anyOf`, `oneOf` and
+//! `not`. Annotation keywords (`title`, `description`, `$schema`, ...) are
+//! ignored; `$ref` is rejected when the schema is compiled, since silently
+//! skipping it would accept anything.
+
+use regex::Regex;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// A schema that could not be compiled
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Invalid schema at '{path}': {message}")]
+pub struct SchemaError {
+    /// JSON pointer to the offending keyword
+    pub path: String,
+    pub message: String,
+}
+
+/// A place where a document does not match its schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// JSON pointer into the document (empty for the root)
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{path}: {}", self.message)
+    }
+}
+
+/// A compiled schema, ready to validate documents
+#[derive(Debug)]
+pub struct JsonSchema {
+    root: Node,
+}
+
+impl JsonSchema {
+    /// Compile a schema document.
+    pub fn compile(schema: &Value) -> Result<Self, SchemaError> {
+        Ok(Self {
+            root: Node::compile(schema, "")?,
+        })
+    }
+
+    /// Check `document` against the schema, listing every violation found.
+    pub fn validate(&self, document: &Value) -> Result<(), Vec<SchemaViolation>> {
+        let mut violations = Vec::new();
+        self.root.check(document, "", &mut violations);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    /// `true` accepts everything, `false` nothing
+    Bool(bool),
+    Keywords(Box<Keywords>),
+}
+
+#[derive(Debug, Default)]
+struct Keywords {
+    types: Option<Vec<String>>,
+    enum_values: Option<Vec<Value>>,
+    constant: Option<Value>,
+    properties: Vec<(String, Node)>,
+    required: Vec<String>,
+    additional_properties: Option<Node>,
+    items: Option<Node>,
+    min_items: Option<u64>,
+    max_items: Option<u64>,
+    min_length: Option<u64>,
+    max_length: Option<u64>,
+    pattern: Option<Regex>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    all_of: Vec<Node>,
+    any_of: Vec<Node>,
+    one_of: Vec<Node>,
+    not: Option<Node>,
+}
+
+const TYPES: &[&str] = &[
+    "null", "boolean", "object", "array", "number", "integer", "string",
+];
+
+fn schema_error(path: &str, message: impl Into<String>) -> SchemaError {
+    SchemaError {
+        path: if path.is_empty() {
+            "/".to_string()
+        } else {
+            path.to_string()
+        },
+        message: message.into(),
+    }
+}
+
+/// Append a key or index to a JSON pointer.
+fn pointer(path: &str, key: &str) -> String {
+    format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+impl Node {
+    fn compile(schema: &Value, path: &str) -> Result<Self, SchemaError> {
+        let object = match schema {
+            Value::Bool(accept) => return Ok(Node::Bool(*accept)),
+            Value::Object(object) => object,
+            _ => return Err(schema_error(path, "schema must be an object or a boolean")),
+        };
+        if object.contains_key("$ref") {
+            return Err(schema_error(
+                &pointer(path, "$ref"),
+                "$ref is not supported",
+            ));
+        }
+
+        let mut keywords = Keywords::default();
+        for (key, value) in object {
+            let at = pointer(path, key);
+            match key.as_str() {
+                "type" => keywords.types = Some(compile_types(value, &at)?),
+                "enum" => {
+                    let values = value
+                        .as_array()
+                        .ok_or_else(|| schema_error(&at, "must be an array"))?;
+                    keywords.enum_values = Some(values.clone());
+                }
+                "const" => keywords.constant = Some(value.clone()),
+                "properties" => {
+                    for (name, schema) in as_object(value, &at)? {
+                        let node = Node::compile(schema, &pointer(&at, name))?;
+                        keywords.properties.push((name.clone(), node));
+                    }
+                }
+                "required" => {
+                    keywords.required = value
+                        .as_array()
+                        .and_then(|names| {
+                            names
+                                .iter()
+                                .map(|name| name.as_str().map(str::to_string))
+                                .collect()
+                        })
+                        .ok_or_else(|| schema_error(&at, "must be an array of strings"))?;
+                }
+                "additionalProperties" => {
+                    keywords.additional_properties = Some(Node::compile(value, &at)?)
+                }
+                "items" => keywords.items = Some(Node::compile(value, &at)?),
+                "minItems" => keywords.min_items = Some(as_count(value, &at)?),
+                "maxItems" => keywords.max_items = Some(as_count(value, &at)?),
+                "minLength" => keywords.min_length = Some(as_count(value, &at)?),
+                "maxLength" => keywords.max_length = Some(as_count(value, &at)?),
+                "pattern" => {
+                    let pattern = value
+                        .as_str()
+                        .ok_or_else(|| schema_error(&at, "must be a string"))?;
+                    let regex = Regex::new(pattern)
+                        .map_err(|e| schema_error(&at, format!("invalid pattern: {e}")))?;
+                    keywords.pattern = Some(regex);
+                }
+                "minimum" => keywords.minimum = Some(as_number(value, &at)?),
+                "maximum" => keywords.maximum = Some(as_number(value, &at)?),
+                "exclusiveMinimum" => keywords.exclusive_minimum = Some(as_number(value, &at)?),
+                "exclusiveMaximum" => keywords.exclusive_maximum = Some(as_number(value, &at)?),
+                "allOf" => keywords.all_of = compile_list(value, &at)?,
+                "anyOf" => keywords.any_of = compile_list(value, &at)?,
+                "oneOf" => keywords.one_of = compile_list(value, &at)?,
+                "not" => keywords.not = Some(Node::compile(value, &at)?),
+                _ => {}
+            }
+        }
+        Ok(Node::Keywords(Box::new(keywords)))
+    }
+
+    fn accepts(&self, value: &Value) -> bool {
+        let mut violations = Vec::new();
+        self.check(value, "", &mut violations);
+        violations.is_empty()
+    }
+
+    fn check(&self, value: &Value, path: &str, out: &mut Vec<SchemaViolation>) {
+        let keywords = match self {
+            Node::Bool(true) => return,
+            Node::Bool(false) => return violation(out, path, "no value is allowed here"),
+            Node::Keywords(keywords) => keywords,
+        };
+
+        if let Some(types) = &keywords.types
+            && !types.iter().any(|name| has_type(value, name))
+        {
+            violation(out, path, format!("expected {}", types.join(" or ")));
+        }
+        if let Some(values) = &keywords.enum_values
+            && !values.iter().any(|allowed| json_equal(allowed, value))
+        {
+            violation(out, path, "value is not one of the allowed values");
+        }
+        if let Some(constant) = &keywords.constant
+            && !json_equal(constant, value)
+        {
+            violation(out, path, format!("expected {constant}"));
+        }
+
+        match value {
+            Value::Object(object) => keywords.check_object(object, path, out),
+            Value::Array(items) => keywords.check_array(items, path, out),
+            Value::String(text) => keywords.check_string(text, path, out),
+            Value::Number(number) => {
+                if let Some(number) = number.as_f64() {
+                    keywords.check_number(number, path, out);
+                }
+            }
+            _ => {}
+        }
+
+        for schema in &keywords.all_of {
+            schema.check(value, path, out);
+        }
+        if !keywords.any_of.is_empty() && !keywords.any_of.iter().any(|s| s.accepts(value)) {
+            violation(out, path, "value matches none of the anyOf schemas");
+        }
+        if !keywords.one_of.is_empty() {
+            let matched = keywords.one_of.iter().filter(|s| s.accepts(value)).count();
+            if matched != 1 {
+                violation(
+                    out,
+                    path,
+                    format!("value matches {matched} of the oneOf schemas, expected exactly 1"),
+                );
+            }
+        }
+        if let Some(not) = &keywords.not
+            && not.accepts(value)
+        {
+            violation(out, path, "value matches a schema it must not match");
+        }
+    }
+}
+
+impl Keywords {
+    fn check_object(
+        &self,
+        object: &Map<String, Value>,
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        for name in &self.required {
+            if !object.contains_key(name) {
+                violation(out, path, format!("missing required property '{name}'"));
+            }
+        }
+        for (name, value) in object {
+            let at = pointer(path, name);
+            match self
+                .properties
+                .iter()
+                .find(|(declared, _)| declared == name)
+            {
+                Some((_, schema)) => schema.check(value, &at, out),
+                None => {
+                    if let Some(schema) = &self.additional_properties {
+                        if matches!(schema, Node::Bool(false)) {
+                            violation(out, &at, "additional property is not allowed");
+                        } else {
+                            schema.check(value, &at, out);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_array(&self, items: &[Value], path: &str, out: &mut Vec<SchemaViolation>) {
+        let len = items.len() as u64;
+        if let Some(min) = self.min_items
+            && len < min
+        {
+            violation(out, path, format!("expected at least {min} items"));
+        }
+        if let Some(max) = self.max_items
+            && len > max
+        {
+            violation(out, path, format!("expected at most {max} items"));
+        }
+        if let Some(schema) = &self.items {
+            for (index, item) in items.iter().enumerate() {
+                schema.check(item, &pointer(path, &index.to_string()), out);
+            }
+        }
+    }
+
+    fn check_string(&self, text: &str, path: &str, out: &mut Vec<SchemaViolation>) {
+        let len = text.chars().count() as u64;
+        if let Some(min) = self.min_length
+            && len < min
+        {
+            violation(out, path, format!("expected at least {min} characters"));
+        }
+        if let Some(max) = self.max_length
+            && len > max
+        {
+            violation(out, path, format!("expected at most {max} characters"));
+        }
+        if let Some(pattern) = &self.pattern
+            && !pattern.is_match(text)
+        {
+            violation(out, path, format!("does not match pattern '{pattern}'"));
+        }
+    }
+
+    fn check_number(&self, number: f64, path: &str, out: &mut Vec<SchemaViolation>) {
+        if let Some(min) = self.minimum
+            && number < min
+        {
+            violation(out, path, format!("must be at least {min}"));
+        }
+        if let Some(max) = self.maximum
+            && number > max
+        {
+            violation(out, path, format!("must be at most {max}"));
+        }
+        if let Some(min) = self.exclusive_minimum
+            && number <= min
+        {
+            violation(out, path, format!("must be greater than {min}"));
+        }
+        if let Some(max) = self.exclusive_maximum
+            && number >= max
+        {
+            violation(out, path, format!("must be less than {max}"));
+        }
+    }
+}
+
+fn violation(out: &mut Vec<SchemaViolation>, path: &str, message: impl Into<String>) {
+    out.push(SchemaViolation {
+        path: path.to_string(),
+        message: message.into(),
+    });
+}
+
+fn compile_types(value: &Value, path: &str) -> Result<Vec<String>, SchemaError> {
+    let names: Vec<&str> = match value {
+        Value::String(name) => vec![name.as_str()],
+        Value::Array(names) => names
+            .iter()
+            .map(Value::as_str)
+            .collect::<Option<_>>()
+            .ok_or_else(|| schema_error(path, "must be a string or an array of strings"))?,
+        _ => {
+            return Err(schema_error(
+                path,
+                "must be a string or an array of strings",
+            ));
+        }
+    };
+    names
+        .into_iter()
+        .map(|name| {
+            if TYPES.contains(&name) {
+                Ok(name.to_string())
+            } else {
+                Err(schema_error(path, format!("unknown type '{name}'")))
+            }
+        })
+        .collect()
+}
+
+fn compile_list(value: &Value, path: &str) -> Result<Vec<Node>, SchemaError> {
+    let schemas = value
+        .as_array()
+        .filter(|schemas| !schemas.is_empty())
+        .ok_or_else(|| schema_error(path, "must be a non-empty array of schemas"))?;
+    schemas
+        .iter()
+        .enumerate()
+        .map(|(index, schema)| Node::compile(schema, &pointer(path, &index.to_string())))
+        .collect()
+}
+
+fn as_object<'a>(value: &'a Value, path: &str) -> Result<&'a Map<String, Value>, SchemaError> {
+    value
+        .as_object()
+        .ok_or_else(|| schema_error(path, "must be an object"))
+}
+
+fn as_count(value: &Value, path: &str) -> Result<u64, SchemaError> {
+    value
+        .as_u64()
+        .ok_or_else(|| schema_error(path, "must be a non-negative integer"))
+}
+
+fn as_number(value: &Value, path: &str) -> Result<f64, SchemaError> {
+    value
+        .as_f64()
+        .ok_or_else(|| schema_error(path, "must be a number"))
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match (name, value) {
+        ("null", Value::Null)
+        | ("boolean", Value::Bool(_))
+        | ("object", Value::Object(_))
+        | ("array", Value::Array(_))
+        | ("number", Value::Number(_))
+        | ("string", Value::String(_)) => true,
+        ("integer", Value::Number(number)) => {
+            number.is_i64() || number.is_u64() || number.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+/// Equality where `1` and `1.0` are the same number.
+fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a == b || a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_equal(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, value)| b.get(key).is_some_and(|other| json_equal(value, other)))
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn paths(schema: &JsonSchema, document: Value) -> Vec<String> {
+        match schema.validate(&document) {
+            Ok(()) => Vec::new(),
+            Err(violations) => {
+                let mut paths: Vec<_> = violations.into_iter().map(|v| v.path).collect();
+                paths.sort();
+                paths
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_documents() {
+        let schema = JsonSchema::compile(&json!({
+            "type": "object",
+            "required": ["name", "tags"],
+            "additionalProperties": false,
+            "properties": {
+                "name": { "type": "string", "minLength": 1, "pattern": "^[a-z]+$" },
+                "age": { "type": "integer", "minimum": 0, "exclusiveMaximum": 150 },
+                "tags": { "type": "array", "maxItems": 2, "items": { "enum": ["a", "b"] } },
+                "kind": { "oneOf": [{ "const": "user" }, { "const": "bot" }] },
+                "id": { "anyOf": [{ "type": "string" }, { "type": "integer" }], "not": { "const": 0 } }
+            }
+        }))
+        .expect("schema compiles");
+
+        assert!(
+            paths(
+                &schema,
+                json!({"name": "ann", "tags": ["a"], "age": 30.0, "id": 7})
+            )
+            .is_empty()
+        );
+        assert_eq!(paths(&schema, json!({"tags": []})), vec![""]);
+        assert_eq!(paths(&schema, json!([])), vec![""]);
+        assert_eq!(
+            paths(
+                &schema,
+                json!({"name": "Ann", "tags": ["a", "c", "b"], "age": 1.5, "extra/key": 1})
+            ),
+            vec!["/age", "/extra~1key", "/name", "/tags", "/tags/1"]
+        );
+        assert_eq!(
+            paths(&schema, json!({"name": "a", "tags": [], "age": 150})),
+            vec!["/age"]
+        );
+        assert_eq!(
+            paths(&schema, json!({"name": "a", "tags": [], "kind": "cat"})),
+            vec!["/kind"]
+        );
+        assert_eq!(
+            paths(&schema, json!({"name": "a", "tags": [], "id": 0})),
+            vec!["/id"]
+        );
+        assert_eq!(
+            paths(&schema, json!({"name": "a", "tags": [], "id": true})),
+            vec!["/id"]
+        );
+
+        let message = schema.validate(&json!({"tags": []})).unwrap_err()[0].to_string();
+        assert_eq!(message, "/: missing required property 'name'");
+
+        assert!(
+            JsonSchema::compile(&json!(true))
+                .unwrap()
+                .validate(&json!(1))
+                .is_ok()
+        );
+        assert!(
+            JsonSchema::compile(&json!(false))
+                .unwrap()
+                .validate(&json!(1))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        for (schema, path) in [
+            (json!(1), "/"),
+            (json!({"type": "float"}), "/type"),
+            (
+                json!({"properties": {"a": {"pattern": "("}}}),
+                "/properties/a/pattern",
+            ),
+            (json!({"minLength": -1}), "/minLength"),
+            (json!({"anyOf": []}), "/anyOf"),
+            (json!({"items": {"$ref": "#/defs/a"}}), "/items/$ref"),
+        ] {
+            assert_eq!(
+                JsonSchema::compile(&schema).unwrap_err().path,
+                path,
+                "{schema}"
+            );
+        }
+    }
+}
@@ -79,6 +79,46 @@ impl LoadBalancingStrategy for RandomStrategy {
     }
 }
 
+/// Pick a target with probability proportional to its weight.
+///
+/// Returns `None` when no target has a positive weight.
+pub fn select_weighted(targets: &[String], weights: &[f64]) -> Option<String> {
+    let total: f64 = weights.iter().filter(|w| **w > 0.0).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut point = rand::rng().random_range(0.0..total);
+    for (target, weight) in targets.iter().zip(weights) {
+        if *weight <= 0.0 {
+            continue;
+        }
+        if point < *weight {
+            return Some(target.clone());
+        }
+        point -= weight;
+    }
+    // Floating point rounding can leave the point just past the last weight
+    targets
+        .iter()
+        .zip(weights)
+        .rev()
+        .find(|(_, w)| **w > 0.0)
+        .map(|(t, _)| t.clone())
+}
+
+/// Pick the target with the fewest active connections relative to its weight.
+///
+/// Returns `None` when no target has a positive weight.
+pub fn select_least_loaded(targets: &[String], loads: &[usize], weights: &[f64]) -> Option<String> {
+    targets
+        .iter()
+        .zip(loads.iter().zip(weights))
+        .filter(|(_, (_, weight))| **weight > 0.0)
+        .map(|(target, (load, weight))| (target, (*load as f64 + 1.0) / weight))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(target, _)| target.clone())
+}
+
 /// Factory for creating load balancing strategies from configuration values.
 pub struct LoadBalancerFactory;
 
@@ -132,6 +172,36 @@ mod tests {
         ); // Wraps around
     }
 
+    #[test]
+    fn test_weighted_selection_skips_zero_weights() {
+        let targets = vec!["warm".to_string(), "ramping".to_string()];
+
+        assert_eq!(select_weighted(&targets, &[0.0, 0.0]), None);
+        for _ in 0..20 {
+            assert_eq!(
+                select_weighted(&targets, &[1.0, 0.0]),
+                Some("warm".to_string())
+            );
+        }
+        let ramping = (0..1000)
+            .filter(|_| select_weighted(&targets, &[1.0, 0.25]).as_deref() == Some("ramping"))
+            .count();
+        assert!(
+            (100..300).contains(&ramping),
+            "ramping picked {ramping} times"
+        );
+
+        // Three connections on a fully warm backend beat one on a backend at 25%
+        assert_eq!(
+            select_least_loaded(&targets, &[3, 1], &[1.0, 0.25]),
+            Some("warm".to_string())
+        );
+        assert_eq!(
+            select_least_loaded(&targets, &[3, 0], &[1.0, 0.5]),
+            Some("ramping".to_string())
+        );
+    }
+
     #[test]
     fn test_round_robin_empty_targets() {
         let strategy = RoundRobinStrategy::new();
@@ -0,0 +1,65 @@
+//! `Accept-Language` matching for locale-based backend selection.
+
+/// Primary language subtags from an `Accept-Language` value, most preferred
+/// first. Ranges with `q=0`, wildcards and malformed entries are skipped.
+pub fn preferred_languages(accept_language: &str) -> Vec<String> {
+    let mut ranges: Vec<(String, f32)> = accept_language
+        .split(',')
+        .filter_map(|range| {
+            let mut params = range.split(';');
+            let tag = params.next()?.trim();
+            let quality = params
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let primary = tag.split(['-', '_']).next()?;
+            let valid = !primary.is_empty()
+                && primary.len() <= 8
+                && primary.chars().all(|c| c.is_ascii_alphabetic());
+            (valid && quality > 0.0).then(|| (primary.to_ascii_lowercase(), quality))
+        })
+        .collect();
+
+    // Stable sort keeps header order among equal weights
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut languages: Vec<String> = Vec::with_capacity(ranges.len());
+    for (language, _) in ranges {
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+    languages
+}
+
+/// The client's most preferred language for which `has_group` is true.
+pub fn match_language(accept_language: &str, has_group: impl Fn(&str) -> bool) -> Option<String> {
+    preferred_languages(accept_language)
+        .into_iter()
+        .find(|language| has_group(language))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preferred_languages_orders_by_quality() {
+        assert_eq!(
+            preferred_languages("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.95, *;q=0.5"),
+            vec!["fr", "de", "en"]
+        );
+        assert_eq!(preferred_languages("en;q=0, es"), vec!["es"]);
+        assert!(preferred_languages("").is_empty());
+    }
+
+    #[test]
+    fn test_match_language_falls_through_to_available_group() {
+        let groups = ["de", "fr"];
+        let has_group = |language: &str| groups.contains(&language);
+        assert_eq!(
+            match_language("en-US, de-AT;q=0.8", has_group),
+            Some("de".to_string())
+        );
+        assert_eq!(match_language("en-US, ja", has_group), None);
+    }
+}
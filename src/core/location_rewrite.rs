@@ -0,0 +1,207 @@
+//! Rewriting backend URLs in response headers.
+//!
+//! Backends behind the gateway often answer with absolute URLs naming their
+//! internal address, e.g. `Location: http://10.0.0.5:8080/login`. A
+//! [`LocationRewriter`] maps such URLs, and root-relative paths under a
+//! route's `path_rewrite`, back to the public route prefix, so redirects and
+//! `Link` relations keep working through the gateway. URLs pointing anywhere
+//! else are left alone.
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+/// How one route's backend URLs map to public ones.
+#[derive(Debug)]
+pub struct LocationRewriter<'a> {
+    /// Backend base URLs, without trailing slashes
+    origins: Vec<&'a str>,
+    /// The path the route prefix is rewritten to, if any
+    backend_prefix: Option<&'a str>,
+    route_prefix: &'a str,
+    /// Prepended to rewritten URLs, which are otherwise root-relative
+    public_url: Option<&'a str>,
+}
+
+impl<'a> LocationRewriter<'a> {
+    pub fn new(
+        origins: impl IntoIterator<Item = &'a str>,
+        route_prefix: &'a str,
+        path_rewrite: Option<&'a str>,
+        public_url: Option<&'a str>,
+    ) -> Self {
+        Self {
+            origins: origins
+                .into_iter()
+                .map(|origin| origin.trim_end_matches('/'))
+                .collect(),
+            backend_prefix: path_rewrite,
+            route_prefix,
+            public_url: public_url.map(|url| url.trim_end_matches('/')),
+        }
+    }
+
+    /// Rewrite every URL in the named headers; values that do not change,
+    /// or that are not valid afterwards, are kept.
+    pub fn apply(&self, headers: &mut HeaderMap, names: &[HeaderName]) {
+        for name in names {
+            let values: Vec<HeaderValue> = headers
+                .get_all(name)
+                .iter()
+                .map(|value| {
+                    value
+                        .to_str()
+                        .ok()
+                        .and_then(|text| {
+                            if name == http::header::LINK {
+                                self.rewrite_link(text)
+                            } else {
+                                self.rewrite_url(text)
+                            }
+                        })
+                        .and_then(|text| HeaderValue::from_str(&text).ok())
+                        .unwrap_or_else(|| value.clone())
+                })
+                .collect();
+            headers.remove(name);
+            for value in values {
+                headers.append(name.clone(), value);
+            }
+        }
+    }
+
+    /// The public form of a backend URL; `None` when it is left unchanged.
+    pub fn rewrite_url(&self, url: &str) -> Option<String> {
+        let url = url.trim();
+        if url.starts_with('/') && !url.starts_with("//") {
+            let path = self.public_path(url)?;
+            return (path != url).then_some(path);
+        }
+        let rest = self.origins.iter().find_map(|origin| {
+            let head = url.get(..origin.len())?;
+            let rest = &url[origin.len()..];
+            (head.eq_ignore_ascii_case(origin)
+                && (rest.is_empty() || rest.starts_with(['/', '?', '#'])))
+            .then_some(rest)
+        })?;
+        let backend_path = match rest.chars().next() {
+            Some('/') => rest.to_string(),
+            _ => format!("/{rest}"),
+        };
+        let path = self.public_path(&backend_path).unwrap_or(backend_path);
+        Some(format!("{}{path}", self.public_url.unwrap_or_default()))
+    }
+
+    /// Rewrite the `<URI>` references of a `Link` header value.
+    pub fn rewrite_link(&self, value: &str) -> Option<String> {
+        let mut out = String::with_capacity(value.len());
+        let mut rest = value;
+        let mut changed = false;
+        while let Some(open) = rest.find('<') {
+            let Some(close) = rest[open..].find('>').map(|i| open + i) else {
+                break;
+            };
+            out.push_str(&rest[..=open]);
+            let target = &rest[open + 1..close];
+            match self.rewrite_url(target) {
+                Some(rewritten) => {
+                    out.push_str(&rewritten);
+                    changed = true;
+                }
+                None => out.push_str(target),
+            }
+            rest = &rest[close..];
+        }
+        out.push_str(rest);
+        changed.then_some(out)
+    }
+
+    /// Map a backend path back under the route prefix; `None` when the
+    /// route has no `path_rewrite` or the path lies outside it.
+    fn public_path(&self, path: &str) -> Option<String> {
+        let prefix = self.backend_prefix?;
+        let prefix = prefix.trim_end_matches('/');
+        let rest = path.strip_prefix(prefix)?;
+        if !(rest.is_empty() || rest.starts_with(['/', '?', '#'])) {
+            return None;
+        }
+        let route_prefix = self.route_prefix.trim_end_matches('/');
+        Some(match rest.chars().next() {
+            Some('/') => format!("{route_prefix}{rest}"),
+            _ if route_prefix.is_empty() => format!("/{rest}"),
+            _ => format!("{route_prefix}{rest}"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_backend_urls() {
+        let rewriter = LocationRewriter::new(
+            ["http://10.0.0.5:8080/", "http://app.internal"],
+            "/app",
+            Some("/"),
+            None,
+        );
+        let rewrite = |url| rewriter.rewrite_url(url);
+        assert_eq!(
+            rewrite("http://10.0.0.5:8080/login?next=%2F").as_deref(),
+            Some("/app/login?next=%2F")
+        );
+        assert_eq!(rewrite("HTTP://APP.internal").as_deref(), Some("/app/"));
+        assert_eq!(rewrite("/login").as_deref(), Some("/app/login"));
+        assert_eq!(rewrite("https://idp.example.com/authorize"), None);
+        assert_eq!(rewrite("http://10.0.0.5:80801/x"), None);
+        assert_eq!(rewrite("//cdn.example.com/x.js"), None);
+
+        let public = LocationRewriter::new(
+            ["http://backend:3000"],
+            "/v1",
+            Some("/api"),
+            Some("https://www.example.com/"),
+        );
+        assert_eq!(
+            public
+                .rewrite_url("http://backend:3000/api/users/7")
+                .as_deref(),
+            Some("https://www.example.com/v1/users/7")
+        );
+        assert_eq!(public.rewrite_url("/apiary"), None);
+        assert_eq!(
+            public.rewrite_url("/api?page=2").as_deref(),
+            Some("/v1?page=2")
+        );
+
+        // Without a path rewrite only the origin changes
+        let plain = LocationRewriter::new(["http://backend:3000"], "/docs", None, None);
+        assert_eq!(
+            plain.rewrite_url("http://backend:3000/docs/a").as_deref(),
+            Some("/docs/a")
+        );
+        assert_eq!(plain.rewrite_url("/docs/a"), None);
+    }
+
+    #[test]
+    fn test_rewrite_headers() {
+        let rewriter = LocationRewriter::new(["http://backend:3000"], "/app", Some("/"), None);
+        let mut headers = HeaderMap::new();
+        headers.insert("location", "http://backend:3000/next".parse().unwrap());
+        headers.append(
+            "link",
+            "<http://backend:3000/p/2>; rel=\"next\", <https://cdn.example.com/s.css>; rel=preload"
+                .parse()
+                .unwrap(),
+        );
+        headers.append("link", "</p/1>; rel=\"prev\"".parse().unwrap());
+        rewriter.apply(&mut headers, &[http::header::LOCATION, http::header::LINK]);
+        assert_eq!(headers["location"], "/app/next");
+        let links: Vec<_> = headers.get_all("link").iter().collect();
+        assert_eq!(
+            links,
+            [
+                "</app/p/2>; rel=\"next\", <https://cdn.example.com/s.css>; rel=preload",
+                "</app/p/1>; rel=\"prev\""
+            ]
+        );
+    }
+}
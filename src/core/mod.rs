@@ -1,10 +1,42 @@
 pub mod backend;
+pub mod body_rewrite;
+pub mod canary;
+pub mod client_ip;
+pub mod coalesce;
+pub mod condition;
+pub mod content_type;
+pub mod cookies;
+pub mod dedupe;
+pub mod error_pages;
+pub mod experiments;
+pub mod framing;
 pub mod gateway;
+pub mod hedge;
+pub mod htpasswd;
+pub mod json_schema;
 pub mod load_balancer;
+pub mod locale;
+pub mod location_rewrite;
+pub mod multipart;
+pub mod path_normalization;
+pub mod path_template;
 pub mod rate_limiter;
+pub mod rbac;
+pub mod retry;
+pub mod schedule;
+pub mod secret;
+pub mod steering;
+pub mod tap;
+pub mod virtual_host;
 pub mod waf;
 
+pub use canary::{CanaryGroup, CanaryState, RollbackReason};
+pub use dedupe::DedupeWindow;
+pub use error_pages::{GatewayError, error_response};
 pub use gateway::GatewayService;
 pub use load_balancer::LoadBalancerFactory;
 pub use rate_limiter::RouteRateLimiter;
+pub use retry::RetryBudget;
+pub use steering::SteeringTable;
+pub use tap::TapRegistry;
 pub use waf::*;
@@ -0,0 +1,275 @@
+//! `multipart/form-data` parsing
+//!
+//! Splits a buffered form body into its parts so the WAF can inspect text
+//! fields without scanning file contents, and upload policies can check
+//! the files.
+
+use axum::http::{HeaderMap, header};
+use thiserror::Error;
+
+/// A form body that could not be split into parts
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Malformed multipart body: {0}")]
+pub struct MultipartError(&'static str);
+
+/// One field of a form
+#[derive(Debug, PartialEq, Eq)]
+pub struct Part<'a> {
+    /// Field name from `Content-Disposition`
+    pub name: Option<String>,
+    /// File name, present for file uploads
+    pub filename: Option<String>,
+    /// The part's `Content-Type`, if it has one
+    pub content_type: Option<String>,
+    pub data: &'a [u8],
+}
+
+impl Part<'_> {
+    /// Whether the part is a file upload rather than a text field
+    pub fn is_file(&self) -> bool {
+        self.filename.is_some()
+    }
+}
+
+/// The boundary of a `multipart/form-data` request, if it is one.
+pub fn form_boundary(headers: &HeaderMap) -> Option<String> {
+    let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+    let mut params = split_params(content_type).into_iter();
+    let media_type = params.next()?;
+    if !media_type
+        .trim()
+        .eq_ignore_ascii_case("multipart/form-data")
+    {
+        return None;
+    }
+    params
+        .filter_map(|param| parse_param(&param))
+        .find(|(name, _)| name.eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value)
+        .filter(|boundary| !boundary.is_empty() && boundary.len() <= 70)
+}
+
+/// Split a form body into its parts.
+pub fn parse<'a>(body: &'a [u8], boundary: &str) -> Result<Vec<Part<'a>>, MultipartError> {
+    let delimiter = format!("--{boundary}").into_bytes();
+    let mut rest = if body.starts_with(&delimiter) {
+        &body[delimiter.len()..]
+    } else {
+        let mut inner = b"\r\n".to_vec();
+        inner.extend_from_slice(&delimiter);
+        let start = find(body, &inner).ok_or(MultipartError("missing boundary"))?;
+        &body[start + inner.len()..]
+    };
+
+    let mut inner_delimiter = b"\r\n".to_vec();
+    inner_delimiter.extend_from_slice(&delimiter);
+    let mut parts = Vec::new();
+    loop {
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+        // Transport padding after a delimiter is allowed before the CRLF
+        let line_end = find(rest, b"\r\n").ok_or(MultipartError("unterminated boundary line"))?;
+        if rest[..line_end].iter().any(|b| *b != b' ' && *b != b'\t') {
+            return Err(MultipartError("unexpected data after boundary"));
+        }
+        rest = &rest[line_end + 2..];
+
+        let headers_end = if rest.starts_with(b"\r\n") {
+            0
+        } else {
+            find(rest, b"\r\n\r\n").ok_or(MultipartError("unterminated part headers"))? + 2
+        };
+        let head = std::str::from_utf8(&rest[..headers_end])
+            .map_err(|_| MultipartError("part headers are not UTF-8"))?;
+        let data_start = headers_end + 2;
+        let data_end = find(&rest[data_start..], &inner_delimiter)
+            .ok_or(MultipartError("missing closing boundary"))?
+            + data_start;
+
+        let mut part = Part {
+            name: None,
+            filename: None,
+            content_type: None,
+            data: &rest[data_start..data_end],
+        };
+        for line in head.split("\r\n").filter(|line| !line.is_empty()) {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or(MultipartError("malformed part header"))?;
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("content-disposition") {
+                for (key, value) in split_params(value)
+                    .iter()
+                    .skip(1)
+                    .filter_map(|param| parse_param(param))
+                {
+                    match key.to_ascii_lowercase().as_str() {
+                        "name" => part.name = Some(value),
+                        "filename" if part.filename.is_none() => part.filename = Some(value),
+                        "filename*" => part.filename = Some(decode_extended(&value)),
+                        _ => {}
+                    }
+                }
+            } else if name.eq_ignore_ascii_case("content-type") {
+                part.content_type = Some(value.trim().to_string());
+            }
+        }
+        parts.push(part);
+        rest = &rest[data_end + inner_delimiter.len()..];
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Split a header value on `;`, ignoring separators inside quoted strings.
+fn split_params(value: &str) -> Vec<String> {
+    let mut params = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                params.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    params.push(current);
+    params
+}
+
+/// Parse `key=value` or `key="quoted value"`.
+fn parse_param(param: &str) -> Option<(String, String)> {
+    let (key, value) = param.split_once('=')?;
+    let value = value.trim();
+    let value = match value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(quoted) => {
+            let mut unescaped = String::with_capacity(quoted.len());
+            let mut chars = quoted.chars();
+            while let Some(c) = chars.next() {
+                unescaped.push(if c == '\\' { chars.next()? } else { c });
+            }
+            unescaped
+        }
+        None => value.to_string(),
+    };
+    Some((key.trim().to_string(), value))
+}
+
+/// Decode an RFC 8187 extended value such as `UTF-8''na%C3%AFve.txt`.
+fn decode_extended(value: &str) -> String {
+    let encoded = value.splitn(3, '\'').nth(2).unwrap_or(value);
+    urlencoding::decode(encoded)
+        .map(|decoded| decoded.into_owned())
+        .unwrap_or_else(|_| encoded.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderValue, Uri};
+
+    use super::*;
+    use crate::{
+        config::{WafConfig, WafRuleConfig},
+        core::waf::WafEngine,
+    };
+
+    #[test]
+    fn test_form_boundary() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(form_boundary(&headers), None);
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("Multipart/Form-Data; charset=utf-8; boundary=\"a;b\""),
+        );
+        assert_eq!(form_boundary(&headers).as_deref(), Some("a;b"));
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("multipart/mixed; boundary=x"),
+        );
+        assert_eq!(form_boundary(&headers), None);
+    }
+
+    #[test]
+    fn test_parse_form() {
+        let body = b"preamble\r\n--XyZ\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+            hello; world\r\n\
+            --XyZ  \r\n\
+            content-disposition: form-data; name=\"doc\"; filename=\"a.txt\"; filename*=UTF-8''na%C3%AFve.pdf\r\n\
+            Content-Type: application/pdf\r\n\r\n\
+            %PDF\r\n--not-the-boundary\r\n\
+            --XyZ\r\n\r\n\
+            anonymous\r\n\
+            --XyZ--\r\nepilogue";
+        let parts = parse(body, "XyZ").expect("parses");
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].name.as_deref(), Some("title"));
+        assert!(!parts[0].is_file());
+        assert_eq!(parts[0].data, b"hello; world");
+        assert_eq!(parts[1].filename.as_deref(), Some("na\u{ef}ve.pdf"));
+        assert_eq!(parts[1].content_type.as_deref(), Some("application/pdf"));
+        assert_eq!(parts[1].data, b"%PDF\r\n--not-the-boundary");
+        assert_eq!(parts[2].name, None);
+        assert_eq!(parts[2].data, b"anonymous");
+
+        assert!(parse(b"--XyZ\r\n\r\nunterminated", "XyZ").is_err());
+        assert!(parse(b"no boundary here", "XyZ").is_err());
+        assert!(parse(b"--XyZjunk\r\n\r\nx\r\n--XyZ--", "XyZ").is_err());
+    }
+
+    #[test]
+    fn test_waf_inspects_text_fields_only() {
+        let engine = WafEngine::from_config(&WafConfig {
+            enabled: true,
+            xss: WafRuleConfig {
+                enabled: true,
+                block_mode: true,
+            },
+            ..WafConfig::default()
+        })
+        .expect("engine");
+        let uri: Uri = "/upload".parse().expect("valid uri");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("multipart/form-data; boundary=b"),
+        );
+        let form = |field: &str, file: &str| {
+            format!(
+                "--b\r\nContent-Disposition: form-data; name=\"caption\"\r\n\r\n{field}\r\n\
+                 --b\r\nContent-Disposition: form-data; name=\"f\"; filename=\"page.html\"\r\n\r\n\
+                 {file}\r\n--b--\r\n"
+            )
+        };
+
+        let file_with_script = form("holiday", "<script>alert(1)</script>");
+        assert!(
+            engine
+                .check_request(&uri, &headers, Some(file_with_script.as_bytes()), None)
+                .is_ok()
+        );
+        let field_with_script = form("<script>alert(1)</script>", "plain");
+        assert_eq!(
+            engine
+                .check_request(&uri, &headers, Some(field_with_script.as_bytes()), None)
+                .unwrap_err()
+                .threat_type,
+            "XSS"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Request path normalization
+//!
+//! Rewrites a request path into a canonical form before the WAF inspects it
+//! and routes are matched, so equivalent spellings of a path (`//admin`,
+//! `/public/%2e%2e/admin`) cannot get past either.
+
+use std::borrow::Cow;
+
+use thiserror::Error;
+
+use crate::config::PathNormalizationConfig;
+
+/// Why a path could not be normalized
+#[derive(Error, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PathNormalizationError {
+    /// A `%` not followed by two hex digits
+    #[error("Malformed percent-encoding in request path")]
+    InvalidEncoding,
+}
+
+/// Normalize `path` according to `config`, borrowing it when nothing changes.
+///
+/// Escapes are checked (and unreserved characters decoded) first, so encoded
+/// dot segments are resolved like literal ones.
+pub fn normalize_path<'a>(
+    path: &'a str,
+    config: &PathNormalizationConfig,
+) -> Result<Cow<'a, str>, PathNormalizationError> {
+    // Asterisk-form (`OPTIONS *`) and other non-origin paths are left alone
+    if !config.enabled || !path.starts_with('/') {
+        return Ok(Cow::Borrowed(path));
+    }
+
+    let decoded = decode(path, config)?;
+    let normalized = if config.merge_slashes || config.resolve_dot_segments {
+        resolve_segments(&decoded, config)
+    } else {
+        decoded
+    };
+    Ok(if normalized == path {
+        Cow::Borrowed(path)
+    } else {
+        Cow::Owned(normalized)
+    })
+}
+
+/// Validate escapes, decode unreserved characters and apply lowercasing.
+fn decode(path: &str, config: &PathNormalizationConfig) -> Result<String, PathNormalizationError> {
+    let mut out = String::with_capacity(path.len());
+    let push_literal = |out: &mut String, literal: &str| {
+        if config.lowercase {
+            out.extend(literal.chars().map(|c| c.to_ascii_lowercase()));
+        } else {
+            out.push_str(literal);
+        }
+    };
+
+    let mut rest = path;
+    while let Some(pos) = rest.find('%') {
+        push_literal(&mut out, &rest[..pos]);
+        let escape = rest.as_bytes();
+        let (Some(high), Some(low)) = (
+            escape.get(pos + 1).copied().and_then(hex_value),
+            escape.get(pos + 2).copied().and_then(hex_value),
+        ) else {
+            return Err(PathNormalizationError::InvalidEncoding);
+        };
+        let byte = high << 4 | low;
+        if !config.decode_unreserved {
+            out.push_str(&rest[pos..pos + 3]);
+        } else if is_unreserved(byte) {
+            push_literal(&mut out, char::from(byte).encode_utf8(&mut [0; 4]));
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+        rest = &rest[pos + 3..];
+    }
+    push_literal(&mut out, rest);
+    Ok(out)
+}
+
+/// Merge empty segments and resolve dot segments of an absolute path.
+fn resolve_segments(path: &str, config: &PathNormalizationConfig) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    // Whether the last segment dropped was at the end, which keeps the
+    // trailing slash (`/a/b/..` is `/a/`)
+    let mut trailing_slash = false;
+    for segment in path[1..].split('/') {
+        trailing_slash = true;
+        match segment {
+            "" if config.merge_slashes => {}
+            "." if config.resolve_dot_segments => {}
+            ".." if config.resolve_dot_segments => {
+                segments.pop();
+            }
+            _ => {
+                segments.push(segment);
+                trailing_slash = false;
+            }
+        }
+    }
+
+    let mut normalized = format!("/{}", segments.join("/"));
+    if trailing_slash && !segments.is_empty() {
+        normalized.push('/');
+    }
+    normalized
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    char::from(byte).to_digit(16).map(|digit| digit as u8)
+}
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(path: &str) -> Result<Cow<'_, str>, PathNormalizationError> {
+        normalize_path(path, &PathNormalizationConfig::default())
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert!(matches!(normalize("/api/users"), Ok(Cow::Borrowed(_))));
+        assert_eq!(normalize("/").unwrap(), "/");
+        assert_eq!(normalize("//api///users/").unwrap(), "/api/users/");
+        assert_eq!(normalize("/public/../admin").unwrap(), "/admin");
+        assert_eq!(normalize("/public/%2e%2E/admin").unwrap(), "/admin");
+        assert_eq!(normalize("/a/./b/.").unwrap(), "/a/b/");
+        assert_eq!(normalize("/a/b/..").unwrap(), "/a/");
+        assert_eq!(normalize("/../../etc/passwd").unwrap(), "/etc/passwd");
+        assert_eq!(normalize("/..").unwrap(), "/");
+        assert_eq!(normalize("/%7Euser/%61pi").unwrap(), "/~user/api");
+
+        // Reserved and control characters stay encoded, with uppercase hex
+        assert_eq!(normalize("/a%2fb/%00").unwrap(), "/a%2Fb/%00");
+        assert_eq!(normalize("/a/%2F..%2F/b").unwrap(), "/a/%2F..%2F/b");
+
+        assert_eq!(
+            normalize("/a%zz"),
+            Err(PathNormalizationError::InvalidEncoding)
+        );
+        assert_eq!(
+            normalize("/a%2"),
+            Err(PathNormalizationError::InvalidEncoding)
+        );
+        assert_eq!(
+            normalize("/a%+1"),
+            Err(PathNormalizationError::InvalidEncoding)
+        );
+        assert_eq!(normalize("*").unwrap(), "*");
+    }
+
+    #[test]
+    fn test_normalization_options() {
+        let config = PathNormalizationConfig {
+            merge_slashes: false,
+            lowercase: true,
+            ..PathNormalizationConfig::default()
+        };
+        assert_eq!(
+            normalize_path("/API//Users/%2E%2E/%4B%2f", &config).unwrap(),
+            "/api//k%2F"
+        );
+
+        let config = PathNormalizationConfig {
+            decode_unreserved: false,
+            resolve_dot_segments: false,
+            ..PathNormalizationConfig::default()
+        };
+        assert_eq!(
+            normalize_path("/a//./%2e%2e/b", &config).unwrap(),
+            "/a/./%2e%2e/b"
+        );
+
+        let config = PathNormalizationConfig {
+            enabled: false,
+            ..PathNormalizationConfig::default()
+        };
+        assert_eq!(
+            normalize_path("/a//%zz/../b", &config).unwrap(),
+            "/a//%zz/../b"
+        );
+    }
+}
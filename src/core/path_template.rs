@@ -0,0 +1,115 @@
+//! Path templates for metric labels
+//!
+//! A template such as `/users/{id}/orders` matches request paths segment by
+//! segment, with `{name}` standing for any single non-empty segment. Routes
+//! use them to label request metrics by the shape of the path rather than
+//! the path itself, so `/users/123` and `/users/456` share a series.
+
+use crate::config::RouteMetricsConfig;
+
+/// Whether `path` has the shape of `template`
+pub fn matches_template(template: &str, path: &str) -> bool {
+    let mut template_segments = template.split('/');
+    let mut path_segments = path.split('/');
+    loop {
+        match (template_segments.next(), path_segments.next()) {
+            (None, None) => return true,
+            (Some(expected), Some(segment)) if is_placeholder(expected) => {
+                if segment.is_empty() {
+                    return false;
+                }
+            }
+            (Some(expected), Some(segment)) if expected == segment => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Check that `template` is an absolute path whose segments are literals or
+/// whole `{name}` placeholders.
+pub fn validate_template(template: &str) -> Result<(), String> {
+    if !template.starts_with('/') {
+        return Err("Path templates must start with '/'".to_string());
+    }
+    match template
+        .split('/')
+        .find(|segment| segment.contains(['{', '}']) && !is_placeholder(segment))
+    {
+        Some(segment) => Err(format!(
+            "Segment '{segment}' must be a literal or a whole '{{name}}' placeholder"
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The `path` label for a request to `path` on the route labelled `route`
+pub fn metrics_path_label<'a>(
+    path: &'a str,
+    route: &'a str,
+    config: Option<&'a RouteMetricsConfig>,
+) -> &'a str {
+    let Some(config) = config else {
+        return route;
+    };
+    if config.raw_path {
+        return path;
+    }
+    config
+        .path_templates
+        .iter()
+        .find(|template| matches_template(template, path))
+        .map_or(route, String::as_str)
+}
+
+fn is_placeholder(segment: &str) -> bool {
+    segment
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .is_some_and(|name| !name.is_empty() && !name.contains(['{', '}']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_template() {
+        assert!(matches_template("/users/{id}", "/users/123"));
+        assert!(matches_template(
+            "/users/{id}/orders/{order}",
+            "/users/7/orders/9"
+        ));
+        assert!(matches_template("/users/", "/users/"));
+        assert!(!matches_template("/users/{id}", "/users/"));
+        assert!(!matches_template("/users/{id}", "/users/1/orders"));
+        assert!(!matches_template("/users/{id}/", "/users/1"));
+        assert!(!matches_template("/users/{id}", "/accounts/1"));
+
+        assert!(validate_template("/users/{id}/orders").is_ok());
+        assert!(validate_template("users/{id}").is_err());
+        assert!(validate_template("/users/{id}.json").is_err());
+        assert!(validate_template("/users/{}").is_err());
+    }
+
+    #[test]
+    fn test_metrics_path_label() {
+        let config = RouteMetricsConfig {
+            path_templates: vec!["/users/{id}".to_string(), "/users/{id}/{tab}".to_string()],
+            raw_path: false,
+        };
+        let label = |path| metrics_path_label(path, "/users/", Some(&config));
+        assert_eq!(label("/users/42"), "/users/{id}");
+        assert_eq!(label("/users/42/posts"), "/users/{id}/{tab}");
+        assert_eq!(label("/users/42/posts/1"), "/users/");
+        assert_eq!(metrics_path_label("/users/42", "/users/", None), "/users/");
+
+        let raw = RouteMetricsConfig {
+            raw_path: true,
+            ..RouteMetricsConfig::default()
+        };
+        assert_eq!(
+            metrics_path_label("/users/42", "/users/", Some(&raw)),
+            "/users/42"
+        );
+    }
+}
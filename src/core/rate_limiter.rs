@@ -6,27 +6,40 @@
 //! `governor`'s in‑memory state stores and support different algorithms
 //! (TokenBucket, SlidingWindow, FixedWindow) mapped onto appropriate quota
 //! constructions.
+//!
+//! Keyed limiters keep their per-key state in a [`BoundedKeyStore`] so that a
+//! flood of unique client IPs or header values cannot grow memory without
+//! bound: keys idle for longer than the key TTL are swept, and once
+//! `max_keys` is reached the least recently used keys are evicted.
 use std::{
     hash::Hash,
-    net::{IpAddr, SocketAddr},
-    num::NonZeroU32,
-    sync::Arc,
+    net::IpAddr,
+    num::{NonZeroU32, NonZeroU64},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
-use axum::{
-    extract::ConnectInfo,
-    response::{IntoResponse, Response as AxumResponse},
-};
+use axum::response::Response as AxumResponse;
 use governor::{
     Quota, RateLimiter,
     clock::DefaultClock,
-    state::{InMemoryState, NotKeyed, keyed::DefaultKeyedStateStore},
+    nanos::Nanos,
+    state::{InMemoryState, NotKeyed, StateStore},
 };
-use http::{HeaderName, Request, StatusCode};
+use http::{HeaderName, Request, StatusCode, header};
 use humantime;
+use scc::hash_map::Entry;
 use tracing;
 
-use crate::config::models::{MissingKeyPolicy, RateLimitAlgorithm, RateLimitBy, RateLimitConfig};
+use crate::{
+    config::models::{
+        MissingKeyPolicy, RateLimitAlgorithm, RateLimitBy, RateLimitConfig, RateLimitKeyPart,
+    },
+    core::{client_ip::request_client_ip, error_pages::error_response},
+};
 
 /// Internal wrapper bundling a concrete governor limiter instance with
 /// response metadata (status + message) and behaviour on missing key.
@@ -36,16 +49,182 @@ pub struct LimiterWrapper<RL> {
     pub status_code: StatusCode,
     pub message: String,
     pub on_missing_key: MissingKeyPolicy,
+    /// Key count and evictions of a keyed limiter's store
+    pub keys: Option<Arc<KeyCounters>>,
 }
 
 pub type DirectRateLimiterImpl = RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
-pub type KeyedRateLimiterImpl<K> = RateLimiter<K, DefaultKeyedStateStore<K>, DefaultClock>;
+pub type KeyedRateLimiterImpl<K> = RateLimiter<K, BoundedKeyStore<K>, DefaultClock>;
+
+/// Live key count and cumulative evictions of a [`BoundedKeyStore`].
+#[derive(Debug, Default)]
+pub struct KeyCounters {
+    keys: AtomicUsize,
+    evictions: AtomicU64,
+}
+
+impl KeyCounters {
+    pub fn keys(&self) -> usize {
+        self.keys.load(Ordering::Relaxed)
+    }
+
+    pub fn evictions(&self) -> u64 {
+        self.evictions.load(Ordering::Relaxed)
+    }
+}
+
+/// Rate limiting state of one key.
+struct KeyState {
+    /// GCRA theoretical arrival time in governor nanos (0 = never measured)
+    tat: AtomicU64,
+    /// Milliseconds since the store's epoch when the key was last checked
+    last_seen: AtomicU64,
+}
+
+impl KeyState {
+    fn new(now_ms: u64) -> Self {
+        Self {
+            tat: AtomicU64::new(0),
+            last_seen: AtomicU64::new(now_ms),
+        }
+    }
+
+    /// Compare-and-swap the arrival time, as governor's in-memory state does.
+    fn measure<T, F, E>(&self, f: &F) -> Result<T, E>
+    where
+        F: Fn(Option<Nanos>) -> Result<(T, Nanos), E>,
+    {
+        let mut prev = self.tat.load(Ordering::Acquire);
+        let mut decision = f(NonZeroU64::new(prev).map(|n| Nanos::from(n.get())));
+        while let Ok((result, new_tat)) = decision {
+            match self.tat.compare_exchange_weak(
+                prev,
+                new_tat.into(),
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Ok(result),
+                Err(next_prev) => prev = next_prev,
+            }
+            decision = f(NonZeroU64::new(prev).map(|n| Nanos::from(n.get())));
+        }
+        decision.map(|(result, _)| result)
+    }
+}
+
+/// Keyed governor state store holding at most `max_keys` keys.
+///
+/// Keys not checked for `key_ttl` are swept when new keys arrive (at most
+/// once per TTL). When the store is still full, the least recently used
+/// sixteenth of `max_keys` is evicted in one pass, keeping the scan cost
+/// amortized. An evicted key starts again with a full quota.
+pub struct BoundedKeyStore<K: Hash + Eq> {
+    keys: scc::HashMap<K, KeyState>,
+    max_keys: usize,
+    key_ttl: Duration,
+    epoch: Instant,
+    last_sweep_ms: AtomicU64,
+    counters: Arc<KeyCounters>,
+}
+
+impl<K: Hash + Eq> BoundedKeyStore<K> {
+    pub fn new(max_keys: usize, key_ttl: Duration) -> Self {
+        Self {
+            keys: scc::HashMap::default(),
+            max_keys: max_keys.max(1),
+            key_ttl,
+            epoch: Instant::now(),
+            last_sweep_ms: AtomicU64::new(0),
+            counters: Arc::new(KeyCounters::default()),
+        }
+    }
+
+    pub fn counters(&self) -> Arc<KeyCounters> {
+        self.counters.clone()
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.epoch.elapsed().as_millis() as u64
+    }
+
+    /// Remove keys matching `evict`, keeping the counters in step.
+    fn evict_where(&self, mut evict: impl FnMut(u64) -> bool) {
+        let mut removed = 0;
+        self.keys.retain_sync(|_, state| {
+            let keep = !evict(state.last_seen.load(Ordering::Relaxed));
+            if !keep {
+                removed += 1;
+            }
+            keep
+        });
+        self.counters.keys.fetch_sub(removed, Ordering::Relaxed);
+        self.counters
+            .evictions
+            .fetch_add(removed as u64, Ordering::Relaxed);
+    }
+
+    /// Make room for one more key.
+    fn make_room(&self, now_ms: u64) {
+        let ttl_ms = self.key_ttl.as_millis().max(1) as u64;
+        let last_sweep = self.last_sweep_ms.load(Ordering::Relaxed);
+        if now_ms.saturating_sub(last_sweep) >= ttl_ms
+            && self
+                .last_sweep_ms
+                .compare_exchange(last_sweep, now_ms, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            self.evict_where(|last_seen| now_ms.saturating_sub(last_seen) >= ttl_ms);
+        }
+
+        if self.counters.keys() < self.max_keys {
+            return;
+        }
+        let mut seen = Vec::with_capacity(self.counters.keys());
+        self.keys.iter_sync(|_, state| {
+            seen.push(state.last_seen.load(Ordering::Relaxed));
+            true
+        });
+        if seen.is_empty() {
+            return;
+        }
+        let batch = (self.max_keys / 16).clamp(1, seen.len());
+        let (_, cutoff, _) = seen.select_nth_unstable(batch - 1);
+        let cutoff = *cutoff;
+        self.evict_where(|last_seen| last_seen <= cutoff);
+    }
+}
+
+impl<K: Hash + Eq + Clone> StateStore for BoundedKeyStore<K> {
+    type Key = K;
+
+    fn measure_and_replace<T, F, E>(&self, key: &K, f: F) -> Result<T, E>
+    where
+        F: Fn(Option<Nanos>) -> Result<(T, Nanos), E>,
+    {
+        let now_ms = self.now_ms();
+        if let Some(result) = self.keys.read_sync(key, |_, state| {
+            state.last_seen.store(now_ms, Ordering::Relaxed);
+            state.measure(&f)
+        }) {
+            return result;
+        }
+
+        self.make_room(now_ms);
+        match self.keys.entry_sync(key.clone()) {
+            Entry::Occupied(entry) => entry.get().measure(&f),
+            Entry::Vacant(entry) => {
+                self.counters.keys.fetch_add(1, Ordering::Relaxed);
+                entry.insert_entry(KeyState::new(now_ms)).get().measure(&f)
+            }
+        }
+    }
+}
 
 /// Non‑keyed (global per route) limiter variant.
 pub type RouteSpecificLimiter = LimiterWrapper<DirectRateLimiterImpl>;
 /// Per‑client‑IP keyed limiter variant.
 pub type IpLimiter = LimiterWrapper<KeyedRateLimiterImpl<IpAddr>>;
-/// Per‑header‑value keyed limiter variant.
+/// Per‑header‑value (or composite key) keyed limiter variant.
 pub type HeaderLimiter = LimiterWrapper<KeyedRateLimiterImpl<String>>;
 
 // --- LimiterWrapper Implementations ---
@@ -54,7 +233,7 @@ pub type HeaderLimiter = LimiterWrapper<KeyedRateLimiterImpl<String>>;
 impl LimiterWrapper<DirectRateLimiterImpl> {
     pub fn check_route(&self) -> Result<(), Box<AxumResponse>> {
         if self.limiter.check().is_err() {
-            let response = (self.status_code, self.message.clone()).into_response();
+            let response = error_response(self.status_code, self.message.clone());
             Err(Box::new(response))
         } else {
             Ok(())
@@ -70,7 +249,7 @@ where
     // Generic check method for keyed limiters
     fn check_keyed(&self, key: &K) -> Result<(), Box<AxumResponse>> {
         if self.limiter.check_key(key).is_err() {
-            let response = (self.status_code, self.message.clone()).into_response();
+            let response = error_response(self.status_code, self.message.clone());
             Err(Box::new(response))
         } else {
             Ok(())
@@ -105,6 +284,18 @@ pub enum RouteRateLimiter {
         limiter: Arc<HeaderLimiter>,
         header_name: HeaderName, // Store HeaderName for extraction in check method
     },
+    Composite {
+        limiter: Arc<HeaderLimiter>,
+        parts: Vec<RateLimitKeyPart>,
+        header_name: Option<HeaderName>,
+    },
+}
+
+/// One of a route's limits together with its metrics label.
+#[derive(Clone)]
+pub struct NamedRateLimiter {
+    pub name: String,
+    pub limiter: RouteRateLimiter,
 }
 
 impl RouteRateLimiter {
@@ -153,6 +344,14 @@ impl RouteRateLimiter {
         let status_code = StatusCode::from_u16(config.status_code)
             .map_err(|_| format!("Invalid status code: {}", config.status_code))?;
 
+        // By default a key is dropped once it has been idle long enough to
+        // refill its whole burst, when its state is the same as a new key's.
+        let key_ttl = match &config.key_ttl {
+            Some(ttl) => humantime::parse_duration(ttl)
+                .map_err(|e| format!("Invalid key_ttl string '{ttl}': {e}"))?,
+            None => period_duration.saturating_mul(quota_requests.get()),
+        };
+
         tracing::info!(
             "Creating rate limiter: by={:?}, algorithm={:?}, requests={}, period={}, status_code={}, on_missing_key={:?}",
             config.by,
@@ -170,12 +369,15 @@ impl RouteRateLimiter {
                     status_code,
                     message: config.message.clone(),
                     on_missing_key: config.on_missing_key,
+                    keys: None,
                 });
                 Ok(RouteRateLimiter::Route(limiter))
             }
             RateLimitBy::Ip => {
+                let store = BoundedKeyStore::new(config.max_keys, key_ttl);
                 let limiter = Arc::new(LimiterWrapper {
-                    limiter: RateLimiter::keyed(quota),
+                    keys: Some(store.counters()),
+                    limiter: RateLimiter::new(quota, store, DefaultClock::default()),
                     status_code,
                     message: config.message.clone(),
                     on_missing_key: config.on_missing_key,
@@ -187,10 +389,11 @@ impl RouteRateLimiter {
                     .header_name
                     .as_ref()
                     .ok_or_else(|| "header_name is required for RateLimitBy::Header".to_string())?;
-                let header_name = HeaderName::from_bytes(header_name_str.as_bytes())
-                    .map_err(|e| format!("Invalid header_name '{header_name_str}': {e}"))?;
+                let header_name = parse_header_name(header_name_str)?;
+                let store = BoundedKeyStore::new(config.max_keys, key_ttl);
                 let limiter = Arc::new(LimiterWrapper {
-                    limiter: RateLimiter::keyed(quota),
+                    keys: Some(store.counters()),
+                    limiter: RateLimiter::new(quota, store, DefaultClock::default()),
                     status_code,
                     message: config.message.clone(),
                     on_missing_key: config.on_missing_key,
@@ -200,6 +403,51 @@ impl RouteRateLimiter {
                     header_name,
                 })
             }
+            RateLimitBy::Composite => {
+                if config.key.is_empty() {
+                    return Err("key is required for RateLimitBy::Composite".to_string());
+                }
+                let header_name = match &config.header_name {
+                    Some(name) => Some(parse_header_name(name)?),
+                    None if config.key.contains(&RateLimitKeyPart::Header) => {
+                        return Err("header_name is required for a 'header' key part".to_string());
+                    }
+                    None => None,
+                };
+                let store = BoundedKeyStore::new(config.max_keys, key_ttl);
+                let limiter = Arc::new(LimiterWrapper {
+                    keys: Some(store.counters()),
+                    limiter: RateLimiter::new(quota, store, DefaultClock::default()),
+                    status_code,
+                    message: config.message.clone(),
+                    on_missing_key: config.on_missing_key,
+                });
+                Ok(RouteRateLimiter::Composite {
+                    limiter,
+                    parts: config.key.clone(),
+                    header_name,
+                })
+            }
+        }
+    }
+
+    /// Limiter type label (`route`, `ip`, `header` or `composite`).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RouteRateLimiter::Route(_) => "route",
+            RouteRateLimiter::Ip(_) => "ip",
+            RouteRateLimiter::Header { .. } => "header",
+            RouteRateLimiter::Composite { .. } => "composite",
+        }
+    }
+
+    /// Current key count and cumulative evictions of a keyed limiter.
+    pub fn key_counters(&self) -> Option<&KeyCounters> {
+        match self {
+            RouteRateLimiter::Route(_) => None,
+            RouteRateLimiter::Ip(limiter) => limiter.keys.as_deref(),
+            RouteRateLimiter::Header { limiter, .. }
+            | RouteRateLimiter::Composite { limiter, .. } => limiter.keys.as_deref(),
         }
     }
 
@@ -210,11 +458,7 @@ impl RouteRateLimiter {
         match self {
             RouteRateLimiter::Route(limiter) => limiter.check_route(),
             RouteRateLimiter::Ip(limiter) => {
-                // Extract client IP from request extensions
-                let client_ip = req
-                    .extensions()
-                    .get::<ConnectInfo<SocketAddr>>()
-                    .map(|connect_info| connect_info.0.ip());
+                let client_ip = request_client_ip(req);
 
                 match client_ip {
                     Some(ip) => limiter.check_ip(ip),
@@ -222,7 +466,7 @@ impl RouteRateLimiter {
                         MissingKeyPolicy::Allow => Ok(()),
                         MissingKeyPolicy::Deny => {
                             let response =
-                                (limiter.status_code, "No client IP available").into_response();
+                                error_response(limiter.status_code, "No client IP available");
                             Err(Box::new(response))
                         }
                     },
@@ -243,22 +487,85 @@ impl RouteRateLimiter {
                     None => match limiter.on_missing_key {
                         MissingKeyPolicy::Allow => Ok(()),
                         MissingKeyPolicy::Deny => {
-                            let response = (
+                            let response = error_response(
                                 limiter.status_code,
                                 format!("Required header '{header_name}' is missing"),
-                            )
-                                .into_response();
+                            );
                             Err(Box::new(response))
                         }
                     },
                 }
             }
+            RouteRateLimiter::Composite {
+                limiter,
+                parts,
+                header_name,
+            } => match composite_key(req, parts, header_name.as_ref()) {
+                Ok(key) => limiter.check_keyed(&key),
+                Err(missing) => match limiter.on_missing_key {
+                    MissingKeyPolicy::Allow => Ok(()),
+                    MissingKeyPolicy::Deny => {
+                        let response = error_response(
+                            limiter.status_code,
+                            format!("Rate limit key part '{missing}' is missing"),
+                        );
+                        Err(Box::new(response))
+                    }
+                },
+            },
+        }
+    }
+}
+
+fn parse_header_name(name: &str) -> Result<HeaderName, String> {
+    HeaderName::from_bytes(name.as_bytes())
+        .map_err(|e| format!("Invalid header_name '{name}': {e}"))
+}
+
+/// Join the request's values for `parts` into one key, or name the first
+/// part the request does not carry.
+fn composite_key<T>(
+    req: &Request<T>,
+    parts: &[RateLimitKeyPart],
+    header_name: Option<&HeaderName>,
+) -> Result<String, String> {
+    let mut key = String::new();
+    for part in parts {
+        let value = match part {
+            RateLimitKeyPart::Ip => request_client_ip(req)
+                .map(|ip| ip.to_string())
+                .ok_or_else(|| "ip".to_string())?,
+            RateLimitKeyPart::Path => req.uri().path().to_string(),
+            RateLimitKeyPart::Method => req.method().to_string(),
+            RateLimitKeyPart::Host => req
+                .headers()
+                .get(header::HOST)
+                .and_then(|v| v.to_str().ok())
+                .or_else(|| req.uri().host())
+                .map(str::to_ascii_lowercase)
+                .ok_or_else(|| "host".to_string())?,
+            RateLimitKeyPart::Header => header_name
+                .and_then(|name| req.headers().get(name))
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    header_name.map_or_else(|| "header".to_string(), |name| name.to_string())
+                })?,
+        };
+        // Header values and paths cannot contain a newline, so keys stay distinct
+        if !key.is_empty() {
+            key.push('\n');
         }
+        key.push_str(&value);
     }
+    Ok(key)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::net::SocketAddr;
+
+    use axum::extract::ConnectInfo;
     use http::{Method, Request};
 
     use super::*;
@@ -268,14 +575,18 @@ mod tests {
 
     fn create_test_rate_limit_config() -> RateLimitConfig {
         RateLimitConfig {
+            name: None,
             by: RateLimitBy::Route,
             header_name: None,
+            key: vec![],
             requests: 5,
             period: "1s".to_string(),
             status_code: 429,
             message: "Too Many Requests".to_string(),
             algorithm: RateLimitAlgorithm::TokenBucket,
             on_missing_key: MissingKeyPolicy::Allow,
+            max_keys: 100_000,
+            key_ttl: None,
         }
     }
 
@@ -311,6 +622,74 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_keyed_limiter_bounds_keys() {
+        let mut config = create_test_rate_limit_config();
+        config.by = RateLimitBy::Ip;
+        config.requests = 1;
+        config.period = "1m".to_string();
+        config.max_keys = 32;
+        let RouteRateLimiter::Ip(limiter) = RouteRateLimiter::new(&config).unwrap() else {
+            panic!("expected an IP limiter");
+        };
+        let counters = limiter.keys.clone().unwrap();
+
+        for i in 0..100u8 {
+            assert!(limiter.check_ip(IpAddr::from([10, 0, 0, i])).is_ok());
+        }
+        assert!(counters.keys() <= 32, "{} keys", counters.keys());
+        assert_eq!(counters.keys() as u64 + counters.evictions(), 100);
+
+        // Recently seen keys keep their state
+        assert!(limiter.check_ip(IpAddr::from([10, 0, 0, 99])).is_err());
+
+        // Idle keys are swept once their TTL has passed
+        config.key_ttl = Some("1ms".to_string());
+        let RouteRateLimiter::Ip(limiter) = RouteRateLimiter::new(&config).unwrap() else {
+            panic!("expected an IP limiter");
+        };
+        let counters = limiter.keys.clone().unwrap();
+        assert!(limiter.check_ip(IpAddr::from([10, 0, 0, 1])).is_ok());
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert!(limiter.check_ip(IpAddr::from([10, 0, 0, 2])).is_ok());
+        assert_eq!((counters.keys(), counters.evictions()), (1, 1));
+        assert!(limiter.check_ip(IpAddr::from([10, 0, 0, 1])).is_ok());
+    }
+
+    #[test]
+    fn test_composite_limiter_keys() {
+        let mut config = create_test_rate_limit_config();
+        config.by = RateLimitBy::Composite;
+        config.key = vec![RateLimitKeyPart::Ip, RateLimitKeyPart::Path];
+        config.requests = 1;
+        config.period = "1m".to_string();
+        let limiter = RouteRateLimiter::new(&config).unwrap();
+        assert_eq!(limiter.kind(), "composite");
+
+        let request = |ip: [u8; 4], path: &str| {
+            let mut req = Request::builder().uri(path).body(()).unwrap();
+            req.extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from((ip, 40000))));
+            req
+        };
+
+        assert!(limiter.check(&request([10, 0, 0, 1], "/a")).is_ok());
+        assert!(limiter.check(&request([10, 0, 0, 1], "/a")).is_err());
+        assert!(limiter.check(&request([10, 0, 0, 1], "/b")).is_ok());
+        assert!(limiter.check(&request([10, 0, 0, 2], "/a")).is_ok());
+        assert_eq!(limiter.key_counters().unwrap().keys(), 3);
+
+        // Requests without a client address fall back to on_missing_key
+        let no_ip = Request::builder().uri("/a").body(()).unwrap();
+        assert!(limiter.check(&no_ip).is_ok());
+        config.on_missing_key = MissingKeyPolicy::Deny;
+        let limiter = RouteRateLimiter::new(&config).unwrap();
+        assert!(limiter.check(&no_ip).is_err());
+
+        config.key = vec![];
+        assert!(RouteRateLimiter::new(&config).is_err());
+    }
+
     #[test]
     fn test_route_limiter_check() {
         let config = create_test_rate_limit_config();
@@ -0,0 +1,224 @@
+//! Role-based access control
+//!
+//! Maps an authenticated identity to roles through user and group bindings,
+//! then allows a request when a rule grants one of those roles its method
+//! and path. Denials carry a reason for logs and the audit trail.
+
+use std::sync::Arc;
+
+use http::Method;
+
+use crate::config::{RbacPolicyConfig, RbacRoleBinding};
+
+/// A user authenticated by the gateway, stored in the request extensions by
+/// authentication middlewares for later authorization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatedUser {
+    pub name: String,
+    /// Group DNs or names, as reported by the identity source
+    pub groups: Arc<[String]>,
+}
+
+/// Whether a group DN or name matches a configured group: equal ignoring
+/// case, or by common name when `wanted` is a plain name
+/// (`cn=ops,ou=groups,...` matches `ops`).
+pub fn group_matches(group: &str, wanted: &str) -> bool {
+    group.eq_ignore_ascii_case(wanted)
+        || (!wanted.contains('=')
+            && group
+                .split(',')
+                .next()
+                .and_then(|rdn| rdn.split_once('='))
+                .is_some_and(|(kind, name)| {
+                    kind.trim().eq_ignore_ascii_case("cn")
+                        && name.trim().eq_ignore_ascii_case(wanted)
+                }))
+}
+
+struct Rule {
+    roles: Vec<String>,
+    paths: Vec<String>,
+    /// `None` allows every method
+    methods: Option<Vec<Method>>,
+}
+
+impl Rule {
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        let method_ok = self
+            .methods
+            .as_ref()
+            .is_none_or(|methods| methods.contains(method));
+        let path_ok = self.paths.is_empty()
+            || self
+                .paths
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => path.starts_with(prefix),
+                    None => path == pattern,
+                });
+        method_ok && path_ok
+    }
+}
+
+/// A compiled [`RbacPolicyConfig`].
+pub struct RbacPolicy {
+    roles: Vec<(String, RbacRoleBinding)>,
+    rules: Vec<Rule>,
+}
+
+impl RbacPolicy {
+    pub fn new(config: &RbacPolicyConfig) -> Self {
+        let mut roles: Vec<_> = config
+            .roles
+            .iter()
+            .map(|(role, binding)| (role.clone(), binding.clone()))
+            .collect();
+        roles.sort_by(|a, b| a.0.cmp(&b.0));
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| Rule {
+                roles: rule.roles.clone(),
+                paths: rule.paths.clone(),
+                methods: (!rule.methods.is_empty()).then(|| {
+                    rule.methods
+                        .iter()
+                        .filter_map(|m| Method::from_bytes(m.to_ascii_uppercase().as_bytes()).ok())
+                        .collect()
+                }),
+            })
+            .collect();
+        Self { roles, rules }
+    }
+
+    /// Roles held by a user, in name order.
+    pub fn roles_of(&self, user: &AuthenticatedUser) -> Vec<&str> {
+        self.roles
+            .iter()
+            .filter(|(_, binding)| {
+                binding
+                    .users
+                    .iter()
+                    .any(|name| name == "*" || *name == user.name)
+                    || binding
+                        .groups
+                        .iter()
+                        .any(|wanted| user.groups.iter().any(|group| group_matches(group, wanted)))
+            })
+            .map(|(role, _)| role.as_str())
+            .collect()
+    }
+
+    /// Allow the request, or give the reason it is denied.
+    pub fn evaluate(
+        &self,
+        user: Option<&AuthenticatedUser>,
+        method: &Method,
+        path: &str,
+    ) -> Result<(), String> {
+        let Some(user) = user else {
+            return Err(format!("anonymous request may not {method} {path}"));
+        };
+        let roles = self.roles_of(user);
+        let allowed = self.rules.iter().any(|rule| {
+            rule.matches(method, path) && rule.roles.iter().any(|r| roles.contains(&r.as_str()))
+        });
+        if allowed {
+            Ok(())
+        } else if roles.is_empty() {
+            Err(format!("user '{}' has no roles", user.name))
+        } else {
+            Err(format!(
+                "user '{}' (roles: {}) may not {method} {path}",
+                user.name,
+                roles.join(", ")
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::config::RbacRule;
+
+    fn user(name: &str, groups: &[&str]) -> AuthenticatedUser {
+        AuthenticatedUser {
+            name: name.to_string(),
+            groups: groups.iter().map(|g| g.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_rbac_policy() {
+        let policy = RbacPolicy::new(&RbacPolicyConfig {
+            roles: HashMap::from([
+                (
+                    "admin".to_string(),
+                    RbacRoleBinding {
+                        users: vec!["alice".to_string()],
+                        groups: vec!["ops".to_string()],
+                    },
+                ),
+                (
+                    "reader".to_string(),
+                    RbacRoleBinding {
+                        users: vec!["*".to_string()],
+                        groups: vec![],
+                    },
+                ),
+            ]),
+            rules: vec![
+                RbacRule {
+                    roles: vec!["reader".to_string()],
+                    paths: vec![],
+                    methods: vec!["get".to_string(), "HEAD".to_string()],
+                },
+                RbacRule {
+                    roles: vec!["admin".to_string()],
+                    paths: vec!["/admin/*".to_string(), "/reports".to_string()],
+                    methods: vec![],
+                },
+            ],
+            ..RbacPolicyConfig::default()
+        });
+
+        let alice = user("alice", &[]);
+        let ops = user("carol", &["CN=Ops,OU=Groups,DC=example,DC=com"]);
+        let bob = user("bob", &["cn=devops,ou=groups,dc=example,dc=com"]);
+        assert_eq!(policy.roles_of(&alice), vec!["admin", "reader"]);
+        assert_eq!(policy.roles_of(&ops), vec!["admin", "reader"]);
+        assert_eq!(policy.roles_of(&bob), vec!["reader"]);
+
+        assert!(
+            policy
+                .evaluate(Some(&bob), &Method::GET, "/admin/x")
+                .is_ok()
+        );
+        assert!(
+            policy
+                .evaluate(Some(&ops), &Method::DELETE, "/admin/users/1")
+                .is_ok()
+        );
+        assert!(
+            policy
+                .evaluate(Some(&alice), &Method::POST, "/reports")
+                .is_ok()
+        );
+        assert!(
+            policy
+                .evaluate(Some(&alice), &Method::POST, "/reports/1")
+                .is_err()
+        );
+        assert_eq!(
+            policy.evaluate(Some(&bob), &Method::POST, "/admin/x"),
+            Err("user 'bob' (roles: reader) may not POST /admin/x".to_string())
+        );
+        assert_eq!(
+            policy.evaluate(None, &Method::GET, "/"),
+            Err("anonymous request may not GET /".to_string())
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Retry decisions for proxied requests.
+//!
+//! A [`RetryBudget`] caps retries at a share of a route's recent requests,
+//! counted over a sliding window of one-second buckets. When a backend
+//! struggles, every failing request would otherwise turn into several, and
+//! the extra load keeps it from recovering; with a budget the gateway stops
+//! retrying and passes failures through instead.
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use http::Method;
+use rand::RngExt;
+
+use crate::config::models::{NonIdempotentRetry, RetryBudgetConfig};
+
+/// Requests and retries seen during one second
+#[derive(Debug, Clone, Copy, Default)]
+struct Bucket {
+    second: u64,
+    requests: u64,
+    retries: u64,
+}
+
+/// Sliding window of requests and retries on one route.
+#[derive(Debug)]
+pub struct RetryBudget {
+    ratio: f64,
+    min_retries: u64,
+    started: Instant,
+    buckets: Mutex<Vec<Bucket>>,
+}
+
+impl RetryBudget {
+    pub fn new(config: &RetryBudgetConfig) -> Self {
+        Self {
+            ratio: config.ratio,
+            min_retries: u64::from(config.min_retries),
+            started: Instant::now(),
+            buckets: Mutex::new(vec![Bucket::default(); config.window_secs.max(1) as usize]),
+        }
+    }
+
+    /// Count a request sent to the route.
+    pub fn record_request(&self, now: Instant) {
+        self.with_bucket(now, |_, bucket| bucket.requests += 1);
+    }
+
+    /// Take a retry from the budget; returns `false` when it is used up.
+    pub fn try_retry(&self, now: Instant) -> bool {
+        let (ratio, min_retries) = (self.ratio, self.min_retries);
+        self.with_bucket(now, |buckets, bucket| {
+            let (requests, retries) = buckets.iter().fold((0, 0), |(requests, retries), b| {
+                (requests + b.requests, retries + b.retries)
+            });
+            let allowed = ((requests as f64 * ratio) as u64).max(min_retries);
+            if retries < allowed {
+                bucket.retries += 1;
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Run `f` with the window's live buckets and the bucket for `now`.
+    fn with_bucket<T>(&self, now: Instant, f: impl FnOnce(&[Bucket], &mut Bucket) -> T) -> T {
+        let second = now.saturating_duration_since(self.started).as_secs();
+        let mut buckets = match self.buckets.lock() {
+            Ok(buckets) => buckets,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let window = buckets.len() as u64;
+        for bucket in buckets.iter_mut() {
+            if bucket.second + window <= second {
+                *bucket = Bucket {
+                    second,
+                    ..Bucket::default()
+                };
+            }
+        }
+        let index = (second % window) as usize;
+        if buckets[index].second != second {
+            buckets[index] = Bucket {
+                second,
+                ..Bucket::default()
+            };
+        }
+        let mut current = buckets[index];
+        let result = f(&buckets, &mut current);
+        buckets[index] = current;
+        result
+    }
+}
+
+/// Whether requests with `method` are only reads (RFC 9110 safe methods),
+/// so sending them twice has no side effects at all
+pub fn is_safe_method(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+/// Whether requests with `method` can be sent again without side effects
+/// (RFC 9110 idempotent methods).
+pub fn is_idempotent_method(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+    )
+}
+
+/// Whether a request with `method` may be retried under `policy`, given
+/// whether it carries an idempotency key.
+pub fn is_retryable_method(method: &Method, policy: NonIdempotentRetry, has_key: bool) -> bool {
+    is_idempotent_method(method)
+        || match policy {
+            NonIdempotentRetry::Never => false,
+            NonIdempotentRetry::WithKey => has_key,
+            NonIdempotentRetry::Always => true,
+        }
+}
+
+/// Delay before retry number `retry` (starting at 1): `base` doubled for
+/// each earlier retry, with up to half of it taken off at random so retries
+/// from many clients spread out.
+pub fn backoff(base: Duration, retry: u32) -> Duration {
+    let delay = base.saturating_mul(1 << retry.saturating_sub(1).min(10));
+    let jitter = rand::rng().random_range(0.0..=0.5);
+    delay.mul_f64(1.0 - jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget(ratio: f64, min_retries: u32) -> RetryBudget {
+        RetryBudget::new(&RetryBudgetConfig {
+            ratio,
+            min_retries,
+            window_secs: 10,
+        })
+    }
+
+    #[test]
+    fn test_budget_limits_retries_to_a_share_of_requests() {
+        let budget = budget(0.2, 1);
+        let now = Instant::now();
+        for _ in 0..20 {
+            budget.record_request(now);
+        }
+        let allowed = (0..10).filter(|_| budget.try_retry(now)).count();
+        assert_eq!(allowed, 4);
+
+        // Retries become available again once the window has moved on
+        let later = now + Duration::from_secs(11);
+        assert!(budget.try_retry(later));
+        assert!(!budget.try_retry(later));
+    }
+
+    #[test]
+    fn test_budget_always_allows_min_retries() {
+        let budget = budget(0.0, 2);
+        let now = Instant::now();
+        budget.record_request(now);
+        assert!(budget.try_retry(now));
+        assert!(budget.try_retry(now + Duration::from_secs(3)));
+        assert!(!budget.try_retry(now + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_retryable_methods_and_backoff() {
+        use NonIdempotentRetry::*;
+
+        assert!(is_retryable_method(&Method::GET, Never, false));
+        assert!(is_retryable_method(&Method::PUT, Never, false));
+        assert!(!is_retryable_method(&Method::POST, Never, true));
+        assert!(!is_retryable_method(&Method::PATCH, WithKey, false));
+        assert!(is_retryable_method(&Method::POST, WithKey, true));
+        assert!(is_retryable_method(&Method::PATCH, Always, false));
+
+        let base = Duration::from_millis(100);
+        for _ in 0..20 {
+            let delay = backoff(base, 3);
+            assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400));
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! Time-based route behavior.
+//!
+//! A [`RouteSchedule`] is compiled from a route's `schedule` when the gateway
+//! is built and consulted on every request. Times are evaluated in a fixed
+//! UTC offset, so a schedule written for a zone with daylight saving time
+//! needs updating when the clocks change.
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveTime, Utc, Weekday};
+
+use crate::config::models::{RouteScheduleConfig, ScheduleWindow};
+
+/// How a scheduled route behaves at a given moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleState<'a> {
+    Active,
+    Inactive,
+    /// Active, served by these targets instead of the route's own
+    Targets(&'a [String]),
+}
+
+#[derive(Debug)]
+struct Window {
+    /// Weekdays the daily window starts on, by `num_days_from_monday`
+    days: [bool; 7],
+    daily: Option<(NaiveTime, NaiveTime)>,
+    from: Option<DateTime<FixedOffset>>,
+    until: Option<DateTime<FixedOffset>>,
+    active: bool,
+    targets: Vec<String>,
+}
+
+impl Window {
+    fn compile(window: &ScheduleWindow) -> Result<Self, String> {
+        let mut days = [window.days.is_empty(); 7];
+        for day in &window.days {
+            let weekday: Weekday = day
+                .parse()
+                .map_err(|_| format!("invalid weekday '{day}'"))?;
+            days[weekday.num_days_from_monday() as usize] = true;
+        }
+        let time = |value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M")
+                .map_err(|_| format!("invalid time '{value}', expected HH:MM"))
+        };
+        let daily = match (&window.start, &window.end) {
+            (Some(start), Some(end)) => Some((time(start)?, time(end)?)),
+            (None, None) if window.days.is_empty() => None,
+            (None, None) => Some((NaiveTime::MIN, NaiveTime::MIN)),
+            _ => return Err("start and end must be set together".to_string()),
+        };
+        let instant = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|value| {
+                    DateTime::parse_from_rfc3339(value)
+                        .map_err(|_| format!("invalid RFC 3339 time '{value}'"))
+                })
+                .transpose()
+        };
+        let (from, until) = (instant(&window.from)?, instant(&window.until)?);
+        if let (Some(from), Some(until)) = (from, until)
+            && from >= until
+        {
+            return Err("from must be before until".to_string());
+        }
+        if daily.is_none() && from.is_none() && until.is_none() {
+            return Err("a window needs days, start/end, or from/until".to_string());
+        }
+        Ok(Self {
+            days,
+            daily,
+            from,
+            until,
+            active: window.active || !window.targets.is_empty(),
+            targets: window.targets.clone(),
+        })
+    }
+
+    fn covers(&self, now: DateTime<FixedOffset>) -> bool {
+        if self.from.is_some_and(|from| now < from) || self.until.is_some_and(|until| now >= until)
+        {
+            return false;
+        }
+        let Some((start, end)) = self.daily else {
+            return true;
+        };
+        let starts_on =
+            |date: DateTime<FixedOffset>| self.days[date.weekday().num_days_from_monday() as usize];
+        let time = now.time();
+        if start < end {
+            starts_on(now) && start <= time && time < end
+        } else {
+            // Past midnight, the window belongs to the day it started on
+            (starts_on(now) && time >= start) || (starts_on(now - Duration::days(1)) && time < end)
+        }
+    }
+}
+
+/// Compiled `schedule` of a route.
+#[derive(Debug)]
+pub struct RouteSchedule {
+    offset: FixedOffset,
+    active_outside_windows: bool,
+    windows: Vec<Window>,
+}
+
+impl RouteSchedule {
+    pub fn compile(config: &RouteScheduleConfig) -> Result<Self, String> {
+        let offset = parse_offset(&config.timezone)
+            .ok_or_else(|| format!("invalid timezone '{}'", config.timezone))?;
+        let windows = config
+            .windows
+            .iter()
+            .enumerate()
+            .map(|(i, window)| Window::compile(window).map_err(|e| format!("windows[{i}]: {e}")))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            offset,
+            active_outside_windows: config.active_outside_windows,
+            windows,
+        })
+    }
+
+    /// The route's behavior right now.
+    pub fn state(&self) -> ScheduleState<'_> {
+        self.state_at(Utc::now())
+    }
+
+    pub fn state_at(&self, now: DateTime<Utc>) -> ScheduleState<'_> {
+        let now = now.with_timezone(&self.offset);
+        match self.windows.iter().find(|window| window.covers(now)) {
+            Some(window) if !window.targets.is_empty() => ScheduleState::Targets(&window.targets),
+            Some(window) if window.active => ScheduleState::Active,
+            Some(_) => ScheduleState::Inactive,
+            None if self.active_outside_windows => ScheduleState::Active,
+            None => ScheduleState::Inactive,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state() != ScheduleState::Inactive
+    }
+}
+
+/// `UTC`, `Z`, or a `+HH:MM` / `-HH:MM` offset
+fn parse_offset(timezone: &str) -> Option<FixedOffset> {
+    if timezone.eq_ignore_ascii_case("utc") || timezone == "Z" {
+        return FixedOffset::east_opt(0);
+    }
+    let (sign, rest) = match timezone.as_bytes().first()? {
+        b'+' => (1, &timezone[1..]),
+        b'-' => (-1, &timezone[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn schedule(timezone: &str, windows: Vec<ScheduleWindow>) -> RouteSchedule {
+        RouteSchedule::compile(&RouteScheduleConfig {
+            timezone: timezone.to_string(),
+            active_outside_windows: true,
+            windows,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_nightly_window_past_midnight() {
+        let maintenance = vec!["http://maintenance:8080".to_string()];
+        let schedule = schedule(
+            "+02:00",
+            vec![ScheduleWindow {
+                days: vec!["sat".to_string()],
+                start: Some("23:00".to_string()),
+                end: Some("02:00".to_string()),
+                targets: maintenance.clone(),
+                ..ScheduleWindow::default()
+            }],
+        );
+        // 2026-10-17 is a Saturday
+        let state = |time| schedule.state_at(at(time));
+        assert_eq!(state("2026-10-17T20:59:00Z"), ScheduleState::Active);
+        assert_eq!(
+            state("2026-10-17T21:00:00Z"),
+            ScheduleState::Targets(&maintenance)
+        );
+        assert_eq!(
+            state("2026-10-17T23:30:00Z"),
+            ScheduleState::Targets(&maintenance)
+        );
+        assert_eq!(state("2026-10-18T00:00:00Z"), ScheduleState::Active);
+        // Friday night is not covered
+        assert_eq!(state("2026-10-16T21:30:00Z"), ScheduleState::Active);
+    }
+
+    #[test]
+    fn test_absolute_range_and_business_hours() {
+        let freeze = schedule(
+            "UTC",
+            vec![ScheduleWindow {
+                from: Some("2026-12-24T00:00:00Z".to_string()),
+                until: Some("2026-12-27T00:00:00+00:00".to_string()),
+                ..ScheduleWindow::default()
+            }],
+        );
+        assert_eq!(
+            freeze.state_at(at("2026-12-25T12:00:00Z")),
+            ScheduleState::Inactive
+        );
+        assert_eq!(
+            freeze.state_at(at("2026-12-27T00:00:00Z")),
+            ScheduleState::Active
+        );
+
+        let office = RouteSchedule::compile(&RouteScheduleConfig {
+            timezone: "-05:00".to_string(),
+            active_outside_windows: false,
+            windows: vec![ScheduleWindow {
+                days: ["mon", "tue", "wed", "thu", "fri"]
+                    .map(String::from)
+                    .to_vec(),
+                start: Some("09:00".to_string()),
+                end: Some("17:00".to_string()),
+                active: true,
+                ..ScheduleWindow::default()
+            }],
+        })
+        .unwrap();
+        assert_eq!(
+            office.state_at(at("2026-10-16T14:00:00Z")),
+            ScheduleState::Active
+        );
+        assert_eq!(
+            office.state_at(at("2026-10-16T22:00:00Z")),
+            ScheduleState::Inactive
+        );
+        assert_eq!(
+            office.state_at(at("2026-10-17T14:00:00Z")),
+            ScheduleState::Inactive
+        );
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let compile = |timezone: &str, window: ScheduleWindow| {
+            RouteSchedule::compile(&RouteScheduleConfig {
+                timezone: timezone.to_string(),
+                active_outside_windows: true,
+                windows: vec![window],
+            })
+        };
+        let daily = ScheduleWindow {
+            start: Some("01:00".to_string()),
+            end: Some("02:00".to_string()),
+            ..ScheduleWindow::default()
+        };
+        assert!(compile("+05:30", daily.clone()).is_ok());
+        assert!(compile("Europe/Berlin", daily.clone()).is_err());
+        assert!(compile("UTC", ScheduleWindow::default()).is_err());
+        let err = compile(
+            "UTC",
+            ScheduleWindow {
+                end: None,
+                ..daily.clone()
+            },
+        )
+        .unwrap_err();
+        assert!(err.contains("start and end"));
+        assert!(
+            compile(
+                "UTC",
+                ScheduleWindow {
+                    days: vec!["someday".to_string()],
+                    ..daily
+                }
+            )
+            .is_err()
+        );
+    }
+}
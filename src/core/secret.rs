@@ -0,0 +1,24 @@
+//! Comparing secrets such as passwords, tokens and credential digests.
+
+/// Compare secrets without short-circuiting on the first differing byte.
+///
+/// Only the length can be learned from the timing, which for hashes and
+/// digests is public anyway.
+pub fn constant_time_eq(a: impl AsRef<[u8]>, b: impl AsRef<[u8]>) -> bool {
+    let (a, b) = (a.as_ref(), b.as_ref());
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "secret2"));
+        assert!(constant_time_eq([7u8; 32], [7u8; 32]));
+        assert!(!constant_time_eq([7u8; 32], [8u8; 32]));
+    }
+}
@@ -0,0 +1,86 @@
+//! Traffic steering weights pushed by an external controller.
+//!
+//! A global controller (for example one measuring latency between regions)
+//! can assign each backend a relative weight through the admin API. Backend
+//! selection multiplies these with local factors such as slow start, and
+//! health filtering still applies first, so steering never sends traffic to a
+//! backend this gateway considers unhealthy. Each weight expires after its
+//! TTL, so a controller that stops reporting cannot pin traffic forever.
+use std::time::{Duration, Instant};
+
+use scc::HashMap;
+
+/// Steering weights keyed by backend URL.
+///
+/// A weight of `1.0` is neutral, `0.0` drains the backend while others can
+/// take its traffic, and larger values attract proportionally more.
+#[derive(Default)]
+pub struct SteeringTable {
+    weights: HashMap<String, (f64, Instant)>,
+}
+
+impl SteeringTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `backend`'s weight until `ttl` elapses.
+    pub fn set(&self, backend: &str, weight: f64, ttl: Duration) {
+        let expires_at = Instant::now() + ttl;
+        self.weights
+            .upsert_sync(backend.to_string(), (weight, expires_at));
+    }
+
+    /// Remove every weight.
+    pub fn clear(&self) {
+        self.weights.clear_sync();
+    }
+
+    /// The unexpired weight for `backend`, if one was pushed.
+    pub fn weight(&self, backend: &str, now: Instant) -> Option<f64> {
+        self.weights
+            .read_sync(backend, |_, (weight, expires_at)| {
+                (*expires_at > now).then_some(*weight)
+            })
+            .flatten()
+    }
+
+    /// Unexpired weights with their remaining lifetime, dropping expired
+    /// entries.
+    pub fn snapshot(&self) -> Vec<(String, f64, Duration)> {
+        let now = Instant::now();
+        self.weights
+            .retain_sync(|_, (_, expires_at)| *expires_at > now);
+        let mut entries = Vec::new();
+        self.weights.iter_sync(|backend, (weight, expires_at)| {
+            entries.push((backend.clone(), *weight, *expires_at - now));
+            true
+        });
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weights_expire() {
+        let table = SteeringTable::new();
+        table.set("http://a", 0.25, Duration::from_secs(60));
+        table.set("http://b", 2.0, Duration::ZERO);
+
+        let now = Instant::now();
+        assert_eq!(table.weight("http://a", now), Some(0.25));
+        assert_eq!(table.weight("http://b", now), None);
+        assert_eq!(table.weight("http://c", now), None);
+
+        let snapshot = table.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].0, "http://a");
+
+        table.clear();
+        assert!(table.snapshot().is_empty());
+    }
+}
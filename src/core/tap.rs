@@ -0,0 +1,293 @@
+//! Recording of backend exchanges for debugging ("tap").
+//!
+//! A tap keeps the most recent request/response pairs a route exchanged
+//! with its backends in a bounded ring buffer, so integration problems can
+//! be inspected through the admin API without turning on debug logging or
+//! capturing traffic. Taps are started by a route's `tap` setting or through
+//! the admin API. Those from the configuration follow reloads; those started
+//! through the API stay until stopped.
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use scc::HashMap;
+use serde::Serialize;
+
+use crate::config::TapConfig;
+
+/// How a tap was started
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TapSource {
+    Config,
+    Admin,
+}
+
+/// A recorded body, cut off after the tap's `max_body_bytes`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TapBody {
+    /// The captured bytes, as text when they are UTF-8 and base64 otherwise
+    pub content: String,
+    /// `utf8` or `base64`
+    pub encoding: &'static str,
+    /// Bytes the body had in total
+    pub size: u64,
+    pub truncated: bool,
+}
+
+impl TapBody {
+    /// Record `captured`, the first bytes of a body of `size` bytes
+    pub fn new(captured: &[u8], size: u64) -> Self {
+        let (content, encoding) = match std::str::from_utf8(captured) {
+            Ok(text) => (text.to_string(), "utf8"),
+            Err(_) => {
+                use base64::Engine;
+                (
+                    base64::engine::general_purpose::STANDARD.encode(captured),
+                    "base64",
+                )
+            }
+        };
+        Self {
+            content,
+            encoding,
+            size,
+            truncated: size > captured.len() as u64,
+        }
+    }
+}
+
+/// The request as sent to the backend
+#[derive(Debug, Clone, Serialize)]
+pub struct TapRequest {
+    pub method: String,
+    pub uri: String,
+    pub headers: Vec<(String, String)>,
+    pub body: TapBody,
+}
+
+/// The response as received from the backend
+#[derive(Debug, Clone, Serialize)]
+pub struct TapResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: TapBody,
+}
+
+/// One recorded request/response pair
+#[derive(Debug, Clone, Serialize)]
+pub struct TapExchange {
+    /// Increasing per tap, so clients can poll for new exchanges
+    pub id: u64,
+    /// RFC 3339 time the request was sent
+    pub started_at: String,
+    pub request_id: Option<String>,
+    pub backend: String,
+    /// Milliseconds until both bodies were finished with
+    pub duration_ms: u64,
+    pub request: TapRequest,
+    /// Missing when the backend request failed
+    pub response: Option<TapResponse>,
+    pub error: Option<String>,
+}
+
+/// A ring buffer of one route's recent exchanges
+#[derive(Debug)]
+pub struct Tap {
+    config: TapConfig,
+    source: TapSource,
+    exchanges: Mutex<VecDeque<TapExchange>>,
+    next_id: AtomicU64,
+}
+
+impl Tap {
+    fn new(config: TapConfig, source: TapSource) -> Self {
+        Self {
+            config,
+            source,
+            exchanges: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn config(&self) -> &TapConfig {
+        &self.config
+    }
+
+    pub fn source(&self) -> TapSource {
+        self.source
+    }
+
+    /// Whether `name`'s value is replaced with `[redacted]`
+    pub fn redacts(&self, name: &str) -> bool {
+        self.config
+            .redact_headers
+            .iter()
+            .any(|redacted| redacted.eq_ignore_ascii_case(name))
+    }
+
+    /// Add `exchange`, assigning its id and dropping the oldest one when
+    /// the buffer is full.
+    pub fn record(&self, mut exchange: TapExchange) {
+        if self.config.capacity == 0 {
+            return;
+        }
+        exchange.id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut exchanges = match self.exchanges.lock() {
+            Ok(exchanges) => exchanges,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        while exchanges.len() >= self.config.capacity {
+            exchanges.pop_front();
+        }
+        exchanges.push_back(exchange);
+    }
+
+    /// Recorded exchanges, oldest first
+    pub fn exchanges(&self) -> Vec<TapExchange> {
+        match self.exchanges.lock() {
+            Ok(exchanges) => exchanges.iter().cloned().collect(),
+            Err(poisoned) => poisoned.into_inner().iter().cloned().collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.exchanges.lock().map_or(0, |exchanges| exchanges.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Active taps keyed by route label (prefix, plus `@host` for host-specific
+/// routes).
+#[derive(Debug, Default)]
+pub struct TapRegistry {
+    taps: HashMap<String, Arc<Tap>>,
+}
+
+impl TapRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match the configuration's taps to `configured`. Taps whose settings
+    /// did not change keep their exchanges; taps started through the admin
+    /// API are left alone.
+    pub fn sync_config(&self, configured: &[(String, TapConfig)]) {
+        self.taps.retain_sync(|route, tap| {
+            tap.source == TapSource::Admin || configured.iter().any(|(r, _)| r == route)
+        });
+        for (route, config) in configured {
+            let unchanged = self
+                .taps
+                .read_sync(route, |_, tap| {
+                    tap.source == TapSource::Admin || tap.config == *config
+                })
+                .unwrap_or(false);
+            if !unchanged {
+                self.taps.upsert_sync(
+                    route.clone(),
+                    Arc::new(Tap::new(config.clone(), TapSource::Config)),
+                );
+            }
+        }
+    }
+
+    /// Start (or restart, discarding its exchanges) a tap on `route`.
+    pub fn start(&self, route: &str, config: TapConfig) {
+        self.taps.upsert_sync(
+            route.to_string(),
+            Arc::new(Tap::new(config, TapSource::Admin)),
+        );
+    }
+
+    /// Stop the tap on `route`, returning whether there was one.
+    pub fn stop(&self, route: &str) -> bool {
+        self.taps.remove_sync(route).is_some()
+    }
+
+    /// The tap on `route`, if any
+    pub fn get(&self, route: &str) -> Option<Arc<Tap>> {
+        self.taps.read_sync(route, |_, tap| tap.clone())
+    }
+
+    /// Every active tap, sorted by route
+    pub fn list(&self) -> Vec<(String, Arc<Tap>)> {
+        let mut taps = Vec::new();
+        self.taps.iter_sync(|route, tap| {
+            taps.push((route.clone(), tap.clone()));
+            true
+        });
+        taps.sort_by(|a, b| a.0.cmp(&b.0));
+        taps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange() -> TapExchange {
+        TapExchange {
+            id: 0,
+            started_at: String::new(),
+            request_id: None,
+            backend: "http://backend".to_string(),
+            duration_ms: 1,
+            request: TapRequest {
+                method: "GET".to_string(),
+                uri: "/".to_string(),
+                headers: Vec::new(),
+                body: TapBody::new(b"", 0),
+            },
+            response: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_tap_keeps_the_latest_exchanges() {
+        let registry = TapRegistry::new();
+        let config = TapConfig {
+            capacity: 2,
+            ..TapConfig::default()
+        };
+        registry.sync_config(&[("/api".to_string(), config.clone())]);
+        let tap = registry.get("/api").unwrap();
+        for _ in 0..3 {
+            tap.record(exchange());
+        }
+        let ids: Vec<u64> = tap.exchanges().iter().map(|e| e.id).collect();
+        assert_eq!(ids, [2, 3]);
+        assert!(tap.redacts("Authorization"));
+
+        // Unchanged settings keep the exchanges; dropped routes lose the tap
+        registry.sync_config(&[("/api".to_string(), config)]);
+        assert_eq!(registry.get("/api").unwrap().len(), 2);
+        registry.start("/debug", TapConfig::default());
+        registry.sync_config(&[]);
+        assert!(registry.get("/api").is_none());
+        assert_eq!(registry.get("/debug").unwrap().source(), TapSource::Admin);
+        assert!(registry.stop("/debug"));
+        assert!(registry.list().is_empty());
+    }
+
+    #[test]
+    fn test_tap_body_encoding() {
+        let body = TapBody::new(b"hello", 11);
+        assert_eq!(body.content, "hello");
+        assert_eq!(body.encoding, "utf8");
+        assert!(body.truncated);
+
+        let body = TapBody::new(&[0xff, 0x00], 2);
+        assert_eq!(body.content, "/wA=");
+        assert_eq!(body.encoding, "base64");
+        assert!(!body.truncated);
+    }
+}
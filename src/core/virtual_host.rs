@@ -0,0 +1,84 @@
+//! Hostname matching for virtual hosts.
+//!
+//! Hostnames are matched exactly or against `*.example.com` wildcards, which
+//! cover names with exactly one label before `.example.com`:
+//! `api.example.com`, but neither `example.com` nor `a.b.example.com`. An
+//! exact name wins over a wildcard. Names are compared without regard to case.
+use std::collections::HashMap;
+
+/// The patterns `host` is looked up under, most specific first: the host
+/// itself, then the wildcard for its parent domain.
+pub fn host_candidates(host: &str) -> impl Iterator<Item = String> + '_ {
+    let host = host.trim_end_matches('.');
+    let wildcard = host
+        .split_once('.')
+        .filter(|(label, _)| *label != "*")
+        .map(|(_, parent)| format!("*.{parent}"));
+    std::iter::once(host.to_string()).chain(wildcard)
+}
+
+/// Values keyed by hostname pattern
+#[derive(Debug, Clone)]
+pub struct HostTable<T> {
+    entries: HashMap<String, T>,
+}
+
+impl<T> Default for HostTable<T> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<T> HostTable<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `value` for `pattern`, an exact hostname or `*.` wildcard,
+    /// replacing any value it had.
+    pub fn insert(&mut self, pattern: &str, value: T) {
+        self.entries.insert(pattern.to_lowercase(), value);
+    }
+
+    /// The value for the most specific pattern matching `host`
+    pub fn get(&self, host: &str) -> Option<&T> {
+        let host = host.to_lowercase();
+        host_candidates(&host).find_map(|candidate| self.entries.get(&candidate))
+    }
+
+    /// The value added for exactly `pattern`
+    pub fn get_pattern(&self, pattern: &str) -> Option<&T> {
+        self.entries.get(&pattern.to_lowercase())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_names_win_over_wildcards() {
+        let candidates: Vec<String> = host_candidates("a.b.example.com").collect();
+        assert_eq!(candidates, ["a.b.example.com", "*.b.example.com"]);
+        let candidates: Vec<String> = host_candidates("*.example.com").collect();
+        assert_eq!(candidates, ["*.example.com"]);
+
+        let mut table = HostTable::new();
+        table.insert("*.Example.com", "wildcard");
+        table.insert("*.eu.example.com", "eu");
+        table.insert("www.example.com", "www");
+        assert_eq!(table.get("WWW.example.com"), Some(&"www"));
+        assert_eq!(table.get("api.example.com"), Some(&"wildcard"));
+        // A wildcard covers a single label
+        assert_eq!(table.get("a.b.example.com"), None);
+        assert_eq!(table.get("shop.eu.example.com"), Some(&"eu"));
+        assert_eq!(table.get("example.com"), None);
+        assert_eq!(table.get("example.org"), None);
+    }
+}
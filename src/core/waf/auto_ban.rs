@@ -0,0 +1,288 @@
+//! Automatic IP banning
+//!
+//! Counts offenses (WAF detections, 4xx responses) per client IP in fixed
+//! windows and bans an IP for a while once it reaches a threshold. Bans are
+//! kept in a [`BanList`] that outlives configuration reloads; the thresholds
+//! come from the current configuration's [`AutoBanPolicy`].
+
+use std::{
+    net::IpAddr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use scc::{HashMap, hash_map::Entry};
+
+use super::ip_filter::IpNetwork;
+use crate::config::AutoBanConfig;
+
+/// Something a client did that counts towards a ban
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Offense {
+    /// The WAF detected a threat in a request
+    WafViolation,
+    /// A request was answered with a 4xx status
+    ClientError,
+}
+
+impl Offense {
+    /// Label used for metrics and logs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Offense::WafViolation => "waf",
+            Offense::ClientError => "client_errors",
+        }
+    }
+}
+
+/// Compiled auto-ban settings
+#[derive(Debug, Clone)]
+pub struct AutoBanPolicy {
+    window: Duration,
+    ban_duration: Duration,
+    waf_violations: u32,
+    client_errors: u32,
+    max_tracked: usize,
+    exempt: Vec<IpNetwork>,
+}
+
+impl AutoBanPolicy {
+    /// Build a policy, or `None` when auto-banning is disabled
+    pub fn from_config(config: &AutoBanConfig) -> Result<Option<Self>, String> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let exempt = config
+            .exempt
+            .iter()
+            .map(|entry| IpNetwork::parse(entry))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(Self {
+            window: Duration::from_secs(config.window_secs),
+            ban_duration: Duration::from_secs(config.ban_secs),
+            waf_violations: config.waf_violations,
+            client_errors: config.client_errors,
+            max_tracked: config.max_tracked_ips,
+            exempt,
+        }))
+    }
+
+    /// Counting window, used when sweeping stale counters
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    fn threshold(&self, offense: Offense) -> u32 {
+        match offense {
+            Offense::WafViolation => self.waf_violations,
+            Offense::ClientError => self.client_errors,
+        }
+    }
+
+    fn is_exempt(&self, ip: IpAddr) -> bool {
+        self.exempt.iter().any(|network| network.contains(ip))
+    }
+}
+
+/// Offense counts of one IP in its current window
+struct Strikes {
+    window_start: Instant,
+    waf_violations: u32,
+    client_errors: u32,
+}
+
+impl Strikes {
+    fn count(&mut self, offense: Offense) -> &mut u32 {
+        match offense {
+            Offense::WafViolation => &mut self.waf_violations,
+            Offense::ClientError => &mut self.client_errors,
+        }
+    }
+}
+
+/// Banned IPs and the offense counters that lead to bans
+#[derive(Default)]
+pub struct BanList {
+    strikes: HashMap<IpAddr, Strikes>,
+    /// Tracked in an atomic because `HashMap::len` walks the whole table
+    tracked: AtomicUsize,
+    bans: HashMap<IpAddr, Instant>,
+}
+
+impl BanList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// When `ip`'s ban ends, if it is banned at `now`
+    pub fn banned_until(&self, ip: IpAddr, now: Instant) -> Option<Instant> {
+        self.bans
+            .read_sync(&ip, |_, until| (*until > now).then_some(*until))
+            .flatten()
+    }
+
+    /// Count an offense by `ip`; returns true if it got `ip` banned.
+    pub fn record(
+        &self,
+        ip: IpAddr,
+        offense: Offense,
+        policy: &AutoBanPolicy,
+        now: Instant,
+    ) -> bool {
+        let threshold = policy.threshold(offense);
+        if threshold == 0 || policy.is_exempt(ip) || self.banned_until(ip, now).is_some() {
+            return false;
+        }
+
+        let reached = match self.strikes.entry_sync(ip) {
+            Entry::Occupied(mut entry) => {
+                let strikes = entry.get_mut();
+                if now.duration_since(strikes.window_start) >= policy.window {
+                    *strikes = Strikes {
+                        window_start: now,
+                        waf_violations: 0,
+                        client_errors: 0,
+                    };
+                }
+                let count = strikes.count(offense);
+                *count += 1;
+                if *count >= threshold {
+                    let _ = entry.remove();
+                    self.tracked.fetch_sub(1, Ordering::Relaxed);
+                    true
+                } else {
+                    false
+                }
+            }
+            Entry::Vacant(entry) => {
+                if threshold == 1 {
+                    true
+                } else if self.tracked.load(Ordering::Relaxed) >= policy.max_tracked {
+                    false
+                } else {
+                    let mut strikes = Strikes {
+                        window_start: now,
+                        waf_violations: 0,
+                        client_errors: 0,
+                    };
+                    *strikes.count(offense) = 1;
+                    entry.insert_entry(strikes);
+                    self.tracked.fetch_add(1, Ordering::Relaxed);
+                    false
+                }
+            }
+        };
+
+        if reached {
+            self.bans.upsert_sync(ip, now + policy.ban_duration);
+        }
+        reached
+    }
+
+    /// Lift `ip`'s ban; returns whether it was banned.
+    pub fn unban(&self, ip: IpAddr) -> bool {
+        self.bans.remove_sync(&ip).is_some()
+    }
+
+    /// Drop expired bans and counters whose window has passed, returning
+    /// the number of active bans.
+    pub fn sweep(&self, window: Duration, now: Instant) -> usize {
+        self.bans.retain_sync(|_, until| *until > now);
+        let mut dropped = 0;
+        self.strikes.retain_sync(|_, strikes| {
+            let keep = now.duration_since(strikes.window_start) < window;
+            if !keep {
+                dropped += 1;
+            }
+            keep
+        });
+        self.tracked.fetch_sub(dropped, Ordering::Relaxed);
+        self.bans.len()
+    }
+
+    /// Active bans with their remaining duration, sorted by IP
+    pub fn snapshot(&self, now: Instant) -> Vec<(IpAddr, Duration)> {
+        let mut bans = Vec::new();
+        self.bans.iter_sync(|ip, until| {
+            if *until > now {
+                bans.push((*ip, *until - now));
+            }
+            true
+        });
+        bans.sort_unstable_by_key(|(ip, _)| *ip);
+        bans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(waf_violations: u32, client_errors: u32) -> AutoBanPolicy {
+        AutoBanPolicy::from_config(&AutoBanConfig {
+            enabled: true,
+            waf_violations,
+            client_errors,
+            ban_secs: 60,
+            window_secs: 10,
+            max_tracked_ips: 2,
+            exempt: vec!["10.0.0.0/8".to_string()],
+        })
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bans_after_threshold() {
+        let bans = BanList::new();
+        let policy = policy(3, 0);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let now = Instant::now();
+
+        assert!(!bans.record(ip, Offense::WafViolation, &policy, now));
+        assert!(!bans.record(ip, Offense::WafViolation, &policy, now));
+        // Client errors are not counted when their threshold is 0
+        assert!(!bans.record(ip, Offense::ClientError, &policy, now));
+        assert!(bans.record(ip, Offense::WafViolation, &policy, now));
+        assert_eq!(
+            bans.banned_until(ip, now),
+            Some(now + Duration::from_secs(60))
+        );
+        assert_eq!(bans.snapshot(now), vec![(ip, Duration::from_secs(60))]);
+
+        // The ban ends on schedule and the sweep forgets it
+        let later = now + Duration::from_secs(61);
+        assert_eq!(bans.banned_until(ip, later), None);
+        assert_eq!(bans.sweep(policy.window(), later), 0);
+
+        // Exempt networks are never banned
+        let internal: IpAddr = "10.1.2.3".parse().unwrap();
+        for _ in 0..5 {
+            assert!(!bans.record(internal, Offense::WafViolation, &policy, now));
+        }
+    }
+
+    #[test]
+    fn test_window_and_tracking_bound() {
+        let bans = BanList::new();
+        let policy = policy(2, 2);
+        let now = Instant::now();
+        let ip = |last: u8| IpAddr::from([192, 0, 2, last]);
+
+        // Offenses in separate windows do not add up
+        assert!(!bans.record(ip(1), Offense::ClientError, &policy, now));
+        let next_window = now + Duration::from_secs(10);
+        assert!(!bans.record(ip(1), Offense::ClientError, &policy, next_window));
+        assert!(bans.record(ip(1), Offense::ClientError, &policy, next_window));
+
+        // At most `max_tracked_ips` IPs are counted
+        assert!(!bans.record(ip(2), Offense::ClientError, &policy, now));
+        assert!(!bans.record(ip(3), Offense::ClientError, &policy, now));
+        assert!(!bans.record(ip(4), Offense::ClientError, &policy, now));
+        assert!(!bans.record(ip(4), Offense::ClientError, &policy, now));
+        assert!(bans.record(ip(2), Offense::ClientError, &policy, now));
+
+        assert!(bans.unban(ip(2)));
+        assert_eq!(bans.banned_until(ip(2), now), None);
+    }
+}
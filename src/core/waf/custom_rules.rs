@@ -0,0 +1,145 @@
+//! User-defined WAF rules
+//!
+//! Matches operator-supplied regexes against a chosen part of the request.
+
+use axum::http::{HeaderMap, Uri, header};
+use regex::Regex;
+
+use super::{SecurityRule, SecurityViolation, ThreatLevel};
+use crate::config::{WafCustomRule, WafRuleTarget};
+
+/// A compiled custom rule
+struct CompiledRule {
+    name: String,
+    pattern: Regex,
+    target: WafRuleTarget,
+    block_mode: bool,
+}
+
+/// Custom rule detector
+pub struct CustomRuleDetector {
+    rules: Vec<CompiledRule>,
+}
+
+impl CustomRuleDetector {
+    /// Compile `rules`, failing on the first invalid pattern
+    pub fn new(rules: &[WafCustomRule]) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let pattern = Regex::new(&rule.pattern)
+                    .map_err(|e| format!("Invalid pattern for rule '{}': {e}", rule.name))?;
+                Ok(CompiledRule {
+                    name: rule.name.clone(),
+                    pattern,
+                    target: rule.target,
+                    block_mode: rule.block_mode,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { rules })
+    }
+
+    fn matches(rule: &CompiledRule, uri: &Uri, headers: &HeaderMap, body: Option<&[u8]>) -> bool {
+        match rule.target {
+            WafRuleTarget::Uri => {
+                let uri_str = uri.to_string();
+                let decoded =
+                    urlencoding::decode(&uri_str).unwrap_or(std::borrow::Cow::Borrowed(&uri_str));
+                rule.pattern.is_match(&decoded)
+            }
+            WafRuleTarget::Headers => headers
+                .values()
+                .filter_map(|v| v.to_str().ok())
+                .any(|v| rule.pattern.is_match(v)),
+            WafRuleTarget::UserAgent => headers
+                .get(header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| rule.pattern.is_match(v)),
+            WafRuleTarget::Body => body
+                .and_then(|b| std::str::from_utf8(b).ok())
+                .is_some_and(|b| rule.pattern.is_match(b)),
+        }
+    }
+}
+
+impl SecurityRule for CustomRuleDetector {
+    fn check(
+        &self,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: Option<&[u8]>,
+    ) -> Result<(), SecurityViolation> {
+        // A blocking match wins over an earlier log-only one
+        let mut logged = None;
+        for rule in &self.rules {
+            if !Self::matches(rule, uri, headers, body) {
+                continue;
+            }
+            let violation = SecurityViolation::new(
+                "CUSTOM_RULE",
+                ThreatLevel::Medium,
+                format!("Custom rule '{}' matched", rule.name),
+                rule.block_mode,
+            );
+            if rule.block_mode {
+                return Err(violation);
+            }
+            logged.get_or_insert(violation);
+        }
+        logged.map_or(Ok(()), Err)
+    }
+
+    fn name(&self) -> &str {
+        "Custom Rules"
+    }
+
+    fn is_enabled(&self) -> bool {
+        !self.rules.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn rule(name: &str, pattern: &str, target: WafRuleTarget, block_mode: bool) -> WafCustomRule {
+        WafCustomRule {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            target,
+            block_mode,
+        }
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let detector = CustomRuleDetector::new(&[
+            rule("legacy", r"^/old/", WafRuleTarget::Uri, false),
+            rule("wp_probe", r"(?i)wp-login\.php", WafRuleTarget::Uri, true),
+            rule("scanner", r"(?i)nikto", WafRuleTarget::UserAgent, true),
+        ])
+        .unwrap();
+        let headers = HeaderMap::new();
+
+        let uri: Uri = "/blog/wp-login%2Ephp".parse().unwrap();
+        let violation = detector.check(&uri, &headers, None).unwrap_err();
+        assert!(violation.blocked);
+        assert!(violation.description.contains("wp_probe"));
+
+        let uri: Uri = "/old/wp-login.php".parse().unwrap();
+        assert!(detector.check(&uri, &headers, None).unwrap_err().blocked);
+
+        let uri: Uri = "/old/page".parse().unwrap();
+        assert!(!detector.check(&uri, &headers, None).unwrap_err().blocked);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("user-agent", HeaderValue::from_static("Nikto/2.1"));
+        let uri: Uri = "/".parse().unwrap();
+        assert!(detector.check(&uri, &headers, None).is_err());
+
+        assert!(CustomRuleDetector::new(&[rule("bad", "(", WafRuleTarget::Body, true)]).is_err());
+    }
+}
@@ -6,10 +6,11 @@ use axum::http::{HeaderMap, Uri};
 use tracing::{debug, warn};
 
 use super::{
-    BotDetector, CommandInjectionDetector, IpFilter, PathTraversalDetector, SecurityRule,
-    SecurityViolation, SqlInjectionDetector, XssDetector,
+    BotDetector, CommandInjectionDetector, CustomRuleDetector, IpFilter, LoadedRuleSet,
+    PathTraversalDetector, SecurityRule, SecurityViolation, SqlInjectionDetector, XmlInspector,
+    XssDetector,
 };
-use crate::config::WafConfig;
+use crate::{config::WafConfig, core::multipart};
 
 /// WAF engine that runs all enabled security rules
 pub struct WafEngine {
@@ -21,10 +22,16 @@ pub struct WafEngine {
     command_injection: Option<CommandInjectionDetector>,
     /// Path traversal detector
     path_traversal: Option<PathTraversalDetector>,
+    /// XML body inspector
+    xml: Option<XmlInspector>,
     /// Bot detector
     bot_detector: Option<BotDetector>,
     /// IP filter
     ip_filter: Option<Arc<IpFilter>>,
+    /// Custom regex rules
+    custom_rules: Option<CustomRuleDetector>,
+    /// External rule file merged into the configuration, if any
+    rule_set: Option<Arc<LoadedRuleSet>>,
     /// Whether WAF is enabled globally
     enabled: bool,
 }
@@ -65,6 +72,12 @@ impl WafEngine {
             None
         };
 
+        let xml = if config.xml.enabled {
+            Some(XmlInspector::new(config.xml.enabled, config.xml.block_mode))
+        } else {
+            None
+        };
+
         let bot_detector = if config.bot_detection.enabled {
             let mut detector = BotDetector::new(
                 config.bot_detection.enabled,
@@ -108,17 +121,38 @@ impl WafEngine {
             None
         };
 
+        let custom_rules = if config.custom_rules.is_empty() {
+            None
+        } else {
+            Some(CustomRuleDetector::new(&config.custom_rules).map_err(|e| eyre::eyre!(e))?)
+        };
+
         Ok(Self {
             sql_injection,
             xss,
             command_injection,
             path_traversal,
+            xml,
             bot_detector,
             ip_filter,
+            custom_rules,
+            rule_set: None,
             enabled: config.enabled,
         })
     }
 
+    /// Create a WAF engine from configuration extended by an external rule set
+    pub fn with_rule_set(config: &WafConfig, rule_set: Arc<LoadedRuleSet>) -> eyre::Result<Self> {
+        let mut engine = Self::from_config(&rule_set.rules.apply(config))?;
+        engine.rule_set = Some(rule_set);
+        Ok(engine)
+    }
+
+    /// The external rule set this engine was built with
+    pub fn rule_set(&self) -> Option<&Arc<LoadedRuleSet>> {
+        self.rule_set.as_ref()
+    }
+
     /// Check a request against all enabled rules
     ///
     /// Returns Ok(()) if no violations are detected, or Err(SecurityViolation) if a violation is found.
@@ -133,7 +167,18 @@ impl WafEngine {
         if !self.enabled {
             return Ok(());
         }
+        self.inspect_request(uri, headers, body, client_ip)
+    }
 
+    /// Like [`Self::check_request`], but also when the engine is disabled,
+    /// for virtual hosts that turn the WAF on for themselves
+    pub fn inspect_request(
+        &self,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: Option<&[u8]>,
+        client_ip: Option<&str>,
+    ) -> Result<(), SecurityViolation> {
         // Check IP filter first (most efficient to fail fast)
         if let Some(ref filter) = self.ip_filter
             && let Some(ip) = client_ip
@@ -167,6 +212,50 @@ impl WafEngine {
             }
         }
 
+        // Inspect only the text fields of forms, not uploaded files. Check XML
+        // bodies for DTDs, then inspect their decoded text rather than markup.
+        let decoded_body;
+        let mut body = body;
+        if let Some(bytes) = body
+            && let Some(boundary) = multipart::form_boundary(headers)
+            && let Ok(parts) = multipart::parse(bytes, &boundary)
+        {
+            let fields: Vec<String> = parts
+                .iter()
+                .filter(|part| !part.is_file())
+                .map(|part| String::from_utf8_lossy(part.data).into_owned())
+                .collect();
+            decoded_body = fields.join(" ");
+            body = Some(decoded_body.as_bytes());
+        } else if let Some(ref inspector) = self.xml
+            && let Some(bytes) = body
+            && XmlInspector::is_xml(headers, bytes)
+        {
+            if let Err(violation) = inspector.check(uri, headers, Some(bytes)) {
+                if violation.blocked {
+                    warn!(
+                        uri = %uri,
+                        threat_type = violation.threat_type,
+                        "XML external entity attempt detected"
+                    );
+                    return Err(violation);
+                } else {
+                    debug!(
+                        uri = %uri,
+                        threat_type = violation.threat_type,
+                        "XML external entity attempt detected (log only)"
+                    );
+                }
+            }
+            if let Some(text) = std::str::from_utf8(bytes)
+                .ok()
+                .and_then(XmlInspector::extract_text)
+            {
+                decoded_body = text;
+                body = Some(decoded_body.as_bytes());
+            }
+        }
+
         // Check SQL injection
         if let Some(ref detector) = self.sql_injection
             && let Err(violation) = detector.check(uri, headers, body)
@@ -247,6 +336,26 @@ impl WafEngine {
             }
         }
 
+        // Check custom rules
+        if let Some(ref detector) = self.custom_rules
+            && let Err(violation) = detector.check(uri, headers, body)
+        {
+            if violation.blocked {
+                warn!(
+                    uri = %uri,
+                    rule = violation.description,
+                    "Custom WAF rule matched"
+                );
+                return Err(violation);
+            } else {
+                debug!(
+                    uri = %uri,
+                    rule = violation.description,
+                    "Custom WAF rule matched (log only)"
+                );
+            }
+        }
+
         Ok(())
     }
 
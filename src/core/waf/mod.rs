@@ -5,26 +5,36 @@
 //! - XSS (Cross-Site Scripting)
 //! - Command injection
 //! - Path traversal
+//! - XML external entities, with XML bodies decoded for the other checks
 //! - Bot detection
-//! - IP filtering
+//! - IP filtering, with automatic temporary bans
+//! - Custom regex rules, optionally loaded from a reloadable rule file
 
+pub mod auto_ban;
 pub mod body_inspector;
 pub mod bot_detector;
 pub mod command_injection;
+pub mod custom_rules;
 pub mod engine;
 pub mod ip_filter;
 pub mod path_traversal;
+pub mod rule_set;
 pub mod sql_injection;
+pub mod xml_inspector;
 pub mod xss_detector;
 
+pub use auto_ban::{AutoBanPolicy, BanList, Offense};
 use axum::http::{HeaderMap, Uri};
 pub use body_inspector::BodyInspector;
 pub use bot_detector::{BotDetector, BotType};
 pub use command_injection::CommandInjectionDetector;
+pub use custom_rules::CustomRuleDetector;
 pub use engine::WafEngine;
 pub use ip_filter::IpFilter;
 pub use path_traversal::PathTraversalDetector;
+pub use rule_set::LoadedRuleSet;
 pub use sql_injection::SqlInjectionDetector;
+pub use xml_inspector::XmlInspector;
 pub use xss_detector::XssDetector;
 
 /// Threat level classification for security violations
@@ -0,0 +1,31 @@
+//! WAF rules loaded from an external file
+//!
+//! The file is read by an adapter; this module only carries the parsed rules
+//! along with what identifies them, so `/status` can show which set is active.
+
+use chrono::{DateTime, Utc};
+
+use crate::config::WafRuleSet;
+
+/// A parsed rule file and the metadata identifying its content
+#[derive(Debug, Clone)]
+pub struct LoadedRuleSet {
+    /// Path the rules were read from
+    pub path: String,
+    /// SHA-256 of the file content, hex encoded
+    pub checksum: String,
+    /// When this content was loaded
+    pub loaded_at: DateTime<Utc>,
+    /// The parsed rules
+    pub rules: WafRuleSet,
+}
+
+impl LoadedRuleSet {
+    /// The rule file's declared version, or a checksum prefix when it has none
+    pub fn version(&self) -> &str {
+        self.rules
+            .version
+            .as_deref()
+            .unwrap_or(&self.checksum[..self.checksum.len().min(12)])
+    }
+}
@@ -0,0 +1,338 @@
+//! XML/SOAP body inspection module
+//!
+//! Flags document type declarations, through which XML external entity (XXE)
+//! and entity expansion attacks are delivered, and extracts the decoded text
+//! of XML bodies so the other detectors see payloads hidden behind character
+//! references or CDATA sections rather than markup. Entities are never
+//! expanded: only the five predefined entities and numeric character
+//! references are decoded.
+
+use axum::http::{HeaderMap, Uri, header};
+
+use super::{SecurityRule, SecurityViolation, ThreatLevel};
+
+/// XML body inspector
+pub struct XmlInspector {
+    /// Whether XML inspection is enabled
+    pub enabled: bool,
+    /// Whether to block requests with a DTD (true) or just log (false)
+    pub block_mode: bool,
+}
+
+impl XmlInspector {
+    /// Create a new XML inspector
+    pub fn new(enabled: bool, block_mode: bool) -> Self {
+        Self {
+            enabled,
+            block_mode,
+        }
+    }
+
+    /// Whether the body is XML, by `Content-Type` (`application/xml`,
+    /// `text/xml`, `*/*+xml`) or by its leading declaration.
+    pub fn is_xml(headers: &HeaderMap, body: &[u8]) -> bool {
+        let declared = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|media_type| media_type.trim().to_ascii_lowercase())
+            .is_some_and(|media_type| {
+                media_type == "application/xml"
+                    || media_type == "text/xml"
+                    || media_type.ends_with("+xml")
+            });
+        declared || {
+            let start = body.trim_ascii_start();
+            start.starts_with(b"<?xml") || starts_with_ignore_case(start, b"<!DOCTYPE")
+        }
+    }
+
+    /// The decoded text content and attribute values of an XML document,
+    /// joined by spaces; `None` when the document is not well-formed enough to
+    /// scan, in which case the raw body should be inspected instead.
+    pub fn extract_text(body: &str) -> Option<String> {
+        let mut values = Vec::new();
+        let mut rest = body;
+        while !rest.is_empty() {
+            let Some(start) = rest.find('<') else {
+                push_text(&mut values, rest);
+                break;
+            };
+            push_text(&mut values, &rest[..start]);
+            rest = &rest[start..];
+
+            if let Some(after) = rest.strip_prefix("<!--") {
+                rest = &after[after.find("-->")? + 3..];
+            } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+                let end = after.find("]]>")?;
+                if !after[..end].trim().is_empty() {
+                    values.push(after[..end].to_string());
+                }
+                rest = &after[end + 3..];
+            } else if let Some(after) = rest.strip_prefix("<?") {
+                rest = &after[after.find("?>")? + 2..];
+            } else if rest.starts_with("<!") {
+                rest = &rest[declaration_end(rest)?..];
+            } else {
+                let end = tag_end(rest)?;
+                push_attributes(&mut values, &rest[1..end]);
+                rest = &rest[end + 1..];
+            }
+        }
+        Some(values.join(" "))
+    }
+}
+
+fn starts_with_ignore_case(bytes: &[u8], prefix: &[u8]) -> bool {
+    bytes.len() >= prefix.len() && bytes[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+fn push_text(values: &mut Vec<String>, text: &str) {
+    if !text.trim().is_empty() {
+        values.push(decode_references(text));
+    }
+}
+
+/// Offset just past the `>` closing a tag, skipping `>` inside quoted
+/// attribute values.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '>') => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Offset just past a `<!...>` declaration, including a DOCTYPE internal
+/// subset in brackets.
+fn declaration_end(declaration: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    for (index, c) in declaration.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '[') => depth += 1,
+            (None, ']') => depth = depth.saturating_sub(1),
+            (None, '>') if depth == 0 => return Some(index + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Push the decoded values of `name="value"` pairs in a tag's contents.
+fn push_attributes(values: &mut Vec<String>, tag: &str) {
+    let mut rest = tag;
+    while let Some(start) = rest.find(['"', '\'']) {
+        let quote = rest[start..].chars().next().unwrap_or('"');
+        let after = &rest[start + 1..];
+        let Some(end) = after.find(quote) else {
+            break;
+        };
+        push_text(values, &after[..end]);
+        rest = &after[end + 1..];
+    }
+}
+
+/// Decode predefined entities and character references; other references are
+/// left as written.
+fn decode_references(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let reference = &rest[1..end];
+            let c = match reference {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => reference
+                    .strip_prefix("#x")
+                    .or_else(|| reference.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| reference.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Find a document type declaration and describe it.
+fn find_doctype(body: &[u8]) -> Option<&'static str> {
+    let upper = body.to_ascii_uppercase();
+    let at = upper.windows(9).position(|w| w == b"<!DOCTYPE")?;
+    let declaration = &upper[at..];
+    let contains = |needle: &[u8]| declaration.windows(needle.len()).any(|w| w == needle);
+    Some(if contains(b"<!ENTITY") {
+        if contains(b"SYSTEM") || contains(b"PUBLIC") {
+            "XML document declares external entities"
+        } else {
+            "XML document declares entities"
+        }
+    } else if contains(b"SYSTEM") || contains(b"PUBLIC") {
+        "XML document references an external DTD"
+    } else {
+        "XML document contains a document type declaration"
+    })
+}
+
+impl SecurityRule for XmlInspector {
+    fn check(
+        &self,
+        _uri: &Uri,
+        headers: &HeaderMap,
+        body: Option<&[u8]>,
+    ) -> Result<(), SecurityViolation> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let Some(body) = body.filter(|body| Self::is_xml(headers, body)) else {
+            return Ok(());
+        };
+        match find_doctype(body) {
+            Some(description) => Err(SecurityViolation::new(
+                "XXE",
+                ThreatLevel::Critical,
+                description,
+                self.block_mode,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "XML Inspector"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+    use crate::{
+        config::{WafConfig, WafRuleConfig},
+        core::waf::WafEngine,
+    };
+
+    fn xml_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/soap+xml; charset=utf-8"),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_doctype_is_flagged() {
+        let inspector = XmlInspector::new(true, true);
+        let uri: Uri = "/soap".parse().expect("valid uri");
+        let xxe = br#"<?xml version="1.0"?>
+<!DOCTYPE foo [<!ENTITY xxe SYSTEM "file:///etc/passwd">]>
+<foo>&xxe;</foo>"#;
+        let violation = inspector
+            .check(&uri, &xml_headers(), Some(xxe))
+            .unwrap_err();
+        assert_eq!(violation.threat_type, "XXE");
+        assert!(violation.description.contains("external entities"));
+
+        // Sniffed without a Content-Type
+        let bomb = br#"<!doctype lolz [<!ENTITY lol "lol">]><lolz>&lol;</lolz>"#;
+        assert!(
+            inspector
+                .check(&uri, &HeaderMap::new(), Some(bomb))
+                .is_err()
+        );
+
+        let plain = b"<foo>ok</foo>";
+        assert!(inspector.check(&uri, &xml_headers(), Some(plain)).is_ok());
+        assert!(
+            inspector
+                .check(&uri, &HeaderMap::new(), Some(b"{\"a\": \"<!DOCTYPE\"}"))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_extract_text() {
+        let body = r#"<?xml version="1.0"?>
+<!-- comment -->
+<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope">
+  <soap:Body>
+    <q name="a &gt; b">&#39; OR &#x31;=1 --</q>
+    <s><![CDATA[<script>alert(1)</script>]]></s>
+    <e a='&unknown;'/>
+  </soap:Body>
+</soap:Envelope>"#;
+        assert_eq!(
+            XmlInspector::extract_text(body).as_deref(),
+            Some(
+                "http://www.w3.org/2003/05/soap-envelope a > b ' OR 1=1 -- \
+                 <script>alert(1)</script> &unknown;"
+            )
+        );
+        assert_eq!(XmlInspector::extract_text("<a>x</a"), None);
+        assert_eq!(XmlInspector::extract_text("<a><![CDATA[x</a>"), None);
+    }
+
+    #[test]
+    fn test_engine_inspects_decoded_xml() {
+        let rule = WafRuleConfig {
+            enabled: true,
+            block_mode: true,
+        };
+        let engine = WafEngine::from_config(&WafConfig {
+            enabled: true,
+            sql_injection: rule.clone(),
+            xss: rule.clone(),
+            command_injection: rule.clone(),
+            xml: rule,
+            ..WafConfig::default()
+        })
+        .expect("engine");
+        let uri: Uri = "/soap".parse().expect("valid uri");
+        let check =
+            |body: &str| engine.check_request(&uri, &xml_headers(), Some(body.as_bytes()), None);
+
+        // Markup alone (`</` looks like a shell redirection) is not a threat
+        assert!(check("<order>\n  <id>42</id>\n  <note>hello</note>\n</order>").is_ok());
+        let encoded_sqli = "<q>&#39; OR &#39;1&#39;=&#39;1&#39; --</q>";
+        assert_eq!(
+            check(encoded_sqli).unwrap_err().threat_type,
+            "SQL_INJECTION"
+        );
+        let cdata_xss = "<s><![CDATA[<script>alert(1)</script>]]></s>";
+        assert_eq!(check(cdata_xss).unwrap_err().threat_type, "XSS");
+        let xxe = "<!DOCTYPE d [<!ENTITY e SYSTEM \"http://evil/\">]><d>&e;</d>";
+        assert_eq!(check(xxe).unwrap_err().threat_type, "XXE");
+    }
+}
@@ -19,15 +19,16 @@
 //!
 //! # Quick Example
 //! ```no_run
-//! use std::sync::Arc;
-//!
-//! use axon::{GatewayService, config::ServerConfig};
+//! use axon::{Server, config::ServerConfig};
 //!
 //! # #[tokio::main] async fn main() -> eyre::Result<()> {
 //! // Load a configuration (see examples/configs/*.toml)
 //! let cfg: ServerConfig = axon::config::loader::load_config("config.toml").await?;
-//! let gateway = Arc::new(GatewayService::new(Arc::new(cfg)));
-//! // You would normally wire this into the provided HttpHandler adapter (see binary crate)
+//! let server = Server::builder(cfg).build().await?;
+//! // Reload or stop the gateway from elsewhere through the handle
+//! let handle = server.handle();
+//! server.run().await?;
+//! # drop(handle);
 //! # Ok(()) }
 //! ```
 //!
@@ -59,9 +60,11 @@
 //!
 //! See README for more extensive usage patterns.
 // Re-export public modules with explicit visibility controls
+pub mod build_info;
 pub mod config;
 pub mod metrics;
 pub mod ports;
+pub mod server;
 pub mod tracing_setup;
 pub mod utils;
 
@@ -74,5 +77,6 @@ pub use crate::{
     adapters::{FileSystemAdapter, HealthChecker, HttpClientAdapter, HttpHandler},
     core::GatewayService,
     ports::http_client::HttpClient,
+    server::{Server, ServerBuilder, ServerHandle},
     utils::{ConnectionTracker, GracefulShutdown},
 };
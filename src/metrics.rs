@@ -10,21 +10,59 @@
 //! * `axon_backend_health_status` (gauge per backend)
 //! * `axon_active_connections` (gauge)
 //! * `axon_active_requests` (gauge)
+//! * `axon_upstream_connections_opened_total` (counter per backend)
+//! * `axon_upstream_connections_open` (gauge)
+//! * `axon_upstream_requests_in_flight` (gauge)
+//! * `axon_rate_limit_allowed_total` / `axon_rate_limit_rejected_total`
+//!   (counters per route and limit name)
+//! * `axon_rate_limit_configured_requests` (gauge per route and limit name)
+//! * `axon_rate_limit_keys` / `axon_rate_limit_key_evictions_total` (keys
+//!   tracked and evicted by keyed limiters)
+//! * `axon_health_check_duration_seconds` (histogram per backend and outcome)
+//! * `axon_backend_in_flight_requests` / `axon_backend_pending_requests`
+//!   (gauges per limited backend)
+//! * `axon_backend_saturated_total` (counter per backend and outcome)
+//! * `axon_retries_total` (counter per route and outcome)
+//! * `axon_failovers_total` (counter per route and reason)
+//! * `axon_tunnels_active` / `axon_tunnels_closed_total` /
+//!   `axon_tunnel_bytes_total` (open tunnels, closed tunnels per reason and
+//!   bytes per direction, per route)
+//! * `axon_unmatched_requests_total` (counter per outcome: `default_route`
+//!   or `not_found`)
+//! * `axon_telemetry_dropped_total` (counter per reason, local `/metrics` only)
+//! * `axon_build_info` (gauge fixed at 1; version, commit, build time and
+//!   features as labels)
+//! * `axon_config_generation` (gauge)
 //!
 //! The `*_timer` structs leverage `Drop` to record durations safely even when
 //! early returns or errors occur.
 
-use std::{collections::HashMap, sync::Mutex, time::Instant};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        Mutex,
+        atomic::{AtomicI64, AtomicU64, Ordering},
+    },
+    time::Instant,
+};
 
 use once_cell::sync::Lazy;
 use opentelemetry::{
     KeyValue, global,
     metrics::{Counter, Gauge, Histogram},
 };
+use serde::Serialize;
+
+use crate::{
+    adapters::notifications::{Notification, notify},
+    config::models::{
+        ExportErrorLog, HistogramConfig, MetricsConfig, MetricsExportConfig, NotificationEvent,
+    },
+};
 
 // Axon-specific metric names
 pub const AXON_BACKEND_HEALTH_STATUS: &str = "axon_backend_health_status";
-pub const AXON_REQUESTS_TOTAL: &str = "axon_requests_total"; // labels: path, method, status, protocol
+pub const AXON_REQUESTS_TOTAL: &str = "axon_requests_total"; // labels: path (route or template), method, status, protocol
 pub const AXON_REQUEST_DURATION_SECONDS: &str = "axon_request_duration_seconds"; // labels: path, method, protocol
 pub const AXON_HTTP3_REQUESTS_TOTAL: &str = "axon_http3_requests_total"; // dedicated http3 counter (experimental)
 pub const AXON_HTTP3_REQUEST_DURATION_SECONDS: &str = "axon_http3_request_duration_seconds"; // experimental
@@ -38,6 +76,55 @@ pub const AXON_WEBSOCKET_BYTES_TOTAL: &str = "axon_websocket_bytes_total"; // la
 pub const AXON_WEBSOCKET_CLOSE_CODES_TOTAL: &str = "axon_websocket_close_codes_total"; // labels: code
 pub const AXON_WAF_VIOLATIONS_TOTAL: &str = "axon_waf_violations_total"; // labels: threat_type, threat_level, blocked
 pub const AXON_WAF_CHECKS_TOTAL: &str = "axon_waf_checks_total"; // labels: result
+pub const AXON_UPSTREAM_CONNECTIONS_OPENED_TOTAL: &str = "axon_upstream_connections_opened_total"; // labels: backend
+pub const AXON_UPSTREAM_CONNECT_FAILURES_TOTAL: &str = "axon_upstream_connect_failures_total"; // labels: backend, family
+pub const AXON_UPSTREAM_CONNECTIONS_OPEN: &str = "axon_upstream_connections_open";
+pub const AXON_UPSTREAM_REQUESTS_IN_FLIGHT: &str = "axon_upstream_requests_in_flight";
+pub const AXON_CANARY_ROLLBACKS_TOTAL: &str = "axon_canary_rollbacks_total"; // labels: route, reason
+pub const AXON_COALESCED_REQUESTS_TOTAL: &str = "axon_coalesced_requests_total"; // labels: route, outcome
+pub const AXON_HEDGED_REQUESTS_TOTAL: &str = "axon_hedged_requests_total"; // labels: route, outcome
+pub const AXON_EXPERIMENT_REQUESTS_TOTAL: &str = "axon_experiment_requests_total"; // labels: experiment, variant, status
+pub const AXON_EXPERIMENT_REQUEST_DURATION_SECONDS: &str =
+    "axon_experiment_request_duration_seconds"; // labels: experiment, variant
+pub const AXON_WASM_FILTER_CALLS_TOTAL: &str = "axon_wasm_filter_calls_total"; // labels: filter, phase, outcome
+pub const AXON_WASM_FILTER_DURATION_SECONDS: &str = "axon_wasm_filter_duration_seconds"; // labels: filter, phase
+pub const AXON_UPLOAD_SCANS_TOTAL: &str = "axon_upload_scans_total"; // labels: outcome
+pub const AXON_UPLOAD_SCAN_DURATION_SECONDS: &str = "axon_upload_scan_duration_seconds"; // labels: outcome
+pub const AXON_BACKEND_BODY_LENGTH_MISMATCH_TOTAL: &str = "axon_backend_body_length_mismatch_total"; // labels: backend, kind
+pub const AXON_RATE_LIMIT_ALLOWED_TOTAL: &str = "axon_rate_limit_allowed_total"; // labels: route, limiter
+pub const AXON_RATE_LIMIT_REJECTED_TOTAL: &str = "axon_rate_limit_rejected_total"; // labels: route, limiter
+pub const AXON_RATE_LIMIT_KEYS: &str = "axon_rate_limit_keys"; // labels: route, limiter
+pub const AXON_RATE_LIMIT_KEY_EVICTIONS_TOTAL: &str = "axon_rate_limit_key_evictions_total"; // labels: route, limiter
+pub const AXON_HEALTH_CHECK_DURATION_SECONDS: &str = "axon_health_check_duration_seconds"; // labels: backend, outcome
+pub const AXON_RATE_LIMIT_CONFIGURED_REQUESTS: &str = "axon_rate_limit_configured_requests"; // labels: route, limiter, period
+pub const AXON_BACKEND_IN_FLIGHT_REQUESTS: &str = "axon_backend_in_flight_requests"; // labels: backend
+pub const AXON_BACKEND_PENDING_REQUESTS: &str = "axon_backend_pending_requests"; // labels: backend
+pub const AXON_BACKEND_SATURATED_TOTAL: &str = "axon_backend_saturated_total"; // labels: backend, outcome
+pub const AXON_TELEMETRY_DROPPED_TOTAL: &str = "axon_telemetry_dropped_total"; // labels: reason
+pub const AXON_AUDIT_RECORDS_DROPPED_TOTAL: &str = "axon_audit_records_dropped_total"; // labels: reason
+pub const AXON_USAGE_DROPPED_TOTAL: &str = "axon_usage_dropped_total"; // labels: reason
+pub const AXON_EVENTS_DROPPED_TOTAL: &str = "axon_events_dropped_total"; // labels: reason
+pub const AXON_AUTO_BANS_TOTAL: &str = "axon_auto_bans_total"; // labels: reason
+pub const AXON_AUTO_BANNED_IPS: &str = "axon_auto_banned_ips";
+pub const AXON_HTTP3_CONNECTIONS_TOTAL: &str = "axon_http3_connections_total"; // labels: outcome
+pub const AXON_HTTP3_ACTIVE_CONNECTIONS: &str = "axon_http3_active_connections";
+pub const AXON_HTTP3_STREAM_BLOCKED_SECONDS_TOTAL: &str = "axon_http3_stream_blocked_seconds_total"; // labels: direction
+pub const AXON_REQUEST_BODY_BYTES: &str = "axon_request_body_bytes"; // labels: protocol
+pub const AXON_RESPONSE_BODY_BYTES: &str = "axon_response_body_bytes"; // labels: protocol
+pub const AXON_REQUEST_BYTES_TOTAL: &str = "axon_request_bytes_total"; // labels: route, direction
+pub const AXON_RESPONSE_BYTES_TOTAL: &str = "axon_response_bytes_total"; // labels: route, direction
+pub const AXON_SLOW_REQUESTS_TOTAL: &str = "axon_slow_requests_total"; // labels: route
+pub const AXON_RETRIES_TOTAL: &str = "axon_retries_total"; // labels: route, outcome
+pub const AXON_FAILOVERS_TOTAL: &str = "axon_failovers_total"; // labels: route, reason
+pub const AXON_CLIENT_DISCONNECTS_TOTAL: &str = "axon_client_disconnects_total"; // labels: route, phase
+pub const AXON_MALFORMED_REQUESTS_TOTAL: &str = "axon_malformed_requests_total"; // labels: reason
+pub const AXON_SCHEMA_VALIDATION_FAILURES_TOTAL: &str = "axon_schema_validation_failures_total"; // labels: route, schema, direction
+pub const AXON_TUNNELS_ACTIVE: &str = "axon_tunnels_active"; // labels: route
+pub const AXON_TUNNELS_CLOSED_TOTAL: &str = "axon_tunnels_closed_total"; // labels: route, reason
+pub const AXON_TUNNEL_BYTES_TOTAL: &str = "axon_tunnel_bytes_total"; // labels: route, direction
+pub const AXON_UNMATCHED_REQUESTS_TOTAL: &str = "axon_unmatched_requests_total"; // labels: outcome
+pub const AXON_BUILD_INFO: &str = "axon_build_info"; // labels: version, commit, build_timestamp, features
+pub const AXON_CONFIG_GENERATION: &str = "axon_config_generation";
 
 /// Global meter
 static METER: Lazy<opentelemetry::metrics::Meter> = Lazy::new(|| global::meter("axon"));
@@ -61,6 +148,60 @@ static WAF_VIOLATIONS_TOTAL: Lazy<Counter<u64>> =
     Lazy::new(|| METER.u64_counter(AXON_WAF_VIOLATIONS_TOTAL).build());
 static WAF_CHECKS_TOTAL: Lazy<Counter<u64>> =
     Lazy::new(|| METER.u64_counter(AXON_WAF_CHECKS_TOTAL).build());
+static UPSTREAM_CONNECTIONS_OPENED_TOTAL: Lazy<Counter<u64>> = Lazy::new(|| {
+    METER
+        .u64_counter(AXON_UPSTREAM_CONNECTIONS_OPENED_TOTAL)
+        .build()
+});
+static UPSTREAM_CONNECT_FAILURES_TOTAL: Lazy<Counter<u64>> = Lazy::new(|| {
+    METER
+        .u64_counter(AXON_UPSTREAM_CONNECT_FAILURES_TOTAL)
+        .build()
+});
+static CANARY_ROLLBACKS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_CANARY_ROLLBACKS_TOTAL).build());
+static COALESCED_REQUESTS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_COALESCED_REQUESTS_TOTAL).build());
+static HEDGED_REQUESTS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_HEDGED_REQUESTS_TOTAL).build());
+static EXPERIMENT_REQUESTS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_EXPERIMENT_REQUESTS_TOTAL).build());
+static EXPERIMENT_REQUEST_DURATION_SECONDS: Lazy<Histogram<f64>> = Lazy::new(|| {
+    METER
+        .f64_histogram(AXON_EXPERIMENT_REQUEST_DURATION_SECONDS)
+        .build()
+});
+static WASM_FILTER_CALLS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_WASM_FILTER_CALLS_TOTAL).build());
+static UPLOAD_SCANS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_UPLOAD_SCANS_TOTAL).build());
+static BACKEND_BODY_LENGTH_MISMATCH_TOTAL: Lazy<Counter<u64>> = Lazy::new(|| {
+    METER
+        .u64_counter(AXON_BACKEND_BODY_LENGTH_MISMATCH_TOTAL)
+        .build()
+});
+static RATE_LIMIT_ALLOWED_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_RATE_LIMIT_ALLOWED_TOTAL).build());
+static RATE_LIMIT_REJECTED_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_RATE_LIMIT_REJECTED_TOTAL).build());
+static RATE_LIMIT_KEY_EVICTIONS_TOTAL: Lazy<Counter<u64>> = Lazy::new(|| {
+    METER
+        .u64_counter(AXON_RATE_LIMIT_KEY_EVICTIONS_TOTAL)
+        .build()
+});
+static UNMATCHED_REQUESTS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_UNMATCHED_REQUESTS_TOTAL).build());
+static AUTO_BANS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_AUTO_BANS_TOTAL).build());
+static HTTP3_CONNECTIONS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_HTTP3_CONNECTIONS_TOTAL).build());
+static HTTP3_STREAM_BLOCKED_SECONDS_TOTAL: Lazy<Counter<f64>> = Lazy::new(|| {
+    METER
+        .f64_counter(AXON_HTTP3_STREAM_BLOCKED_SECONDS_TOTAL)
+        .build()
+});
+static BACKEND_SATURATED_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_BACKEND_SATURATED_TOTAL).build());
 
 /// Histograms
 static REQUEST_DURATION_SECONDS: Lazy<Histogram<f64>> =
@@ -70,39 +211,1286 @@ static HTTP3_REQUEST_DURATION_SECONDS: Lazy<Histogram<f64>> = Lazy::new(|| {
         .f64_histogram(AXON_HTTP3_REQUEST_DURATION_SECONDS)
         .build()
 });
+/// Body size buckets: 1 KiB to 256 MiB in powers of eight
+const BODY_SIZE_BOUNDARIES: [f64; 8] = [
+    0.0,
+    1024.0,
+    8192.0,
+    65536.0,
+    524288.0,
+    4194304.0,
+    33554432.0,
+    268435456.0,
+];
+static REQUEST_BODY_BYTES: Lazy<Histogram<u64>> = Lazy::new(|| {
+    METER
+        .u64_histogram(AXON_REQUEST_BODY_BYTES)
+        .with_boundaries(BODY_SIZE_BOUNDARIES.to_vec())
+        .build()
+});
+static RESPONSE_BODY_BYTES: Lazy<Histogram<u64>> = Lazy::new(|| {
+    METER
+        .u64_histogram(AXON_RESPONSE_BODY_BYTES)
+        .with_boundaries(BODY_SIZE_BOUNDARIES.to_vec())
+        .build()
+});
+static REQUEST_BYTES_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_REQUEST_BYTES_TOTAL).build());
+static RESPONSE_BYTES_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_RESPONSE_BYTES_TOTAL).build());
+static SLOW_REQUESTS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_SLOW_REQUESTS_TOTAL).build());
+static RETRIES_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_RETRIES_TOTAL).build());
+static FAILOVERS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_FAILOVERS_TOTAL).build());
+static CLIENT_DISCONNECTS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_CLIENT_DISCONNECTS_TOTAL).build());
+static MALFORMED_REQUESTS_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_MALFORMED_REQUESTS_TOTAL).build());
+static SCHEMA_VALIDATION_FAILURES_TOTAL: Lazy<Counter<u64>> = Lazy::new(|| {
+    METER
+        .u64_counter(AXON_SCHEMA_VALIDATION_FAILURES_TOTAL)
+        .build()
+});
+static TUNNELS_CLOSED_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_TUNNELS_CLOSED_TOTAL).build());
+static TUNNEL_BYTES_TOTAL: Lazy<Counter<u64>> =
+    Lazy::new(|| METER.u64_counter(AXON_TUNNEL_BYTES_TOTAL).build());
 static BACKEND_REQUEST_DURATION_SECONDS: Lazy<Histogram<f64>> = Lazy::new(|| {
     METER
         .f64_histogram(AXON_BACKEND_REQUEST_DURATION_SECONDS)
         .build()
 });
+static WASM_FILTER_DURATION_SECONDS: Lazy<Histogram<f64>> = Lazy::new(|| {
+    METER
+        .f64_histogram(AXON_WASM_FILTER_DURATION_SECONDS)
+        .build()
+});
+static UPLOAD_SCAN_DURATION_SECONDS: Lazy<Histogram<f64>> = Lazy::new(|| {
+    METER
+        .f64_histogram(AXON_UPLOAD_SCAN_DURATION_SECONDS)
+        .build()
+});
+static HEALTH_CHECK_DURATION_SECONDS: Lazy<Histogram<f64>> = Lazy::new(|| {
+    METER
+        .f64_histogram(AXON_HEALTH_CHECK_DURATION_SECONDS)
+        .build()
+});
 
 /// Gauges
 static ACTIVE_CONNECTIONS: Lazy<Gauge<f64>> =
     Lazy::new(|| METER.f64_gauge(AXON_ACTIVE_CONNECTIONS).build());
 static ACTIVE_REQUESTS: Lazy<Gauge<f64>> =
     Lazy::new(|| METER.f64_gauge(AXON_ACTIVE_REQUESTS).build());
+static UPSTREAM_CONNECTIONS_OPEN: Lazy<Gauge<f64>> =
+    Lazy::new(|| METER.f64_gauge(AXON_UPSTREAM_CONNECTIONS_OPEN).build());
+static UPSTREAM_REQUESTS_IN_FLIGHT: Lazy<Gauge<f64>> =
+    Lazy::new(|| METER.f64_gauge(AXON_UPSTREAM_REQUESTS_IN_FLIGHT).build());
+static RATE_LIMIT_CONFIGURED_REQUESTS: Lazy<Gauge<f64>> =
+    Lazy::new(|| METER.f64_gauge(AXON_RATE_LIMIT_CONFIGURED_REQUESTS).build());
+static RATE_LIMIT_KEYS: Lazy<Gauge<u64>> =
+    Lazy::new(|| METER.u64_gauge(AXON_RATE_LIMIT_KEYS).build());
+static BACKEND_IN_FLIGHT_REQUESTS: Lazy<Gauge<f64>> =
+    Lazy::new(|| METER.f64_gauge(AXON_BACKEND_IN_FLIGHT_REQUESTS).build());
+static BACKEND_PENDING_REQUESTS: Lazy<Gauge<f64>> =
+    Lazy::new(|| METER.f64_gauge(AXON_BACKEND_PENDING_REQUESTS).build());
+static BUILD_INFO: Lazy<Gauge<u64>> = Lazy::new(|| METER.u64_gauge(AXON_BUILD_INFO).build());
+static AUTO_BANNED_IPS: Lazy<Gauge<u64>> =
+    Lazy::new(|| METER.u64_gauge(AXON_AUTO_BANNED_IPS).build());
+static HTTP3_ACTIVE_CONNECTIONS: Lazy<Gauge<u64>> =
+    Lazy::new(|| METER.u64_gauge(AXON_HTTP3_ACTIVE_CONNECTIONS).build());
+static TUNNELS_ACTIVE: Lazy<Gauge<u64>> =
+    Lazy::new(|| METER.u64_gauge(AXON_TUNNELS_ACTIVE).build());
+static CONFIG_GENERATION: Lazy<Gauge<u64>> =
+    Lazy::new(|| METER.u64_gauge(AXON_CONFIG_GENERATION).build());
+
+/// Current upstream pool counters backing the gauges above
+static UPSTREAM_OPEN_COUNT: AtomicI64 = AtomicI64::new(0);
+static UPSTREAM_IN_FLIGHT_COUNT: AtomicI64 = AtomicI64::new(0);
+
+/// Per-limiter decision counts and configured quota, keyed by (route, limiter)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimitStats {
+    pub allowed: u64,
+    pub rejected: u64,
+    pub requests: u64,
+    pub period: String,
+    /// Whether the limiter keeps per-key state (IP, header or composite)
+    pub keyed: bool,
+    /// Keys tracked by a keyed limiter
+    pub keys: u64,
+    /// Keys evicted by a keyed limiter
+    pub evictions: u64,
+}
+
+/// Storage backing the rate limit families in the `/metrics` exposition
+static RATE_LIMIT_STATS: Lazy<Mutex<HashMap<(String, String), RateLimitStats>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// How far back the in-process traffic windows reach
+const TRAFFIC_WINDOW: std::time::Duration = std::time::Duration::from_secs(300);
+/// Most recent samples kept per (route, backend) window
+const TRAFFIC_WINDOW_SAMPLES: usize = 1024;
+
+/// Cumulative counts plus a sliding window of `(at, latency_ms, error)` samples
+#[derive(Debug, Default)]
+struct TrafficStats {
+    requests: u64,
+    errors: u64,
+    window: VecDeque<(Instant, f64, bool)>,
+}
+
+impl TrafficStats {
+    fn prune(&mut self, now: Instant) {
+        while self
+            .window
+            .front()
+            .is_some_and(|(at, ..)| now.duration_since(*at) > TRAFFIC_WINDOW)
+        {
+            self.window.pop_front();
+        }
+    }
+}
+
+/// Per (route, backend) traffic served by the admin API
+static TRAFFIC_STATS: Lazy<Mutex<HashMap<(String, String), TrafficStats>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Metric batches dropped instead of exported, keyed by reason. Kept in
+/// process because the OTLP pipeline is the thing that failed.
+static TELEMETRY_DROPPED: Lazy<Mutex<HashMap<&'static str, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Audit records that never reached the sink, keyed by reason.
+static AUDIT_RECORDS_DROPPED: Lazy<Mutex<HashMap<&'static str, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Metered requests and usage records that were never exported, keyed by
+/// reason.
+static USAGE_DROPPED: Lazy<Mutex<HashMap<&'static str, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Request events that were never published, keyed by reason.
+static EVENTS_DROPPED: Lazy<Mutex<HashMap<&'static str, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Automatic bans issued, keyed by reason, and the active ban count as of
+/// the last sweep.
+static UNMATCHED_REQUESTS: Lazy<Mutex<HashMap<&'static str, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static AUTO_BANS: Lazy<Mutex<HashMap<&'static str, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static AUTO_BANNED_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// QUIC connections by handshake outcome, and those currently open.
+static HTTP3_CONNECTIONS: Lazy<Mutex<HashMap<&'static str, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static HTTP3_ACTIVE_COUNT: AtomicU64 = AtomicU64::new(0);
+/// Seconds QUIC request streams spent blocked on flow control, by direction.
+static HTTP3_STREAM_BLOCKED: Lazy<Mutex<HashMap<&'static str, f64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+/// (family, route, direction) of a per-route byte total
+type RouteBytesKey = (&'static str, String, &'static str);
+/// Body bytes per route and direction for the `/metrics` exposition
+static ROUTE_BYTES: Lazy<Mutex<HashMap<RouteBytesKey, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+/// Slow requests per route for the `/metrics` exposition
+static SLOW_REQUESTS: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// Retry decisions per route and outcome for the `/metrics` exposition
+static RETRIES: Lazy<Mutex<HashMap<(String, &'static str), u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+/// Requests sent to fallback targets per route and reason for the `/metrics`
+/// exposition
+static FAILOVERS: Lazy<Mutex<HashMap<(String, &'static str), u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+/// Backend requests cancelled by client disconnects per route and phase for
+/// the `/metrics` exposition
+static CLIENT_DISCONNECTS: Lazy<Mutex<HashMap<(String, &'static str), u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+/// Requests rejected for malformed framing per reason for the `/metrics`
+/// exposition
+static MALFORMED_REQUESTS: Lazy<Mutex<HashMap<&'static str, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+/// Route, schema and direction of a failed JSON Schema check
+type SchemaFailureKey = (String, String, &'static str);
+/// Bodies failing JSON Schema checks per route, schema and direction for the
+/// `/metrics` exposition
+static SCHEMA_VALIDATION_FAILURES: Lazy<Mutex<HashMap<SchemaFailureKey, u64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Open tunnels per route, and closed tunnels and tunneled bytes per route
+/// and reason or direction
+#[derive(Debug, Default)]
+struct TunnelStats {
+    active: HashMap<String, u64>,
+    closed: HashMap<(String, &'static str), u64>,
+    bytes: HashMap<(String, &'static str), u64>,
+}
+
+/// Tunnels for the `/metrics` exposition
+static TUNNELS: Lazy<Mutex<TunnelStats>> = Lazy::new(|| Mutex::new(TunnelStats::default()));
+
+/// Set once the global meter provider has been installed
+static METRICS_INITIALIZED: once_cell::sync::OnceCell<()> = once_cell::sync::OnceCell::new();
 
 /// Storage for backend health status gauges
 pub static BACKEND_HEALTH_GAUGES: Lazy<Mutex<HashMap<String, f64>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
-/// Initialize OpenTelemetry metrics with OTLP exporter
-pub async fn init_metrics() -> eyre::Result<()> {
-    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+/// Initialize OpenTelemetry metrics with the exporters in `metrics.exporters`.
+///
+/// Only the first call installs a provider; later calls are no-ops, so the
+/// exporters and their reader threads are never duplicated.
+pub async fn init_metrics(config: &MetricsConfig) -> eyre::Result<()> {
+    use opentelemetry_sdk::metrics::{Instrument, SdkMeterProvider, Stream};
+
+    use crate::config::models::MetricsExporter;
+
+    if METRICS_INITIALIZED.get().is_some() {
+        return Ok(());
+    }
+
+    let interval = config.interval_secs.map(std::time::Duration::from_secs);
+    let timeout = std::time::Duration::from_secs(config.export.timeout_secs);
+    let mut builder = SdkMeterProvider::builder();
+    for exporter in &config.exporters {
+        if let Some(feature) = exporter.feature()
+            && !exporter.is_available()
+        {
+            eyre::bail!("metrics exporter '{feature}' requires the `{feature}` feature");
+        }
+        match exporter {
+            MetricsExporter::Otlp => {
+                use opentelemetry_otlp::WithExportConfig;
 
-    let exporter = opentelemetry_otlp::MetricExporter::builder()
-        .with_tonic()
-        .build()?;
+                let otlp = opentelemetry_otlp::MetricExporter::builder()
+                    .with_tonic()
+                    .with_timeout(timeout)
+                    .build()?;
+                builder = builder.with_reader(periodic_reader(
+                    ResilientExporter::new(otlp, &config.export),
+                    interval,
+                ));
+            }
+            #[cfg(feature = "prometheus")]
+            MetricsExporter::Prometheus => {
+                use crate::adapters::metrics_exporters::PrometheusExporter;
 
-    let reader = PeriodicReader::builder(exporter).build();
+                builder = builder.with_reader(periodic_reader(PrometheusExporter, interval));
+            }
+            #[cfg(feature = "statsd")]
+            MetricsExporter::Statsd => {
+                use crate::adapters::metrics_exporters::StatsdExporter;
 
-    let provider = SdkMeterProvider::builder().with_reader(reader).build();
+                let statsd = config.statsd.as_ref().ok_or_else(|| {
+                    eyre::eyre!("metrics.statsd is required by the statsd exporter")
+                })?;
+                builder = builder.with_reader(periodic_reader(
+                    ResilientExporter::new(StatsdExporter::new(statsd), &config.export),
+                    interval,
+                ));
+            }
+            #[cfg(feature = "pushgateway")]
+            MetricsExporter::Pushgateway => {
+                use crate::adapters::metrics_exporters::PushgatewayExporter;
 
-    opentelemetry::global::set_meter_provider(provider);
+                let pushgateway = config.pushgateway.as_ref().ok_or_else(|| {
+                    eyre::eyre!("metrics.pushgateway is required by the pushgateway exporter")
+                })?;
+                builder = builder.with_reader(periodic_reader(
+                    ResilientExporter::new(
+                        PushgatewayExporter::new(pushgateway, timeout)?,
+                        &config.export,
+                    ),
+                    interval,
+                ));
+            }
+            // Not built in; rejected above
+            #[allow(unreachable_patterns)]
+            _ => {}
+        }
+    }
+
+    let histograms: [(&'static [&'static str], &HistogramConfig); 2] = [
+        (
+            &[
+                AXON_REQUEST_DURATION_SECONDS,
+                AXON_HTTP3_REQUEST_DURATION_SECONDS,
+            ],
+            &config.request_duration,
+        ),
+        (
+            &[AXON_BACKEND_REQUEST_DURATION_SECONDS],
+            &config.backend_duration,
+        ),
+    ];
+    for (names, histogram) in histograms {
+        if let Some(aggregation) = histogram_aggregation(histogram) {
+            builder = builder.with_view(move |instrument: &Instrument| {
+                if !names.contains(&instrument.name()) {
+                    return None;
+                }
+                Stream::builder()
+                    .with_aggregation(aggregation.clone())
+                    .build()
+                    .ok()
+            });
+        }
+    }
+
+    if METRICS_INITIALIZED.set(()).is_ok() {
+        opentelemetry::global::set_meter_provider(builder.build());
+        BUILD_INFO.record(1, &build_info_labels());
+    }
 
     Ok(())
 }
 
+fn periodic_reader<E: opentelemetry_sdk::metrics::exporter::PushMetricExporter>(
+    exporter: E,
+    interval: Option<std::time::Duration>,
+) -> opentelemetry_sdk::metrics::PeriodicReader<E> {
+    let builder = opentelemetry_sdk::metrics::PeriodicReader::builder(exporter);
+    match interval {
+        Some(interval) => builder.with_interval(interval),
+        None => builder,
+    }
+    .build()
+}
+
+fn build_info_labels() -> [KeyValue; 4] {
+    use crate::build_info;
+
+    [
+        KeyValue::new("version", build_info::VERSION),
+        KeyValue::new("commit", build_info::GIT_COMMIT),
+        KeyValue::new("build_timestamp", build_info::build_timestamp()),
+        KeyValue::new("features", build_info::features().join(",")),
+    ]
+}
+
+/// Publish the generation of the configuration being served.
+pub fn set_config_generation(generation: u64) {
+    CONFIG_GENERATION.record(generation, &[]);
+}
+
+/// Prometheus text lines for `axon_build_info` and `axon_config_generation`.
+pub fn build_info_exposition(config_generation: u64) -> String {
+    let labels = build_info_labels()
+        .iter()
+        .map(|kv| format!("{}=\"{}\"", kv.key, kv.value))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "# HELP {AXON_BUILD_INFO} Build information; the value is always 1.\n\
+         # TYPE {AXON_BUILD_INFO} gauge\n\
+         {AXON_BUILD_INFO}{{{labels}}} 1\n\
+         # HELP {AXON_CONFIG_GENERATION} Configuration generation, incremented by each reload.\n\
+         # TYPE {AXON_CONFIG_GENERATION} gauge\n\
+         {AXON_CONFIG_GENERATION} {config_generation}\n"
+    )
+}
+
+/// Circuit breaker state of a [`ResilientExporter`].
+#[derive(Debug, Default)]
+struct ExportState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    last_logged: Option<Instant>,
+    /// Failures not logged since `last_logged`
+    unlogged: u64,
+}
+
+/// Wraps a push exporter so an unreachable collector costs nothing but
+/// dropped batches: failures are counted rather than reported to the SDK, and
+/// repeated failures open a circuit that skips exports for a while.
+struct ResilientExporter<E> {
+    inner: E,
+    config: MetricsExportConfig,
+    state: Mutex<ExportState>,
+}
+
+impl<E> ResilientExporter<E> {
+    fn new(inner: E, config: &MetricsExportConfig) -> Self {
+        Self {
+            inner,
+            config: config.clone(),
+            state: Mutex::new(ExportState::default()),
+        }
+    }
+
+    /// Whether an export should be attempted now; a circuit whose open period
+    /// has passed lets one trial export through.
+    fn should_export(&self, now: Instant) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return true;
+        };
+        match state.open_until {
+            Some(until) if now < until => false,
+            Some(_) => {
+                state.open_until = None;
+                true
+            }
+            None => true,
+        }
+    }
+
+    fn on_success(&self) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.consecutive_failures >= self.config.failure_threshold
+            && self.config.error_log == ExportErrorLog::Interval
+        {
+            tracing::info!("metrics export recovered");
+        }
+        *state = ExportState::default();
+    }
+
+    fn on_failure(&self, error: &opentelemetry_sdk::error::OTelSdkError, now: Instant) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        let opened = state.consecutive_failures >= self.config.failure_threshold;
+        if opened {
+            state.open_until =
+                Some(now + std::time::Duration::from_secs(self.config.circuit_open_secs));
+        }
+        // Failed trial exports keep it open; only the first opening is news
+        if state.consecutive_failures == self.config.failure_threshold {
+            notify(
+                Notification::new(NotificationEvent::CircuitOpen)
+                    .subject("metrics_export")
+                    .detail(format!(
+                        "{} consecutive failed exports: {error}",
+                        state.consecutive_failures
+                    )),
+            );
+        }
+
+        if self.config.error_log == ExportErrorLog::Silent {
+            return;
+        }
+        let interval = std::time::Duration::from_secs(self.config.error_log_interval_secs);
+        if state
+            .last_logged
+            .is_some_and(|at| now.duration_since(at) < interval)
+        {
+            state.unlogged += 1;
+            return;
+        }
+        tracing::warn!(
+            %error,
+            consecutive_failures = state.consecutive_failures,
+            suppressed = state.unlogged,
+            circuit_open = opened,
+            "metrics export failed; dropping batch"
+        );
+        state.last_logged = Some(now);
+        state.unlogged = 0;
+    }
+}
+
+impl<E: opentelemetry_sdk::metrics::exporter::PushMetricExporter>
+    opentelemetry_sdk::metrics::exporter::PushMetricExporter for ResilientExporter<E>
+{
+    async fn export(
+        &self,
+        metrics: &opentelemetry_sdk::metrics::data::ResourceMetrics,
+    ) -> opentelemetry_sdk::error::OTelSdkResult {
+        if !self.should_export(Instant::now()) {
+            record_telemetry_dropped("circuit_open");
+            return Ok(());
+        }
+        match self.inner.export(metrics).await {
+            Ok(()) => self.on_success(),
+            Err(error) => {
+                record_telemetry_dropped("export_error");
+                self.on_failure(&error, Instant::now());
+            }
+        }
+        Ok(())
+    }
+
+    fn force_flush(&self) -> opentelemetry_sdk::error::OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(
+        &self,
+        timeout: std::time::Duration,
+    ) -> opentelemetry_sdk::error::OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn temporality(&self) -> opentelemetry_sdk::metrics::Temporality {
+        self.inner.temporality()
+    }
+}
+
+/// Publish a limited backend's in-flight and pending request counts.
+pub fn set_backend_saturation(backend: &str, in_flight: usize, pending: usize) {
+    let labels = [KeyValue::new("backend", backend.to_string())];
+    BACKEND_IN_FLIGHT_REQUESTS.record(in_flight as f64, &labels);
+    BACKEND_PENDING_REQUESTS.record(pending as f64, &labels);
+}
+
+/// Count a request that found its backend at `max_requests`; `outcome` is
+/// `rerouted`, `queued` or `rejected`.
+pub fn record_backend_saturated(backend: &str, outcome: &'static str) {
+    BACKEND_SATURATED_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("backend", backend.to_string()),
+            KeyValue::new("outcome", outcome),
+        ],
+    );
+}
+
+/// Prometheus text lines for limited backends, given
+/// `(backend, in_flight, pending, max_requests)` tuples.
+pub fn backend_limit_exposition(limits: &[(String, usize, usize, usize)]) -> String {
+    let mut out = String::new();
+    if limits.is_empty() {
+        return out;
+    }
+    let families = [
+        (
+            AXON_BACKEND_IN_FLIGHT_REQUESTS,
+            "Requests holding a slot on a limited backend.",
+        ),
+        (
+            AXON_BACKEND_PENDING_REQUESTS,
+            "Requests queued for a slot on a limited backend.",
+        ),
+    ];
+    for (name, help) in families {
+        out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} gauge\n"));
+        for (backend, in_flight, pending, max_requests) in limits {
+            let value = if name == AXON_BACKEND_IN_FLIGHT_REQUESTS {
+                in_flight
+            } else {
+                pending
+            };
+            out.push_str(&format!(
+                "{name}{{backend=\"{backend}\",max_requests=\"{max_requests}\"}} {value}\n"
+            ));
+        }
+    }
+    out
+}
+
+/// Count a metric batch that was not exported.
+pub fn record_telemetry_dropped(reason: &'static str) {
+    if let Ok(mut dropped) = TELEMETRY_DROPPED.lock() {
+        *dropped.entry(reason).or_default() += 1;
+    }
+}
+
+/// Prometheus text lines for `axon_telemetry_dropped_total` (empty until a
+/// batch has been dropped).
+pub fn telemetry_exposition() -> String {
+    let mut dropped: Vec<_> = TELEMETRY_DROPPED
+        .lock()
+        .map(|dropped| dropped.iter().map(|(k, v)| (*k, *v)).collect())
+        .unwrap_or_default();
+    if dropped.is_empty() {
+        return String::new();
+    }
+    dropped.sort_unstable();
+
+    let name = AXON_TELEMETRY_DROPPED_TOTAL;
+    let mut out = format!(
+        "# HELP {name} Metric batches dropped instead of exported.\n# TYPE {name} counter\n"
+    );
+    for (reason, count) in dropped {
+        out.push_str(&format!("{name}{{reason=\"{reason}\"}} {count}\n"));
+    }
+    out
+}
+
+/// Count audit records lost because the queue was full or the sink failed.
+pub fn record_audit_dropped(reason: &'static str, count: u64) {
+    if let Ok(mut dropped) = AUDIT_RECORDS_DROPPED.lock() {
+        *dropped.entry(reason).or_default() += count;
+    }
+}
+
+/// Prometheus text lines for `axon_audit_records_dropped_total` (empty until
+/// a record has been dropped).
+pub fn audit_exposition() -> String {
+    let mut dropped: Vec<_> = AUDIT_RECORDS_DROPPED
+        .lock()
+        .map(|dropped| dropped.iter().map(|(k, v)| (*k, *v)).collect())
+        .unwrap_or_default();
+    if dropped.is_empty() {
+        return String::new();
+    }
+    dropped.sort_unstable();
+
+    let name = AXON_AUDIT_RECORDS_DROPPED_TOTAL;
+    let mut out = format!(
+        "# HELP {name} Security audit records that were not delivered.\n# TYPE {name} counter\n"
+    );
+    for (reason, count) in dropped {
+        out.push_str(&format!("{name}{{reason=\"{reason}\"}} {count}\n"));
+    }
+    out
+}
+
+/// Count metered requests (`queue_full`) or usage records (`pending_full`)
+/// that will not be exported.
+pub fn record_usage_dropped(reason: &'static str, count: u64) {
+    if let Ok(mut dropped) = USAGE_DROPPED.lock() {
+        *dropped.entry(reason).or_default() += count;
+    }
+}
+
+/// Prometheus text lines for `axon_usage_dropped_total` (empty until
+/// something has been dropped).
+pub fn usage_exposition() -> String {
+    let mut dropped: Vec<_> = USAGE_DROPPED
+        .lock()
+        .map(|dropped| dropped.iter().map(|(k, v)| (*k, *v)).collect())
+        .unwrap_or_default();
+    if dropped.is_empty() {
+        return String::new();
+    }
+    dropped.sort_unstable();
+
+    let name = AXON_USAGE_DROPPED_TOTAL;
+    let mut out = format!(
+        "# HELP {name} Metered requests or usage records that were not exported.\n# TYPE {name} counter\n"
+    );
+    for (reason, count) in dropped {
+        out.push_str(&format!("{name}{{reason=\"{reason}\"}} {count}\n"));
+    }
+    out
+}
+
+/// Count request events lost because the queue was full (`queue_full`),
+/// the publishing task stopped (`sink_closed`) or the broker failed
+/// (`sink_error`).
+pub fn record_events_dropped(reason: &'static str, count: u64) {
+    if let Ok(mut dropped) = EVENTS_DROPPED.lock() {
+        *dropped.entry(reason).or_default() += count;
+    }
+}
+
+/// Prometheus text lines for `axon_events_dropped_total` (empty until an
+/// event has been dropped).
+pub fn events_exposition() -> String {
+    let mut dropped: Vec<_> = EVENTS_DROPPED
+        .lock()
+        .map(|dropped| dropped.iter().map(|(k, v)| (*k, *v)).collect())
+        .unwrap_or_default();
+    if dropped.is_empty() {
+        return String::new();
+    }
+    dropped.sort_unstable();
+
+    let name = AXON_EVENTS_DROPPED_TOTAL;
+    let mut out =
+        format!("# HELP {name} Request events that were not published.\n# TYPE {name} counter\n");
+    for (reason, count) in dropped {
+        out.push_str(&format!("{name}{{reason=\"{reason}\"}} {count}\n"));
+    }
+    out
+}
+
+/// Count a request no route matched, by `outcome`: `default_route` when
+/// the default route served it, `not_found` otherwise.
+pub fn record_unmatched_request(outcome: &'static str) {
+    UNMATCHED_REQUESTS_TOTAL.add(1, &[KeyValue::new("outcome", outcome)]);
+    if let Ok(mut unmatched) = UNMATCHED_REQUESTS.lock() {
+        *unmatched.entry(outcome).or_default() += 1;
+    }
+}
+
+/// Prometheus text lines for `axon_unmatched_requests_total` (empty until
+/// the first unmatched request).
+pub fn unmatched_exposition() -> String {
+    let mut unmatched: Vec<_> = UNMATCHED_REQUESTS
+        .lock()
+        .map(|unmatched| unmatched.iter().map(|(k, v)| (*k, *v)).collect())
+        .unwrap_or_default();
+    if unmatched.is_empty() {
+        return String::new();
+    }
+    unmatched.sort_unstable();
+
+    let name = AXON_UNMATCHED_REQUESTS_TOTAL;
+    let mut out = format!("# HELP {name} Requests no route matched.\n# TYPE {name} counter\n");
+    for (outcome, count) in unmatched {
+        out.push_str(&format!("{name}{{outcome=\"{outcome}\"}} {count}\n"));
+    }
+    out
+}
+
+/// Count an automatic IP ban issued for `reason`.
+pub fn record_auto_ban(reason: &'static str) {
+    AUTO_BANS_TOTAL.add(1, &[KeyValue::new("reason", reason)]);
+    if let Ok(mut bans) = AUTO_BANS.lock() {
+        *bans.entry(reason).or_default() += 1;
+    }
+}
+
+/// Record how many IPs are currently banned.
+pub fn set_auto_banned_ips(count: u64) {
+    AUTO_BANNED_IPS.record(count, &[]);
+    AUTO_BANNED_COUNT.store(count, Ordering::Relaxed);
+}
+
+/// Prometheus text lines for `axon_auto_bans_total` and
+/// `axon_auto_banned_ips` (empty until the first ban).
+pub fn auto_ban_exposition() -> String {
+    let mut bans: Vec<_> = AUTO_BANS
+        .lock()
+        .map(|bans| bans.iter().map(|(k, v)| (*k, *v)).collect())
+        .unwrap_or_default();
+    if bans.is_empty() {
+        return String::new();
+    }
+    bans.sort_unstable();
+
+    let name = AXON_AUTO_BANS_TOTAL;
+    let mut out =
+        format!("# HELP {name} Client IPs banned automatically.\n# TYPE {name} counter\n");
+    for (reason, count) in bans {
+        out.push_str(&format!("{name}{{reason=\"{reason}\"}} {count}\n"));
+    }
+    let name = AXON_AUTO_BANNED_IPS;
+    out.push_str(&format!(
+        "# HELP {name} Client IPs currently banned.\n# TYPE {name} gauge\n{name} {}\n",
+        AUTO_BANNED_COUNT.load(Ordering::Relaxed)
+    ));
+    out
+}
+
+/// Count an incoming QUIC connection by handshake `outcome` (`accepted` or
+/// `handshake_failed`); accepted ones stay active until
+/// [`record_http3_connection_closed`].
+pub fn record_http3_connection(outcome: &'static str) {
+    HTTP3_CONNECTIONS_TOTAL.add(1, &[KeyValue::new("outcome", outcome)]);
+    if let Ok(mut connections) = HTTP3_CONNECTIONS.lock() {
+        *connections.entry(outcome).or_default() += 1;
+    }
+    if outcome == "accepted" {
+        let active = HTTP3_ACTIVE_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
+        HTTP3_ACTIVE_CONNECTIONS.record(active, &[]);
+    }
+}
+
+/// Record that an accepted QUIC connection has closed.
+pub fn record_http3_connection_closed() {
+    let active = HTTP3_ACTIVE_COUNT
+        .fetch_sub(1, Ordering::Relaxed)
+        .saturating_sub(1);
+    HTTP3_ACTIVE_CONNECTIONS.record(active, &[]);
+}
+
+/// Add time a QUIC request stream was blocked: `request` while the gateway
+/// had not yet taken the previous body chunk, `response` while the client
+/// had not granted flow-control credit.
+pub fn record_http3_stream_blocked(direction: &'static str, blocked: std::time::Duration) {
+    let seconds = blocked.as_secs_f64();
+    HTTP3_STREAM_BLOCKED_SECONDS_TOTAL.add(seconds, &[KeyValue::new("direction", direction)]);
+    if let Ok(mut totals) = HTTP3_STREAM_BLOCKED.lock() {
+        *totals.entry(direction).or_default() += seconds;
+    }
+}
+
+/// Record the request and response body sizes of a finished request.
+pub fn record_body_sizes(protocol: &'static str, bytes_in: u64, bytes_out: u64) {
+    let attributes = [KeyValue::new("protocol", protocol)];
+    REQUEST_BODY_BYTES.record(bytes_in, &attributes);
+    RESPONSE_BODY_BYTES.record(bytes_out, &attributes);
+}
+
+/// Add request body bytes for a route. `direction` is `in` for bytes read
+/// from the client and `out` for bytes written to a backend.
+pub fn record_request_bytes(route: &str, direction: &'static str, bytes: u64) {
+    record_route_bytes(
+        &REQUEST_BYTES_TOTAL,
+        AXON_REQUEST_BYTES_TOTAL,
+        route,
+        direction,
+        bytes,
+    );
+}
+
+/// Add response body bytes for a route. `direction` is `in` for bytes read
+/// from a backend and `out` for bytes written to the client.
+pub fn record_response_bytes(route: &str, direction: &'static str, bytes: u64) {
+    record_route_bytes(
+        &RESPONSE_BYTES_TOTAL,
+        AXON_RESPONSE_BYTES_TOTAL,
+        route,
+        direction,
+        bytes,
+    );
+}
+
+fn record_route_bytes(
+    counter: &Counter<u64>,
+    name: &'static str,
+    route: &str,
+    direction: &'static str,
+    bytes: u64,
+) {
+    counter.add(
+        bytes,
+        &[
+            KeyValue::new("route", route.to_string()),
+            KeyValue::new("direction", direction),
+        ],
+    );
+    if let Ok(mut totals) = ROUTE_BYTES.lock() {
+        *totals
+            .entry((name, route.to_string(), direction))
+            .or_default() += bytes;
+    }
+}
+
+/// Prometheus text lines for `axon_request_bytes_total` and
+/// `axon_response_bytes_total` (empty until a routed request finishes).
+pub fn route_bytes_exposition() -> String {
+    let mut totals: Vec<_> = ROUTE_BYTES
+        .lock()
+        .map(|totals| totals.iter().map(|(k, v)| (k.clone(), *v)).collect())
+        .unwrap_or_default();
+    totals.sort_unstable();
+
+    let mut out = String::new();
+    for (name, help) in [
+        (
+            AXON_REQUEST_BYTES_TOTAL,
+            "Request body bytes read from clients (in) and written to backends (out).",
+        ),
+        (
+            AXON_RESPONSE_BYTES_TOTAL,
+            "Response body bytes read from backends (in) and written to clients (out).",
+        ),
+    ] {
+        let mut family = totals
+            .iter()
+            .filter(|((family, ..), _)| *family == name)
+            .peekable();
+        if family.peek().is_none() {
+            continue;
+        }
+        out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} counter\n"));
+        for ((_, route, direction), bytes) in family {
+            let route = route.replace('\\', "\\\\").replace('"', "\\\"");
+            out.push_str(&format!(
+                "{name}{{route=\"{route}\",direction=\"{direction}\"}} {bytes}\n"
+            ));
+        }
+    }
+    out
+}
+
+/// Count a request that took longer than its route's
+/// `slow_request_threshold_ms`.
+pub fn record_slow_request(route: &str) {
+    SLOW_REQUESTS_TOTAL.add(1, &[KeyValue::new("route", route.to_string())]);
+    if let Ok(mut counts) = SLOW_REQUESTS.lock() {
+        *counts.entry(route.to_string()).or_default() += 1;
+    }
+}
+
+/// Prometheus text lines for `axon_slow_requests_total` (empty until the
+/// first slow request).
+pub fn slow_request_exposition() -> String {
+    let mut counts: Vec<_> = SLOW_REQUESTS
+        .lock()
+        .map(|counts| counts.iter().map(|(k, v)| (k.clone(), *v)).collect())
+        .unwrap_or_default();
+    let mut out = String::new();
+    if counts.is_empty() {
+        return out;
+    }
+    counts.sort_unstable();
+
+    let name = AXON_SLOW_REQUESTS_TOTAL;
+    out.push_str(&format!(
+        "# HELP {name} Requests slower than their route's slow_request_threshold_ms.\n# TYPE {name} counter\n"
+    ));
+    for (route, count) in counts {
+        let route = route.replace('\\', "\\\\").replace('"', "\\\"");
+        out.push_str(&format!("{name}{{route=\"{route}\"}} {count}\n"));
+    }
+    out
+}
+
+/// Count a retry decision on `route`: `retried` when the request was sent
+/// again, `budget_exhausted` when the route's retry budget refused it.
+pub fn record_retry(route: &str, outcome: &'static str) {
+    RETRIES_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("route", route.to_string()),
+            KeyValue::new("outcome", outcome),
+        ],
+    );
+    if let Ok(mut counts) = RETRIES.lock() {
+        *counts.entry((route.to_string(), outcome)).or_default() += 1;
+    }
+}
+
+/// Prometheus text lines for `axon_retries_total` (empty until the first
+/// retry decision).
+pub fn retry_exposition() -> String {
+    let mut counts: Vec<_> = RETRIES
+        .lock()
+        .map(|counts| counts.iter().map(|(k, v)| (k.clone(), *v)).collect())
+        .unwrap_or_default();
+    let mut out = String::new();
+    if counts.is_empty() {
+        return out;
+    }
+    counts.sort_unstable();
+
+    let name = AXON_RETRIES_TOTAL;
+    out.push_str(&format!(
+        "# HELP {name} Backend request retries, and retries refused by the route's budget.\n# TYPE {name} counter\n"
+    ));
+    for ((route, outcome), count) in counts {
+        let route = route.replace('\\', "\\\\").replace('"', "\\\"");
+        out.push_str(&format!(
+            "{name}{{route=\"{route}\",outcome=\"{outcome}\"}} {count}\n"
+        ));
+    }
+    out
+}
+
+/// Count a request sent to `route`'s fallback targets: `unhealthy` when none
+/// of the route's own backends was healthy, `failed` when its backend failed.
+pub fn record_failover(route: &str, reason: &'static str) {
+    FAILOVERS_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("route", route.to_string()),
+            KeyValue::new("reason", reason),
+        ],
+    );
+    if let Ok(mut counts) = FAILOVERS.lock() {
+        *counts.entry((route.to_string(), reason)).or_default() += 1;
+    }
+}
+
+/// Prometheus text lines for `axon_failovers_total` (empty until the first
+/// failover).
+pub fn failover_exposition() -> String {
+    let mut counts: Vec<_> = FAILOVERS
+        .lock()
+        .map(|counts| counts.iter().map(|(k, v)| (k.clone(), *v)).collect())
+        .unwrap_or_default();
+    let mut out = String::new();
+    if counts.is_empty() {
+        return out;
+    }
+    counts.sort_unstable();
+
+    let name = AXON_FAILOVERS_TOTAL;
+    out.push_str(&format!(
+        "# HELP {name} Requests sent to a route's fallback targets.\n# TYPE {name} counter\n"
+    ));
+    for ((route, reason), count) in counts {
+        let route = route.replace('\\', "\\\\").replace('"', "\\\"");
+        out.push_str(&format!(
+            "{name}{{route=\"{route}\",reason=\"{reason}\"}} {count}\n"
+        ));
+    }
+    out
+}
+
+/// Count a backend request cancelled because the client went away: while
+/// sending its body (`request_body`) or while waiting for the backend's
+/// response (`awaiting_response`).
+pub fn record_client_disconnect(route: &str, phase: &'static str) {
+    CLIENT_DISCONNECTS_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("route", route.to_string()),
+            KeyValue::new("phase", phase),
+        ],
+    );
+    if let Ok(mut counts) = CLIENT_DISCONNECTS.lock() {
+        *counts.entry((route.to_string(), phase)).or_default() += 1;
+    }
+}
+
+/// Prometheus text lines for `axon_client_disconnects_total` (empty until
+/// the first disconnect).
+pub fn client_disconnect_exposition() -> String {
+    let mut counts: Vec<_> = CLIENT_DISCONNECTS
+        .lock()
+        .map(|counts| counts.iter().map(|(k, v)| (k.clone(), *v)).collect())
+        .unwrap_or_default();
+    let mut out = String::new();
+    if counts.is_empty() {
+        return out;
+    }
+    counts.sort_unstable();
+
+    let name = AXON_CLIENT_DISCONNECTS_TOTAL;
+    out.push_str(&format!(
+        "# HELP {name} Backend requests cancelled because the client disconnected.\n# TYPE {name} counter\n"
+    ));
+    for ((route, phase), count) in counts {
+        let route = route.replace('\\', "\\\\").replace('"', "\\\"");
+        out.push_str(&format!(
+            "{name}{{route=\"{route}\",phase=\"{phase}\"}} {count}\n"
+        ));
+    }
+    out
+}
+
+/// Count a request rejected because its body length could be read more than
+/// one way (`reason` names the conflict).
+pub fn record_malformed_request(reason: &'static str) {
+    MALFORMED_REQUESTS_TOTAL.add(1, &[KeyValue::new("reason", reason)]);
+    if let Ok(mut counts) = MALFORMED_REQUESTS.lock() {
+        *counts.entry(reason).or_default() += 1;
+    }
+}
+
+/// Prometheus text lines for `axon_malformed_requests_total` (empty until
+/// the first rejection).
+pub fn malformed_request_exposition() -> String {
+    let mut counts: Vec<_> = MALFORMED_REQUESTS
+        .lock()
+        .map(|counts| counts.iter().map(|(k, v)| (*k, *v)).collect())
+        .unwrap_or_default();
+    let mut out = String::new();
+    if counts.is_empty() {
+        return out;
+    }
+    counts.sort_unstable();
+
+    let name = AXON_MALFORMED_REQUESTS_TOTAL;
+    out.push_str(&format!(
+        "# HELP {name} Requests rejected for ambiguous or malformed framing.\n# TYPE {name} counter\n"
+    ));
+    for (reason, count) in counts {
+        out.push_str(&format!("{name}{{reason=\"{reason}\"}} {count}\n"));
+    }
+    out
+}
+
+/// Record a request or response body that failed a JSON Schema check.
+pub fn record_schema_validation_failure(route: &str, schema: &str, direction: &'static str) {
+    SCHEMA_VALIDATION_FAILURES_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("route", route.to_string()),
+            KeyValue::new("schema", schema.to_string()),
+            KeyValue::new("direction", direction),
+        ],
+    );
+    if let Ok(mut counts) = SCHEMA_VALIDATION_FAILURES.lock() {
+        *counts
+            .entry((route.to_string(), schema.to_string(), direction))
+            .or_default() += 1;
+    }
+}
+
+/// Prometheus text lines for `axon_schema_validation_failures_total` (empty
+/// until a body fails validation).
+pub fn schema_validation_failure_exposition() -> String {
+    let mut counts: Vec<_> = SCHEMA_VALIDATION_FAILURES
+        .lock()
+        .map(|counts| counts.iter().map(|(k, v)| (k.clone(), *v)).collect())
+        .unwrap_or_default();
+    let mut out = String::new();
+    if counts.is_empty() {
+        return out;
+    }
+    counts.sort_unstable();
+
+    let name = AXON_SCHEMA_VALIDATION_FAILURES_TOTAL;
+    out.push_str(&format!(
+        "# HELP {name} Request and response bodies that failed JSON Schema validation.\n# TYPE {name} counter\n"
+    ));
+    for ((route, schema, direction), count) in counts {
+        let route = route.replace('\\', "\\\\").replace('"', "\\\"");
+        let schema = schema.replace('\\', "\\\\").replace('"', "\\\"");
+        out.push_str(&format!(
+            "{name}{{route=\"{route}\",schema=\"{schema}\",direction=\"{direction}\"}} {count}\n"
+        ));
+    }
+    out
+}
+
+/// Record a tunnel opened on `route`.
+pub fn record_tunnel_opened(route: &str) {
+    if let Ok(mut tunnels) = TUNNELS.lock() {
+        let active = tunnels.active.entry(route.to_string()).or_default();
+        *active += 1;
+        TUNNELS_ACTIVE.record(*active, &[KeyValue::new("route", route.to_string())]);
+    }
+}
+
+/// Record a tunnel on `route` closing for `reason`, after carrying
+/// `upstream` bytes from the client and `downstream` bytes from the backend.
+pub fn record_tunnel_closed(route: &str, reason: &'static str, upstream: u64, downstream: u64) {
+    TUNNELS_CLOSED_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("route", route.to_string()),
+            KeyValue::new("reason", reason),
+        ],
+    );
+    for (direction, bytes) in [("upstream", upstream), ("downstream", downstream)] {
+        TUNNEL_BYTES_TOTAL.add(
+            bytes,
+            &[
+                KeyValue::new("route", route.to_string()),
+                KeyValue::new("direction", direction),
+            ],
+        );
+    }
+    if let Ok(mut tunnels) = TUNNELS.lock() {
+        let active = tunnels.active.entry(route.to_string()).or_default();
+        *active = active.saturating_sub(1);
+        TUNNELS_ACTIVE.record(*active, &[KeyValue::new("route", route.to_string())]);
+        *tunnels
+            .closed
+            .entry((route.to_string(), reason))
+            .or_default() += 1;
+        *tunnels
+            .bytes
+            .entry((route.to_string(), "upstream"))
+            .or_default() += upstream;
+        *tunnels
+            .bytes
+            .entry((route.to_string(), "downstream"))
+            .or_default() += downstream;
+    }
+}
+
+/// Prometheus text lines for `axon_tunnels_active`,
+/// `axon_tunnels_closed_total` and `axon_tunnel_bytes_total` (empty until the
+/// first tunnel).
+pub fn tunnel_exposition() -> String {
+    let Ok(tunnels) = TUNNELS.lock() else {
+        return String::new();
+    };
+    let mut out = String::new();
+    if tunnels.active.is_empty() {
+        return out;
+    }
+    let escape = |route: &str| route.replace('\\', "\\\\").replace('"', "\\\"");
+
+    let mut active: Vec<_> = tunnels.active.iter().collect();
+    active.sort_unstable();
+    let name = AXON_TUNNELS_ACTIVE;
+    out.push_str(&format!(
+        "# HELP {name} Open Upgrade and CONNECT tunnels.\n# TYPE {name} gauge\n"
+    ));
+    for (route, count) in active {
+        out.push_str(&format!("{name}{{route=\"{}\"}} {count}\n", escape(route)));
+    }
+
+    for (name, help, label, totals) in [
+        (
+            AXON_TUNNELS_CLOSED_TOTAL,
+            "Tunnels closed, by reason.",
+            "reason",
+            &tunnels.closed,
+        ),
+        (
+            AXON_TUNNEL_BYTES_TOTAL,
+            "Bytes tunneled from clients (upstream) and from backends (downstream).",
+            "direction",
+            &tunnels.bytes,
+        ),
+    ] {
+        let mut totals: Vec<_> = totals.iter().collect();
+        if totals.is_empty() {
+            continue;
+        }
+        totals.sort_unstable();
+        out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} counter\n"));
+        for ((route, value), count) in totals {
+            out.push_str(&format!(
+                "{name}{{route=\"{}\",{label}=\"{value}\"}} {count}\n",
+                escape(route)
+            ));
+        }
+    }
+    out
+}
+
+/// Prometheus text lines for `axon_http3_connections_total`,
+/// `axon_http3_active_connections` and
+/// `axon_http3_stream_blocked_seconds_total` (empty until the first QUIC
+/// connection).
+pub fn http3_exposition() -> String {
+    let mut connections: Vec<_> = HTTP3_CONNECTIONS
+        .lock()
+        .map(|connections| connections.iter().map(|(k, v)| (*k, *v)).collect())
+        .unwrap_or_default();
+    if connections.is_empty() {
+        return String::new();
+    }
+    connections.sort_unstable();
+
+    let name = AXON_HTTP3_CONNECTIONS_TOTAL;
+    let mut out =
+        format!("# HELP {name} Incoming HTTP/3 (QUIC) connections.\n# TYPE {name} counter\n");
+    for (outcome, count) in connections {
+        out.push_str(&format!("{name}{{outcome=\"{outcome}\"}} {count}\n"));
+    }
+    let name = AXON_HTTP3_ACTIVE_CONNECTIONS;
+    out.push_str(&format!(
+        "# HELP {name} Open HTTP/3 (QUIC) connections.\n# TYPE {name} gauge\n{name} {}\n",
+        HTTP3_ACTIVE_COUNT.load(Ordering::Relaxed)
+    ));
+
+    let mut blocked: Vec<_> = HTTP3_STREAM_BLOCKED
+        .lock()
+        .map(|totals| totals.iter().map(|(k, v)| (*k, *v)).collect())
+        .unwrap_or_default();
+    if !blocked.is_empty() {
+        blocked.sort_unstable_by_key(|(direction, _)| *direction);
+        let name = AXON_HTTP3_STREAM_BLOCKED_SECONDS_TOTAL;
+        out.push_str(&format!(
+            "# HELP {name} Time HTTP/3 request streams spent blocked on flow control.\n# TYPE {name} counter\n"
+        ));
+        for (direction, seconds) in blocked {
+            out.push_str(&format!("{name}{{direction=\"{direction}\"}} {seconds}\n"));
+        }
+    }
+    out
+}
+
+/// Map a configured histogram onto an SDK aggregation (`None` keeps the default).
+fn histogram_aggregation(
+    config: &HistogramConfig,
+) -> Option<opentelemetry_sdk::metrics::Aggregation> {
+    use opentelemetry_sdk::metrics::Aggregation;
+
+    match config {
+        HistogramConfig::Default => None,
+        HistogramConfig::Explicit { buckets } => Some(Aggregation::ExplicitBucketHistogram {
+            boundaries: buckets.clone(),
+            record_min_max: true,
+        }),
+        HistogramConfig::Exponential {
+            max_size,
+            max_scale,
+        } => Some(Aggregation::Base2ExponentialHistogram {
+            max_size: *max_size,
+            max_scale: *max_scale,
+            record_min_max: true,
+        }),
+    }
+}
+
 /// Set (and record) the health status gauge for a backend.
 pub fn set_backend_health_status(backend_id: &str, is_healthy: bool) {
     let health_value = if is_healthy { 1.0 } else { 0.0 };
@@ -183,6 +1571,18 @@ pub fn record_backend_request_duration(
     );
 }
 
+/// Record one health probe's latency; `outcome` is healthy, unhealthy,
+/// error or timeout.
+pub fn record_health_check_duration(backend: &str, outcome: &str, duration: std::time::Duration) {
+    HEALTH_CHECK_DURATION_SECONDS.record(
+        duration.as_secs_f64(),
+        &[
+            KeyValue::new("backend", backend.to_string()),
+            KeyValue::new("outcome", outcome.to_string()),
+        ],
+    );
+}
+
 /// Set current active connection count.
 pub fn set_active_connections(count: usize) {
     // Simplified, in production use ObservableGauge
@@ -195,6 +1595,45 @@ pub fn set_active_requests(count: u64) {
     ACTIVE_REQUESTS.record(count as f64, &[]);
 }
 
+/// Record a newly established upstream connection to `backend` (scheme://authority).
+pub fn record_upstream_connection_opened(backend: &str) {
+    UPSTREAM_CONNECTIONS_OPENED_TOTAL.add(1, &[KeyValue::new("backend", backend.to_string())]);
+    let open = UPSTREAM_OPEN_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
+    UPSTREAM_CONNECTIONS_OPEN.record(open as f64, &[]);
+}
+
+/// Count a failed connection attempt to one of `backend`'s addresses
+/// (`family` is `ipv4` or `ipv6`)
+pub fn record_upstream_connect_failure(backend: &str, family: &'static str) {
+    UPSTREAM_CONNECT_FAILURES_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("backend", backend.to_string()),
+            KeyValue::new("family", family),
+        ],
+    );
+}
+
+/// Record that an upstream connection has been closed (dropped by the pool).
+pub fn record_upstream_connection_closed() {
+    let open = UPSTREAM_OPEN_COUNT.fetch_sub(1, Ordering::Relaxed) - 1;
+    UPSTREAM_CONNECTIONS_OPEN.record(open as f64, &[]);
+}
+
+/// Adjust the number of upstream requests currently in flight by `delta`.
+pub fn add_upstream_requests_in_flight(delta: i64) {
+    let in_flight = UPSTREAM_IN_FLIGHT_COUNT.fetch_add(delta, Ordering::Relaxed) + delta;
+    UPSTREAM_REQUESTS_IN_FLIGHT.record(in_flight as f64, &[]);
+}
+
+/// Snapshot of upstream pool usage as `(open_connections, requests_in_flight)`.
+pub fn upstream_pool_stats() -> (i64, i64) {
+    (
+        UPSTREAM_OPEN_COUNT.load(Ordering::Relaxed),
+        UPSTREAM_IN_FLIGHT_COUNT.load(Ordering::Relaxed),
+    )
+}
+
 /// RAII helper measuring inbound request duration.
 pub struct RequestTimer {
     start: Instant,
@@ -294,6 +1733,13 @@ pub fn get_current_metrics() -> HashMap<String, f64> {
         }
     }
 
+    let (open, in_flight) = upstream_pool_stats();
+    metrics.insert(AXON_UPSTREAM_CONNECTIONS_OPEN.to_string(), open as f64);
+    metrics.insert(
+        AXON_UPSTREAM_REQUESTS_IN_FLIGHT.to_string(),
+        in_flight as f64,
+    );
+
     metrics
 }
 
@@ -323,6 +1769,353 @@ pub fn record_waf_check(passed: bool) {
     );
 }
 
+/// Record an automatic canary rollback
+pub fn record_canary_rollback(route: &str, reason: &str) {
+    CANARY_ROLLBACKS_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("route", route.to_string()),
+            KeyValue::new("reason", reason.to_string()),
+        ],
+    );
+}
+
+/// Count a request that joined another's backend request (`outcome` is
+/// `shared`, `unshared` when it had to send its own, or `full`)
+pub fn record_coalesced_request(route: &str, outcome: &'static str) {
+    COALESCED_REQUESTS_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("route", route.to_string()),
+            KeyValue::new("outcome", outcome),
+        ],
+    );
+}
+
+/// Count a hedge decision (`outcome` is `won` when the hedge answered first,
+/// `lost` when the first backend did, or `budget_exhausted`)
+pub fn record_hedged_request(route: &str, outcome: &'static str) {
+    HEDGED_REQUESTS_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("route", route.to_string()),
+            KeyValue::new("outcome", outcome),
+        ],
+    );
+}
+
+/// Record a request assigned to an experiment variant
+pub fn record_experiment_request(
+    experiment: &str,
+    variant: &str,
+    status: u16,
+    duration: std::time::Duration,
+) {
+    let experiment = KeyValue::new("experiment", experiment.to_string());
+    let variant = KeyValue::new("variant", variant.to_string());
+    EXPERIMENT_REQUESTS_TOTAL.add(
+        1,
+        &[
+            experiment.clone(),
+            variant.clone(),
+            KeyValue::new("status", status.to_string()),
+        ],
+    );
+    EXPERIMENT_REQUEST_DURATION_SECONDS.record(duration.as_secs_f64(), &[experiment, variant]);
+}
+
+/// Count a backend response body that did not match its Content-Length
+/// (`kind` is `truncated` or `overflow`).
+pub fn record_body_length_mismatch(backend: &str, kind: &str) {
+    BACKEND_BODY_LENGTH_MISMATCH_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("backend", backend.to_string()),
+            KeyValue::new("kind", kind.to_string()),
+        ],
+    );
+}
+
+/// Record the configured quota of a route's rate limiter (`limiter` is the
+/// limit's name, by default `route`, `ip`, `header` or `composite`).
+pub fn set_rate_limit_configured(route: &str, limiter: &str, requests: u64, period: &str) {
+    RATE_LIMIT_CONFIGURED_REQUESTS.record(
+        requests as f64,
+        &[
+            KeyValue::new("route", route.to_string()),
+            KeyValue::new("limiter", limiter.to_string()),
+            KeyValue::new("period", period.to_string()),
+        ],
+    );
+    if let Ok(mut stats) = RATE_LIMIT_STATS.lock() {
+        let entry = stats
+            .entry((route.to_string(), limiter.to_string()))
+            .or_default();
+        entry.requests = requests;
+        entry.period = period.to_string();
+    }
+}
+
+/// Count a rate limiter decision for a route.
+pub fn record_rate_limit_decision(route: &str, limiter: &str, allowed: bool) {
+    let labels = [
+        KeyValue::new("route", route.to_string()),
+        KeyValue::new("limiter", limiter.to_string()),
+    ];
+    if allowed {
+        RATE_LIMIT_ALLOWED_TOTAL.add(1, &labels);
+    } else {
+        RATE_LIMIT_REJECTED_TOTAL.add(1, &labels);
+    }
+    if let Ok(mut stats) = RATE_LIMIT_STATS.lock() {
+        let entry = stats
+            .entry((route.to_string(), limiter.to_string()))
+            .or_default();
+        if allowed {
+            entry.allowed += 1;
+        } else {
+            entry.rejected += 1;
+        }
+    }
+}
+
+/// Record the key count and cumulative evictions of a keyed rate limiter.
+///
+/// `evictions` is the limiter's own running total; a limiter rebuilt by a
+/// reload starts again from zero.
+pub fn set_rate_limit_keys(route: &str, limiter: &str, keys: usize, evictions: u64) {
+    let labels = [
+        KeyValue::new("route", route.to_string()),
+        KeyValue::new("limiter", limiter.to_string()),
+    ];
+    RATE_LIMIT_KEYS.record(keys as u64, &labels);
+    if let Ok(mut stats) = RATE_LIMIT_STATS.lock() {
+        let entry = stats
+            .entry((route.to_string(), limiter.to_string()))
+            .or_default();
+        let new_evictions = evictions.saturating_sub(entry.evictions);
+        if new_evictions > 0 {
+            RATE_LIMIT_KEY_EVICTIONS_TOTAL.add(new_evictions, &labels);
+        }
+        entry.keyed = true;
+        entry.keys = keys as u64;
+        entry.evictions = evictions;
+    }
+}
+
+/// Snapshot of rate limiter stats as `((route, limiter), stats)`, sorted by key.
+pub fn rate_limit_stats() -> Vec<((String, String), RateLimitStats)> {
+    let mut stats: Vec<_> = RATE_LIMIT_STATS
+        .lock()
+        .map(|stats| stats.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default();
+    stats.sort_by(|a, b| a.0.cmp(&b.0));
+    stats
+}
+
+/// Prometheus text lines for the rate limit families (empty when no limiter
+/// has been configured).
+pub fn rate_limit_exposition() -> String {
+    let stats = rate_limit_stats();
+    let mut out = String::new();
+    if stats.is_empty() {
+        return out;
+    }
+
+    let families = [
+        (
+            AXON_RATE_LIMIT_ALLOWED_TOTAL,
+            "Requests allowed by route rate limiters.",
+            "counter",
+        ),
+        (
+            AXON_RATE_LIMIT_REJECTED_TOTAL,
+            "Requests rejected by route rate limiters.",
+            "counter",
+        ),
+        (
+            AXON_RATE_LIMIT_CONFIGURED_REQUESTS,
+            "Configured requests per period of route rate limiters.",
+            "gauge",
+        ),
+        (
+            AXON_RATE_LIMIT_KEYS,
+            "Keys tracked by keyed rate limiters.",
+            "gauge",
+        ),
+        (
+            AXON_RATE_LIMIT_KEY_EVICTIONS_TOTAL,
+            "Keys evicted from keyed rate limiters.",
+            "counter",
+        ),
+    ];
+    for (name, help, kind) in families {
+        out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+        for ((route, limiter), entry) in &stats {
+            let keyed_only = matches!(
+                name,
+                AXON_RATE_LIMIT_KEYS | AXON_RATE_LIMIT_KEY_EVICTIONS_TOTAL
+            );
+            if keyed_only && !entry.keyed {
+                continue;
+            }
+            let route = route.replace('\\', "\\\\").replace('"', "\\\"");
+            let (extra, value) = match name {
+                AXON_RATE_LIMIT_ALLOWED_TOTAL => (String::new(), entry.allowed),
+                AXON_RATE_LIMIT_REJECTED_TOTAL => (String::new(), entry.rejected),
+                AXON_RATE_LIMIT_KEYS => (String::new(), entry.keys),
+                AXON_RATE_LIMIT_KEY_EVICTIONS_TOTAL => (String::new(), entry.evictions),
+                _ => (format!(",period=\"{}\"", entry.period), entry.requests),
+            };
+            out.push_str(&format!(
+                "{name}{{route=\"{route}\",limiter=\"{limiter}\"{extra}}} {value}\n"
+            ));
+        }
+    }
+    out
+}
+
+/// Point-in-time counters for one (route, backend) pair.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrafficSnapshot {
+    pub route: String,
+    pub backend: String,
+    pub requests_total: u64,
+    pub errors_total: u64,
+    pub window: TrafficWindow,
+}
+
+/// Error rate and latency percentiles over the recent window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrafficWindow {
+    pub requests: usize,
+    pub errors: usize,
+    pub error_rate: f64,
+    pub latency_p50_ms: Option<f64>,
+    pub latency_p90_ms: Option<f64>,
+    pub latency_p99_ms: Option<f64>,
+}
+
+/// Record a proxied request's outcome for a route and backend; 5xx statuses
+/// (including gateway-generated 502/504) count as errors.
+pub fn record_route_traffic(
+    route: &str,
+    backend: &str,
+    status: u16,
+    duration: std::time::Duration,
+) {
+    let error = status >= 500;
+    let now = Instant::now();
+    if let Ok(mut stats) = TRAFFIC_STATS.lock() {
+        let entry = stats
+            .entry((route.to_string(), backend.to_string()))
+            .or_default();
+        entry.requests += 1;
+        entry.errors += u64::from(error);
+        entry.prune(now);
+        if entry.window.len() >= TRAFFIC_WINDOW_SAMPLES {
+            entry.window.pop_front();
+        }
+        entry
+            .window
+            .push_back((now, duration.as_secs_f64() * 1000.0, error));
+    }
+}
+
+/// Snapshot of all route/backend traffic, sorted by route then backend.
+pub fn traffic_snapshot() -> Vec<TrafficSnapshot> {
+    let now = Instant::now();
+    let mut snapshot: Vec<_> = TRAFFIC_STATS
+        .lock()
+        .map(|mut stats| {
+            stats
+                .iter_mut()
+                .map(|((route, backend), entry)| {
+                    entry.prune(now);
+                    let mut latencies: Vec<f64> =
+                        entry.window.iter().map(|(_, ms, _)| *ms).collect();
+                    latencies.sort_by(f64::total_cmp);
+                    let errors = entry.window.iter().filter(|(.., error)| *error).count();
+                    let requests = entry.window.len();
+                    TrafficSnapshot {
+                        route: route.clone(),
+                        backend: backend.clone(),
+                        requests_total: entry.requests,
+                        errors_total: entry.errors,
+                        window: TrafficWindow {
+                            requests,
+                            errors,
+                            error_rate: if requests == 0 {
+                                0.0
+                            } else {
+                                errors as f64 / requests as f64
+                            },
+                            latency_p50_ms: percentile(&latencies, 50.0),
+                            latency_p90_ms: percentile(&latencies, 90.0),
+                            latency_p99_ms: percentile(&latencies, 99.0),
+                        },
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    snapshot.sort_by(|a, b| (&a.route, &a.backend).cmp(&(&b.route, &b.backend)));
+    snapshot
+}
+
+/// Clear the recent windows (not the cumulative totals) of every backend of
+/// `route`, or of all routes; returns how many windows were cleared.
+pub fn reset_traffic_windows(route: Option<&str>) -> usize {
+    let Ok(mut stats) = TRAFFIC_STATS.lock() else {
+        return 0;
+    };
+    let mut cleared = 0;
+    for ((entry_route, _), entry) in stats.iter_mut() {
+        if route.is_none_or(|route| route == entry_route) {
+            entry.window.clear();
+            cleared += 1;
+        }
+    }
+    cleared
+}
+
+/// Nearest-rank percentile of already sorted samples.
+fn percentile(sorted: &[f64], pct: f64) -> Option<f64> {
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.max(1) - 1).copied()
+}
+
+/// Record one WASM filter invocation and how long it took.
+pub fn record_wasm_filter_call(
+    filter: &str,
+    phase: &str,
+    outcome: &str,
+    duration: std::time::Duration,
+) {
+    WASM_FILTER_CALLS_TOTAL.add(
+        1,
+        &[
+            KeyValue::new("filter", filter.to_string()),
+            KeyValue::new("phase", phase.to_string()),
+            KeyValue::new("outcome", outcome.to_string()),
+        ],
+    );
+    WASM_FILTER_DURATION_SECONDS.record(
+        duration.as_secs_f64(),
+        &[
+            KeyValue::new("filter", filter.to_string()),
+            KeyValue::new("phase", phase.to_string()),
+        ],
+    );
+}
+
+/// Record one antivirus scan of an uploaded file and how long it took.
+pub fn record_upload_scan(outcome: &'static str, duration: std::time::Duration) {
+    let labels = [KeyValue::new("outcome", outcome)];
+    UPLOAD_SCANS_TOTAL.add(1, &labels);
+    UPLOAD_SCAN_DURATION_SECONDS.record(duration.as_secs_f64(), &labels);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -358,8 +2151,164 @@ mod tests {
 
     #[tokio::test]
     async fn test_init_metrics() {
-        let result = init_metrics().await;
+        let result = init_metrics(&MetricsConfig::default()).await;
         assert!(result.is_ok());
+
+        let config = MetricsConfig {
+            request_duration: HistogramConfig::Explicit {
+                buckets: vec![0.001, 0.005, 0.01, 0.05],
+            },
+            backend_duration: HistogramConfig::Exponential {
+                max_size: 160,
+                max_scale: 20,
+            },
+            ..MetricsConfig::default()
+        };
+        assert!(init_metrics(&config).await.is_ok());
+    }
+
+    #[test]
+    fn test_histogram_aggregation() {
+        use opentelemetry_sdk::metrics::Aggregation;
+
+        assert!(histogram_aggregation(&HistogramConfig::Default).is_none());
+        assert!(matches!(
+            histogram_aggregation(&HistogramConfig::Explicit {
+                buckets: vec![0.001, 0.01]
+            }),
+            Some(Aggregation::ExplicitBucketHistogram { boundaries, .. }) if boundaries == vec![0.001, 0.01]
+        ));
+        assert!(matches!(
+            histogram_aggregation(&HistogramConfig::Exponential {
+                max_size: 80,
+                max_scale: 10
+            }),
+            Some(Aggregation::Base2ExponentialHistogram {
+                max_size: 80,
+                max_scale: 10,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resilient_exporter_opens_circuit() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize};
+
+        use opentelemetry_sdk::{
+            error::{OTelSdkError, OTelSdkResult},
+            metrics::{Temporality, data::ResourceMetrics, exporter::PushMetricExporter},
+        };
+
+        #[derive(Default)]
+        struct FlakyExporter {
+            failing: AtomicBool,
+            calls: AtomicUsize,
+        }
+
+        impl PushMetricExporter for FlakyExporter {
+            async fn export(&self, _metrics: &ResourceMetrics) -> OTelSdkResult {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                if self.failing.load(Ordering::SeqCst) {
+                    Err(OTelSdkError::InternalFailure("collector down".into()))
+                } else {
+                    Ok(())
+                }
+            }
+            fn force_flush(&self) -> OTelSdkResult {
+                Ok(())
+            }
+            fn shutdown_with_timeout(&self, _timeout: std::time::Duration) -> OTelSdkResult {
+                Ok(())
+            }
+            fn temporality(&self) -> Temporality {
+                Temporality::Cumulative
+            }
+        }
+
+        let config = MetricsExportConfig {
+            failure_threshold: 2,
+            error_log: ExportErrorLog::Silent,
+            ..MetricsExportConfig::default()
+        };
+        let exporter = ResilientExporter::new(FlakyExporter::default(), &config);
+        exporter.inner.failing.store(true, Ordering::SeqCst);
+        let batch = ResourceMetrics::default();
+
+        // Failures never reach the SDK; the second one opens the circuit
+        for _ in 0..4 {
+            assert!(exporter.export(&batch).await.is_ok());
+        }
+        assert_eq!(exporter.inner.calls.load(Ordering::SeqCst), 2);
+
+        // Once the open period has passed, a trial export closes the circuit
+        exporter.inner.failing.store(false, Ordering::SeqCst);
+        exporter.state.lock().unwrap().open_until = Some(Instant::now());
+        assert!(exporter.export(&batch).await.is_ok());
+        assert_eq!(exporter.inner.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(exporter.state.lock().unwrap().consecutive_failures, 0);
+
+        let exposition = telemetry_exposition();
+        assert!(exposition.contains("axon_telemetry_dropped_total{reason=\"circuit_open\"}"));
+        assert!(exposition.contains("axon_telemetry_dropped_total{reason=\"export_error\"}"));
+    }
+
+    #[test]
+    fn test_rate_limit_stats() {
+        set_rate_limit_configured("/stats-test", "ip", 10, "1s");
+        record_rate_limit_decision("/stats-test", "ip", true);
+        record_rate_limit_decision("/stats-test", "ip", true);
+        record_rate_limit_decision("/stats-test", "ip", false);
+
+        let stats = rate_limit_stats();
+        let (_, entry) = stats
+            .iter()
+            .find(|(key, _)| key == &("/stats-test".to_string(), "ip".to_string()))
+            .unwrap();
+        assert_eq!(
+            entry,
+            &RateLimitStats {
+                allowed: 2,
+                rejected: 1,
+                requests: 10,
+                period: "1s".to_string(),
+                keyed: false,
+                keys: 0,
+                evictions: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_traffic_snapshot_and_window_reset() {
+        for ms in 1..=10 {
+            let status = if ms == 10 { 502 } else { 200 };
+            record_route_traffic(
+                "/traffic-test",
+                "http://a",
+                status,
+                std::time::Duration::from_millis(ms),
+            );
+        }
+        let find = || {
+            traffic_snapshot()
+                .into_iter()
+                .find(|s| s.route == "/traffic-test")
+                .unwrap()
+        };
+
+        let snapshot = find();
+        assert_eq!((snapshot.requests_total, snapshot.errors_total), (10, 1));
+        assert_eq!(snapshot.window.requests, 10);
+        assert_eq!(snapshot.window.error_rate, 0.1);
+        assert_eq!(snapshot.window.latency_p50_ms, Some(5.0));
+        assert_eq!(snapshot.window.latency_p99_ms, Some(10.0));
+
+        assert_eq!(reset_traffic_windows(Some("/traffic-test")), 1);
+        let snapshot = find();
+        assert_eq!(snapshot.requests_total, 10);
+        assert_eq!(snapshot.window.requests, 0);
+        assert_eq!(snapshot.window.latency_p50_ms, None);
     }
 
     #[test]
@@ -367,5 +2316,6 @@ mod tests {
         set_backend_health_status("http://test", true);
         let metrics = get_current_metrics();
         assert!(metrics.contains_key("backend_health_http://test"));
+        assert!(metrics.contains_key(AXON_UPSTREAM_CONNECTIONS_OPEN));
     }
 }
@@ -1,8 +1,13 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
 use axum::body::Body as AxumBody;
 use eyre::Result;
 use hyper::{Request, Response};
 use thiserror::Error;
 
+use crate::config::models::{EtagMode, ListingFormat};
+
 /// Error type for file system operations
 #[derive(Error, Debug)]
 #[non_exhaustive]
@@ -19,7 +24,31 @@ pub enum FileSystemError {
 /// Result type for file system operations
 pub type FileSystemResult<T> = Result<T, FileSystemError>;
 
+/// Per-route behaviour for static file serving
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StaticServeOptions {
+    /// File served for directory requests (defaults to `index.html`)
+    pub index_file: Option<String>,
+    /// Serve the root index file for missing GET/HEAD paths
+    pub spa_fallback: bool,
+    /// File relative to the root served with status 404 for missing paths
+    pub not_found_page: Option<String>,
+    /// Render a listing for directories without an index file
+    pub directory_listing: bool,
+    /// Body format of generated listings
+    pub listing_format: ListingFormat,
+    /// `Cache-Control` value added to successful responses
+    pub cache_control: Option<String>,
+    /// Serve `.br` / `.gz` siblings when the client accepts them
+    pub precompressed: bool,
+    /// Entity tag generation for served files
+    pub etag: EtagMode,
+    /// Media types by file extension, overriding the built-in table
+    pub content_types: HashMap<String, String>,
+}
+
 /// FileSystem defines the port (interface) for handling static files
+#[async_trait]
 pub trait FileSystem: Send + Sync + 'static {
     /// Serve a file from the file system
     ///
@@ -27,13 +56,15 @@ pub trait FileSystem: Send + Sync + 'static {
     /// * `root` - The root directory to serve files from
     /// * `path` - The path to the file relative to the root
     /// * `req` - The original HTTP request
+    /// * `options` - Index, fallback, listing and caching behaviour
     ///
     /// # Returns
     /// A future that resolves to the file response or an error
-    fn serve_file(
+    async fn serve_file(
         &self,
         root: &str,
         path: &str,
         req: Request<AxumBody>,
-    ) -> impl std::future::Future<Output = FileSystemResult<Response<AxumBody>>> + Send;
+        options: &StaticServeOptions,
+    ) -> FileSystemResult<Response<AxumBody>>;
 }
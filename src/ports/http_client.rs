@@ -1,9 +1,15 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use axum::body::Body as AxumBody;
 use eyre::Result;
 use hyper::{Request, Response, StatusCode};
 use thiserror::Error;
 
+use crate::config::models::{
+    FastCgiConfig, ResponseTimeoutConfig, UpstreamSocketConfig, UpstreamTlsConfig,
+};
+
 /// Custom error type for HTTP client operations
 #[derive(Error, Debug)]
 #[non_exhaustive]
@@ -28,6 +34,61 @@ pub enum HttpClientError {
         /// The status code returned by the backend
         status: StatusCode,
     },
+
+    /// Error when the gateway abandons a request, e.g. because a reload
+    /// removed its backend
+    #[error("Request cancelled: {0}")]
+    Cancelled(String),
+}
+
+/// Per-route options for an outbound request.
+///
+/// Callers attach this as a request extension; `HttpClient` implementations
+/// remove it and apply the settings to the connection used for the request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct UpstreamRequestOptions {
+    /// Backend TLS settings (custom CA, SNI override, client certificate)
+    pub tls: Option<UpstreamTlsConfig>,
+    /// Forward HTTP/1 header names with their original casing
+    pub preserve_header_case: bool,
+    /// Add the client's configured default headers when absent
+    pub inject_default_headers: bool,
+    /// Keep `Connection: upgrade` and `Upgrade` so the backend can switch
+    /// protocols; a `101` response then carries the upgraded connection for
+    /// `hyper::upgrade::on`. Such requests always use HTTP/1.1.
+    pub upgrade: bool,
+    /// Response timeouts; unset limits fall back to the client's
+    /// `upstream.response_timeout`. `total_secs` does not apply to upgrades.
+    pub response_timeout: ResponseTimeoutConfig,
+    /// Socket options of new connections; unset options fall back to the
+    /// client's `upstream.socket`
+    pub socket: UpstreamSocketConfig,
+    /// Script mapping for `fastcgi://` backends
+    pub fastcgi: Option<FastCgiConfig>,
+}
+
+/// Per-backend settings for a health probe.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthCheckOptions {
+    /// Host header sent instead of the URL's authority
+    pub host: Option<String>,
+    /// Backend TLS settings (custom CA, SNI override, client certificate)
+    pub tls: Option<UpstreamTlsConfig>,
+    /// Value of the `Authorization` header, e.g. `Basic ...`
+    pub authorization: Option<String>,
+}
+
+/// How long a backend took to answer.
+///
+/// `HttpClient` implementations attach this as an extension of the responses
+/// they return.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackendTiming {
+    /// Time spent waiting for a new connection; zero when a pooled
+    /// connection was reused
+    pub connect: Duration,
+    /// Time from the request being sent until the response head arrived
+    pub first_byte: Duration,
 }
 
 /// Result type alias for HTTP client operations
@@ -49,9 +110,15 @@ pub trait HttpClient: Send + Sync + 'static {
     ///
     /// # Arguments
     /// * `url` - The URL to check
+    /// * `options` - Host, TLS and credentials the backend expects
     /// * `timeout_secs` - Timeout in seconds
     ///
     /// # Returns
     /// A future that resolves to true if the backend is healthy, false otherwise
-    async fn health_check(&self, url: &str, timeout_secs: u64) -> HttpClientResult<bool>;
+    async fn health_check(
+        &self,
+        url: &str,
+        options: &HealthCheckOptions,
+        timeout_secs: u64,
+    ) -> HttpClientResult<bool>;
 }
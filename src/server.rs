@@ -0,0 +1,898 @@
+//! Running the gateway inside another binary.
+//!
+//! [`Server`] does the wiring the `axon` binary does: it builds the gateway
+//! from a [`ServerConfig`], loads WAF rules and WASM filters, starts health
+//! checks and the audit log, binds the listener (plain, TLS or ACME, plus
+//! HTTP/3 when enabled) and drains connections on shutdown. A cloneable
+//! [`ServerHandle`] reloads the configuration and triggers shutdown while the
+//! server runs.
+//!
+//! Process-wide setup stays with the embedding application: install a rustls
+//! crypto provider, and call [`tracing_setup::init_tracing_from_config`]
+//! and [`metrics::init_metrics`] if you want Axon's telemetry. Reloads
+//! apply changed log filters when tracing was set up that way. Signals are
+//! not handled unless you pass a [`GracefulShutdown`] whose signal handler
+//! you have started.
+//!
+//! ```no_run
+//! # async fn demo() -> eyre::Result<()> {
+//! let config = axon::config::loader::load_config("config.toml").await?;
+//! let server = axon::Server::builder(config).build().await?;
+//!
+//! let handle = server.handle();
+//! tokio::spawn(async move {
+//!     let _ = tokio::signal::ctrl_c().await;
+//!     handle.shutdown();
+//! });
+//!
+//! server.run().await?;
+//! # Ok(()) }
+//! ```
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use axum::{Router, serve::Listener};
+use eyre::{Result, WrapErr};
+use futures_util::StreamExt;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+    sync::Mutex as TokioMutex,
+    task::JoinHandle,
+};
+use tokio_stream::wrappers::TcpListenerStream;
+use tokio_util::{
+    compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt},
+    sync::CancellationToken,
+};
+
+use crate::{
+    adapters::{
+        AuditLog, FileSystemAdapter, HealthChecker, HttpClientAdapter, HttpHandler,
+        MiddlewareRegistry, TlsIo,
+        basic_auth::register_basic_auth,
+        cookie_policy::register_cookie_policies,
+        events::EventPublisher,
+        json_schema::register_json_schemas,
+        ldap_auth::register_ldap_auth,
+        notifications::{Notification, Notifier, notify},
+        opa::register_opa,
+        rbac::register_rbac_policies,
+        serve_until, tls_certs,
+        upload_policy::register_upload_policies,
+        usage::register_usage,
+        waf_rules,
+        wasm_filter::register_wasm_filters,
+    },
+    config::{
+        AcmeConfig, NotificationEvent, ServerConfig, TlsConfig, validation::ServerConfigValidator,
+    },
+    core::GatewayService,
+    metrics,
+    ports::{file_system::FileSystem, http_client::HttpClient},
+    tracing_setup,
+    utils::{ConnectionTracker, GracefulShutdown, graceful_shutdown::ShutdownReason},
+};
+
+/// How often expired automatic bans are lifted
+const BAN_SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Configures a [`Server`]; created with [`Server::builder`].
+pub struct ServerBuilder {
+    config: ServerConfig,
+    http_client: Option<Arc<dyn HttpClient>>,
+    file_system: Option<Arc<dyn FileSystem>>,
+    middleware_registry: Option<MiddlewareRegistry>,
+    listener: Option<TcpListener>,
+    graceful_shutdown: Option<Arc<GracefulShutdown>>,
+}
+
+impl ServerBuilder {
+    /// Send backend and health check traffic through `client` instead of an
+    /// [`HttpClientAdapter`] built from the `upstream` settings.
+    pub fn http_client(mut self, client: Arc<dyn HttpClient>) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Serve static routes from `file_system` instead of a [`FileSystemAdapter`].
+    pub fn file_system(mut self, file_system: Arc<dyn FileSystem>) -> Self {
+        self.file_system = Some(file_system);
+        self
+    }
+
+    /// Start from `registry` (e.g. with custom middlewares) instead of the
+    /// built-ins. WASM filters from the config are added to it.
+    pub fn middleware_registry(mut self, registry: MiddlewareRegistry) -> Self {
+        self.middleware_registry = Some(registry);
+        self
+    }
+
+    /// Accept connections on `listener` instead of binding `listen_addr`.
+    pub fn listener(mut self, listener: TcpListener) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// Stop when `shutdown` is triggered. By default the server gets its own,
+    /// with the configured `shutdown.timeout_secs`, triggered only through
+    /// [`ServerHandle::shutdown`].
+    pub fn graceful_shutdown(mut self, shutdown: Arc<GracefulShutdown>) -> Self {
+        self.graceful_shutdown = Some(shutdown);
+        self
+    }
+
+    /// Validate the configuration, set up the gateway, load TLS certificates
+    /// and bind the listener.
+    ///
+    /// Health checks, WAF rule reloading and ban expiry start here and stop
+    /// when the [`Server`] finishes running or is dropped.
+    pub async fn build(self) -> Result<Server> {
+        ServerConfigValidator::validate(&self.config).context("Invalid configuration")?;
+        let config = Arc::new(self.config);
+
+        let http_client = match self.http_client {
+            Some(client) => client,
+            None => Arc::new(
+                HttpClientAdapter::with_config(&config.upstream)
+                    .context("Failed to create HTTP client adapter")?,
+            ),
+        };
+        let file_system = self
+            .file_system
+            .unwrap_or_else(|| Arc::new(FileSystemAdapter::new()));
+
+        let gateway = Arc::new(GatewayService::new(config.clone()));
+        waf_rules::prepare_waf_rules(&gateway, None)
+            .await
+            .context("Failed to load WAF rules")?;
+        metrics::set_config_generation(gateway.generation());
+
+        let mut middleware_registry = self
+            .middleware_registry
+            .unwrap_or_else(MiddlewareRegistry::with_builtins);
+        register_wasm_filters(&mut middleware_registry, &config.middleware.wasm)
+            .wrap_err("Failed to load WASM filters")?;
+        register_json_schemas(&mut middleware_registry, &config.middleware.json_schema)
+            .wrap_err("Failed to load JSON schemas")?;
+        register_upload_policies(&mut middleware_registry, &config.middleware.upload_policy)
+            .wrap_err("Failed to set up upload policies")?;
+        register_cookie_policies(&mut middleware_registry, &config.middleware.cookie_policy)
+            .wrap_err("Failed to set up cookie policies")?;
+        register_basic_auth(&mut middleware_registry, &config.middleware.basic_auth)
+            .wrap_err("Failed to set up basic authentication")?;
+        register_ldap_auth(&mut middleware_registry, &config.middleware.ldap_auth)
+            .wrap_err("Failed to set up LDAP authentication")?;
+        register_rbac_policies(&mut middleware_registry, &config.middleware.rbac);
+        register_opa(
+            &mut middleware_registry,
+            &config.middleware.opa,
+            http_client.clone(),
+        )
+        .wrap_err("Failed to set up OPA authorization")?;
+        register_usage(
+            &mut middleware_registry,
+            config.middleware.usage.as_ref(),
+            http_client.clone(),
+        )
+        .await
+        .wrap_err("Failed to start usage metering")?;
+        let audit_log = AuditLog::start(&config.audit, http_client.clone())
+            .await
+            .wrap_err("Failed to start the audit log")?;
+        if let Some(notifier) = Notifier::start(&config.notifications, http_client.clone()) {
+            notifier.install();
+        }
+        let events = EventPublisher::start(&config.events)
+            .await
+            .wrap_err("Failed to start the event sink")?;
+
+        let listener = match self.listener {
+            Some(listener) => listener,
+            None => {
+                let addr: SocketAddr = config
+                    .listen_addr
+                    .parse()
+                    .context("Failed to parse listen address")?;
+                TcpListener::bind(addr)
+                    .await
+                    .with_context(|| format!("Failed to bind to {addr}"))?
+            }
+        };
+
+        let tls = config
+            .tls
+            .as_ref()
+            .map(|tls| TlsMode::load(tls, &config))
+            .transpose()?;
+
+        let graceful_shutdown = self.graceful_shutdown.unwrap_or_else(|| {
+            Arc::new(GracefulShutdown::with_timeout(Duration::from_secs(
+                config.shutdown.timeout_secs,
+            )))
+        });
+        let connection_tracker = Arc::new(ConnectionTracker::new().with_drain_poll_interval(
+            Duration::from_millis(config.shutdown.drain_poll_interval_ms),
+        ));
+
+        let handle = ServerHandle {
+            config: Arc::new(ArcSwap::new(config.clone())),
+            gateway: Arc::new(ArcSwap::new(gateway.clone())),
+            http_client: http_client.clone(),
+            health_checker: Arc::new(TokioMutex::new(None)),
+            graceful_shutdown,
+        };
+        let http_handler = Arc::new(
+            HttpHandler::new(
+                handle.gateway.clone(),
+                http_client,
+                file_system,
+                connection_tracker.clone(),
+                handle.config.clone(),
+            )
+            .with_middleware_registry(middleware_registry)
+            .with_audit_log(audit_log)
+            .with_event_publisher(events),
+        );
+
+        let mut tasks = BackgroundTasks {
+            tasks: vec![waf_rules::spawn_waf_rules_watcher(handle.gateway.clone())],
+            health_checker: handle.health_checker.clone(),
+        };
+        // Lift expired automatic bans and keep the banned IP gauge current
+        let gateway_for_bans = handle.gateway.clone();
+        tasks.tasks.push(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(BAN_SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                gateway_for_bans.load().sweep_bans();
+            }
+        }));
+        handle.restart_health_checker(&config, gateway).await;
+
+        Ok(Server {
+            handle,
+            http_handler,
+            connection_tracker,
+            listener,
+            tls,
+            tasks,
+        })
+    }
+}
+
+/// An embedded Axon gateway, ready to [`run`](Server::run).
+pub struct Server {
+    handle: ServerHandle,
+    http_handler: Arc<HttpHandler>,
+    connection_tracker: Arc<ConnectionTracker>,
+    listener: TcpListener,
+    tls: Option<TlsMode>,
+    tasks: BackgroundTasks,
+}
+
+impl Server {
+    /// Start configuring a server for `config`
+    pub fn builder(config: ServerConfig) -> ServerBuilder {
+        ServerBuilder {
+            config,
+            http_client: None,
+            file_system: None,
+            middleware_registry: None,
+            listener: None,
+            graceful_shutdown: None,
+        }
+    }
+
+    /// A handle for reloading and stopping the server
+    pub fn handle(&self) -> ServerHandle {
+        self.handle.clone()
+    }
+
+    /// Address the listener is bound to
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serve until shutdown is triggered, then wait for open connections to
+    /// drain (bounded by the `shutdown` settings) and stop background tasks.
+    ///
+    /// HTTP/3 is started here when `protocols.http3_enabled` is set and the
+    /// crate is built with the `http3` feature.
+    pub async fn run(self) -> Result<()> {
+        let Server {
+            handle,
+            http_handler,
+            connection_tracker,
+            listener,
+            tls,
+            mut tasks,
+        } = self;
+        let config = handle.config();
+
+        #[cfg(feature = "http3")]
+        if let Some(http3) =
+            start_http3(&config, &http_handler, &connection_tracker, &handle).await?
+        {
+            tasks.tasks.push(http3);
+        }
+
+        for (prefix, route) in &config.routes {
+            tracing::info!("Configured route: {} -> {:?}", prefix, route);
+        }
+        let app = HttpHandler::clone(&http_handler).into_router();
+
+        // Stop accepting on shutdown; open connections drain below
+        let drain = CancellationToken::new();
+        {
+            let graceful_shutdown = handle.graceful_shutdown.clone();
+            let connection_tracker = connection_tracker.clone();
+            let drain = drain.clone();
+            tasks.tasks.push(tokio::spawn(async move {
+                let mut shutdown_rx = graceful_shutdown.subscribe();
+                if !graceful_shutdown.is_shutdown_initiated() {
+                    let reason = shutdown_rx.recv().await.unwrap_or(ShutdownReason::Force);
+                    tracing::info!("Shutdown signal received: {:?}", reason);
+                }
+                connection_tracker.signal_shutdown();
+                drain.cancel();
+            }));
+        }
+
+        tracing::info!(
+            "Axon API Gateway server starting on {}",
+            listener.local_addr()?
+        );
+        match tls {
+            Some(tls) => serve_tls(listener, tls, app, drain, connection_tracker.clone()).await?,
+            None => serve_until(listener, app, drain, connection_tracker.clone())
+                .await
+                .context("Server error")?,
+        }
+
+        // Let requests accepted before shutdown finish, then give connections
+        // that outlive them (WebSockets, slow readers) a last chance to close
+        connection_tracker
+            .wait_for_drain(handle.graceful_shutdown.shutdown_timeout())
+            .await;
+        let force_close_after =
+            Duration::from_secs(handle.config().shutdown.force_close_after_secs);
+        if !connection_tracker.wait_for_close(force_close_after).await {
+            tracing::warn!(
+                "Force-closing {} connections still open after the drain",
+                connection_tracker.active_connection_count()
+            );
+        }
+
+        tasks.stop().await;
+        tracing::info!("Graceful shutdown completed");
+        Ok(())
+    }
+}
+
+/// Controls a [`Server`]; cheap to clone and usable from any task.
+#[derive(Clone)]
+pub struct ServerHandle {
+    config: Arc<ArcSwap<ServerConfig>>,
+    gateway: Arc<ArcSwap<GatewayService>>,
+    http_client: Arc<dyn HttpClient>,
+    health_checker: Arc<TokioMutex<Option<JoinHandle<()>>>>,
+    graceful_shutdown: Arc<GracefulShutdown>,
+}
+
+impl ServerHandle {
+    /// The configuration currently in effect
+    pub fn config(&self) -> Arc<ServerConfig> {
+        self.config.load_full()
+    }
+
+    /// The gateway currently serving requests
+    pub fn gateway(&self) -> Arc<GatewayService> {
+        self.gateway.load_full()
+    }
+
+    /// Switch to `config` without dropping connections.
+    ///
+    /// Traffic steering, bans, WAF state and canaries whose settings are
+    /// unchanged carry over; backends missing
+    /// from the new config are retired per `backend_removal`, and the health
+    /// checker restarts with the new settings. The listener, TLS, HTTP/3,
+    /// WASM filters, the audit log and notifications keep their startup
+    /// settings. On error the current configuration stays in place. Either
+    /// outcome is sent to the notification webhooks.
+    pub async fn reload(&self, config: ServerConfig) -> Result<()> {
+        match self.apply(config).await {
+            Ok(generation) => {
+                notify(
+                    Notification::new(NotificationEvent::ConfigReloaded)
+                        .detail(format!("configuration generation {generation}")),
+                );
+                Ok(())
+            }
+            Err(e) => {
+                notify(
+                    Notification::new(NotificationEvent::ConfigReloadFailed)
+                        .detail(format!("{e:#}")),
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Switch to `config`, returning its generation
+    async fn apply(&self, config: ServerConfig) -> Result<u64> {
+        ServerConfigValidator::validate(&config).context("Invalid configuration")?;
+        let config = Arc::new(config);
+
+        let current_gateway = self.gateway.load_full();
+        let new_gateway = Arc::new(
+            GatewayService::new(config.clone())
+                .with_steering(current_gateway.steering())
+                .with_taps(current_gateway.taps())
+                .with_bans(current_gateway.bans())
+                .with_canaries(&current_gateway)
+                .with_generation(current_gateway.generation() + 1),
+        );
+        waf_rules::prepare_waf_rules(&new_gateway, Some(&current_gateway))
+            .await
+            .context("Failed to load WAF rules for the new configuration")?;
+        drop(current_gateway);
+
+        let previous_config = self.config.swap(config.clone());
+        if let Err(e) = tracing_setup::reload_filter(&previous_config.tracing, &config.tracing) {
+            tracing::warn!("Keeping the previous log filter: {e:#}");
+        }
+        let previous_gateway = self.gateway.swap(new_gateway.clone());
+        tracing::info!(
+            "Gateway switched to configuration generation {}",
+            new_gateway.generation()
+        );
+
+        // In-flight traffic to dropped backends follows the new removal policy
+        let removed =
+            previous_gateway.retire_removed_backends(&new_gateway, &config.backend_removal);
+        if !removed.is_empty() {
+            tracing::info!(backends = ?removed, "Backends removed by reload");
+        }
+        drop(previous_gateway);
+        let generation = new_gateway.generation();
+        metrics::set_config_generation(generation);
+
+        self.restart_health_checker(&config, new_gateway).await;
+        Ok(generation)
+    }
+
+    /// Stop accepting connections and let [`Server::run`] drain and return
+    pub fn shutdown(&self) {
+        let _ = self
+            .graceful_shutdown
+            .trigger_shutdown(ShutdownReason::Graceful);
+    }
+
+    /// Replace the running health checker with one for `gateway`, if enabled
+    async fn restart_health_checker(&self, config: &ServerConfig, gateway: Arc<GatewayService>) {
+        let mut health_checker = self.health_checker.lock().await;
+        if let Some(previous) = health_checker.take() {
+            previous.abort();
+        }
+        let health_check = &config.health_check;
+        if !health_check.enabled {
+            tracing::info!("Health checking is disabled in the configuration.");
+            return;
+        }
+        tracing::info!(
+            "Health checker started. Interval: {}s, Path: {}, Unhealthy Threshold: {}, Healthy Threshold: {}",
+            health_check.interval_secs,
+            health_check.path,
+            health_check.unhealthy_threshold,
+            health_check.healthy_threshold
+        );
+        let checker = HealthChecker::new(gateway, self.http_client.clone());
+        *health_checker = Some(tokio::spawn(async move {
+            if let Err(e) = checker.run().await {
+                tracing::error!("Health checker run error: {}", e);
+            }
+        }));
+    }
+}
+
+/// Tasks owned by a [`Server`], aborted when it stops or is dropped
+struct BackgroundTasks {
+    tasks: Vec<JoinHandle<()>>,
+    health_checker: Arc<TokioMutex<Option<JoinHandle<()>>>>,
+}
+
+impl BackgroundTasks {
+    async fn stop(&mut self) {
+        if let Some(health_checker) = self.health_checker.lock().await.take() {
+            tracing::info!("Shutting down health checker...");
+            health_checker.abort();
+        }
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+    }
+}
+
+impl Drop for BackgroundTasks {
+    fn drop(&mut self) {
+        if let Ok(mut health_checker) = self.health_checker.try_lock()
+            && let Some(health_checker) = health_checker.take()
+        {
+            health_checker.abort();
+        }
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Where TLS certificates come from
+enum TlsMode {
+    /// Obtained and renewed through ACME (Let's Encrypt)
+    Acme(AcmeConfig),
+    /// Loaded from the configured files at startup
+    Manual(tokio_rustls::TlsAcceptor),
+}
+
+impl TlsMode {
+    fn load(tls: &TlsConfig, config: &ServerConfig) -> Result<Self> {
+        if let Some(acme) = &tls.acme {
+            return Ok(Self::Acme(acme.clone()));
+        }
+        let config = tls_certs::server_config(config)?;
+        Ok(Self::Manual(tokio_rustls::TlsAcceptor::from(Arc::new(
+            config,
+        ))))
+    }
+}
+
+/// Serve TLS connections on `listener`
+async fn serve_tls(
+    listener: TcpListener,
+    tls: TlsMode,
+    app: Router,
+    drain: CancellationToken,
+    tracker: Arc<ConnectionTracker>,
+) -> Result<()> {
+    let local_addr = listener.local_addr().context("Failed to get local addr")?;
+    match tls {
+        TlsMode::Acme(acme) => {
+            use rustls_acme::caches::DirCache;
+
+            tracing::info!("Starting server with ACME (Let's Encrypt) support");
+            let state = rustls_acme::AcmeConfig::new(acme.domains)
+                .contact([format!("mailto:{}", acme.email)])
+                .cache(RenewalNotifyingCache(DirCache::new(acme.cache_dir)))
+                .directory_lets_encrypt(acme.production)
+                .state();
+
+            let incoming = state.incoming(
+                TcpListenerStream::new(listener).map(|res| res.map(|s| s.compat())),
+                vec![],
+            );
+            let stream = incoming
+                .filter_map(|res| async {
+                    match res {
+                        Ok(stream) => {
+                            let stream = stream.compat();
+                            let (tcp, session) = stream.get_ref().get_ref();
+                            let addr = tcp.get_ref().peer_addr().unwrap_or_else(|_| {
+                                "0.0.0.0:0".parse().expect("valid fallback address")
+                            });
+                            let server_name = session.server_name().map(str::to_string);
+                            Some(Ok::<_, std::io::Error>((
+                                TlsIo::new(stream, server_name.as_deref()),
+                                addr,
+                            )))
+                        }
+                        Err(e) => {
+                            tracing::debug!("TLS accept error: {}", e);
+                            None
+                        }
+                    }
+                })
+                .boxed();
+
+            let tls_listener = StreamListener { stream, local_addr };
+            // Boxing pins down the `Send` bound, which the compiler cannot
+            // otherwise prove through the accept stream's closures
+            let serving: futures_util::future::BoxFuture<'_, std::io::Result<()>> =
+                Box::pin(serve_until(tls_listener, app, drain, tracker));
+            serving.await.context("Server error")
+        }
+        TlsMode::Manual(acceptor) => {
+            use tls_listener::TlsListener;
+
+            tracing::info!("Starting server with manual TLS");
+            let stream = TlsListener::new(acceptor, listener).map(|accepted| {
+                accepted.map(|(stream, addr)| {
+                    let server_name = stream.get_ref().1.server_name().map(str::to_string);
+                    (TlsIo::new(stream, server_name.as_deref()), addr)
+                })
+            });
+
+            let tls_listener = StreamListener { stream, local_addr };
+            serve_until(tls_listener, app, drain, tracker)
+                .await
+                .context("Server error")
+        }
+    }
+}
+
+/// An ACME cache that reports each newly issued certificate, which is
+/// stored right after it is obtained.
+struct RenewalNotifyingCache<C>(C);
+
+#[async_trait::async_trait]
+impl<C: rustls_acme::CertCache> rustls_acme::CertCache for RenewalNotifyingCache<C> {
+    type EC = C::EC;
+
+    async fn load_cert(
+        &self,
+        domains: &[String],
+        directory_url: &str,
+    ) -> Result<Option<Vec<u8>>, Self::EC> {
+        self.0.load_cert(domains, directory_url).await
+    }
+
+    async fn store_cert(
+        &self,
+        domains: &[String],
+        directory_url: &str,
+        cert: &[u8],
+    ) -> Result<(), Self::EC> {
+        tracing::info!(domains = ?domains, "ACME certificate issued");
+        notify(
+            Notification::new(NotificationEvent::CertificateRenewed)
+                .subject(domains.join(","))
+                .detail(format!("issued by {directory_url}")),
+        );
+        self.0.store_cert(domains, directory_url, cert).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: rustls_acme::AccountCache> rustls_acme::AccountCache for RenewalNotifyingCache<C> {
+    type EA = C::EA;
+
+    async fn load_account(
+        &self,
+        contact: &[String],
+        directory_url: &str,
+    ) -> Result<Option<Vec<u8>>, Self::EA> {
+        self.0.load_account(contact, directory_url).await
+    }
+
+    async fn store_account(
+        &self,
+        contact: &[String],
+        directory_url: &str,
+        account: &[u8],
+    ) -> Result<(), Self::EA> {
+        self.0.store_account(contact, directory_url, account).await
+    }
+}
+
+/// A [`Listener`] over a stream of accepted TLS connections
+struct StreamListener<S> {
+    stream: S,
+    local_addr: SocketAddr,
+}
+
+impl<S, I, E> Listener for StreamListener<S>
+where
+    S: futures_util::Stream<Item = Result<(I, SocketAddr), E>> + Unpin + Send + 'static,
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    E: std::fmt::Display + Send + 'static,
+{
+    type Io = I;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            match self.stream.next().await {
+                Some(Ok((io, addr))) => return (io, addr),
+                Some(Err(e)) => tracing::debug!("Accept error: {}", e),
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+/// Start the QUIC endpoint if HTTP/3 is enabled. Problems with the
+/// certificate files are logged and leave HTTP/3 off.
+#[cfg(feature = "http3")]
+async fn start_http3(
+    config: &ServerConfig,
+    http_handler: &Arc<HttpHandler>,
+    connection_tracker: &Arc<ConnectionTracker>,
+    handle: &ServerHandle,
+) -> Result<Option<JoinHandle<()>>> {
+    if !config.protocols.http3_enabled {
+        return Ok(None);
+    }
+    let Some(tls) = &config.tls else {
+        tracing::warn!(
+            "HTTP/3 enabled but TLS configuration missing – QUIC requires TLS; skipping http3 start"
+        );
+        return Ok(None);
+    };
+    if tls.cert_path.is_none() || tls.key_path.is_none() {
+        tracing::warn!(
+            "HTTP/3 enabled but TLS manual cert/key paths not provided (ACME unsupported yet)"
+        );
+        return Ok(None);
+    }
+    let mut server_config = match tls_certs::server_config(config) {
+        Ok(server_config) => server_config,
+        Err(e) => {
+            tracing::error!("Failed loading certificates for http3: {e:#}");
+            return Ok(None);
+        }
+    };
+    let Ok(addr) = config.listen_addr.parse::<SocketAddr>() else {
+        tracing::error!("Failed to parse listen address for http3");
+        return Ok(None);
+    };
+
+    server_config.alpn_protocols = vec![b"h3".to_vec()];
+    match crate::adapters::http3::spawn_http3(
+        addr,
+        http_handler.clone(),
+        server_config,
+        &config.protocols.http3_config.clone().unwrap_or_default(),
+        connection_tracker.clone(),
+        Some(handle.graceful_shutdown.shutdown_token()),
+    )
+    .await
+    {
+        Ok(task) => {
+            tracing::info!("HTTP/3 endpoint started on {addr}");
+            Ok(Some(task))
+        }
+        Err(e) => {
+            tracing::error!(error=%e, "Failed to start HTTP/3 endpoint");
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::config::RouteConfig;
+
+    fn redirect_config(target: &str) -> ServerConfig {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/".to_string(),
+            RouteConfig::Redirect {
+                target: target.to_string(),
+                host: None,
+                status_code: Some(302),
+                rate_limit: None,
+                middlewares: Vec::new(),
+                error_pages: None,
+                disable_http3: false,
+                priority: None,
+            }
+            .into(),
+        );
+        ServerConfig {
+            listen_addr: "127.0.0.1:0".to_string(),
+            routes,
+            ..ServerConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embedded_server_reload_and_shutdown() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = Server::builder(redirect_config("https://one.example/"))
+            .listener(listener)
+            .build()
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = server.handle();
+        let running = tokio::spawn(server.run());
+
+        let client = HttpClientAdapter::new().unwrap();
+        let location = || async {
+            let request = hyper::Request::get(format!("http://{addr}/"))
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let response = client.send_request(request).await.unwrap();
+            response.headers()["location"].to_str().unwrap().to_string()
+        };
+        assert_eq!(location().await, "https://one.example/");
+
+        let generation = handle.gateway().generation();
+        handle
+            .reload(redirect_config("https://two.example/"))
+            .await
+            .unwrap();
+        assert_eq!(handle.gateway().generation(), generation + 1);
+        assert_eq!(location().await, "https://two.example/");
+
+        // An invalid config is rejected and the current one stays in place
+        let mut invalid = redirect_config("https://three.example/");
+        invalid.routes.clear();
+        assert!(handle.reload(invalid).await.is_err());
+        assert_eq!(handle.gateway().generation(), generation + 1);
+
+        handle.shutdown();
+        tokio::time::timeout(Duration::from_secs(10), running)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_canary_rollback_survives_unchanged_reload() {
+        use crate::{
+            config::{CanaryConfig, CanaryRollbackConfig, LoadBalanceStrategy},
+            core::CanaryGroup,
+        };
+
+        let config = |weight| {
+            let mut route = RouteConfig::load_balance(
+                vec!["http://stable:3000".to_string()],
+                LoadBalanceStrategy::RoundRobin,
+            );
+            if let RouteConfig::LoadBalance { canary, .. } = &mut route {
+                *canary = Some(CanaryConfig {
+                    targets: vec!["http://canary:3000".to_string()],
+                    weight,
+                    rollback: Some(CanaryRollbackConfig {
+                        window_secs: 60,
+                        min_requests: 1,
+                        max_error_rate_increase: Some(0.1),
+                        max_latency_ratio: None,
+                        webhook_url: None,
+                    }),
+                });
+            }
+            let mut config = redirect_config("https://one.example/");
+            config.routes.insert("/api".to_string(), route.into());
+            config
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = Server::builder(config(10))
+            .listener(listener)
+            .build()
+            .await
+            .unwrap();
+        let handle = server.handle();
+        let canary = || handle.gateway().get_canary("/api", None).unwrap();
+
+        let latency = Duration::from_millis(1);
+        assert!(
+            canary()
+                .record(CanaryGroup::Stable, false, latency)
+                .is_none()
+        );
+        assert!(
+            canary()
+                .record(CanaryGroup::Canary, true, latency)
+                .is_some()
+        );
+        assert!(canary().is_rolled_back());
+
+        handle.reload(config(10)).await.unwrap();
+        assert!(canary().is_rolled_back());
+
+        // New canary settings start it over
+        handle.reload(config(20)).await.unwrap();
+        assert!(!canary().is_rolled_back());
+        assert_eq!(canary().effective_weight(), 20);
+    }
+}
@@ -1,8 +1,12 @@
 //! Tracing initialization utilities.
 //!
 //! Centralizes setup of `tracing` subscribers for different runtime scenarios:
-//! * Auto mode (`init_tracing`) selects human readable formatting when stderr
-//!   is a TTY, else JSON (good for containers).
+//! * Configured mode (`init_tracing_from_config`) applies the `[tracing]`
+//!   section: level filters, output format and optional OTLP span export.
+//!   Its filter can be replaced at runtime with `set_log_filter`.
+//! * Auto mode (`init_tracing`) is configured mode with the defaults:
+//!   human readable formatting when stderr is a TTY, else JSON (good for
+//!   containers).
 //! * Developer mode (`init_console_tracing`) enables pretty formatting with
 //!   rich metadata.
 //! * Custom mode (`init_tracing_with_config`) lets callers choose level,
@@ -10,55 +14,334 @@
 //!
 //! Helper functions create commonly used spans with consistent field sets so
 //! downstream code records structured attributes uniformly.
-use std::io::IsTerminal;
+use std::{io::IsTerminal, str::FromStr, sync::Mutex};
 
-use eyre::{Result, WrapErr};
-use tracing_subscriber::{EnvFilter, Registry, layer::SubscriberExt, util::SubscriberInitExt};
+use eyre::{Result, WrapErr, eyre};
+use opentelemetry::{
+    Context as OtelContext, KeyValue,
+    trace::{TraceContextExt, Tracer, TracerProvider as _},
+};
+use opentelemetry_sdk::trace::{Sampler, SdkTracer, SdkTracerProvider};
+use tracing::{
+    Event, Subscriber,
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    span::{Attributes, Id, Record},
+};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry,
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    reload,
+    util::SubscriberInitExt,
+};
+
+use crate::config::models::{LogFormat, TracingConfig, TracingOtlpConfig};
+
+/// Filter installed by `init_tracing_from_config`, kept for runtime changes
+struct LogFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+    directives: Mutex<String>,
+}
+
+static LOG_FILTER: once_cell::sync::OnceCell<LogFilter> = once_cell::sync::OnceCell::new();
+static TRACER_PROVIDER: once_cell::sync::OnceCell<SdkTracerProvider> =
+    once_cell::sync::OnceCell::new();
 
 /// Initialize structured logging with automatic format selection.
 ///
 /// Human‑friendly compact format when attached to a TTY, otherwise JSON.
 pub fn init_tracing() -> Result<()> {
-    let is_terminal = std::io::stderr().is_terminal();
+    init_tracing_from_config(&TracingConfig::default())
+}
 
-    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+/// Initialize logging (and span export, if configured) from the `[tracing]`
+/// configuration section.
+///
+/// Must be called within a Tokio runtime when `otlp` is set. `RUST_LOG`, when
+/// set, takes the place of the configured filters.
+pub fn init_tracing_from_config(config: &TracingConfig) -> Result<()> {
+    let directives = configured_filter(config);
+    let env_filter = EnvFilter::try_new(&directives)
+        .wrap_err_with(|| format!("Invalid log filter: {directives}"))?;
+    let (filter_layer, handle) = reload::Layer::new(env_filter);
 
-    if is_terminal {
-        // Terminal output: human-friendly, compact format
-        Registry::default()
-            .with(env_filter)
-            .with(
-                tracing_subscriber::fmt::layer()
-                    .compact()
-                    .with_target(false)
-                    .with_thread_ids(false)
-                    .with_file(false)
-                    .with_line_number(false)
-                    .with_ansi(true),
-            )
-            .init();
-        tracing::info!("🚀 Axon logging initialized (terminal mode)");
-    } else {
-        // Non-terminal: structured JSON format for log aggregation
-        Registry::default()
-            .with(env_filter)
-            .with(
-                tracing_subscriber::fmt::layer()
-                    .json()
-                    .with_current_span(false)
-                    .with_span_list(false)
-                    .with_target(true)
-                    .with_thread_ids(false)
-                    .with_file(false)
-                    .with_line_number(false),
-            )
-            .init();
-        tracing::info!("Axon logging initialized (JSON mode)");
-    }
+    let format = match config.format {
+        LogFormat::Auto if std::io::stderr().is_terminal() => LogFormat::Compact,
+        LogFormat::Auto => LogFormat::Json,
+        format => format,
+    };
+    let fmt_layer = match format {
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(false)
+            .with_span_list(false)
+            .with_target(true)
+            .with_thread_ids(false)
+            .with_file(false)
+            .with_line_number(false)
+            .boxed(),
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .pretty()
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_file(true)
+            .with_line_number(true)
+            .boxed(),
+        LogFormat::Compact | LogFormat::Auto => tracing_subscriber::fmt::layer()
+            .compact()
+            .with_target(false)
+            .with_thread_ids(false)
+            .with_file(false)
+            .with_line_number(false)
+            .with_ansi(std::io::stderr().is_terminal())
+            .boxed(),
+    };
+
+    let otel_layer = match &config.otlp {
+        Some(otlp) => {
+            let provider = otlp_tracer_provider(otlp)?;
+            let layer = OtelLayer::new(provider.tracer("axon"));
+            let _ = TRACER_PROVIDER.set(provider);
+            Some(layer)
+        }
+        None => None,
+    };
+
+    Registry::default()
+        .with(filter_layer)
+        .with(fmt_layer)
+        .with(otel_layer)
+        .try_init()
+        .wrap_err("A global tracing subscriber is already installed")?;
+    let _ = LOG_FILTER.set(LogFilter {
+        handle,
+        directives: Mutex::new(directives.clone()),
+    });
 
+    tracing::info!(
+        filter = %directives,
+        format = ?format,
+        otlp = config.otlp.is_some(),
+        "Axon logging initialized"
+    );
     Ok(())
 }
 
+/// The filter a configuration asks for: `RUST_LOG` if set, else `level` and
+/// `modules`.
+pub fn configured_filter(config: &TracingConfig) -> String {
+    std::env::var(EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|directives| !directives.is_empty())
+        .unwrap_or_else(|| config.filter_directives())
+}
+
+/// The active filter, if logging was set up by `init_tracing_from_config`.
+pub fn log_filter() -> Option<String> {
+    LOG_FILTER
+        .get()
+        .map(|filter| match filter.directives.lock() {
+            Ok(directives) => directives.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        })
+}
+
+/// Replace the active filter with `directives` (`EnvFilter` syntax, e.g.
+/// `info,axon::adapters::http_client=debug`).
+pub fn set_log_filter(directives: &str) -> Result<()> {
+    let filter = LOG_FILTER
+        .get()
+        .ok_or_else(|| eyre!("Log filters are not reloadable in this process"))?;
+    let env_filter = EnvFilter::try_new(directives)
+        .wrap_err_with(|| format!("Invalid log filter: {directives}"))?;
+    filter
+        .handle
+        .reload(env_filter)
+        .wrap_err("Failed to replace the log filter")?;
+    let mut current = match filter.directives.lock() {
+        Ok(current) => current,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    *current = directives.to_string();
+    Ok(())
+}
+
+/// Apply a reloaded configuration's filters when they differ from the
+/// previous configuration's. Runtime changes made through the admin API
+/// survive reloads that leave the filters alone.
+pub fn reload_filter(previous: &TracingConfig, config: &TracingConfig) -> Result<()> {
+    let directives = configured_filter(config);
+    if LOG_FILTER.get().is_none() || configured_filter(previous) == directives {
+        return Ok(());
+    }
+    set_log_filter(&directives)?;
+    tracing::info!(filter = %directives, "Log filter changed by reload");
+    Ok(())
+}
+
+/// Check that `level` names a level (`trace` .. `error`, or `off`).
+pub fn validate_level(level: &str) -> std::result::Result<(), String> {
+    LevelFilter::from_str(level)
+        .map(|_| ())
+        .map_err(|_| format!("'{level}' is not one of trace, debug, info, warn, error, off"))
+}
+
+fn otlp_tracer_provider(config: &TracingOtlpConfig) -> Result<SdkTracerProvider> {
+    use opentelemetry_otlp::{WithExportConfig, WithTonicConfig};
+    use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+
+    let mut metadata = MetadataMap::new();
+    for (name, value) in &config.headers {
+        let key = MetadataKey::from_bytes(name.as_bytes())
+            .wrap_err_with(|| format!("Invalid OTLP header name: {name}"))?;
+        let value = MetadataValue::try_from(value.as_str())
+            .wrap_err_with(|| format!("Invalid value for OTLP header {name}"))?;
+        metadata.insert(key, value);
+    }
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(&config.endpoint)
+        .with_timeout(std::time::Duration::from_secs(config.timeout_secs))
+        .with_metadata(metadata)
+        .build()
+        .wrap_err("Failed to create the OTLP span exporter")?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(
+            opentelemetry_sdk::Resource::builder()
+                .with_service_name("axon")
+                .build(),
+        )
+        .build())
+}
+
+/// OpenTelemetry context of a `tracing` span, kept in its extensions
+struct SpanContext(OtelContext);
+
+/// Mirrors `tracing` spans as OpenTelemetry spans: fields become attributes,
+/// events inside a span become span events, and the span ends when it
+/// closes.
+struct OtelLayer {
+    tracer: SdkTracer,
+}
+
+impl OtelLayer {
+    fn new(tracer: SdkTracer) -> Self {
+        Self { tracer }
+    }
+}
+
+impl<S> Layer<S> for OtelLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let parent = span
+            .parent()
+            .and_then(|parent| {
+                parent
+                    .extensions()
+                    .get::<SpanContext>()
+                    .map(|cx| cx.0.clone())
+            })
+            .unwrap_or_default();
+        let mut fields = FieldVisitor::default();
+        attrs.record(&mut fields);
+        let otel_span = self
+            .tracer
+            .span_builder(attrs.metadata().name())
+            .with_attributes(fields.attributes)
+            .start_with_context(&self.tracer, &parent);
+        span.extensions_mut()
+            .insert(SpanContext(parent.with_span(otel_span)));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if let Some(cx) = span.extensions().get::<SpanContext>() {
+            let mut fields = FieldVisitor::default();
+            values.record(&mut fields);
+            cx.0.span().set_attributes(fields.attributes);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.event_span(event) else {
+            return;
+        };
+        if let Some(cx) = span.extensions().get::<SpanContext>() {
+            let mut fields = FieldVisitor::default();
+            event.record(&mut fields);
+            let name = fields
+                .message
+                .unwrap_or_else(|| event.metadata().name().to_string());
+            let mut attributes = fields.attributes;
+            attributes.push(KeyValue::new("level", event.metadata().level().as_str()));
+            cx.0.span().add_event(name, attributes);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        if let Some(cx) = span.extensions_mut().remove::<SpanContext>() {
+            cx.0.span().end();
+        }
+    }
+}
+
+/// Collects `tracing` fields as OpenTelemetry attributes
+#[derive(Default)]
+struct FieldVisitor {
+    attributes: Vec<KeyValue>,
+    message: Option<String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else {
+            self.attributes
+                .push(KeyValue::new(field.name(), value.to_string()));
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.attributes.push(KeyValue::new(field.name(), value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.attributes.push(KeyValue::new(
+            field.name(),
+            i64::try_from(value).unwrap_or(i64::MAX),
+        ));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.attributes.push(KeyValue::new(field.name(), value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.attributes.push(KeyValue::new(field.name(), value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record_str(field, &format!("{value:?}"));
+    }
+}
+
 /// Initialize verbose console logging for local development.
 pub fn init_console_tracing() -> Result<()> {
     tracing::info!("Initializing Axon console logging");
@@ -118,11 +401,14 @@ pub fn init_tracing_with_config(level: &str, json_format: bool, include_spans: b
     Ok(())
 }
 
-/// Emit shutdown messages (explicit flushing not currently required).
+/// Flush exported spans and emit shutdown messages.
 pub fn shutdown_tracing() {
     tracing::info!("Axon tracing shutdown initiated");
-    // tracing-subscriber doesn't provide explicit shutdown,
-    // but we can flush any pending logs
+    if let Some(provider) = TRACER_PROVIDER.get()
+        && let Err(e) = provider.shutdown()
+    {
+        tracing::warn!("Failed to flush exported spans: {e}");
+    }
     tracing::info!("Axon tracing shutdown complete");
 }
 
@@ -151,16 +437,24 @@ pub fn create_request_span(
         duration_ms = tracing::field::Empty,
         bytes_sent = tracing::field::Empty,
         bytes_received = tracing::field::Empty,
+        client.disconnected = tracing::field::Empty,
     )
 }
 
-/// Create a backend request span capturing target URL and verb/path.
-pub fn create_backend_span(backend_url: &str, method: &str, path: &str) -> tracing::Span {
+/// Create a backend request span capturing target URL, verb/path and the
+/// correlating request ID.
+pub fn create_backend_span(
+    backend_url: &str,
+    method: &str,
+    path: &str,
+    request_id: &str,
+) -> tracing::Span {
     tracing::info_span!(
         "backend_request",
         backend.url = backend_url,
         http.method = method,
         http.path = path,
+        request.id = request_id,
         http.status_code = tracing::field::Empty,
         duration_ms = tracing::field::Empty,
     )
@@ -193,13 +487,75 @@ mod tests {
 
     #[test]
     fn test_create_backend_span() {
-        let span = create_backend_span("http://backend", "POST", "/data");
+        let span = create_backend_span("http://backend", "POST", "/data", "req-123");
         assert_eq!(
             span.metadata().expect("Should have metadata").name(),
             "backend_request"
         );
     }
 
+    /// Keeps exported spans for inspection
+    #[derive(Clone, Debug, Default)]
+    struct CaptureSpans(std::sync::Arc<Mutex<Vec<opentelemetry_sdk::trace::SpanData>>>);
+
+    impl opentelemetry_sdk::trace::SpanExporter for CaptureSpans {
+        async fn export(
+            &self,
+            batch: Vec<opentelemetry_sdk::trace::SpanData>,
+        ) -> opentelemetry_sdk::error::OTelSdkResult {
+            self.0.lock().unwrap().extend(batch);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_otel_layer_exports_nested_spans() {
+        let capture = CaptureSpans::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(capture.clone())
+            .build();
+        let subscriber = Registry::default().with(OtelLayer::new(provider.tracer("test")));
+        tracing::subscriber::with_default(subscriber, || {
+            let request = create_request_span("GET", "/api", "req-1", None, None);
+            let _entered = request.enter();
+            request.record("http.status_code", 200);
+            let backend = create_backend_span("http://backend", "GET", "/api", "req-1");
+            backend.in_scope(|| tracing::warn!(attempt = 2, "retrying"));
+        });
+
+        let spans = capture.0.lock().unwrap();
+        let request = spans.iter().find(|span| span.name == "request").unwrap();
+        let backend = spans
+            .iter()
+            .find(|span| span.name == "backend_request")
+            .unwrap();
+        assert_eq!(backend.parent_span_id, request.span_context.span_id());
+        assert_eq!(
+            backend.span_context.trace_id(),
+            request.span_context.trace_id()
+        );
+        assert!(
+            request
+                .attributes
+                .contains(&KeyValue::new("http.status_code", 200))
+        );
+        assert!(
+            request
+                .attributes
+                .contains(&KeyValue::new("request.id", "req-1"))
+        );
+        let event = &backend.events.events[0];
+        assert_eq!(event.name, "retrying");
+        assert!(event.attributes.contains(&KeyValue::new("attempt", 2)));
+    }
+
+    #[test]
+    fn test_validate_level() {
+        assert!(validate_level("debug").is_ok());
+        assert!(validate_level("OFF").is_ok());
+        assert!(validate_level("verbose").is_err());
+    }
+
     #[test]
     fn test_configure_component_tracing() {
         let span = configure_component_tracing("health_checker");
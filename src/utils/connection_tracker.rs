@@ -1,19 +1,22 @@
 //! Connection tracking & graceful draining utilities.
 //!
 //! Each TCP connection is registered with a lightweight record keeping active
-//! request count and age. During shutdown the tracker can wait for in‑flight
-//! requests to finish (bounded by a timeout) and optionally close idle ones.
+//! request count and age. The server loop registers connections on accept,
+//! counts each request until its response body ends, and unregisters on
+//! close. During shutdown the tracker can wait for in‑flight requests to
+//! finish (bounded by a timeout) and optionally close idle ones.
 use std::{
     net::SocketAddr,
     sync::{
         Arc,
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
     },
     time::{Duration, Instant},
 };
 
 use scc::HashMap;
 use tokio::{sync::broadcast, time::sleep};
+use tokio_util::sync::CancellationToken;
 
 /// Unique identifier for a connection
 pub type ConnectionId = u64;
@@ -25,6 +28,7 @@ pub struct ConnectionInfo {
     pub remote_addr: SocketAddr,
     pub established_at: Instant,
     pub active_requests: AtomicU64,
+    close: CancellationToken,
 }
 
 impl Clone for ConnectionInfo {
@@ -34,6 +38,7 @@ impl Clone for ConnectionInfo {
             remote_addr: self.remote_addr,
             established_at: self.established_at,
             active_requests: AtomicU64::new(self.active_requests.load(Ordering::Relaxed)),
+            close: self.close.clone(),
         }
     }
 }
@@ -45,9 +50,16 @@ impl ConnectionInfo {
             remote_addr,
             established_at: Instant::now(),
             active_requests: AtomicU64::new(0),
+            close: CancellationToken::new(),
         }
     }
 
+    /// Count a request until the returned guard is dropped.
+    pub fn track_request(self: &Arc<Self>) -> RequestGuard {
+        self.increment_requests();
+        RequestGuard(self.clone())
+    }
+
     pub fn increment_requests(&self) {
         self.active_requests.fetch_add(1, Ordering::Relaxed);
     }
@@ -67,6 +79,26 @@ impl ConnectionInfo {
     pub fn age(&self) -> Duration {
         self.established_at.elapsed()
     }
+
+    /// Ask the connection's owner to close it once in-flight requests finish.
+    pub fn request_close(&self) {
+        self.close.cancel();
+    }
+
+    /// Resolves once [`request_close`](Self::request_close) has been called.
+    pub async fn close_requested(&self) {
+        self.close.cancelled().await;
+    }
+}
+
+/// Keeps a request counted against its connection while alive.
+#[derive(Debug)]
+pub struct RequestGuard(Arc<ConnectionInfo>);
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        self.0.decrement_requests();
+    }
 }
 
 /// Manages active connections and provides graceful draining capabilities
@@ -75,6 +107,8 @@ pub struct ConnectionTracker {
     connections: Arc<HashMap<ConnectionId, Arc<ConnectionInfo>>>,
     next_id: Arc<AtomicU64>,
     shutdown_tx: broadcast::Sender<()>,
+    shutting_down: Arc<AtomicBool>,
+    drain_poll_interval: Duration,
 }
 
 impl ConnectionTracker {
@@ -85,9 +119,17 @@ impl ConnectionTracker {
             connections: Arc::new(HashMap::new()),
             next_id: Arc::new(AtomicU64::new(1)),
             shutdown_tx,
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            drain_poll_interval: Duration::from_millis(100),
         }
     }
 
+    /// Set how often draining checks for remaining requests and connections.
+    pub fn with_drain_poll_interval(mut self, interval: Duration) -> Self {
+        self.drain_poll_interval = interval;
+        self
+    }
+
     /// Register a new connection and return its info
     /// Register a new connection and return an Arc to its info record.
     pub async fn register_connection(&self, remote_addr: SocketAddr) -> Arc<ConnectionInfo> {
@@ -217,9 +259,15 @@ impl ConnectionTracker {
     /// Broadcast a shutdown signal to subscribers.
     pub fn signal_shutdown(&self) {
         tracing::info!("Signaling shutdown to all connections");
+        self.shutting_down.store(true, Ordering::Relaxed);
         let _ = self.shutdown_tx.send(());
     }
 
+    /// Whether [`signal_shutdown`](Self::signal_shutdown) has been called.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Relaxed)
+    }
+
     /// Subscribe to shutdown signals
     /// Subscribe to the shutdown broadcast channel.
     pub fn subscribe_shutdown(&self) -> broadcast::Receiver<()> {
@@ -230,7 +278,6 @@ impl ConnectionTracker {
     /// Wait until all connections are idle or the timeout elapses.
     pub async fn wait_for_drain(&self, timeout: Duration) -> bool {
         let start = Instant::now();
-        let mut check_interval = Duration::from_millis(100);
 
         while start.elapsed() < timeout {
             if !self.has_active_requests().await {
@@ -245,10 +292,7 @@ impl ConnectionTracker {
                 start.elapsed()
             );
 
-            sleep(check_interval).await;
-
-            // Exponential backoff up to 1 second
-            check_interval = std::cmp::min(check_interval * 2, Duration::from_secs(1));
+            sleep(self.drain_poll_interval).await;
         }
 
         let remaining_requests = self.total_active_requests().await;
@@ -261,14 +305,30 @@ impl ConnectionTracker {
         false
     }
 
+    /// Wait until every connection has been closed and unregistered, or the
+    /// timeout elapses.
+    pub async fn wait_for_close(&self, timeout: Duration) -> bool {
+        let start = Instant::now();
+
+        while start.elapsed() < timeout {
+            if self.connections.is_empty() {
+                return true;
+            }
+            sleep(self.drain_poll_interval).await;
+        }
+
+        self.connections.is_empty()
+    }
+
     /// Force close all idle connections
-    /// Force removal of all idle connections.
+    /// Ask every idle connection to close; each is unregistered by its owner
+    /// once the socket is closed.
     pub async fn close_idle_connections(&self) {
         let idle_connections = self.get_idle_connections().await;
         tracing::info!("Closing {} idle connections", idle_connections.len());
 
         for connection in idle_connections {
-            self.unregister_connection(connection.id).await;
+            connection.request_close();
         }
     }
 
@@ -356,6 +416,31 @@ mod tests {
         assert!(conn.is_idle());
     }
 
+    #[tokio::test]
+    async fn test_request_guard_and_idle_close() {
+        let tracker = ConnectionTracker::new();
+        let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+
+        let busy = tracker.register_connection(addr).await;
+        let idle = tracker.register_connection(addr).await;
+        let guard = busy.track_request();
+        assert_eq!(busy.active_request_count(), 1);
+
+        tracker.close_idle_connections().await;
+        tokio::time::timeout(Duration::from_millis(50), idle.close_requested())
+            .await
+            .expect("idle connection asked to close");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), busy.close_requested())
+                .await
+                .is_err()
+        );
+
+        drop(guard);
+        assert!(busy.is_idle());
+        assert_eq!(tracker.active_connection_count(), 2);
+    }
+
     #[tokio::test]
     async fn test_drain_functionality() {
         let tracker = ConnectionTracker::new();
@@ -372,6 +457,21 @@ mod tests {
         assert!(tracker.wait_for_drain(Duration::from_millis(50)).await);
     }
 
+    #[tokio::test]
+    async fn test_wait_for_close() {
+        let tracker = ConnectionTracker::new().with_drain_poll_interval(Duration::from_millis(5));
+        let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        assert!(!tracker.is_shutting_down());
+
+        let conn = tracker.register_connection(addr).await;
+        tracker.signal_shutdown();
+        assert!(tracker.is_shutting_down());
+        assert!(!tracker.wait_for_close(Duration::from_millis(20)).await);
+
+        tracker.unregister_connection(conn.id).await;
+        assert!(tracker.wait_for_close(Duration::from_millis(20)).await);
+    }
+
     #[tokio::test]
     async fn test_connection_stats() {
         let tracker = ConnectionTracker::new();
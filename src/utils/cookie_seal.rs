@@ -0,0 +1,214 @@
+//! Cookie sealing with rotating keys.
+//!
+//! A [`CookieSealer`] encrypts (AES-256-GCM) or signs (HMAC-SHA256) cookie
+//! values with keys derived from base64 secrets. New values always use the
+//! current key; values made with a retired key are still accepted until that
+//! key's grace period ends, so keys can be rotated without logging everyone
+//! out. Values are bound to the cookie name, so one cookie's value cannot be
+//! replayed as another's.
+//!
+//! Encrypted values are `base64url(nonce || ciphertext || tag)`; signed
+//! values are `value.base64url(hmac(name=value))`.
+use base64::{
+    Engine as _,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use chrono::{DateTime, Utc};
+use ring::{
+    aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+    hmac,
+    rand::{SecureRandom, SystemRandom},
+};
+use thiserror::Error;
+
+/// Shortest accepted secret, in bytes
+pub const MIN_SECRET_BYTES: usize = 32;
+
+/// A secret that cannot be used for sealing
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SealError {
+    #[error("Cookie secret is not valid base64")]
+    InvalidBase64,
+    #[error("Cookie secret must be at least {MIN_SECRET_BYTES} bytes")]
+    TooShort,
+}
+
+/// Keys derived from one secret
+struct SealKey {
+    aead: LessSafeKey,
+    mac: hmac::Key,
+    /// End of the grace period of a retired key
+    accept_until: Option<DateTime<Utc>>,
+}
+
+impl SealKey {
+    fn new(secret: &str, accept_until: Option<DateTime<Utc>>) -> Result<Self, SealError> {
+        let secret = STANDARD
+            .decode(secret.trim())
+            .map_err(|_| SealError::InvalidBase64)?;
+        if secret.len() < MIN_SECRET_BYTES {
+            return Err(SealError::TooShort);
+        }
+        let master = hmac::Key::new(hmac::HMAC_SHA256, &secret);
+        let derive = |label: &[u8]| hmac::sign(&master, label);
+        let aead = UnboundKey::new(&AES_256_GCM, derive(b"axon cookie encryption").as_ref())
+            .expect("an HMAC-SHA256 tag is a valid AES-256 key");
+        Ok(Self {
+            aead: LessSafeKey::new(aead),
+            mac: hmac::Key::new(hmac::HMAC_SHA256, derive(b"axon cookie signing").as_ref()),
+            accept_until,
+        })
+    }
+}
+
+/// Seals cookie values with a current key and accepts retired keys during
+/// their grace period.
+pub struct CookieSealer {
+    /// The current key first, then retired keys
+    keys: Vec<SealKey>,
+    rng: SystemRandom,
+}
+
+impl CookieSealer {
+    /// Create a sealer whose current key is derived from a base64 secret.
+    pub fn new(secret: &str) -> Result<Self, SealError> {
+        Ok(Self {
+            keys: vec![SealKey::new(secret, None)?],
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// Also accept values sealed with a retired secret until `accept_until`.
+    pub fn with_retired_secret(
+        mut self,
+        secret: &str,
+        accept_until: DateTime<Utc>,
+    ) -> Result<Self, SealError> {
+        self.keys.push(SealKey::new(secret, Some(accept_until))?);
+        Ok(self)
+    }
+
+    /// Keys that may open values right now, current key first.
+    fn accepted_keys(&self) -> impl Iterator<Item = &SealKey> {
+        let now = Utc::now();
+        self.keys
+            .iter()
+            .filter(move |key| key.accept_until.is_none_or(|until| now < until))
+    }
+
+    /// Encrypt a cookie value with the current key.
+    pub fn seal(&self, name: &str, value: &str) -> String {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .expect("system random source is available");
+        let mut in_out = value.as_bytes().to_vec();
+        self.keys[0]
+            .aead
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(name.as_bytes()),
+                &mut in_out,
+            )
+            .expect("cookie values are far below the AES-GCM size limit");
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&in_out);
+        URL_SAFE_NO_PAD.encode(sealed)
+    }
+
+    /// Decrypt a value made by [`seal`](Self::seal); `None` if it was
+    /// tampered with, made for another cookie, or made with an unknown or
+    /// expired key.
+    pub fn open(&self, name: &str, sealed: &str) -> Option<String> {
+        let sealed = URL_SAFE_NO_PAD.decode(sealed).ok()?;
+        if sealed.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        self.accepted_keys().find_map(|key| {
+            let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+            let mut in_out = ciphertext.to_vec();
+            let plain = key
+                .aead
+                .open_in_place(nonce, Aad::from(name.as_bytes()), &mut in_out)
+                .ok()?;
+            String::from_utf8(plain.to_vec()).ok()
+        })
+    }
+
+    /// Append a signature made with the current key to a cookie value.
+    pub fn sign(&self, name: &str, value: &str) -> String {
+        let tag = hmac::sign(&self.keys[0].mac, format!("{name}={value}").as_bytes());
+        format!("{value}.{}", URL_SAFE_NO_PAD.encode(tag.as_ref()))
+    }
+
+    /// The value of a cookie made by [`sign`](Self::sign), if its signature
+    /// verifies with an accepted key.
+    pub fn verify<'a>(&self, name: &str, signed: &'a str) -> Option<&'a str> {
+        let (value, tag) = signed.rsplit_once('.')?;
+        let tag = URL_SAFE_NO_PAD.decode(tag).ok()?;
+        let message = format!("{name}={value}");
+        self.accepted_keys()
+            .any(|key| hmac::verify(&key.mac, message.as_bytes(), &tag).is_ok())
+            .then_some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn secret(byte: u8) -> String {
+        STANDARD.encode([byte; MIN_SECRET_BYTES])
+    }
+
+    #[test]
+    fn test_seal_and_sign() {
+        let sealer = CookieSealer::new(&secret(1)).unwrap();
+        let sealed = sealer.seal("session", "user-42");
+        assert!(!sealed.contains("user-42"));
+        assert_ne!(sealed, sealer.seal("session", "user-42"), "fresh nonce");
+        assert_eq!(sealer.open("session", &sealed).as_deref(), Some("user-42"));
+        assert_eq!(sealer.open("other", &sealed), None);
+        assert_eq!(sealer.open("session", "bm9wZQ"), None);
+
+        let signed = sealer.sign("cart", "a.b");
+        assert_eq!(sealer.verify("cart", &signed), Some("a.b"));
+        assert_eq!(sealer.verify("other", &signed), None);
+        assert_eq!(sealer.verify("cart", &signed.replacen('a', "c", 1)), None);
+
+        assert_eq!(
+            CookieSealer::new("not base64!").err(),
+            Some(SealError::InvalidBase64)
+        );
+        assert_eq!(
+            CookieSealer::new(&STANDARD.encode([1u8; 16])).err(),
+            Some(SealError::TooShort)
+        );
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old = CookieSealer::new(&secret(1)).unwrap();
+        let sealed = old.seal("session", "user-42");
+        let signed = old.sign("cart", "3");
+
+        let rotated = CookieSealer::new(&secret(2))
+            .unwrap()
+            .with_retired_secret(&secret(1), Utc::now() + Duration::hours(1))
+            .unwrap();
+        assert_eq!(rotated.open("session", &sealed).as_deref(), Some("user-42"));
+        assert_eq!(rotated.verify("cart", &signed), Some("3"));
+        // New values use the new key only
+        assert_eq!(old.open("session", &rotated.seal("session", "x")), None);
+
+        let expired = CookieSealer::new(&secret(2))
+            .unwrap()
+            .with_retired_secret(&secret(1), Utc::now() - Duration::seconds(1))
+            .unwrap();
+        assert_eq!(expired.open("session", &sealed), None);
+        assert_eq!(expired.verify("cart", &signed), None);
+    }
+}
@@ -4,6 +4,8 @@
 //! events across async tasks plus convenience tokens for cooperative
 //! cancellation. Supports:
 //! * OS signal handling (SIGINT / SIGTERM for graceful shutdown, SIGUSR1 for restart on Unix).
+//!   SIGUSR2 binary upgrades are handled by `socket_handover`, which triggers
+//!   shutdown once the new process is ready.
 //! * Manual programmatic triggering (e.g. admin API) via `trigger_shutdown`.
 //! * Timeout‑guarded waiting (`wait_for_shutdown`) vs infinite wait.
 //! * Lightweight cloneable `ShutdownToken` to poll or await signals.
@@ -25,6 +27,8 @@ pub enum ShutdownReason {
     Graceful,
     /// Restart requested (SIGUSR1)
     Restart,
+    /// A new process took over the listener (SIGUSR2)
+    Upgrade,
     /// Force shutdown (timeout exceeded)
     Force,
 }
@@ -55,6 +59,11 @@ impl GracefulShutdown {
         }
     }
 
+    /// Maximum time to wait for in-flight work once shutdown starts.
+    pub fn shutdown_timeout(&self) -> Duration {
+        self.shutdown_timeout
+    }
+
     /// Subscribe to future shutdown events.
     pub fn subscribe(&self) -> broadcast::Receiver<ShutdownReason> {
         self.shutdown_tx.subscribe()
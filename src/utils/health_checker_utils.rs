@@ -178,6 +178,7 @@ mod tests {
                 unhealthy_threshold: 3,
                 healthy_threshold: 2,
                 timeout_secs: 5,
+                max_concurrency: None,
             },
             ..Default::default()
         }
@@ -1,7 +1,10 @@
 pub mod connection_tracker;
+pub mod cookie_seal;
 pub mod graceful_shutdown;
 pub mod health_checker_utils;
+pub mod socket_handover;
 
-pub use connection_tracker::{ConnectionInfo, ConnectionStats, ConnectionTracker};
+pub use connection_tracker::{ConnectionInfo, ConnectionStats, ConnectionTracker, RequestGuard};
+pub use cookie_seal::CookieSealer;
 pub use graceful_shutdown::GracefulShutdown;
 pub use health_checker_utils::*;
@@ -0,0 +1,190 @@
+//! Zero-downtime binary upgrades by handing the listening socket to a new
+//! process.
+//!
+//! On SIGUSR2 the running gateway re-executes its own binary with the same
+//! arguments. The listening socket's descriptor is inherited by the child and
+//! named in `AXON_LISTEN_FD`, so the kernel keeps queueing connections
+//! throughout the switch. The child reports readiness by writing one byte to
+//! the pipe named in `AXON_READY_FD`. Only then does the parent stop
+//! accepting and drain its connections via [`GracefulShutdown`]. If the child
+//! exits or is not ready within the timeout, the parent keeps serving and the
+//! upgrade can be retried.
+#[cfg(unix)]
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::Result;
+
+#[cfg(unix)]
+use crate::utils::graceful_shutdown::{GracefulShutdown, ShutdownReason};
+
+/// Environment variable carrying the inherited listener's descriptor.
+pub const LISTEN_FD_ENV: &str = "AXON_LISTEN_FD";
+/// Environment variable carrying the write end of the readiness pipe.
+pub const READY_FD_ENV: &str = "AXON_READY_FD";
+
+/// How long a new process may take to report readiness.
+pub const READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Take over the listener passed by a parent process, if any.
+#[cfg(unix)]
+pub fn inherited_listener() -> Result<Option<std::net::TcpListener>> {
+    use std::os::fd::{FromRawFd, RawFd};
+
+    use eyre::{WrapErr, eyre};
+
+    let Ok(value) = std::env::var(LISTEN_FD_ENV) else {
+        return Ok(None);
+    };
+    let fd: RawFd = value
+        .parse()
+        .wrap_err_with(|| format!("Invalid {LISTEN_FD_ENV} value '{value}'"))?;
+    // SAFETY: libc::fcntl only inspects the descriptor.
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(eyre!("{LISTEN_FD_ENV}={fd} is not an open descriptor"));
+    }
+    // SAFETY: the parent passed this descriptor to us for exclusive use.
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+    listener.set_nonblocking(true)?;
+    Ok(Some(listener))
+}
+
+#[cfg(not(unix))]
+pub fn inherited_listener() -> Result<Option<std::net::TcpListener>> {
+    Ok(None)
+}
+
+/// Tell the parent process that this one is accepting connections.
+///
+/// Does nothing when the process was not started by an upgrade.
+#[cfg(unix)]
+pub fn notify_ready() {
+    use std::{
+        io::Write,
+        os::fd::{FromRawFd, RawFd},
+    };
+
+    let Some(fd) = std::env::var(READY_FD_ENV)
+        .ok()
+        .and_then(|value| value.parse::<RawFd>().ok())
+    else {
+        return;
+    };
+    // SAFETY: the parent passed the pipe's write end to us; we close it here.
+    let mut pipe = unsafe { std::fs::File::from_raw_fd(fd) };
+    if let Err(e) = pipe.write_all(&[1]) {
+        tracing::warn!("Failed to report readiness to the previous process: {e}");
+    }
+}
+
+#[cfg(not(unix))]
+pub fn notify_ready() {}
+
+/// Start a new copy of this binary that inherits `listener_fd`, and wait
+/// until it reports readiness. Returns the new process id.
+#[cfg(unix)]
+pub async fn spawn_successor(
+    listener_fd: std::os::fd::RawFd,
+    ready_timeout: Duration,
+) -> Result<u32> {
+    use std::{io::Read, os::fd::AsRawFd};
+
+    use eyre::{WrapErr, eyre};
+
+    let (mut ready_reader, ready_writer) = std::io::pipe()?;
+    let ready_fd = ready_writer.as_raw_fd();
+
+    let mut command = tokio::process::Command::new(std::env::current_exe()?);
+    command
+        .args(std::env::args_os().skip(1))
+        .env(LISTEN_FD_ENV, listener_fd.to_string())
+        .env(READY_FD_ENV, ready_fd.to_string());
+    // SAFETY: the closure only calls async-signal-safe fcntl.
+    unsafe {
+        command.pre_exec(move || {
+            for fd in [listener_fd, ready_fd] {
+                if libc::fcntl(fd, libc::F_SETFD, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+    let mut child = command.spawn().wrap_err("Failed to start new process")?;
+    let pid = child.id().unwrap_or_default();
+    // Our copy of the write end must be closed so EOF means the child is gone.
+    drop(ready_writer);
+
+    let ready = tokio::task::spawn_blocking(move || {
+        let mut byte = [0u8; 1];
+        ready_reader.read(&mut byte).map(|read| read == 1)
+    });
+    match tokio::time::timeout(ready_timeout, ready).await {
+        Ok(Ok(Ok(true))) => Ok(pid),
+        Ok(Ok(Ok(false))) => Err(eyre!("New process {pid} exited before becoming ready")),
+        Ok(Ok(Err(e))) => Err(eyre!("Failed to read readiness of process {pid}: {e}")),
+        Ok(Err(e)) => Err(eyre!("Readiness wait for process {pid} failed: {e}")),
+        Err(_) => {
+            let _ = child.kill().await;
+            Err(eyre!(
+                "New process {pid} was not ready within {ready_timeout:?} and was stopped"
+            ))
+        }
+    }
+}
+
+/// Handle SIGUSR2 by upgrading to a fresh copy of the binary, then trigger
+/// a graceful shutdown of this process. Failed attempts are logged and the
+/// current process keeps serving.
+#[cfg(unix)]
+pub async fn run_upgrade_handler(
+    listener_fd: std::os::fd::RawFd,
+    shutdown: Arc<GracefulShutdown>,
+    ready_timeout: Duration,
+) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut sigusr2 = match signal(SignalKind::user_defined2()) {
+        Ok(sigusr2) => sigusr2,
+        Err(e) => {
+            tracing::error!("Failed to register SIGUSR2 handler, upgrades disabled: {e}");
+            return;
+        }
+    };
+    while sigusr2.recv().await.is_some() {
+        if shutdown.is_shutdown_initiated() {
+            break;
+        }
+        tracing::info!("Received SIGUSR2, starting binary upgrade...");
+        match spawn_successor(listener_fd, ready_timeout).await {
+            Ok(pid) => {
+                tracing::info!("New process {pid} is ready, draining this one");
+                let _ = shutdown.trigger_shutdown(ShutdownReason::Upgrade);
+                break;
+            }
+            Err(e) => tracing::error!("Binary upgrade failed, still serving: {e}"),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::fd::IntoRawFd;
+
+    use super::*;
+
+    #[test]
+    fn test_inherited_listener_round_trip() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let fd = listener.into_raw_fd();
+
+        // SAFETY: tests in this module are the only readers of the variable.
+        unsafe { std::env::set_var(LISTEN_FD_ENV, fd.to_string()) };
+        let inherited = inherited_listener().unwrap().expect("listener");
+        unsafe { std::env::remove_var(LISTEN_FD_ENV) };
+
+        assert_eq!(inherited.local_addr().unwrap(), addr);
+        assert!(inherited_listener().unwrap().is_none());
+    }
+}
@@ -19,33 +19,16 @@ mod tests {
         // Add a route with host specified
         config.routes.insert(
             "/api-with-host".to_string(),
-            RouteConfigEntry::Single(Box::new(RouteConfig::Proxy {
-                target: "http://api-backend:3001".to_string(),
-                host: Some("api.example.com".to_string()),
-                path_rewrite: None,
-                rate_limit: None,
-                request_headers: None,
-                response_headers: None,
-                request_body: None,
-                response_body: None,
-                middlewares: vec![],
-            })),
+            RouteConfigEntry::Single(Box::new(proxy(
+                "http://api-backend:3001",
+                Some("api.example.com"),
+            ))),
         );
 
         // Add a default route without host for same path pattern
         config.routes.insert(
             "/api".to_string(),
-            RouteConfigEntry::Single(Box::new(RouteConfig::Proxy {
-                target: "http://default-backend:5000".to_string(),
-                host: None,
-                path_rewrite: None,
-                rate_limit: None,
-                request_headers: None,
-                response_headers: None,
-                request_body: None,
-                response_body: None,
-                middlewares: vec![],
-            })),
+            RouteConfigEntry::Single(Box::new(proxy("http://default-backend:5000", None))),
         );
 
         let gateway = GatewayService::new(Arc::new(config));
@@ -90,17 +73,7 @@ mod tests {
 
         config.routes.insert(
             "/".to_string(),
-            RouteConfigEntry::Single(Box::new(RouteConfig::Proxy {
-                target: "http://backend:3000".to_string(),
-                host: Some("Example.Com".to_string()),
-                path_rewrite: None,
-                rate_limit: None,
-                request_headers: None,
-                response_headers: None,
-                request_body: None,
-                response_body: None,
-                middlewares: vec![],
-            })),
+            RouteConfigEntry::Single(Box::new(proxy("http://backend:3000", Some("Example.Com")))),
         );
 
         let gateway = GatewayService::new(Arc::new(config));
@@ -133,33 +106,19 @@ mod tests {
         // Longer path with host
         config.routes.insert(
             "/api/v2".to_string(),
-            RouteConfigEntry::Single(Box::new(RouteConfig::Proxy {
-                target: "http://api-v2:3002".to_string(),
-                host: Some("api.example.com".to_string()),
-                path_rewrite: None,
-                rate_limit: None,
-                request_headers: None,
-                response_headers: None,
-                request_body: None,
-                response_body: None,
-                middlewares: vec![],
-            })),
+            RouteConfigEntry::Single(Box::new(proxy(
+                "http://api-v2:3002",
+                Some("api.example.com"),
+            ))),
         );
 
         // Shorter path with same host
         config.routes.insert(
             "/api".to_string(),
-            RouteConfigEntry::Single(Box::new(RouteConfig::Proxy {
-                target: "http://api-v1:3001".to_string(),
-                host: Some("api.example.com".to_string()),
-                path_rewrite: None,
-                rate_limit: None,
-                request_headers: None,
-                response_headers: None,
-                request_body: None,
-                response_body: None,
-                middlewares: vec![],
-            })),
+            RouteConfigEntry::Single(Box::new(proxy(
+                "http://api-v1:3001",
+                Some("api.example.com"),
+            ))),
         );
 
         let gateway = GatewayService::new(Arc::new(config));
@@ -182,4 +141,188 @@ mod tests {
             assert_eq!(target, "http://api-v1:3001");
         }
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_virtual_hosts_with_wildcards() {
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::with_suffix(".toml").unwrap();
+        write!(
+            file,
+            r#"
+listen_addr = "127.0.0.1:8080"
+
+[waf]
+enabled = false
+
+[routes."/"]
+type = "proxy"
+target = "http://default:5000"
+
+[virtual_hosts.shop]
+hostnames = ["shop.example.com", "*.shop.example.com"]
+waf = true
+
+[virtual_hosts.shop.routes."/"]
+type = "proxy"
+target = "http://shop:3000"
+
+[virtual_hosts.eu-shop]
+hostnames = ["*.eu.shop.example.com"]
+
+[virtual_hosts.eu-shop.routes."/api"]
+type = "proxy"
+target = "http://eu-shop:3000"
+
+[virtual_hosts.admin]
+hostnames = ["admin.shop.example.com"]
+waf = false
+
+[virtual_hosts.admin.routes."/admin"]
+type = "proxy"
+target = "http://admin:3000"
+"#
+        )
+        .unwrap();
+        let config = axon::config::load_config(file.path().to_str().unwrap())
+            .await
+            .unwrap();
+        axon::config::ServerConfigValidator::validate(&config).unwrap();
+        let gateway = GatewayService::new(Arc::new(config));
+
+        let target = |path: &str, host: Option<&str>| match gateway.find_matching_route(path, host)
+        {
+            Some((_, RouteConfig::Proxy { target, .. })) => target,
+            other => panic!("Expected Proxy route, got {other:?}"),
+        };
+        assert_eq!(target("/", Some("shop.example.com")), "http://shop:3000");
+        assert_eq!(target("/", Some("DE.shop.example.com")), "http://shop:3000");
+        assert_eq!(
+            target("/api/items", Some("fr.eu.shop.example.com")),
+            "http://eu-shop:3000"
+        );
+        // A host is only served by its own virtual host's routes, so the WAF
+        // setting that applies to it covers the route that answers. Other
+        // paths fall back to the host-less routes.
+        assert_eq!(
+            target("/cart", Some("fr.eu.shop.example.com")),
+            "http://default:5000"
+        );
+        assert_eq!(
+            target("/admin", Some("admin.shop.example.com")),
+            "http://admin:3000"
+        );
+        assert_eq!(
+            target("/", Some("admin.shop.example.com")),
+            "http://default:5000"
+        );
+        // A wildcard covers a single label
+        assert_eq!(
+            target("/", Some("a.b.shop.example.com")),
+            "http://default:5000"
+        );
+        assert_eq!(target("/", Some("example.com")), "http://default:5000");
+
+        let (name, _) = gateway
+            .virtual_host(Some("fr.eu.shop.example.com"))
+            .unwrap();
+        assert_eq!(name, "eu-shop");
+        assert!(gateway.virtual_host(Some("other.example.com")).is_none());
+        assert!(gateway.virtual_host(Some("a.b.shop.example.com")).is_none());
+        assert!(gateway.is_waf_enabled(Some("de.shop.example.com")));
+        assert!(!gateway.is_waf_enabled(Some("fr.eu.shop.example.com")));
+        assert!(!gateway.is_waf_enabled(Some("admin.shop.example.com")));
+        assert!(!gateway.is_waf_enabled(None));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_route_priority_overrides_longest_prefix() {
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::with_suffix(".toml").unwrap();
+        write!(
+            file,
+            r#"
+listen_addr = "127.0.0.1:8080"
+
+[routes."/"]
+type = "proxy"
+target = "http://default:5000"
+
+[routes."/api"]
+type = "proxy"
+target = "http://maintenance:5000"
+priority = 10
+
+[routes."/api/v2"]
+type = "proxy"
+host = "api.example.com"
+target = "http://api-v2:3000"
+
+[routes."/assets"]
+type = "proxy"
+host = "api.example.com"
+target = "http://assets:3000"
+
+[routes."/assets/img"]
+type = "proxy"
+host = "*.example.com"
+target = "http://images:3000"
+
+[routes."/api/v3"]
+type = "proxy"
+host = "*.example.com"
+target = "http://api-v3:3000"
+priority = 20
+"#
+        )
+        .unwrap();
+        let config = axon::config::load_config(file.path().to_str().unwrap())
+            .await
+            .unwrap();
+        axon::config::ServerConfigValidator::validate(&config).unwrap();
+        let gateway = GatewayService::new(Arc::new(config));
+
+        let target = |path: &str, host: Option<&str>| match gateway.find_matching_route(path, host)
+        {
+            Some((_, RouteConfig::Proxy { target, .. })) => target,
+            other => panic!("Expected Proxy route, got {other:?}"),
+        };
+        // A higher priority beats both a longer prefix and an exact host
+        assert_eq!(
+            target("/api/v2/users", Some("api.example.com")),
+            "http://maintenance:5000"
+        );
+        assert_eq!(
+            target("/api/v3/users", Some("api.example.com")),
+            "http://api-v3:3000"
+        );
+        assert_eq!(target("/api/v3", None), "http://maintenance:5000");
+        // Equal priorities keep the usual order
+        assert_eq!(
+            target("/assets/img/logo.png", Some("api.example.com")),
+            "http://assets:3000"
+        );
+        assert_eq!(
+            target("/assets/img/logo.png", Some("cdn.example.com")),
+            "http://images:3000"
+        );
+        assert_eq!(target("/other", None), "http://default:5000");
+        assert!(
+            gateway
+                .find_matching_route("/api", Some("api.example.org"))
+                .is_some()
+        );
+    }
+
+    fn proxy(target: &str, host: Option<&str>) -> RouteConfig {
+        let mut route = RouteConfig::proxy(target);
+        if let RouteConfig::Proxy {
+            host: route_host, ..
+        } = &mut route
+        {
+            *route_host = host.map(str::to_string);
+        }
+        route
+    }
 }
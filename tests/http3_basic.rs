@@ -7,7 +7,10 @@
 //! * HTTP/3 QUIC listener via `spawn_http3`
 //! * An h3 client (quinn) performing a GET /api/test over HTTP/3
 //!
-//! Verifies that the proxy path works end-to-end and returns the expected body.
+//! Verifies that the proxy path works end-to-end and returns the expected body,
+//! that a request body larger than the stream window is streamed through and
+//! back, that the QUIC connection is tracked, and that a route with
+//! `disable_http3` answers 421.
 //!
 //! NOTE: This test purposefully avoids spawning the full binary; it assembles the
 //! required pieces directly to keep the test fast and deterministic.
@@ -17,7 +20,7 @@ use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
 use arc_swap::ArcSwap;
 use axon::{
     adapters::{FileSystemAdapter, HttpClientAdapter, HttpHandler, http3},
-    config::models::{ProtocolConfig, RouteConfig, RouteConfigEntry, ServerConfig},
+    config::models::{Http3Config, ProtocolConfig, RouteConfig, RouteConfigEntry, ServerConfig},
     core::GatewayService,
     ports::http_client::HttpClient,
     utils::ConnectionTracker,
@@ -49,8 +52,14 @@ async fn http3_proxy_basic() -> Result<()> {
     let backend_addr: SocketAddr = format!("127.0.0.1:{backend_port}").parse().unwrap();
     let backend_listener = tokio::net::TcpListener::bind(backend_addr).await.unwrap();
     tokio::spawn(async move {
-        use axum::{Router, routing::get};
-        let app = Router::new().route("/api/test", get(|| async { "ok-h3" }));
+        use axum::{
+            Router,
+            routing::{get, post},
+        };
+        let app = Router::new()
+            .route("/api/test", get(|| async { "ok-h3" }))
+            .route("/api/echo", post(|body: bytes::Bytes| async move { body }))
+            .layer(axum::extract::DefaultBodyLimit::disable());
         if let Err(e) = axum::serve(backend_listener, app).await {
             eprintln!("backend server error: {e}");
         }
@@ -60,16 +69,21 @@ async fn http3_proxy_basic() -> Result<()> {
     let mut routes = HashMap::new();
     routes.insert(
         "/api".to_string(),
-        RouteConfigEntry::Single(Box::new(RouteConfig::Proxy {
-            target: format!("http://127.0.0.1:{backend_port}"),
-            path_rewrite: None,
+        RouteConfigEntry::Single(Box::new(RouteConfig::proxy(format!(
+            "http://127.0.0.1:{backend_port}"
+        )))),
+    );
+    routes.insert(
+        "/tcp-only".to_string(),
+        RouteConfigEntry::Single(Box::new(RouteConfig::Redirect {
+            target: "/api/test".to_string(),
+            host: None,
+            status_code: None,
             rate_limit: None,
-            request_headers: None,
-            response_headers: None,
-            request_body: None,
-            response_body: None,
             middlewares: vec![],
-            host: None,
+            error_pages: None,
+            disable_http3: true,
+            priority: None,
         })),
     );
     let protocols = ProtocolConfig {
@@ -108,7 +122,15 @@ async fn http3_proxy_basic() -> Result<()> {
 
     // Start HTTP/3 listener
     let listen_addr: SocketAddr = format!("127.0.0.1:{h3_port}").parse().unwrap();
-    let _h3_task = http3::spawn_http3(listen_addr, handler.clone(), rustls_config, None).await?;
+    let _h3_task = http3::spawn_http3(
+        listen_addr,
+        handler.clone(),
+        rustls_config,
+        &Http3Config::default(),
+        connection_tracker.clone(),
+        None,
+    )
+    .await?;
 
     // Retry loop for client connect (server startup is async)
     // Build client config trusting our self-signed cert
@@ -171,6 +193,48 @@ async fn http3_proxy_basic() -> Result<()> {
         }
     }
     assert_eq!(String::from_utf8_lossy(&body), "ok-h3");
+    assert_eq!(connection_tracker.active_connection_count(), 1);
+
+    // A request body several times the stream window makes it to the backend
+    // and back in full
+    let payload: Vec<u8> = (0..4 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    let req = http::Request::builder()
+        .method("POST")
+        .uri(format!("https://localhost:{h3_port}/api/echo"))
+        .body(())
+        .unwrap();
+    let req_stream = send_req.send_request(req).await.expect("send h3 request");
+    let (mut send_half, mut recv_half) = req_stream.split();
+    let upload = payload.clone();
+    let uploader = tokio::spawn(async move {
+        for chunk in upload.chunks(64 * 1024) {
+            send_half
+                .send_data(bytes::Bytes::copy_from_slice(chunk))
+                .await
+                .expect("send request chunk");
+        }
+        send_half.finish().await.expect("finish request");
+    });
+    let response = recv_half.recv_response().await.expect("recv response");
+    assert_eq!(response.status(), 200);
+    let mut echoed = Vec::new();
+    while let Some(mut chunk) = recv_half.recv_data().await.expect("recv data") {
+        echoed.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+    }
+    uploader.await.unwrap();
+    assert_eq!(echoed.len(), payload.len());
+    assert!(echoed == payload, "echoed body differs");
+
+    // A route that opts out of HTTP/3 is refused so the client retries over TCP
+    let req = http::Request::builder()
+        .method("GET")
+        .uri(format!("https://localhost:{h3_port}/tcp-only"))
+        .body(())
+        .unwrap();
+    let mut req_stream = send_req.send_request(req).await.expect("send h3 request");
+    req_stream.finish().await.expect("finish request");
+    let response = req_stream.recv_response().await.expect("recv response");
+    assert_eq!(response.status(), 421);
 
     Ok(())
 }
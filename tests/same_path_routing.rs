@@ -4,7 +4,9 @@ mod test {
     use std::sync::Arc;
 
     use axon::{
-        config::models::{RouteConfig, RouteConfigEntry, ServerConfig},
+        config::models::{
+            RouteConfig, RouteConfigEntry, RouteScheduleConfig, ScheduleWindow, ServerConfig,
+        },
         core::GatewayService,
     };
 
@@ -19,28 +21,8 @@ mod test {
         config.routes.insert(
             "/".to_string(),
             RouteConfigEntry::Multiple(vec![
-                RouteConfig::Proxy {
-                    target: "http://api-backend:3001".to_string(),
-                    host: Some("api.example.com".to_string()),
-                    path_rewrite: None,
-                    rate_limit: None,
-                    request_headers: None,
-                    response_headers: None,
-                    request_body: None,
-                    response_body: None,
-                    middlewares: vec![],
-                },
-                RouteConfig::Proxy {
-                    target: "http://fallback-backend:5555".to_string(),
-                    host: None,
-                    path_rewrite: None,
-                    rate_limit: None,
-                    request_headers: None,
-                    response_headers: None,
-                    request_body: None,
-                    response_body: None,
-                    middlewares: vec![],
-                },
+                proxy("http://api-backend:3001", Some("api.example.com")),
+                proxy("http://fallback-backend:5555", None),
             ]),
         );
 
@@ -72,4 +54,49 @@ mod test {
             assert_eq!(target, "http://fallback-backend:5555");
         }
     }
+
+    fn proxy(target: &str, host: Option<&str>) -> RouteConfig {
+        let mut route = RouteConfig::proxy(target);
+        if let RouteConfig::Proxy {
+            host: route_host, ..
+        } = &mut route
+        {
+            *route_host = host.map(str::to_string);
+        }
+        route
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scheduled_off_route_is_skipped() {
+        let mut config = ServerConfig {
+            listen_addr: "127.0.0.1:8080".to_string(),
+            ..ServerConfig::default()
+        };
+
+        // Switched off since 2020, with no end
+        let mut api = proxy("http://api-backend:3001", Some("api.example.com"));
+        if let RouteConfig::Proxy { schedule, .. } = &mut api {
+            *schedule = Some(RouteScheduleConfig {
+                timezone: "UTC".to_string(),
+                active_outside_windows: true,
+                windows: vec![ScheduleWindow {
+                    from: Some("2020-01-01T00:00:00Z".to_string()),
+                    ..ScheduleWindow::default()
+                }],
+            });
+        }
+        config.routes.insert(
+            "/".to_string(),
+            RouteConfigEntry::Multiple(vec![api, proxy("http://fallback-backend:5555", None)]),
+        );
+
+        let gateway = GatewayService::new(Arc::new(config));
+        let (_, route_config) = gateway
+            .find_matching_route("/users", Some("api.example.com"))
+            .expect("host-less route");
+        let RouteConfig::Proxy { target, .. } = route_config else {
+            panic!("expected a proxy route");
+        };
+        assert_eq!(target, "http://fallback-backend:5555");
+    }
 }